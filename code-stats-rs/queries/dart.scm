@@ -0,0 +1,37 @@
+; Declarations counted for Dart. Capture names map to CodeStats fields.
+
+; Functions and methods with a body, including getters, setters, operators
+; and factory constructors
+[
+  (function_declaration)
+  (local_function_declaration)
+  (function_expression)
+  (method_declaration)
+] @function
+
+; Constructors and abstract methods, declared by a signature alone
+(declaration
+  [
+    (constructor_signature)
+    (constant_constructor_signature)
+    (redirecting_factory_constructor_signature)
+    (factory_constructor_signature)
+    (function_signature)
+    (getter_signature)
+    (setter_signature)
+    (operator_signature)
+  ]) @function
+
+; Also wraps mixin applications, `class A = B with M;`
+(class_declaration) @class
+
+; Mixins are counted with interfaces, like Rust traits
+(mixin_declaration) @interface
+
+(enum_declaration) @enum
+
+(type_alias) @type_alias
+
+; `library app;` (tree-sitter-dart misreads a single-word name as a variable
+; declaration when a declaration rather than a directive follows it)
+(library_name) @module
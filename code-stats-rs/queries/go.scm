@@ -0,0 +1,45 @@
+; Declarations counted for Go. Capture names map to CodeStats fields.
+
+[
+  (function_declaration)
+  (method_declaration)
+] @function
+
+(package_clause) @module
+
+; Function literals, counted as functions with `--count-anonymous`
+(func_literal) @anonymous_function
+
+; Generic functions and types, counted in addition to their declaration
+[
+  (function_declaration
+    type_parameters: (_))
+  (type_spec
+    type_parameters: (_))
+] @generic
+
+; `type A = B`
+(type_alias) @type_alias
+
+; All other type declarations are a type_spec; its type decides whether it
+; declares a struct, an interface, or a defined type such as `type ID int`
+(type_spec
+  type: (struct_type)) @class
+
+(type_spec
+  type: (interface_type)) @interface
+
+(type_spec
+  type: [
+    (array_type)
+    (channel_type)
+    (function_type)
+    (generic_type)
+    (map_type)
+    (negated_type)
+    (parenthesized_type)
+    (pointer_type)
+    (qualified_type)
+    (slice_type)
+    (type_identifier)
+  ]) @type_alias
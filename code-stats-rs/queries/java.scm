@@ -0,0 +1,27 @@
+; Declarations counted for Java. Capture names map to CodeStats fields.
+
+[
+  (method_declaration)
+  (constructor_declaration)
+] @function
+
+; Records are classes
+[
+  (class_declaration)
+  (record_declaration)
+] @class
+
+; Annotation types (`@interface`) are a kind of interface
+[
+  (interface_declaration)
+  (annotation_type_declaration)
+] @interface
+
+(enum_declaration) @enum
+
+(package_declaration) @module
+
+(lambda_expression) @lambda
+
+; Lambdas are counted as functions with `--count-anonymous`
+(lambda_expression) @anonymous_function
@@ -0,0 +1,10 @@
+; Declarations counted for JavaScript. Capture names map to CodeStats fields.
+
+[
+  (function_declaration)
+  (function_expression)
+  (arrow_function)
+  (method_definition)
+] @function
+
+(class_declaration) @class
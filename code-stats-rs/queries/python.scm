@@ -0,0 +1,20 @@
+; Declarations counted for Python. Capture names map to CodeStats fields.
+; Packages are declared by `__init__.py` files and counted by the analyzer.
+
+(function_definition) @function
+
+(class_definition) @class
+
+(type_alias_statement) @type_alias
+
+; Kinds of functions, counted in addition to the functions themselves
+(function_definition
+  "async") @async_function
+
+(decorated_definition
+  definition: (function_definition)) @decorated_function
+
+(lambda) @lambda
+
+; Lambdas are counted as functions with `--count-anonymous`
+(lambda) @anonymous_function
@@ -0,0 +1,20 @@
+; Declarations counted for Rust. Capture names map to CodeStats fields.
+
+(function_item) @function
+
+(struct_item) @class
+
+(trait_item) @interface
+
+(enum_item) @enum
+
+(type_item) @type_alias
+
+(mod_item) @module
+
+; Closures, counted as functions with `--count-anonymous`
+(closure_expression) @anonymous_function
+
+(impl_item !trait) @impl
+
+(impl_item trait: (_)) @trait_impl
@@ -0,0 +1,23 @@
+; Declarations counted for TypeScript and TSX. Capture names map to CodeStats
+; fields.
+
+[
+  (function_declaration)
+  (function_expression)
+  (arrow_function)
+  (method_definition)
+] @function
+
+(class_declaration) @class
+
+(interface_declaration) @interface
+
+(enum_declaration) @enum
+
+(type_alias_declaration) @type_alias
+
+; `namespace A {}` and `module A {}` / `declare module "a" {}`
+[
+  (internal_module)
+  (module)
+] @module
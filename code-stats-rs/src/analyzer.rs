@@ -1,21 +1,123 @@
 //! Code analysis engine for processing source files and directories.
 
-use crate::error::{CodeStatsError, Result};
-use crate::language::SupportedLanguage;
-use crate::parser::{analyze_code, create_parser};
+use crate::archive::Member;
+use crate::cache::{FileCache, content_hash};
+use crate::config::{
+    AnonymousFunctions, CONFIG_FILE_NAME, ConfigResolver, CountPolicy, DirectoryConfig,
+};
+use crate::detection::ContentLanguages;
+use crate::error::{CodeStatsError, ErrorKind, Result};
+use crate::findings::{Finding, Severity};
+use crate::functions::function_names;
+use crate::hierarchy::HierarchyCollector;
+use crate::interrupt::Interrupt;
+use crate::language::{DetectionStrategy, ExtensionOverrides, Grammar, SupportedLanguage};
+use crate::metrics::HalsteadCounter;
+use crate::obfuscation::{IdentifierLengths, assess};
+use crate::origin::{Origin, classify};
+use crate::parser::{
+    collect_stats, create_grammar_parser, declares_python_package, oversized_functions,
+    package_name, parse_source_interruptible,
+};
+use crate::paths::{is_hidden_name, path_contains};
+use crate::profile::{PhaseTimes, Profile};
+use crate::queries::QueryCounter;
+use crate::rust_modules::ModuleCollector;
+use crate::secrets::SecretScanner;
+use crate::source::{decode_source, read_source, screen};
 use crate::stats::{DirectoryStats, FileStats};
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-use tree_sitter::Parser;
-use walkdir::{DirEntry, WalkDir};
+use crate::symbols::symbols;
+use crate::test_code::{inline_tests, is_test_path};
+use crate::visitor::NodeVisitor;
+use crate::walk::{DirectoryWalk, WalkOptions, Walked, is_candidate, is_visible};
+use encoding_rs::Encoding;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser};
+use walkdir::WalkDir;
+
+/// Limits that stop a directory analysis whose traversal runs away, e.g.
+/// after following a link to `/`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TraversalLimits {
+    /// Most files to consider for analysis
+    pub max_files: Option<usize>,
+    /// Most bytes the considered files may hold together
+    pub max_total_bytes: Option<u64>,
+    /// Most symbolic links to follow on the way to a file
+    pub max_link_depth: Option<usize>,
+}
+
+impl TraversalLimits {
+    /// Checks the files and bytes considered so far against the limits.
+    ///
+    /// # Returns
+    ///
+    /// * `Err(CodeStatsError::LimitExceeded)` naming the exceeded limit
+    pub(crate) fn check(&self, (files, bytes): (usize, u64)) -> Result<()> {
+        if let Some(max) = self.max_files
+            && files > max
+        {
+            return Err(CodeStatsError::LimitExceeded(format!(
+                "more than {max} files (--max-files)"
+            )));
+        }
+        if let Some(max) = self.max_total_bytes
+            && bytes > max
+        {
+            return Err(CodeStatsError::LimitExceeded(format!(
+                "more than {max} bytes in total (--max-total-bytes)"
+            )));
+        }
+        Ok(())
+    }
+}
 
 /// Main analyzer that manages parsers and coordinates code analysis.
 ///
 /// Maintains a cache of tree-sitter parsers for each language to improve
 /// performance when analyzing multiple files.
 pub(crate) struct CodeAnalyzer {
-    parsers: HashMap<SupportedLanguage, Parser>,
+    parsers: HashMap<Grammar, Parser>,
+    secret_scanner: Option<SecretScanner>,
+    query_counter: Option<QueryCounter>,
+    list_functions: bool,
+    list_symbols: bool,
+    extended_metrics: bool,
+    content_hashes: bool,
+    modification_times: bool,
+    mmap_threshold: Option<u64>,
+    detection: DetectionStrategy,
+    extensions: ExtensionOverrides,
+    anonymous_functions: Option<AnonymousFunctions>,
+    exclude_suspicious: bool,
+    include_vendored: bool,
+    exclude_generated: bool,
+    interrupt: Interrupt,
+    cache_dir: Option<PathBuf>,
+    encoding: Option<&'static Encoding>,
+    aggregate_only: bool,
+    /// Which files directory analyses read; the depth, links and ignore
+    /// patterns are set per analysis
+    walk: WalkOptions,
+    /// Files and bytes considered against the limits by the current
+    /// revision or archive analysis
+    visited: (usize, u64),
+    /// Buffer the next file is read into, reused across files
+    read_buffer: Vec<u8>,
+    /// The cache of the directory being analyzed, open during `analyze_directory`
+    cache: Option<FileCache>,
+    /// Languages of the directory's files identified by content ahead of
+    /// the analysis, kept during `analyze_directory`
+    content_languages: Option<ContentLanguages>,
+    /// The directory being analyzed, kept during `analyze_directory` and
+    /// `analyze_revision` so files are classified by their relative path
+    root: Option<PathBuf>,
+    /// Timings of the files of `analyze_directory`, if profiling
+    profile: Option<Profile>,
 }
 
 impl CodeAnalyzer {
@@ -23,11 +125,255 @@ impl CodeAnalyzer {
     pub(crate) fn new() -> Self {
         Self {
             parsers: HashMap::new(),
+            secret_scanner: None,
+            query_counter: None,
+            list_functions: false,
+            list_symbols: false,
+            extended_metrics: false,
+            content_hashes: false,
+            modification_times: false,
+            mmap_threshold: None,
+            detection: DetectionStrategy::Auto,
+            extensions: ExtensionOverrides::default(),
+            anonymous_functions: None,
+            exclude_suspicious: false,
+            include_vendored: false,
+            exclude_generated: false,
+            interrupt: Interrupt::default(),
+            cache_dir: None,
+            encoding: None,
+            aggregate_only: false,
+            walk: WalkOptions::default(),
+            visited: (0, 0),
+            read_buffer: Vec::new(),
+            cache: None,
+            content_languages: None,
+            root: None,
+            profile: None,
         }
     }
 
+    /// Enables scanning string literals for secrets in every analyzed file.
+    ///
+    /// Matches are attached to each file's `FileStats::secrets`.
+    pub(crate) fn with_secret_scanner(mut self, scanner: SecretScanner) -> Self {
+        self.secret_scanner = Some(scanner);
+        self
+    }
+
+    /// Counts the captures of user-supplied queries in every analyzed file.
+    ///
+    /// Counts are attached to each file's `CodeStats::query_counts`.
+    pub(crate) fn with_query_counter(mut self, counter: QueryCounter) -> Self {
+        self.query_counter = Some(counter);
+        self
+    }
+
+    /// Lists the named functions of every analyzed file.
+    ///
+    /// Names are attached to each file's `FileStats::functions`, e.g. to find
+    /// names defined in many places.
+    pub(crate) fn with_function_names(mut self, list_functions: bool) -> Self {
+        self.list_functions = list_functions;
+        self
+    }
+
+    /// Lists the named declarations of every analyzed file with their spans.
+    ///
+    /// Symbols are attached to each file's `FileStats::symbols`, and cached
+    /// with them, so repeated lookups only parse the files that changed.
+    pub(crate) fn with_symbols(mut self, list_symbols: bool) -> Self {
+        self.list_symbols = list_symbols;
+        self
+    }
+
+    /// Computes the Halstead volume and maintainability index of every analyzed file.
+    ///
+    /// Metrics are attached to each file's `FileStats::metrics`.
+    pub(crate) fn with_extended_metrics(mut self, extended_metrics: bool) -> Self {
+        self.extended_metrics = extended_metrics;
+        self
+    }
+
+    /// Records the SHA-256 of every analyzed file's source.
+    ///
+    /// Hashes are attached to each file's `FileStats::sha256`, so a report
+    /// can be checked against the exact sources it was computed from.
+    pub(crate) fn with_content_hashes(mut self, content_hashes: bool) -> Self {
+        self.content_hashes = content_hashes;
+        self
+    }
+
+    /// Records when every analyzed file on disk was last modified.
+    ///
+    /// Times are attached to each file's `FileStats::modified`. They are off
+    /// by default, as they make reports of identical sources differ.
+    pub(crate) fn with_modification_times(mut self, modification_times: bool) -> Self {
+        self.modification_times = modification_times;
+        self
+    }
+
+    /// Reads source files of at least `threshold` bytes through memory maps
+    /// instead of copying them into memory; `Some(0)` maps every file.
+    ///
+    /// Smaller files, and files that cannot be mapped, are read normally.
+    pub(crate) fn with_mmap_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.mmap_threshold = threshold;
+        self
+    }
+
+    /// Selects how the language of each file is detected.
+    ///
+    /// Extensions added or removed in `.code-stats.toml` apply with every strategy.
+    pub(crate) fn with_detection(mut self, detection: DetectionStrategy) -> Self {
+        self.detection = detection;
+        self
+    }
+
+    /// Maps extra extensions to languages in every analyzed directory.
+    ///
+    /// `.code-stats.toml` files apply on top, so they can remove a mapping
+    /// again for their subtree.
+    pub(crate) fn with_extensions(mut self, extensions: ExtensionOverrides) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Decides which anonymous functions count as functions in every language.
+    ///
+    /// `.code-stats.toml` files can choose differently for their subtree.
+    pub(crate) fn with_anonymous_functions(
+        mut self,
+        anonymous_functions: Option<AnonymousFunctions>,
+    ) -> Self {
+        self.anonymous_functions = anonymous_functions;
+        self
+    }
+
+    /// Leaves files flagged as possibly obfuscated out of directory totals.
+    ///
+    /// Such files are collected in `DirectoryStats::excluded_files` instead.
+    pub(crate) fn with_exclude_suspicious(mut self, exclude_suspicious: bool) -> Self {
+        self.exclude_suspicious = exclude_suspicious;
+        self
+    }
+
+    /// Counts vendored files (e.g. below `vendor/` or `node_modules/`) in
+    /// directory totals.
+    ///
+    /// By default they are collected in `DirectoryStats::set_aside_files` instead.
+    pub(crate) fn with_include_vendored(mut self, include_vendored: bool) -> Self {
+        self.include_vendored = include_vendored;
+        self
+    }
+
+    /// Leaves generated files (marked e.g. `@generated`) out of directory totals.
+    ///
+    /// Such files are collected in `DirectoryStats::set_aside_files` instead.
+    pub(crate) fn with_exclude_generated(mut self, exclude_generated: bool) -> Self {
+        self.exclude_generated = exclude_generated;
+        self
+    }
+
+    /// Stops directory analysis before the next file once `interrupt` is raised,
+    /// cancelling the parse of the current file.
+    ///
+    /// The statistics gathered until then are returned with
+    /// `DirectoryStats::interrupted` set; the cancelled file is left out.
+    pub(crate) fn with_interrupt(mut self, interrupt: Interrupt) -> Self {
+        self.interrupt = interrupt;
+        self
+    }
+
+    /// Reuses the results of unchanged files from earlier directory analyses.
+    ///
+    /// Each analyzed directory has its own cache file inside `cache_dir` (see
+    /// the `cache` module); `None` disables caching.
+    pub(crate) fn with_cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    /// Restricts directory analysis to the given files, e.g. those changed in git.
+    ///
+    /// Paths must be spelled as traversal finds them, i.e. joined onto the
+    /// analyzed directory. Ignore patterns still apply to the listed files.
+    pub(crate) fn with_only_files(mut self, files: HashSet<PathBuf>) -> Self {
+        self.walk.only_files = Some(files);
+        self
+    }
+
+    /// Counts a file reached through several symbolic links only once when
+    /// following links (the default), or once per path.
+    pub(crate) fn with_dedup_links(mut self, dedup_links: bool) -> Self {
+        self.walk.dedup_links = dedup_links;
+        self
+    }
+
+    /// Skips files larger than `max_file_size` bytes during directory
+    /// analysis without reading them; `None` analyzes files of any size.
+    pub(crate) fn with_max_file_size(mut self, max_file_size: Option<u64>) -> Self {
+        self.walk.max_file_size = max_file_size;
+        self
+    }
+
+    /// Decodes files that are not valid UTF-8 with `encoding`; `None` (the
+    /// default) detects the encoding of each such file.
+    pub(crate) fn with_encoding(mut self, encoding: Option<&'static Encoding>) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Keeps only the totals of directory analyses, dropping the per-file
+    /// records after merging, see [`DirectoryStats::aggregate_only`].
+    pub(crate) fn with_aggregate_only(mut self, aggregate_only: bool) -> Self {
+        self.aggregate_only = aggregate_only;
+        self
+    }
+
+    /// Reports recoverable issues of directory analyses as warning findings:
+    /// skipped files that look like source code or are too large (otherwise
+    /// an info finding), and files detected by extension because their
+    /// content was not recognized.
+    pub(crate) fn with_warnings(mut self, collect_warnings: bool) -> Self {
+        self.walk.collect_warnings = collect_warnings;
+        self
+    }
+
+    /// Analyzes hidden files and descends into hidden directories, whose
+    /// names start with a dot, instead of skipping them (the default).
+    pub(crate) fn with_hidden(mut self, hidden: bool) -> Self {
+        self.walk.hidden = hidden;
+        self
+    }
+
+    /// Stops directory and revision analyses with
+    /// [`CodeStatsError::LimitExceeded`] once they consider more files or
+    /// bytes than `limits` allow; files behind too many symbolic links are
+    /// skipped with an info finding.
+    pub(crate) fn with_limits(mut self, limits: TraversalLimits) -> Self {
+        self.walk.limits = limits;
+        self
+    }
+
+    /// Times the detection, reading and parsing of every file during
+    /// directory analysis, for [`Self::take_profile`].
+    pub(crate) fn with_profiling(mut self, profile: bool) -> Self {
+        self.profile = profile.then(Profile::default);
+        self
+    }
+
+    /// Returns the timings recorded since profiling was enabled and starts
+    /// over, or `None` if profiling is disabled.
+    pub(crate) fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.as_mut().map(std::mem::take)
+    }
+
     /// Analyzes a single source code file and returns its statistics.
     ///
+    /// The counting policy of a `.code-stats.toml` next to the file applies;
+    /// its ignore patterns do not, since the file was named explicitly.
+    ///
     /// # Arguments
     ///
     /// * `path` - Path to the source file to analyze
@@ -35,34 +381,38 @@ impl CodeAnalyzer {
     /// # Returns
     ///
     /// * `Ok(FileStats)` - Statistics for the analyzed file
-    /// * `Err` if the path is not a file, the file type is unsupported, or parsing fails
+    /// * `Err` if the path is not a file, the file type is unsupported, the config is
+    ///   invalid, or parsing fails
     pub(crate) fn analyze_file(&mut self, path: &Path) -> Result<FileStats> {
-        if !path.is_file() {
-            return Err(CodeStatsError::IoError(format!(
-                "{} is not a file",
-                path.display()
-            )));
+        let metadata = path.metadata().map_err(|e| CodeStatsError::io(path, e))?;
+        if !metadata.is_file() {
+            let source = io::Error::new(io::ErrorKind::InvalidInput, "not a file");
+            return Err(CodeStatsError::io(path, source));
         }
 
-        let path_str = path.to_string_lossy();
-        let language = SupportedLanguage::from_file_path(&path_str)
-            .ok_or_else(|| CodeStatsError::UnsupportedFileType(path_str.to_string()))?;
-
-        let source_code = fs::read_to_string(path)
-            .map_err(|e| CodeStatsError::IoError(format!("Failed to read {path_str}: {e}")))?;
+        let root = path.parent().unwrap_or(Path::new(""));
+        let config = self.config_resolver(root).for_file(path)?;
 
-        let parser = self.get_or_create_parser(&language)?;
-        let code_stats = analyze_code(parser, &source_code, &path_str, &language)?;
+        let language = config
+            .extensions
+            .detect(path, self.detection)
+            .ok_or_else(|| CodeStatsError::UnsupportedFileType(path.display().to_string()))?;
 
-        Ok(FileStats {
-            path: path.to_path_buf(),
-            language,
-            stats: code_stats,
-        })
+        self.analyze_source_file(path, language, &config.count)
+            .map(|file_stats| {
+                if self.modification_times {
+                    file_stats.with_modification_time(&metadata)
+                } else {
+                    file_stats
+                }
+            })
     }
 
     /// Recursively analyzes all supported files in a directory.
     ///
+    /// `.code-stats.toml` files inside the directory add ignore patterns and
+    /// counting policies for their subtree (see the `config` module).
+    ///
     /// # Arguments
     ///
     /// * `path` - Root directory to analyze
@@ -72,13 +422,21 @@ impl CodeAnalyzer {
     ///
     /// # Returns
     ///
-    /// * `Ok(DirectoryStats)` - Aggregated statistics for all analyzed files
-    /// * `Err` only if no files could be analyzed and errors occurred
+    /// * `Ok(DirectoryStats)` - Aggregated statistics for all analyzed files, or
+    ///   for the files analyzed before an interrupt (see [`Self::with_interrupt`])
+    /// * `Err` if no files could be analyzed and errors occurred
+    /// * `Err(CodeStatsError::LimitExceeded)` if a limit of
+    ///   [`Self::with_limits`] was exceeded
     ///
     /// # Error Handling
     ///
-    /// Individual file errors are collected but don't fail the entire operation.
-    /// The analysis only fails if no files could be successfully processed.
+    /// Individual file errors are recorded as error findings in
+    /// `DirectoryStats::findings` but don't fail the entire operation.
+    /// When following links, a file reached again through another link and a
+    /// link leading back to a parent directory are skipped with info findings.
+    /// The analysis only fails if no files could be successfully processed, or
+    /// immediately if a configuration file is invalid. A cache file that cannot
+    /// be written is recorded as a warning finding.
     pub(crate) fn analyze_directory(
         &mut self,
         path: &Path,
@@ -86,117 +444,659 @@ impl CodeAnalyzer {
         follow_links: bool,
         ignore_patterns: &[String],
     ) -> Result<DirectoryStats> {
-        let mut stats = DirectoryStats::new();
-        let mut errors = Vec::new();
-
-        let walker = WalkDir::new(path)
-            .max_depth(max_depth)
-            .follow_links(follow_links);
-
-        for entry in walker {
-            match entry {
-                Ok(dir_entry) => {
-                    if let Err(e) = self.process_entry(&dir_entry, &mut stats, ignore_patterns) {
-                        errors.push(e);
+        let options = WalkOptions {
+            max_depth,
+            follow_links,
+            ignore_patterns: ignore_patterns.to_vec(),
+            ..self.walk.clone()
+        };
+        if self.detection != DetectionStrategy::Extension {
+            let started = Instant::now();
+            let candidates = self.candidate_paths(path, options.clone())?;
+            self.content_languages = Some(ContentLanguages::identify(&candidates, &self.interrupt));
+            if let Some(profile) = &mut self.profile {
+                profile.batch_detection += started.elapsed();
+            }
+        }
+
+        let mut stats = self.new_stats();
+        let mut first_error = None;
+        self.cache = self
+            .cache_dir
+            .as_deref()
+            .map(|cache_dir| FileCache::open(cache_dir, path));
+        self.root = Some(path.to_path_buf());
+        let walk = DirectoryWalk::new(path, options, self.config_resolver(path));
+        let mut walk_error = None;
+
+        for walked in walk {
+            if self.interrupt.is_raised() {
+                stats.interrupted = true;
+                break;
+            }
+
+            match walked {
+                Ok(Walked::File(file, config)) => {
+                    let mut times = PhaseTimes::default();
+                    let result = self.analyze_entry(&file, &mut stats, &config, &mut times);
+                    if let Some(profile) = &mut self.profile {
+                        profile.record(&file, times);
                     }
+                    if let Err(e) = result {
+                        stats.findings.push(Finding::error(Some(file), &e));
+                        first_error.get_or_insert(e);
+                    }
+                }
+                Ok(Walked::Skipped(finding)) => stats.findings.push(finding),
+                Ok(Walked::Error(e)) => {
+                    let path = e.path().map(Path::to_path_buf);
+                    stats.findings.push(Finding::error(path, &e));
+                    first_error.get_or_insert(e);
                 }
+                // An invalid configuration file or an exceeded limit
                 Err(e) => {
-                    errors.push(CodeStatsError::IoError(e.to_string()));
+                    walk_error = Some(e);
+                    break;
                 }
             }
         }
 
-        if !errors.is_empty() && stats.total_files() == 0 {
+        self.content_languages = None;
+        self.root = None;
+
+        // Results of the files visited before an interrupt are kept as well
+        if let Some(cache) = self.cache.take()
+            && let Err(e) = cache.save(!stats.interrupted && walk_error.is_none())
+        {
+            stats
+                .findings
+                .push(Finding::new(Severity::Warning, None, e.to_string()));
+        }
+
+        if let Some(e) = walk_error {
+            return Err(e);
+        }
+
+        if let Some(e) = first_error
+            && stats.total_files() == 0
+            && !stats.interrupted
+        {
             // If no files were successfully processed, return the first error
-            return Err(errors.into_iter().next().unwrap());
+            return Err(e);
         }
 
         Ok(stats)
     }
 
-    /// Processes a single directory entry during directory traversal.
+    /// Recursively analyzes the files of a directory as of a git revision.
     ///
-    /// This method implements the filtering logic for determining which files
-    /// should be analyzed:
-    /// 1. Skip non-file entries (directories, symlinks, etc.)
-    /// 2. Skip files matching any ignore pattern (substring matching)
-    /// 3. Skip files with unsupported extensions
-    /// 4. Analyze supported source files and add to statistics
+    /// File contents are read from the repository, so the work tree is left
+    /// untouched and may hold other changes. Languages are detected from file
+    /// names only, and `.code-stats.toml` files are taken from the work tree.
+    /// Symbolic links and submodules are skipped. The cache is not used.
     ///
     /// # Arguments
     ///
-    /// * `entry` - Directory entry from walkdir traversal
-    /// * `stats` - Accumulator for directory statistics
-    /// * `ignore_patterns` - Patterns to exclude (matched as substrings)
+    /// * `path` - Directory inside a git repository
+    /// * `rev` - The revision to analyze, e.g. `HEAD~10` or a tag
+    /// * `max_depth` - Maximum depth of the analyzed files below `path`
+    /// * `ignore_patterns` - Patterns to exclude files (substring matching)
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - File was processed or skipped successfully
-    /// * `Err` - File reading or parsing failed
-    fn process_entry(
+    /// * `Ok(DirectoryStats)` - Aggregated statistics of the revision's files
+    /// * `Err(CodeStatsError::GitError)` if the revision cannot be read
+    /// * `Err` if no files could be analyzed and errors occurred, if a
+    ///   configuration file is invalid, or if a limit of [`Self::with_limits`]
+    ///   was exceeded
+    pub(crate) fn analyze_revision(
         &mut self,
-        entry: &DirEntry,
-        stats: &mut DirectoryStats,
+        path: &Path,
+        rev: &str,
+        max_depth: usize,
         ignore_patterns: &[String],
-    ) -> Result<()> {
-        let path = entry.path();
+    ) -> Result<DirectoryStats> {
+        let files = crate::git::revision_files(path, rev)?
+            .into_iter()
+            .map(|(file, content)| (file, Member::Read(content)))
+            .collect();
+        self.analyze_contents(path, files, max_depth, ignore_patterns)
+    }
+
+    /// Analyzes the files of a `.zip`, `.tar` or `.tar.gz` archive.
+    ///
+    /// The archive is read into memory and nothing is extracted to disk.
+    /// Languages are detected from file names only, and `.code-stats.toml`
+    /// files in the archive are not applied. Links and directories in the
+    /// archive, and entries with absolute paths or `..` components, are
+    /// skipped. Files larger than [`Self::with_max_file_size`] are skipped
+    /// without being decompressed. The cache is not used.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The archive; the analyzed files are reported below it, e.g.
+    ///   `release.tar.gz/src/lib.rs`
+    /// * `max_depth` - Maximum depth of the analyzed files in the archive
+    /// * `ignore_patterns` - Patterns to exclude files (substring matching)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DirectoryStats)` - Aggregated statistics of the archive's files
+    /// * `Err(CodeStatsError::IoError)` if the archive cannot be read
+    /// * `Err` if no files could be analyzed and errors occurred, or if a limit
+    ///   of [`Self::with_limits`] was exceeded; every regular file of the
+    ///   archive counts against the limits as it is read
+    pub(crate) fn analyze_archive(
+        &mut self,
+        path: &Path,
+        max_depth: usize,
+        ignore_patterns: &[String],
+    ) -> Result<DirectoryStats> {
+        let files = crate::archive::archive_files(path, self.walk.max_file_size, self.walk.limits)?;
+        self.analyze_contents(path, files, max_depth, ignore_patterns)
+    }
+
+    /// Analyzes files whose contents were read ahead, for
+    /// [`Self::analyze_revision`] and [`Self::analyze_archive`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory or archive the files are below
+    /// * `files` - Each file, joined onto `path`, with its content or size
+    /// * `max_depth` - Maximum depth of the analyzed files below `path`
+    /// * `ignore_patterns` - Patterns to exclude files (substring matching)
+    fn analyze_contents(
+        &mut self,
+        path: &Path,
+        files: Vec<(PathBuf, Member)>,
+        max_depth: usize,
+        ignore_patterns: &[String],
+    ) -> Result<DirectoryStats> {
+        let mut stats = self.new_stats();
+        let mut first_error = None;
+        let mut configs = self.config_resolver(path);
+        self.root = Some(path.to_path_buf());
+        self.visited = (0, 0);
+        let mut limit_error = None;
+
+        for (file, content) in files {
+            if self.interrupt.is_raised() {
+                stats.interrupted = true;
+                break;
+            }
+
+            let relative = file.strip_prefix(path).unwrap_or(Path::new(""));
+            let depth = relative.iter().count();
+            let config = configs.for_file(&file)?;
+            if depth > max_depth
+                || (!self.walk.hidden && relative.iter().any(is_hidden_name))
+                || file.file_name() == Some(CONFIG_FILE_NAME.as_ref())
+                || ignore_patterns
+                    .iter()
+                    .any(|pattern| path_contains(&file, pattern))
+                || config.is_ignored(&file)
+            {
+                continue;
+            }
+
+            let content = match content {
+                Member::Read(content) => content,
+                Member::TooLarge(size) => {
+                    let limit = self.walk.max_file_size.unwrap_or_default();
+                    stats.findings.push(
+                        Finding::skipped(
+                            file,
+                            ErrorKind::TooLarge,
+                            format_args!("{size} bytes, larger than {limit}"),
+                        )
+                        .warning_if(self.walk.collect_warnings),
+                    );
+                    continue;
+                }
+            };
+
+            let Some(language) = config
+                .extensions
+                .detect(&file, DetectionStrategy::Extension)
+            else {
+                if self.walk.collect_warnings
+                    && let Some(warning) = Finding::unsupported_code(&file)
+                {
+                    stats.findings.push(warning);
+                }
+                stats.unsupported_files += 1;
+                continue;
+            };
 
-        // Skip if not a file
-        if !path.is_file() {
+            self.visited.0 += 1;
+            self.visited.1 += content.len() as u64;
+            if let Err(e) = self.walk.limits.check(self.visited) {
+                limit_error = Some(e);
+                break;
+            }
+
+            let result = decode_source(&file, content, self.encoding)
+                .and_then(|source| self.analyze_source(&file, &source, language, &config.count));
+            match result {
+                Ok(file_stats) => self.record(&mut stats, file_stats),
+                Err(CodeStatsError::Interrupted(_)) => stats.interrupted = true,
+                Err(e) => {
+                    stats.findings.push(Finding::error(Some(file), &e));
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        self.root = None;
+
+        if let Some(e) = limit_error {
+            return Err(e);
+        }
+
+        if let Some(e) = first_error
+            && stats.total_files() == 0
+            && !stats.interrupted
+        {
+            return Err(e);
+        }
+
+        Ok(stats)
+    }
+
+    /// Lists the files of a directory whose language directory analysis
+    /// detects, for identifying their content in batches.
+    ///
+    /// The files are those the analysis walks, so the traversal limits apply
+    /// before anything is identified. Entries that cannot be read are left
+    /// out; the analysis itself reports them.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<PathBuf>)` - The files to identify
+    /// * `Err(CodeStatsError::LimitExceeded)` if the files exceed a limit of
+    ///   [`Self::with_limits`]
+    /// * `Err(CodeStatsError::ConfigError)` if a configuration file is invalid
+    fn candidate_paths(&self, path: &Path, options: WalkOptions) -> Result<Vec<PathBuf>> {
+        let mut candidates = Vec::new();
+        for walked in DirectoryWalk::new(path, options, self.config_resolver(path)) {
+            if let Walked::File(file, _) = walked? {
+                candidates.push(file);
+            }
+        }
+        Ok(candidates)
+    }
+
+    /// Detects the language of a candidate file, then reads and analyzes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file, as yielded by the [`DirectoryWalk`]
+    /// * `stats` - Statistics the file is added to, or findings if skipped
+    /// * `config` - Effective configuration for the file's directory
+    /// * `times` - Receives the time spent on each phase
+    fn analyze_entry(
+        &mut self,
+        path: &Path,
+        stats: &mut DirectoryStats,
+        config: &DirectoryConfig,
+        times: &mut PhaseTimes,
+    ) -> Result<()> {
+        // Check if it's a supported language, by content and/or extension
+        let started = Instant::now();
+        let content = self
+            .content_languages
+            .as_ref()
+            .and_then(|languages| languages.get(path));
+        let detected = match content {
+            Some(content) => config
+                .extensions
+                .detect_identified(path, self.detection, content),
+            None => config.extensions.detect(path, self.detection),
+        };
+        times.detection = started.elapsed();
+        let Some(language) = detected else {
+            stats.unsupported_files += 1;
+            if self.walk.collect_warnings
+                && let Some(warning) = Finding::unsupported_code(path)
+            {
+                stats.findings.push(warning);
+            }
             return Ok(());
+        };
+        // Magika identified the content as something other than a supported language
+        if self.walk.collect_warnings
+            && self.detection == DetectionStrategy::Auto
+            && content == Some(None)
+        {
+            stats
+                .findings
+                .push(Finding::detection_fallback(path, language));
         }
 
-        // Check if path matches any ignore pattern using substring matching
-        let path_str = path.to_string_lossy();
-        for pattern in ignore_patterns {
-            if path_str.contains(pattern) {
+        // Read the file, skipping content that would only produce parse noise
+        let started = Instant::now();
+        let source = read_source(
+            path,
+            self.mmap_threshold,
+            self.encoding,
+            &mut self.read_buffer,
+        );
+        times.reading = started.elapsed();
+        let source = match source {
+            Err(e @ CodeStatsError::EncodingError(_)) => {
+                stats.findings.push(Finding::skipped(
+                    path.to_path_buf(),
+                    e.kind(),
+                    "malformed text",
+                ));
                 return Ok(());
             }
+            source => source?,
+        };
+        let file_stats = match screen(source.as_str()) {
+            Some((kind, reason)) => {
+                stats
+                    .findings
+                    .push(Finding::skipped(path.to_path_buf(), kind, reason));
+                None
+            }
+            None => {
+                let started = Instant::now();
+                let file_stats =
+                    self.analyze_source(path, source.as_str(), language, &config.count);
+                times.parsing = started.elapsed();
+                Some(file_stats)
+            }
+        };
+        source.recycle(&mut self.read_buffer);
+
+        match file_stats {
+            Some(Err(CodeStatsError::Interrupted(_))) => stats.interrupted = true,
+            Some(file_stats) => {
+                let mut file_stats = file_stats?;
+                if self.modification_times
+                    && let Ok(metadata) = path.metadata()
+                {
+                    file_stats = file_stats.with_modification_time(&metadata);
+                }
+                self.record(stats, file_stats);
+            }
+            None => {}
         }
+        Ok(())
+    }
 
-        // Check if it's a supported language using AI-powered content detection
-        let language = match SupportedLanguage::from_file_path(&path_str) {
-            Some(lang) => lang,
-            None => return Ok(()), // Skip unsupported files silently
+    /// Adds a file's statistics to the directory totals, or to the excluded
+    /// files if it looks obfuscated and such files are excluded, or to the
+    /// set-aside files if it is vendored or generated and not counted.
+    fn record(&self, stats: &mut DirectoryStats, file_stats: FileStats) {
+        let set_aside = match file_stats.origin {
+            Some(Origin::Vendored) => !self.include_vendored,
+            Some(Origin::Generated) => self.exclude_generated,
+            None => false,
         };
+        if self.exclude_suspicious && file_stats.suspicion.is_some() {
+            stats.excluded_files.push(file_stats);
+        } else if set_aside {
+            stats.set_aside_files.push(file_stats);
+        } else {
+            stats.add_file(file_stats);
+        }
+    }
+
+    /// Reads, parses and analyzes a source file whose language is already known.
+    ///
+    /// See [`Self::analyze_source`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the source file
+    /// * `language` - The detected programming language of the file
+    /// * `policy` - Which optional counters to collect for the file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FileStats)` - Statistics for the analyzed file
+    /// * `Err` if reading or parsing fails
+    fn analyze_source_file(
+        &mut self,
+        path: &Path,
+        language: SupportedLanguage,
+        policy: &CountPolicy,
+    ) -> Result<FileStats> {
+        let source = read_source(
+            path,
+            self.mmap_threshold,
+            self.encoding,
+            &mut self.read_buffer,
+        )?;
+        let file_stats = self.analyze_source(path, source.as_str(), language, policy);
+        source.recycle(&mut self.read_buffer);
+        file_stats
+    }
 
-        // Read and analyze the file
-        let source_code = fs::read_to_string(path)
-            .map_err(|e| CodeStatsError::IoError(format!("Failed to read {path_str}: {e}")))?;
+    /// Parses and analyzes source code whose language is already known.
+    ///
+    /// The source is parsed once; the resulting tree feeds the structural counts
+    /// and any optional passes enabled on this analyzer (e.g. secret scanning).
+    /// Identifier lengths for the obfuscation heuristics, the type hierarchy
+    /// and the declarations of Rust modules are gathered during the same
+    /// traversal as the counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path the statistics are reported under
+    /// * `source_code` - The file's content
+    /// * `language` - The detected programming language of the file
+    /// * `policy` - Which optional counters to collect for the file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(FileStats)` - Statistics for the analyzed source
+    /// * `Err` if parsing fails
+    pub(crate) fn analyze_source(
+        &mut self,
+        path: &Path,
+        source_code: &str,
+        language: SupportedLanguage,
+        policy: &CountPolicy,
+    ) -> Result<FileStats> {
+        let cache_key = self.cache.as_ref().map(|_| {
+            let scanner = self.secret_scanner.as_ref().map(SecretScanner::fingerprint);
+            let queries = self.query_counter.as_ref().map(QueryCounter::fingerprint);
+            let (functions, metrics) = (self.list_functions, self.extended_metrics);
+            let (symbols, hashes) = (self.list_symbols, self.content_hashes);
+            let settings = format!(
+                "{language:?} {policy:?} {scanner:?} {queries:?} {functions} {symbols} {metrics} \
+                 {hashes}"
+            );
+            (content_hash(source_code.as_bytes()), settings)
+        });
+        if let (Some(cache), Some((hash, settings))) = (&mut self.cache, &cache_key)
+            && let Some(cached) = cache.lookup(path, hash, settings)
+        {
+            return Ok(cached);
+        }
 
-        let parser = self.get_or_create_parser(&language)?;
-        let code_stats = analyze_code(parser, &source_code, &path_str, &language)?;
+        let grammar = Grammar::for_file(language, path);
+        let interrupt = self.interrupt.clone();
+        let parser = self.get_or_create_parser(grammar)?;
+        let tree = parse_source_interruptible(parser, source_code, path, &interrupt)?;
+        let relative = self
+            .root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
 
-        let file_stats = FileStats {
-            path: path.to_path_buf(),
-            language,
-            stats: code_stats,
+        let mut identifiers = IdentifierLengths::default();
+        let mut hierarchy = HierarchyCollector::default();
+        let mut modules = ModuleCollector::new(relative);
+        let mut halstead = self.extended_metrics.then(HalsteadCounter::default);
+        let mut visit_all = |language: SupportedLanguage, node: &Node<'_>, source: &str| {
+            identifiers.visit(language, node, source);
+            hierarchy.visit(language, node, source);
+            modules.visit(language, node, source);
+            if let Some(halstead) = &mut halstead {
+                halstead.visit(language, node, source);
+            }
         };
+        let mut stats = collect_stats(&tree, source_code, &language, policy, Some(&mut visit_all));
+        if declares_python_package(language, path) {
+            stats.module_count += 1;
+        }
+        if let Some(counter) = &self.query_counter {
+            stats.query_counts = counter.count(&tree, source_code, grammar);
+        }
 
-        stats.add_file(file_stats);
-        Ok(())
+        let code_lines = stats.code_lines;
+        let mut file_stats = FileStats::new(path.to_path_buf(), language, stats);
+        file_stats.metrics = halstead.map(|halstead| halstead.finish(code_lines));
+        file_stats.package = package_name(&tree, source_code, &language);
+        file_stats.suspicion = assess(source_code, &identifiers);
+        file_stats.hierarchy = hierarchy.into_relations();
+        file_stats.rust_modules = modules.into_modules();
+        file_stats.origin = classify(relative, source_code);
+        file_stats.is_test = is_test_path(relative);
+        if !file_stats.is_test {
+            file_stats.inline_tests = inline_tests(&tree, source_code, &language, policy);
+        }
+        // Only files with oversized functions pay for the second traversal
+        if file_stats.stats.oversized_function_count > 0 {
+            file_stats.oversized_functions =
+                oversized_functions(&tree, source_code, &language, policy);
+        }
+
+        if self.list_functions {
+            file_stats.functions = function_names(&tree, source_code, &language, policy);
+        }
+        if self.list_symbols {
+            file_stats.symbols = symbols(&tree, source_code, &language, policy);
+        }
+
+        if let Some(scanner) = &self.secret_scanner {
+            file_stats.secrets = scanner.scan(&tree, source_code, &language);
+        }
+
+        if self.content_hashes {
+            file_stats.sha256 = Some(match &cache_key {
+                Some((hash, _)) => hash.clone(),
+                None => content_hash(source_code.as_bytes()),
+            });
+        }
+
+        if let (Some(cache), Some((hash, settings))) = (&mut self.cache, cache_key) {
+            cache.insert(hash, settings, &file_stats);
+        }
+
+        Ok(file_stats)
     }
 
-    /// Gets a parser for the specified language from cache or creates a new one.
+    /// Creates the statistics a directory analysis adds its files to.
+    fn new_stats(&self) -> DirectoryStats {
+        if self.aggregate_only {
+            DirectoryStats::aggregate_only()
+        } else {
+            DirectoryStats::new()
+        }
+    }
+
+    /// Creates the resolver of `.code-stats.toml` files below `root`,
+    /// starting from the settings given to this analyzer.
+    fn config_resolver(&self, root: &Path) -> ConfigResolver {
+        ConfigResolver::new(root)
+            .with_extensions(self.extensions.clone())
+            .with_anonymous_functions(self.anonymous_functions)
+    }
+
+    /// Gets a parser for the specified grammar from cache or creates a new one.
     ///
     /// This method implements a simple caching strategy: if a parser for the
-    /// requested language already exists in the cache, it's returned. Otherwise,
-    /// a new parser is created, configured for the language, cached, and returned.
+    /// requested grammar already exists in the cache, it's returned. Otherwise,
+    /// a new parser is created, configured for the grammar, cached, and returned.
     ///
     /// # Arguments
     ///
-    /// * `language` - The programming language requiring a parser
+    /// * `grammar` - The grammar requiring a parser
     ///
     /// # Returns
     ///
-    /// A mutable reference to the cached parser for the language
-    fn get_or_create_parser(&mut self, language: &SupportedLanguage) -> Result<&mut Parser> {
-        if !self.parsers.contains_key(language) {
-            let parser = create_parser(language)?;
-            self.parsers.insert(*language, parser);
+    /// A mutable reference to the cached parser for the grammar
+    fn get_or_create_parser(&mut self, grammar: Grammar) -> Result<&mut Parser> {
+        match self.parsers.entry(grammar) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(create_grammar_parser(grammar)?)),
+        }
+    }
+}
+
+/// Collects the supported source files that directory analysis would visit.
+///
+/// Applies the same traversal options and filtering as
+/// [`CodeAnalyzer::analyze_directory`], but only detects each file's language
+/// instead of parsing it. A single file path yields that file alone.
+///
+/// # Arguments
+///
+/// * `path` - File or root directory to scan
+/// * `max_depth` - Maximum depth for directory traversal
+/// * `follow_links` - Whether to follow symbolic links
+/// * `hidden` - Whether to include hidden files and directories
+/// * `ignore_patterns` - Patterns to exclude files (substring matching)
+/// * `detection` - How the language of each file is detected
+///
+/// # Returns
+///
+/// * `Ok(Vec<(PathBuf, SupportedLanguage)>)` - Source files paired with their language
+/// * `Err` if a single file is unsupported, a config file is invalid, or if nothing was
+///   found and traversal errors occurred
+pub(crate) fn collect_source_files(
+    path: &Path,
+    max_depth: usize,
+    follow_links: bool,
+    hidden: bool,
+    ignore_patterns: &[String],
+    detection: DetectionStrategy,
+) -> Result<Vec<(PathBuf, SupportedLanguage)>> {
+    if path.is_file() {
+        let root = path.parent().unwrap_or(Path::new(""));
+        let language = ConfigResolver::new(root)
+            .for_file(path)?
+            .extensions
+            .detect(path, detection)
+            .ok_or_else(|| CodeStatsError::UnsupportedFileType(path.display().to_string()))?;
+        return Ok(vec![(path.to_path_buf(), language)]);
+    }
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    let mut configs = ConfigResolver::new(path);
+
+    let walker = WalkDir::new(path)
+        .max_depth(max_depth)
+        .follow_links(follow_links)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| is_visible(entry, hidden));
+
+    for entry in walker {
+        match entry {
+            Ok(dir_entry) => {
+                let config = configs.for_file(dir_entry.path())?;
+                if is_candidate(dir_entry.path(), ignore_patterns, &config)
+                    && let Some(language) = config.extensions.detect(dir_entry.path(), detection)
+                {
+                    files.push((dir_entry.into_path(), language));
+                }
+            }
+            Err(e) => errors.push(CodeStatsError::from(io::Error::from(e))),
         }
-        Ok(self.parsers.get_mut(language).unwrap())
     }
+
+    if files.is_empty() && !errors.is_empty() {
+        return Err(errors.into_iter().next().unwrap());
+    }
+
+    Ok(files)
 }
 
 impl Default for CodeAnalyzer {
@@ -208,6 +1108,7 @@ impl Default for CodeAnalyzer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ErrorKind;
     use tempfile::TempDir;
 
     #[test]
@@ -218,7 +1119,8 @@ mod tests {
         let result = analyzer.analyze_file(temp_dir.path());
         assert!(matches!(
             result,
-            Err(CodeStatsError::IoError(msg)) if msg.contains("is not a file")
+            Err(CodeStatsError::IoError { source, .. })
+                if source.kind() == io::ErrorKind::InvalidInput
         ));
     }
 
@@ -253,7 +1155,11 @@ mod tests {
 
         analyzer.analyze_file(&rs_file).unwrap();
         assert_eq!(analyzer.parsers.len(), 1);
-        assert!(analyzer.parsers.contains_key(&SupportedLanguage::Rust));
+        assert!(
+            analyzer
+                .parsers
+                .contains_key(&Grammar::Primary(SupportedLanguage::Rust))
+        );
 
         // Second analysis succeeds and parser count remains the same
         analyzer.analyze_file(&rs_file).unwrap();
@@ -268,7 +1174,8 @@ mod tests {
         let result = analyzer.analyze_file(non_existent);
         assert!(matches!(
             result,
-            Err(CodeStatsError::IoError(msg)) if !msg.is_empty()
+            Err(CodeStatsError::IoError { path: Some(path), source })
+                if path == non_existent && source.kind() == io::ErrorKind::NotFound
         ));
     }
 
@@ -286,6 +1193,61 @@ mod tests {
         let stats = result.unwrap();
         assert_eq!(stats.total_files(), 0);
         assert_eq!(stats.total_stats.function_count, 0);
+
+        // Skipped files are counted, but not listed as findings
+        assert_eq!(stats.unsupported_files, 2);
+        assert!(stats.findings.is_empty());
+        assert_eq!(stats.error_count(), 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_analyze_directory_records_unreadable_files_as_errors() {
+        let mut analyzer = CodeAnalyzer::new();
+        let temp_dir = TempDir::new().unwrap();
+
+        std::fs::write(temp_dir.path().join("valid.rs"), "fn valid() {}").unwrap();
+        std::os::unix::fs::symlink("missing.rs", temp_dir.path().join("broken.rs")).unwrap();
+
+        let stats = analyzer
+            .analyze_directory(temp_dir.path(), 100, true, &[])
+            .unwrap();
+
+        assert_eq!(stats.total_files(), 1);
+        assert_eq!(stats.error_count(), 1);
+        assert_eq!(
+            stats.findings[0].path,
+            Some(temp_dir.path().join("broken.rs"))
+        );
+    }
+
+    #[test]
+    fn test_analyze_directory_skips_files_not_worth_parsing() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("valid.rs"), "fn valid() {}").unwrap();
+        std::fs::write(temp_dir.path().join("invalid.rs"), [b'f', 0xC3, 0x28]).unwrap();
+        std::fs::write(
+            temp_dir.path().join("large.rs"),
+            "fn large() {}\n".repeat(10),
+        )
+        .unwrap();
+
+        let stats = CodeAnalyzer::new()
+            .with_detection(DetectionStrategy::Extension)
+            .with_encoding(Some(encoding_rs::UTF_8))
+            .with_max_file_size(Some(100))
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+
+        assert_eq!(stats.total_files(), 1);
+        assert_eq!(stats.error_count(), 0);
+        let mut kinds: Vec<String> = stats
+            .findings
+            .iter()
+            .filter_map(|finding| finding.kind.map(|kind| kind.to_string()))
+            .collect();
+        kinds.sort();
+        assert_eq!(kinds, ["encoding", "too_large"]);
     }
 
     #[test]
@@ -304,4 +1266,414 @@ mod tests {
         assert_eq!(stats.total_files(), 1);
         assert_eq!(stats.total_stats.function_count, 1);
     }
+
+    #[test]
+    fn test_analyze_directory_skips_hidden_entries_by_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join(".checkout");
+        std::fs::create_dir_all(root.join(".git/hooks")).unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(
+            root.join(".git/hooks/pre-commit.py"),
+            "def hook():\n    pass\n",
+        )
+        .unwrap();
+        std::fs::write(root.join(".build.rs"), "fn build() {}\n").unwrap();
+
+        // The root is analyzed although its own name is hidden
+        let stats = CodeAnalyzer::new()
+            .analyze_directory(&root, 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 1);
+
+        let stats = CodeAnalyzer::new()
+            .with_hidden(true)
+            .analyze_directory(&root, 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 3);
+    }
+
+    #[test]
+    fn test_analyze_directory_applies_config_count_policy() {
+        let mut analyzer = CodeAnalyzer::new();
+        let temp_dir = TempDir::new().unwrap();
+        let source = "fn main() {\n    retry(3, \"try again later\");\n}\n";
+
+        std::fs::create_dir(temp_dir.path().join("quiet")).unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), source).unwrap();
+        std::fs::write(temp_dir.path().join("quiet/main.rs"), source).unwrap();
+        std::fs::write(
+            temp_dir.path().join("quiet/.code-stats.toml"),
+            "[count]\nmagic_values = false\n",
+        )
+        .unwrap();
+
+        let stats = analyzer
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+
+        assert_eq!(stats.total_files(), 2);
+        assert_eq!(stats.total_stats.magic_value_count(), 2);
+        assert_eq!(stats.total_stats.untranslated_string_count, 2);
+    }
+
+    #[test]
+    fn test_analyze_records_modification_time_on_request() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {}\n").unwrap();
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let file_stats = CodeAnalyzer::new().analyze_file(&path).unwrap();
+        assert_eq!(file_stats.modified, None);
+
+        let analyzer = || CodeAnalyzer::new().with_modification_times(true);
+        let file_stats = analyzer().analyze_file(&path).unwrap();
+        assert_eq!(file_stats.modified, Some(1_700_000_000));
+
+        let stats = analyzer()
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.files[0].modified, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn test_analyze_directory_records_content_hashes_on_request() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("repo");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        let expected = content_hash(b"fn main() {}\n");
+
+        let analyze = |content_hashes: bool| {
+            CodeAnalyzer::new()
+                .with_content_hashes(content_hashes)
+                .with_cache_dir(Some(temp_dir.path().join("cache")))
+                .analyze_directory(&root, 100, false, &[])
+                .unwrap()
+                .files
+                .remove(0)
+                .sha256
+        };
+        assert_eq!(analyze(false), None);
+        // Results cached without hashes are not reused for a run with them
+        assert_eq!(analyze(true), Some(expected.clone()));
+        assert_eq!(analyze(true), Some(expected));
+        assert_eq!(analyze(false), None);
+    }
+
+    #[test]
+    fn test_analyze_directory_counts_packages_once() {
+        let mut analyzer = CodeAnalyzer::new();
+        let temp_dir = TempDir::new().unwrap();
+
+        std::fs::create_dir(temp_dir.path().join("pkg")).unwrap();
+        std::fs::write(temp_dir.path().join("pkg/__init__.py"), "").unwrap();
+        std::fs::write(temp_dir.path().join("pkg/util.py"), "def f():\n    pass\n").unwrap();
+        std::fs::write(
+            temp_dir.path().join("a.go"),
+            "package main\n\nfunc a() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("b.go"),
+            "package main\n\nfunc b() {}\n",
+        )
+        .unwrap();
+
+        let stats = analyzer
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+
+        assert_eq!(
+            stats.total_by_language[&SupportedLanguage::Python].module_count,
+            1
+        );
+        assert_eq!(
+            stats.total_by_language[&SupportedLanguage::Go].module_count,
+            1
+        );
+        assert_eq!(stats.total_stats.module_count, 2);
+    }
+
+    #[test]
+    fn test_analyze_file_parses_tsx_with_jsx_grammar() {
+        let mut analyzer = CodeAnalyzer::new();
+        let temp_dir = TempDir::new().unwrap();
+        let tsx_file = temp_dir.path().join("App.tsx");
+        let ts_file = temp_dir.path().join("cast.ts");
+        std::fs::write(
+            &tsx_file,
+            "function App() {\n    return <div>{render()}</div>;\n}\n\nfunction render() {\n    return <span />;\n}\n",
+        )
+        .unwrap();
+        // An angle-bracket assertion, which only the TypeScript grammar accepts
+        std::fs::write(
+            &ts_file,
+            "function f(x: unknown) {\n    return <string>x;\n}\n",
+        )
+        .unwrap();
+
+        let tsx_stats = analyzer.analyze_file(&tsx_file).unwrap();
+        let ts_stats = analyzer.analyze_file(&ts_file).unwrap();
+
+        assert_eq!(tsx_stats.stats.function_count, 2);
+        assert_eq!(ts_stats.stats.function_count, 1);
+        assert!(analyzer.parsers.contains_key(&Grammar::Tsx));
+        assert!(
+            analyzer
+                .parsers
+                .contains_key(&Grammar::Primary(SupportedLanguage::TypeScript))
+        );
+    }
+
+    #[test]
+    fn test_analyze_directory_can_exclude_suspicious_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("bundle.js"),
+            "function a(b,c){var d=b+c;return e(d,b)}".repeat(30),
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("main.js"), "function main() {}\n").unwrap();
+
+        let stats = CodeAnalyzer::new()
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 2);
+        assert!(stats.files.iter().any(|f| f.suspicion.is_some()));
+
+        let stats = CodeAnalyzer::new()
+            .with_exclude_suspicious(true)
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 1);
+        assert_eq!(stats.total_stats.function_count, 1);
+        assert_eq!(stats.excluded_files.len(), 1);
+        assert!(stats.excluded_files[0].path.ends_with("bundle.js"));
+    }
+
+    #[test]
+    fn test_analyze_directory_sets_aside_vendored_and_generated_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let vendor = temp_dir.path().join("vendor");
+        std::fs::create_dir(&vendor).unwrap();
+        std::fs::write(vendor.join("lib.go"), "package lib\n\nfunc A() {}\n").unwrap();
+        std::fs::write(
+            temp_dir.path().join("enum_string.go"),
+            "// Code generated by stringer; DO NOT EDIT.\n\npackage main\n\nfunc B() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("main.go"),
+            "package main\n\nfunc main() {}\n",
+        )
+        .unwrap();
+
+        // By default vendored files are set aside and generated files counted
+        let stats = CodeAnalyzer::new()
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 2);
+        assert_eq!(stats.set_aside_files.len(), 1);
+        assert_eq!(stats.set_aside_files[0].origin, Some(Origin::Vendored));
+        assert!(
+            stats
+                .files
+                .iter()
+                .any(|f| f.origin == Some(Origin::Generated))
+        );
+
+        let stats = CodeAnalyzer::new()
+            .with_include_vendored(true)
+            .with_exclude_generated(true)
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 2);
+        assert_eq!(stats.set_aside_files.len(), 1);
+        assert!(stats.set_aside_files[0].path.ends_with("enum_string.go"));
+
+        // The analyzed directory's own name does not count
+        let stats = CodeAnalyzer::new()
+            .analyze_directory(&vendor, 100, false, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 1);
+        assert!(stats.set_aside_files.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_directory_stops_when_interrupted() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+        let interrupt = Interrupt::default();
+        interrupt.raise();
+        let stats = CodeAnalyzer::new()
+            .with_interrupt(interrupt)
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+
+        assert!(stats.interrupted);
+        assert_eq!(stats.total_files(), 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_analyze_directory_counts_linked_files_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        std::os::unix::fs::symlink(root.join("main.rs"), root.join("alias.rs")).unwrap();
+
+        let stats = CodeAnalyzer::new()
+            .analyze_directory(root, 100, true, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 1);
+        assert_eq!(stats.findings[0].kind, Some(ErrorKind::Duplicate));
+
+        let stats = CodeAnalyzer::new()
+            .with_dedup_links(false)
+            .analyze_directory(root, 100, true, &[])
+            .unwrap();
+        assert_eq!(stats.total_files(), 2);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_analyze_directory_skips_files_behind_too_many_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("real/nested")).unwrap();
+        std::fs::write(root.join("real/nested/lib.rs"), "fn lib() {}\n").unwrap();
+        std::os::unix::fs::symlink(root.join("real/nested"), root.join("real/inner")).unwrap();
+        std::os::unix::fs::symlink(root.join("real"), root.join("outer")).unwrap();
+
+        let limits = TraversalLimits {
+            max_link_depth: Some(1),
+            ..TraversalLimits::default()
+        };
+        let stats = CodeAnalyzer::new()
+            .with_dedup_links(false)
+            .with_limits(limits)
+            .analyze_directory(root, 100, true, &[])
+            .unwrap();
+        // Reached directly, through `outer` and through `real/inner`, but not
+        // through both links
+        assert_eq!(stats.total_files(), 3);
+        let skipped: Vec<_> = stats
+            .findings
+            .iter()
+            .filter(|finding| finding.kind == Some(ErrorKind::LinkDepth))
+            .map(|finding| finding.path.clone().unwrap())
+            .collect();
+        assert_eq!(skipped, [root.join("outer/inner")]);
+    }
+
+    #[test]
+    fn test_analyze_directory_fails_when_exceeding_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("b.rs"), "fn b() {}\n").unwrap();
+
+        let analyze = |limits| {
+            CodeAnalyzer::new().with_limits(limits).analyze_directory(
+                temp_dir.path(),
+                100,
+                false,
+                &[],
+            )
+        };
+        let files = TraversalLimits {
+            max_files: Some(1),
+            ..TraversalLimits::default()
+        };
+        assert!(matches!(
+            analyze(files),
+            Err(CodeStatsError::LimitExceeded(message)) if message.contains("--max-files")
+        ));
+        let bytes = TraversalLimits {
+            max_total_bytes: Some(15),
+            ..TraversalLimits::default()
+        };
+        assert!(matches!(
+            analyze(bytes),
+            Err(CodeStatsError::LimitExceeded(message)) if message.contains("--max-total-bytes")
+        ));
+
+        let enough = TraversalLimits {
+            max_files: Some(2),
+            max_total_bytes: Some(20),
+            ..TraversalLimits::default()
+        };
+        assert_eq!(analyze(enough).unwrap().total_files(), 2);
+
+        // Content detection fails before identifying anything
+        let analyzer = CodeAnalyzer::new().with_limits(bytes);
+        assert!(matches!(
+            analyzer.candidate_paths(temp_dir.path(), analyzer.walk.clone()),
+            Err(CodeStatsError::LimitExceeded(message)) if message.contains("--max-total-bytes")
+        ));
+        let analyzer = CodeAnalyzer::new().with_limits(files);
+        assert!(matches!(
+            analyzer.candidate_paths(temp_dir.path(), analyzer.walk.clone()),
+            Err(CodeStatsError::LimitExceeded(message)) if message.contains("--max-files")
+        ));
+    }
+
+    #[test]
+    fn test_analyze_directory_reuses_cached_results_of_unchanged_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("repo");
+        let cache_dir = temp_dir.path().join("cache");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let analyze = || {
+            CodeAnalyzer::new()
+                .with_cache_dir(Some(cache_dir.clone()))
+                .analyze_directory(&root, 100, false, &[])
+                .unwrap()
+        };
+        assert_eq!(analyze().total_stats.function_count, 1);
+
+        // Tamper with the cached result to tell reuse apart from re-analysis
+        let cache_file = std::fs::read_dir(cache_dir.join("files"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension() == Some("json".as_ref()))
+            .unwrap();
+        let cached = std::fs::read_to_string(&cache_file).unwrap();
+        std::fs::write(
+            &cache_file,
+            cached.replace("\"function_count\":1", "\"function_count\":42"),
+        )
+        .unwrap();
+        assert_eq!(analyze().total_stats.function_count, 42);
+
+        std::fs::write(root.join("main.rs"), "fn main() {}\nfn other() {}\n").unwrap();
+        assert_eq!(analyze().total_stats.function_count, 2);
+    }
+
+    #[test]
+    fn test_analyze_directory_only_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let changed = temp_dir.path().join("changed.rs");
+        std::fs::write(&changed, "fn a() {}\nfn b() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("other.rs"), "fn c() {}\n").unwrap();
+
+        let stats = CodeAnalyzer::new()
+            .with_only_files(HashSet::from([changed.clone()]))
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+
+        assert_eq!(stats.total_files(), 1);
+        assert_eq!(stats.files[0].path, changed);
+        assert_eq!(stats.total_stats.function_count, 2);
+    }
 }
@@ -0,0 +1,314 @@
+//! Reading the files of `.zip`, `.tar` and `.tar.gz` archives into memory.
+//!
+//! Release tarballs and dependency archives are analyzed without being
+//! extracted: every regular file is read into memory and handed to the
+//! analyzer like the files of a git revision. Nothing is written to disk, so
+//! entries with absolute paths or `..` components cannot escape anywhere;
+//! they are left out all the same, together with links and directories.
+//!
+//! Compressed archives can expand to far more than their own size, so every
+//! file counts against the traversal limits as it is read, and files larger
+//! than the size limit are read no further than that limit.
+
+use crate::analyzer::TraversalLimits;
+use crate::error::{CodeStatsError, Result};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// The archive formats that can be analyzed, told apart by file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArchiveFormat {
+    /// `.zip`, deflated or stored
+    Zip,
+    /// `.tar`
+    Tar,
+    /// `.tar.gz` or `.tgz`
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Recognizes an archive by the extension of its file name, ignoring case.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(ArchiveFormat)` - For `.zip`, `.tar`, `.tar.gz` and `.tgz` files
+    /// * `None` - For any other file, e.g. source code
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// The content of a file of an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Member {
+    /// The file was read
+    Read(Vec<u8>),
+    /// The file was left unread for being larger than the size limit, with
+    /// its size
+    TooLarge(u64),
+}
+
+/// Reads the regular files of an archive.
+///
+/// # Arguments
+///
+/// * `path` - The archive, whose format is recognized by [`ArchiveFormat::from_path`]
+/// * `max_file_size` - Files larger than this many bytes are left unread
+/// * `limits` - Limits on the files and bytes read, every regular file of the
+///   archive included
+///
+/// # Returns
+///
+/// * `Ok(Vec<(PathBuf, Member)>)` - Each file, joined onto `path`, with its
+///   content, sorted by path
+/// * `Err(CodeStatsError::UnsupportedFileType)` if `path` is no archive
+/// * `Err(CodeStatsError::IoError)` if the archive cannot be read or is corrupt
+/// * `Err(CodeStatsError::LimitExceeded)` if the files exceed a limit
+pub(crate) fn archive_files(
+    path: &Path,
+    max_file_size: Option<u64>,
+    limits: TraversalLimits,
+) -> Result<Vec<(PathBuf, Member)>> {
+    let format = ArchiveFormat::from_path(path)
+        .ok_or_else(|| CodeStatsError::UnsupportedFileType(path.display().to_string()))?;
+    let file = File::open(path).map_err(|e| CodeStatsError::io(path, e))?;
+    let reader = BufReader::new(file);
+
+    let mut members = MemberReader {
+        path,
+        max_file_size,
+        limits,
+        read: (0, 0),
+    };
+    let mut files = match format {
+        ArchiveFormat::Zip => zip_files(reader, &mut members),
+        ArchiveFormat::Tar => tar_files(reader, &mut members),
+        ArchiveFormat::TarGz => tar_files(flate2::read::GzDecoder::new(reader), &mut members),
+    }?;
+
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files
+        .into_iter()
+        .map(|(name, content)| (path.join(name), content))
+        .collect())
+}
+
+/// Reads the files of an archive within the limits of an analysis.
+struct MemberReader<'a> {
+    /// The archive, for errors
+    path: &'a Path,
+    max_file_size: Option<u64>,
+    limits: TraversalLimits,
+    /// Files and bytes read so far, checked against the limits
+    read: (usize, u64),
+}
+
+impl MemberReader<'_> {
+    /// Reads a file whose header claims `size` bytes.
+    ///
+    /// A file whose header claims more than the size limit is left unread.
+    /// Headers can understate the size of a compressed file, so reading
+    /// stops one byte past the limit either way.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Member)` - The content of the file, or its size if too large
+    /// * `Err(CodeStatsError::IoError)` if the file cannot be decompressed
+    /// * `Err(CodeStatsError::LimitExceeded)` if the file exceeds a limit
+    fn read(&mut self, member: impl Read, size: u64) -> Result<Member> {
+        let limit = self.max_file_size.unwrap_or(u64::MAX);
+        if size > limit {
+            return Ok(Member::TooLarge(size));
+        }
+
+        // Not sized by the header, which may claim any size without a limit
+        let mut content = Vec::new();
+        member
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut content)
+            .map_err(|e| self.io(e))?;
+        let read = content.len() as u64;
+        if read > limit {
+            return Ok(Member::TooLarge(read));
+        }
+
+        self.read.0 += 1;
+        self.read.1 += read;
+        self.limits.check(self.read)?;
+        Ok(Member::Read(content))
+    }
+
+    /// Attributes an error to the archive.
+    fn io(&self, e: impl Into<io::Error>) -> CodeStatsError {
+        CodeStatsError::io(self.path, e.into())
+    }
+}
+
+/// Reads the regular files of a zip archive, with their paths in the archive.
+fn zip_files(
+    reader: impl Read + io::Seek,
+    members: &mut MemberReader,
+) -> Result<Vec<(PathBuf, Member)>> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(|e| members.io(e))?;
+    let mut files = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(|e| members.io(e))?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name().as_deref().and_then(archive_path) else {
+            continue;
+        };
+        let size = entry.size();
+        files.push((name, members.read(entry, size)?));
+    }
+    Ok(files)
+}
+
+/// Reads the regular files of a tar stream, with their paths in the archive.
+fn tar_files(reader: impl Read, members: &mut MemberReader) -> Result<Vec<(PathBuf, Member)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = Vec::new();
+    for entry in archive.entries().map_err(|e| members.io(e))? {
+        let entry = entry.map_err(|e| members.io(e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some(name) = archive_path(&entry.path().map_err(|e| members.io(e))?) else {
+            continue;
+        };
+        let size = entry.size();
+        files.push((name, members.read(entry, size)?));
+    }
+    Ok(files)
+}
+
+/// Normalizes the path of an archive entry, dropping `.` components.
+///
+/// # Returns
+///
+/// * `Some(PathBuf)` - The relative path of the entry
+/// * `None` - For empty and absolute paths, and paths with `..` components
+fn archive_path(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!normalized.as_os_str().is_empty()).then_some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_format_from_path() {
+        for (path, expected) in [
+            ("release.zip", Some(ArchiveFormat::Zip)),
+            ("release-1.0.TAR.GZ", Some(ArchiveFormat::TarGz)),
+            ("crate.tgz", Some(ArchiveFormat::TarGz)),
+            ("backup.tar", Some(ArchiveFormat::Tar)),
+            ("main.rs", None),
+            ("tar", None),
+        ] {
+            assert_eq!(
+                ArchiveFormat::from_path(Path::new(path)),
+                expected,
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_archive_path() {
+        assert_eq!(
+            archive_path(Path::new("./pkg/src/lib.rs")),
+            Some(PathBuf::from("pkg/src/lib.rs"))
+        );
+        assert_eq!(archive_path(Path::new("../outside.rs")), None);
+        assert_eq!(archive_path(Path::new("/etc/passwd")), None);
+        assert_eq!(archive_path(Path::new(".")), None);
+    }
+
+    /// Writes a `.tar.gz` archive of the given files.
+    fn create_tar_gz(path: &Path, files: &[(&str, &[u8])]) {
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_tar_gz_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("release.tar.gz");
+        create_tar_gz(
+            &path,
+            &[
+                ("pkg/src/main.rs", b"fn main() {}\n"),
+                ("pkg/a.py", b"x = 1\n"),
+            ],
+        );
+
+        let files = archive_files(&path, None, TraversalLimits::default()).unwrap();
+        let names: Vec<_> = files.iter().map(|(name, _)| name.clone()).collect();
+        assert_eq!(names, [path.join("pkg/a.py"), path.join("pkg/src/main.rs")]);
+        assert_eq!(files[1].1, Member::Read(b"fn main() {}\n".to_vec()));
+    }
+
+    /// Tests that a file expanding past the size limits is not read into memory.
+    #[test]
+    fn test_archive_files_within_limits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("bomb.tar.gz");
+        let zeros = vec![0; 1 << 20];
+        create_tar_gz(&path, &[("a.rs", b"fn a() {}\n"), ("b.rs", &zeros)]);
+
+        let files = archive_files(&path, Some(1024), TraversalLimits::default()).unwrap();
+        assert_eq!(files[0].1, Member::Read(b"fn a() {}\n".to_vec()));
+        assert_eq!(files[1].1, Member::TooLarge(1 << 20));
+
+        let limits = TraversalLimits {
+            max_total_bytes: Some(1024),
+            ..TraversalLimits::default()
+        };
+        let error = archive_files(&path, None, limits).unwrap_err();
+        assert!(matches!(error, CodeStatsError::LimitExceeded(_)));
+
+        // A header understating the size does not lift the limit
+        let mut members = MemberReader {
+            path: &path,
+            max_file_size: Some(1024),
+            limits: TraversalLimits::default(),
+            read: (0, 0),
+        };
+        assert_eq!(
+            members.read(zeros.as_slice(), 10).unwrap(),
+            Member::TooLarge(1025)
+        );
+        assert_eq!(members.read.0, 0);
+    }
+}
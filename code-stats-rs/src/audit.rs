@@ -0,0 +1,325 @@
+//! Repository policy audits run over the analyzed source files.
+
+use crate::language::SupportedLanguage;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// The license header every source file is required to start with.
+///
+/// Template lines are stored without comment markers so that a single template
+/// can be checked against languages with different comment syntax. Blank lines
+/// are ignored on both sides of the comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HeaderTemplate {
+    lines: Vec<String>,
+}
+
+impl HeaderTemplate {
+    /// Builds a template from raw header text.
+    ///
+    /// The text may be written either as plain lines or already wrapped in
+    /// comment markers (`//`, `#`, `/* ... */`); the markers are stripped.
+    pub(crate) fn parse(text: &str) -> Self {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        let lines = text
+            .lines()
+            .map(strip_any_comment_marker)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        Self { lines }
+    }
+
+    /// Returns `true` if the template contains no header text at all.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Checks whether the source code starts with this header.
+    ///
+    /// A leading byte order mark and a shebang line are skipped before the
+    /// header comment is read, and blank lines before the header are tolerated.
+    pub(crate) fn matches(&self, source_code: &str, language: &SupportedLanguage) -> bool {
+        let header = leading_comment_lines(source_code, language);
+        header.len() >= self.lines.len()
+            && header
+                .iter()
+                .zip(&self.lines)
+                .all(|(actual, expected)| actual == expected)
+    }
+}
+
+/// Why a file failed the license header audit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ViolationReason {
+    /// The file does not start with the required header.
+    MissingHeader,
+    /// The file could not be read, so the header could not be checked.
+    Unreadable(String),
+}
+
+/// A single file that failed the license header audit.
+#[derive(Debug, Clone)]
+pub(crate) struct HeaderViolation {
+    /// The path to the offending file
+    pub path: PathBuf,
+    /// The detected programming language of the file
+    pub language: SupportedLanguage,
+    /// Why the file failed the audit
+    pub reason: ViolationReason,
+}
+
+/// Per-language results of a license header audit.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LanguageAudit {
+    /// Number of files checked for this language
+    pub file_count: usize,
+    /// Number of files of this language that failed the audit
+    pub violation_count: usize,
+}
+
+/// Results of auditing a set of source files for the required license header.
+#[derive(Debug, Default)]
+pub(crate) struct LicenseAudit {
+    /// Audit results grouped by programming language
+    pub by_language: HashMap<SupportedLanguage, LanguageAudit>,
+    /// Every file that failed the audit, in the order they were checked
+    pub violations: Vec<HeaderViolation>,
+}
+
+impl LicenseAudit {
+    /// Returns the total number of files that were checked.
+    pub(crate) fn total_files(&self) -> usize {
+        self.by_language
+            .values()
+            .map(|audit| audit.file_count)
+            .sum()
+    }
+
+    /// Returns `true` if every checked file carries the required header.
+    pub(crate) fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks every given source file for the required license header.
+///
+/// Files that cannot be read are reported as violations rather than aborting
+/// the audit, so one unreadable file does not hide problems in the rest.
+pub(crate) fn audit_license_headers(
+    files: &[(PathBuf, SupportedLanguage)],
+    template: &HeaderTemplate,
+) -> LicenseAudit {
+    let mut audit = LicenseAudit::default();
+
+    for (path, language) in files {
+        let reason = match fs::read_to_string(path) {
+            Ok(source_code) if template.matches(&source_code, language) => None,
+            Ok(_) => Some(ViolationReason::MissingHeader),
+            Err(e) => Some(ViolationReason::Unreadable(e.to_string())),
+        };
+
+        let lang_audit = audit.by_language.entry(*language).or_default();
+        lang_audit.file_count += 1;
+
+        if let Some(reason) = reason {
+            lang_audit.violation_count += 1;
+            audit.violations.push(HeaderViolation {
+                path: path.clone(),
+                language: *language,
+                reason,
+            });
+        }
+    }
+
+    audit
+}
+
+/// Extracts the text of the comment block at the top of a source file.
+///
+/// Comment markers are stripped from each line and blank lines are dropped.
+/// Reading stops at the first line of code.
+fn leading_comment_lines(source_code: &str, language: &SupportedLanguage) -> Vec<String> {
+    let source_code = source_code.strip_prefix('\u{feff}').unwrap_or(source_code);
+    let mut lines = source_code.lines().peekable();
+
+    // Interpreter directives have to stay on the first line, so the header follows them
+    if lines.peek().is_some_and(|line| line.starts_with("#!")) {
+        lines.next();
+    }
+
+    let mut header = Vec::new();
+    let mut in_block_comment = false;
+
+    for line in lines {
+        let trimmed = line.trim();
+
+        if in_block_comment {
+            let (content, closed) = match trimmed.find("*/") {
+                Some(end) => (&trimmed[..end], true),
+                None => (trimmed, false),
+            };
+            push_comment_text(&mut header, content.trim_start_matches('*'));
+            in_block_comment = !closed;
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        match language {
+            SupportedLanguage::Python => match trimmed.strip_prefix('#') {
+                Some(content) => push_comment_text(&mut header, content.trim_start_matches('#')),
+                None => break,
+            },
+            _ => {
+                if let Some(content) = trimmed.strip_prefix("//") {
+                    push_comment_text(&mut header, content.trim_start_matches(['/', '!']));
+                } else if let Some(content) = trimmed.strip_prefix("/*") {
+                    let content = content.trim_start_matches(['*', '!']);
+                    match content.find("*/") {
+                        Some(end) => push_comment_text(&mut header, &content[..end]),
+                        None => {
+                            push_comment_text(&mut header, content);
+                            in_block_comment = true;
+                        }
+                    }
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    header
+}
+
+/// Appends a line of comment text to the header unless it is blank.
+fn push_comment_text(header: &mut Vec<String>, content: &str) {
+    let content = content.trim();
+    if !content.is_empty() {
+        header.push(content.to_string());
+    }
+}
+
+/// Strips whichever comment marker a template line is written with.
+fn strip_any_comment_marker(line: &str) -> &str {
+    let line = line.trim();
+    let line = line.strip_suffix("*/").unwrap_or(line);
+    line.trim_start_matches(['/', '*', '#', '!']).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const TEMPLATE: &str = "Copyright 2024 Example Corp\nSPDX-License-Identifier: MIT\n";
+
+    #[test]
+    fn test_template_strips_comment_markers() {
+        let plain = HeaderTemplate::parse(TEMPLATE);
+        let commented = HeaderTemplate::parse(
+            "// Copyright 2024 Example Corp\n//\n// SPDX-License-Identifier: MIT\n",
+        );
+
+        assert_eq!(plain, commented);
+        assert!(!plain.is_empty());
+        assert!(HeaderTemplate::parse("\n//\n").is_empty());
+    }
+
+    #[test]
+    fn test_matches_line_comments() {
+        let template = HeaderTemplate::parse(TEMPLATE);
+        let source =
+            "// Copyright 2024 Example Corp\n// SPDX-License-Identifier: MIT\n\nfn main() {}\n";
+
+        assert!(template.matches(source, &SupportedLanguage::Rust));
+        assert!(!template.matches("fn main() {}\n", &SupportedLanguage::Rust));
+    }
+
+    #[test]
+    fn test_matches_block_comments() {
+        let template = HeaderTemplate::parse(TEMPLATE);
+        let source = "/*\n * Copyright 2024 Example Corp\n * SPDX-License-Identifier: MIT\n */\npublic class Main {}\n";
+
+        assert!(template.matches(source, &SupportedLanguage::Java));
+    }
+
+    #[test]
+    fn test_matches_python_hash_comments_after_shebang_and_bom() {
+        let template = HeaderTemplate::parse(TEMPLATE);
+        let source = "\u{feff}#!/usr/bin/env python3\n# Copyright 2024 Example Corp\n# SPDX-License-Identifier: MIT\ndef main():\n    pass\n";
+
+        assert!(template.matches(source, &SupportedLanguage::Python));
+    }
+
+    #[test]
+    fn test_header_must_come_before_code() {
+        let template = HeaderTemplate::parse(TEMPLATE);
+        let source =
+            "package main\n// Copyright 2024 Example Corp\n// SPDX-License-Identifier: MIT\n";
+
+        assert!(!template.matches(source, &SupportedLanguage::Go));
+    }
+
+    #[test]
+    fn test_partial_header_does_not_match() {
+        let template = HeaderTemplate::parse(TEMPLATE);
+        let source = "// Copyright 2024 Example Corp\nfn main() {}\n";
+
+        assert!(!template.matches(source, &SupportedLanguage::Rust));
+    }
+
+    #[test]
+    fn test_audit_license_headers_groups_by_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let good = temp_dir.path().join("good.rs");
+        let bad = temp_dir.path().join("bad.rs");
+        let script = temp_dir.path().join("script.py");
+        fs::write(
+            &good,
+            "// Copyright 2024 Example Corp\n// SPDX-License-Identifier: MIT\nfn a() {}\n",
+        )
+        .unwrap();
+        fs::write(&bad, "fn b() {}\n").unwrap();
+        fs::write(&script, "def c():\n    pass\n").unwrap();
+
+        let files = vec![
+            (good, SupportedLanguage::Rust),
+            (bad.clone(), SupportedLanguage::Rust),
+            (script, SupportedLanguage::Python),
+        ];
+        let audit = audit_license_headers(&files, &HeaderTemplate::parse(TEMPLATE));
+
+        assert!(!audit.passed());
+        assert_eq!(audit.total_files(), 3);
+        assert_eq!(audit.violations.len(), 2);
+        assert_eq!(audit.violations[0].path, bad);
+        assert_eq!(audit.violations[0].reason, ViolationReason::MissingHeader);
+
+        let rust = &audit.by_language[&SupportedLanguage::Rust];
+        assert_eq!(rust.file_count, 2);
+        assert_eq!(rust.violation_count, 1);
+        assert_eq!(
+            audit.by_language[&SupportedLanguage::Python].violation_count,
+            1
+        );
+    }
+
+    #[test]
+    fn test_audit_reports_unreadable_files() {
+        let files = vec![(
+            PathBuf::from("/non/existent/file.rs"),
+            SupportedLanguage::Rust,
+        )];
+        let audit = audit_license_headers(&files, &HeaderTemplate::parse(TEMPLATE));
+
+        assert!(matches!(
+            audit.violations[0].reason,
+            ViolationReason::Unreadable(_)
+        ));
+    }
+}
@@ -0,0 +1,185 @@
+//! SVG badges in the style of shields.io for embedding in READMEs.
+//!
+//! A badge has a grey label on the left and a colored message on the right,
+//! e.g. "functions | 1,234" or "Rust | 62%".
+
+use crate::formatter::NumberStyle;
+use crate::language::SupportedLanguage;
+use crate::stats::DirectoryStats;
+use clap::ValueEnum;
+
+/// Approximate width of a character of 11px Verdana, the badge font.
+const CHAR_WIDTH: usize = 7;
+
+/// Horizontal padding around the label and around the message.
+const PADDING: usize = 10;
+
+/// The statistic a badge shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BadgeMetric {
+    /// Number of analyzed files
+    Files,
+    /// Number of functions
+    Functions,
+    /// Number of classes/structs
+    Classes,
+    /// Number of code lines
+    Lines,
+    /// Share of the code lines written in the language given by --language,
+    /// or in the most used language
+    Language,
+}
+
+/// Chooses the label and message of a badge.
+///
+/// # Arguments
+///
+/// * `stats` - The analysis the badge summarizes
+/// * `metric` - The statistic to show
+/// * `language` - The language of a [`BadgeMetric::Language`] badge, or
+///   `None` for the language with the most code lines
+///
+/// # Returns
+///
+/// * `Ok((label, message))`, e.g. `("functions", "1,234")` or `("Rust", "62%")`;
+///   counts are grouped with commas regardless of the locale
+/// * `Err(String)` for a language badge of an analysis without files
+pub(crate) fn badge_text(
+    stats: &DirectoryStats,
+    metric: BadgeMetric,
+    language: Option<SupportedLanguage>,
+) -> Result<(String, String), String> {
+    let (label, count) = match metric {
+        BadgeMetric::Files => ("files", stats.total_files()),
+        BadgeMetric::Functions => ("functions", stats.total_stats.function_count),
+        BadgeMetric::Classes => ("classes", stats.total_stats.class_struct_count),
+        BadgeMetric::Lines => ("lines of code", stats.total_stats.code_lines),
+        BadgeMetric::Language => return language_share(stats, language),
+    };
+    Ok((label.to_string(), NumberStyle::default().number(count)))
+}
+
+/// Labels a language with its share of the code lines, rounded to a percent.
+fn language_share(
+    stats: &DirectoryStats,
+    language: Option<SupportedLanguage>,
+) -> Result<(String, String), String> {
+    let language = match language {
+        Some(language) => language,
+        // Ties go to the alphabetically first name, so badges are stable
+        None => stats
+            .total_by_language
+            .iter()
+            .max_by(|(a, a_stats), (b, b_stats)| {
+                a_stats
+                    .code_lines
+                    .cmp(&b_stats.code_lines)
+                    .then_with(|| b.name().cmp(a.name()))
+            })
+            .map(|(language, _)| *language)
+            .ok_or("No files were analyzed, so there is no language to show")?,
+    };
+
+    let lines = stats
+        .total_by_language
+        .get(&language)
+        .map_or(0, |language_stats| language_stats.code_lines);
+    let share = lines as f64 * 100.0 / stats.total_stats.code_lines.max(1) as f64;
+    Ok((language.name().to_string(), format!("{share:.0}%")))
+}
+
+/// Renders a flat badge as a standalone SVG document.
+///
+/// # Arguments
+///
+/// * `label` - Text of the grey left half
+/// * `message` - Text of the colored right half
+/// * `color` - Fill of the right half, a CSS color such as `#4c1` or `blue`
+pub(crate) fn render_badge(label: &str, message: &str, color: &str) -> String {
+    let label_width = label.chars().count() * CHAR_WIDTH + PADDING;
+    let message_width = message.chars().count() * CHAR_WIDTH + PADDING;
+    let width = label_width + message_width;
+    let label_x = label_width / 2;
+    let message_x = label_width + message_width / 2;
+    let (label, message, color) = (escape(label), escape(message), escape(color));
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r">
+    <rect width="{width}" height="20" rx="3" fill="#fff"/>
+  </clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##
+    )
+}
+
+/// Escapes text for use in XML content and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::CodeStats;
+    use crate::stats::FileStats;
+    use std::path::PathBuf;
+
+    fn file(path: &str, language: SupportedLanguage, code_lines: usize) -> FileStats {
+        let stats = CodeStats {
+            function_count: 617,
+            code_lines,
+            ..CodeStats::default()
+        };
+        FileStats::new(PathBuf::from(path), language, stats)
+    }
+
+    #[test]
+    fn test_badge_text() {
+        let mut stats = DirectoryStats::new();
+        stats.add_file(file("a.rs", SupportedLanguage::Rust, 62));
+        stats.add_file(file("b.py", SupportedLanguage::Python, 38));
+
+        assert_eq!(
+            badge_text(&stats, BadgeMetric::Functions, None).unwrap(),
+            ("functions".to_string(), "1,234".to_string())
+        );
+        assert_eq!(
+            badge_text(&stats, BadgeMetric::Language, None).unwrap(),
+            ("Rust".to_string(), "62%".to_string())
+        );
+        assert_eq!(
+            badge_text(&stats, BadgeMetric::Language, Some(SupportedLanguage::Go)).unwrap(),
+            ("Go".to_string(), "0%".to_string())
+        );
+        assert!(badge_text(&DirectoryStats::new(), BadgeMetric::Language, None).is_err());
+    }
+
+    #[test]
+    fn test_render_badge_escapes_text() {
+        let svg = render_badge("a<b", "1 & 2", "#4c1");
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"aria-label="a&lt;b: 1 &amp; 2""#));
+        assert!(svg.contains(r##"fill="#4c1""##));
+        assert!(!svg.contains("a<b"));
+    }
+}
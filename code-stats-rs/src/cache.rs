@@ -0,0 +1,366 @@
+//! On-disk cache of per-file results, so unchanged files are not parsed again.
+//!
+//! Each analyzed root directory gets its own cache file, holding the
+//! statistics of every file keyed by its path below the root. An entry is
+//! only reused when the file's content hash and the settings that affect its
+//! statistics (language, counting policy, secret patterns) are unchanged.
+//! Cache files of another [`CACHE_FORMAT_VERSION`] are ignored.
+//!
+//! Next to each cache file, a `.root` file records the root it belongs to;
+//! saving a cache removes the cache files of roots that no longer exist,
+//! such as temporary directories.
+
+use crate::error::{CodeStatsError, Result};
+use crate::provenance::hex;
+use crate::stats::FileStats;
+use hmac_sha256::Hash;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the cache file layout, including the serialized shape of
+/// [`FileStats`]; bump it whenever either changes, so older entries are not
+/// read as results of the current version.
+const CACHE_FORMAT_VERSION: u32 = 2;
+
+/// Name of the directory below the user's cache directory.
+const CACHE_DIR_NAME: &str = "code-stats-rs";
+
+/// Number of hex digits of the root's digest used as the cache file name.
+const ROOT_ID_LENGTH: usize = 16;
+
+/// Extension of the file recording the root of a cache file.
+const ROOT_EXTENSION: &str = "root";
+
+/// Returns the default cache directory, `$XDG_CACHE_HOME/code-stats-rs` or
+/// `~/.cache/code-stats-rs`.
+///
+/// # Returns
+///
+/// * `Some(PathBuf)` - The cache directory (it may not exist yet)
+/// * `None` if neither `XDG_CACHE_HOME` nor `HOME` is set
+pub(crate) fn default_cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .map(|dir| dir.join(CACHE_DIR_NAME))
+}
+
+/// Returns the SHA-256 of a file's content in hex, as stored in the cache.
+pub(crate) fn content_hash(content: &[u8]) -> String {
+    hex(&Hash::hash(content))
+}
+
+/// A cached result and the inputs it was computed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// SHA-256 of the file's content, in hex
+    hash: String,
+    /// Fingerprint of the settings the statistics depend on
+    settings: String,
+    stats: FileStats,
+}
+
+/// The contents of a cache file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// [`CACHE_FORMAT_VERSION`] of the tool that wrote the file
+    format: u32,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+/// The per-file results of one root directory.
+///
+/// Entries found during a run are kept in memory and written back by
+/// [`FileCache::save`], which drops the entries of files that were not
+/// visited (deleted or now ignored) unless the run was cut short.
+#[derive(Debug)]
+pub(crate) struct FileCache {
+    root: PathBuf,
+    /// The root as recorded next to the cache file
+    canonical: PathBuf,
+    file: PathBuf,
+    previous: HashMap<PathBuf, CacheEntry>,
+    visited: HashMap<PathBuf, CacheEntry>,
+}
+
+impl FileCache {
+    /// Opens the cache of `root` inside `cache_dir`.
+    ///
+    /// A missing, unreadable or outdated cache file is treated as empty.
+    pub(crate) fn open(cache_dir: &Path, root: &Path) -> Self {
+        let canonical = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let mut id = hex(&Hash::hash(canonical.as_os_str().as_encoded_bytes()));
+        id.truncate(ROOT_ID_LENGTH);
+        let file = cache_dir.join("files").join(format!("{id}.json"));
+
+        let previous = fs::read_to_string(&file)
+            .ok()
+            .and_then(|text| serde_json::from_str::<CacheFile>(&text).ok())
+            .filter(|cache| cache.format == CACHE_FORMAT_VERSION)
+            .map(|cache| cache.entries)
+            .unwrap_or_default();
+
+        Self {
+            root: root.to_path_buf(),
+            canonical,
+            file,
+            previous,
+            visited: HashMap::new(),
+        }
+    }
+
+    /// Returns the cached statistics of a file if its content and settings are unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file, as found while traversing the root
+    /// * `hash` - The [`content_hash`] of the file's current content
+    /// * `settings` - Fingerprint of the settings its statistics depend on
+    ///
+    /// # Returns
+    ///
+    /// * `Some(FileStats)` - The cached statistics, reported under `path`
+    /// * `None` if the file must be analyzed again
+    pub(crate) fn lookup(&mut self, path: &Path, hash: &str, settings: &str) -> Option<FileStats> {
+        let key = self.key(path);
+        let entry = self
+            .previous
+            .remove(&key)
+            .filter(|entry| entry.hash == hash && entry.settings == settings)?;
+
+        let mut stats = entry.stats.clone();
+        stats.path = path.to_path_buf();
+        self.visited.insert(key, entry);
+        Some(stats)
+    }
+
+    /// Stores the statistics of a file, computed for content with the given hash.
+    pub(crate) fn insert(&mut self, hash: String, settings: String, stats: &FileStats) {
+        let key = self.key(&stats.path);
+        self.visited.insert(
+            key,
+            CacheEntry {
+                hash,
+                settings,
+                stats: stats.clone(),
+            },
+        );
+    }
+
+    /// Writes the cache file, and removes those of roots that no longer exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `complete` - Whether every file of the root was visited; if not, the
+    ///   entries of unvisited files are kept for the next run
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the cache file was written
+    /// * `Err(CodeStatsError::IoError)` if it could not be written
+    pub(crate) fn save(self, complete: bool) -> Result<()> {
+        let write_error = |e| CodeStatsError::io(&self.file, e);
+
+        let mut entries = self.visited;
+        if !complete {
+            for (key, entry) in self.previous {
+                entries.entry(key).or_insert(entry);
+            }
+        }
+
+        let dir = self.file.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir).map_err(write_error)?;
+        prune(dir);
+
+        // Recorded first, so a cache file never lacks its root
+        fs::write(
+            self.file.with_extension(ROOT_EXTENSION),
+            self.canonical.as_os_str().as_encoded_bytes(),
+        )
+        .map_err(write_error)?;
+        let cache = CacheFile {
+            format: CACHE_FORMAT_VERSION,
+            entries,
+        };
+        let json = serde_json::to_string(&cache).map_err(|e| write_error(e.into()))?;
+        fs::write(&self.file, json).map_err(write_error)
+    }
+
+    /// Returns the key of a file: its path below the root.
+    fn key(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).unwrap_or(path).to_path_buf()
+    }
+}
+
+/// Removes the cache files in `dir` whose root no longer exists, and those
+/// without a recorded root, written by older versions.
+///
+/// Failures are ignored: another run may be pruning the same directory.
+fn prune(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+        if path.extension() != Some("json".as_ref()) {
+            continue;
+        }
+        let root_file = path.with_extension(ROOT_EXTENSION);
+        let exists = fs::read(&root_file)
+            .ok()
+            .and_then(root_path)
+            .is_some_and(|root| root.exists());
+        if !exists {
+            let _ = fs::remove_file(&path);
+            let _ = fs::remove_file(&root_file);
+        }
+    }
+}
+
+/// Reads a root recorded with `OsStr::as_encoded_bytes`, which are the raw
+/// bytes on Unix and UTF-8 elsewhere for any path that is valid Unicode.
+fn root_path(bytes: Vec<u8>) -> Option<PathBuf> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Some(std::ffi::OsString::from_vec(bytes).into())
+    }
+    #[cfg(not(unix))]
+    {
+        String::from_utf8(bytes).ok().map(PathBuf::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::SupportedLanguage;
+    use crate::parser::CodeStats;
+    use tempfile::TempDir;
+
+    /// Builds file statistics with the given function count.
+    fn file_stats(path: &Path, function_count: usize) -> FileStats {
+        let stats = CodeStats {
+            function_count,
+            ..CodeStats::default()
+        };
+        FileStats::new(path.to_path_buf(), SupportedLanguage::Rust, stats)
+    }
+
+    #[test]
+    fn test_entries_are_reused_until_content_or_settings_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("repo");
+        let path = root.join("src/lib.rs");
+
+        let hash = content_hash(b"fn a() {}");
+
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        assert!(cache.lookup(&path, &hash, "rust").is_none());
+        cache.insert(hash.clone(), "rust".to_string(), &file_stats(&path, 1));
+        cache.save(true).unwrap();
+
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        let cached = cache.lookup(&path, &hash, "rust").unwrap();
+        assert_eq!(cached.stats.function_count, 1);
+        assert_eq!(cached.path, path);
+
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        let edited = content_hash(b"fn a() {}\nfn b() {}");
+        assert!(cache.lookup(&path, &edited, "rust").is_none());
+
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        assert!(cache.lookup(&path, &hash, "rust+magic").is_none());
+    }
+
+    #[test]
+    fn test_caches_of_other_formats_are_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("repo");
+        let path = root.join("a.rs");
+        let hash = content_hash(b"");
+
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        cache.insert(hash.clone(), String::new(), &file_stats(&path, 3));
+        let file = cache.file.clone();
+        cache.save(false).unwrap();
+
+        let text = fs::read_to_string(&file).unwrap();
+        let current = format!("\"format\":{CACHE_FORMAT_VERSION}");
+        assert!(text.contains(&current));
+        fs::write(&file, text.replace(&current, "\"format\":0")).unwrap();
+        assert!(
+            FileCache::open(temp_dir.path(), &root)
+                .lookup(&path, &hash, "")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_unvisited_entries_are_dropped_only_after_complete_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("repo");
+        let (a, b) = (root.join("a.rs"), root.join("b.rs"));
+
+        let hash = content_hash(b"");
+
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        for path in [&a, &b] {
+            cache.insert(hash.clone(), String::new(), &file_stats(path, 0));
+        }
+        cache.save(true).unwrap();
+
+        // An interrupted run that only reached `a` keeps `b`
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        assert!(cache.lookup(&a, &hash, "").is_some());
+        cache.save(false).unwrap();
+
+        // A complete run without `b` (deleted) forgets it
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        assert!(cache.lookup(&a, &hash, "").is_some());
+        cache.save(true).unwrap();
+
+        let mut cache = FileCache::open(temp_dir.path(), &root);
+        assert!(cache.lookup(&b, &hash, "").is_none());
+    }
+
+    #[test]
+    fn test_saving_prunes_caches_of_removed_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let (kept, removed) = (
+            temp_dir.path().join("kept"),
+            temp_dir.path().join("removed"),
+        );
+        fs::create_dir(&kept).unwrap();
+        fs::create_dir(&removed).unwrap();
+
+        let removed_cache = FileCache::open(temp_dir.path(), &removed);
+        let stale = removed_cache.file.clone();
+        removed_cache.save(true).unwrap();
+        let older = temp_dir.path().join("files/0123456789abcdef.json");
+        fs::write(&older, "{}").unwrap();
+        fs::remove_dir(&removed).unwrap();
+
+        let kept_cache = FileCache::open(temp_dir.path(), &kept);
+        let file = kept_cache.file.clone();
+        kept_cache.save(true).unwrap();
+        FileCache::open(temp_dir.path(), &kept).save(true).unwrap();
+
+        assert!(file.exists());
+        assert!(file.with_extension(ROOT_EXTENSION).exists());
+        assert!(!stale.exists());
+        assert!(!stale.with_extension(ROOT_EXTENSION).exists());
+        assert!(!older.exists());
+    }
+
+    #[test]
+    fn test_roots_have_separate_cache_files() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let first = FileCache::open(temp_dir.path(), &temp_dir.path().join("a"));
+        let second = FileCache::open(temp_dir.path(), &temp_dir.path().join("b"));
+        assert_ne!(first.file, second.file);
+    }
+}
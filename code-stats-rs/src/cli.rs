@@ -1,7 +1,16 @@
 //! Command-line interface definitions and argument handling.
 
-use clap::{Parser, ValueEnum};
-use std::path::PathBuf;
+use crate::badge::BadgeMetric;
+use crate::config::AnonymousFunctions;
+use crate::findings::{Finding, Severity, count_at_least};
+use crate::language::{DetectionStrategy, ExtensionOverrides, SupportedLanguage};
+use crate::stats::{DirectoryStats, REPORT_SCHEMA, ReportMeta};
+use crate::symbols::SymbolKind;
+use crate::thresholds::{Ratchet, Threshold};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 /// Command-line arguments for the code statistics analyzer.
 ///
@@ -9,18 +18,37 @@ use std::path::PathBuf;
 #[derive(Parser, Debug)]
 #[command(name = "code-stats-rs")]
 #[command(about = "Analyze code statistics for functions and classes", long_about = None)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub struct Cli {
-    /// Path to analyze (file or directory)
-    pub path: PathBuf,
+    /// Subcommand to run instead of the default analysis
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to analyze (file, directory, or .zip/.tar/.tar.gz archive)
+    #[arg(required_unless_present = "schema")]
+    pub path: Option<PathBuf>,
 
     /// Output format
     #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
     pub format: OutputFormat,
 
-    /// Show detailed statistics for each file
+    /// Show detailed statistics for each file (with --format table, as a table of files)
     #[arg(short, long)]
     pub detail: bool,
 
+    /// Keep only the totals and leave the per-file records out of the report, so
+    /// memory stays flat on huge trees (summaries without per-file sections do this
+    /// automatically)
+    #[arg(
+        long,
+        conflicts_with_all = ["detail", "top", "find_duplicates", "magic_values", "i18n", "sign"]
+    )]
+    pub no_file_list: bool,
+
+    /// Report file paths relative to this directory [default: the analyzed directory]
+    #[arg(long, value_name = "DIR")]
+    pub relative_to: Option<PathBuf>,
+
     /// File patterns to ignore (can be used multiple times)
     #[arg(long, value_name = "PATTERN")]
     pub ignore: Vec<String>,
@@ -29,9 +57,232 @@ pub struct Cli {
     #[arg(long)]
     pub follow_links: bool,
 
+    /// Analyze hidden files and directories, whose names start with a dot
+    #[arg(long, overrides_with = "no_hidden")]
+    pub hidden: bool,
+
+    /// Skip hidden files and directories (the default), overriding --hidden
+    #[arg(long, overrides_with = "hidden")]
+    pub no_hidden: bool,
+
+    /// With --follow-links, count a file reached through several links once per path
+    #[arg(long, requires = "follow_links")]
+    pub no_dedup: bool,
+
+    /// With --follow-links, skip files and directories behind more than N links
+    #[arg(long, value_name = "N", requires = "follow_links")]
+    pub max_link_depth: Option<usize>,
+
     /// Maximum depth for directory traversal
     #[arg(long, default_value_t = 100)]
     pub max_depth: usize,
+
+    /// Fail instead of analyzing more than N files
+    #[arg(long, value_name = "N")]
+    pub max_files: Option<usize>,
+
+    /// Fail instead of analyzing files of more than this many bytes in total
+    #[arg(long, value_name = "BYTES")]
+    pub max_total_bytes: Option<u64>,
+
+    /// Scan string literals for credentials such as AWS keys and private keys
+    #[arg(long)]
+    pub scan_secrets: bool,
+
+    /// Additional secret pattern for --scan-secrets (can be used multiple times)
+    #[arg(long, value_name = "NAME=REGEX", requires = "scan_secrets")]
+    pub secret_pattern: Vec<String>,
+
+    /// Count the captures of a tree-sitter query file in files of a language,
+    /// e.g. rust=macros.scm (can be used multiple times)
+    #[arg(long, value_name = "LANG=PATH")]
+    pub query: Vec<String>,
+
+    /// Decode files that are not valid UTF-8 with this encoding (e.g. shift_jis,
+    /// latin1) instead of detecting it
+    #[arg(long, value_name = "LABEL", value_parser = crate::source::parse_encoding)]
+    pub encoding: Option<&'static encoding_rs::Encoding>,
+
+    /// Skip files larger than this many bytes (0 = no limit)
+    #[arg(long, value_name = "BYTES", default_value_t = crate::source::DEFAULT_MAX_FILE_SIZE)]
+    pub max_file_size: u64,
+
+    /// Leave files that look minified or obfuscated out of the totals
+    #[arg(long)]
+    pub exclude_suspicious: bool,
+
+    /// Count files below vendor/, third_party/ and node_modules/ in the totals
+    #[arg(long)]
+    pub include_vendored: bool,
+
+    /// Leave generated files (marked "@generated" or "DO NOT EDIT") out of the totals
+    #[arg(long)]
+    pub exclude_generated: bool,
+
+    /// Report magic string/number totals and the files with the most of them
+    #[arg(long)]
+    pub magic_values: bool,
+
+    /// Report user-facing string literals that are not passed through a translation call
+    #[arg(long)]
+    pub i18n: bool,
+
+    /// Rank the language summary and per-file detail by this column
+    #[arg(long, value_enum, default_value_t = SortKey::Name)]
+    pub sort: SortKey,
+
+    /// Direction of --sort [default: asc for name, desc for counts]
+    #[arg(long, value_enum)]
+    pub order: Option<SortOrder>,
+
+    /// Write plain numbers without thousands separators or column padding, for scripts
+    #[arg(long)]
+    pub no_align: bool,
+
+    /// Wrap the cells of --format table to this many columns instead of the
+    /// terminal's width
+    #[arg(long, value_name = "COLUMNS", value_parser = clap::value_parser!(u16).range(1..))]
+    pub width: Option<u16>,
+
+    /// List the N files with the most functions, to spot refactoring hotspots
+    #[arg(long, value_name = "N")]
+    pub top: Option<NonZeroUsize>,
+
+    /// Metrics computed per file; extended adds Halstead volume and the
+    /// maintainability index to detail and JSON output
+    #[arg(long, value_enum, default_value_t = MetricsLevel::Basic)]
+    pub metrics: MetricsLevel,
+
+    /// Which anonymous functions (closures, lambdas, callbacks) count as
+    /// functions, in every language; by default only those of JavaScript,
+    /// TypeScript and Dart do
+    #[arg(long, value_enum, value_name = "WHICH")]
+    pub count_anonymous: Option<AnonymousFunctions>,
+
+    /// Record the SHA-256 of every analyzed file in detail and JSON output, so
+    /// the report can be verified against the exact sources
+    #[arg(long)]
+    pub hash: bool,
+
+    /// Record when every analyzed file was last modified ("modified" in JSON,
+    /// seconds since the Unix epoch), to correlate structure with churn
+    #[arg(long)]
+    pub mtime: bool,
+
+    /// Record when the report was written ("timestamp" in the report meta);
+    /// off by default so identical sources give identical reports, and
+    /// SOURCE_DATE_EPOCH takes precedence
+    #[arg(long)]
+    pub timestamp: bool,
+
+    /// Write JSON reports (json, cloc-json) on a single line instead of
+    /// pretty-printing them, for piping into jq or sending over the network
+    #[arg(long)]
+    pub compact: bool,
+
+    /// Print the JSON Schema of the JSON report and exit, to validate reports
+    /// with; YAML and TOML reports have the same structure
+    #[arg(long)]
+    pub schema: bool,
+
+    /// Report function names defined in many places, to spot copy-paste proliferation
+    #[arg(long)]
+    pub find_duplicates: bool,
+
+    /// Minimum number of definitions for --find-duplicates to report a name
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "3",
+        requires = "find_duplicates"
+    )]
+    pub min_duplicates: NonZeroUsize,
+
+    /// Maximum number of files listed by detail and JSON output
+    #[arg(long, value_name = "N")]
+    pub limit: Option<NonZeroUsize>,
+
+    /// Page of files to list, in --sort order (1-based, used with --limit)
+    #[arg(long, value_name = "N", requires = "limit")]
+    pub page: Option<NonZeroUsize>,
+
+    /// Print directly instead of through $PAGER on an interactive terminal
+    #[arg(long)]
+    pub no_pager: bool,
+
+    /// How to detect the language of each file: file content (Magika), file
+    /// extension (fast), or content with the extension as fallback
+    #[arg(long, value_enum, default_value_t = DetectionStrategy::Auto)]
+    pub detection: DetectionStrategy,
+
+    /// Detect files with extension EXT as LANGUAGE, e.g. pyx=python (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+
+    /// Memory-map source files instead of reading them (falls back to reading)
+    #[arg(long)]
+    pub mmap: bool,
+
+    /// Memory-map only files of at least this many bytes, and read smaller ones
+    #[arg(long, value_name = "BYTES", conflicts_with = "mmap")]
+    pub mmap_threshold: Option<u64>,
+
+    /// Time the detection, reading and parsing of every file and print the N
+    /// slowest files and the time per phase to stderr
+    #[arg(long, value_name = "N", conflicts_with = "rev")]
+    pub profile: Option<NonZeroUsize>,
+
+    /// Only analyze files changed since REF in the work tree, including untracked files
+    #[arg(long, value_name = "REF")]
+    pub git_diff: Option<String>,
+
+    /// Only analyze files with staged changes (against --git-diff REF, or HEAD)
+    #[arg(long)]
+    pub staged: bool,
+
+    /// Analyze the directory's files as of a git revision, without checking it out
+    #[arg(long, value_name = "REV", conflicts_with_all = ["git_diff", "staged", "sign"])]
+    pub rev: Option<String>,
+
+    /// Directory for cached per-file results [default: ~/.cache/code-stats-rs]
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Analyze every file again instead of reusing cached results
+    #[arg(long, conflicts_with = "cache_dir")]
+    pub no_cache: bool,
+
+    /// Report recoverable issues as warning findings: skipped files that look
+    /// like source code or are too large, and files detected by extension
+    /// because their content was not recognized
+    #[arg(long)]
+    pub warnings: bool,
+
+    /// Fail when the analysis reports a finding of at least this severity
+    #[arg(long, value_enum, value_name = "SEVERITY")]
+    pub fail_on: Option<Severity>,
+
+    /// Fail when any file could not be analyzed (same as --fail-on error)
+    #[arg(long, conflicts_with = "fail_on")]
+    pub strict: bool,
+
+    /// Exit with status 2 when a rule such as functions_per_file>100 holds (can be used multiple times)
+    #[arg(long = "fail-if", value_name = "RULE", value_parser = Threshold::parse)]
+    pub fail_if: Vec<Threshold>,
+
+    /// Print no report, only exit with 0 on success, 1 on usage or I/O errors, 2 when a
+    /// --fail-if rule holds and 3 when some files could not be analyzed
+    #[arg(long, conflicts_with = "sign")]
+    pub check: bool,
+
+    /// Write a provenance attestation of the report (tool version, options, input digests) to FILE;
+    /// an analyzed archive is recorded as a single input
+    #[arg(long, value_name = "FILE")]
+    pub sign: Option<PathBuf>,
+
+    /// Sign the attestation with the HMAC-SHA256 key read from KEY_FILE
+    #[arg(long, value_name = "KEY_FILE", requires = "sign")]
+    pub signing_key: Option<PathBuf>,
 }
 
 impl Cli {
@@ -39,9 +290,19 @@ impl Cli {
     ///
     /// This method implements the main execution flow:
     /// 1. Creates a new analyzer instance
-    /// 2. Determines whether the path is a file or directory
+    /// 2. Determines whether the path is a file, an archive or a directory
     /// 3. Runs the appropriate analysis
     /// 4. Formats and displays the results based on the selected output format
+    /// 5. Writes a one-line machine-parsable status to stderr, even on failure
+    /// 6. Writes a provenance attestation of the printed report if `--sign` is set
+    /// 7. Fails with [`RunError::THRESHOLD_EXIT_CODE`] if a `--fail-if` rule holds
+    /// 8. With `--check`, prints no report and otherwise fails with
+    ///    [`RunError::PARTIAL_EXIT_CODE`] if any file could not be analyzed
+    /// 9. Otherwise fails with [`RunError::THRESHOLD_EXIT_CODE`] if `--fail-on`
+    ///    is set and a finding of that severity or higher was reported, or with
+    ///    `--strict` if any file could not be analyzed
+    /// 10. Fails with [`RunError::INTERRUPTED_EXIT_CODE`] after printing the
+    ///     partial report of an interrupted directory analysis
     ///
     /// # Output Format Logic
     ///
@@ -53,55 +314,1475 @@ impl Cli {
     /// # Returns
     ///
     /// * `Ok(())` if analysis completes successfully
-    /// * `Err(String)` with error message if analysis fails
-    pub fn run(self) -> Result<(), String> {
-        use crate::analyzer::CodeAnalyzer;
-        use crate::formatter::{format_output, format_single_file};
+    /// * `Err(RunError)` with error message if analysis fails, or after printing
+    ///   a partial report when a directory analysis is interrupted (Ctrl-C) or
+    ///   violates a `--fail-if` rule
+    pub fn run(self) -> Result<(), RunError> {
+        use crate::analyzer::{CodeAnalyzer, TraversalLimits};
+        use crate::formatter::{
+            FormatOptions, NumberStyle, Pagination, RunStatus, Sorting, TableStyle, format_profile,
+            format_single_file, format_status_line, write_output,
+        };
+        use crate::pager::write_paged;
+        use crate::paths::relative_to;
+        use crate::provenance::{DigestWriter, Statement, read_key, write_attestation};
+        use hmac_sha256::Hash;
 
-        let mut analyzer = CodeAnalyzer::new();
+        if let Some(command) = self.command {
+            return command.run();
+        }
+        if self.schema {
+            print!("{REPORT_SCHEMA}");
+            return Ok(());
+        }
 
-        if self.path.is_file() {
-            // Single file analysis
-            match analyzer.analyze_file(&self.path) {
-                Ok(file_stats) => {
-                    println!("{}", format_single_file(&file_stats));
-                    Ok(())
-                }
-                Err(e) => Err(e.to_string()),
+        let Some(path) = self.path else {
+            return Err("a path to analyze is required".to_string().into());
+        };
+        if self.compact && !matches!(self.format, OutputFormat::Json | OutputFormat::ClocJson) {
+            return Err("--compact requires --format json or cloc-json"
+                .to_string()
+                .into());
+        }
+
+        let options = FormatOptions {
+            magic_values: self.magic_values,
+            i18n: self.i18n,
+            pagination: self.limit.map(|limit| Pagination {
+                limit: limit.get(),
+                page: self.page.map_or(1, NonZeroUsize::get),
+            }),
+            top: self.top.map(NonZeroUsize::get),
+            duplicates: self.find_duplicates.then_some(self.min_duplicates.get()),
+            sorting: Sorting::new(self.sort, self.order),
+            numbers: if self.no_align {
+                NumberStyle::plain()
+            } else {
+                NumberStyle::from_locale()
+            },
+            file_table: self.detail && self.format == OutputFormat::Table,
+            tables: TableStyle::from_env(self.width),
+            compact: self.compact,
+        };
+
+        if self.no_file_list
+            && let Some(rule) = self.fail_if.iter().find(|rule| rule.is_per_file())
+        {
+            return Err(format!(
+                "--fail-if {rule} needs the per-file records --no-file-list drops"
+            )
+            .into());
+        }
+        // Summaries only need the totals, unless a section or rule looks at single files
+        let summary_only = self.format == OutputFormat::Summary
+            && !self.detail
+            && self.top.is_none()
+            && !self.find_duplicates
+            && !self.magic_values
+            && !self.i18n
+            && self.sign.is_none()
+            && !self.fail_if.iter().any(Threshold::is_per_file);
+
+        let mut analyzer = CodeAnalyzer::new()
+            .with_aggregate_only(self.no_file_list || summary_only)
+            .with_mmap_threshold(if self.mmap {
+                Some(0)
+            } else {
+                self.mmap_threshold
+            })
+            .with_detection(self.detection)
+            .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+            .with_anonymous_functions(self.count_anonymous)
+            .with_exclude_suspicious(self.exclude_suspicious)
+            .with_include_vendored(self.include_vendored)
+            .with_exclude_generated(self.exclude_generated)
+            .with_function_names(self.find_duplicates)
+            .with_extended_metrics(self.metrics == MetricsLevel::Extended)
+            .with_content_hashes(self.hash)
+            .with_modification_times(self.mtime)
+            .with_dedup_links(!self.no_dedup)
+            .with_hidden(self.hidden)
+            .with_max_file_size((self.max_file_size > 0).then_some(self.max_file_size))
+            .with_limits(TraversalLimits {
+                max_files: self.max_files,
+                max_total_bytes: self.max_total_bytes,
+                max_link_depth: self.max_link_depth,
+            })
+            .with_encoding(self.encoding)
+            .with_warnings(self.warnings)
+            .with_profiling(self.profile.is_some());
+
+        if self.scan_secrets {
+            use crate::secrets::{SecretPattern, SecretScanner};
+
+            let patterns = self
+                .secret_pattern
+                .iter()
+                .map(|spec| SecretPattern::parse(spec))
+                .collect::<Result<Vec<_>, _>>()?;
+            analyzer = analyzer.with_secret_scanner(SecretScanner::new(patterns));
+        }
+
+        if !self.query.is_empty() {
+            analyzer = analyzer.with_query_counter(crate::queries::QueryCounter::new(&self.query)?);
+        }
+
+        if (self.git_diff.is_some() || self.staged) && !path.is_dir() {
+            return Err("--git-diff and --staged require a directory"
+                .to_string()
+                .into());
+        }
+        if self.rev.is_some() && !path.is_dir() {
+            return Err("--rev requires a directory".to_string().into());
+        }
+        if self.profile.is_some() && !path.is_dir() {
+            return Err("--profile requires a directory".to_string().into());
+        }
+
+        // Archives are analyzed like directories, from their files in memory
+        let archive = path.is_file() && crate::archive::ArchiveFormat::from_path(&path).is_some();
+
+        // Only directory runs can stop between files and still report, and
+        // only they are worth caching
+        if path.is_dir() || archive {
+            analyzer = analyzer.with_interrupt(crate::interrupt::install_handler()?);
+        }
+        if path.is_dir() {
+            if self.git_diff.is_some() || self.staged {
+                let files = crate::git::changed_files(&path, self.git_diff.as_deref(), self.staged)
+                    .map_err(|e| e.to_string())?;
+                analyzer = analyzer.with_only_files(files);
             }
-        } else if self.path.is_dir() {
-            // Directory analysis
-            match analyzer.analyze_directory(
-                &self.path,
-                self.max_depth,
-                self.follow_links,
-                &self.ignore,
-            ) {
-                Ok(stats) => {
-                    // Determine output format based on --detail flag compatibility
-                    let format = if self.detail && self.format == OutputFormat::Summary {
-                        // When --detail is used with default Summary format,
-                        // switch to Detail format for backward compatibility
-                        OutputFormat::Detail
-                    } else {
-                        // Use the explicitly specified format
-                        self.format
+            if !self.no_cache {
+                analyzer = analyzer.with_cache_dir(
+                    self.cache_dir
+                        .clone()
+                        .or_else(crate::cache::default_cache_dir),
+                );
+            }
+        }
+
+        // Read before analyzing, so a bad key fails fast
+        let signing_key = self.signing_key.as_deref().map(read_key).transpose()?;
+
+        let started = Instant::now();
+        let mut gate_failure: Option<RunError> = None;
+        // Digest of the printed report and the analyzed files, for `--sign`
+        let mut attested: Option<([u8; 32], Vec<PathBuf>)> = None;
+
+        let outcome = if path.is_file() && !archive {
+            // Single file analysis
+            analyzer
+                .analyze_file(&path)
+                .map_err(|e| e.to_string())
+                .map(|mut file_stats| {
+                    if let Some(relative) = self
+                        .relative_to
+                        .as_deref()
+                        .and_then(|root| relative_to(&file_stats.path, root))
+                    {
+                        file_stats.path = relative;
+                    }
+                    let report = format!("{}\n", format_single_file(&file_stats, &options));
+                    if !self.check {
+                        print!("{report}");
+                    }
+                    attested = Some((Hash::hash(report.as_bytes()), vec![path.clone()]));
+                    let status = RunStatus {
+                        files: 1,
+                        functions: file_stats.stats.function_count,
+                        classes: file_stats.stats.class_struct_count,
+                        errors: 0,
                     };
+                    let mut stats = DirectoryStats::new();
+                    stats.add_file(file_stats);
+                    gate_failure = RunError::from_thresholds(&self.fail_if, &stats);
+                    status
+                })
+        } else if path.is_dir() || archive {
+            // Directory analysis
+            match &self.rev {
+                _ if archive => analyzer.analyze_archive(&path, self.max_depth, &self.ignore),
+                Some(rev) => analyzer.analyze_revision(&path, rev, self.max_depth, &self.ignore),
+                None => analyzer.analyze_directory(
+                    &path,
+                    self.max_depth,
+                    self.follow_links,
+                    &self.ignore,
+                ),
+            }
+            .map_err(|e| e.to_string())
+            .and_then(|mut stats| {
+                // The files of an archive exist only in memory, so the
+                // archive itself is attested
+                let inputs = if archive {
+                    vec![path.clone()]
+                } else {
+                    stats
+                        .files
+                        .iter()
+                        .chain(&stats.excluded_files)
+                        .chain(&stats.set_aside_files)
+                        .map(|file| file.path.clone())
+                        .collect()
+                };
+                let root = self.relative_to.as_deref().unwrap_or(&path);
+                stats.make_paths_relative(root);
+                // Relative like the file paths, so checkouts in different
+                // places produce the same report
+                let report_root = match self.relative_to.as_deref() {
+                    Some(base) => {
+                        let analyzed = std::path::absolute(&path).unwrap_or_else(|_| path.clone());
+                        if std::path::absolute(base).is_ok_and(|base| base == analyzed) {
+                            PathBuf::from(".")
+                        } else {
+                            relative_to(&analyzed, base).unwrap_or(analyzed)
+                        }
+                    }
+                    None => PathBuf::from("."),
+                };
+                let meta = ReportMeta::new(report_root, std::env::args().skip(1).collect());
+                stats.meta = Some(if self.timestamp {
+                    meta.with_current_time()
+                } else {
+                    meta
+                });
+                let profile = analyzer.take_profile().map(|mut profile| {
+                    profile.make_paths_relative(root);
+                    profile
+                });
+
+                // Determine output format based on --detail flag compatibility
+                let format = if self.detail && self.format == OutputFormat::Summary {
+                    // When --detail is used with default Summary format,
+                    // switch to Detail format for backward compatibility
+                    OutputFormat::Detail
+                } else {
+                    // Use the explicitly specified format
+                    self.format
+                };
 
-                    println!("{}", format_output(&stats, format, self.detail));
-                    Ok(())
+                if !self.check {
+                    let mut report_hash = Hash::new();
+                    write_paged(!self.no_pager, |out| {
+                        write_output(
+                            &stats,
+                            format,
+                            &options,
+                            &mut DigestWriter::new(out, &mut report_hash),
+                        )
+                    })
+                    .map_err(|e| format!("Failed to write output: {e}"))?;
+                    attested = Some((report_hash.finalize(), inputs));
                 }
-                Err(e) => Err(e.to_string()),
-            }
+                // On stderr, so the report stays machine-readable
+                if let (Some(profile), Some(slowest)) = (&profile, self.profile) {
+                    eprintln!("{}", format_profile(profile, slowest.get()));
+                }
+
+                gate_failure = RunError::from_thresholds(&self.fail_if, &stats);
+                if self.check && gate_failure.is_none() && stats.error_count() > 0 {
+                    gate_failure = Some(RunError::partial(stats.error_count()));
+                }
+
+                let fail_on = self.fail_on.or(self.strict.then_some(Severity::Error));
+                if let Some(severity) = fail_on
+                    && gate_failure.is_none()
+                {
+                    gate_failure = RunError::from_findings(&stats.findings, severity);
+                }
+
+                if stats.interrupted {
+                    gate_failure = Some(RunError::interrupted(format!(
+                        "interrupted after {} files; the report is partial",
+                        stats.total_files()
+                    )));
+                }
+
+                Ok(RunStatus {
+                    files: stats.total_files(),
+                    functions: stats.total_stats.function_count,
+                    classes: stats.total_stats.class_struct_count,
+                    errors: stats.error_count(),
+                })
+            })
         } else {
             Err(format!(
                 "{} is neither a file nor a directory",
-                self.path.display()
+                path.display()
+            ))
+        };
+
+        let outcome = match (outcome, &self.sign, attested) {
+            (Ok(status), Some(attestation), Some((report_digest, inputs))) => {
+                let inputs: Vec<&Path> = inputs.iter().map(PathBuf::as_path).collect();
+                let arguments = std::env::args().skip(1).collect();
+                Statement::for_report(report_digest, &inputs, arguments)
+                    .and_then(|statement| {
+                        write_attestation(attestation, &statement, signing_key.as_deref())
+                    })
+                    .map(|()| status)
+                    .map_err(|e| {
+                        format!("Failed to write attestation {}: {e}", attestation.display())
+                    })
+            }
+            (outcome, _, _) => outcome,
+        };
+
+        // Emitted on every run, so wrapper scripts never have to parse the report
+        let status = match &outcome {
+            Ok(status) => status.clone(),
+            Err(_) => RunStatus {
+                errors: 1,
+                ..RunStatus::default()
+            },
+        };
+        eprintln!("{}", format_status_line(&status, started.elapsed()));
+
+        outcome?;
+        gate_failure.map_or(Ok(()), Err)
+    }
+}
+
+/// Builds the command-line parser with the long help of `--help` and the
+/// man page: an example per output format, the supported languages and the
+/// exit statuses.
+///
+/// The lists are generated from [`OutputFormat`] and [`SupportedLanguage`],
+/// so they never fall behind the code. `-h` prints the short help without them.
+pub fn command() -> clap::Command {
+    Cli::command().after_long_help(long_help())
+}
+
+/// Renders the sections [`command`] appends to the long help.
+fn long_help() -> String {
+    let mut help = String::from("Examples:\n");
+    for format in OutputFormat::value_variants() {
+        let (description, example) = match format {
+            OutputFormat::Summary => ("Totals per language", "code-stats-rs src"),
+            OutputFormat::Detail => ("Statistics of every file", "code-stats-rs src --detail"),
+            OutputFormat::Json => (
+                "Full report for scripts and `diff --baseline`",
+                "code-stats-rs src --format json > report.json",
+            ),
+            OutputFormat::Table => (
+                "Every metric per language as a table",
+                "code-stats-rs src --format table",
+            ),
+            OutputFormat::Yaml => (
+                "The JSON report as YAML",
+                "code-stats-rs src --format yaml > report.yaml",
+            ),
+            OutputFormat::Toml => (
+                "The JSON report as TOML",
+                "code-stats-rs src --format toml > report.toml",
+            ),
+            OutputFormat::ClocJson => (
+                "Line counts in the layout of `cloc --json`",
+                "code-stats-rs src --format cloc-json",
+            ),
+        };
+        help.push_str(&format!("  {description}:\n      {example}\n"));
+    }
+
+    help.push_str("\nSupported languages:\n");
+    let mut languages = SupportedLanguage::ALL;
+    languages.sort();
+    for language in languages {
+        let extensions: Vec<String> = language
+            .extensions()
+            .map(|extension| format!(".{extension}"))
+            .collect();
+        help.push_str(&format!(
+            "  {:<12}{}\n",
+            language.name(),
+            extensions.join(" ")
+        ));
+    }
+
+    help.push_str(&format!(
+        "\nExit status:\n  \
+         0  Success\n  \
+         {}  Usage or I/O error\n  \
+         {}  A --fail-if rule holds, or a --fail-on/--strict finding\n  \
+         {}  Some files could not be analyzed (--check)\n  \
+         {}  Interrupted (Ctrl-C); the report printed is partial",
+        RunError::EXIT_CODE,
+        RunError::THRESHOLD_EXIT_CODE,
+        RunError::PARTIAL_EXIT_CODE,
+        RunError::INTERRUPTED_EXIT_CODE
+    ));
+    help
+}
+
+/// Why [`Cli::run`] failed, with the exit status the process should report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunError {
+    /// Human-readable description of the failure
+    pub message: String,
+    /// Process exit status
+    pub exit_code: i32,
+}
+
+impl RunError {
+    /// Exit status of failed runs.
+    pub const EXIT_CODE: i32 = 1;
+    /// Exit status of runs that succeeded but violated a `--fail-if` rule.
+    pub const THRESHOLD_EXIT_CODE: i32 = 2;
+    /// Exit status of `--check` runs in which some files could not be analyzed.
+    pub const PARTIAL_EXIT_CODE: i32 = 3;
+    /// Exit status of runs stopped by Ctrl-C or SIGTERM after printing a
+    /// partial report, 128 + SIGINT as shells report it.
+    pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+    /// Checks the `--fail-if` rules against the statistics of a run.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RunError)` - Listing every violated rule, with
+    ///   [`RunError::THRESHOLD_EXIT_CODE`]
+    /// * `None` - If no rule is violated
+    fn from_thresholds(rules: &[Threshold], stats: &DirectoryStats) -> Option<Self> {
+        let violations: Vec<String> = rules
+            .iter()
+            .filter_map(|rule| rule.violation(stats))
+            .collect();
+        (!violations.is_empty()).then(|| Self {
+            message: format!("threshold exceeded: {}", violations.join("; ")),
+            exit_code: Self::THRESHOLD_EXIT_CODE,
+        })
+    }
+
+    /// Checks the `diff --ratchet` rules against the baseline and the
+    /// current statistics.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RunError)` - Listing every metric that grew, with
+    ///   [`RunError::THRESHOLD_EXIT_CODE`]
+    /// * `None` - If no metric grew
+    fn from_ratchets(
+        rules: &[Ratchet],
+        baseline: &DirectoryStats,
+        current: &DirectoryStats,
+    ) -> Option<Self> {
+        let violations: Vec<String> = rules
+            .iter()
+            .filter_map(|rule| rule.violation(baseline, current))
+            .collect();
+        (!violations.is_empty()).then(|| Self {
+            message: format!("ratchet violated: {}", violations.join("; ")),
+            exit_code: Self::THRESHOLD_EXIT_CODE,
+        })
+    }
+
+    /// Checks the findings of a run against the `--fail-on` severity.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RunError)` - Counting the findings at or above `severity`,
+    ///   with [`RunError::THRESHOLD_EXIT_CODE`]
+    /// * `None` - If no finding reaches `severity`
+    fn from_findings(findings: &[Finding], severity: Severity) -> Option<Self> {
+        let count = count_at_least(findings, severity);
+        (count > 0).then(|| Self {
+            message: format!("{count} findings at or above severity {severity}"),
+            exit_code: Self::THRESHOLD_EXIT_CODE,
+        })
+    }
+
+    /// Reports that some files of a `--check` run could not be analyzed, with
+    /// [`RunError::PARTIAL_EXIT_CODE`].
+    fn partial(errors: usize) -> Self {
+        Self {
+            message: format!("{errors} files could not be analyzed"),
+            exit_code: Self::PARTIAL_EXIT_CODE,
+        }
+    }
+
+    /// Reports that a run was interrupted after printing a partial report,
+    /// with [`RunError::INTERRUPTED_EXIT_CODE`].
+    fn interrupted(message: String) -> Self {
+        Self {
+            message,
+            exit_code: Self::INTERRUPTED_EXIT_CODE,
+        }
+    }
+}
+
+impl From<String> for RunError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            exit_code: Self::EXIT_CODE,
+        }
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Subcommands that run instead of the default statistics analysis.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Audit source files against repository policies
+    Audit {
+        /// The audit to run
+        #[command(subcommand)]
+        check: AuditCheck,
+    },
+
+    /// Analyze every repository listed in a manifest and report org-wide totals
+    Multi(MultiArgs),
+
+    /// Compare the tree against a saved JSON report and print what changed
+    Diff(DiffArgs),
+
+    /// Analyze two trees, e.g. a fork and its upstream, and print them side by side
+    Compare(CompareArgs),
+
+    /// Analyze a range of git commits and print the statistics as a time series
+    History(HistoryArgs),
+
+    /// Rank files by how often they changed times their complexity, to prioritize refactoring
+    Hotspots(HotspotsArgs),
+
+    /// Show how concentrated the authorship of each directory is, flagging bus-factor-1 areas
+    Ownership(OwnershipArgs),
+
+    /// Find where functions and types are declared, reusing the cached symbols of unchanged files
+    Find(FindArgs),
+
+    /// Check that a report matches the signed attestation written with --sign
+    Verify(VerifyArgs),
+
+    /// Print an SVG badge (e.g. "functions: 1,234" or "Rust 62%") for a README
+    Badge(BadgeArgs),
+
+    /// List the supported languages with their extensions, Magika labels and counted node kinds
+    Languages(LanguagesArgs),
+
+    /// Print the man page in roff format, e.g. `code-stats-rs man > code-stats-rs.1`
+    Man,
+
+    /// Generate a synthetic repository for benchmarks and stress tests
+    #[cfg(feature = "gen-fixtures")]
+    GenFixtures(GenFixturesArgs),
+}
+
+impl Command {
+    /// Executes the selected subcommand.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the subcommand completes successfully
+    /// * `Err(RunError)` with error message if it fails or an audit finds
+    ///   violations, with [`RunError::INTERRUPTED_EXIT_CODE`] if interrupted
+    pub fn run(self) -> Result<(), RunError> {
+        match self {
+            Command::Audit { check } => match check {
+                AuditCheck::LicenseHeaders(args) => args.run().map_err(RunError::from),
+            },
+            Command::Multi(args) => args.run(),
+            Command::Diff(args) => args.run(),
+            Command::Compare(args) => args.run().map_err(RunError::from),
+            Command::History(args) => args.run(),
+            Command::Hotspots(args) => args.run().map_err(RunError::from),
+            Command::Ownership(args) => args.run().map_err(RunError::from),
+            Command::Find(args) => args.run().map_err(RunError::from),
+            Command::Verify(args) => args.run().map_err(RunError::from),
+            Command::Badge(args) => args.run().map_err(RunError::from),
+            Command::Languages(args) => {
+                args.run();
+                Ok(())
+            }
+            Command::Man => clap_mangen::Man::new(command())
+                .render(&mut std::io::stdout())
+                .map_err(|e| format!("Failed to write man page: {e}").into()),
+            #[cfg(feature = "gen-fixtures")]
+            Command::GenFixtures(args) => args.run().map_err(RunError::from),
+        }
+    }
+}
+
+/// Available audits for the `audit` subcommand.
+#[derive(Subcommand, Debug)]
+pub enum AuditCheck {
+    /// Check that every source file starts with the required license header
+    LicenseHeaders(LicenseHeadersArgs),
+}
+
+/// Arguments for the `audit license-headers` subcommand.
+#[derive(Args, Debug)]
+pub struct LicenseHeadersArgs {
+    /// Path to audit (file or directory)
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// File containing the required header text
+    #[arg(long, value_name = "FILE")]
+    pub template: PathBuf,
+
+    /// File patterns to ignore (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Follow symbolic links
+    #[arg(long)]
+    pub follow_links: bool,
+
+    /// Audit hidden files and directories, whose names start with a dot
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// How to detect the language of each file
+    #[arg(long, value_enum, default_value_t = DetectionStrategy::Auto)]
+    pub detection: DetectionStrategy,
+}
+
+impl LicenseHeadersArgs {
+    /// Audits every supported source file for the required license header.
+    ///
+    /// Prints the audit report and fails when any file is missing the header,
+    /// so the command can gate CI pipelines via its exit code.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every file starts with the header
+    /// * `Err(String)` if the template cannot be used or violations were found
+    pub fn run(self) -> Result<(), String> {
+        use crate::analyzer::collect_source_files;
+        use crate::audit::{HeaderTemplate, audit_license_headers};
+        use crate::formatter::format_license_audit;
+
+        let template_text = std::fs::read_to_string(&self.template).map_err(|e| {
+            format!(
+                "Failed to read license template {}: {e}",
+                self.template.display()
+            )
+        })?;
+        let template = HeaderTemplate::parse(&template_text);
+        if template.is_empty() {
+            return Err(format!(
+                "License template {} is empty",
+                self.template.display()
+            ));
+        }
+
+        let files = collect_source_files(
+            &self.path,
+            self.max_depth,
+            self.follow_links,
+            self.hidden,
+            &self.ignore,
+            self.detection,
+        )
+        .map_err(|e| e.to_string())?;
+
+        let audit = audit_license_headers(&files, &template);
+        println!("{}", format_license_audit(&audit));
+
+        if audit.passed() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} of {} files are missing the required license header",
+                audit.violations.len(),
+                audit.total_files()
+            ))
+        }
+    }
+}
+
+/// Arguments for the `multi` subcommand.
+#[derive(Args, Debug)]
+pub struct MultiArgs {
+    /// TOML manifest listing the repositories (`[[repo]]` tables with `name` and `path` or `git`)
+    pub manifest: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
+    pub format: OutputFormat,
+
+    /// Directory for git checkouts and cached results [default: .code-stats-cache next to the manifest]
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Analyze every repository again instead of reusing cached results
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+impl MultiArgs {
+    /// Analyzes every repository of the manifest and prints the consolidated report.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every repository was analyzed
+    /// * `Err(RunError)` if the manifest is invalid, any repository could not be
+    ///   analyzed (the report for the others is still printed), or the run was
+    ///   interrupted
+    pub fn run(self) -> Result<(), RunError> {
+        use crate::formatter::format_multi_report;
+        use crate::interrupt::install_handler;
+        use crate::multi::{DEFAULT_CACHE_DIR, analyze_repos, load_manifest};
+
+        let repos = load_manifest(&self.manifest).map_err(|e| e.to_string())?;
+        let cache_dir = self.cache_dir.unwrap_or_else(|| {
+            self.manifest
+                .parent()
+                .unwrap_or(std::path::Path::new(""))
+                .join(DEFAULT_CACHE_DIR)
+        });
+
+        let interrupt = install_handler()?;
+        let report = analyze_repos(&repos, &cache_dir, !self.no_cache, &interrupt);
+        println!("{}", format_multi_report(&report, self.format));
+
+        if report.interrupted {
+            Err(RunError::interrupted(
+                "interrupted; the report is partial".to_string(),
             ))
+        } else if report.findings.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} of {} repositories could not be analyzed",
+                report.findings.len(),
+                repos.len()
+            )
+            .into())
         }
     }
 }
 
+/// Arguments for the `diff` subcommand.
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Directory to analyze and compare
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Report saved earlier with `--format json` (without --limit), analyzed from the same path
+    #[arg(long, value_name = "FILE")]
+    pub baseline: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
+    pub format: OutputFormat,
+
+    /// File patterns to ignore (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Follow symbolic links
+    #[arg(long)]
+    pub follow_links: bool,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// Detect files with extension EXT as LANGUAGE (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+
+    /// How to detect the language of each file (use the baseline's setting)
+    #[arg(long, value_enum, default_value_t = DetectionStrategy::Auto)]
+    pub detection: DetectionStrategy,
+
+    /// Exit with status 2 when a metric grew since the baseline, e.g. functions,
+    /// or avg_function_lines@src/legacy for the files below a path (can be used multiple times)
+    #[arg(long, value_name = "METRIC[@PATH]", value_parser = Ratchet::parse)]
+    pub ratchet: Vec<Ratchet>,
+}
+
+impl DiffArgs {
+    /// Analyzes the directory and prints its changes since the baseline report.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if both the baseline and the directory could be analyzed
+    ///   and no `--ratchet` metric grew
+    /// * `Err(RunError)` if the baseline is unreadable or not a JSON report,
+    ///   or the analysis fails; after printing the changes with
+    ///   [`RunError::THRESHOLD_EXIT_CODE`] if a `--ratchet` metric grew
+    pub fn run(self) -> Result<(), RunError> {
+        use crate::analyzer::CodeAnalyzer;
+        use crate::diff::{diff_reports, load_baseline};
+        use crate::formatter::format_diff;
+
+        let mut baseline = load_baseline(&self.baseline).map_err(|e| e.to_string())?;
+        if !self.path.is_dir() {
+            return Err(format!("{} is not a directory", self.path.display()).into());
+        }
+
+        // Renames are detected by content, if the baseline was saved with --hash
+        let hashed = baseline.files.iter().any(|file| file.sha256.is_some());
+        let mut current = CodeAnalyzer::new()
+            .with_detection(self.detection)
+            .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+            .with_content_hashes(hashed)
+            .analyze_directory(&self.path, self.max_depth, self.follow_links, &self.ignore)
+            .map_err(|e| e.to_string())?;
+        // Reports are saved with relative paths, except by older versions
+        baseline.make_paths_relative(&self.path);
+        current.make_paths_relative(&self.path);
+
+        println!(
+            "{}",
+            format_diff(&diff_reports(&baseline, &current), self.format)
+        );
+        RunError::from_ratchets(&self.ratchet, &baseline, &current).map_or(Ok(()), Err)
+    }
+}
+
+/// Arguments for the `compare` subcommand.
+#[derive(Args, Debug)]
+pub struct CompareArgs {
+    /// First directory or archive, e.g. upstream or the tree before a refactor
+    pub path_a: PathBuf,
+
+    /// Second directory or archive, e.g. a fork or the tree after a refactor
+    pub path_b: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
+    pub format: OutputFormat,
+
+    /// File patterns to ignore in both trees (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Follow symbolic links
+    #[arg(long)]
+    pub follow_links: bool,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// Detect files with extension EXT as LANGUAGE (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+
+    /// How to detect the language of each file
+    #[arg(long, value_enum, default_value_t = DetectionStrategy::Auto)]
+    pub detection: DetectionStrategy,
+
+    /// Wrap the cells of the table to this many columns instead of the
+    /// terminal's width
+    #[arg(long, value_name = "COLUMNS", value_parser = clap::value_parser!(u16).range(1..))]
+    pub width: Option<u16>,
+}
+
+impl CompareArgs {
+    /// Analyzes both trees and prints their statistics side by side.
+    ///
+    /// Files are matched by their path relative to each tree's root, or by
+    /// their content if they moved.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if both trees could be analyzed
+    /// * `Err(String)` if a path is neither a directory nor an archive, or an
+    ///   analysis fails
+    pub fn run(self) -> Result<(), String> {
+        use crate::diff::compare_reports;
+        use crate::formatter::{TableStyle, format_comparison};
+
+        let a = self.analyze(&self.path_a)?;
+        let b = self.analyze(&self.path_b)?;
+        println!(
+            "{}",
+            format_comparison(
+                &compare_reports(&a, &b),
+                &self.path_a,
+                &self.path_b,
+                self.format,
+                TableStyle::from_env(self.width)
+            )
+        );
+        Ok(())
+    }
+
+    /// Analyzes one of the trees, with paths relative to its root.
+    fn analyze(&self, path: &Path) -> Result<DirectoryStats, String> {
+        use crate::analyzer::CodeAnalyzer;
+        use crate::archive::ArchiveFormat;
+
+        let mut analyzer = CodeAnalyzer::new()
+            .with_detection(self.detection)
+            .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+            .with_content_hashes(true);
+        let mut stats = if path.is_dir() {
+            analyzer.analyze_directory(path, self.max_depth, self.follow_links, &self.ignore)
+        } else if path.is_file() && ArchiveFormat::from_path(path).is_some() {
+            analyzer.analyze_archive(path, self.max_depth, &self.ignore)
+        } else {
+            return Err(format!(
+                "{} is neither a directory nor an archive",
+                path.display()
+            ));
+        }
+        .map_err(|e| e.to_string())?;
+        stats.make_paths_relative(path);
+        Ok(stats)
+    }
+}
+
+/// Arguments for the `history` subcommand.
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Directory inside a git repository
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Newest commit of the range; earlier commits follow its first parents
+    #[arg(long, value_name = "REV", default_value = "HEAD")]
+    pub rev: String,
+
+    /// Only commits made after this date (e.g. 2024-01-01 or "6 months ago")
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// Analyze every Nth commit, counting back from --rev
+    #[arg(long, value_name = "N", default_value = "1")]
+    pub every: NonZeroUsize,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = HistoryFormat::Json)]
+    pub format: HistoryFormat,
+
+    /// File patterns to ignore (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// Detect files with extension EXT as LANGUAGE (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+}
+
+impl HistoryArgs {
+    /// Analyzes each sampled commit and prints the time series.
+    ///
+    /// Ctrl-C stops before the next commit; the commits analyzed so far are
+    /// printed and the run fails.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every sampled commit was analyzed
+    /// * `Err(RunError)` if the path is not a directory in a git repository, a
+    ///   commit cannot be analyzed, or the run was interrupted
+    pub fn run(self) -> Result<(), RunError> {
+        use crate::analyzer::CodeAnalyzer;
+        use crate::formatter::format_history;
+        use crate::history::{collect_history, sample_commits};
+
+        if !self.path.is_dir() {
+            return Err(format!("{} is not a directory", self.path.display()).into());
+        }
+
+        let commits = sample_commits(&self.path, &self.rev, self.since.as_deref(), self.every)
+            .map_err(|e| e.to_string())?;
+        let mut analyzer = CodeAnalyzer::new()
+            .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+            .with_interrupt(crate::interrupt::install_handler()?);
+        let (points, interrupted) = collect_history(
+            &mut analyzer,
+            &self.path,
+            commits,
+            self.max_depth,
+            &self.ignore,
+        )
+        .map_err(|e| e.to_string())?;
+
+        println!("{}", format_history(&points, self.format));
+        if interrupted {
+            Err(RunError::interrupted(format!(
+                "interrupted after {} commits; the history is partial",
+                points.len()
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Arguments for the `hotspots` subcommand.
+#[derive(Args, Debug)]
+pub struct HotspotsArgs {
+    /// Directory inside a git repository
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Count the commits made after this date (e.g. 2024-01-01 or "6 months ago")
+    #[arg(long, value_name = "DATE", default_value = "1 year ago")]
+    pub since: String,
+
+    /// Number of files to list
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub top: usize,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
+    pub format: OutputFormat,
+
+    /// File patterns to ignore (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// Detect files with extension EXT as LANGUAGE (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+
+    /// Wrap the cells of the table to this many columns instead of the
+    /// terminal's width
+    #[arg(long, value_name = "COLUMNS", value_parser = clap::value_parser!(u16).range(1..))]
+    pub width: Option<u16>,
+}
+
+impl HotspotsArgs {
+    /// Counts the commits of each file since the date, analyzes the work tree
+    /// and prints the files with the highest churn times complexity.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the history and the directory could be analyzed
+    /// * `Err(String)` if the path is not a directory in a git repository or
+    ///   the analysis fails
+    pub fn run(self) -> Result<(), String> {
+        use crate::analyzer::CodeAnalyzer;
+        use crate::formatter::{TableStyle, format_hotspots};
+        use crate::git::file_churn;
+        use crate::hotspots::rank_hotspots;
+        use crate::paths::relative_to;
+
+        if !self.path.is_dir() {
+            return Err(format!("{} is not a directory", self.path.display()));
+        }
+
+        let churn = file_churn(&self.path, Some(&self.since)).map_err(|e| e.to_string())?;
+        let stats = CodeAnalyzer::new()
+            .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+            .with_extended_metrics(true)
+            .analyze_directory(&self.path, self.max_depth, false, &self.ignore)
+            .map_err(|e| e.to_string())?;
+        // Both are keyed by the paths joined onto the directory
+        let mut hotspots = rank_hotspots(&stats, &churn, self.top);
+        for hotspot in &mut hotspots {
+            if let Some(relative) = relative_to(&hotspot.path, &self.path) {
+                hotspot.path = relative;
+            }
+        }
+
+        println!(
+            "{}",
+            format_hotspots(&hotspots, self.format, TableStyle::from_env(self.width))
+        );
+        Ok(())
+    }
+}
+
+/// Arguments for the `ownership` subcommand.
+#[derive(Args, Debug)]
+pub struct OwnershipArgs {
+    /// Directory inside a git repository
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
+    pub format: OutputFormat,
+
+    /// File patterns to ignore (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// Detect files with extension EXT as LANGUAGE (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+
+    /// Wrap the cells of the table to this many columns instead of the
+    /// terminal's width
+    #[arg(long, value_name = "COLUMNS", value_parser = clap::value_parser!(u16).range(1..))]
+    pub width: Option<u16>,
+}
+
+impl OwnershipArgs {
+    /// Attributes every function to the author of its declaration line and
+    /// prints the authorship of each directory.
+    ///
+    /// Files and lines not committed yet are left out.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the directory could be analyzed
+    /// * `Err(String)` if the path is not a directory in a git repository
+    ///   with commits, or the analysis fails
+    pub fn run(self) -> Result<(), String> {
+        use crate::analyzer::CodeAnalyzer;
+        use crate::formatter::{TableStyle, format_ownership};
+        use crate::git::{blame_authors, run_git};
+        use crate::ownership::directory_ownership;
+        use crate::paths::relative_to;
+
+        if !self.path.is_dir() {
+            return Err(format!("{} is not a directory", self.path.display()));
+        }
+        run_git(&self.path, &["rev-parse", "--verify", "-q", "HEAD"]).map_err(|e| e.to_string())?;
+
+        let stats = CodeAnalyzer::new()
+            .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+            .with_function_names(true)
+            .analyze_directory(&self.path, self.max_depth, false, &self.ignore)
+            .map_err(|e| e.to_string())?;
+
+        let mut functions = Vec::new();
+        for file in stats.files.iter().filter(|file| !file.functions.is_empty()) {
+            // Untracked files have no history to blame
+            let Ok(authors) = blame_authors(&file.path) else {
+                continue;
+            };
+            let path = relative_to(&file.path, &self.path).unwrap_or_else(|| file.path.clone());
+            for function in &file.functions {
+                if let Some(Some(author)) = authors.get(function.line - 1) {
+                    functions.push((path.clone(), author.clone()));
+                }
+            }
+        }
+
+        let directories = directory_ownership(
+            functions
+                .iter()
+                .map(|(path, author)| (path.as_path(), author.as_str())),
+        );
+        println!(
+            "{}",
+            format_ownership(&directories, self.format, TableStyle::from_env(self.width))
+        );
+        Ok(())
+    }
+}
+
+/// Arguments for the `find` subcommand.
+#[derive(Args, Debug)]
+pub struct FindArgs {
+    /// Name of the function, type or module to find
+    pub name: String,
+
+    /// Directory to search
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Also find names starting with NAME
+    #[arg(long)]
+    pub prefix: bool,
+
+    /// Only find symbols of this kind
+    #[arg(long, value_enum)]
+    pub kind: Option<SymbolKind>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
+    pub format: OutputFormat,
+
+    /// File patterns to ignore (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// Detect files with extension EXT as LANGUAGE (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+
+    /// Directory of the cached symbols [default: ~/.cache/code-stats-rs]
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Parse every file again instead of reusing cached symbols
+    #[arg(long, conflicts_with = "cache_dir")]
+    pub no_cache: bool,
+}
+
+impl FindArgs {
+    /// Lists the declarations named like the given name.
+    ///
+    /// Symbols are cached with the other per-file results, so only the
+    /// files that changed since the last run are parsed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the directory could be searched, whether or not a symbol
+    ///   was found
+    /// * `Err(String)` if the path is not a directory or the analysis fails
+    pub fn run(self) -> Result<(), String> {
+        use crate::analyzer::CodeAnalyzer;
+        use crate::cache::default_cache_dir;
+        use crate::formatter::format_symbols;
+        use crate::symbols::find_symbols;
+
+        if !self.path.is_dir() {
+            return Err(format!("{} is not a directory", self.path.display()));
+        }
+
+        let cache_dir = match self.no_cache {
+            true => None,
+            false => self.cache_dir.clone().or_else(default_cache_dir),
+        };
+        let stats = CodeAnalyzer::new()
+            .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+            .with_symbols(true)
+            .with_cache_dir(cache_dir)
+            .analyze_directory(&self.path, self.max_depth, false, &self.ignore)
+            .map_err(|e| e.to_string())?;
+
+        let matches = find_symbols(&stats, &self.path, &self.name, self.prefix, self.kind);
+        println!("{}", format_symbols(&matches, &self.name, self.format));
+        Ok(())
+    }
+}
+
+/// Arguments for the `verify` subcommand.
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// The published report
+    pub report: PathBuf,
+
+    /// Attestation written by `--sign` together with `--signing-key`
+    #[arg(long, value_name = "FILE")]
+    pub attestation: PathBuf,
+
+    /// The HMAC-SHA256 key the attestation was signed with
+    #[arg(long, value_name = "KEY_FILE")]
+    pub key: PathBuf,
+}
+
+impl VerifyArgs {
+    /// Verifies the report against its attestation and prints the attested provenance.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the attestation is signed with the key and covers the report
+    /// * `Err(String)` if either file cannot be read, the signature does not
+    ///   match or the report was modified
+    pub fn run(self) -> Result<(), String> {
+        use crate::provenance::{read_key, verify_report};
+
+        let key = read_key(&self.key)?;
+        let statement = verify_report(&self.report, &self.attestation, &key)?;
+
+        let builder = &statement.predicate.run_details.builder;
+        let version = builder
+            .version
+            .get(&builder.id)
+            .map_or("unknown", String::as_str);
+        let definition = &statement.predicate.build_definition;
+        println!(
+            "Verified: {} was produced by {} {} from {} input files\nArguments: {}",
+            self.report.display(),
+            builder.id,
+            version,
+            definition.resolved_dependencies.len(),
+            definition
+                .external_parameters
+                .get("arguments")
+                .map(|arguments| arguments.join(" "))
+                .unwrap_or_default()
+        );
+        Ok(())
+    }
+}
+
+/// Arguments for the `badge` subcommand.
+#[derive(Args, Debug)]
+pub struct BadgeArgs {
+    /// Directory to analyze
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Take the statistics from a report saved with `--format json` instead
+    /// of analyzing PATH
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+
+    /// Statistic to show
+    #[arg(long, value_enum, default_value_t = BadgeMetric::Functions)]
+    pub metric: BadgeMetric,
+
+    /// Language of a `--metric language` badge (the most used language by default)
+    #[arg(long, value_name = "LANGUAGE")]
+    pub language: Option<String>,
+
+    /// Text of the left half instead of the metric's name
+    #[arg(long)]
+    pub label: Option<String>,
+
+    /// Color of the right half, a CSS color such as blue or #4c1
+    #[arg(long, default_value = "#007ec6")]
+    pub color: String,
+
+    /// File patterns to ignore (can be used multiple times)
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<String>,
+
+    /// Follow symbolic links
+    #[arg(long)]
+    pub follow_links: bool,
+
+    /// Maximum depth for directory traversal
+    #[arg(long, default_value_t = 100)]
+    pub max_depth: usize,
+
+    /// Detect files with extension EXT as LANGUAGE (can be used multiple times)
+    #[arg(long = "map-ext", value_name = "EXT=LANGUAGE")]
+    pub map_ext: Vec<String>,
+
+    /// How to detect the language of each file
+    #[arg(long, value_enum, default_value_t = DetectionStrategy::Auto)]
+    pub detection: DetectionStrategy,
+}
+
+impl BadgeArgs {
+    /// Prints the badge as an SVG document.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the statistics could be analyzed or loaded
+    /// * `Err(String)` if the report is unreadable, the analysis fails, the
+    ///   language is unknown, or a language badge has no files to show
+    pub fn run(self) -> Result<(), String> {
+        use crate::analyzer::CodeAnalyzer;
+        use crate::badge::{badge_text, render_badge};
+        use crate::diff::load_baseline;
+
+        let language = self
+            .language
+            .as_deref()
+            .map(|name| {
+                SupportedLanguage::from_config_name(name)
+                    .ok_or_else(|| format!("Unknown language '{name}'"))
+            })
+            .transpose()?;
+
+        let stats = match &self.report {
+            Some(report) => load_baseline(report),
+            None => CodeAnalyzer::new()
+                .with_detection(self.detection)
+                .with_extensions(ExtensionOverrides::from_mappings(&self.map_ext)?)
+                .analyze_directory(&self.path, self.max_depth, self.follow_links, &self.ignore),
+        }
+        .map_err(|e| e.to_string())?;
+
+        let (label, message) = badge_text(&stats, self.metric, language)?;
+        print!(
+            "{}",
+            render_badge(
+                self.label.as_deref().unwrap_or(&label),
+                &message,
+                &self.color
+            )
+        );
+        Ok(())
+    }
+}
+
+/// Arguments for the `languages` subcommand.
+#[derive(Args, Debug)]
+pub struct LanguagesArgs {
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Summary)]
+    pub format: OutputFormat,
+}
+
+impl LanguagesArgs {
+    /// Prints every supported language with the node kinds counted for it.
+    pub fn run(self) {
+        use crate::formatter::format_languages;
+        use crate::language::LanguageCoverage;
+
+        print!(
+            "{}",
+            format_languages(&LanguageCoverage::all(), self.format)
+        );
+    }
+}
+
+/// Arguments for the `gen-fixtures` developer subcommand.
+#[cfg(feature = "gen-fixtures")]
+#[derive(Args, Debug)]
+pub struct GenFixturesArgs {
+    /// Directory to write the generated repository into
+    pub output: PathBuf,
+
+    /// Number of files to generate per language
+    #[arg(long, default_value_t = 100)]
+    pub files_per_language: usize,
+
+    /// Number of functions in every generated file
+    #[arg(long, default_value_t = 10)]
+    pub functions_per_file: usize,
+
+    /// Number of classes/structs in every generated file
+    #[arg(long, default_value_t = 1)]
+    pub classes_per_file: usize,
+}
+
+#[cfg(feature = "gen-fixtures")]
+impl GenFixturesArgs {
+    /// Writes the synthetic repository and reports how many files were created.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every file was written
+    /// * `Err(String)` if the output directory cannot be written
+    pub fn run(self) -> Result<(), String> {
+        use crate::fixtures::{FixtureSpec, generate_fixtures};
+
+        let spec = FixtureSpec {
+            files_per_language: self.files_per_language,
+            functions_per_file: self.functions_per_file,
+            classes_per_file: self.classes_per_file,
+        };
+
+        let written = generate_fixtures(&self.output, &spec).map_err(|e| {
+            format!(
+                "Failed to generate fixtures in {}: {e}",
+                self.output.display()
+            )
+        })?;
+
+        println!(
+            "Generated {} files in {}",
+            written.len(),
+            self.output.display()
+        );
+        Ok(())
+    }
+}
+
 /// Available output formats for the analysis results.
 ///
 /// Each format provides a different level of detail and structure
@@ -114,30 +1795,361 @@ pub enum OutputFormat {
     Detail,
     /// JSON output
     Json,
+    /// Box-drawn tables of every metric per language (and per file with
+    /// --detail); subcommands print their summary instead
+    Table,
+    /// The JSON report as YAML, readable enough to commit next to the code;
+    /// subcommands print their summary instead
+    Yaml,
+    /// The JSON report as TOML; subcommands print their summary instead
+    Toml,
+    /// Line counts per language in the JSON layout of `cloc --json`, for
+    /// dashboards built on cloc; subcommands print their summary instead
+    ClocJson,
+}
+
+/// Columns the language summary and per-file detail can be ranked by.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Language name or file path
+    #[default]
+    Name,
+    /// Number of files (per-file detail falls back to the path)
+    Files,
+    /// Number of functions
+    Functions,
+    /// Number of classes/structs
+    Classes,
+}
+
+/// How many metrics are computed per file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MetricsLevel {
+    /// Counts of declarations and lines, and the function measurements
+    #[default]
+    Basic,
+    /// Additionally Halstead volume, cyclomatic complexity and maintainability index
+    Extended,
+}
+
+/// Direction of a `--sort` ranking.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    /// Smallest first
+    Asc,
+    /// Largest first
+    Desc,
+}
+
+/// Output formats of the `history` subcommand.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum HistoryFormat {
+    /// JSON array of commits with totals and per-language counts
+    Json,
+    /// One CSV row per commit and language
+    Csv,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use clap::CommandFactory;
 
     #[test]
     fn test_cli_parse_basic() {
         let cli = Cli::try_parse_from(["code-stats-rs", "src/main.rs"]).unwrap();
 
-        assert_eq!(cli.path, PathBuf::from("src/main.rs"));
+        assert_eq!(cli.path, Some(PathBuf::from("src/main.rs")));
+        assert!(cli.command.is_none());
         assert_eq!(cli.format, OutputFormat::Summary);
         assert!(!cli.detail);
         assert!(cli.ignore.is_empty());
         assert!(!cli.follow_links);
         assert_eq!(cli.max_depth, 100);
+        assert!(!cli.scan_secrets);
+        assert!(!cli.exclude_suspicious);
+        assert!(!cli.include_vendored);
+        assert!(!cli.exclude_generated);
+        assert!(cli.secret_pattern.is_empty());
+        assert!(cli.query.is_empty());
+        assert!(!cli.magic_values);
+        assert!(!cli.i18n);
+        assert!(cli.top.is_none());
+        assert_eq!(cli.metrics, MetricsLevel::Basic);
+        assert!(!cli.find_duplicates);
+        assert!(cli.limit.is_none());
+        assert!(cli.page.is_none());
+        assert!(!cli.no_pager);
+        assert!(!cli.mmap);
+        assert!(cli.mmap_threshold.is_none());
+        assert_eq!(cli.detection, DetectionStrategy::Auto);
+        assert!(cli.map_ext.is_empty());
+        assert!(cli.git_diff.is_none());
+        assert!(!cli.staged);
+        assert!(cli.rev.is_none());
+        assert!(cli.cache_dir.is_none());
+        assert!(!cli.no_cache);
+        assert!(!cli.warnings);
+        assert!(cli.fail_on.is_none());
+        assert!(cli.sign.is_none());
+        assert!(cli.signing_key.is_none());
+    }
+
+    #[test]
+    fn test_cli_parse_signing_key_requires_sign() {
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--signing-key", "key"]).is_err());
+
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--sign",
+            "report.att.json",
+            "--signing-key",
+            "key",
+        ])
+        .unwrap();
+        assert_eq!(cli.sign, Some(PathBuf::from("report.att.json")));
+        assert_eq!(cli.signing_key, Some(PathBuf::from("key")));
+    }
+
+    #[test]
+    fn test_cli_parse_history() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "history"]).unwrap();
+        let Some(Command::History(args)) = cli.command else {
+            panic!("Expected history subcommand");
+        };
+        assert_eq!(args.path, PathBuf::from("."));
+        assert_eq!(args.rev, "HEAD");
+        assert!(args.since.is_none());
+        assert_eq!(args.every.get(), 1);
+        assert_eq!(args.format, HistoryFormat::Json);
+
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "history",
+            "repo",
+            "--since",
+            "6 months ago",
+            "--every",
+            "10",
+            "--format",
+            "csv",
+        ])
+        .unwrap();
+        let Some(Command::History(args)) = cli.command else {
+            panic!("Expected history subcommand");
+        };
+        assert_eq!(args.since.as_deref(), Some("6 months ago"));
+        assert_eq!(args.every.get(), 10);
+        assert_eq!(args.format, HistoryFormat::Csv);
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "history", "--every", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_ownership() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "ownership"]).unwrap();
+        let Some(Command::Ownership(args)) = cli.command else {
+            panic!("Expected ownership subcommand");
+        };
+        assert_eq!(args.path, PathBuf::from("."));
+        assert_eq!(args.format, OutputFormat::Summary);
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "ownership", "repo", "--format", "json"])
+            .unwrap();
+        let Some(Command::Ownership(args)) = cli.command else {
+            panic!("Expected ownership subcommand");
+        };
+        assert_eq!(args.path, PathBuf::from("repo"));
+        assert_eq!(args.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_cli_parse_find() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "find", "parse"]).unwrap();
+        let Some(Command::Find(args)) = cli.command else {
+            panic!("Expected find subcommand");
+        };
+        assert_eq!(args.name, "parse");
+        assert_eq!(args.path, PathBuf::from("."));
+        assert!(!args.prefix);
+        assert!(args.kind.is_none());
+
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "find",
+            "Parse",
+            "src",
+            "--prefix",
+            "--kind",
+            "type-alias",
+            "--no-cache",
+        ])
+        .unwrap();
+        let Some(Command::Find(args)) = cli.command else {
+            panic!("Expected find subcommand");
+        };
+        assert_eq!(args.path, PathBuf::from("src"));
+        assert!(args.prefix && args.no_cache);
+        assert_eq!(args.kind, Some(SymbolKind::TypeAlias));
+        assert!(Cli::try_parse_from(["code-stats-rs", "find"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_hotspots() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "hotspots"]).unwrap();
+        let Some(Command::Hotspots(args)) = cli.command else {
+            panic!("Expected hotspots subcommand");
+        };
+        assert_eq!(args.path, PathBuf::from("."));
+        assert_eq!(args.since, "1 year ago");
+        assert_eq!(args.top, 20);
+        assert_eq!(args.format, OutputFormat::Summary);
+
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "hotspots",
+            "repo",
+            "--since",
+            "6 months ago",
+            "--top",
+            "5",
+        ])
+        .unwrap();
+        let Some(Command::Hotspots(args)) = cli.command else {
+            panic!("Expected hotspots subcommand");
+        };
+        assert_eq!(args.since, "6 months ago");
+        assert_eq!(args.top, 5);
+    }
+
+    #[test]
+    fn test_cli_parse_rev() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "--rev", "HEAD~10", "."]).unwrap();
+        assert_eq!(cli.rev.as_deref(), Some("HEAD~10"));
+
+        for conflicting in [
+            &["--staged"][..],
+            &["--git-diff", "main"],
+            &["--sign", "a.json"],
+        ] {
+            let mut args = vec!["code-stats-rs", ".", "--rev", "HEAD"];
+            args.extend(conflicting);
+            assert!(Cli::try_parse_from(args).is_err());
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_cache_options() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--cache-dir", "/tmp/c"]).unwrap();
+        assert_eq!(cli.cache_dir, Some(PathBuf::from("/tmp/c")));
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--no-cache"]).unwrap();
+        assert!(cli.no_cache);
+
+        assert!(
+            Cli::try_parse_from(["code-stats-rs", "src", "--no-cache", "--cache-dir", "c"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_fail_on() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--fail-on", "warning"]).unwrap();
+        assert_eq!(cli.fail_on, Some(Severity::Warning));
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--fail-on", "fatal"]).is_err());
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--strict"]).unwrap();
+        assert!(cli.strict);
+        assert!(
+            Cli::try_parse_from(["code-stats-rs", "src", "--strict", "--fail-on", "info"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_long_help_lists_formats_and_languages() {
+        let help = long_help();
+        assert_eq!(
+            help.matches("      code-stats-rs ").count(),
+            OutputFormat::value_variants().len()
+        );
+        for language in SupportedLanguage::ALL {
+            assert!(help.contains(language.name()), "{language:?}");
+        }
+        assert!(help.contains("  TypeScript  .ts .tsx\n"));
+        assert!(help.contains("  130  Interrupted (Ctrl-C)"));
+    }
+
+    #[test]
+    fn test_interrupted_runs_have_distinct_exit_code() {
+        let error = RunError::interrupted("interrupted after 3 files".to_string());
+        assert_eq!(error.exit_code, RunError::INTERRUPTED_EXIT_CODE);
+        for other in [
+            RunError::EXIT_CODE,
+            RunError::THRESHOLD_EXIT_CODE,
+            RunError::PARTIAL_EXIT_CODE,
+        ] {
+            assert_ne!(error.exit_code, other);
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_no_file_list() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--no-file-list"]).unwrap();
+        assert!(cli.no_file_list);
+        for conflicting in [
+            ["--detail"].as_slice(),
+            &["--top", "3"],
+            &["--sign", "a.json"],
+        ] {
+            let mut args = vec!["code-stats-rs", "src", "--no-file-list"];
+            args.extend(conflicting);
+            assert!(Cli::try_parse_from(args).is_err(), "{conflicting:?}");
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_profile() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--profile", "5"]).unwrap();
+        assert_eq!(cli.profile, NonZeroUsize::new(5));
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--profile", "0"]).is_err());
+        assert!(
+            Cli::try_parse_from(["code-stats-rs", "src", "--profile", "5", "--rev", "HEAD"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_check() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--check"]).unwrap();
+        assert!(cli.check);
+        assert!(
+            Cli::try_parse_from(["code-stats-rs", "src", "--check", "--sign", "a.json"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_fail_if() {
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--fail-if",
+            "functions_per_file>100",
+            "--fail-if",
+            "errors>0",
+        ])
+        .unwrap();
+        let rules: Vec<String> = cli.fail_if.iter().map(ToString::to_string).collect();
+        assert_eq!(rules, ["functions_per_file>100", "errors>0"]);
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--fail-if", "functions"]).is_err());
     }
 
     #[test]
     fn test_cli_parse_with_format() {
         let cli = Cli::try_parse_from(["code-stats-rs", "src", "--format", "json"]).unwrap();
 
-        assert_eq!(cli.path, PathBuf::from("src"));
+        assert_eq!(cli.path, Some(PathBuf::from("src")));
         assert_eq!(cli.format, OutputFormat::Json);
     }
 
@@ -176,6 +2188,137 @@ mod tests {
         let cli = Cli::try_parse_from(["code-stats-rs", "src", "--follow-links"]).unwrap();
 
         assert!(cli.follow_links);
+        assert!(!cli.no_dedup);
+
+        let cli =
+            Cli::try_parse_from(["code-stats-rs", "src", "--follow-links", "--no-dedup"]).unwrap();
+        assert!(cli.no_dedup);
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--no-dedup"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_encoding() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--encoding", "sjis"]).unwrap();
+        assert_eq!(cli.encoding, Some(encoding_rs::SHIFT_JIS));
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--encoding", "klingon"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_max_file_size() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src"]).unwrap();
+        assert_eq!(cli.max_file_size, 10 * 1024 * 1024);
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--max-file-size", "0"]).unwrap();
+        assert_eq!(cli.max_file_size, 0);
+    }
+
+    #[test]
+    fn test_cli_parse_hidden() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src"]).unwrap();
+        assert!(!cli.hidden);
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--hidden"]).unwrap();
+        assert!(cli.hidden);
+
+        // The last of the two flags wins, as in ripgrep
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--hidden", "--no-hidden"]).unwrap();
+        assert!(!cli.hidden);
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--no-hidden", "--hidden"]).unwrap();
+        assert!(cli.hidden);
+    }
+
+    #[test]
+    fn test_cli_parse_count_anonymous() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src"]).unwrap();
+        assert_eq!(cli.count_anonymous, None);
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--count-anonymous", "top-level"])
+            .unwrap();
+        assert_eq!(cli.count_anonymous, Some(AnonymousFunctions::TopLevel));
+
+        assert!(
+            Cli::try_parse_from(["code-stats-rs", "src", "--count-anonymous", "some"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_hash() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src"]).unwrap();
+        assert!(!cli.hash);
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--hash"]).unwrap();
+        assert!(cli.hash);
+    }
+
+    #[test]
+    fn test_cli_parse_schema() {
+        // The schema needs no path to analyze
+        let cli = Cli::try_parse_from(["code-stats-rs", "--schema"]).unwrap();
+        assert!(cli.schema);
+        assert_eq!(cli.path, None);
+
+        assert!(Cli::try_parse_from(["code-stats-rs"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_width() {
+        let cli = Cli::try_parse_from(["code-stats-rs", ".", "--width", "80"]).unwrap();
+        assert_eq!(cli.width, Some(80));
+        assert!(Cli::try_parse_from(["code-stats-rs", ".", "--width", "0"]).is_err());
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "."]).unwrap();
+        assert_eq!(cli.width, None);
+    }
+
+    #[test]
+    fn test_cli_parse_compact() {
+        let cli =
+            Cli::try_parse_from(["code-stats-rs", ".", "--format", "json", "--compact"]).unwrap();
+        assert!(cli.compact);
+
+        let cli = Cli::try_parse_from(["code-stats-rs", "."]).unwrap();
+        assert!(!cli.compact);
+    }
+
+    #[test]
+    fn test_cli_parse_limits() {
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--max-files",
+            "100",
+            "--max-total-bytes",
+            "4096",
+        ])
+        .unwrap();
+        assert_eq!(cli.max_files, Some(100));
+        assert_eq!(cli.max_total_bytes, Some(4096));
+        assert_eq!(cli.max_link_depth, None);
+
+        // Only links that are followed can be too deep
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--max-link-depth", "1"]).is_err());
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--follow-links",
+            "--max-link-depth",
+            "1",
+        ])
+        .unwrap();
+        assert_eq!(cli.max_link_depth, Some(1));
+    }
+
+    #[test]
+    fn test_cli_parse_mmap_threshold() {
+        let cli =
+            Cli::try_parse_from(["code-stats-rs", "src", "--mmap-threshold", "1048576"]).unwrap();
+        assert_eq!(cli.mmap_threshold, Some(1024 * 1024));
+
+        assert!(
+            Cli::try_parse_from(["code-stats-rs", "src", "--mmap", "--mmap-threshold", "1"])
+                .is_err()
+        );
     }
 
     #[test]
@@ -203,7 +2346,7 @@ mod tests {
         ])
         .unwrap();
 
-        assert_eq!(cli.path, PathBuf::from("/path/to/analyze"));
+        assert_eq!(cli.path, Some(PathBuf::from("/path/to/analyze")));
         assert_eq!(cli.format, OutputFormat::Json);
         assert!(cli.detail);
         assert_eq!(cli.ignore, vec!["node_modules", "vendor"]);
@@ -211,6 +2354,211 @@ mod tests {
         assert_eq!(cli.max_depth, 3);
     }
 
+    #[test]
+    fn test_cli_parse_diff() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "diff", "--baseline", "old.json"]).unwrap();
+
+        let Some(Command::Diff(args)) = cli.command else {
+            panic!("Expected diff subcommand");
+        };
+        assert_eq!(args.path, PathBuf::from("."));
+        assert_eq!(args.baseline, PathBuf::from("old.json"));
+        assert_eq!(args.format, OutputFormat::Summary);
+        assert!(args.ratchet.is_empty());
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "diff", "."]).is_err());
+
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "diff",
+            "--baseline",
+            "old.json",
+            "--ratchet",
+            "functions@src/legacy",
+        ])
+        .unwrap();
+        let Some(Command::Diff(args)) = cli.command else {
+            panic!("Expected diff subcommand");
+        };
+        assert_eq!(args.ratchet[0].path, Some(PathBuf::from("src/legacy")));
+        assert!(
+            Cli::try_parse_from([
+                "code-stats-rs",
+                "diff",
+                "--baseline",
+                "old.json",
+                "--ratchet",
+                "complexity",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_audit_license_headers() {
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "audit",
+            "license-headers",
+            "--template",
+            "header.txt",
+            "--ignore",
+            "vendor",
+        ])
+        .unwrap();
+
+        assert!(cli.path.is_none());
+        let Some(Command::Audit {
+            check: AuditCheck::LicenseHeaders(args),
+        }) = cli.command
+        else {
+            panic!("Expected audit license-headers subcommand");
+        };
+        assert_eq!(args.path, PathBuf::from("."));
+        assert_eq!(args.template, PathBuf::from("header.txt"));
+        assert_eq!(args.ignore, vec!["vendor"]);
+        assert_eq!(args.max_depth, 100);
+    }
+
+    #[test]
+    fn test_cli_parse_audit_requires_template() {
+        let result = Cli::try_parse_from(["code-stats-rs", "audit", "license-headers", "src"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_secret_scanning() {
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--scan-secrets",
+            "--secret-pattern",
+            "token=tok_[a-z]+",
+        ])
+        .unwrap();
+
+        assert!(cli.scan_secrets);
+        assert_eq!(cli.secret_pattern, vec!["token=tok_[a-z]+"]);
+
+        // Custom patterns only make sense when scanning is enabled
+        let result =
+            Cli::try_parse_from(["code-stats-rs", "src", "--secret-pattern", "token=tok_"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_queries() {
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--query",
+            "rust=macros.scm",
+            "--query",
+            "typescript=hooks.scm",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.query, vec!["rust=macros.scm", "typescript=hooks.scm"]);
+    }
+
+    #[test]
+    fn test_cli_parse_sort() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src"]).unwrap();
+        assert_eq!(cli.sort, SortKey::Name);
+        assert_eq!(cli.order, None);
+
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--sort",
+            "functions",
+            "--order",
+            "asc",
+        ])
+        .unwrap();
+        assert_eq!(cli.sort, SortKey::Functions);
+        assert_eq!(cli.order, Some(SortOrder::Asc));
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--sort", "size"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_pagination() {
+        let cli =
+            Cli::try_parse_from(["code-stats-rs", "src", "--limit", "50", "--page", "3"]).unwrap();
+
+        assert_eq!(cli.limit, NonZeroUsize::new(50));
+        assert_eq!(cli.page, NonZeroUsize::new(3));
+
+        // Pages are 1-based and only meaningful with a page size
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--limit", "0"]).is_err());
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--page", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_detection() {
+        for (value, expected) in [
+            ("content", DetectionStrategy::Content),
+            ("extension", DetectionStrategy::Extension),
+            ("auto", DetectionStrategy::Auto),
+        ] {
+            let cli = Cli::try_parse_from(["code-stats-rs", "src", "--detection", value]).unwrap();
+            assert_eq!(cli.detection, expected);
+        }
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--detection", "magic"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_map_ext() {
+        let cli = Cli::try_parse_from([
+            "code-stats-rs",
+            "src",
+            "--map-ext",
+            "pyx=python",
+            "--map-ext",
+            "mjs=javascript",
+        ])
+        .unwrap();
+        assert_eq!(cli.map_ext, vec!["pyx=python", "mjs=javascript"]);
+    }
+
+    #[test]
+    fn test_cli_parse_top() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--top", "10"]).unwrap();
+        assert_eq!(cli.top, NonZeroUsize::new(10));
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--top", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_metrics() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--metrics", "extended"]).unwrap();
+        assert_eq!(cli.metrics, MetricsLevel::Extended);
+
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--metrics", "all"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_find_duplicates() {
+        let cli = Cli::try_parse_from(["code-stats-rs", "src", "--find-duplicates"]).unwrap();
+        assert!(cli.find_duplicates);
+        assert_eq!(cli.min_duplicates.get(), 3);
+
+        let args = [
+            "code-stats-rs",
+            "src",
+            "--find-duplicates",
+            "--min-duplicates",
+            "2",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.min_duplicates.get(), 2);
+
+        // The threshold only applies to the duplicate report
+        assert!(Cli::try_parse_from(["code-stats-rs", "src", "--min-duplicates", "2"]).is_err());
+    }
+
     #[test]
     fn test_cli_parse_missing_path() {
         let result = Cli::try_parse_from(["code-stats-rs"]);
@@ -258,6 +2606,14 @@ mod tests {
             OutputFormat::from_str("JSON", true).unwrap(),
             OutputFormat::Json
         );
+        assert_eq!(
+            OutputFormat::from_str("yaml", true).unwrap(),
+            OutputFormat::Yaml
+        );
+        assert_eq!(
+            OutputFormat::from_str("cloc-json", true).unwrap(),
+            OutputFormat::ClocJson
+        );
     }
 
     #[test]
@@ -0,0 +1,199 @@
+//! Detection of React components in JavaScript and TypeScript.
+//!
+//! A function component is a function with a capitalized name that returns
+//! JSX, either declared as `function Button()` or assigned, as in
+//! `const Button = () => <button />`, possibly wrapped in a call such as
+//! `memo(...)` or `forwardRef(...)`. A class component extends `Component` or
+//! `PureComponent`, with or without the `React.` prefix.
+
+use crate::language::SupportedLanguage;
+use tree_sitter::Node;
+
+/// Base classes of class components.
+const COMPONENT_BASES: [&str; 4] = [
+    "Component",
+    "PureComponent",
+    "React.Component",
+    "React.PureComponent",
+];
+
+/// Node kinds that start a new function or class, whose returns are their own.
+const SCOPE_KINDS: [&str; 6] = [
+    "function_declaration",
+    "function_expression",
+    "arrow_function",
+    "method_definition",
+    "class_declaration",
+    "class",
+];
+
+/// Checks whether a declaration is a React component.
+///
+/// # Arguments
+///
+/// * `declaration` - A node captured as `@function` or `@class` by the built-in query
+/// * `source_code` - The source code the node was parsed from
+/// * `language` - The programming language of the source
+pub(crate) fn is_component(
+    declaration: &Node,
+    source_code: &str,
+    language: &SupportedLanguage,
+) -> bool {
+    if !matches!(
+        language,
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript
+    ) {
+        return false;
+    }
+
+    match declaration.kind() {
+        "class_declaration" => extends_component(declaration, source_code),
+        "method_definition" => false,
+        _ => {
+            function_name(declaration, source_code)
+                .is_some_and(|name| name.starts_with(|c: char| c.is_ascii_uppercase()))
+                && returns_jsx(declaration)
+        }
+    }
+}
+
+/// Returns the name of a function: its own, or that of the variable it is
+/// assigned to, looking through wrapping calls like `memo(() => ...)`.
+fn function_name<'a>(function: &Node, source_code: &'a str) -> Option<&'a str> {
+    if let Some(name) = function.child_by_field_name("name") {
+        return Some(&source_code[name.byte_range()]);
+    }
+
+    let mut node = function.parent()?;
+    while matches!(node.kind(), "arguments" | "call_expression") {
+        node = node.parent()?;
+    }
+    (node.kind() == "variable_declarator")
+        .then(|| node.child_by_field_name("name"))
+        .flatten()
+        .filter(|name| name.kind() == "identifier")
+        .map(|name| &source_code[name.byte_range()])
+}
+
+/// Checks whether a class extends one of the [`COMPONENT_BASES`], ignoring
+/// type arguments such as `Component<Props>`.
+fn extends_component(class: &Node, source_code: &str) -> bool {
+    let mut cursor = class.walk();
+    let Some(heritage) = class
+        .children(&mut cursor)
+        .find(|child| child.kind() == "class_heritage")
+    else {
+        return false;
+    };
+
+    let base = source_code[heritage.byte_range()]
+        .trim_start()
+        .strip_prefix("extends")
+        .unwrap_or_default()
+        .trim_start();
+    let end = base
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == '$'))
+        .unwrap_or(base.len());
+    COMPONENT_BASES.contains(&&base[..end])
+}
+
+/// Checks whether a function returns JSX from its expression body or from
+/// any `return` outside of nested functions.
+fn returns_jsx(function: &Node) -> bool {
+    let Some(body) = function.child_by_field_name("body") else {
+        return false;
+    };
+    if body.kind() != "statement_block" {
+        return is_jsx(&body);
+    }
+    has_jsx_return(&body)
+}
+
+/// Searches a block for a `return` of JSX, skipping nested functions and classes.
+fn has_jsx_return(node: &Node) -> bool {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor).any(|child| {
+        if child.kind() == "return_statement" {
+            child.named_child(0).is_some_and(|value| is_jsx(&value))
+        } else {
+            !SCOPE_KINDS.contains(&child.kind()) && has_jsx_return(&child)
+        }
+    })
+}
+
+/// Checks whether an expression evaluates to JSX in some branch, as in
+/// `(<div />)`, `ready ? <App /> : null` or `open && <Dialog />`.
+fn is_jsx(expression: &Node) -> bool {
+    match expression.kind() {
+        "jsx_element" | "jsx_self_closing_element" => true,
+        "parenthesized_expression" => expression
+            .named_child(0)
+            .is_some_and(|inner| is_jsx(&inner)),
+        "ternary_expression" => ["consequence", "alternative"].iter().any(|field| {
+            expression
+                .child_by_field_name(field)
+                .is_some_and(|branch| is_jsx(&branch))
+        }),
+        "binary_expression" => expression
+            .child_by_field_name("right")
+            .is_some_and(|right| is_jsx(&right)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::SupportedLanguage;
+    use crate::parser::count_source;
+
+    #[test]
+    fn test_function_and_class_components() {
+        let source = r#"
+import React, { Component, memo } from "react";
+
+export function Header({ title }) {
+    const label = () => <span>{title}</span>;
+    return <h1>{label()}</h1>;
+}
+
+const Footer = () => (
+    <footer />
+);
+
+export const Badge = memo(function ({ count }) {
+    return count > 0 ? <b>{count}</b> : null;
+});
+
+class Page extends React.Component {
+    render() {
+        return <main />;
+    }
+}
+
+class Store extends Base {}
+
+function helper() {
+    return <div />;
+}
+
+function Format(value) {
+    return String(value);
+}
+"#;
+
+        // Header, Footer, Badge and Page; not the lowercase `label` and
+        // `helper`, `Format` returning no JSX, or `Store`
+        let stats = count_source(SupportedLanguage::JavaScript, source);
+        assert_eq!(stats.component_count, 4);
+    }
+
+    #[test]
+    fn test_typescript_class_components() {
+        let source = "class Counter extends Component<Props, State> {}\n\
+                      class Pure extends PureComponent {}\n\
+                      class Model extends Entity<Props> {}\n";
+
+        let stats = count_source(SupportedLanguage::TypeScript, source);
+        assert_eq!(stats.component_count, 2);
+    }
+}
@@ -0,0 +1,590 @@
+//! Per-directory `.code-stats.toml` configuration files.
+//!
+//! A configuration file applies to the directory it lives in and everything
+//! below it. Nested files cascade: ignore patterns accumulate from the analysis
+//! root downwards, and counting policies set in a subdirectory override the
+//! ones inherited from its parents. A file with `root = true` discards
+//! everything inherited.
+//!
+//! ```toml
+//! root = false
+//! ignore = ["generated/", ".pb.go"]
+//!
+//! [count]
+//! magic_values = false
+//! untranslated_strings = true
+//! max_function_lines = 2000
+//! anonymous_functions = "top-level"
+//!
+//! [extensions.python]
+//! add = ["cgi"]
+//!
+//! [extensions.typescript]
+//! remove = ["ts"]
+//!
+//! [rules.rust]
+//! trait_item = "class"
+//! macro_definition = "function"
+//! ```
+//!
+//! Extension changes cascade like the other settings and are applied before
+//! content detection (see [`ExtensionOverrides`]). Node kind rules cascade
+//! per kind, so a subdirectory can replace a single rule of its parents
+//! (see [`KindRules`]).
+
+use crate::error::{CodeStatsError, Result};
+use crate::language::{ExtensionOverrides, SupportedLanguage};
+use crate::paths::path_contains;
+use crate::queries::KindRules;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Name of the configuration file looked up in every analyzed directory.
+pub(crate) const CONFIG_FILE_NAME: &str = ".code-stats.toml";
+
+/// The contents of a single `.code-stats.toml` file.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    /// Stop inheriting settings from parent directories
+    root: bool,
+    /// Additional ignore patterns for this subtree (substring matching)
+    ignore: Vec<String>,
+    /// Counting policy overrides for this subtree
+    count: CountPolicyOverrides,
+    /// Extension changes for this subtree, keyed by language name
+    extensions: BTreeMap<String, ExtensionEdits>,
+    /// What node kinds count as, keyed by language name and node kind
+    rules: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Extensions to add to or remove from one language.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ExtensionEdits {
+    add: Vec<String>,
+    remove: Vec<String>,
+}
+
+/// Counting policy settings as written in a configuration file.
+///
+/// Unset entries keep the value inherited from the parent directory.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CountPolicyOverrides {
+    magic_values: Option<bool>,
+    untranslated_strings: Option<bool>,
+    /// `0` removes the limit
+    max_function_lines: Option<usize>,
+    anonymous_functions: Option<AnonymousFunctions>,
+}
+
+/// Default upper bound on the lines a single function contributes to the statistics.
+pub(crate) const DEFAULT_MAX_FUNCTION_LINES: usize = 2000;
+
+/// Which anonymous functions, such as closures, lambdas and callbacks, are
+/// counted as functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnonymousFunctions {
+    /// Every anonymous function
+    All,
+    /// No anonymous function; only named functions and methods
+    None,
+    /// Anonymous functions outside of other functions, such as
+    /// `const handler = () => {}` at module level, but no callbacks passed
+    /// around inside function bodies
+    TopLevel,
+}
+
+/// Which optional counters are collected for a file, and their limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CountPolicy {
+    /// Count magic strings and numbers
+    pub magic_values: bool,
+    /// Count user-facing strings outside translation calls
+    pub untranslated_strings: bool,
+    /// Longer functions count as this many lines and are reported as
+    /// oversized, so a generated outlier cannot dominate the averages
+    pub max_function_lines: Option<usize>,
+    /// Which anonymous functions count as functions in every language;
+    /// `None` keeps each language's convention, counting those of
+    /// JavaScript, TypeScript and Dart but no closures or lambdas elsewhere
+    pub anonymous_functions: Option<AnonymousFunctions>,
+    /// Node kinds counted differently than the built-in queries say
+    pub kind_rules: KindRules,
+}
+
+impl Default for CountPolicy {
+    fn default() -> Self {
+        Self {
+            magic_values: true,
+            untranslated_strings: true,
+            max_function_lines: Some(DEFAULT_MAX_FUNCTION_LINES),
+            anonymous_functions: None,
+            kind_rules: KindRules::default(),
+        }
+    }
+}
+
+/// The effective settings for one directory after cascading.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DirectoryConfig {
+    /// Ignore patterns from this directory's config and every inherited one
+    pub ignore: Vec<String>,
+    /// Counting policy after applying all overrides
+    pub count: CountPolicy,
+    /// Extension table changes used for language detection
+    pub extensions: ExtensionOverrides,
+}
+
+impl DirectoryConfig {
+    /// Returns `true` if the path contains any of the configured ignore patterns.
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        self.ignore
+            .iter()
+            .any(|pattern| path_contains(path, pattern))
+    }
+
+    /// Layers a directory's own config file on top of the inherited settings.
+    ///
+    /// A `root = true` file starts over from `base`, the settings given
+    /// outside of config files.
+    fn cascade(&self, file: ConfigFile, base: &Self) -> Self {
+        let mut config = if file.root {
+            base.clone()
+        } else {
+            self.clone()
+        };
+
+        config.ignore.extend(file.ignore);
+        if let Some(magic_values) = file.count.magic_values {
+            config.count.magic_values = magic_values;
+        }
+        if let Some(untranslated_strings) = file.count.untranslated_strings {
+            config.count.untranslated_strings = untranslated_strings;
+        }
+        if let Some(max_function_lines) = file.count.max_function_lines {
+            config.count.max_function_lines =
+                (max_function_lines > 0).then_some(max_function_lines);
+        }
+        if let Some(anonymous_functions) = file.count.anonymous_functions {
+            config.count.anonymous_functions = Some(anonymous_functions);
+        }
+        for (name, edits) in file.extensions {
+            // Names were validated when the file was read
+            let Some(language) = SupportedLanguage::from_config_name(&name) else {
+                continue;
+            };
+            for extension in &edits.remove {
+                config.extensions.remove(extension, language);
+            }
+            for extension in &edits.add {
+                config.extensions.add(extension, language);
+            }
+        }
+        for (name, rules) in file.rules {
+            let Some(language) = SupportedLanguage::from_config_name(&name) else {
+                continue;
+            };
+            for (kind, declaration) in &rules {
+                // Rules were validated when the file was read
+                let _ = config.count.kind_rules.insert(language, kind, declaration);
+            }
+        }
+        config
+    }
+}
+
+/// Resolves and caches the effective configuration of directories below a root.
+///
+/// Config files above the analysis root are not consulted, so the same tree
+/// analyzed from different roots only picks up the files inside it.
+#[derive(Debug)]
+pub(crate) struct ConfigResolver {
+    root: PathBuf,
+    /// Settings below every config file, e.g. from the command line
+    base: DirectoryConfig,
+    resolved: HashMap<PathBuf, Arc<DirectoryConfig>>,
+}
+
+impl ConfigResolver {
+    /// Creates a resolver for the tree starting at `root`.
+    pub(crate) fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            base: DirectoryConfig::default(),
+            resolved: HashMap::new(),
+        }
+    }
+
+    /// Starts every directory from the given extension changes.
+    ///
+    /// Config files can still override them, as they override their parents.
+    pub(crate) fn with_extensions(mut self, extensions: ExtensionOverrides) -> Self {
+        self.base.extensions = extensions;
+        self
+    }
+
+    /// Starts every directory from the given anonymous function policy.
+    ///
+    /// Config files can still override it, as they override their parents.
+    pub(crate) fn with_anonymous_functions(
+        mut self,
+        anonymous_functions: Option<AnonymousFunctions>,
+    ) -> Self {
+        self.base.count.anonymous_functions = anonymous_functions;
+        self
+    }
+
+    /// Returns the effective configuration for a file inside the tree.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DirectoryConfig)` - Settings merged from the root down to the file's directory
+    /// * `Err(CodeStatsError::ConfigError)` if a config file on the way is invalid
+    pub(crate) fn for_file(&mut self, path: &Path) -> Result<Arc<DirectoryConfig>> {
+        match path.parent() {
+            Some(dir) if dir.starts_with(&self.root) => self.for_directory(dir),
+            _ => self.for_directory(&self.root.clone()),
+        }
+    }
+
+    /// Returns the effective configuration for a directory, resolving parents first.
+    fn for_directory(&mut self, dir: &Path) -> Result<Arc<DirectoryConfig>> {
+        if let Some(config) = self.resolved.get(dir) {
+            return Ok(Arc::clone(config));
+        }
+
+        let inherited = match dir.parent() {
+            Some(parent) if dir != self.root && parent.starts_with(&self.root) => {
+                self.for_directory(parent)?
+            }
+            _ => Arc::new(self.base.clone()),
+        };
+
+        let config = match read_config_file(dir)? {
+            Some(file) => Arc::new(inherited.cascade(file, &self.base)),
+            None => inherited,
+        };
+
+        self.resolved.insert(dir.to_path_buf(), Arc::clone(&config));
+        Ok(config)
+    }
+}
+
+/// Reads and parses the config file of a directory, if it has one.
+fn read_config_file(dir: &Path) -> Result<Option<ConfigFile>> {
+    let path = dir.join(CONFIG_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let text = fs::read_to_string(&path).map_err(|e| {
+        CodeStatsError::ConfigError(format!("Failed to read {}: {e}", path.display()))
+    })?;
+    let file: ConfigFile = toml::from_str(&text)
+        .map_err(|e| CodeStatsError::ConfigError(format!("{}: {e}", path.display())))?;
+
+    let languages = (file.extensions.keys().map(|name| ("extensions", name)))
+        .chain(file.rules.keys().map(|name| ("rules", name)));
+    for (section, name) in languages {
+        if SupportedLanguage::from_config_name(name).is_none() {
+            return Err(CodeStatsError::ConfigError(format!(
+                "{}: unknown language `{name}` in [{section}]",
+                path.display()
+            )));
+        }
+    }
+
+    let mut rules = KindRules::default();
+    for (name, kinds) in &file.rules {
+        let Some(language) = SupportedLanguage::from_config_name(name) else {
+            continue;
+        };
+        for (kind, declaration) in kinds {
+            rules
+                .insert(language, kind, declaration)
+                .map_err(|e| CodeStatsError::ConfigError(format!("{}: {e}", path.display())))?;
+        }
+    }
+
+    Ok(Some(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::DetectionStrategy;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_no_config_files_yields_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+
+        let config = resolver.for_file(&temp_dir.path().join("main.rs")).unwrap();
+        assert_eq!(*config, DirectoryConfig::default());
+    }
+
+    #[test]
+    fn test_nested_configs_cascade() {
+        let temp_dir = TempDir::new().unwrap();
+        let team = temp_dir.path().join("team");
+        fs::create_dir(&team).unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "ignore = [\"vendor\"]\n\n[count]\nmagic_values = false\n",
+        )
+        .unwrap();
+        fs::write(
+            team.join(CONFIG_FILE_NAME),
+            "ignore = [\"generated\"]\n\n[count]\nuntranslated_strings = false\n",
+        )
+        .unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+
+        let root_config = resolver.for_file(&temp_dir.path().join("main.rs")).unwrap();
+        assert_eq!(root_config.ignore, vec!["vendor"]);
+        assert!(!root_config.count.magic_values);
+        assert!(root_config.count.untranslated_strings);
+
+        let team_config = resolver.for_file(&team.join("lib.rs")).unwrap();
+        assert_eq!(team_config.ignore, vec!["vendor", "generated"]);
+        assert!(!team_config.count.magic_values);
+        assert!(!team_config.count.untranslated_strings);
+        assert!(team_config.is_ignored(&team.join("generated/api.rs")));
+    }
+
+    #[test]
+    fn test_max_function_lines_cascades_and_can_be_disabled() {
+        let temp_dir = TempDir::new().unwrap();
+        let generated = temp_dir.path().join("generated");
+        fs::create_dir(&generated).unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "[count]\nmax_function_lines = 300\n",
+        )
+        .unwrap();
+        fs::write(
+            generated.join(CONFIG_FILE_NAME),
+            "[count]\nmax_function_lines = 0\n",
+        )
+        .unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+
+        let root_config = resolver.for_file(&temp_dir.path().join("main.rs")).unwrap();
+        assert_eq!(root_config.count.max_function_lines, Some(300));
+        let generated_config = resolver.for_file(&generated.join("api.rs")).unwrap();
+        assert_eq!(generated_config.count.max_function_lines, None);
+        assert!(generated_config.count.magic_values);
+    }
+
+    #[test]
+    fn test_anonymous_functions_override_the_command_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let web = temp_dir.path().join("web");
+        fs::create_dir(&web).unwrap();
+        fs::write(
+            web.join(CONFIG_FILE_NAME),
+            "[count]\nanonymous_functions = \"top-level\"\n",
+        )
+        .unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path())
+            .with_anonymous_functions(Some(AnonymousFunctions::None));
+
+        let root_config = resolver.for_file(&temp_dir.path().join("main.rs")).unwrap();
+        assert_eq!(
+            root_config.count.anonymous_functions,
+            Some(AnonymousFunctions::None)
+        );
+        let web_config = resolver.for_file(&web.join("app.js")).unwrap();
+        assert_eq!(
+            web_config.count.anonymous_functions,
+            Some(AnonymousFunctions::TopLevel)
+        );
+    }
+
+    #[test]
+    fn test_kind_rules_cascade_per_kind() {
+        let temp_dir = TempDir::new().unwrap();
+        let macros = temp_dir.path().join("macros");
+        fs::create_dir(&macros).unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "[rules.rust]\ntrait_item = \"class\"\nenum_item = \"none\"\n",
+        )
+        .unwrap();
+        fs::write(
+            macros.join(CONFIG_FILE_NAME),
+            "[rules.Rust]\nenum_item = \"enum\"\nmacro_definition = \"function\"\n",
+        )
+        .unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+
+        let mut expected = KindRules::default();
+        let language = SupportedLanguage::Rust;
+        expected.insert(language, "trait_item", "class").unwrap();
+        expected.insert(language, "enum_item", "none").unwrap();
+        let root_config = resolver.for_file(&temp_dir.path().join("main.rs")).unwrap();
+        assert_eq!(root_config.count.kind_rules, expected);
+
+        expected.insert(language, "enum_item", "enum").unwrap();
+        expected
+            .insert(language, "macro_definition", "function")
+            .unwrap();
+        let macros_config = resolver.for_file(&macros.join("lib.rs")).unwrap();
+        assert_eq!(macros_config.count.kind_rules, expected);
+    }
+
+    #[test]
+    fn test_invalid_kind_rules_are_errors() {
+        let cases = [
+            (
+                "[rules.cobol]\nparagraph = \"function\"\n",
+                "unknown language `cobol` in [rules]",
+            ),
+            (
+                "[rules.rust]\ntrait_itme = \"class\"\n",
+                "unknown node kind `trait_itme` for Rust",
+            ),
+            (
+                "[rules.rust]\ntrait_item = \"struct\"\n",
+                "cannot count `trait_item` as `struct`",
+            ),
+        ];
+
+        for (text, message) in cases {
+            let temp_dir = TempDir::new().unwrap();
+            fs::write(temp_dir.path().join(CONFIG_FILE_NAME), text).unwrap();
+
+            let mut resolver = ConfigResolver::new(temp_dir.path());
+            let result = resolver.for_file(&temp_dir.path().join("main.rs"));
+
+            assert!(
+                matches!(&result, Err(CodeStatsError::ConfigError(msg)) if msg.contains(message)),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_root_config_stops_inheritance() {
+        let temp_dir = TempDir::new().unwrap();
+        let team = temp_dir.path().join("team");
+        fs::create_dir(&team).unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "ignore = [\"vendor\"]\n\n[count]\nmagic_values = false\n",
+        )
+        .unwrap();
+        fs::write(team.join(CONFIG_FILE_NAME), "root = true\n").unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+        let team_config = resolver.for_file(&team.join("lib.rs")).unwrap();
+
+        assert_eq!(*team_config, DirectoryConfig::default());
+    }
+
+    #[test]
+    fn test_extension_overrides_cascade() {
+        let temp_dir = TempDir::new().unwrap();
+        let legacy = temp_dir.path().join("legacy");
+        fs::create_dir(&legacy).unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "[extensions.python]\nadd = [\"cgi\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            legacy.join(CONFIG_FILE_NAME),
+            "[extensions.python]\nremove = [\"cgi\"]\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("form.cgi"), "print('a')\n").unwrap();
+        fs::write(legacy.join("form.cgi"), "print('a')\n").unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+        let root_file = temp_dir.path().join("form.cgi");
+        let legacy_file = legacy.join("form.cgi");
+
+        let root_config = resolver.for_file(&root_file).unwrap();
+        assert_eq!(
+            root_config
+                .extensions
+                .detect(&root_file, DetectionStrategy::Auto),
+            Some(SupportedLanguage::Python)
+        );
+        let legacy_config = resolver.for_file(&legacy_file).unwrap();
+        assert_eq!(
+            legacy_config
+                .extensions
+                .detect(&legacy_file, DetectionStrategy::Auto),
+            None
+        );
+    }
+
+    #[test]
+    fn test_base_extensions_apply_below_config_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let vendored = temp_dir.path().join("vendored");
+        fs::create_dir(&vendored).unwrap();
+        fs::write(
+            vendored.join(CONFIG_FILE_NAME),
+            "root = true\n[extensions.python]\nremove = [\"pyx\"]\n",
+        )
+        .unwrap();
+
+        let base = ExtensionOverrides::from_mappings(&["pyx=python".to_string()]).unwrap();
+        let mut resolver = ConfigResolver::new(temp_dir.path()).with_extensions(base);
+        let detect = |resolver: &mut ConfigResolver, path: &Path| {
+            resolver
+                .for_file(path)
+                .unwrap()
+                .extensions
+                .detect(path, DetectionStrategy::Extension)
+        };
+
+        let top = temp_dir.path().join("fast.pyx");
+        assert_eq!(detect(&mut resolver, &top), Some(SupportedLanguage::Python));
+        // `root = true` keeps the base mappings, but the file can still remove them
+        assert_eq!(detect(&mut resolver, &vendored.join("fast.pyx")), None);
+    }
+
+    #[test]
+    fn test_unknown_extension_language_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "[extensions.cobol]\nadd = [\"cbl\"]\n",
+        )
+        .unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+        let result = resolver.for_file(&temp_dir.path().join("main.rs"));
+
+        assert!(
+            matches!(result, Err(CodeStatsError::ConfigError(msg)) if msg.contains("unknown language `cobol`"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(CONFIG_FILE_NAME), "ignroe = []\n").unwrap();
+
+        let mut resolver = ConfigResolver::new(temp_dir.path());
+        let result = resolver.for_file(&temp_dir.path().join("main.rs"));
+
+        assert!(
+            matches!(result, Err(CodeStatsError::ConfigError(msg)) if msg.contains(CONFIG_FILE_NAME))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Batch content detection of languages ahead of directory analysis.
+//!
+//! Identifying files one by one runs Magika's model once per file. Before a
+//! directory is analyzed, its candidate files are instead split among worker
+//! threads, each of which extracts the files' features and runs the model on
+//! whole batches. The analysis then looks the results up instead of calling
+//! Magika again.
+
+use crate::interrupt::Interrupt;
+use crate::language::SupportedLanguage;
+use magika::{FeaturesOrRuled, Session};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Number of files identified by one run of the model.
+const BATCH_SIZE: usize = 256;
+
+/// The content-detected languages of a set of files.
+#[derive(Debug, Default)]
+pub(crate) struct ContentLanguages {
+    /// Language of each identified file; `None` when Magika identified
+    /// something other than a supported language
+    languages: HashMap<PathBuf, Option<SupportedLanguage>>,
+}
+
+impl ContentLanguages {
+    /// Identifies the languages of files by their content, in parallel batches.
+    ///
+    /// Files that cannot be read are left out, as is everything if Magika
+    /// cannot be initialized; such files are detected individually later.
+    /// Workers stop before their next batch once `interrupt` is raised.
+    pub(crate) fn identify(paths: &[PathBuf], interrupt: &Interrupt) -> Self {
+        let workers = std::thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(paths.len().div_ceil(BATCH_SIZE));
+        if workers == 0 {
+            return Self::default();
+        }
+
+        let chunk_size = paths.len().div_ceil(workers);
+        let languages = std::thread::scope(|scope| {
+            let handles: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || identify_chunk(chunk, interrupt)))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+
+        Self { languages }
+    }
+
+    /// Returns the result for a file, or `None` if it was not identified.
+    ///
+    /// The inner option is the detected language, `None` for content that is
+    /// not a supported language.
+    pub(crate) fn get(&self, path: &Path) -> Option<Option<SupportedLanguage>> {
+        self.languages.get(path).copied()
+    }
+}
+
+/// Identifies the files of one worker with its own Magika session.
+fn identify_chunk(
+    paths: &[PathBuf],
+    interrupt: &Interrupt,
+) -> Vec<(PathBuf, Option<SupportedLanguage>)> {
+    let Ok(mut session) = Session::new() else {
+        return Vec::new();
+    };
+
+    let mut identified = Vec::with_capacity(paths.len());
+    for batch in paths.chunks(BATCH_SIZE) {
+        if interrupt.is_raised() {
+            break;
+        }
+
+        // Small and empty files are identified by rules, the rest by the model
+        let mut inferred = Vec::new();
+        let mut features = Vec::new();
+        for path in batch {
+            let Ok(extracted) = File::open(path).and_then(|file| {
+                FeaturesOrRuled::extract_sync(file).map_err(std::io::Error::other)
+            }) else {
+                continue;
+            };
+            match extracted {
+                FeaturesOrRuled::Ruled(content_type) => identified.push((
+                    path.clone(),
+                    SupportedLanguage::from_magika_label(content_type.info().label),
+                )),
+                FeaturesOrRuled::Features(file_features) => {
+                    inferred.push(path);
+                    features.push(file_features);
+                }
+            }
+        }
+
+        let Ok(file_types) = session.identify_features_batch_sync(&features) else {
+            continue;
+        };
+        identified.extend(
+            inferred
+                .into_iter()
+                .zip(file_types)
+                .map(|(path, file_type)| {
+                    (
+                        path.clone(),
+                        SupportedLanguage::from_magika_label(file_type.info().label),
+                    )
+                }),
+        );
+    }
+
+    identified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identify_nothing() {
+        let languages = ContentLanguages::identify(&[], &Interrupt::default());
+        assert!(languages.languages.is_empty());
+    }
+
+    #[test]
+    fn test_identify_stops_when_interrupted() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {}\n".repeat(100)).unwrap();
+
+        let interrupt = Interrupt::default();
+        interrupt.raise();
+        let languages = ContentLanguages::identify(std::slice::from_ref(&path), &interrupt);
+        assert_eq!(languages.get(&path), None);
+    }
+}
@@ -0,0 +1,488 @@
+//! Comparison of a fresh analysis against a previously saved JSON report, or
+//! of two trees side by side.
+//!
+//! Files are matched by their path as recorded in the reports, so the
+//! baseline should be produced with the same path argument as the comparison
+//! (typically `.` from the repository root, in CI). Two trees compared with
+//! `compare` are matched by the paths relative to their roots.
+//!
+//! When both sides carry content hashes (reports saved with `--hash`), a file
+//! found only on one side is matched with an identical file found only on the
+//! other, and reported as renamed rather than as removed and added.
+
+use crate::error::{CodeStatsError, Result};
+use crate::language::SupportedLanguage;
+use crate::parser::CodeStats;
+use crate::stats::{DirectoryStats, FileStats, LanguageStats};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// A count before and after a change.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct Delta {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl Delta {
+    /// Creates a delta from the baseline and current values.
+    fn new(before: usize, after: usize) -> Self {
+        Self { before, after }
+    }
+
+    /// Returns the signed difference, `after - before`.
+    pub(crate) fn change(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+
+    /// Returns whether the value changed.
+    pub(crate) fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// Changes of the counts compared for languages and totals.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct CountDeltas {
+    pub files: Delta,
+    pub functions: Delta,
+    pub classes: Delta,
+    pub code_lines: Delta,
+}
+
+impl CountDeltas {
+    /// Returns whether any count changed.
+    pub(crate) fn changed(&self) -> bool {
+        self.files.changed()
+            || self.functions.changed()
+            || self.classes.changed()
+            || self.code_lines.changed()
+    }
+}
+
+/// How a file differs from the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FileStatus {
+    /// The file is new since the baseline
+    Added,
+    /// The file existed in the baseline only
+    Removed,
+    /// The file exists in both, with different counts
+    Modified,
+    /// The file moved from another path with its content unchanged
+    Renamed,
+}
+
+/// The changes of one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct FileDelta {
+    pub path: PathBuf,
+    pub status: FileStatus,
+    /// The baseline path of a renamed file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<PathBuf>,
+    pub functions: Delta,
+    pub classes: Delta,
+    pub code_lines: Delta,
+}
+
+/// The changes of one language.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct LanguageDelta {
+    pub language: SupportedLanguage,
+    #[serde(flatten)]
+    pub counts: CountDeltas,
+}
+
+/// Differences between a baseline report and the current tree, or between
+/// two compared trees.
+///
+/// Files whose counts did not change are left out, as are unchanged
+/// languages of [`diff_reports`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct ReportDiff {
+    /// Overall changes across all languages
+    pub total: CountDeltas,
+    /// Changed languages, sorted by name
+    pub languages: Vec<LanguageDelta>,
+    /// Added, removed, modified and renamed files, sorted by path
+    pub files: Vec<FileDelta>,
+}
+
+impl ReportDiff {
+    /// Returns whether anything changed since the baseline.
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.total.changed() && self.languages.is_empty() && self.files.is_empty()
+    }
+}
+
+/// Loads a report saved with `--format json`.
+///
+/// # Returns
+///
+/// * `Ok(DirectoryStats)` with the files and totals of the report
+/// * `Err(CodeStatsError::IoError)` if the file cannot be read
+/// * `Err(CodeStatsError::InvalidReport)` if it is not a directory JSON report,
+///   or one of a newer schema version
+pub(crate) fn load_baseline(path: &Path) -> Result<DirectoryStats> {
+    let file = File::open(path).map_err(|e| CodeStatsError::io(path, e))?;
+    DirectoryStats::from_reader(BufReader::new(file)).map_err(|e| match e {
+        CodeStatsError::InvalidReport(message) => {
+            CodeStatsError::InvalidReport(format!("{}: {message}", path.display()))
+        }
+        e => e,
+    })
+}
+
+/// Compares the current statistics against a baseline.
+///
+/// # Arguments
+///
+/// * `baseline` - The statistics of the saved report
+/// * `current` - The statistics of the fresh analysis
+///
+/// # Returns
+///
+/// The per-language, per-file and total changes
+pub(crate) fn diff_reports(baseline: &DirectoryStats, current: &DirectoryStats) -> ReportDiff {
+    ReportDiff {
+        total: count_deltas(
+            baseline.total_files(),
+            &baseline.total_stats,
+            current.total_files(),
+            &current.total_stats,
+        ),
+        languages: language_deltas(&baseline.total_by_language, &current.total_by_language)
+            .into_iter()
+            .filter(|delta| delta.counts.changed())
+            .collect(),
+        files: file_deltas(&baseline.files, &current.files),
+    }
+}
+
+/// Compares two trees side by side, such as a fork and its upstream.
+///
+/// Counts of `a` are the `before` side of each delta, counts of `b` the
+/// `after` side. Unlike [`diff_reports`], every language found in either
+/// tree is kept, so the two trees can be listed in one table.
+///
+/// # Arguments
+///
+/// * `a` - The statistics of the first tree, with paths relative to its root
+/// * `b` - The statistics of the second tree, with paths relative to its root
+pub(crate) fn compare_reports(a: &DirectoryStats, b: &DirectoryStats) -> ReportDiff {
+    ReportDiff {
+        total: count_deltas(
+            a.total_files(),
+            &a.total_stats,
+            b.total_files(),
+            &b.total_stats,
+        ),
+        languages: language_deltas(&a.total_by_language, &b.total_by_language),
+        files: file_deltas(&a.files, &b.files),
+    }
+}
+
+/// Compares the counts tracked by [`CountDeltas`] of two sets of statistics.
+fn count_deltas(
+    before_files: usize,
+    before: &CodeStats,
+    after_files: usize,
+    after: &CodeStats,
+) -> CountDeltas {
+    CountDeltas {
+        files: Delta::new(before_files, after_files),
+        functions: Delta::new(before.function_count, after.function_count),
+        classes: Delta::new(before.class_struct_count, after.class_struct_count),
+        code_lines: Delta::new(before.code_lines, after.code_lines),
+    }
+}
+
+/// Compares the per-language totals of every language on either side.
+fn language_deltas(
+    baseline: &BTreeMap<SupportedLanguage, LanguageStats>,
+    current: &BTreeMap<SupportedLanguage, LanguageStats>,
+) -> Vec<LanguageDelta> {
+    let empty = LanguageStats::default();
+    let mut languages: Vec<SupportedLanguage> =
+        baseline.keys().chain(current.keys()).copied().collect();
+    languages.sort_by_key(|language| language.name());
+    languages.dedup();
+
+    languages
+        .into_iter()
+        .map(|language| {
+            let before = baseline.get(&language).unwrap_or(&empty);
+            let after = current.get(&language).unwrap_or(&empty);
+            LanguageDelta {
+                language,
+                counts: CountDeltas {
+                    files: Delta::new(before.file_count, after.file_count),
+                    functions: Delta::new(before.function_count, after.function_count),
+                    classes: Delta::new(before.class_struct_count, after.class_struct_count),
+                    code_lines: Delta::new(before.code_lines, after.code_lines),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Compares files by path, keeping the added, removed and modified ones.
+///
+/// An added file with the same content hash as a removed one is kept as
+/// renamed from it instead; among several identical files, removed and added
+/// paths are paired in path order.
+fn file_deltas(baseline: &[FileStats], current: &[FileStats]) -> Vec<FileDelta> {
+    let mut paths: BTreeMap<&Path, (Option<&FileStats>, Option<&FileStats>)> = BTreeMap::new();
+    for file in baseline {
+        paths.entry(&file.path).or_default().0 = Some(file);
+    }
+    for file in current {
+        paths.entry(&file.path).or_default().1 = Some(file);
+    }
+
+    let mut removed: HashMap<&str, VecDeque<&FileStats>> = HashMap::new();
+    for sides in paths.values() {
+        if let (Some(file), None) = sides
+            && let Some(hash) = &file.sha256
+        {
+            removed.entry(hash).or_default().push_back(file);
+        }
+    }
+    // Current path of each renamed file, and the baseline file it was
+    let mut renames: HashMap<&Path, &FileStats> = HashMap::new();
+    for (path, sides) in &paths {
+        if let (None, Some(file)) = sides
+            && let Some(from) = file
+                .sha256
+                .as_deref()
+                .and_then(|hash| removed.get_mut(hash)?.pop_front())
+        {
+            renames.insert(path, from);
+        }
+    }
+    let moved: HashSet<&Path> = renames.values().map(|file| file.path.as_path()).collect();
+
+    let empty = CodeStats::default();
+    paths
+        .into_iter()
+        .filter(|(path, _)| !moved.contains(path))
+        .filter_map(|(path, (before, after))| {
+            let from = renames.get(path).copied();
+            let status = match (before, after) {
+                _ if from.is_some() => FileStatus::Renamed,
+                (None, _) => FileStatus::Added,
+                (_, None) => FileStatus::Removed,
+                _ => FileStatus::Modified,
+            };
+            let before = before.or(from).map_or(&empty, |file| &file.stats);
+            let after = after.map_or(&empty, |file| &file.stats);
+            let delta = FileDelta {
+                path: path.to_path_buf(),
+                status,
+                from: from.map(|file| file.path.clone()),
+                functions: Delta::new(before.function_count, after.function_count),
+                classes: Delta::new(before.class_struct_count, after.class_struct_count),
+                code_lines: Delta::new(before.code_lines, after.code_lines),
+            };
+
+            let changed =
+                delta.functions.changed() || delta.classes.changed() || delta.code_lines.changed();
+            (status != FileStatus::Modified || changed).then_some(delta)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds directory statistics from `(path, functions, classes, code lines)`.
+    fn directory(files: &[(&str, usize, usize, usize)]) -> DirectoryStats {
+        let mut stats = DirectoryStats::new();
+        for &(path, functions, classes, code_lines) in files {
+            let language = SupportedLanguage::from_file_extension(path).unwrap();
+            let code_stats = CodeStats {
+                function_count: functions,
+                class_struct_count: classes,
+                code_lines,
+                total_lines: code_lines,
+                ..CodeStats::default()
+            };
+            stats.add_file(FileStats::new(PathBuf::from(path), language, code_stats));
+        }
+        stats
+    }
+
+    #[test]
+    fn test_delta_change() {
+        assert_eq!(Delta::new(3, 5).change(), 2);
+        assert_eq!(Delta::new(5, 3).change(), -2);
+        assert!(!Delta::new(4, 4).changed());
+    }
+
+    #[test]
+    fn test_diff_reports() {
+        let baseline = directory(&[
+            ("src/lib.rs", 4, 1, 40),
+            ("src/old.rs", 2, 0, 10),
+            ("app.py", 1, 0, 5),
+        ]);
+        let current = directory(&[
+            ("src/lib.rs", 5, 1, 48),
+            ("src/new.rs", 1, 1, 12),
+            ("app.py", 1, 0, 5),
+        ]);
+
+        let diff = diff_reports(&baseline, &current);
+
+        assert_eq!(diff.total.files, Delta::new(3, 3));
+        assert_eq!(diff.total.functions, Delta::new(7, 7));
+        assert_eq!(diff.total.classes, Delta::new(1, 2));
+
+        assert_eq!(diff.languages.len(), 1);
+        assert_eq!(diff.languages[0].language, SupportedLanguage::Rust);
+        assert_eq!(diff.languages[0].counts.code_lines, Delta::new(50, 60));
+
+        let files: Vec<(&Path, FileStatus)> = diff
+            .files
+            .iter()
+            .map(|file| (file.path.as_path(), file.status))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                (Path::new("src/lib.rs"), FileStatus::Modified),
+                (Path::new("src/new.rs"), FileStatus::Added),
+                (Path::new("src/old.rs"), FileStatus::Removed),
+            ]
+        );
+        assert_eq!(diff.files[0].functions, Delta::new(4, 5));
+        assert_eq!(diff.files[2].functions, Delta::new(2, 0));
+    }
+
+    #[test]
+    fn test_file_deltas_detect_renames() {
+        let hashed = |files: &[(&str, &str)]| {
+            let mut stats = directory(
+                &files
+                    .iter()
+                    .map(|&(path, _)| (path, 1, 0, 3))
+                    .collect::<Vec<_>>(),
+            );
+            for (file, &(_, hash)) in stats.files.iter_mut().zip(files) {
+                file.sha256 = Some(hash.to_string());
+            }
+            stats
+        };
+        let baseline = hashed(&[
+            ("a.rs", "1"),
+            ("b.rs", "2"),
+            ("empty1.py", "0"),
+            ("util.rs", "3"),
+        ]);
+        let current = hashed(&[
+            ("c.rs", "4"),
+            ("empty2.py", "0"),
+            ("src/util.rs", "3"),
+            ("x.rs", "2"),
+        ]);
+
+        let diff = diff_reports(&baseline, &current);
+        let files: Vec<(&Path, FileStatus, Option<&Path>)> = diff
+            .files
+            .iter()
+            .map(|file| (file.path.as_path(), file.status, file.from.as_deref()))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                (Path::new("a.rs"), FileStatus::Removed, None),
+                (Path::new("c.rs"), FileStatus::Added, None),
+                (
+                    Path::new("empty2.py"),
+                    FileStatus::Renamed,
+                    Some(Path::new("empty1.py"))
+                ),
+                (
+                    Path::new("src/util.rs"),
+                    FileStatus::Renamed,
+                    Some(Path::new("util.rs"))
+                ),
+                (
+                    Path::new("x.rs"),
+                    FileStatus::Renamed,
+                    Some(Path::new("b.rs"))
+                ),
+            ]
+        );
+
+        // Without hashes, files are matched by path only
+        let unhashed = directory(&[("x.rs", 1, 0, 3)]);
+        let statuses: Vec<FileStatus> = diff_reports(&baseline, &unhashed)
+            .files
+            .iter()
+            .map(|file| file.status)
+            .collect();
+        assert!(!statuses.contains(&FileStatus::Renamed));
+    }
+
+    #[test]
+    fn test_compare_reports_keeps_unchanged_languages() {
+        let upstream = directory(&[("src/lib.rs", 4, 1, 40), ("app.py", 1, 0, 5)]);
+        let fork = directory(&[("src/lib.rs", 6, 1, 52), ("app.py", 1, 0, 5)]);
+
+        let comparison = compare_reports(&upstream, &fork);
+        let languages: Vec<_> = comparison
+            .languages
+            .iter()
+            .map(|delta| (delta.language, delta.counts.functions))
+            .collect();
+        assert_eq!(
+            languages,
+            [
+                (SupportedLanguage::Python, Delta::new(1, 1)),
+                (SupportedLanguage::Rust, Delta::new(4, 6)),
+            ]
+        );
+        assert_eq!(comparison.files.len(), 1);
+        assert_eq!(diff_reports(&upstream, &fork).languages.len(), 1);
+    }
+
+    #[test]
+    fn test_identical_reports_have_no_changes() {
+        let stats = directory(&[("src/lib.rs", 4, 1, 40)]);
+        assert!(diff_reports(&stats, &directory(&[("src/lib.rs", 4, 1, 40)])).is_empty());
+    }
+
+    #[test]
+    fn test_load_baseline_accepts_reports_from_older_versions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("baseline.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "files": [{"path": "a.rs", "language": "Rust",
+                           "stats": {"function_count": 2, "class_struct_count": 1}}],
+                "total_by_language": {"Rust": {"file_count": 1, "function_count": 2,
+                                               "class_struct_count": 1}},
+                "total_stats": {"function_count": 2, "class_struct_count": 1}
+            }"#,
+        )
+        .unwrap();
+
+        let baseline = load_baseline(&path).unwrap();
+        assert_eq!(baseline.files[0].stats.function_count, 2);
+        assert_eq!(baseline.total_stats.code_lines, 0);
+
+        std::fs::write(&path, r#"{"repos": []}"#).unwrap();
+        assert!(matches!(
+            load_baseline(&path),
+            Err(CodeStatsError::InvalidReport(_))
+        ));
+    }
+}
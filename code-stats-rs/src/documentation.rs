@@ -0,0 +1,147 @@
+//! Detection of documented functions.
+//!
+//! A function counts as documented when a doc comment immediately precedes
+//! it (`///` or `/** */` in Rust, Dart, Java, JavaScript and TypeScript, any
+//! comment in Go, as `go doc` reads them) or, in Python, when its body starts
+//! with a docstring.
+
+use crate::language::SupportedLanguage;
+use tree_sitter::Node;
+
+/// Node kinds that may sit between a doc comment and the item it documents.
+const ATTRIBUTE_KINDS: [&str; 3] = ["attribute_item", "decorator", "annotation"];
+
+/// Checks whether a function node has documentation.
+///
+/// # Arguments
+///
+/// * `function` - A node captured as `@function` by the built-in query
+/// * `source_code` - The source code the node was parsed from
+/// * `language` - The programming language of the source
+pub(crate) fn is_documented(
+    function: &Node,
+    source_code: &str,
+    language: &SupportedLanguage,
+) -> bool {
+    if *language == SupportedLanguage::Python {
+        return has_docstring(function);
+    }
+
+    // A doc comment precedes the whole declaration, e.g. `export function`
+    // or `const f = () => ...`, which starts on the same line as the function
+    let mut item = *function;
+    while let Some(parent) = item.parent()
+        && parent.parent().is_some()
+        && parent.start_position().row == item.start_position().row
+    {
+        item = parent;
+    }
+
+    let mut next_row = item.start_position().row;
+    let mut sibling = item.prev_sibling();
+    while let Some(node) = sibling {
+        if last_row(&node) + 1 < next_row {
+            return false;
+        }
+        if language.comment_kinds().contains(&node.kind()) {
+            return is_doc_comment(&source_code[node.byte_range()], language);
+        }
+        if !ATTRIBUTE_KINDS.contains(&node.kind()) {
+            return false;
+        }
+        next_row = node.start_position().row;
+        sibling = node.prev_sibling();
+    }
+    false
+}
+
+/// Returns the last line a node has text on; Rust line comments include
+/// their newline, so they end at the start of the next line.
+fn last_row(node: &Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+/// Checks whether a comment's text marks it as documentation.
+fn is_doc_comment(comment: &str, language: &SupportedLanguage) -> bool {
+    match language {
+        SupportedLanguage::Go => true,
+        SupportedLanguage::Rust | SupportedLanguage::Dart => {
+            (comment.starts_with("///") && !comment.starts_with("////"))
+                || (comment.starts_with("/**") && !comment.starts_with("/**/"))
+        }
+        _ => comment.starts_with("/**") && !comment.starts_with("/**/"),
+    }
+}
+
+/// Checks whether a Python function's body starts with a string literal.
+fn has_docstring(function: &Node) -> bool {
+    function
+        .child_by_field_name("body")
+        .and_then(|body| body.named_child(0))
+        .filter(|statement| statement.kind() == "expression_statement")
+        .and_then(|statement| statement.named_child(0))
+        .is_some_and(|expression| expression.kind() == "string")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::SupportedLanguage;
+    use crate::parser::count_source;
+
+    fn documented(language: SupportedLanguage, source: &str) -> (usize, usize) {
+        let stats = count_source(language, source);
+        (stats.documented_function_count, stats.function_count)
+    }
+
+    #[test]
+    fn test_documented_functions_per_language() {
+        let cases = [
+            (
+                SupportedLanguage::Rust,
+                "/// Adds.\n#[inline]\nfn add() {}\n\n// Not a doc comment\nfn sub() {}\n\
+                 /// Detached\n\nfn mul() {}\n",
+                (1, 3),
+            ),
+            (
+                SupportedLanguage::Go,
+                "package main\n\n// Serve serves.\nfunc Serve() {}\n\nfunc main() {}\n",
+                (1, 2),
+            ),
+            (
+                SupportedLanguage::Python,
+                "def a():\n    \"\"\"Docs.\"\"\"\n\n# comment\ndef b():\n    return 1\n",
+                (1, 2),
+            ),
+            (
+                SupportedLanguage::JavaScript,
+                "/** Docs. */\nexport function a() {}\n/** Docs. */\nconst b = () => 1;\n\
+                 // plain\nfunction c() {}\n",
+                (2, 3),
+            ),
+            (
+                SupportedLanguage::TypeScript,
+                "class A {\n  /** Docs. */\n  m(): void {}\n  n(): void {}\n}\n",
+                (1, 2),
+            ),
+            (
+                SupportedLanguage::Java,
+                "class A {\n  /** Docs. */\n  @Override\n  public void m() {}\n  void n() {}\n}\n",
+                (1, 2),
+            ),
+            (
+                SupportedLanguage::Dart,
+                "/// Docs.\nvoid a() {}\n\nvoid b() {}\n",
+                (1, 2),
+            ),
+        ];
+
+        for (language, source, expected) in cases {
+            assert_eq!(documented(language, source), expected, "{language:?}");
+        }
+    }
+}
@@ -1,5 +1,9 @@
 //! Error handling for the code statistics analyzer.
 
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Represents all possible errors that can occur during code analysis.
@@ -7,8 +11,13 @@ use thiserror::Error;
 /// This enum encapsulates various error conditions that may arise when analyzing
 /// source code files, from file system operations to tree-sitter parsing failures.
 /// Each variant provides specific context about the error that occurred.
+///
+/// Library users see it in the errors of [`pipeline`](crate::pipeline)
+/// sources, where an [`IoError`](CodeStatsError::IoError) keeps the
+/// [`io::ErrorKind`] of the failed operation.
 #[derive(Debug, Error)]
-pub(crate) enum CodeStatsError {
+#[non_exhaustive]
+pub enum CodeStatsError {
     /// Indicates that tree-sitter failed to parse a source code file.
     ///
     /// This error occurs when the tree-sitter parser encounters syntax errors
@@ -45,17 +54,204 @@ pub(crate) enum CodeStatsError {
 
     /// Indicates that an I/O operation failed during file processing.
     ///
-    /// This error wraps various file system related errors that can occur
-    /// when reading files, traversing directories, or accessing file metadata.
-    /// The error message provides details about the specific I/O failure.
+    /// This error wraps the [`io::Error`] of reading files, traversing
+    /// directories, or accessing file metadata, so its
+    /// [`kind`](io::Error::kind) tells e.g. a missing file from one that may
+    /// not be read. The path the operation was on is kept next to it.
     ///
     /// # Common causes
     /// - File or directory does not exist
     /// - Insufficient permissions to read files
     /// - Network issues when accessing remote files
     /// - Disk I/O errors or corrupted file systems
-    #[error("IO error: {0}")]
-    IoError(String),
+    #[error("IO error: {}{source}", display_path(.path))]
+    IoError {
+        /// The file or directory the operation was on, if known
+        path: Option<PathBuf>,
+        /// The underlying error
+        source: io::Error,
+    },
+
+    /// Indicates that a source file is not valid UTF-8 text.
+    ///
+    /// The message names the file and the position of the first invalid byte.
+    /// Directory analysis skips such files instead of reporting an error.
+    #[error("Not UTF-8 text: {0}")]
+    EncodingError(String),
+
+    /// Indicates that a `.code-stats.toml` configuration file is invalid.
+    ///
+    /// The message names the offending file and describes the problem.
+    /// Configuration errors abort the analysis rather than being skipped,
+    /// since silently ignoring them would change what gets counted.
+    #[error("Invalid configuration: {0}")]
+    ConfigError(String),
+
+    /// Indicates that a `git` command run on the user's behalf failed.
+    ///
+    /// The message contains the command and what git reported on stderr.
+    ///
+    /// # Common causes
+    /// - `git` is not installed or not on `PATH`
+    /// - The path is not a git repository, or a URL or revision does not exist
+    #[error("Git command failed: {0}")]
+    GitError(String),
+
+    /// Indicates that a saved report cannot be loaded for comparison.
+    ///
+    /// The message names the file and describes what did not match the
+    /// expected JSON report layout.
+    ///
+    /// # Common causes
+    /// - The report was written with `--format summary` or `--format detail`
+    /// - The report comes from the `multi` subcommand
+    #[error("Invalid report: {0}")]
+    InvalidReport(String),
+
+    /// Indicates that parsing a file was cancelled by an interrupt.
+    ///
+    /// The message names the file. Directory analysis marks its statistics
+    /// as partial instead of reporting the file as an error.
+    #[error("Interrupted while parsing {0}")]
+    Interrupted(String),
+
+    /// Indicates that a directory analysis was stopped by a traversal limit.
+    ///
+    /// The message names the limit, such as `--max-files`, and the value
+    /// that was exceeded. No partial report is produced.
+    ///
+    /// # Common causes
+    /// - Following a link into a large directory, such as `/` or a home directory
+    /// - Analyzing a directory that holds build output or dependencies
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+}
+
+impl CodeStatsError {
+    /// Creates a [`CodeStatsError::IoError`] about an operation on `path`.
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        CodeStatsError::IoError {
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    /// Returns the category of the error, as reported with skipped and errored files.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            CodeStatsError::ParseError(_) => ErrorKind::Parse,
+            CodeStatsError::LanguageSetupError => ErrorKind::LanguageSetup,
+            CodeStatsError::UnsupportedFileType(_) => ErrorKind::Unsupported,
+            CodeStatsError::IoError { .. } => ErrorKind::Io,
+            CodeStatsError::EncodingError(_) => ErrorKind::Encoding,
+            CodeStatsError::ConfigError(_) => ErrorKind::Config,
+            CodeStatsError::GitError(_) => ErrorKind::Git,
+            CodeStatsError::InvalidReport(_) => ErrorKind::InvalidReport,
+            CodeStatsError::Interrupted(_) => ErrorKind::Interrupted,
+            CodeStatsError::LimitExceeded(_) => ErrorKind::Limit,
+        }
+    }
+
+    /// Returns the file or directory an [`IoError`](CodeStatsError::IoError)
+    /// was about, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            CodeStatsError::IoError { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Formats the error like `Display` but without the path of an
+    /// [`IoError`](CodeStatsError::IoError), for messages that already name
+    /// the file.
+    pub(crate) fn message_without_path(&self) -> String {
+        match self {
+            CodeStatsError::IoError { source, .. } => format!("IO error: {source}"),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Wraps an I/O error that is not about a particular path, such as writing
+/// a report. Implemented by hand because `#[from]` cannot leave the path of
+/// [`CodeStatsError::IoError`] empty.
+impl From<io::Error> for CodeStatsError {
+    fn from(source: io::Error) -> Self {
+        CodeStatsError::IoError { path: None, source }
+    }
+}
+
+/// Formats the path of an [`CodeStatsError::IoError`] as a message prefix.
+fn display_path(path: &Option<PathBuf>) -> String {
+    path.as_deref()
+        .map(|path| format!("{}: ", path.display()))
+        .unwrap_or_default()
+}
+
+/// The category of a [`CodeStatsError`], one per variant, or why a file was
+/// skipped.
+///
+/// Reports carry the kind next to the message, so scripts can tell e.g.
+/// unreadable files from unparsable ones without matching message text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A [`CodeStatsError::ParseError`]
+    Parse,
+    /// A [`CodeStatsError::LanguageSetupError`]
+    LanguageSetup,
+    /// A [`CodeStatsError::UnsupportedFileType`]
+    Unsupported,
+    /// A [`CodeStatsError::IoError`]
+    Io,
+    /// A [`CodeStatsError::EncodingError`]
+    Encoding,
+    /// A [`CodeStatsError::ConfigError`]
+    Config,
+    /// A [`CodeStatsError::GitError`]
+    Git,
+    /// A [`CodeStatsError::InvalidReport`]
+    InvalidReport,
+    /// A [`CodeStatsError::Interrupted`]
+    Interrupted,
+    /// A [`CodeStatsError::LimitExceeded`]
+    Limit,
+    /// The file was already analyzed under another path
+    Duplicate,
+    /// A symbolic link leads back to one of its parent directories
+    SymlinkLoop,
+    /// The file is larger than the configured maximum
+    TooLarge,
+    /// The file is behind more symbolic links than the configured maximum
+    LinkDepth,
+    /// The file contains NUL bytes
+    Binary,
+    /// The file has lines only minified or generated code has
+    LongLines,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::Parse => "parse",
+            ErrorKind::LanguageSetup => "language_setup",
+            ErrorKind::Unsupported => "unsupported",
+            ErrorKind::Io => "io",
+            ErrorKind::Encoding => "encoding",
+            ErrorKind::Config => "config",
+            ErrorKind::Git => "git",
+            ErrorKind::InvalidReport => "invalid_report",
+            ErrorKind::Interrupted => "interrupted",
+            ErrorKind::Limit => "limit",
+            ErrorKind::Duplicate => "duplicate",
+            ErrorKind::SymlinkLoop => "symlink_loop",
+            ErrorKind::TooLarge => "too_large",
+            ErrorKind::LinkDepth => "link_depth",
+            ErrorKind::Binary => "binary",
+            ErrorKind::LongLines => "long_lines",
+        })
+    }
 }
 
 /// A type alias for `Result<T, CodeStatsError>`.
@@ -80,8 +276,53 @@ mod tests {
         let err = CodeStatsError::UnsupportedFileType("test.md".to_string());
         assert_eq!(err.to_string(), "Unsupported file type: test.md");
 
-        let err = CodeStatsError::IoError("File not found".to_string());
-        assert_eq!(err.to_string(), "IO error: File not found");
+        let err = CodeStatsError::io(
+            "a.rs",
+            io::Error::new(io::ErrorKind::NotFound, "File not found"),
+        );
+        assert_eq!(err.to_string(), "IO error: a.rs: File not found");
+        assert_eq!(err.message_without_path(), "IO error: File not found");
+
+        let err = CodeStatsError::from(io::Error::other("broken pipe"));
+        assert_eq!(err.to_string(), "IO error: broken pipe");
+
+        let err = CodeStatsError::EncodingError("a.rs: invalid byte".to_string());
+        assert_eq!(err.to_string(), "Not UTF-8 text: a.rs: invalid byte");
+
+        let err = CodeStatsError::ConfigError(".code-stats.toml: unknown field".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration: .code-stats.toml: unknown field"
+        );
+
+        let err = CodeStatsError::GitError("git fetch: repository not found".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Git command failed: git fetch: repository not found"
+        );
+
+        let err = CodeStatsError::InvalidReport("old.json: expected value".to_string());
+        assert_eq!(err.to_string(), "Invalid report: old.json: expected value");
+
+        let err = CodeStatsError::Interrupted("big.rs".to_string());
+        assert_eq!(err.to_string(), "Interrupted while parsing big.rs");
+
+        let err = CodeStatsError::LimitExceeded("more than 10 files (--max-files)".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Limit exceeded: more than 10 files (--max-files)"
+        );
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let err = CodeStatsError::from(io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert_eq!(err.kind().to_string(), "io");
+        assert_eq!(
+            serde_json::to_string(&CodeStatsError::LanguageSetupError.kind()).unwrap(),
+            r#""language_setup""#
+        );
     }
 
     #[test]
@@ -120,7 +361,16 @@ mod tests {
             CodeStatsError::ParseError("file.rs".to_string()),
             CodeStatsError::LanguageSetupError,
             CodeStatsError::UnsupportedFileType("file.doc".to_string()),
-            CodeStatsError::IoError("Permission denied".to_string()),
+            CodeStatsError::io(
+                "secret.rs",
+                io::Error::from(io::ErrorKind::PermissionDenied),
+            ),
+            CodeStatsError::EncodingError("latin1.py".to_string()),
+            CodeStatsError::ConfigError("bad.toml".to_string()),
+            CodeStatsError::GitError("git clone".to_string()),
+            CodeStatsError::InvalidReport("old.json".to_string()),
+            CodeStatsError::Interrupted("big.rs".to_string()),
+            CodeStatsError::LimitExceeded("--max-files".to_string()),
         ];
 
         for error in errors {
@@ -129,10 +379,18 @@ mod tests {
                     assert!(!file.is_empty());
                 }
                 CodeStatsError::LanguageSetupError => {}
-                CodeStatsError::UnsupportedFileType(file) => {
+                CodeStatsError::UnsupportedFileType(file) | CodeStatsError::Interrupted(file) => {
                     assert!(!file.is_empty());
                 }
-                CodeStatsError::IoError(msg) => {
+                CodeStatsError::IoError { path, source } => {
+                    assert_eq!(path, Some(PathBuf::from("secret.rs")));
+                    assert_eq!(source.kind(), io::ErrorKind::PermissionDenied);
+                }
+                CodeStatsError::EncodingError(msg)
+                | CodeStatsError::ConfigError(msg)
+                | CodeStatsError::GitError(msg)
+                | CodeStatsError::InvalidReport(msg)
+                | CodeStatsError::LimitExceeded(msg) => {
                     assert!(!msg.is_empty());
                 }
             }
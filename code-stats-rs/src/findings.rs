@@ -0,0 +1,260 @@
+//! Severity-tiered findings reported alongside the statistics.
+//!
+//! Every issue noticed during a run (a file that could not be read, a file
+//! that was skipped, a broken rule) becomes a [`Finding`] instead of being
+//! dropped or printed ad hoc, so all formatters report the same list and the
+//! command line can turn findings into a failing exit code.
+
+use crate::error::{CodeStatsError, ErrorKind};
+use crate::language::SupportedLanguage;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Extensions of widely used programming languages that are not supported.
+const UNSUPPORTED_CODE_EXTENSIONS: [&str; 24] = [
+    "c", "h", "cc", "cpp", "cxx", "hpp", "cs", "rb", "php", "kt", "kts", "swift", "scala", "lua",
+    "sh", "bash", "pl", "ex", "exs", "erl", "hs", "clj", "zig", "jl",
+];
+
+/// How serious a finding is, ordered from least to most severe.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Informational, e.g. a file skipped because its type is unsupported
+    Info,
+    /// The results may be incomplete or a rule was broken
+    Warning,
+    /// Something could not be analyzed at all
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A single issue found during analysis.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Finding {
+    /// How serious the issue is
+    pub severity: Severity,
+    /// The file or directory the issue concerns, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Human-readable description of the issue
+    pub message: String,
+    /// Category of a skipped or errored file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ErrorKind>,
+}
+
+impl Finding {
+    /// Creates a finding about a path.
+    pub(crate) fn new(
+        severity: Severity,
+        path: Option<PathBuf>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            path,
+            message: message.into(),
+            kind: None,
+        }
+    }
+
+    /// Creates an error finding about a path that could not be analyzed.
+    ///
+    /// The message leaves out the path of the error when the finding names
+    /// one, so it is not printed twice.
+    pub(crate) fn error(path: Option<PathBuf>, error: &CodeStatsError) -> Self {
+        let message = match path {
+            Some(_) => error.message_without_path(),
+            None => error.to_string(),
+        };
+        Self {
+            kind: Some(error.kind()),
+            ..Self::new(Severity::Error, path, message)
+        }
+    }
+
+    /// Creates an info finding about a file that was deliberately not analyzed.
+    pub(crate) fn skipped(path: PathBuf, kind: ErrorKind, reason: impl fmt::Display) -> Self {
+        Self {
+            kind: Some(kind),
+            ..Self::new(Severity::Info, Some(path), format!("skipped: {reason}"))
+        }
+    }
+
+    /// Creates a warning about an unsupported file whose extension belongs
+    /// to a programming language, such as `.c` or `.rb`, for `--warnings`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Finding)` - For source code of an unsupported language
+    /// * `None` - For other files, e.g. documentation or data
+    pub(crate) fn unsupported_code(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        UNSUPPORTED_CODE_EXTENSIONS
+            .iter()
+            .any(|known| extension.eq_ignore_ascii_case(known))
+            .then(|| {
+                Self::skipped(
+                    path.to_path_buf(),
+                    ErrorKind::Unsupported,
+                    format_args!(".{extension} source code, which is not supported"),
+                )
+                .warning_if(true)
+            })
+    }
+
+    /// Creates a warning that content detection did not recognize a file,
+    /// which was analyzed as `language` by its extension instead.
+    pub(crate) fn detection_fallback(path: &Path, language: SupportedLanguage) -> Self {
+        Self::new(
+            Severity::Warning,
+            Some(path.to_path_buf()),
+            format!(
+                "content not recognized, analyzed as {} by its extension",
+                language.name()
+            ),
+        )
+    }
+
+    /// Raises the finding to a warning if `warn` is set, e.g. for skipped
+    /// files that `--warnings` asks to hear about.
+    pub(crate) fn warning_if(self, warn: bool) -> Self {
+        Self {
+            severity: if warn {
+                Severity::Warning
+            } else {
+                self.severity
+            },
+            ..self
+        }
+    }
+
+    /// Returns whether the finding reports a file that was skipped or could
+    /// not be analyzed, as opposed to a problem of the run as a whole.
+    pub(crate) fn is_file_problem(&self) -> bool {
+        self.path.is_some() && self.kind.is_some()
+    }
+}
+
+impl fmt::Display for Finding {
+    /// Formats the finding as `severity: path: message`, omitting a missing path.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}: {}", self.severity, path.display(), self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+/// Counts the findings of exactly the given severity.
+pub(crate) fn count_severity(findings: &[Finding], severity: Severity) -> usize {
+    findings
+        .iter()
+        .filter(|finding| finding.severity == severity)
+        .count()
+}
+
+/// Counts the findings at or above the given severity.
+pub(crate) fn count_at_least(findings: &[Finding], severity: Severity) -> usize {
+    findings
+        .iter()
+        .filter(|finding| finding.severity >= severity)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn findings() -> Vec<Finding> {
+        vec![
+            Finding::new(Severity::Info, Some(PathBuf::from("README.md")), "skipped"),
+            Finding::new(Severity::Error, Some(PathBuf::from("a.rs")), "unreadable"),
+            Finding::new(Severity::Warning, None, "partial"),
+        ]
+    }
+
+    #[test]
+    fn test_severity_ordering() {
+        assert!(Severity::Info < Severity::Warning);
+        assert!(Severity::Warning < Severity::Error);
+    }
+
+    #[test]
+    fn test_counts_by_severity() {
+        let findings = findings();
+
+        assert_eq!(count_severity(&findings, Severity::Warning), 1);
+        assert_eq!(count_at_least(&findings, Severity::Info), 3);
+        assert_eq!(count_at_least(&findings, Severity::Warning), 2);
+        assert_eq!(count_at_least(&findings, Severity::Error), 1);
+    }
+
+    #[test]
+    fn test_finding_display() {
+        let findings = findings();
+
+        assert_eq!(findings[1].to_string(), "error: a.rs: unreadable");
+        assert_eq!(findings[2].to_string(), "warning: partial");
+    }
+
+    #[test]
+    fn test_finding_serialization() {
+        let json = serde_json::to_string(&findings()[2]).unwrap();
+        assert_eq!(json, r#"{"severity":"warning","message":"partial"}"#);
+    }
+
+    #[test]
+    fn test_file_problems() {
+        let error = CodeStatsError::ParseError("a.rs".to_string());
+        let errored = Finding::error(Some(PathBuf::from("a.rs")), &error);
+        assert_eq!(errored.severity, Severity::Error);
+        assert_eq!(errored.kind, Some(ErrorKind::Parse));
+        assert!(errored.is_file_problem());
+
+        let skipped = Finding::skipped(
+            PathBuf::from("README.md"),
+            ErrorKind::Unsupported,
+            "unsupported file type",
+        );
+        assert_eq!(skipped.severity, Severity::Info);
+        assert!(skipped.is_file_problem());
+        assert!(
+            serde_json::to_string(&skipped)
+                .unwrap()
+                .contains(r#""kind":"unsupported""#)
+        );
+
+        assert!(!Finding::error(None, &error).is_file_problem());
+        assert!(!findings()[2].is_file_problem());
+    }
+
+    #[test]
+    fn test_unsupported_code() {
+        let warning = Finding::unsupported_code(Path::new("native/zlib.C")).unwrap();
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.kind, Some(ErrorKind::Unsupported));
+        assert_eq!(
+            warning.message,
+            "skipped: .C source code, which is not supported"
+        );
+
+        for path in ["README.md", "data.json", "Makefile"] {
+            assert_eq!(Finding::unsupported_code(Path::new(path)), None, "{path}");
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Synthetic repository generation for benchmarks and stress tests.
+//!
+//! Generated files are deterministic: the same parameters always produce the
+//! same tree, so performance numbers can be reproduced without access to a
+//! private codebase. Every file has a known number of functions and
+//! classes/structs, which makes the expected analysis totals easy to check.
+
+use crate::language::SupportedLanguage;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Every language fixtures are generated for, in output order.
+const LANGUAGES: [SupportedLanguage; 7] = [
+    SupportedLanguage::Rust,
+    SupportedLanguage::Go,
+    SupportedLanguage::Python,
+    SupportedLanguage::JavaScript,
+    SupportedLanguage::TypeScript,
+    SupportedLanguage::Java,
+    SupportedLanguage::Dart,
+];
+
+/// Maximum number of files placed in a single generated directory.
+const FILES_PER_DIRECTORY: usize = 100;
+
+/// Shape of the generated repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FixtureSpec {
+    /// Number of files generated for each language
+    pub files_per_language: usize,
+    /// Number of functions in every file
+    pub functions_per_file: usize,
+    /// Number of classes/structs in every file
+    ///
+    /// Java methods must live in a class, so Java files always contain at
+    /// least one class even when this is zero.
+    pub classes_per_file: usize,
+}
+
+/// Writes a synthetic repository below `root`.
+///
+/// Files are laid out as `<language>/<batch>/file_<n>.<ext>`, with at most
+/// [`FILES_PER_DIRECTORY`] files per batch directory so traversal also
+/// exercises nested directories.
+///
+/// # Returns
+///
+/// * `Ok(Vec<PathBuf>)` - The paths of all generated files
+/// * `Err(io::Error)` if a directory or file cannot be written
+pub(crate) fn generate_fixtures(root: &Path, spec: &FixtureSpec) -> io::Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+
+    for language in LANGUAGES {
+        let language_dir = root.join(language.name().to_lowercase());
+
+        for index in 0..spec.files_per_language {
+            let dir = language_dir.join(format!("batch_{}", index / FILES_PER_DIRECTORY));
+            fs::create_dir_all(&dir)?;
+
+            let path = dir.join(format!("file_{index}.{}", extension(language)));
+            fs::write(&path, render_file(language, index, spec))?;
+            written.push(path);
+        }
+    }
+
+    Ok(written)
+}
+
+/// Returns the file extension used for generated files of a language.
+fn extension(language: SupportedLanguage) -> &'static str {
+    match language {
+        SupportedLanguage::Rust => "rs",
+        SupportedLanguage::Go => "go",
+        SupportedLanguage::Python => "py",
+        SupportedLanguage::JavaScript => "js",
+        SupportedLanguage::TypeScript => "ts",
+        SupportedLanguage::Java => "java",
+        SupportedLanguage::Dart => "dart",
+    }
+}
+
+/// Renders the source of one generated file.
+fn render_file(language: SupportedLanguage, index: usize, spec: &FixtureSpec) -> String {
+    let classes = 0..spec.classes_per_file;
+    let functions = 0..spec.functions_per_file;
+    let mut source = String::new();
+
+    match language {
+        SupportedLanguage::Rust => {
+            for c in classes {
+                source.push_str(&format!(
+                    "pub struct Type{c} {{\n    pub value: i64,\n}}\n\n"
+                ));
+            }
+            for f in functions {
+                source.push_str(&format!(
+                    "pub fn function_{f}(x: i64) -> i64 {{\n    x + {f}\n}}\n\n"
+                ));
+            }
+        }
+        SupportedLanguage::Go => {
+            source.push_str("package fixtures\n\n");
+            for c in classes {
+                source.push_str(&format!("type Type{c} struct {{\n\tValue int\n}}\n\n"));
+            }
+            for f in functions {
+                source.push_str(&format!(
+                    "func Function{f}(x int) int {{\n\treturn x + {f}\n}}\n\n"
+                ));
+            }
+        }
+        SupportedLanguage::Python => {
+            for c in classes {
+                source.push_str(&format!("class Type{c}:\n    value = 0\n\n\n"));
+            }
+            for f in functions {
+                source.push_str(&format!("def function_{f}(x):\n    return x + {f}\n\n\n"));
+            }
+        }
+        SupportedLanguage::JavaScript => {
+            for c in classes {
+                source.push_str(&format!("class Type{c} {{}}\n\n"));
+            }
+            for f in functions {
+                source.push_str(&format!(
+                    "function function_{f}(x) {{\n  return x + {f};\n}}\n\n"
+                ));
+            }
+        }
+        SupportedLanguage::TypeScript => {
+            for c in classes {
+                source.push_str(&format!("class Type{c} {{}}\n\n"));
+            }
+            for f in functions {
+                source.push_str(&format!(
+                    "function function_{f}(x: number): number {{\n  return x + {f};\n}}\n\n"
+                ));
+            }
+        }
+        SupportedLanguage::Java => {
+            source.push_str(&format!("public class File{index} {{\n"));
+            for f in functions {
+                source.push_str(&format!(
+                    "    public static int function{f}(int x) {{\n        return x + {f};\n    }}\n"
+                ));
+            }
+            source.push_str("}\n");
+            for c in classes.skip(1) {
+                source.push_str(&format!("\nclass Type{c} {{}}\n"));
+            }
+        }
+        SupportedLanguage::Dart => {
+            for c in classes {
+                source.push_str(&format!("class Type{c} {{\n  int value = 0;\n}}\n\n"));
+            }
+            for f in functions {
+                source.push_str(&format!(
+                    "int function{f}(int x) {{\n  return x + {f};\n}}\n\n"
+                ));
+            }
+        }
+    }
+
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::CodeAnalyzer;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generated_counts_match_spec() {
+        let temp_dir = TempDir::new().unwrap();
+        let spec = FixtureSpec {
+            files_per_language: 3,
+            functions_per_file: 4,
+            classes_per_file: 2,
+        };
+
+        let written = generate_fixtures(temp_dir.path(), &spec).unwrap();
+        assert_eq!(written.len(), 21);
+
+        let stats = CodeAnalyzer::new()
+            .analyze_directory(temp_dir.path(), 100, false, &[])
+            .unwrap();
+
+        assert_eq!(stats.total_files(), 21);
+        for language in LANGUAGES {
+            let lang_stats = &stats.total_by_language[&language];
+            assert_eq!(lang_stats.file_count, 3, "{language:?}");
+            assert_eq!(lang_stats.function_count, 12, "{language:?}");
+            assert_eq!(lang_stats.class_struct_count, 6, "{language:?}");
+        }
+    }
+
+    #[test]
+    fn test_files_are_split_into_batches() {
+        let temp_dir = TempDir::new().unwrap();
+        let spec = FixtureSpec {
+            files_per_language: FILES_PER_DIRECTORY + 1,
+            functions_per_file: 1,
+            classes_per_file: 0,
+        };
+
+        generate_fixtures(temp_dir.path(), &spec).unwrap();
+
+        assert!(temp_dir.path().join("go/batch_0/file_99.go").is_file());
+        assert!(temp_dir.path().join("go/batch_1/file_100.go").is_file());
+    }
+}
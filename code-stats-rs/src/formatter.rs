@@ -1,7 +1,398 @@
 //! Output formatting for code statistics in Summary, Detail, and JSON formats.
 
-use crate::cli::OutputFormat;
-use crate::stats::{DirectoryStats, FileStats};
+use crate::audit::{LicenseAudit, ViolationReason};
+use crate::cli::{HistoryFormat, OutputFormat, SortKey, SortOrder};
+use crate::diff::{CountDeltas, Delta, FileDelta, FileStatus, ReportDiff};
+use crate::error::ErrorKind;
+use crate::findings::{Finding, Severity, count_severity};
+use crate::functions::MANY_PARAMETERS;
+use crate::history::HistoryPoint;
+use crate::hotspots::Hotspot;
+use crate::language::{LanguageCoverage, SupportedLanguage};
+use crate::metrics::ExtendedMetrics;
+use crate::multi::MultiReport;
+use crate::origin::Origin;
+use crate::ownership::DirectoryOwnership;
+use crate::parser::{CodeStats, UNRELIABLE_PARSE_ERROR_RATIO};
+use crate::profile::Profile;
+use crate::rust_modules::ModuleStats;
+use crate::stats::{DirectoryStats, FileStats, LanguageStats, ReportMeta};
+use crate::symbols::SymbolMatch;
+use crate::test_code::{CodeGroup, TestSplit};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Maximum number of files listed in the magic value worst-offender list.
+const MAGIC_VALUE_OFFENDERS: usize = 10;
+
+/// Optional report sections requested on the command line.
+///
+/// Sections are only added to the text formats; JSON output always carries
+/// the underlying data.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct FormatOptions {
+    /// Append magic value totals and the worst-offender files
+    pub magic_values: bool,
+    /// Append per-file counts of potentially untranslated strings
+    pub i18n: bool,
+    /// Restrict per-file listings (detail and JSON) to one page of files
+    pub pagination: Option<Pagination>,
+    /// Append the given number of files with the most functions
+    pub top: Option<usize>,
+    /// Append the function names defined in at least this many places
+    pub duplicates: Option<usize>,
+    /// Order of the language summary and the per-file detail listing
+    pub sorting: Sorting,
+    /// How counts are written in the summary and detail views
+    pub numbers: NumberStyle,
+    /// List every file in a table of its own (the table format's `--detail`)
+    pub file_table: bool,
+    /// Width and colors of the table format's tables
+    pub tables: TableStyle,
+    /// Write JSON reports on a single line instead of pretty-printing them
+    pub compact: bool,
+}
+
+/// How counts are written in text reports.
+///
+/// By default, large numbers get thousands separators and the summary's
+/// columns widen to fit them. The plain style (`--no-align`) writes digits
+/// only and separates columns by single spaces, for scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NumberStyle {
+    /// Separator between groups of three digits, `None` for plain digits
+    separator: Option<char>,
+    /// Whether columns are padded to line up
+    align: bool,
+}
+
+impl Default for NumberStyle {
+    fn default() -> Self {
+        Self {
+            separator: Some(','),
+            align: true,
+        }
+    }
+}
+
+impl NumberStyle {
+    /// Aligned columns with the thousands separator of the user's locale,
+    /// read from `LC_ALL`, `LC_NUMERIC` or `LANG`.
+    pub(crate) fn from_locale() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self {
+            separator: Some(thousands_separator(&locale)),
+            align: true,
+        }
+    }
+
+    /// Plain digits and unpadded columns.
+    pub(crate) fn plain() -> Self {
+        Self {
+            separator: None,
+            align: false,
+        }
+    }
+
+    /// Writes a count, e.g. `12,345`.
+    pub(crate) fn number(&self, count: usize) -> String {
+        let digits = count.to_string();
+        let Some(separator) = self.separator else {
+            return digits;
+        };
+
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// Returns the width of a column: at least `minimum` and wide enough for
+    /// every cell, or 0 when columns are not aligned.
+    fn width<'a>(&self, minimum: usize, cells: impl IntoIterator<Item = &'a String>) -> usize {
+        if !self.align {
+            return 0;
+        }
+        cells
+            .into_iter()
+            .map(|cell| cell.chars().count())
+            .fold(minimum, usize::max)
+    }
+}
+
+/// Picks the thousands separator of a locale such as `de_DE.UTF-8`.
+///
+/// Locales that write `1.234` or `1 234` get a dot or a space; all others,
+/// including `C` and `POSIX`, a comma.
+fn thousands_separator(locale: &str) -> char {
+    let language = locale.split(['_', '.', '@', '-']).next().unwrap_or("");
+    match language {
+        "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "sl" | "hr" => '.',
+        "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu"
+        | "bg" | "et" | "lt" | "lv" => ' ',
+        _ => ',',
+    }
+}
+
+/// How the box-drawn tables of the table format are laid out and colored.
+///
+/// By default tables wrap their cells to the terminal's width and have bold
+/// headers when printed to a terminal, and neither when piped.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TableStyle {
+    /// Width to wrap cells to, instead of the terminal's (`--width`)
+    pub width: Option<u16>,
+    /// Whether headers are bold; `None` decides by whether stdout is a terminal
+    pub color: Option<bool>,
+}
+
+impl TableStyle {
+    /// Tables of the given width, colored as the `NO_COLOR`, `CLICOLOR` and
+    /// `CLICOLOR_FORCE` environment variables ask.
+    pub(crate) fn from_env(width: Option<u16>) -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self {
+            width,
+            color: color_choice(
+                var("NO_COLOR").as_deref(),
+                var("CLICOLOR").as_deref(),
+                var("CLICOLOR_FORCE").as_deref(),
+            ),
+        }
+    }
+}
+
+/// Decides on colors from the values of `NO_COLOR`, `CLICOLOR` and
+/// `CLICOLOR_FORCE`.
+///
+/// A non-empty `NO_COLOR` turns colors off, and takes precedence over a
+/// `CLICOLOR_FORCE` other than `0`, which turns them on even when piped.
+/// `CLICOLOR=0` turns them off.
+///
+/// # Returns
+///
+/// * `Some(bool)` - Whether to color
+/// * `None` - Color only when printing to a terminal
+fn color_choice(
+    no_color: Option<&str>,
+    clicolor: Option<&str>,
+    clicolor_force: Option<&str>,
+) -> Option<bool> {
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        Some(false)
+    } else if clicolor_force.is_some_and(|value| !value.is_empty() && value != "0") {
+        Some(true)
+    } else if clicolor == Some("0") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// The order of the language summary and the per-file detail listing.
+///
+/// Ties are always broken by name or path, ascending, so the output is
+/// deterministic. The default is alphabetical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Sorting {
+    key: SortKey,
+    order: SortOrder,
+}
+
+impl Default for Sorting {
+    fn default() -> Self {
+        Self::new(SortKey::Name, None)
+    }
+}
+
+impl Sorting {
+    /// Creates a sorting by `key`; without an order, names are sorted
+    /// ascending and counts descending, so the largest come first.
+    pub(crate) fn new(key: SortKey, order: Option<SortOrder>) -> Self {
+        let order = order.unwrap_or(match key {
+            SortKey::Name => SortOrder::Asc,
+            SortKey::Files | SortKey::Functions | SortKey::Classes => SortOrder::Desc,
+        });
+        Self { key, order }
+    }
+
+    /// Sorts the languages of a summary.
+    fn sort_languages(&self, languages: &mut [(&SupportedLanguage, &LanguageStats)]) {
+        languages.sort_by(|(a, a_stats), (b, b_stats)| {
+            let count = |stats: &LanguageStats| match self.key {
+                SortKey::Name => None,
+                SortKey::Files => Some(stats.file_count),
+                SortKey::Functions => Some(stats.function_count),
+                SortKey::Classes => Some(stats.class_struct_count),
+            };
+            self.compare(count(a_stats), count(b_stats), a.name().cmp(b.name()))
+        });
+    }
+
+    /// Sorts the files of a detail listing.
+    fn sort_files(&self, files: &mut [FileStats]) {
+        files.sort_by(|a, b| {
+            let count = |file: &FileStats| match self.key {
+                // Every file is a single file, so they are listed by path
+                SortKey::Name | SortKey::Files => None,
+                SortKey::Functions => Some(file.stats.function_count),
+                SortKey::Classes => Some(file.stats.class_struct_count),
+            };
+            self.compare(count(a), count(b), a.path.cmp(&b.path))
+        });
+    }
+
+    /// Compares two entries by their counts in this sorting's order, or by
+    /// name when sorting by name. Equal counts are ordered by name, ascending.
+    fn compare(&self, a: Option<usize>, b: Option<usize>, by_name: Ordering) -> Ordering {
+        let ordering = match (a, b) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => by_name,
+        };
+        match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+        .then(by_name)
+    }
+}
+
+/// A window into the sorted list of analyzed files.
+///
+/// Pages are numbered from 1. Totals are always computed over every file;
+/// only the per-file listing is cut down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Pagination {
+    /// Maximum number of files per page
+    pub limit: usize,
+    /// The 1-based page to show
+    pub page: usize,
+}
+
+impl Pagination {
+    /// Returns the items that fall on this page (empty if the page is past the end).
+    fn apply<'a, T>(&self, items: &'a [T]) -> &'a [T] {
+        let start = self.limit.saturating_mul(self.page - 1).min(items.len());
+        let end = start.saturating_add(self.limit).min(items.len());
+        &items[start..end]
+    }
+
+    /// Describes which part of the file list is shown, e.g. `Showing files 1-100 of 5000 (page 1 of 50)`.
+    fn describe(&self, total: usize) -> String {
+        let pages = total.div_ceil(self.limit).max(1);
+        let start = self.limit.saturating_mul(self.page - 1);
+        if start >= total {
+            return format!(
+                "No files on page {} ({total} files, {pages} pages)",
+                self.page
+            );
+        }
+
+        let end = start.saturating_add(self.limit).min(total);
+        format!(
+            "Showing files {}-{end} of {total} (page {} of {pages})",
+            start + 1,
+            self.page
+        )
+    }
+}
+
+/// Totals of a finished run, reported on stderr by [`format_status_line`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct RunStatus {
+    /// Number of files analyzed successfully
+    pub files: usize,
+    /// Total number of functions found
+    pub functions: usize,
+    /// Total number of classes/structs found
+    pub classes: usize,
+    /// Number of files or paths that failed to analyze
+    pub errors: usize,
+}
+
+/// Formats the single `key=value` status line written to stderr after every run.
+///
+/// The keys and their order are stable so scripts can parse the line with a
+/// simple split. The line does not depend on the selected output format.
+///
+/// # Output Format
+///
+/// ```text
+/// code-stats: files=123 functions=456 classes=78 errors=2 duration_ms=789
+/// ```
+pub(crate) fn format_status_line(status: &RunStatus, duration: Duration) -> String {
+    format!(
+        "code-stats: files={} functions={} classes={} errors={} duration_ms={}",
+        status.files,
+        status.functions,
+        status.classes,
+        status.errors,
+        duration.as_millis()
+    )
+}
+
+/// Formats the phase totals and slowest files of a `--profile` run.
+///
+/// Times are wall-clock milliseconds. Batched content detection runs once
+/// ahead of all files and is only listed when it took place.
+///
+/// # Output Format
+///
+/// ```text
+/// Profile of 1234 files:
+///   Content detection (batched):  210.4 ms
+///   Detection:                      1.2 ms
+///   Reading:                       50.3 ms
+///   Parsing:                      900.1 ms
+///
+/// Slowest files:
+///   390.2 ms  src/schema_generated.rs (detection 0.0 ms, reading 10.1 ms, parsing 380.1 ms)
+/// ```
+pub(crate) fn format_profile(profile: &Profile, slowest: usize) -> String {
+    let millis = |duration: Duration| format!("{:.1} ms", duration.as_secs_f64() * 1000.0);
+    let totals = profile.totals();
+
+    let mut output = format!("Profile of {} files:", profile.files.len());
+    let mut phases = vec![
+        ("Detection:", totals.detection),
+        ("Reading:", totals.reading),
+        ("Parsing:", totals.parsing),
+    ];
+    if !profile.batch_detection.is_zero() {
+        phases.insert(0, ("Content detection (batched):", profile.batch_detection));
+    }
+    for (phase, duration) in phases {
+        output.push_str(&format!("\n  {phase:28} {:>10}", millis(duration)));
+    }
+
+    let files = profile.slowest(slowest);
+    if !files.is_empty() {
+        output.push_str("\n\nSlowest files:");
+        for file in files {
+            output.push_str(&format!(
+                "\n  {:>10}  {} (detection {}, reading {}, parsing {})",
+                millis(file.times.total()),
+                file.path.display(),
+                millis(file.times.detection),
+                millis(file.times.reading),
+                millis(file.times.parsing)
+            ));
+        }
+    }
+    output
+}
 
 /// Formats directory statistics according to the specified output format.
 ///
@@ -12,21 +403,69 @@ use crate::stats::{DirectoryStats, FileStats};
 ///
 /// * `stats` - Directory statistics containing aggregated results from all analyzed files
 /// * `format` - The desired output format (Summary, Detail, or JSON)
-/// * `_show_detail` - Currently unused parameter (reserved for future functionality)
+/// * `options` - Optional report sections to include
 ///
 /// # Returns
 ///
-/// A formatted string ready for display or further processing
+/// A formatted string ready for display or further processing. Text reports
+/// start with a line describing the analysis if it is known (see
+/// [`format_report_header`]), then, if the analysis was interrupted, a line
+/// marking them as partial.
 pub(crate) fn format_output(
     stats: &DirectoryStats,
     format: OutputFormat,
-    _show_detail: bool,
+    options: &FormatOptions,
 ) -> String {
-    match format {
-        OutputFormat::Summary => format_summary(stats),
-        OutputFormat::Detail => format_detail(stats),
-        OutputFormat::Json => format_json(stats),
+    let mut output = match format {
+        OutputFormat::Summary => format_summary(stats, options),
+        OutputFormat::Detail => format_detail(stats, options),
+        OutputFormat::Table => format_table(stats, options),
+        OutputFormat::Json => return format_json(stats, options.pagination, options.compact),
+        OutputFormat::Yaml => return format_yaml(stats, options.pagination),
+        OutputFormat::Toml => return format_toml(stats, options.pagination),
+        OutputFormat::ClocJson => return format_cloc_json(stats, options.compact),
+    };
+
+    // The detail view lists skipped and errored files in a section of their
+    // own; the summary only lists problems
+    if format == OutputFormat::Detail {
+        output.push_str(&format_file_problems(stats));
+    }
+    output.push_str(&format_findings(&stats.findings, |finding| {
+        finding.severity >= Severity::Warning
+            && !(format == OutputFormat::Detail && finding.is_file_problem())
+    }));
+
+    if stats.interrupted {
+        output.insert_str(
+            0,
+            &format!(
+                "Partial report: analysis was interrupted after {} files\n\n",
+                stats.total_files()
+            ),
+        );
+    }
+    if let Some(meta) = &stats.meta {
+        output.insert_str(0, &format_report_header(meta));
+    }
+
+    if let Some(count) = options.top {
+        output.push_str(&format_top_files(stats, count));
+    }
+
+    if let Some(min_places) = options.duplicates {
+        output.push_str(&format_duplicate_names(stats, min_places));
+    }
+
+    if options.magic_values {
+        output.push_str(&format_magic_values(stats));
     }
+
+    if options.i18n {
+        output.push_str(&format_untranslated_strings(stats));
+    }
+
+    output
 }
 
 /// Formats statistics for a single file analysis.
@@ -37,31 +476,91 @@ pub(crate) fn format_output(
 /// # Arguments
 ///
 /// * `file_stats` - Statistics for a single file including path, language, and counts
+/// * `options` - Optional report sections to include
 ///
 /// # Returns
 ///
 /// A formatted string containing the file path, detected language, and code statistics
-pub(crate) fn format_single_file(file_stats: &FileStats) -> String {
-    format!(
+pub(crate) fn format_single_file(file_stats: &FileStats, options: &FormatOptions) -> String {
+    let mut output = format!(
         "Analyzing file: {} (Language: {:?})\n\
          Code Statistics:\n\
          Functions: {}\n\
-         Classes/Structs: {}",
+         Classes/Structs: {}\n\
+         Interfaces/Traits: {}\n\
+         Enums: {}\n\
+         Type aliases: {}\n\
+         Type definitions: {}\n\
+         Modules: {}\n\
+         Lines: {}",
         file_stats.path.display(),
         file_stats.language,
         file_stats.stats.function_count,
-        file_stats.stats.class_struct_count
-    )
+        file_stats.stats.class_struct_count,
+        file_stats.stats.interface_count,
+        file_stats.stats.enum_count,
+        file_stats.stats.type_alias_count,
+        file_stats.stats.type_definition_count(),
+        file_stats.stats.module_count,
+        format_line_counts(&file_stats.stats, options.numbers)
+    );
+    output.push_str(&format_hash(file_stats, ""));
+    if file_stats.stats.impl_count + file_stats.stats.trait_impl_count > 0 {
+        output.push_str(&format!(
+            "\nImpl blocks: {} inherent, {} trait impls ({} methods)",
+            file_stats.stats.impl_count,
+            file_stats.stats.trait_impl_count,
+            file_stats.stats.impl_method_count
+        ));
+    }
+    if file_stats.stats.generic_count > 0 {
+        output.push_str(&format!(
+            "\nGenerics: {} declarations, {} type parameters",
+            file_stats.stats.generic_count, file_stats.stats.type_parameter_count
+        ));
+    }
+    let stats = &file_stats.stats;
+    if stats.async_function_count + stats.decorated_function_count + stats.lambda_count > 0 {
+        output.push_str(&format!(
+            "\nFunction kinds: {} async, {} decorated functions; {} lambdas",
+            stats.async_function_count, stats.decorated_function_count, stats.lambda_count
+        ));
+    }
+    output.push_str(&format_components(&file_stats.stats, ""));
+    output.push_str(&format_function_length(&file_stats.stats));
+    output.push_str(&format_parse_errors(&file_stats.stats, ""));
+    output.push_str(&format_query_counts(&file_stats.stats, ""));
+
+    if options.magic_values {
+        output.push_str(&format!(
+            "\nMagic strings: {}\nMagic numbers: {}",
+            file_stats.stats.magic_string_count, file_stats.stats.magic_number_count
+        ));
+    }
+
+    if options.i18n {
+        output.push_str(&format!(
+            "\nUntranslated strings: {}",
+            file_stats.stats.untranslated_string_count
+        ));
+    }
+
+    output.push_str(&format_secrets(&[file_stats]));
+    output.push_str(&format_oversized_functions(&[file_stats]));
+    output.push_str(&format_suspicious(&[file_stats], &[]));
+
+    output
 }
 
 /// Formats directory statistics as a summary view.
 ///
 /// Creates a concise overview showing aggregated statistics by programming language,
-/// followed by overall totals. Languages are sorted alphabetically for consistent output.
+/// followed by overall totals. Languages are listed in the order of `sorting`.
 ///
 /// # Arguments
 ///
 /// * `stats` - Directory statistics containing per-language aggregations
+/// * `sorting` - Order of the languages, alphabetical by default
 ///
 /// # Returns
 ///
@@ -71,348 +570,3837 @@ pub(crate) fn format_single_file(file_stats: &FileStats) -> String {
 ///
 /// ```text
 /// Language Summary:
-///   Go:           15 functions,    3 structs/classes in 5 files
-///   Python:       8 functions,    2 structs/classes in 3 files
-///   Rust:         20 functions,   12 structs/classes in 8 files
+///   Go:            15 functions,    3 structs/classes in 5 files, 2 modules  ( 31.2% of files,  34.9% of functions)
+///   Python:         8 functions,    2 structs/classes in 3 files, 1 modules  ( 18.8% of files,  18.6% of functions)
+///   Rust:          20 functions,   12 structs/classes in 8 files, 4 modules  ( 50.0% of files,  46.5% of functions)
+///
+/// Line Summary:
+///   Go:            900 code,   120 comments,    80 blank
+///   Python:        400 code,    60 comments,    40 blank
+///   Rust:         1500 code,   300 comments,   200 blank
+///
+/// Size Summary:
+///   Go:           1100 lines,   38500 bytes  ( 30.6% of lines,  32.1% of bytes)
+///   Python:        500 lines,   15000 bytes  ( 13.9% of lines,  12.5% of bytes)
+///   Rust:         2000 lines,   66500 bytes  ( 55.6% of lines,  55.4% of bytes)
+///
+/// Documentation Coverage:
+///   Go:             9 of   15 functions documented ( 60.0%)
+///   Python:         2 of    8 functions documented ( 25.0%)
+///   Rust:          18 of   20 functions documented ( 90.0%)
+///
+/// API Surface:
+///   Rust:            6 public,   14 private functions;    5 public,    7 private types
+///
+/// Type Definitions:
+///   Go:              2 interfaces,    0 enums,    3 type aliases (5 type definitions)
+///   Rust:            4 interfaces,    3 enums,    1 type aliases (8 type definitions)
+///
+/// Impl Blocks:
+///   Rust:            9 inherent,    4 trait impls;   16 methods, 1.3 per struct
 ///
-/// Total: 43 functions, 17 structs/classes in 16 files
+/// Generics:
+///   Go:              3 generic functions and types,    4 type parameters
+///
+/// Function Kinds:
+///   Java:            0 async,    0 decorated functions;   12 lambdas
+///   Python:          2 async,    5 decorated functions;    7 lambdas
+///
+/// React Components:
+///   TypeScript:     24 components in 18 files
+///
+/// Total: 43 functions, 17 structs/classes in 16 files, 7 modules
+/// Lines: 3600 (2800 code, 480 comments, 320 blank)
+/// Average function length: 24.5 lines
 /// ```
-fn format_summary(stats: &DirectoryStats) -> String {
+fn format_summary(stats: &DirectoryStats, options: &FormatOptions) -> String {
+    let numbers = options.numbers;
     let mut output = String::new();
 
     output.push_str("Language Summary:\n");
 
-    // Sort languages alphabetically for consistent output ordering
     let mut languages: Vec<_> = stats.total_by_language.iter().collect();
-    languages.sort_by_key(|(lang, _)| format!("{lang:?}"));
+    options.sorting.sort_languages(&mut languages);
+
+    // Columns widen to fit the largest count, but never shrink below the
+    // widths that line up typical repositories
+    let names: Vec<_> = languages
+        .iter()
+        .map(|(language, _)| format!("{}:", language.name()))
+        .collect();
+    let column = |count: fn(&LanguageStats) -> usize| -> Vec<String> {
+        languages
+            .iter()
+            .map(|(_, lang_stats)| numbers.number(count(lang_stats)))
+            .collect()
+    };
+    let percent = |share: fn(&LanguageStats, &DirectoryStats) -> f64| -> Vec<String> {
+        languages
+            .iter()
+            .map(|(_, lang_stats)| format!("{:.1}", share(lang_stats, stats)))
+            .collect()
+    };
+    let functions = column(|s| s.function_count);
+    let classes = column(|s| s.class_struct_count);
+    let files = column(|s| s.file_count);
+    let modules = column(|s| s.module_count);
+    let file_shares = percent(LanguageStats::file_share);
+    let function_shares = percent(LanguageStats::function_share);
+    let name_width = numbers.width(12, &names);
+    let count_width = numbers.width(4, functions.iter().chain(&classes));
+    let files_width = numbers.width(1, &files);
+    let modules_width = numbers.width(1, &modules);
+    let share_width = numbers.width(5, file_shares.iter().chain(&function_shares));
+
+    for i in 0..languages.len() {
+        output.push_str(&format!(
+            "  {:name_width$} {:>count_width$} functions, {:>count_width$} structs/classes in \
+             {:>files_width$} files, {:>modules_width$} modules  \
+             ({:>share_width$}% of files, {:>share_width$}% of functions)\n",
+            names[i],
+            functions[i],
+            classes[i],
+            files[i],
+            modules[i],
+            file_shares[i],
+            function_shares[i]
+        ));
+    }
 
-    // Format each language's statistics with aligned columns
-    for (language, lang_stats) in languages {
+    output.push_str("\nLine Summary:\n");
+    let code = column(|s| s.code_lines);
+    let comments = column(|s| s.comment_lines);
+    let blank = column(|s| s.blank_lines);
+    let line_width = numbers.width(5, code.iter().chain(&comments).chain(&blank));
+    for i in 0..languages.len() {
         output.push_str(&format!(
-            "  {:12} {:4} functions, {:4} structs/classes in {} files\n",
-            format!("{:?}:", language),
-            lang_stats.function_count,
-            lang_stats.class_struct_count,
-            lang_stats.file_count
+            "  {:name_width$} {:>line_width$} code, {:>line_width$} comments, {:>line_width$} blank\n",
+            names[i], code[i], comments[i], blank[i]
         ));
     }
 
+    output.push_str("\nSize Summary:\n");
+    let lines = column(|s| s.total_lines);
+    let bytes = column(|s| s.byte_count);
+    let line_shares = percent(LanguageStats::line_share);
+    let byte_shares = percent(LanguageStats::byte_share);
+    let lines_width = numbers.width(5, &lines);
+    let bytes_width = numbers.width(7, &bytes);
+    let size_share_width = numbers.width(5, line_shares.iter().chain(&byte_shares));
+    for i in 0..languages.len() {
+        output.push_str(&format!(
+            "  {:name_width$} {:>lines_width$} lines, {:>bytes_width$} bytes  \
+             ({:>size_share_width$}% of lines, {:>size_share_width$}% of bytes)\n",
+            names[i], lines[i], bytes[i], line_shares[i], byte_shares[i]
+        ));
+    }
+
+    output.push_str("\nDocumentation Coverage:\n");
+    let documented = column(|s| s.documented_function_count);
+    let documented_width = numbers.width(4, &documented);
+    for i in 0..languages.len() {
+        output.push_str(&format!(
+            "  {:name_width$} {:>documented_width$} of {:>count_width$} functions documented ({:>5.1}%)\n",
+            names[i],
+            documented[i],
+            functions[i],
+            languages[i].1.documentation_coverage()
+        ));
+    }
+
+    // Only languages with visibility have an API surface
+    let with_visibility: Vec<_> = (0..languages.len())
+        .filter(|&i| {
+            let lang_stats = languages[i].1;
+            lang_stats.public_function_count
+                + lang_stats.private_function_count
+                + lang_stats.public_type_count
+                + lang_stats.private_type_count
+                > 0
+        })
+        .collect();
+    if !with_visibility.is_empty() {
+        output.push_str("\nAPI Surface:\n");
+        let public_functions = column(|s| s.public_function_count);
+        let private_functions = column(|s| s.private_function_count);
+        let public_types = column(|s| s.public_type_count);
+        let private_types = column(|s| s.private_type_count);
+        let surface_width = numbers.width(
+            4,
+            public_functions
+                .iter()
+                .chain(&private_functions)
+                .chain(&public_types)
+                .chain(&private_types),
+        );
+        for i in with_visibility {
+            output.push_str(&format!(
+                "  {:name_width$} {:>surface_width$} public, {:>surface_width$} private functions; \
+                 {:>surface_width$} public, {:>surface_width$} private types\n",
+                names[i],
+                public_functions[i],
+                private_functions[i],
+                public_types[i],
+                private_types[i]
+            ));
+        }
+    }
+
+    let with_types: Vec<_> = (0..languages.len())
+        .filter(|&i| languages[i].1.type_definition_count() > 0)
+        .collect();
+    if !with_types.is_empty() {
+        output.push_str("\nType Definitions:\n");
+        let interfaces = column(|s| s.interface_count);
+        let enums = column(|s| s.enum_count);
+        let type_aliases = column(|s| s.type_alias_count);
+        let type_width = numbers.width(4, interfaces.iter().chain(&enums).chain(&type_aliases));
+        for i in with_types {
+            output.push_str(&format!(
+                "  {:name_width$} {:>type_width$} interfaces, {:>type_width$} enums, \
+                 {:>type_width$} type aliases ({} type definitions)\n",
+                names[i],
+                interfaces[i],
+                enums[i],
+                type_aliases[i],
+                numbers.number(languages[i].1.type_definition_count())
+            ));
+        }
+    }
+
+    // Only Rust has impl blocks
+    let with_impls: Vec<_> = (0..languages.len())
+        .filter(|&i| languages[i].1.impl_count + languages[i].1.trait_impl_count > 0)
+        .collect();
+    if !with_impls.is_empty() {
+        output.push_str("\nImpl Blocks:\n");
+        let inherent = column(|s| s.impl_count);
+        let trait_impls = column(|s| s.trait_impl_count);
+        let methods = column(|s| s.impl_method_count);
+        let impl_width = numbers.width(4, inherent.iter().chain(&trait_impls).chain(&methods));
+        for i in with_impls {
+            let per_struct = languages[i]
+                .1
+                .methods_per_struct()
+                .map_or(String::new(), |average| {
+                    format!(", {average:.1} per struct")
+                });
+            output.push_str(&format!(
+                "  {:name_width$} {:>impl_width$} inherent, {:>impl_width$} trait impls; \
+                 {:>impl_width$} methods{per_struct}\n",
+                names[i], inherent[i], trait_impls[i], methods[i]
+            ));
+        }
+    }
+
+    // Only Go declarations with type parameters are counted as generic
+    let with_generics: Vec<_> = (0..languages.len())
+        .filter(|&i| languages[i].1.generic_count > 0)
+        .collect();
+    if !with_generics.is_empty() {
+        output.push_str("\nGenerics:\n");
+        let generics = column(|s| s.generic_count);
+        let type_parameters = column(|s| s.type_parameter_count);
+        let generic_width = numbers.width(4, generics.iter().chain(&type_parameters));
+        for i in with_generics {
+            output.push_str(&format!(
+                "  {:name_width$} {:>generic_width$} generic functions and types, \
+                 {:>generic_width$} type parameters\n",
+                names[i], generics[i], type_parameters[i]
+            ));
+        }
+    }
+
+    // Only Python and Java functions are told apart by kind
+    let with_kinds: Vec<_> = (0..languages.len())
+        .filter(|&i| {
+            let lang_stats = languages[i].1;
+            lang_stats.async_function_count
+                + lang_stats.decorated_function_count
+                + lang_stats.lambda_count
+                > 0
+        })
+        .collect();
+    if !with_kinds.is_empty() {
+        output.push_str("\nFunction Kinds:\n");
+        let async_functions = column(|s| s.async_function_count);
+        let decorated = column(|s| s.decorated_function_count);
+        let lambdas = column(|s| s.lambda_count);
+        let kind_width = numbers.width(4, async_functions.iter().chain(&decorated).chain(&lambdas));
+        for i in with_kinds {
+            output.push_str(&format!(
+                "  {:name_width$} {:>kind_width$} async, {:>kind_width$} decorated functions; \
+                 {:>kind_width$} lambdas\n",
+                names[i], async_functions[i], decorated[i], lambdas[i]
+            ));
+        }
+    }
+
+    // Only JavaScript and TypeScript have React components
+    let with_components: Vec<_> = (0..languages.len())
+        .filter(|&i| languages[i].1.component_count > 0)
+        .collect();
+    if !with_components.is_empty() {
+        output.push_str("\nReact Components:\n");
+        let components = column(|s| s.component_count);
+        let component_width = numbers.width(4, &components);
+        for i in with_components {
+            output.push_str(&format!(
+                "  {:name_width$} {:>component_width$} components in {} files\n",
+                names[i], components[i], files[i]
+            ));
+        }
+    }
+
     // Add grand totals at the end
     output.push_str(&format!(
-        "\nTotal: {} functions, {} structs/classes in {} files",
-        stats.total_stats.function_count,
-        stats.total_stats.class_struct_count,
-        stats.total_files()
+        "\nTotal: {} functions, {} structs/classes in {} files, {} modules\nLines: {}",
+        numbers.number(stats.total_stats.function_count),
+        numbers.number(stats.total_stats.class_struct_count),
+        numbers.number(stats.total_files()),
+        numbers.number(stats.total_stats.module_count),
+        format_line_counts(&stats.total_stats, numbers)
     ));
+    output.push_str(&format_total_sections(stats, numbers));
 
     output
 }
 
-/// Formats directory statistics as a detailed view.
-///
-/// Provides comprehensive output showing individual file statistics followed by
-/// the summary view. Files are sorted by path for deterministic output ordering.
-///
-/// # Arguments
-///
-/// * `stats` - Directory statistics containing individual file results
-///
-/// # Returns
+/// Formats the sections that follow the totals of a summary: the average
+/// function length, query matches, secrets, oversized functions and
+/// suspicious files.
+fn format_total_sections(stats: &DirectoryStats, numbers: NumberStyle) -> String {
+    let mut output = format_function_length(&stats.total_stats);
+    output.push_str(&format_function_shape(&stats.total_stats, ""));
+    output.push_str(&format_query_counts(&stats.total_stats, ""));
+
+    let mut files: Vec<_> = stats.files.iter().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    output.push_str(&format_secrets(&files));
+    output.push_str(&format_oversized_functions(&files));
+
+    let mut excluded: Vec<_> = stats.excluded_files.iter().collect();
+    excluded.sort_by(|a, b| a.path.cmp(&b.path));
+    output.push_str(&format_suspicious(&files, &excluded));
+    output.push_str(&format_origins(stats, numbers));
+    output.push_str(&format_test_split(stats, numbers));
+
+    output
+}
+
+/// Formats directory statistics as box-drawn tables.
 ///
-/// A formatted string with per-file details and summary statistics
+/// The language table lists every metric per language, followed by a total
+/// row. With `options.file_table`, a table of the (paginated) files comes
+/// first. Rows follow `options.sorting`; the sections after the totals
+/// are the same as in the summary view.
 ///
 /// # Output Format
 ///
 /// ```text
-/// src/main.rs (Rust):
-///   Functions: 3
-///   Structs/Classes: 2
-///
-/// src/lib.rs (Rust):
-///   Functions: 5
-///   Structs/Classes: 1
-///
-/// Language Summary:
-/// [... summary content ...]
+/// ┌──────────┬───────┬─────────┬───────────┬─────────────┬─────────────────┬─ ...
+/// │ Language ┆ Files ┆ Files % ┆ Functions ┆ Functions % ┆ Structs/Classes ┆  ...
+/// ╞══════════╪═══════╪═════════╪═══════════╪═════════════╪═════════════════╪═ ...
+/// │ Python   ┆     1 ┆    33.3 ┆         2 ┆        20.0 ┆               1 ┆  ...
+/// ├╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌┼╌ ...
+/// │ Rust     ┆     2 ┆    66.7 ┆         8 ┆        80.0 ┆               3 ┆  ...
+/// ├╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌┼╌ ...
+/// │ Total    ┆     3 ┆   100.0 ┆        10 ┆       100.0 ┆               4 ┆  ...
+/// └──────────┴───────┴─────────┴───────────┴─────────────┴─────────────────┴─ ...
 /// ```
-fn format_detail(stats: &DirectoryStats) -> String {
+fn format_table(stats: &DirectoryStats, options: &FormatOptions) -> String {
+    let numbers = options.numbers;
     let mut output = String::new();
 
-    // Sort files by path for consistent, deterministic output
-    let mut files = stats.files.clone();
-    files.sort_by(|a, b| a.path.cmp(&b.path));
+    if options.file_table {
+        let mut files = stats.files.clone();
+        options.sorting.sort_files(&mut files);
+        let page = match options.pagination {
+            Some(pagination) => pagination.apply(&files),
+            None => &files,
+        };
 
-    // Display individual file statistics
-    for file in &files {
-        output.push_str(&format!(
-            "{} ({:?}):\n  Functions: {}\n  Structs/Classes: {}\n\n",
-            file.path.display(),
-            file.language,
-            file.stats.function_count,
-            file.stats.class_struct_count
-        ));
+        let mut table = new_table(
+            &[
+                "Path",
+                "Language",
+                "Functions",
+                "Structs/Classes",
+                "Interfaces/Traits",
+                "Enums",
+                "Type aliases",
+                "Modules",
+                "Code",
+                "Comments",
+                "Blank",
+            ],
+            2,
+            options.tables,
+        );
+        for file in page {
+            let counts = &file.stats;
+            table.add_row(vec![
+                file.path.display().to_string(),
+                file.language.name().to_string(),
+                numbers.number(counts.function_count),
+                numbers.number(counts.class_struct_count),
+                numbers.number(counts.interface_count),
+                numbers.number(counts.enum_count),
+                numbers.number(counts.type_alias_count),
+                numbers.number(counts.module_count),
+                numbers.number(counts.code_lines),
+                numbers.number(counts.comment_lines),
+                numbers.number(counts.blank_lines),
+            ]);
+        }
+        // Path, language, functions and code lines are kept
+        output.push_str(&render_table(table, &[6, 7, 5, 4, 10, 9, 3]));
+        output.push('\n');
+        if let Some(pagination) = options.pagination {
+            output.push_str(&pagination.describe(files.len()));
+            output.push('\n');
+        }
+        output.push('\n');
     }
 
-    // Append summary statistics at the end
-    output.push_str(&format_summary(stats));
+    let mut languages: Vec<_> = stats.total_by_language.iter().collect();
+    options.sorting.sort_languages(&mut languages);
+
+    let mut table = new_table(
+        &[
+            "Language",
+            "Files",
+            "Files %",
+            "Functions",
+            "Functions %",
+            "Documented %",
+            "Structs/Classes",
+            "Interfaces/Traits",
+            "Enums",
+            "Type aliases",
+            "Modules",
+            "Code",
+            "Comments",
+            "Blank",
+        ],
+        1,
+        options.tables,
+    );
+    let row = |name: &str, counts: &LanguageStats| {
+        vec![
+            name.to_string(),
+            numbers.number(counts.file_count),
+            format!("{:.1}", counts.file_share(stats)),
+            numbers.number(counts.function_count),
+            format!("{:.1}", counts.function_share(stats)),
+            format!("{:.1}", counts.documentation_coverage()),
+            numbers.number(counts.class_struct_count),
+            numbers.number(counts.interface_count),
+            numbers.number(counts.enum_count),
+            numbers.number(counts.type_alias_count),
+            numbers.number(counts.module_count),
+            numbers.number(counts.code_lines),
+            numbers.number(counts.comment_lines),
+            numbers.number(counts.blank_lines),
+        ]
+    };
+    for (language, lang_stats) in &languages {
+        table.add_row(row(language.name(), lang_stats));
+    }
+
+    let total = &stats.total_stats;
+    table.add_row(row(
+        "Total",
+        &LanguageStats {
+            file_count: stats.total_files(),
+            function_count: total.function_count,
+            documented_function_count: total.documented_function_count,
+            public_function_count: total.public_function_count,
+            private_function_count: total.private_function_count,
+            public_type_count: total.public_type_count,
+            private_type_count: total.private_type_count,
+            class_struct_count: total.class_struct_count,
+            interface_count: total.interface_count,
+            enum_count: total.enum_count,
+            type_alias_count: total.type_alias_count,
+            module_count: total.module_count,
+            impl_count: total.impl_count,
+            trait_impl_count: total.trait_impl_count,
+            impl_method_count: total.impl_method_count,
+            generic_count: total.generic_count,
+            type_parameter_count: total.type_parameter_count,
+            async_function_count: total.async_function_count,
+            decorated_function_count: total.decorated_function_count,
+            lambda_count: total.lambda_count,
+            component_count: total.component_count,
+            total_lines: total.total_lines,
+            code_lines: total.code_lines,
+            comment_lines: total.comment_lines,
+            blank_lines: total.blank_lines,
+            byte_count: total.byte_count,
+        },
+    ));
+    // Language, files, functions and code lines are kept
+    output.push_str(&render_table(table, &[9, 10, 8, 7, 5, 2, 4, 13, 12, 6]));
+    output.push_str(&format_total_sections(stats, numbers));
 
     output
 }
 
-/// Formats directory statistics as JSON for machine consumption.
+/// Creates a box-drawn table with a header row whose columns after the
+/// first `text_columns` hold right-aligned numbers.
 ///
-/// Serializes the complete directory statistics structure to pretty-printed JSON.
-/// This format is ideal for programmatic processing, integration with other tools,
-/// or storage for later analysis.
+/// Tables shrink to the style's width, or the terminal's when printed to one,
+/// wrapping cells.
+fn new_table(header: &[&str], text_columns: usize, style: TableStyle) -> comfy_table::Table {
+    use comfy_table::{
+        Attribute, Cell, CellAlignment, ContentArrangement, Table, presets::UTF8_FULL,
+    };
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header.iter().map(|name| match style.color {
+            Some(false) => Cell::new(name),
+            // Left unstyled by the table itself unless printed to a terminal
+            _ => Cell::new(name).add_attribute(Attribute::Bold),
+        }));
+    if style.color == Some(true) {
+        table.enforce_styling();
+    }
+    if let Some(width) = style.width {
+        table.set_width(width);
+    }
+    for column in table.column_iter_mut().skip(text_columns) {
+        column.set_cell_alignment(CellAlignment::Right);
+    }
+    table
+}
+
+/// Renders a table, first hiding optional columns until every header fits
+/// the table's width without being broken inside a word.
+///
+/// Wrapping a wide table into a narrow width would otherwise squeeze its
+/// headers to a letter per line. Tables without a width are rendered whole.
 ///
 /// # Arguments
 ///
-/// * `stats` - Directory statistics to serialize
+/// * `table` - A table from [`new_table`] with its rows
+/// * `optional` - Indices of the columns that may be hidden, least important
+///   first
 ///
 /// # Returns
 ///
-/// A JSON string with pretty formatting, or an error message if serialization fails
+/// The table, followed by a line naming the hidden columns if there are any
+fn render_table(mut table: comfy_table::Table, optional: &[usize]) -> String {
+    use comfy_table::{ColumnConstraint, Width};
+
+    let Some(width) = table.width() else {
+        return table.to_string();
+    };
+    let headers: Vec<String> = table
+        .header()
+        .map(|header| header.cell_iter().map(|cell| cell.content()).collect())
+        .unwrap_or_default();
+    // The longest word of each header, which must not be broken
+    let minimums: Vec<usize> = headers
+        .iter()
+        .map(|header| {
+            header
+                .split_whitespace()
+                .map(|word| word.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    // A left border, then padding on both sides and a border per column
+    let needed = |hidden: &[usize]| {
+        1 + minimums
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !hidden.contains(index))
+            .map(|(_, minimum)| minimum + 3)
+            .sum::<usize>()
+    };
+    let mut hidden = Vec::new();
+    for &index in optional {
+        if needed(&hidden) <= usize::from(width) {
+            break;
+        }
+        hidden.push(index);
+    }
+
+    for (index, &minimum) in minimums.iter().enumerate() {
+        if let Some(column) = table.column_mut(index) {
+            column.set_constraint(if hidden.contains(&index) {
+                ColumnConstraint::Hidden
+            } else {
+                // The boundary includes the padding
+                ColumnConstraint::LowerBoundary(Width::Fixed(minimum as u16 + 2))
+            });
+        }
+    }
+
+    if hidden.is_empty() {
+        return table.to_string();
+    }
+    hidden.sort_unstable();
+    let names: Vec<&str> = hidden
+        .iter()
+        .map(|&index| headers[index].as_str())
+        .collect();
+    format!(
+        "{table}\nColumns hidden to fit a width of {width}: {}",
+        names.join(", ")
+    )
+}
+
+/// Formats line counts as `120 (100 code, 15 comments, 5 blank)`.
+fn format_line_counts(stats: &CodeStats, numbers: NumberStyle) -> String {
+    format!(
+        "{} ({} code, {} comments, {} blank)",
+        numbers.number(stats.total_lines),
+        numbers.number(stats.code_lines),
+        numbers.number(stats.comment_lines),
+        numbers.number(stats.blank_lines)
+    )
+}
+
+/// Formats the average function length as a new line, noting capped functions.
 ///
-/// # JSON Structure
+/// Produces nothing when there are no functions to average.
 ///
-/// The output includes:
-/// - `files`: Array of individual file statistics
-/// - `total_by_language`: Language-aggregated statistics
-/// - `total_stats`: Overall totals across all languages
+/// # Output Format
 ///
-/// # Error Handling
+/// ```text
 ///
-/// If JSON serialization fails (highly unlikely with our data structures),
-/// returns a formatted error message instead of panicking.
-fn format_json(stats: &DirectoryStats) -> String {
-    serde_json::to_string_pretty(stats)
-        .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"))
-}
+/// Average function length: 18.2 lines (1 oversized functions capped)
+/// ```
+fn format_function_length(stats: &CodeStats) -> String {
+    let Some(average) = stats.average_function_lines() else {
+        return String::new();
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::language::SupportedLanguage;
-    use crate::parser::CodeStats;
-    use std::path::PathBuf;
+    let mut output = format!("\nAverage function length: {average:.1} lines");
+    if stats.oversized_function_count > 0 {
+        output.push_str(&format!(
+            " ({} oversized functions capped)",
+            stats.oversized_function_count
+        ));
+    }
+    output
+}
 
-    /// Creates a sample DirectoryStats for testing purposes.
-    ///
-    /// This helper function sets up realistic test data with multiple files
+/// Formats the number of React components as a new line, indented by `indent`.
+///
+/// Produces nothing for files without components.
+///
+/// # Output Format
+///
+/// ```text
+///
+/// React components: 3
+/// ```
+fn format_components(stats: &CodeStats, indent: &str) -> String {
+    if stats.component_count == 0 {
+        return String::new();
+    }
+    format!("\n{indent}React components: {}", stats.component_count)
+}
+
+/// Formats the SHA-256 of a file's source as a new line, indented by `indent`.
+///
+/// Produces nothing unless hashes were requested with `--hash`.
+///
+/// # Output Format
+///
+/// ```text
+///
+/// SHA-256: 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08
+/// ```
+fn format_hash(file_stats: &FileStats, indent: &str) -> String {
+    file_stats
+        .sha256
+        .as_ref()
+        .map_or(String::new(), |hash| format!("\n{indent}SHA-256: {hash}"))
+}
+
+/// Formats the parameter and nesting signals of the functions as new lines,
+/// indented by `indent`.
+///
+/// Produces nothing when there are no functions.
+///
+/// # Output Format
+///
+/// ```text
+///
+/// Parameters: 2.3 per function on average, 4 functions with more than 5
+/// Maximum nesting depth: 6
+/// ```
+fn format_function_shape(stats: &CodeStats, indent: &str) -> String {
+    let Some(average) = stats.average_parameters() else {
+        return String::new();
+    };
+
+    format!(
+        "\n{indent}Parameters: {average:.1} per function on average, {} functions with more than {MANY_PARAMETERS}\n\
+         {indent}Maximum nesting depth: {}",
+        stats.many_parameter_function_count, stats.max_nesting_depth
+    )
+}
+
+/// Formats a file's extended metrics as new lines, indented by `indent`.
+///
+/// Produces nothing unless extended metrics were computed.
+///
+/// # Output Format
+///
+/// ```text
+///
+///   Halstead volume: 1234.5 (712 operators and operands, 115 distinct)
+///   Maintainability index: 42.7 (cyclomatic complexity 12)
+/// ```
+fn format_extended_metrics(metrics: Option<&ExtendedMetrics>, indent: &str) -> String {
+    let Some(metrics) = metrics else {
+        return String::new();
+    };
+
+    format!(
+        "\n{indent}Halstead volume: {:.1} ({} operators and operands, {} distinct)\n\
+         {indent}Maintainability index: {:.1} (cyclomatic complexity {})",
+        metrics.volume,
+        metrics.total_operators + metrics.total_operands,
+        metrics.distinct_operators + metrics.distinct_operands,
+        metrics.maintainability_index,
+        metrics.cyclomatic_complexity
+    )
+}
+
+/// Formats the number of parse errors as a new line, indented by `indent`.
+///
+/// Produces nothing for files that parsed cleanly. Files whose error ratio
+/// reaches [`UNRELIABLE_PARSE_ERROR_RATIO`] are marked, since most of their
+/// counts may be missing.
+///
+/// # Output Format
+///
+/// ```text
+///
+///   Parse errors: 7 (results unreliable)
+/// ```
+fn format_parse_errors(stats: &CodeStats, indent: &str) -> String {
+    if stats.parse_errors == 0 {
+        return String::new();
+    }
+
+    let mut output = format!("\n{indent}Parse errors: {}", stats.parse_errors);
+    if stats.parse_error_ratio() >= UNRELIABLE_PARSE_ERROR_RATIO {
+        output.push_str(" (results unreliable)");
+    }
+    output
+}
+
+/// Formats the counts of user-supplied queries as a new line, indented by `indent`.
+///
+/// Produces nothing when no `--query` applied.
+///
+/// # Output Format
+///
+/// ```text
+///
+/// Query matches: hooks 12, macros 3
+/// ```
+fn format_query_counts(stats: &CodeStats, indent: &str) -> String {
+    if stats.query_counts.is_empty() {
+        return String::new();
+    }
+
+    let counts: Vec<String> = stats
+        .query_counts
+        .iter()
+        .map(|(name, count)| format!("{name} {count}"))
+        .collect();
+    format!("\n{indent}Query matches: {}", counts.join(", "))
+}
+
+/// Lists the functions whose length was capped, so the cap is never silent.
+///
+/// Produces nothing when no function exceeded its limit.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Oversized functions:
+///   src/generated/tables.rs:12: 20412 lines (counted as 2000)
+/// ```
+fn format_oversized_functions(files: &[&FileStats]) -> String {
+    let mut output = String::new();
+
+    for file in files {
+        for function in &file.oversized_functions {
+            output.push_str(&format!(
+                "\n  {}:{}: {} lines (counted as {})",
+                file.path.display(),
+                function.line,
+                function.lines,
+                function.counted_lines
+            ));
+        }
+    }
+
+    if output.is_empty() {
+        output
+    } else {
+        format!("\n\nOversized functions:{output}")
+    }
+}
+
+/// Formats the secret pattern matches found in the given files.
+///
+/// Produces nothing when no file has matches, so reports without secret
+/// scanning (or with a clean scan) are unchanged.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Potential secrets:
+///   src/config.rs:12:17: aws-access-key-id
+/// ```
+fn format_secrets(files: &[&FileStats]) -> String {
+    let mut output = String::new();
+
+    for file in files {
+        for secret in &file.secrets {
+            output.push_str(&format!(
+                "\n  {}:{}:{}: {}",
+                file.path.display(),
+                secret.line,
+                secret.column,
+                secret.pattern
+            ));
+        }
+    }
+
+    if output.is_empty() {
+        output
+    } else {
+        format!("\n\nPotential secrets:{output}")
+    }
+}
+
+/// Formats the files flagged as possibly obfuscated, with the signals that fired.
+///
+/// `excluded` files were left out of the totals and are marked as such.
+/// Produces nothing when no file was flagged.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Possibly obfuscated:
+///   vendor/app.min.js: short identifiers (entropy 5.21 bits/char, mean identifier length 1.8)
+///   vendor/payload.js: high entropy (entropy 5.93 bits/char, mean identifier length 4.2), excluded from totals
+/// ```
+fn format_suspicious(files: &[&FileStats], excluded: &[&FileStats]) -> String {
+    let mut output = String::new();
+
+    let flagged = files
+        .iter()
+        .map(|file| (file, false))
+        .chain(excluded.iter().map(|file| (file, true)));
+    for (file, is_excluded) in flagged {
+        let Some(suspicion) = &file.suspicion else {
+            continue;
+        };
+        let signals: Vec<String> = suspicion.signals.iter().map(ToString::to_string).collect();
+        output.push_str(&format!(
+            "\n  {}: {} (entropy {:.2} bits/char, mean identifier length {:.1}){}",
+            file.path.display(),
+            signals.join(", "),
+            suspicion.entropy,
+            suspicion.mean_identifier_length,
+            if is_excluded {
+                ", excluded from totals"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    if output.is_empty() {
+        output
+    } else {
+        format!("\n\nPossibly obfuscated:{output}")
+    }
+}
+
+/// Formats the number and code lines of the vendored and generated files.
+///
+/// Files of each origin are counted whether they were included in the totals
+/// or set aside. Produces nothing when no file was classified.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Vendored and generated code:
+///   Vendored: 1,204 files, 80,321 code lines, excluded from totals
+///   Generated: 3 files, 1020 code lines
+/// ```
+fn format_origins(stats: &DirectoryStats, numbers: NumberStyle) -> String {
+    let mut output = String::new();
+
+    for (origin, name) in [
+        (Origin::Vendored, "Vendored"),
+        (Origin::Generated, "Generated"),
+    ] {
+        let mut totals = stats.origin_totals(origin);
+        let set_aside = stats
+            .set_aside_files
+            .iter()
+            .filter(|file| file.origin == Some(origin));
+        for file in set_aside.clone() {
+            totals.add(file);
+        }
+        if totals.files == 0 {
+            continue;
+        }
+
+        output.push_str(&format!(
+            "\n  {name}: {} files, {} code lines{}",
+            numbers.number(totals.files),
+            numbers.number(totals.code_lines),
+            if set_aside.count() > 0 {
+                ", excluded from totals"
+            } else {
+                ""
+            }
+        ));
+    }
+
+    if output.is_empty() {
+        output
+    } else {
+        format!("\n\nVendored and generated code:{output}")
+    }
+}
+
+/// Formats the size of the production and the test code.
+///
+/// Produces nothing when no test code was found, so code bases without tests
+/// keep their summary unchanged.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Production and test code:
+///   Production: 12 files, 340 functions, 8,000 code lines
+///   Test: 5 files, 120 functions, 3,000 code lines (27.3% of code lines)
+/// ```
+fn format_test_split(stats: &DirectoryStats, numbers: NumberStyle) -> String {
+    let split = stats.test_split();
+    if split.test == CodeGroup::default() {
+        return String::new();
+    }
+
+    let group = |name: &str, group: &CodeGroup| {
+        format!(
+            "\n  {name}: {} files, {} functions, {} code lines",
+            numbers.number(group.files),
+            numbers.number(group.function_count),
+            numbers.number(group.code_lines)
+        )
+    };
+    format!(
+        "\n\nProduction and test code:{}{} ({:.1}% of code lines)",
+        group("Production", &split.production),
+        group("Test", &split.test),
+        split.test_line_share()
+    )
+}
+
+/// Formats the findings of a run: counts per severity, then the listed findings.
+///
+/// Findings for which `listed` returns false are only counted. Produces
+/// nothing unless the run had warnings or errors, so skipped files alone do
+/// not add a section to every report.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Findings: 1 errors, 0 warnings, 2 info
+///   error: src/broken.rs: IO error: stream did not contain valid UTF-8
+/// ```
+fn format_findings(findings: &[Finding], listed: impl Fn(&Finding) -> bool) -> String {
+    let errors = count_severity(findings, Severity::Error);
+    let warnings = count_severity(findings, Severity::Warning);
+    if errors + warnings == 0 {
+        return String::new();
+    }
+
+    let mut output = format!(
+        "\n\nFindings: {errors} errors, {warnings} warnings, {} info",
+        count_severity(findings, Severity::Info)
+    );
+
+    for finding in findings.iter().filter(|finding| listed(finding)) {
+        output.push_str(&format!("\n  {finding}"));
+    }
+
+    output
+}
+
+/// Formats the files that were skipped or could not be analyzed, with the
+/// kind of problem, followed by the number of files of unsupported types.
+///
+/// Produces nothing when every file was analyzed.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Skipped/Errored files:
+///   dump.rs (too_large): skipped: 20971520 bytes, larger than 10485760
+///   src/broken.rs (io): IO error: stream did not contain valid UTF-8
+///   12 files of unsupported types
+/// ```
+fn format_file_problems(stats: &DirectoryStats) -> String {
+    let mut output = String::new();
+    for finding in stats
+        .findings
+        .iter()
+        .filter(|finding| finding.is_file_problem())
+    {
+        if let (Some(path), Some(kind)) = (&finding.path, finding.kind) {
+            output.push_str(&format!(
+                "\n  {} ({kind}): {}",
+                path.display(),
+                finding.message
+            ));
+        }
+    }
+
+    if stats.unsupported_files > 0 {
+        output.push_str(&format!(
+            "\n  {} files of unsupported types",
+            stats.unsupported_files
+        ));
+    }
+
+    if output.is_empty() {
+        output
+    } else {
+        format!("\n\nSkipped/Errored files:{output}")
+    }
+}
+
+/// Formats directory statistics as a detailed view.
+///
+/// Provides comprehensive output showing individual file statistics followed by
+/// the summary view. Files are listed in the order of `sorting`, by path by default.
+///
+/// # Arguments
+///
+/// * `stats` - Directory statistics containing individual file results
+/// * `pagination` - Optional page of files to list; the summary always covers all files
+/// * `sorting` - Order of the files and of the summary's languages
+///
+/// # Returns
+///
+/// A formatted string with per-file details and summary statistics
+///
+/// # Output Format
+///
+/// ```text
+/// src/main.rs (Rust):
+///   Functions: 3
+///   Structs/Classes: 2
+///   Interfaces/Traits: 1
+///   Enums: 1
+///   Type aliases: 0
+///   Type definitions: 2
+///   Modules: 2
+///   Lines: 40 (30 code, 6 comments, 4 blank)
+///
+/// src/lib.rs (Rust):
+///   Functions: 5
+///   Structs/Classes: 1
+///   Interfaces/Traits: 0
+///   Enums: 0
+///   Type aliases: 2
+///   Type definitions: 2
+///   Modules: 0
+///   Lines: 75 (60 code, 10 comments, 5 blank)
+///
+/// Rust modules:
+///   crate: 2 functions, 1 structs, 0 enums, 0 traits
+///   crate::parser: 6 functions, 2 structs, 1 enums, 1 traits
+///
+/// Language Summary:
+/// [... summary content ...]
+/// ```
+fn format_detail(stats: &DirectoryStats, options: &FormatOptions) -> String {
+    let numbers = options.numbers;
+    let mut output = String::new();
+
+    let mut files = stats.files.clone();
+    options.sorting.sort_files(&mut files);
+
+    let page = match options.pagination {
+        Some(pagination) => pagination.apply(&files),
+        None => &files,
+    };
+
+    // Display individual file statistics
+    for file in page {
+        output.push_str(&format!(
+            "{} ({:?}{}):\n  Functions: {}\n  Structs/Classes: {}\n  Interfaces/Traits: {}\n  \
+             Enums: {}\n  Type aliases: {}\n  Type definitions: {}\n  Modules: {}\n  Lines: {}{}{}{}{}{}{}\n\n",
+            file.path.display(),
+            file.language,
+            file.origin
+                .map_or(String::new(), |origin| format!(", {origin}")),
+            numbers.number(file.stats.function_count),
+            numbers.number(file.stats.class_struct_count),
+            numbers.number(file.stats.interface_count),
+            numbers.number(file.stats.enum_count),
+            numbers.number(file.stats.type_alias_count),
+            numbers.number(file.stats.type_definition_count()),
+            numbers.number(file.stats.module_count),
+            format_line_counts(&file.stats, numbers),
+            format_hash(file, "  "),
+            format_components(&file.stats, "  "),
+            format_function_shape(&file.stats, "  "),
+            format_extended_metrics(file.metrics.as_ref(), "  "),
+            format_parse_errors(&file.stats, "  "),
+            format_query_counts(&file.stats, "  ")
+        ));
+    }
+
+    if let Some(pagination) = options.pagination {
+        output.push_str(&pagination.describe(files.len()));
+        output.push_str("\n\n");
+    }
+
+    output.push_str(&format_rust_modules(&stats.rust_modules));
+
+    // Append summary statistics at the end
+    output.push_str(&format_summary(stats, options));
+
+    output
+}
+
+/// Formats the per-module rollup of Rust declarations, followed by a blank
+/// line; modules of crates below the analyzed directory name their crate.
+///
+/// Produces nothing when no Rust module was found.
+///
+/// # Output Format
+///
+/// ```text
+/// Rust modules:
+///   crate: 2 functions, 1 structs, 0 enums, 0 traits
+///   crate::io (crates/core): 4 functions, 0 structs, 1 enums, 0 traits
+///
+/// ```
+fn format_rust_modules(modules: &[ModuleStats]) -> String {
+    if modules.is_empty() {
+        return String::new();
+    }
+
+    let mut output = String::from("Rust modules:\n");
+    for module in modules {
+        let crate_root = module
+            .crate_root
+            .as_ref()
+            .map_or(String::new(), |root| format!(" ({})", root.display()));
+        output.push_str(&format!(
+            "  {}{crate_root}: {} functions, {} structs, {} enums, {} traits\n",
+            module.path, module.functions, module.structs, module.enums, module.traits
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+/// Formats the hotspot report: the `count` files with the most functions.
+///
+/// Files without functions are never listed. Ties are broken by path so the
+/// output is deterministic.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Top 2 files by functions:
+///   src/parser.rs: 42 functions (avg 12.5 lines)
+///   src/cli.rs: 17 functions (avg 20.1 lines)
+/// ```
+fn format_top_files(stats: &DirectoryStats, count: usize) -> String {
+    let mut files: Vec<_> = stats
+        .files
+        .iter()
+        .filter(|file| file.stats.function_count > 0)
+        .collect();
+    files.sort_by(|a, b| {
+        b.stats
+            .function_count
+            .cmp(&a.stats.function_count)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let mut output = format!("\n\nTop {count} files by functions:");
+    if files.is_empty() {
+        output.push_str("\n  (no functions found)");
+    }
+    for file in files.into_iter().take(count) {
+        output.push_str(&format!(
+            "\n  {}: {} functions",
+            file.path.display(),
+            file.stats.function_count
+        ));
+        if let Some(average) = file.stats.average_function_lines() {
+            output.push_str(&format!(" (avg {average:.1} lines)"));
+        }
+    }
+
+    output
+}
+
+/// Formats the function names defined in at least `min_places` places,
+/// with every definition.
+///
+/// Names defined most often come first; ties are broken by name so the
+/// output is deterministic.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Function names defined in 3 or more places:
+///   parse_args: 3 definitions
+///     src/a.rs:12
+///     src/b.rs:40
+///     tools/c.rs:7
+/// ```
+fn format_duplicate_names(stats: &DirectoryStats, min_places: usize) -> String {
+    let mut definitions: HashMap<&str, Vec<(&Path, usize)>> = HashMap::new();
+    for file in &stats.files {
+        for function in &file.functions {
+            definitions
+                .entry(&function.name)
+                .or_default()
+                .push((&file.path, function.line));
+        }
+    }
+
+    let mut duplicates: Vec<_> = definitions
+        .into_iter()
+        .filter(|(_, places)| places.len() >= min_places)
+        .collect();
+    duplicates.sort_by(|(a, a_places), (b, b_places)| {
+        b_places.len().cmp(&a_places.len()).then_with(|| a.cmp(b))
+    });
+
+    let mut output = format!("\n\nFunction names defined in {min_places} or more places:");
+    if duplicates.is_empty() {
+        output.push_str("\n  (none found)");
+    }
+    for (name, mut places) in duplicates {
+        places.sort();
+        output.push_str(&format!("\n  {name}: {} definitions", places.len()));
+        for (path, line) in places {
+            output.push_str(&format!("\n    {}:{line}", path.display()));
+        }
+    }
+
+    output
+}
+
+/// Formats the magic value report: totals plus the files with the most magic values.
+///
+/// Files without any magic values are never listed. Ties are broken by path so
+/// the output is deterministic.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Magic Values:
+///   Total: 42 strings, 17 numbers outside constant declarations
+///
+/// Worst offenders:
+///   src/parser.rs: 20 (12 strings, 8 numbers)
+///   src/main.rs: 5 (3 strings, 2 numbers)
+/// ```
+fn format_magic_values(stats: &DirectoryStats) -> String {
+    let mut output = format!(
+        "\n\nMagic Values:\n  Total: {} strings, {} numbers outside constant declarations",
+        stats.total_stats.magic_string_count, stats.total_stats.magic_number_count
+    );
+
+    let mut offenders: Vec<_> = stats
+        .files
+        .iter()
+        .filter(|file| file.stats.magic_value_count() > 0)
+        .collect();
+    offenders.sort_by(|a, b| {
+        b.stats
+            .magic_value_count()
+            .cmp(&a.stats.magic_value_count())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    if !offenders.is_empty() {
+        output.push_str("\n\nWorst offenders:");
+        for file in offenders.into_iter().take(MAGIC_VALUE_OFFENDERS) {
+            output.push_str(&format!(
+                "\n  {}: {} ({} strings, {} numbers)",
+                file.path.display(),
+                file.stats.magic_value_count(),
+                file.stats.magic_string_count,
+                file.stats.magic_number_count
+            ));
+        }
+    }
+
+    output
+}
+
+/// Formats the i18n report: every file with potentially untranslated strings.
+///
+/// Unlike the magic value report the list is not truncated, since it serves as
+/// a work list for translation. Files are ordered by count, then by path.
+///
+/// # Output Format
+///
+/// ```text
+///
+///
+/// Untranslated Strings:
+///   Total: 12 in 2 files
+///   src/ui/dialog.js: 9
+///   src/main.rs: 3
+/// ```
+fn format_untranslated_strings(stats: &DirectoryStats) -> String {
+    let mut files: Vec<_> = stats
+        .files
+        .iter()
+        .filter(|file| file.stats.untranslated_string_count > 0)
+        .collect();
+    files.sort_by(|a, b| {
+        b.stats
+            .untranslated_string_count
+            .cmp(&a.stats.untranslated_string_count)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let mut output = format!(
+        "\n\nUntranslated Strings:\n  Total: {} in {} files",
+        stats.total_stats.untranslated_string_count,
+        files.len()
+    );
+    for file in files {
+        output.push_str(&format!(
+            "\n  {}: {}",
+            file.path.display(),
+            file.stats.untranslated_string_count
+        ));
+    }
+
+    output
+}
+
+/// Formats directory statistics as JSON for machine consumption.
+///
+/// Serializes the complete directory statistics structure to pretty-printed JSON.
+/// This format is ideal for programmatic processing, integration with other tools,
+/// or storage for later analysis. The command line streams the same JSON with
+/// [`write_json`] instead of building the string.
+///
+/// # Arguments
+///
+/// * `stats` - Directory statistics to serialize
+/// * `pagination` - Optional page of files to include; files are then sorted by path
+///
+/// # Returns
+///
+/// A JSON string with pretty formatting, or an error message if serialization fails
+///
+/// # JSON Structure
+///
+/// The output includes:
+/// - `meta`: Tool version, time, root and options of the analysis (omitted
+///   unless set)
+/// - `files`: Array of individual file statistics
+/// - `total_by_language`: Language-aggregated statistics
+/// - `total_stats`: Overall totals across all languages
+/// - `findings`: Errors, warnings and skipped files (omitted when empty)
+/// - `unsupported_files`: Number of files skipped as unsupported types
+/// - `errors`: The files that could not be analyzed, with `path`, `kind` and
+///   `message` (omitted when empty)
+///
+/// # Error Handling
+///
+/// If JSON serialization fails (highly unlikely with our data structures),
+/// returns a formatted error message instead of panicking.
+fn format_json(stats: &DirectoryStats, pagination: Option<Pagination>, compact: bool) -> String {
+    let mut buffer = Vec::new();
+    match write_json(stats, pagination, compact, &mut buffer) {
+        Ok(()) => String::from_utf8(buffer).expect("serde_json writes valid UTF-8"),
+        Err(e) => format!("Error serializing to JSON: {e}"),
+    }
+}
+
+/// Serializes directory statistics as JSON directly into a writer.
+///
+/// File entries are written one at a time as they are serialized, so the
+/// report is never held in memory as a whole. See [`format_json`] for the
+/// structure of the output.
+///
+/// # Arguments
+///
+/// * `stats` - Directory statistics to serialize
+/// * `pagination` - Optional page of files to include; files are then sorted by path
+/// * `compact` - Write the document on a single line instead of pretty-printing it
+/// * `writer` - Destination of the JSON text
+///
+/// # Returns
+///
+/// * `Ok(())` once the complete document has been written
+/// * `Err(io::Error)` if writing or serialization fails
+pub(crate) fn write_json(
+    stats: &DirectoryStats,
+    pagination: Option<Pagination>,
+    compact: bool,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    with_report(stats, pagination, |report| {
+        if compact {
+            serde_json::to_writer(&mut *writer, report)
+        } else {
+            serde_json::to_writer_pretty(&mut *writer, report)
+        }
+    })?;
+
+    Ok(())
+}
+
+/// Formats directory statistics as YAML, for reports read by people but
+/// still processed by tools, e.g. committed next to the code.
+///
+/// The document has the structure of the JSON output, see [`format_json`].
+fn format_yaml(stats: &DirectoryStats, pagination: Option<Pagination>) -> String {
+    with_report(stats, pagination, |report| serde_yaml::to_string(report))
+        .unwrap_or_else(|e| format!("Error serializing to YAML: {e}"))
+}
+
+/// Formats directory statistics as TOML, the format of the tool's own
+/// configuration files.
+///
+/// The document has the structure of the JSON output, see [`format_json`];
+/// files are an array of `[[files]]` tables.
+fn format_toml(stats: &DirectoryStats, pagination: Option<Pagination>) -> String {
+    with_report(stats, pagination, |report| toml::to_string_pretty(report))
+        .unwrap_or_else(|e| format!("Error serializing to TOML: {e}"))
+}
+
+/// Builds the structured report shared by the JSON, YAML and TOML output
+/// and hands it to a serializer.
+///
+/// # Arguments
+///
+/// * `stats` - Directory statistics to report
+/// * `pagination` - Optional page of files to include; files are then sorted by path
+/// * `serialize` - Called with the report
+fn with_report<T>(
+    stats: &DirectoryStats,
+    pagination: Option<Pagination>,
+    serialize: impl FnOnce(&StructuredReport) -> T,
+) -> T {
+    let mut files: Vec<&FileStats> = stats.files.iter().collect();
+    if pagination.is_some() {
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+    let errors = stats
+        .findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .filter_map(|finding| {
+            Some(FileError {
+                path: finding.path.as_deref()?,
+                kind: finding.kind?,
+                message: &finding.message,
+            })
+        })
+        .collect();
+
+    serialize(&StructuredReport {
+        meta: stats.meta.as_ref(),
+        files: pagination.map_or(&files[..], |pagination| pagination.apply(&files)),
+        total_by_language: &stats.total_by_language,
+        total_stats: &stats.total_stats,
+        findings: &stats.findings,
+        unsupported_files: stats.unsupported_files,
+        rust_modules: &stats.rust_modules,
+        errors,
+        excluded_files: &stats.excluded_files,
+        set_aside_files: &stats.set_aside_files,
+        test_split: stats.test_split(),
+        interrupted: stats.interrupted,
+    })
+}
+
+/// Same shape as `DirectoryStats`, with `files` possibly restricted to one
+/// page and the error findings repeated as `errors`.
+#[derive(Serialize)]
+struct StructuredReport<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<&'a ReportMeta>,
+    files: &'a [&'a FileStats],
+    total_by_language: &'a BTreeMap<SupportedLanguage, LanguageStats>,
+    total_stats: &'a CodeStats,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    findings: &'a [Finding],
+    unsupported_files: usize,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    rust_modules: &'a [ModuleStats],
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FileError<'a>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    excluded_files: &'a [FileStats],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    set_aside_files: &'a [FileStats],
+    test_split: TestSplit,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    interrupted: bool,
+}
+
+/// A file that could not be analyzed.
+#[derive(Serialize)]
+struct FileError<'a> {
+    path: &'a Path,
+    kind: ErrorKind,
+    message: &'a str,
+}
+
+/// Formats directory statistics in the JSON layout of `cloc --json`.
+///
+/// Dashboards and scripts that parse cloc reports can read this output
+/// unchanged: every language is a key holding its file count and line
+/// counts, followed by their `SUM`. The `header` names this tool instead of
+/// cloc and leaves out cloc's timing fields.
+///
+/// # Output Format
+///
+/// ```json
+/// {
+///   "header": {
+///     "program": "code-stats-rs",
+///     "version": "0.1.0",
+///     "n_files": 3,
+///     "n_lines": 120
+///   },
+///   "Python": { "nFiles": 1, "blank": 4, "comment": 2, "code": 30 },
+///   "Rust": { "nFiles": 2, "blank": 10, "comment": 14, "code": 60 },
+///   "SUM": { "nFiles": 3, "blank": 14, "comment": 16, "code": 90 }
+/// }
+/// ```
+///
+/// With `compact`, the document is written on a single line.
+fn format_cloc_json(stats: &DirectoryStats, compact: bool) -> String {
+    #[derive(Serialize)]
+    struct ClocReport {
+        header: ClocHeader,
+        #[serde(flatten)]
+        languages: BTreeMap<&'static str, ClocCounts>,
+        #[serde(rename = "SUM")]
+        sum: ClocCounts,
+    }
+
+    #[derive(Serialize)]
+    struct ClocHeader {
+        program: &'static str,
+        version: &'static str,
+        n_files: usize,
+        n_lines: usize,
+    }
+
+    #[derive(Serialize, Default)]
+    struct ClocCounts {
+        #[serde(rename = "nFiles")]
+        n_files: usize,
+        blank: usize,
+        comment: usize,
+        code: usize,
+    }
+
+    let mut sum = ClocCounts::default();
+    let languages = stats
+        .total_by_language
+        .iter()
+        .map(|(language, totals)| {
+            sum.n_files += totals.file_count;
+            sum.blank += totals.blank_lines;
+            sum.comment += totals.comment_lines;
+            sum.code += totals.code_lines;
+            let counts = ClocCounts {
+                n_files: totals.file_count,
+                blank: totals.blank_lines,
+                comment: totals.comment_lines,
+                code: totals.code_lines,
+            };
+            (language.name(), counts)
+        })
+        .collect();
+
+    let report = ClocReport {
+        header: ClocHeader {
+            program: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            n_files: sum.n_files,
+            n_lines: sum.blank + sum.comment + sum.code,
+        },
+        languages,
+        sum,
+    };
+    let json = if compact {
+        serde_json::to_string(&report)
+    } else {
+        serde_json::to_string_pretty(&report)
+    };
+    json.unwrap_or_else(|e| format!("Error serializing to JSON: {e}"))
+}
+
+/// Writes directory statistics in the requested format.
+///
+/// JSON is streamed with [`write_json`]; the text formats are small enough to
+/// be built with [`format_output`] first. A trailing newline is always added.
+///
+/// # Arguments
+///
+/// * `stats` - Directory statistics containing aggregated results from all analyzed files
+/// * `format` - The desired output format (Summary, Detail, or JSON)
+/// * `options` - Optional report sections to include
+/// * `writer` - Destination of the report
+pub(crate) fn write_output(
+    stats: &DirectoryStats,
+    format: OutputFormat,
+    options: &FormatOptions,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Json => write_json(stats, options.pagination, options.compact, writer)?,
+        _ => write!(writer, "{}", format_output(stats, format, options))?,
+    }
+    writeln!(writer)
+}
+
+/// Formats the line a text report starts with to describe how it was
+/// produced, followed by a blank line.
+///
+/// # Output Format
+///
+/// ```text
+/// Report of . by code-stats-rs 0.1.0 at 2026-10-18 09:30:00 UTC (options: . --detail)
+///
+/// ```
+///
+/// The time is left out when the report has none.
+fn format_report_header(meta: &ReportMeta) -> String {
+    let options = if meta.options.is_empty() {
+        String::new()
+    } else {
+        format!(" (options: {})", meta.options.join(" "))
+    };
+    let time = meta
+        .timestamp
+        .map(|timestamp| format!(" at {}", format_utc(timestamp)))
+        .unwrap_or_default();
+    format!(
+        "Report of {} by code-stats-rs {}{time}{options}\n\n",
+        meta.root.display(),
+        meta.tool_version,
+    )
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time, e.g.
+/// `2026-10-18 09:30:00 UTC`.
+fn format_utc(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+
+    // Civil date from days since 1970-01-01, counting in 400-year eras
+    // that start on March 1st so leap days fall at the end of a year
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Formats the results of a license header audit.
+///
+/// Lists per-language violation counts (sorted alphabetically), then every file
+/// that failed the audit (sorted by path), followed by the overall totals.
+///
+/// # Arguments
+///
+/// * `audit` - Results of the license header audit
+///
+/// # Returns
+///
+/// A formatted string ready for display
+///
+/// # Output Format
+///
+/// ```text
+/// License Header Audit:
+///   Python:         0 of    3 files missing header
+///   Rust:           2 of    8 files missing header
+///
+/// Missing header:
+///   src/lib.rs (Rust)
+///   src/main.rs (Rust)
+///
+/// Total: 2 of 11 files missing header
+/// ```
+pub(crate) fn format_license_audit(audit: &LicenseAudit) -> String {
+    let mut output = String::new();
+
+    output.push_str("License Header Audit:\n");
+
+    let mut languages: Vec<_> = audit.by_language.iter().collect();
+    languages.sort_by_key(|(lang, _)| lang.name());
+
+    for (language, lang_audit) in languages {
+        output.push_str(&format!(
+            "  {:12} {:4} of {:4} files missing header\n",
+            format!("{}:", language.name()),
+            lang_audit.violation_count,
+            lang_audit.file_count
+        ));
+    }
+
+    if !audit.violations.is_empty() {
+        let mut violations: Vec<_> = audit.violations.iter().collect();
+        violations.sort_by(|a, b| a.path.cmp(&b.path));
+
+        output.push_str("\nMissing header:\n");
+        for violation in violations {
+            match &violation.reason {
+                ViolationReason::MissingHeader => output.push_str(&format!(
+                    "  {} ({:?})\n",
+                    violation.path.display(),
+                    violation.language
+                )),
+                ViolationReason::Unreadable(error) => output.push_str(&format!(
+                    "  {} ({:?}): unreadable: {}\n",
+                    violation.path.display(),
+                    violation.language,
+                    error
+                )),
+            }
+        }
+    }
+
+    output.push_str(&format!(
+        "\nTotal: {} of {} files missing header",
+        audit.violations.len(),
+        audit.total_files()
+    ));
+
+    output
+}
+
+/// Formats a consolidated multi-repository report.
+///
+/// Repositories keep their manifest order. The detail format adds each
+/// repository's per-language breakdown; JSON serializes the whole report.
+/// Repositories that could not be analyzed are listed as findings.
+///
+/// # Arguments
+///
+/// * `report` - Results of analyzing every repository of a manifest
+/// * `format` - The desired output format (Summary, Detail, or JSON)
+///
+/// # Returns
+///
+/// A formatted string ready for display
+///
+/// # Output Format
+///
+/// ```text
+/// Repository Summary:
+///   api:           450 functions,   80 structs/classes in 120 files, 15000 lines
+///   web:           300 functions,   40 structs/classes in 95 files, 9000 lines (cached)
+///
+/// Total: 750 functions, 120 structs/classes in 215 files across 2 repositories
+/// Lines: 24000 (19000 code, 3000 comments, 2000 blank)
+/// ```
+pub(crate) fn format_multi_report(report: &MultiReport, format: OutputFormat) -> String {
+    if format == OutputFormat::Json {
+        return serde_json::to_string_pretty(report)
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"));
+    }
+
+    let mut output = String::new();
+    if report.interrupted {
+        output.push_str("Partial report: analysis was interrupted\n\n");
+    }
+    output.push_str("Repository Summary:\n");
+
+    for repo in &report.repos {
+        output.push_str(&format!(
+            "  {:14} {:4} functions, {:4} structs/classes in {} files, {} lines{}\n",
+            format!("{}:", repo.name),
+            repo.total_stats.function_count,
+            repo.total_stats.class_struct_count,
+            repo.file_count,
+            repo.total_stats.total_lines,
+            if repo.cached {
+                " (cached)"
+            } else if repo.partial {
+                " (partial)"
+            } else {
+                ""
+            }
+        ));
+
+        if format == OutputFormat::Detail {
+            let mut languages: Vec<_> = repo.total_by_language.iter().collect();
+            languages.sort_by_key(|(lang, _)| lang.name());
+
+            for (language, lang_stats) in languages {
+                output.push_str(&format!(
+                    "    {:12} {:4} functions, {:4} structs/classes in {} files\n",
+                    format!("{}:", language.name()),
+                    lang_stats.function_count,
+                    lang_stats.class_struct_count,
+                    lang_stats.file_count
+                ));
+            }
+        }
+    }
+
+    output.push_str(&format!(
+        "\nTotal: {} functions, {} structs/classes in {} files across {} repositories\nLines: {}",
+        report.total_stats.function_count,
+        report.total_stats.class_struct_count,
+        report.total_files,
+        report.repos.len(),
+        format_line_counts(&report.total_stats, NumberStyle::plain())
+    ));
+    output.push_str(&format_findings(&report.findings, |finding| {
+        finding.severity >= Severity::Warning
+    }));
+
+    output
+}
+
+/// Formats the changes since a baseline report.
+///
+/// Only changed counts are shown. Added and removed files list their counts,
+/// modified files list the counts that changed and renamed files their former
+/// path. JSON serializes the whole diff.
+///
+/// # Output Format
+///
+/// ```text
+/// Changes since baseline:
+///   Rust:       +1 files (2 -> 3), +5 functions (40 -> 45), +60 code lines (400 -> 460)
+///
+/// Total: +1 files (4 -> 5), +5 functions (52 -> 57), +60 code lines (610 -> 670)
+///
+/// Files:
+///   added    src/cache.rs: 4 functions, 1 structs/classes, 52 code lines
+///   modified src/lib.rs: +1 functions (12 -> 13), +8 code lines (140 -> 148)
+///   removed  src/old.rs: 0 functions, 0 structs/classes, 0 code lines
+///   renamed  src/util.rs -> src/util/mod.rs
+/// ```
+pub(crate) fn format_diff(diff: &ReportDiff, format: OutputFormat) -> String {
+    if format == OutputFormat::Json {
+        return serde_json::to_string_pretty(diff)
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"));
+    }
+
+    if diff.is_empty() {
+        return "No changes since baseline".to_string();
+    }
+
+    let mut output = String::from("Changes since baseline:\n");
+    for language in &diff.languages {
+        output.push_str(&format!(
+            "  {:12} {}\n",
+            format!("{}:", language.language.name()),
+            format_count_deltas(&language.counts)
+        ));
+    }
+    output.push_str(&format!("\nTotal: {}", format_count_deltas(&diff.total)));
+
+    if !diff.files.is_empty() {
+        output.push_str("\n\nFiles:");
+        for file in &diff.files {
+            let path = file.path.display();
+            let line = match file.status {
+                FileStatus::Added => format!(
+                    "added    {path}: {}",
+                    format_file_counts(file, |delta| delta.after)
+                ),
+                FileStatus::Removed => format!(
+                    "removed  {path}: {}",
+                    format_file_counts(file, |delta| delta.before)
+                ),
+                FileStatus::Modified => format!(
+                    "modified {path}: {}",
+                    format_deltas(&[
+                        (file.functions, "functions"),
+                        (file.classes, "structs/classes"),
+                        (file.code_lines, "code lines"),
+                    ])
+                ),
+                FileStatus::Renamed => match &file.from {
+                    Some(from) => format!("renamed  {} -> {path}", from.display()),
+                    None => format!("renamed  {path}"),
+                },
+            };
+            output.push_str(&format!("\n  {line}"));
+        }
+    }
+
+    output
+}
+
+/// Formats two trees compared side by side.
+///
+/// The table lists every language of either tree with the files, functions
+/// and code lines of both and the change from `a` to `b`; the files that
+/// differ are only counted. JSON serializes the whole comparison, with the
+/// counts of `a` as `before` and those of `b` as `after`.
+///
+/// # Output Format
+///
+/// ```text
+/// A: ../upstream
+/// B: .
+/// ┌──────────┬─────────┬─────────┬────┬─────────────┬─────────────┬─────┬─ ...
+/// │ Language ┆ Files A ┆ Files B ┆  Δ ┆ Functions A ┆ Functions B ┆   Δ ┆  ...
+/// ╞══════════╪═════════╪═════════╪════╪═════════════╪═════════════╪═════╪═ ...
+/// │ Rust     ┆      12 ┆      14 ┆ +2 ┆         140 ┆         152 ┆ +12 ┆  ...
+/// ├╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌┼╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌┼╌ ...
+/// │ Total    ┆      12 ┆      14 ┆ +2 ┆         140 ┆         152 ┆ +12 ┆  ...
+/// └──────────┴─────────┴─────────┴────┴─────────────┴─────────────┴─────┴─ ...
+///
+/// Files: 1 only in A, 3 only in B, 2 moved, 5 with different counts
+/// ```
+pub(crate) fn format_comparison(
+    diff: &ReportDiff,
+    a: &Path,
+    b: &Path,
+    format: OutputFormat,
+    tables: TableStyle,
+) -> String {
+    if format == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct Comparison<'a> {
+            a: &'a Path,
+            b: &'a Path,
+            #[serde(flatten)]
+            diff: &'a ReportDiff,
+        }
+
+        return serde_json::to_string_pretty(&Comparison { a, b, diff })
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"));
+    }
+
+    let mut table = new_table(
+        &[
+            "Language",
+            "Files A",
+            "Files B",
+            "Δ",
+            "Functions A",
+            "Functions B",
+            "Δ",
+            "Code A",
+            "Code B",
+            "Δ",
+        ],
+        1,
+        tables,
+    );
+    let row = |name: &str, counts: &CountDeltas| {
+        let mut cells = vec![name.to_string()];
+        for delta in [counts.files, counts.functions, counts.code_lines] {
+            cells.push(delta.before.to_string());
+            cells.push(delta.after.to_string());
+            cells.push(match delta.change() {
+                0 => "0".to_string(),
+                change => format!("{change:+}"),
+            });
+        }
+        cells
+    };
+    for language in &diff.languages {
+        table.add_row(row(language.language.name(), &language.counts));
+    }
+    table.add_row(row("Total", &diff.total));
+
+    let count = |status| {
+        diff.files
+            .iter()
+            .filter(|file| file.status == status)
+            .count()
+    };
+    // The A columns go first, then the files and the code lines
+    let table = render_table(table, &[1, 4, 7, 2, 3, 8, 9]);
+    format!(
+        "A: {}\nB: {}\n{table}\n\nFiles: {} only in A, {} only in B, {} moved, {} with different counts",
+        a.display(),
+        b.display(),
+        count(FileStatus::Removed),
+        count(FileStatus::Added),
+        count(FileStatus::Renamed),
+        count(FileStatus::Modified)
+    )
+}
+
+/// Formats the files ranked by churn times complexity, highest first.
+///
+/// JSON serializes the ranking as an array.
+///
+/// # Output Format
+///
+/// ```text
+/// ┌────────────────┬──────────┬─────────┬────────────┬────────────┬───────┐
+/// │ File           ┆ Language ┆ Commits ┆ Complexity ┆ Code lines ┆ Score │
+/// ╞════════════════╪══════════╪═════════╪════════════╪════════════╪═══════╡
+/// │ src/parser.rs  ┆ Rust     ┆      42 ┆         87 ┆        912 ┆  3654 │
+/// ├╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌┤
+/// │ src/cli.rs     ┆ Rust     ┆      57 ┆         31 ┆        640 ┆  1767 │
+/// └────────────────┴──────────┴─────────┴────────────┴────────────┴───────┘
+/// ```
+pub(crate) fn format_hotspots(
+    hotspots: &[Hotspot],
+    format: OutputFormat,
+    tables: TableStyle,
+) -> String {
+    if format == OutputFormat::Json {
+        return serde_json::to_string_pretty(hotspots)
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"));
+    }
+
+    if hotspots.is_empty() {
+        return "No files changed in the window".to_string();
+    }
+
+    let mut table = new_table(
+        &[
+            "File",
+            "Language",
+            "Commits",
+            "Complexity",
+            "Code lines",
+            "Score",
+        ],
+        2,
+        tables,
+    );
+    for hotspot in hotspots {
+        table.add_row([
+            hotspot.path.display().to_string(),
+            hotspot.language.name().to_string(),
+            hotspot.commits.to_string(),
+            hotspot.complexity.to_string(),
+            hotspot.code_lines.to_string(),
+            hotspot.score.to_string(),
+        ]);
+    }
+    // The file and its score are kept
+    render_table(table, &[4, 1, 2, 3])
+}
+
+/// Formats the authorship of each directory, flagging those with a bus
+/// factor of 1.
+///
+/// JSON serializes the directories as an array.
+///
+/// # Output Format
+///
+/// ```text
+/// ┌────────────┬───────────┬─────────┬────────────┬───────┬────────────┐
+/// │ Directory  ┆ Functions ┆ Authors ┆ Top author ┆ Share ┆ Bus factor │
+/// ╞════════════╪═══════════╪═════════╪════════════╪═══════╪════════════╡
+/// │ .          ┆       412 ┆      14 ┆ Ada        ┆   38% ┆          2 │
+/// ├╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌╌╌┤
+/// │ src/legacy ┆        86 ┆       3 ┆ Grace      ┆   92% ┆          1 │
+/// └────────────┴───────────┴─────────┴────────────┴───────┴────────────┘
+///
+/// 1 directories with a bus factor of 1: src/legacy
+/// ```
+pub(crate) fn format_ownership(
+    directories: &[DirectoryOwnership],
+    format: OutputFormat,
+    tables: TableStyle,
+) -> String {
+    if format == OutputFormat::Json {
+        return serde_json::to_string_pretty(directories)
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"));
+    }
+
+    if directories.is_empty() {
+        return "No committed functions found".to_string();
+    }
+
+    let mut table = new_table(
+        &[
+            "Directory",
+            "Functions",
+            "Authors",
+            "Top author",
+            "Share",
+            "Bus factor",
+        ],
+        1,
+        tables,
+    );
+    for directory in directories {
+        table.add_row([
+            directory.path.display().to_string(),
+            directory.functions.to_string(),
+            directory.authors.to_string(),
+            directory.top_author.clone(),
+            format!("{:.0}%", directory.top_share),
+            directory.bus_factor.to_string(),
+        ]);
+    }
+    // The top author's name is text among the numbers
+    if let Some(column) = table.column_mut(3) {
+        column.set_cell_alignment(comfy_table::CellAlignment::Left);
+    }
+
+    let at_risk: Vec<String> = directories
+        .iter()
+        .filter(|directory| directory.at_risk())
+        .map(|directory| directory.path.display().to_string())
+        .collect();
+    // The directory, its top author and bus factor are kept
+    let table = render_table(table, &[2, 1, 4]);
+    if at_risk.is_empty() {
+        format!("{table}\n\nNo directories with a bus factor of 1")
+    } else {
+        format!(
+            "{table}\n\n{} directories with a bus factor of 1: {}",
+            at_risk.len(),
+            at_risk.join(", ")
+        )
+    }
+}
+
+/// Formats found symbols as `path:line:column: kind name`, one per line, the
+/// locations editors and terminals jump to.
+///
+/// JSON serializes the symbols as an array.
+///
+/// # Output Format
+///
+/// ```text
+/// src/parser.rs:42:1: function parse
+/// src/parser.rs:96:5: function parse
+/// src/cli.rs:12:1: class Parser
+/// ```
+pub(crate) fn format_symbols(matches: &[SymbolMatch], name: &str, format: OutputFormat) -> String {
+    if format == OutputFormat::Json {
+        return serde_json::to_string_pretty(matches)
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"));
+    }
+
+    if matches.is_empty() {
+        return format!("No symbols named {name} found");
+    }
+    matches
+        .iter()
+        .map(|found| {
+            let symbol = &found.symbol;
+            format!(
+                "{}:{}:{}: {} {}",
+                found.path.display(),
+                symbol.line,
+                symbol.column,
+                symbol.kind.name(),
+                symbol.name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats the statistics of sampled commits as a time series.
+///
+/// CSV has one row per commit and language, oldest commit first; JSON lists
+/// the commits with their totals and per-language counts.
+///
+/// # Output Format
+///
+/// ```text
+/// commit,date,language,files,functions,classes
+/// 3f2a...,2024-05-01T12:00:00+02:00,Python,4,31,2
+/// 3f2a...,2024-05-01T12:00:00+02:00,Rust,12,140,25
+/// 9c1d...,2024-06-01T09:30:00+02:00,Rust,13,152,26
+/// ```
+pub(crate) fn format_history(points: &[HistoryPoint], format: HistoryFormat) -> String {
+    match format {
+        HistoryFormat::Json => serde_json::to_string_pretty(points)
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}")),
+        HistoryFormat::Csv => {
+            let mut output = String::from("commit,date,language,files,functions,classes");
+            for point in points {
+                for trend in &point.languages {
+                    output.push_str(&format!(
+                        "\n{},{},{},{},{},{}",
+                        point.commit,
+                        point.date,
+                        trend.language.name(),
+                        trend.counts.files,
+                        trend.counts.functions,
+                        trend.counts.classes
+                    ));
+                }
+            }
+            output
+        }
+    }
+}
+
+/// Formats how the supported languages are recognized and counted.
+///
+/// JSON lists the same fields for every language.
+///
+/// # Output Format
+///
+/// ```text
+/// Rust
+///   Extensions:    .rs
+///   Magika labels: rust
+///   class:         struct_item
+///   function:      function_item
+/// ```
+pub(crate) fn format_languages(languages: &[LanguageCoverage], format: OutputFormat) -> String {
+    if format == OutputFormat::Json {
+        return serde_json::to_string_pretty(languages)
+            .unwrap_or_else(|e| format!("Error serializing to JSON: {e}"));
+    }
+
+    let mut output = String::new();
+    for language in languages {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        let extensions: Vec<String> = language
+            .extensions
+            .iter()
+            .map(|extension| format!(".{extension}"))
+            .collect();
+        output.push_str(&format!(
+            "{}\n  {:15}{}\n  {:15}{}\n",
+            language.name,
+            "Extensions:",
+            extensions.join(" "),
+            "Magika labels:",
+            language.magika_labels.join(" ")
+        ));
+        for (capture, kinds) in &language.node_kinds {
+            output.push_str(&format!(
+                "  {:15}{}\n",
+                format!("{capture}:"),
+                kinds.join(" ")
+            ));
+        }
+    }
+    output
+}
+
+/// Formats the changed counts of a language or the total, or `no changes`.
+fn format_count_deltas(counts: &CountDeltas) -> String {
+    if !counts.changed() {
+        return "no changes".to_string();
+    }
+    format_deltas(&[
+        (counts.files, "files"),
+        (counts.functions, "functions"),
+        (counts.classes, "structs/classes"),
+        (counts.code_lines, "code lines"),
+    ])
+}
+
+/// Formats each changed count as `+5 functions (40 -> 45)`.
+fn format_deltas(deltas: &[(Delta, &str)]) -> String {
+    deltas
+        .iter()
+        .filter(|(delta, _)| delta.changed())
+        .map(|(delta, label)| {
+            format!(
+                "{:+} {label} ({} -> {})",
+                delta.change(),
+                delta.before,
+                delta.after
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats one side of a file's counts, for files that exist on one side only.
+fn format_file_counts(file: &FileDelta, side: fn(&Delta) -> usize) -> String {
+    format!(
+        "{} functions, {} structs/classes, {} code lines",
+        side(&file.functions),
+        side(&file.classes),
+        side(&file.code_lines)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Creates a sample DirectoryStats for testing purposes.
+    ///
+    /// This helper function sets up realistic test data with multiple files
     /// across different programming languages to verify formatting behavior.
     fn create_test_directory_stats() -> DirectoryStats {
         let mut stats = DirectoryStats::new();
 
-        stats.add_file(FileStats {
-            path: PathBuf::from("src/main.rs"),
+        stats.add_file(FileStats::new(
+            PathBuf::from("src/main.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 3,
+                class_struct_count: 2,
+                ..CodeStats::default()
+            },
+        ));
+
+        stats.add_file(FileStats::new(
+            PathBuf::from("src/lib.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 5,
+                class_struct_count: 1,
+                ..CodeStats::default()
+            },
+        ));
+
+        stats.add_file(FileStats::new(
+            PathBuf::from("test.py"),
+            SupportedLanguage::Python,
+            CodeStats {
+                function_count: 2,
+                class_struct_count: 1,
+                ..CodeStats::default()
+            },
+        ));
+
+        stats
+    }
+
+    /// Tests single file formatting output.
+    ///
+    /// Verifies that format_single_file produces the expected content including
+    /// file path, language detection, and statistical counts.
+    #[test]
+    fn test_format_single_file() {
+        let file_stats = FileStats::new(
+            PathBuf::from("test.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 10,
+                class_struct_count: 5,
+                ..CodeStats::default()
+            },
+        );
+
+        let output = format_single_file(&file_stats, &FormatOptions::default());
+
+        assert!(output.contains("Analyzing file: test.rs"));
+        assert!(output.contains("Language: Rust"));
+        assert!(output.contains("Functions: 10"));
+        assert!(output.contains("Classes/Structs: 5"));
+    }
+
+    /// Tests summary format output structure and content.
+    ///
+    /// Validates that format_summary correctly aggregates statistics by language,
+    /// sorts languages alphabetically, and includes accurate totals.
+    #[test]
+    fn test_format_summary() {
+        let stats = create_test_directory_stats();
+        let output = format_summary(&stats, &FormatOptions::default());
+
+        // Check structure
+        assert!(output.contains("Language Summary:"));
+        assert!(output.contains("Total:"));
+
+        // Check language stats
+        assert!(output.contains("Rust:"));
+        assert!(output.contains("8 functions")); // 3 + 5
+        assert!(output.contains("3 structs/classes")); // 2 + 1
+        assert!(output.contains("in 2 files"));
+
+        assert!(output.contains("Python:"));
+        assert!(output.contains("2 functions"));
+        assert!(output.contains("1 structs/classes"));
+        assert!(output.contains("in 1 files"));
+
+        // Check each language's share
+        assert!(output.contains("( 66.7% of files,  80.0% of functions)"));
+        assert!(output.contains("( 33.3% of files,  20.0% of functions)"));
+
+        // Check totals
+        assert!(output.contains("Total: 10 functions, 4 structs/classes in 3 files"));
+    }
+
+    /// Tests detailed format output including individual files and summary.
+    ///
+    /// Ensures that format_detail displays each file's statistics separately
+    /// and includes the summary section at the end.
+    #[test]
+    fn test_format_detail() {
+        let stats = create_test_directory_stats();
+        let output = format_detail(&stats, &FormatOptions::default());
+
+        // Check individual file details
+        assert!(output.contains("src/lib.rs (Rust):"));
+        assert!(output.contains("src/main.rs (Rust):"));
+        assert!(output.contains("test.py (Python):"));
+
+        // Should also include summary
+        assert!(output.contains("Language Summary:"));
+        assert!(output.contains("Total:"));
+    }
+
+    #[test]
+    fn test_sorting() {
+        let stats = create_test_directory_stats();
+        let position = |output: &str, needle: &str| output.find(needle).unwrap();
+
+        // Alphabetical by default
+        let output = format_detail(&stats, &FormatOptions::default());
+        assert!(position(&output, "  Python:") < position(&output, "  Rust:"));
+        assert!(position(&output, "src/lib.rs (") < position(&output, "src/main.rs ("));
+
+        // Counts rank the largest first unless the order is given
+        let sorted = |sorting| FormatOptions {
+            sorting,
+            ..FormatOptions::default()
+        };
+        let output = format_detail(&stats, &sorted(Sorting::new(SortKey::Functions, None)));
+        assert!(position(&output, "  Rust:") < position(&output, "  Python:"));
+        assert!(position(&output, "src/lib.rs (") < position(&output, "src/main.rs ("));
+        assert!(position(&output, "src/main.rs (") < position(&output, "test.py ("));
+
+        let sorting = Sorting::new(SortKey::Classes, Some(SortOrder::Asc));
+        let output = format_detail(&stats, &sorted(sorting));
+        assert!(position(&output, "  Python:") < position(&output, "  Rust:"));
+        // Files with equal counts stay in path order
+        assert!(position(&output, "src/lib.rs (") < position(&output, "test.py ("));
+        assert!(position(&output, "test.py (") < position(&output, "src/main.rs ("));
+
+        let sorting = Sorting::new(SortKey::Name, Some(SortOrder::Desc));
+        let output = format_detail(&stats, &sorted(sorting));
+        assert!(position(&output, "test.py (") < position(&output, "src/main.rs ("));
+        assert!(position(&output, "  Rust:") < position(&output, "  Python:"));
+    }
+
+    /// Tests JSON format serialization and structure.
+    ///
+    /// Verifies that format_json produces valid JSON with the expected
+    /// structure and correct data values.
+    #[test]
+    fn test_format_json() {
+        let stats = create_test_directory_stats();
+        let output = format_json(&stats, None, false);
+
+        // Parse JSON to verify it's valid
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        // Check structure
+        assert!(parsed.get("files").is_some());
+        assert!(parsed.get("total_by_language").is_some());
+        assert!(parsed.get("total_stats").is_some());
+
+        // Check file count
+        let files = parsed["files"].as_array().unwrap();
+        assert_eq!(files.len(), 3);
+
+        // Check total stats
+        assert_eq!(parsed["total_stats"]["function_count"], 10);
+        assert_eq!(parsed["total_stats"]["class_struct_count"], 4);
+    }
+
+    /// Tests that compact JSON is one line holding the same document.
+    #[test]
+    fn test_format_json_compact() {
+        let stats = create_test_directory_stats();
+        let options = FormatOptions {
+            compact: true,
+            ..FormatOptions::default()
+        };
+
+        for format in [OutputFormat::Json, OutputFormat::ClocJson] {
+            let compact = format_output(&stats, format, &options);
+            let pretty = format_output(&stats, format, &FormatOptions::default());
+            assert!(!compact.contains('\n'));
+            assert!(pretty.contains('\n'));
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&compact).unwrap(),
+                serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+            );
+        }
+    }
+
+    /// Tests the main format_output function with all supported formats.
+    ///
+    /// Validates that the format dispatcher correctly routes to the appropriate
+    /// formatting function based on the OutputFormat parameter.
+    #[test]
+    fn test_format_output_with_different_formats() {
+        let stats = create_test_directory_stats();
+
+        let summary = format_output(&stats, OutputFormat::Summary, &FormatOptions::default());
+        assert!(summary.contains("Language Summary:"));
+        assert!(!summary.contains("src/main.rs"));
+
+        let detail = format_output(&stats, OutputFormat::Detail, &FormatOptions::default());
+        assert!(detail.contains("src/main.rs"));
+        assert!(detail.contains("Language Summary:"));
+
+        let json = format_output(&stats, OutputFormat::Json, &FormatOptions::default());
+        assert!(json.starts_with('{'));
+        assert!(json.contains("\"files\""));
+    }
+
+    /// Tests formatting behavior with empty statistics.
+    ///
+    /// Ensures that all formatters handle the edge case of no analyzed files
+    /// gracefully, displaying appropriate zero values.
+    #[test]
+    fn test_format_empty_stats() {
+        let stats = DirectoryStats::new();
+
+        let summary = format_summary(&stats, &FormatOptions::default());
+        assert!(summary.contains("Total: 0 functions, 0 structs/classes in 0 files"));
+
+        let detail = format_detail(&stats, &FormatOptions::default());
+        assert!(detail.contains("Total: 0 functions, 0 structs/classes in 0 files"));
+
+        let json = format_json(&stats, None, false);
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["files"].as_array().unwrap().len(), 0);
+    }
+
+    /// Tests that languages are sorted alphabetically in summary output.
+    ///
+    /// Verifies the alphabetical ordering requirement by adding languages
+    /// in non-alphabetical order and checking their position in the output.
+    #[test]
+    fn test_format_language_sorting() {
+        let mut stats = DirectoryStats::new();
+
+        // Add files in non-alphabetical order
+        stats.add_file(FileStats::new(
+            PathBuf::from("test.py"),
+            SupportedLanguage::Python,
+            CodeStats {
+                function_count: 1,
+                class_struct_count: 0,
+                ..CodeStats::default()
+            },
+        ));
+
+        stats.add_file(FileStats::new(
+            PathBuf::from("test.go"),
+            SupportedLanguage::Go,
+            CodeStats {
+                function_count: 1,
+                class_struct_count: 0,
+                ..CodeStats::default()
+            },
+        ));
+
+        stats.add_file(FileStats::new(
+            PathBuf::from("test.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 1,
+                class_struct_count: 0,
+                ..CodeStats::default()
+            },
+        ));
+
+        let output = format_summary(&stats, &FormatOptions::default());
+
+        // Languages should be sorted alphabetically
+        let go_pos = output.find("Go:").unwrap();
+        let python_pos = output.find("Python:").unwrap();
+        let rust_pos = output.find("Rust:").unwrap();
+
+        assert!(go_pos < python_pos);
+        assert!(python_pos < rust_pos);
+    }
+
+    /// Tests license audit formatting for per-language counts and violations.
+    ///
+    /// Verifies that violating files are listed and that totals reflect
+    /// every checked file.
+    #[test]
+    fn test_format_license_audit() {
+        use crate::audit::{HeaderViolation, LanguageAudit};
+
+        let mut audit = LicenseAudit::default();
+        audit.by_language.insert(
+            SupportedLanguage::Rust,
+            LanguageAudit {
+                file_count: 2,
+                violation_count: 1,
+            },
+        );
+        audit.by_language.insert(
+            SupportedLanguage::Go,
+            LanguageAudit {
+                file_count: 1,
+                violation_count: 0,
+            },
+        );
+        audit.violations.push(HeaderViolation {
+            path: PathBuf::from("src/lib.rs"),
             language: SupportedLanguage::Rust,
-            stats: CodeStats {
-                function_count: 3,
-                class_struct_count: 2,
+            reason: ViolationReason::MissingHeader,
+        });
+
+        let output = format_license_audit(&audit);
+
+        assert!(output.contains("License Header Audit:"));
+        assert!(output.contains("Missing header:\n  src/lib.rs (Rust)"));
+        assert!(output.contains("Total: 1 of 3 files missing header"));
+        assert!(output.find("Go:").unwrap() < output.find("Rust:").unwrap());
+    }
+
+    /// Tests that secret matches are listed with file and line.
+    ///
+    /// Verifies the section is omitted entirely when nothing matched.
+    #[test]
+    fn test_format_secrets_section() {
+        use crate::secrets::SecretMatch;
+
+        let mut stats = create_test_directory_stats();
+        assert!(!format_summary(&stats, &FormatOptions::default()).contains("Potential secrets:"));
+
+        stats.files[0].secrets.push(SecretMatch {
+            pattern: "aws-access-key-id".to_string(),
+            line: 12,
+            column: 17,
+        });
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains("Potential secrets:\n  src/main.rs:12:17: aws-access-key-id"));
+        assert!(
+            format_single_file(&stats.files[0], &FormatOptions::default())
+                .contains("src/main.rs:12:17")
+        );
+    }
+
+    /// Tests that suspicious files are listed, marking those left out of the totals.
+    #[test]
+    fn test_format_suspicious_section() {
+        use crate::obfuscation::{Signal, Suspicion};
+
+        let mut stats = create_test_directory_stats();
+        assert!(
+            !format_summary(&stats, &FormatOptions::default()).contains("Possibly obfuscated:")
+        );
+
+        let suspicion = Suspicion {
+            signals: vec![Signal::ShortIdentifiers],
+            entropy: 5.214,
+            mean_identifier_length: 1.84,
+        };
+        stats.files[0].suspicion = Some(suspicion.clone());
+        let mut excluded = FileStats::new(
+            PathBuf::from("vendor/bundle.js"),
+            SupportedLanguage::JavaScript,
+            CodeStats::default(),
+        );
+        excluded.suspicion = Some(Suspicion {
+            signals: vec![Signal::HighEntropy, Signal::ShortIdentifiers],
+            ..suspicion
+        });
+        stats.excluded_files.push(excluded);
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains(
+            "Possibly obfuscated:\n  \
+             src/main.rs: short identifiers (entropy 5.21 bits/char, mean identifier length 1.8)\n  \
+             vendor/bundle.js: high entropy, short identifiers (entropy 5.21 bits/char, mean identifier length 1.8), excluded from totals"
+        ));
+        assert!(
+            format_single_file(&stats.files[0], &FormatOptions::default())
+                .contains("Possibly obfuscated:\n  src/main.rs: short identifiers")
+        );
+    }
+
+    /// Tests that each language's documented functions are shown with the coverage.
+    #[test]
+    fn test_format_summary_documentation_coverage() {
+        let mut stats = DirectoryStats::new();
+        stats.add_file(FileStats::new(
+            PathBuf::from("src/lib.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 8,
+                documented_function_count: 6,
+                ..CodeStats::default()
+            },
+        ));
+        stats.add_file(FileStats::new(
+            PathBuf::from("main.go"),
+            SupportedLanguage::Go,
+            CodeStats::default(),
+        ));
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains(
+            "Documentation Coverage:\n  \
+             Go:             0 of    0 functions documented (  0.0%)\n  \
+             Rust:           6 of    8 functions documented ( 75.0%)\n"
+        ));
+    }
+
+    /// Tests that the API surface lists only languages with visibility.
+    #[test]
+    fn test_format_summary_api_surface() {
+        let mut stats = create_test_directory_stats();
+        assert!(!format_summary(&stats, &FormatOptions::default()).contains("API Surface:"));
+
+        stats.add_file(FileStats::new(
+            PathBuf::from("src/api.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                public_function_count: 3,
+                private_function_count: 12,
+                public_type_count: 2,
+                ..CodeStats::default()
+            },
+        ));
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains(
+            "API Surface:\n  \
+             Rust:           3 public,   12 private functions;    2 public,    0 private types\n\n"
+        ));
+    }
+
+    /// Tests that vendored and generated files are counted by origin and tagged in detail output.
+    #[test]
+    fn test_format_origins_section() {
+        let mut stats = create_test_directory_stats();
+        assert!(!format_summary(&stats, &FormatOptions::default()).contains("Vendored and"));
+
+        stats.files[2].origin = Some(Origin::Generated);
+        stats.files[2].stats.code_lines = 40;
+        let mut vendored = FileStats::new(
+            PathBuf::from("vendor/lib.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                code_lines: 12,
+                ..CodeStats::default()
+            },
+        );
+        vendored.origin = Some(Origin::Vendored);
+        stats.set_aside_files.push(vendored);
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains(
+            "Vendored and generated code:\n  \
+             Vendored: 1 files, 12 code lines, excluded from totals\n  \
+             Generated: 1 files, 40 code lines"
+        ));
+        assert!(
+            format_detail(&stats, &FormatOptions::default())
+                .contains("test.py (Python, generated):")
+        );
+    }
+
+    /// Tests that production and test code are reported separately once tests exist.
+    #[test]
+    fn test_format_test_split_section() {
+        let mut stats = create_test_directory_stats();
+        assert!(!format_summary(&stats, &FormatOptions::default()).contains("Production and"));
+
+        let mut test = FileStats::new(
+            PathBuf::from("test_app.py"),
+            SupportedLanguage::Python,
+            CodeStats {
+                function_count: 2,
+                code_lines: 10,
+                ..CodeStats::default()
+            },
+        );
+        test.is_test = true;
+        stats.add_file(test);
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains(
+            "Production and test code:\n  \
+             Production: 3 files, 10 functions, 0 code lines\n  \
+             Test: 1 files, 2 functions, 10 code lines (100.0% of code lines)"
+        ));
+
+        stats.files[0].stats.code_lines = 12000;
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains("Production: 3 files, 10 functions, 12,000 code lines"));
+        let options = FormatOptions {
+            numbers: NumberStyle::plain(),
+            ..FormatOptions::default()
+        };
+        assert!(
+            format_summary(&stats, &options)
+                .contains("Production: 3 files, 10 functions, 12000 code lines")
+        );
+    }
+
+    /// Tests that parameter and nesting signals appear in the totals and per file.
+    #[test]
+    fn test_format_function_shape() {
+        let mut stats = DirectoryStats::new();
+        stats.add_file(FileStats::new(
+            PathBuf::from("src/lib.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 4,
+                parameter_count: 9,
+                many_parameter_function_count: 1,
+                max_nesting_depth: 3,
+                ..CodeStats::default()
             },
+        ));
+
+        let expected = "Parameters: 2.2 per function on average, 1 functions with more than 5\n\
+                        Maximum nesting depth: 3";
+        assert!(format_summary(&stats, &FormatOptions::default()).contains(expected));
+        assert!(
+            format_detail(&stats, &FormatOptions::default()).contains(
+                &expected
+                    .replace("Parameters", "  Parameters")
+                    .replace("\nMax", "\n  Max")
+            )
+        );
+        assert!(format_function_shape(&CodeStats::default(), "").is_empty());
+    }
+
+    /// Tests that extended metrics are listed per file only when computed.
+    #[test]
+    fn test_format_extended_metrics() {
+        use crate::metrics::ExtendedMetrics;
+
+        let mut file = FileStats::new(
+            PathBuf::from("src/lib.rs"),
+            SupportedLanguage::Rust,
+            CodeStats::default(),
+        );
+        let mut stats = DirectoryStats::new();
+        stats.add_file(file.clone());
+        assert!(!format_detail(&stats, &FormatOptions::default()).contains("Halstead"));
+
+        file.metrics = Some(ExtendedMetrics {
+            distinct_operators: 8,
+            distinct_operands: 4,
+            total_operators: 8,
+            total_operands: 6,
+            volume: 50.19,
+            cyclomatic_complexity: 1,
+            maintainability_index: 88.07,
         });
+        let mut stats = DirectoryStats::new();
+        stats.add_file(file);
+        assert!(format_detail(&stats, &FormatOptions::default()).contains(
+            "\n  Halstead volume: 50.2 (14 operators and operands, 12 distinct)\n  \
+                 Maintainability index: 88.1 (cyclomatic complexity 1)"
+        ));
+        let json = format_output(&stats, OutputFormat::Json, &FormatOptions::default());
+        assert!(json.contains("\"maintainability_index\": 88.07"));
+    }
+
+    /// Tests that the average function length is shown and capped functions are listed.
+    #[test]
+    fn test_format_function_length_and_oversized_functions() {
+        use crate::parser::OversizedFunction;
 
-        stats.add_file(FileStats {
-            path: PathBuf::from("src/lib.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
-                function_count: 5,
-                class_struct_count: 1,
+        let mut stats = DirectoryStats::new();
+        let mut file_stats = FileStats::new(
+            PathBuf::from("src/tables.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 4,
+                function_lines: 2030,
+                oversized_function_count: 1,
+                ..CodeStats::default()
             },
+        );
+        file_stats.oversized_functions.push(OversizedFunction {
+            line: 12,
+            lines: 20412,
+            counted_lines: 2000,
         });
+        stats.add_file(file_stats);
 
-        stats.add_file(FileStats {
-            path: PathBuf::from("test.py"),
-            language: SupportedLanguage::Python,
-            stats: CodeStats {
-                function_count: 2,
-                class_struct_count: 1,
-            },
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(
+            output
+                .contains("\nAverage function length: 507.5 lines (1 oversized functions capped)")
+        );
+        assert!(
+            output.contains(
+                "Oversized functions:\n  src/tables.rs:12: 20412 lines (counted as 2000)"
+            )
+        );
+        assert!(
+            format_single_file(&stats.files[0], &FormatOptions::default())
+                .contains("Oversized functions:\n  src/tables.rs:12:")
+        );
+
+        // Without functions there is nothing to average
+        assert!(
+            !format_summary(&DirectoryStats::new(), &FormatOptions::default())
+                .contains("Average function length")
+        );
+    }
+
+    /// Tests that parse errors are shown per file and high ratios are flagged.
+    #[test]
+    fn test_format_parse_errors() {
+        let mut stats = DirectoryStats::new();
+        for (path, total_lines, parse_errors) in [("broken.rs", 20, 3), ("long.rs", 1000, 1)] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats {
+                    total_lines,
+                    parse_errors,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let output = format_detail(&stats, &FormatOptions::default());
+        assert!(output.contains("blank)\n  Parse errors: 3 (results unreliable)\n\n"));
+        assert!(output.contains("blank)\n  Parse errors: 1\n\n"));
+        assert!(
+            format_single_file(&stats.files[0], &FormatOptions::default())
+                .contains("\nParse errors: 3 (results unreliable)")
+        );
+
+        // Clean files have no parse error line
+        let output = format_detail(&create_test_directory_stats(), &FormatOptions::default());
+        assert!(!output.contains("Parse errors"));
+    }
+
+    /// Tests that counts of user-supplied queries are listed per file and in total.
+    #[test]
+    fn test_format_query_counts() {
+        let mut stats = DirectoryStats::new();
+        for (path, macros) in [("a.rs", 2), ("b.rs", 3)] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats {
+                    query_counts: [("macros".to_string(), macros), ("hooks".to_string(), 0)].into(),
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let output = format_detail(&stats, &FormatOptions::default());
+        assert!(output.contains("blank)\n  Query matches: hooks 0, macros 2\n\n"));
+        assert!(output.contains("\nQuery matches: hooks 0, macros 5"));
+        assert!(
+            !format_summary(&create_test_directory_stats(), &FormatOptions::default())
+                .contains("Query matches")
+        );
+    }
+
+    /// Tests that interrupted runs are marked as partial in every format.
+    #[test]
+    fn test_format_output_marks_interrupted_runs() {
+        let mut stats = create_test_directory_stats();
+        let options = FormatOptions::default();
+        assert!(!format_output(&stats, OutputFormat::Summary, &options).contains("Partial report"));
+
+        stats.interrupted = true;
+        for format in [OutputFormat::Summary, OutputFormat::Detail] {
+            assert!(
+                format_output(&stats, format, &options)
+                    .starts_with("Partial report: analysis was interrupted after 3 files\n\n")
+            );
+        }
+        assert!(format_json(&stats, None, false).contains("\"interrupted\": true"));
+    }
+
+    /// Tests that text reports start with their manifest once it is known.
+    #[test]
+    fn test_format_output_starts_with_report_header() {
+        let mut stats = create_test_directory_stats();
+        stats.meta = Some(ReportMeta {
+            timestamp: Some(1_792_304_474),
+            ..ReportMeta::new(
+                PathBuf::from("/src/project"),
+                vec![".".to_string(), "--detail".to_string()],
+            )
         });
+        stats.interrupted = true;
+
+        let header = format!(
+            "Report of /src/project by code-stats-rs {} at 2026-10-18 06:21:14 UTC \
+             (options: . --detail)\n\nPartial report:",
+            env!("CARGO_PKG_VERSION")
+        );
+        for format in [
+            OutputFormat::Summary,
+            OutputFormat::Detail,
+            OutputFormat::Table,
+        ] {
+            assert!(format_output(&stats, format, &FormatOptions::default()).starts_with(&header));
+        }
+        assert!(format_json(&stats, None, false).contains("\"timestamp\": 1792304474"));
+
+        stats.meta.as_mut().unwrap().timestamp = None;
+        assert!(
+            format_output(&stats, OutputFormat::Summary, &FormatOptions::default()).starts_with(
+                &format!(
+                    "Report of /src/project by code-stats-rs {} (options: . --detail)\n",
+                    env!("CARGO_PKG_VERSION")
+                )
+            )
+        );
+
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_utc(1_767_225_599), "2025-12-31 23:59:59 UTC");
+    }
+
+    /// Tests that findings are counted in every format and listed by severity.
+    ///
+    /// The summary lists only warnings and errors, the detail view lists
+    /// skipped and errored files in their own section, and JSON carries every
+    /// finding plus the errored files.
+    #[test]
+    fn test_format_findings_section() {
+        use crate::error::CodeStatsError;
+        use crate::findings::Finding;
+        use std::path::PathBuf;
+
+        let mut stats = create_test_directory_stats();
+        let options = FormatOptions::default();
+        assert!(!format_output(&stats, OutputFormat::Summary, &options).contains("Findings:"));
+        assert!(!format_output(&stats, OutputFormat::Detail, &options).contains("Skipped/"));
+
+        // Skipped files alone add no findings section
+        stats.findings.push(Finding::skipped(
+            PathBuf::from("dump.rs"),
+            ErrorKind::TooLarge,
+            "20971520 bytes, larger than 10485760",
+        ));
+        stats.unsupported_files = 12;
+        assert!(!format_output(&stats, OutputFormat::Summary, &options).contains("Findings:"));
 
+        stats.findings.push(Finding::error(
+            Some(PathBuf::from("src/broken.rs")),
+            &CodeStatsError::io(
+                "src/broken.rs",
+                std::io::Error::new(std::io::ErrorKind::PermissionDenied, "Permission denied"),
+            ),
+        ));
         stats
+            .findings
+            .push(Finding::new(Severity::Warning, None, "cache not saved"));
+
+        let summary = format_output(&stats, OutputFormat::Summary, &options);
+        assert!(summary.contains(
+            "Findings: 1 errors, 1 warnings, 1 info\n  error: src/broken.rs: IO error: Permission denied\n  warning: cache not saved"
+        ));
+        assert!(!summary.contains("dump.rs"));
+
+        let detail = format_output(&stats, OutputFormat::Detail, &options);
+        assert!(detail.contains(
+            "Skipped/Errored files:\n  dump.rs (too_large): skipped: 20971520 bytes, larger than 10485760\n  src/broken.rs (io): IO error: Permission denied\n  12 files of unsupported types\n\nFindings: 1 errors, 1 warnings, 1 info\n  warning: cache not saved"
+        ));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_output(&stats, OutputFormat::Json, &options)).unwrap();
+        assert_eq!(json["findings"][1]["severity"], "error");
+        assert_eq!(json["findings"][1]["path"], "src/broken.rs");
+        assert_eq!(json["errors"].as_array().unwrap().len(), 1);
+        assert_eq!(json["errors"][0]["path"], "src/broken.rs");
+        assert_eq!(json["errors"][0]["kind"], "io");
+        assert_eq!(json["unsupported_files"], 12);
     }
 
-    /// Tests single file formatting output.
+    /// Tests the opt-in magic value section and its worst-offender ordering.
     ///
-    /// Verifies that format_single_file produces the expected content including
-    /// file path, language detection, and statistical counts.
+    /// Verifies that files are ranked by total magic values and that files
+    /// without any are left out.
     #[test]
-    fn test_format_single_file() {
-        let file_stats = FileStats {
-            path: PathBuf::from("test.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
-                function_count: 10,
-                class_struct_count: 5,
-            },
+    fn test_format_magic_values() {
+        let mut stats = DirectoryStats::new();
+        for (path, strings, numbers) in [("a.rs", 1, 1), ("b.rs", 5, 2), ("c.rs", 0, 0)] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats {
+                    magic_string_count: strings,
+                    magic_number_count: numbers,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let plain = format_output(&stats, OutputFormat::Summary, &FormatOptions::default());
+        assert!(!plain.contains("Magic Values:"));
+
+        let options = FormatOptions {
+            magic_values: true,
+            ..FormatOptions::default()
         };
+        let output = format_output(&stats, OutputFormat::Summary, &options);
+        assert!(output.contains("Total: 6 strings, 3 numbers outside constant declarations"));
+        assert!(output.contains("Worst offenders:\n  b.rs: 7 (5 strings, 2 numbers)\n  a.rs: 2"));
+        assert!(!output.contains("c.rs"));
 
-        let output = format_single_file(&file_stats);
+        let json = format_output(&stats, OutputFormat::Json, &options);
+        assert!(!json.contains("Magic Values:"));
+        assert!(json.contains("\"magic_string_count\": 6"));
+    }
 
-        assert!(output.contains("Analyzing file: test.rs"));
-        assert!(output.contains("Language: Rust"));
-        assert!(output.contains("Functions: 10"));
-        assert!(output.contains("Classes/Structs: 5"));
+    /// Tests the opt-in hotspot section.
+    ///
+    /// Verifies that files are ranked by function count, cut at the requested
+    /// number, and that files without functions are left out.
+    #[test]
+    fn test_format_top_files() {
+        let mut stats = DirectoryStats::new();
+        for (path, functions) in [("a.rs", 2), ("b.rs", 5), ("c.rs", 0), ("d.rs", 2)] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats {
+                    function_count: functions,
+                    function_lines: functions * 3,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let plain = format_output(&stats, OutputFormat::Summary, &FormatOptions::default());
+        assert!(!plain.contains("files by functions"));
+
+        let options = FormatOptions {
+            top: Some(2),
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Summary, &options);
+        assert!(output.ends_with(
+            "Top 2 files by functions:\n  \
+             b.rs: 5 functions (avg 3.0 lines)\n  \
+             a.rs: 2 functions (avg 3.0 lines)"
+        ));
+
+        let options = FormatOptions {
+            top: Some(10),
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Summary, &options);
+        assert!(output.contains("d.rs: 2 functions"));
+        assert!(!output.contains("c.rs"));
+
+        let json = format_output(&stats, OutputFormat::Json, &options);
+        assert!(!json.contains("files by functions"));
     }
 
-    /// Tests summary format output structure and content.
+    /// Tests the opt-in duplicate function name section.
     ///
-    /// Validates that format_summary correctly aggregates statistics by language,
-    /// sorts languages alphabetically, and includes accurate totals.
+    /// Verifies that only names defined at least the given number of times are
+    /// listed, most frequent first, with every definition.
     #[test]
-    fn test_format_summary() {
-        let stats = create_test_directory_stats();
-        let output = format_summary(&stats);
+    fn test_format_duplicate_names() {
+        use crate::functions::FunctionName;
 
-        // Check structure
-        assert!(output.contains("Language Summary:"));
-        assert!(output.contains("Total:"));
+        let mut stats = DirectoryStats::new();
+        for (path, names) in [
+            ("a.rs", vec![("parse", 3), ("new", 10)]),
+            ("b.rs", vec![("new", 1), ("parse", 8)]),
+            ("c.rs", vec![("new", 4), ("run", 2)]),
+        ] {
+            let mut file = FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats::default(),
+            );
+            file.functions = names
+                .into_iter()
+                .map(|(name, line)| FunctionName {
+                    name: name.to_string(),
+                    line,
+                })
+                .collect();
+            stats.add_file(file);
+        }
 
-        // Check language stats
-        assert!(output.contains("Rust:"));
-        assert!(output.contains("8 functions")); // 3 + 5
-        assert!(output.contains("3 structs/classes")); // 2 + 1
-        assert!(output.contains("in 2 files"));
+        let plain = format_output(&stats, OutputFormat::Summary, &FormatOptions::default());
+        assert!(!plain.contains("Function names defined"));
 
-        assert!(output.contains("Python:"));
-        assert!(output.contains("2 functions"));
-        assert!(output.contains("1 structs/classes"));
-        assert!(output.contains("in 1 files"));
+        let options = FormatOptions {
+            duplicates: Some(2),
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Summary, &options);
+        assert!(output.ends_with(
+            "Function names defined in 2 or more places:\n  \
+             new: 3 definitions\n    \
+             a.rs:10\n    \
+             b.rs:1\n    \
+             c.rs:4\n  \
+             parse: 2 definitions\n    \
+             a.rs:3\n    \
+             b.rs:8"
+        ));
 
-        // Check totals
-        assert!(output.contains("Total: 10 functions, 4 structs/classes in 3 files"));
+        let options = FormatOptions {
+            duplicates: Some(4),
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Summary, &options);
+        assert!(output.ends_with("Function names defined in 4 or more places:\n  (none found)"));
     }
 
-    /// Tests detailed format output including individual files and summary.
+    /// Tests the opt-in untranslated string section.
     ///
-    /// Ensures that format_detail displays each file's statistics separately
-    /// and includes the summary section at the end.
+    /// Verifies that every file with untranslated strings is listed, most first.
     #[test]
-    fn test_format_detail() {
-        let stats = create_test_directory_stats();
-        let output = format_detail(&stats);
+    fn test_format_untranslated_strings() {
+        let mut stats = DirectoryStats::new();
+        for (path, count) in [("a.js", 2), ("b.js", 4), ("c.js", 0)] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::JavaScript,
+                CodeStats {
+                    untranslated_string_count: count,
+                    ..CodeStats::default()
+                },
+            ));
+        }
 
-        // Check individual file details
-        assert!(output.contains("src/lib.rs (Rust):"));
-        assert!(output.contains("src/main.rs (Rust):"));
-        assert!(output.contains("test.py (Python):"));
+        let plain = format_output(&stats, OutputFormat::Summary, &FormatOptions::default());
+        assert!(!plain.contains("Untranslated Strings:"));
 
-        // Should also include summary
-        assert!(output.contains("Language Summary:"));
-        assert!(output.contains("Total:"));
+        let options = FormatOptions {
+            i18n: true,
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Detail, &options);
+        assert!(
+            output.contains("Untranslated Strings:\n  Total: 6 in 2 files\n  b.js: 4\n  a.js: 2")
+        );
+        assert!(!output.contains("c.js: 0"));
     }
 
-    /// Tests JSON format serialization and structure.
+    /// Tests that pagination limits the per-file listing but not the totals.
     ///
-    /// Verifies that format_json produces valid JSON with the expected
-    /// structure and correct data values.
+    /// Files are paged in path order (src/lib.rs, src/main.rs, test.py).
     #[test]
-    fn test_format_json() {
+    fn test_format_detail_pagination() {
         let stats = create_test_directory_stats();
-        let output = format_json(&stats);
+        let paged = |page| FormatOptions {
+            pagination: Some(Pagination { limit: 2, page }),
+            ..FormatOptions::default()
+        };
 
-        // Parse JSON to verify it's valid
-        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let first = format_detail(&stats, &paged(1));
+        assert!(first.contains("src/lib.rs (Rust):"));
+        assert!(first.contains("src/main.rs (Rust):"));
+        assert!(!first.contains("test.py (Python):"));
+        assert!(first.contains("Showing files 1-2 of 3 (page 1 of 2)"));
+        assert!(first.contains("Total: 10 functions, 4 structs/classes in 3 files"));
 
-        // Check structure
-        assert!(parsed.get("files").is_some());
-        assert!(parsed.get("total_by_language").is_some());
-        assert!(parsed.get("total_stats").is_some());
+        let second = format_detail(&stats, &paged(2));
+        assert!(second.contains("test.py (Python):"));
+        assert!(second.contains("Showing files 3-3 of 3 (page 2 of 2)"));
 
-        // Check file count
-        let files = parsed["files"].as_array().unwrap();
-        assert_eq!(files.len(), 3);
+        let past_end = format_detail(&stats, &paged(5));
+        assert!(past_end.contains("No files on page 5 (3 files, 2 pages)"));
+    }
 
-        // Check total stats
+    /// Tests that paginated JSON keeps the regular shape with a subset of files.
+    #[test]
+    fn test_format_json_pagination() {
+        let stats = create_test_directory_stats();
+        let output = format_json(&stats, Some(Pagination { limit: 1, page: 2 }), false);
+
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let files = parsed["files"].as_array().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0]["path"], "src/main.rs");
         assert_eq!(parsed["total_stats"]["function_count"], 10);
-        assert_eq!(parsed["total_stats"]["class_struct_count"], 4);
     }
 
-    /// Tests the main format_output function with all supported formats.
-    ///
-    /// Validates that the format dispatcher correctly routes to the appropriate
-    /// formatting function based on the OutputFormat parameter.
     #[test]
-    fn test_format_output_with_different_formats() {
+    fn test_format_status_line() {
+        let status = RunStatus {
+            files: 3,
+            functions: 10,
+            classes: 4,
+            errors: 1,
+        };
+
+        assert_eq!(
+            format_status_line(&status, Duration::from_millis(1234)),
+            "code-stats: files=3 functions=10 classes=4 errors=1 duration_ms=1234"
+        );
+    }
+
+    #[test]
+    fn test_format_profile() {
+        use crate::profile::PhaseTimes;
+        use std::path::Path;
+
+        let mut profile = Profile::default();
+        profile.record(
+            Path::new("src/big.rs"),
+            PhaseTimes {
+                detection: Duration::from_micros(100),
+                reading: Duration::from_millis(2),
+                parsing: Duration::from_millis(40),
+            },
+        );
+        profile.record(Path::new("src/small.rs"), PhaseTimes::default());
+
+        let output = format_profile(&profile, 1);
+        assert_eq!(
+            output,
+            "Profile of 2 files:\n  \
+             Detection:                       0.1 ms\n  \
+             Reading:                         2.0 ms\n  \
+             Parsing:                        40.0 ms\n\n\
+             Slowest files:\n     \
+             42.1 ms  src/big.rs (detection 0.1 ms, reading 2.0 ms, parsing 40.0 ms)"
+        );
+
+        profile.batch_detection = Duration::from_millis(5);
+        assert!(
+            format_profile(&profile, 1).contains("\n  Content detection (batched):     5.0 ms\n")
+        );
+    }
+
+    /// Tests that YAML and TOML reports carry the same data as the JSON report.
+    #[test]
+    fn test_format_yaml_and_toml() {
         let stats = create_test_directory_stats();
+        let options = FormatOptions::default();
+        let json: serde_json::Value =
+            serde_json::from_str(&format_output(&stats, OutputFormat::Json, &options)).unwrap();
 
-        let summary = format_output(&stats, OutputFormat::Summary, false);
-        assert!(summary.contains("Language Summary:"));
-        assert!(!summary.contains("src/main.rs"));
+        let yaml = format_output(&stats, OutputFormat::Yaml, &options);
+        assert!(yaml.contains("total_stats:\n  function_count: 10\n"));
+        let from_yaml: serde_json::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(from_yaml, json);
 
-        let detail = format_output(&stats, OutputFormat::Detail, false);
-        assert!(detail.contains("src/main.rs"));
-        assert!(detail.contains("Language Summary:"));
+        let toml = format_output(&stats, OutputFormat::Toml, &options);
+        assert!(toml.contains("[[files]]\npath = \"src/main.rs\"\n"));
+        let from_toml: serde_json::Value = toml::from_str(&toml).unwrap();
+        assert_eq!(from_toml["total_stats"], json["total_stats"]);
+        assert_eq!(from_toml["files"], json["files"]);
+    }
 
-        let json = format_output(&stats, OutputFormat::Json, false);
-        assert!(json.starts_with('{'));
-        assert!(json.contains("\"files\""));
+    /// Tests that cloc-compatible output has one entry per language and their sum.
+    #[test]
+    fn test_format_cloc_json() {
+        let mut stats = DirectoryStats::new();
+        for (path, language, (code, comment, blank)) in [
+            ("src/main.rs", SupportedLanguage::Rust, (40, 10, 5)),
+            ("src/lib.rs", SupportedLanguage::Rust, (20, 4, 5)),
+            ("tool.py", SupportedLanguage::Python, (30, 2, 4)),
+        ] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                language,
+                CodeStats {
+                    total_lines: code + comment + blank,
+                    code_lines: code,
+                    comment_lines: comment,
+                    blank_lines: blank,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let output = format_output(&stats, OutputFormat::ClocJson, &FormatOptions::default());
+        let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            report["Rust"],
+            serde_json::json!({ "nFiles": 2, "blank": 10, "comment": 14, "code": 60 })
+        );
+        assert_eq!(
+            report["Python"],
+            serde_json::json!({ "nFiles": 1, "blank": 4, "comment": 2, "code": 30 })
+        );
+        assert_eq!(
+            report["SUM"],
+            serde_json::json!({ "nFiles": 3, "blank": 14, "comment": 16, "code": 90 })
+        );
+        assert_eq!(report["header"]["n_files"], 3);
+        assert_eq!(report["header"]["n_lines"], 120);
+        assert_eq!(report["header"]["program"], "code-stats-rs");
     }
 
-    /// Tests formatting behavior with empty statistics.
-    ///
-    /// Ensures that all formatters handle the edge case of no analyzed files
-    /// gracefully, displaying appropriate zero values.
+    /// Tests that streamed output matches the formatted string plus a newline.
     #[test]
-    fn test_format_empty_stats() {
-        let stats = DirectoryStats::new();
+    fn test_write_output_matches_format_output() {
+        let stats = create_test_directory_stats();
+        let options = FormatOptions::default();
 
-        let summary = format_summary(&stats);
-        assert!(summary.contains("Total: 0 functions, 0 structs/classes in 0 files"));
+        for format in [
+            OutputFormat::Summary,
+            OutputFormat::Detail,
+            OutputFormat::Json,
+            OutputFormat::Table,
+            OutputFormat::Yaml,
+            OutputFormat::Toml,
+            OutputFormat::ClocJson,
+        ] {
+            let mut buffer = Vec::new();
+            write_output(&stats, format, &options, &mut buffer).unwrap();
 
-        let detail = format_detail(&stats);
-        assert!(detail.contains("Total: 0 functions, 0 structs/classes in 0 files"));
+            let expected = format!("{}\n", format_output(&stats, format, &options));
+            assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+        }
+    }
 
-        let json = format_json(&stats);
-        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
-        assert_eq!(parsed["files"].as_array().unwrap().len(), 0);
+    #[test]
+    fn test_format_table() {
+        let stats = create_test_directory_stats();
+
+        let output = format_output(&stats, OutputFormat::Table, &FormatOptions::default());
+        let rows: Vec<_> = output.lines().collect();
+        assert!(rows[0].starts_with('┌'));
+        assert!(rows[1].starts_with("│ Language ┆ Files ┆ Files % ┆ Functions ┆ Functions %"));
+        assert!(rows[3].starts_with("│ Python   ┆     1 ┆    33.3 ┆         2 ┆        20.0"));
+        assert!(rows[5].starts_with("│ Rust     ┆     2 ┆    66.7 ┆         8 ┆        80.0"));
+        assert!(rows[7].starts_with("│ Total    ┆     3 ┆   100.0 ┆        10 ┆       100.0"));
+        assert!(!output.contains("src/lib.rs"));
+
+        let options = FormatOptions {
+            file_table: true,
+            pagination: Some(Pagination { limit: 1, page: 1 }),
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Table, &options);
+        assert!(output.contains("│ Path       ┆ Language ┆ Functions"));
+        assert!(output.contains("│ src/lib.rs ┆ Rust     ┆         5"));
+        assert!(!output.contains("src/main.rs"));
+        assert!(output.contains("Showing files 1-1 of 3 (page 1 of 3)"));
     }
 
-    /// Tests that languages are sorted alphabetically in summary output.
-    ///
-    /// Verifies the alphabetical ordering requirement by adding languages
-    /// in non-alphabetical order and checking their position in the output.
+    /// Tests that tables wrap to the given width and are colored on request only.
     #[test]
-    fn test_format_language_sorting() {
-        let mut stats = DirectoryStats::new();
+    fn test_format_table_style() {
+        let stats = create_test_directory_stats();
+        let table = |tables| {
+            let options = FormatOptions {
+                tables,
+                ..FormatOptions::default()
+            };
+            format_output(&stats, OutputFormat::Table, &options)
+        };
 
-        // Add files in non-alphabetical order
-        stats.add_file(FileStats {
-            path: PathBuf::from("test.py"),
-            language: SupportedLanguage::Python,
-            stats: CodeStats {
-                function_count: 1,
-                class_struct_count: 0,
-            },
+        // Not a terminal: neither wrapped nor colored
+        let output = table(TableStyle::default());
+        assert!(output.lines().next().unwrap().chars().count() > 150);
+        assert!(!output.contains('\x1b'));
+
+        let output = table(TableStyle {
+            width: Some(100),
+            color: None,
         });
+        let rows: Vec<_> = output.lines().take_while(|line| !line.is_empty()).collect();
+        assert!(rows.len() > 9, "cells are wrapped onto more lines");
+        assert!(rows.iter().all(|row| row.chars().count() <= 100));
 
-        stats.add_file(FileStats {
-            path: PathBuf::from("test.go"),
-            language: SupportedLanguage::Go,
-            stats: CodeStats {
-                function_count: 1,
-                class_struct_count: 0,
-            },
+        let output = table(TableStyle {
+            width: None,
+            color: Some(true),
         });
+        assert!(output.contains("\x1b[1m Language \x1b[0m"));
+    }
 
-        stats.add_file(FileStats {
-            path: PathBuf::from("test.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
-                function_count: 1,
-                class_struct_count: 0,
+    /// Tests that narrow tables hide optional columns instead of breaking headers.
+    #[test]
+    fn test_narrow_tables_hide_columns() {
+        let stats = create_test_directory_stats();
+        let options = FormatOptions {
+            tables: TableStyle {
+                width: Some(60),
+                color: Some(false),
             },
-        });
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Table, &options);
 
-        let output = format_summary(&stats);
+        let rows: Vec<_> = output
+            .lines()
+            .take_while(|line| !line.starts_with("Columns"))
+            .collect();
+        assert!(rows.iter().all(|row| row.chars().count() <= 60));
+        assert!(rows[1].contains("┆ Functions ┆"), "header: {}", rows[1]);
+        assert!(rows[1].contains("┆ Code │"), "header: {}", rows[1]);
+        assert!(rows[2].starts_with('╞'), "headers fit one line");
+        assert!(!rows[1].contains("Modules"));
+        assert!(output.contains("Columns hidden to fit a width of 60: Files %, "));
+        assert!(output.contains(", Modules, Comments, Blank\n"));
 
-        // Languages should be sorted alphabetically
-        let go_pos = output.find("Go:").unwrap();
-        let python_pos = output.find("Python:").unwrap();
-        let rust_pos = output.find("Rust:").unwrap();
+        // Tables that fit are rendered whole
+        let wide = FormatOptions {
+            tables: TableStyle {
+                width: Some(250),
+                color: Some(false),
+            },
+            ..FormatOptions::default()
+        };
+        let output = format_output(&stats, OutputFormat::Table, &wide);
+        assert!(output.contains("┆ Modules ┆"));
+        assert!(!output.contains("Columns hidden"));
+    }
 
-        assert!(go_pos < python_pos);
-        assert!(python_pos < rust_pos);
+    #[test]
+    fn test_color_choice() {
+        assert_eq!(color_choice(None, None, None), None);
+        assert_eq!(color_choice(Some("1"), None, None), Some(false));
+        assert_eq!(color_choice(Some(""), None, None), None);
+        assert_eq!(color_choice(None, None, Some("1")), Some(true));
+        assert_eq!(color_choice(None, None, Some("0")), None);
+        assert_eq!(color_choice(Some("1"), None, Some("1")), Some(false));
+        assert_eq!(color_choice(None, Some("0"), None), Some(false));
+        assert_eq!(color_choice(None, Some("1"), None), None);
+    }
+
+    #[test]
+    fn test_number_style() {
+        let numbers = NumberStyle::default();
+        assert_eq!(numbers.number(0), "0");
+        assert_eq!(numbers.number(9999), "9,999");
+        assert_eq!(numbers.number(1234567), "1,234,567");
+        assert_eq!(NumberStyle::plain().number(1234567), "1234567");
+
+        assert_eq!(thousands_separator("de_DE.UTF-8"), '.');
+        assert_eq!(thousands_separator("fr_FR"), ' ');
+        assert_eq!(thousands_separator("en_US.UTF-8"), ',');
+        assert_eq!(thousands_separator("C"), ',');
+        assert_eq!(thousands_separator(""), ',');
+    }
+
+    /// Tests that columns widen for large counts and that plain output has no padding.
+    #[test]
+    fn test_format_summary_large_numbers() {
+        let mut stats = DirectoryStats::new();
+        for (path, language, functions) in [
+            ("a.rs", SupportedLanguage::Rust, 123456),
+            ("b.py", SupportedLanguage::Python, 7),
+        ] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                language,
+                CodeStats {
+                    function_count: functions,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains("  Python:            7 functions,       0 structs/classes"));
+        assert!(output.contains("  Rust:        123,456 functions,       0 structs/classes"));
+        assert!(output.contains("Total: 123,463 functions"));
+
+        let options = FormatOptions {
+            numbers: NumberStyle::plain(),
+            ..FormatOptions::default()
+        };
+        let output = format_summary(&stats, &options);
+        assert!(output.contains(
+            "  Rust: 123456 functions, 0 structs/classes in 1 files, 0 modules  \
+             (50.0% of files, 100.0% of functions)"
+        ));
+        assert!(output.contains("  Python: 0 code, 0 comments, 0 blank"));
+        assert!(output.contains("Total: 123463 functions"));
+
+        let output = format_detail(&stats, &FormatOptions::default());
+        assert!(output.contains("a.rs (Rust):\n  Functions: 123,456"));
+    }
+
+    /// Tests that the file and module counts of the language summary are aligned.
+    #[test]
+    fn test_format_summary_aligns_file_counts() {
+        let mut stats = DirectoryStats::new();
+        for i in 0..12 {
+            stats.add_file(FileStats::new(
+                PathBuf::from(format!("{i}.rs")),
+                SupportedLanguage::Rust,
+                CodeStats::default(),
+            ));
+        }
+        stats.add_file(FileStats::new(
+            PathBuf::from("a.py"),
+            SupportedLanguage::Python,
+            CodeStats::default(),
+        ));
+
+        let output = format_summary(&stats, &FormatOptions::default());
+        assert!(output.contains("structs/classes in  1 files, 0 modules"));
+        assert!(output.contains("structs/classes in 12 files, 0 modules"));
+
+        let options = FormatOptions {
+            numbers: NumberStyle::plain(),
+            ..FormatOptions::default()
+        };
+        let output = format_summary(&stats, &options);
+        assert!(output.contains("structs/classes in 1 files, 0 modules"));
+    }
+
+    /// Tests that line counts appear per language, in totals and per file.
+    #[test]
+    fn test_format_line_counts() {
+        let mut stats = DirectoryStats::new();
+        for (path, language, code, comment, blank, bytes) in [
+            ("a.rs", SupportedLanguage::Rust, 10, 4, 2, 400),
+            ("b.rs", SupportedLanguage::Rust, 5, 1, 1, 200),
+            ("c.py", SupportedLanguage::Python, 7, 0, 3, 1400),
+        ] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                language,
+                CodeStats {
+                    total_lines: code + comment + blank,
+                    code_lines: code,
+                    comment_lines: comment,
+                    blank_lines: blank,
+                    byte_count: bytes,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let summary = format_summary(&stats, &FormatOptions::default());
+        // Python has a third of the files and lines but most of the bytes
+        assert!(summary.contains(
+            "Size Summary:\n  Python:         10 lines,   1,400 bytes  ( 30.3% of lines,  70.0% of bytes)\n  \
+             Rust:           23 lines,     600 bytes  ( 69.7% of lines,  30.0% of bytes)"
+        ));
+        assert!(summary.contains("Line Summary:\n  Python:          7 code,     0 comments,     3 blank\n  Rust:           15 code,     5 comments,     3 blank"));
+        assert!(summary.contains("Lines: 33 (22 code, 5 comments, 6 blank)"));
+
+        let detail = format_detail(&stats, &FormatOptions::default());
+        assert!(detail.contains("a.rs (Rust):\n  Functions: 0\n  Structs/Classes: 0\n  Interfaces/Traits: 0\n  Enums: 0\n  Type aliases: 0\n  Type definitions: 0\n  Modules: 0\n  Lines: 16 (10 code, 4 comments, 2 blank)"));
+
+        let single = format_single_file(&stats.files[2], &FormatOptions::default());
+        assert!(single.contains("Lines: 10 (7 code, 0 comments, 3 blank)"));
+
+        let json = format_json(&stats, None, false);
+        assert!(json.contains("\"comment_lines\": 5"));
+        assert!(json.contains("\"byte_count\": 2000"));
+    }
+
+    #[test]
+    fn test_format_languages() {
+        let languages = [LanguageCoverage {
+            name: "Rust",
+            extensions: vec!["rs"],
+            magika_labels: vec!["rust"],
+            node_kinds: BTreeMap::from([
+                ("class", vec!["struct_item"]),
+                ("function", vec!["function_item"]),
+            ]),
+        }];
+
+        assert_eq!(
+            format_languages(&languages, OutputFormat::Summary),
+            "Rust\n  Extensions:    .rs\n  Magika labels: rust\n  \
+             class:         struct_item\n  function:      function_item\n"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&format_languages(&languages, OutputFormat::Json)).unwrap();
+        assert_eq!(json[0]["node_kinds"]["function"][0], "function_item");
+    }
+
+    #[test]
+    fn test_format_history_csv() {
+        use crate::history::{LanguageTrend, TrendCounts};
+
+        let counts = |files, functions, classes| TrendCounts {
+            files,
+            functions,
+            classes,
+        };
+        let points = [HistoryPoint {
+            commit: "abc".to_string(),
+            date: "2024-05-01T12:00:00+00:00".to_string(),
+            total: counts(3, 9, 1),
+            languages: vec![
+                LanguageTrend {
+                    language: SupportedLanguage::Python,
+                    counts: counts(1, 4, 0),
+                },
+                LanguageTrend {
+                    language: SupportedLanguage::Rust,
+                    counts: counts(2, 5, 1),
+                },
+            ],
+        }];
+
+        assert_eq!(
+            format_history(&points, HistoryFormat::Csv),
+            "commit,date,language,files,functions,classes\n\
+             abc,2024-05-01T12:00:00+00:00,Python,1,4,0\n\
+             abc,2024-05-01T12:00:00+00:00,Rust,2,5,1"
+        );
+        assert_eq!(
+            format_history(&[], HistoryFormat::Csv),
+            "commit,date,language,files,functions,classes"
+        );
+    }
+
+    #[test]
+    fn test_format_diff() {
+        use crate::diff::LanguageDelta;
+
+        let unchanged = Delta {
+            before: 3,
+            after: 3,
+        };
+        let diff = ReportDiff {
+            total: CountDeltas {
+                files: unchanged,
+                functions: Delta {
+                    before: 10,
+                    after: 12,
+                },
+                classes: unchanged,
+                code_lines: unchanged,
+            },
+            languages: vec![LanguageDelta {
+                language: SupportedLanguage::Rust,
+                counts: CountDeltas {
+                    files: unchanged,
+                    functions: Delta {
+                        before: 10,
+                        after: 12,
+                    },
+                    classes: unchanged,
+                    code_lines: unchanged,
+                },
+            }],
+            files: vec![
+                FileDelta {
+                    path: PathBuf::from("src/new.rs"),
+                    status: FileStatus::Added,
+                    from: None,
+                    functions: Delta {
+                        before: 0,
+                        after: 2,
+                    },
+                    classes: Delta::default(),
+                    code_lines: Delta {
+                        before: 0,
+                        after: 9,
+                    },
+                },
+                FileDelta {
+                    path: PathBuf::from("src/old.rs"),
+                    status: FileStatus::Removed,
+                    from: None,
+                    functions: Delta::default(),
+                    classes: Delta {
+                        before: 1,
+                        after: 0,
+                    },
+                    code_lines: Delta {
+                        before: 4,
+                        after: 0,
+                    },
+                },
+                FileDelta {
+                    path: PathBuf::from("src/util/mod.rs"),
+                    status: FileStatus::Renamed,
+                    from: Some(PathBuf::from("src/util.rs")),
+                    functions: unchanged,
+                    classes: Delta::default(),
+                    code_lines: unchanged,
+                },
+            ],
+        };
+
+        assert_eq!(
+            format_diff(&diff, OutputFormat::Summary),
+            "Changes since baseline:\n  \
+             Rust:        +2 functions (10 -> 12)\n\
+             \nTotal: +2 functions (10 -> 12)\n\
+             \nFiles:\n  \
+             added    src/new.rs: 2 functions, 0 structs/classes, 9 code lines\n  \
+             removed  src/old.rs: 0 functions, 1 structs/classes, 4 code lines\n  \
+             renamed  src/util.rs -> src/util/mod.rs"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_diff(&diff, OutputFormat::Json)).unwrap();
+        assert_eq!(json["languages"][0]["language"], "Rust");
+        assert_eq!(json["languages"][0]["functions"]["after"], 12);
+        assert_eq!(json["files"][1]["status"], "removed");
+        assert!(json["files"][1].get("from").is_none());
+        assert_eq!(json["files"][2]["status"], "renamed");
+        assert_eq!(json["files"][2]["from"], "src/util.rs");
+
+        assert_eq!(
+            format_diff(&ReportDiff::default(), OutputFormat::Summary),
+            "No changes since baseline"
+        );
     }
 }
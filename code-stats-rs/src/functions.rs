@@ -0,0 +1,255 @@
+//! Parameter counts and block nesting of individual functions.
+//!
+//! Long parameter lists and deeply nested blocks make functions hard to
+//! follow, so both are measured for every counted function as
+//! maintainability signals. Function names can be listed as well, to find
+//! names defined in many places.
+
+use crate::config::CountPolicy;
+use crate::language::{Grammar, SupportedLanguage};
+use crate::queries::{Declaration, DeclarationQuery};
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Tree};
+
+/// Functions with more parameters than this are reported as having many.
+pub(crate) const MANY_PARAMETERS: usize = 5;
+
+/// Node kinds of braced or indented statement blocks in the grammars.
+const BLOCK_KINDS: [&str; 2] = ["block", "statement_block"];
+
+/// Node kinds inside parameter lists that do not declare a parameter, such as
+/// Python's `/` and `*` separators.
+const NON_PARAMETER_KINDS: [&str; 3] = [
+    "positional_separator",
+    "keyword_separator",
+    "attribute_item",
+];
+
+/// Measurements of a single function.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FunctionShape {
+    /// Number of declared parameters, excluding Go receivers
+    pub parameters: usize,
+    /// Deepest nesting of blocks inside the function's body; a body without
+    /// nested blocks has depth 0
+    pub nesting_depth: usize,
+}
+
+/// A named function declared in a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FunctionName {
+    /// The function's name, without the type of a method
+    pub name: String,
+    /// 1-based line of the declaration
+    pub line: usize,
+}
+
+/// Lists the named functions of a file in source order.
+///
+/// Anonymous functions are named after the variable they are assigned to,
+/// e.g. `handler` for `const handler = () => {}`, and left out otherwise;
+/// `policy` decides which of them count as functions at all.
+pub(crate) fn function_names(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    policy: &CountPolicy,
+) -> Vec<FunctionName> {
+    let mut names = Vec::new();
+    let query = DeclarationQuery::for_grammar(Grammar::of_tree(*language, tree));
+    query.for_each_counted(tree, source_code, policy, |declaration, node| {
+        if declaration != Declaration::Function {
+            return;
+        }
+        if let Some(name) = function_name(&node) {
+            names.push(FunctionName {
+                name: source_code[name.byte_range()].to_string(),
+                line: node.start_position().row + 1,
+            });
+        }
+    });
+    names.sort_by_key(|function| function.line);
+    names
+}
+
+/// Finds the name node of a function, which Dart nests in signatures.
+pub(crate) fn function_name<'tree>(function: &Node<'tree>) -> Option<Node<'tree>> {
+    if let Some(declarator) = function
+        .parent()
+        .filter(|parent| parent.kind() == "variable_declarator")
+    {
+        return declarator.child_by_field_name("name");
+    }
+    signature_field(function, "name")
+}
+
+/// Measures a function node captured as `@function` by the built-in query.
+pub(crate) fn function_shape(function: &Node, language: &SupportedLanguage) -> FunctionShape {
+    let parameters = if function.child_by_field_name("parameter").is_some()
+        || function
+            .child_by_field_name("parameters")
+            .is_some_and(|parameters| parameters.kind() == "identifier")
+    {
+        // An arrow function or Java lambda with a single unparenthesized
+        // parameter, `x => x` or `x -> x`
+        1
+    } else {
+        signature_field(function, "parameters").map_or(0, |list| count_parameters(&list, language))
+    };
+
+    FunctionShape {
+        parameters,
+        nesting_depth: block_depth(function).saturating_sub(1),
+    }
+}
+
+/// Finds a field of a function's signature, such as its name or parameter
+/// list, which Dart nests in signature nodes.
+fn signature_field<'tree>(node: &Node<'tree>, field: &str) -> Option<Node<'tree>> {
+    if let Some(found) = node.child_by_field_name(field) {
+        return Some(found);
+    }
+
+    let body = node.child_by_field_name("body");
+    let mut cursor = node.walk();
+    let children: Vec<_> = node.named_children(&mut cursor).collect();
+    children
+        .into_iter()
+        .filter(|child| Some(*child) != body && !BLOCK_KINDS.contains(&child.kind()))
+        .find_map(|child| signature_field(&child, field))
+}
+
+/// Counts the parameters declared in a parameter list.
+fn count_parameters(list: &Node, language: &SupportedLanguage) -> usize {
+    let mut cursor = list.walk();
+    list.named_children(&mut cursor)
+        .filter(|child| {
+            !NON_PARAMETER_KINDS.contains(&child.kind())
+                && !language.comment_kinds().contains(&child.kind())
+        })
+        .map(|child| match child.kind() {
+            // Dart's `[...]` and `{...}` groups, which also hold default values
+            "optional_formal_parameters" => {
+                let mut cursor = child.walk();
+                child
+                    .named_children(&mut cursor)
+                    .filter(|parameter| parameter.kind() == "formal_parameter")
+                    .count()
+            }
+            // Go declares several parameters of one type at once, `a, b int`
+            "parameter_declaration" | "variadic_parameter_declaration" => {
+                let mut cursor = child.walk();
+                child
+                    .children_by_field_name("name", &mut cursor)
+                    .count()
+                    .max(1)
+            }
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Returns the largest number of nested blocks on any path below a node,
+/// counting a block at the node itself.
+fn block_depth(node: &Node) -> usize {
+    let own = usize::from(BLOCK_KINDS.contains(&node.kind()));
+    let mut cursor = node.walk();
+    let deepest = node
+        .named_children(&mut cursor)
+        .map(|child| block_depth(&child))
+        .max()
+        .unwrap_or(0);
+    own + deepest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{count_source, create_parser, parse_source};
+
+    /// Returns the total parameters, functions with many and the deepest nesting.
+    fn shape(language: SupportedLanguage, source: &str) -> (usize, usize, usize) {
+        let stats = count_source(language, source);
+        (
+            stats.parameter_count,
+            stats.many_parameter_function_count,
+            stats.max_nesting_depth,
+        )
+    }
+
+    #[test]
+    fn test_function_shapes_per_language() {
+        let cases = [
+            (
+                SupportedLanguage::Rust,
+                "fn a(&self, x: i32) { if x > 0 { loop {} } }\n\
+                 fn b(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8) {}\n",
+                (8, 1, 2),
+            ),
+            (
+                SupportedLanguage::Go,
+                "package m\nfunc f(a, b int, c ...string) { for {} }\nfunc (r *R) g(int, string) {}\n",
+                (5, 0, 1),
+            ),
+            (
+                SupportedLanguage::Python,
+                "def f(self, a, /, b=1, *args, c: int, **kw):\n    if a:\n        pass\n",
+                (6, 1, 1),
+            ),
+            (
+                SupportedLanguage::Java,
+                "class A { void m(int a, String... b) { if (a) { while (b) {} } } }\n",
+                (2, 0, 2),
+            ),
+            (
+                SupportedLanguage::TypeScript,
+                "function f(a: number, b?: string, ...c: any[]) {}\nconst g = x => x;\n",
+                (4, 0, 0),
+            ),
+            (
+                SupportedLanguage::Dart,
+                "void a(int x, {int y = 1, String z = ''}) { if (x > 0) { for (;;) {} } }\n",
+                (3, 0, 2),
+            ),
+        ];
+
+        for (language, source, expected) in cases {
+            assert_eq!(shape(language, source), expected, "{language:?}");
+        }
+    }
+
+    #[test]
+    fn test_function_names() {
+        let cases = [
+            (
+                SupportedLanguage::Rust,
+                "fn parse() {}\nimpl A {\n    fn new() -> Self { A }\n}\n",
+                vec![("parse", 1), ("new", 3)],
+            ),
+            (
+                SupportedLanguage::JavaScript,
+                "const handler = () => 1;\n[1].map((x) => x);\nclass A { run() {} }\n",
+                vec![("handler", 1), ("run", 3)],
+            ),
+            (
+                SupportedLanguage::Dart,
+                "void main() {}\nclass A {\n  int size() => 0;\n}\n",
+                vec![("main", 1), ("size", 3)],
+            ),
+        ];
+
+        for (language, source, expected) in cases {
+            let mut parser = create_parser(&language).unwrap();
+            let tree = parse_source(&mut parser, source, "test").unwrap();
+            let names: Vec<_> = function_names(&tree, source, &language, &CountPolicy::default())
+                .into_iter()
+                .map(|function| (function.name, function.line))
+                .collect();
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|(name, line)| (name.to_string(), line))
+                .collect();
+            assert_eq!(names, expected, "{language:?}");
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Thin wrapper around the `git` command line.
+//!
+//! Git is invoked as an external program rather than linked as a library, so
+//! the user's own git configuration (credentials, proxies, SSH keys) applies.
+
+use crate::error::{CodeStatsError, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Mode of symbolic links in tree listings.
+const SYMLINK_MODE: &str = "120000";
+
+/// Runs `git` with the given arguments inside `dir`.
+///
+/// # Arguments
+///
+/// * `dir` - Working directory of the command (passed as `git -C <dir>`)
+/// * `args` - Arguments following `git -C <dir>`
+///
+/// # Returns
+///
+/// * `Ok(String)` - The command's stdout with trailing whitespace removed
+/// * `Err(CodeStatsError::GitError)` if git cannot be started or exits unsuccessfully
+pub(crate) fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let command = format!("git {}", args.join(" "));
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| CodeStatsError::GitError(format!("{command}: {e}")))?;
+
+    if !output.status.success() {
+        return Err(CodeStatsError::GitError(format!(
+            "{command}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+/// Lists the files below `dir` that changed, as paths joined onto `dir`.
+///
+/// Deleted files are left out, since there is nothing to analyze.
+///
+/// # Arguments
+///
+/// * `dir` - Directory inside a git work tree
+/// * `base` - Revision to compare against; `None` means `HEAD`
+/// * `staged` - Compare the index instead of the work tree, so only staged
+///   changes count (as in a pre-commit hook); otherwise untracked files that
+///   are not ignored count as changed as well
+///
+/// # Returns
+///
+/// * `Ok(HashSet<PathBuf>)` - The changed files
+/// * `Err(CodeStatsError::GitError)` if `dir` is not in a work tree or `base`
+///   does not exist
+pub(crate) fn changed_files(
+    dir: &Path,
+    base: Option<&str>,
+    staged: bool,
+) -> Result<HashSet<PathBuf>> {
+    let mut args = vec!["diff", "--name-only", "-z", "--relative", "--diff-filter=d"];
+    if staged {
+        args.push("--cached");
+    }
+    args.extend([base.unwrap_or("HEAD"), "--", "."]);
+
+    let mut files = nul_separated(dir, &run_git(dir, &args)?);
+    if !staged {
+        let untracked = run_git(
+            dir,
+            &[
+                "ls-files",
+                "-z",
+                "--others",
+                "--exclude-standard",
+                "--",
+                ".",
+            ],
+        )?;
+        files.extend(nul_separated(dir, &untracked));
+    }
+    Ok(files)
+}
+
+/// Counts the commits touching each file below `dir`.
+///
+/// Merge commits are not counted, so a change counts once, in the commit
+/// that made it. A file's earlier names are not followed: commits made
+/// before a rename count for the old path.
+///
+/// # Arguments
+///
+/// * `dir` - Directory inside a git repository
+/// * `since` - Only commits made after this date (any format `git log
+///   --since` accepts); `None` counts the whole history
+///
+/// # Returns
+///
+/// * `Ok(HashMap<PathBuf, usize>)` - The number of commits of each file
+///   touched at least once, joined onto `dir`; deleted files included
+/// * `Err(CodeStatsError::GitError)` if `dir` is not in a repository
+pub(crate) fn file_churn(dir: &Path, since: Option<&str>) -> Result<HashMap<PathBuf, usize>> {
+    let since = since.map(|date| format!("--since={date}"));
+    let mut args = vec!["log", "--format=", "--name-only", "-z", "--relative"];
+    args.extend(since.as_deref());
+    args.extend(["HEAD", "--", "."]);
+
+    let mut churn = HashMap::new();
+    for name in run_git(dir, &args)?.split('\0') {
+        // Commits are separated by a newline before their first file
+        let name = name.trim_start_matches('\n');
+        if !name.is_empty() {
+            *churn.entry(dir.join(name)).or_default() += 1;
+        }
+    }
+    Ok(churn)
+}
+
+/// Attributes each line of a committed file to the author of its last change.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Option<String>>)` - The author's name of every line in order,
+///   `None` for lines changed in the work tree and not committed yet
+/// * `Err(CodeStatsError::GitError)` if the file is not in a repository or
+///   not committed
+pub(crate) fn blame_authors(file: &Path) -> Result<Vec<Option<String>>> {
+    let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
+        return Err(CodeStatsError::GitError(format!(
+            "git blame: {} is not a file",
+            file.display()
+        )));
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let name = name.to_string_lossy();
+    let output = run_git(dir, &["blame", "--line-porcelain", "--", &name])?;
+
+    // Every line's header repeats its commit's `author` and `author-mail`
+    let mut authors = Vec::new();
+    let mut author = None;
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("author ") {
+            author = Some(name.to_string());
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            let committed = mail != "<not.committed.yet>";
+            authors.push(author.take().filter(|_| committed));
+        }
+    }
+    Ok(authors)
+}
+
+/// Runs `git` inside `dir`, feeding `input` to its stdin.
+///
+/// Unlike [`run_git`], the output is returned unchanged as bytes, so it can
+/// carry file contents.
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The command's stdout
+/// * `Err(CodeStatsError::GitError)` if git cannot be started or exits unsuccessfully
+fn run_git_with_input(dir: &Path, args: &[&str], input: Vec<u8>) -> Result<Vec<u8>> {
+    let command = format!("git {}", args.join(" "));
+    let git_error = |e: &dyn std::fmt::Display| CodeStatsError::GitError(format!("{command}: {e}"));
+
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| git_error(&e))?;
+
+    // Write from another thread so a full stdout pipe cannot block git
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().map_err(|e| git_error(&e))?;
+    let written = writer.join().expect("stdin writer panicked");
+
+    if !output.status.success() {
+        return Err(git_error(&String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    written.map_err(|e| git_error(&e))?;
+    Ok(output.stdout)
+}
+
+/// Reads the files below `dir` as of a revision, without touching the work tree.
+///
+/// Symbolic links and submodules are left out.
+///
+/// # Arguments
+///
+/// * `dir` - Directory inside a git repository
+/// * `rev` - The revision to read, e.g. `HEAD~10` or a tag
+///
+/// # Returns
+///
+/// * `Ok(Vec<(PathBuf, Vec<u8>)>)` - Each file, joined onto `dir`, with its
+///   content at `rev`, sorted by path
+/// * `Err(CodeStatsError::GitError)` if `dir` is not in a repository or `rev`
+///   does not exist
+pub(crate) fn revision_files(dir: &Path, rev: &str) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    // `<rev>^{tree}` rejects revisions that are not commits or trees up front
+    let tree = format!("{rev}^{{tree}}");
+    let listing = run_git(dir, &["ls-tree", "-r", "-z", &tree, "--", "."])?;
+
+    // Entries are `<mode> <type> <object>\t<path>`, with paths relative to `dir`
+    let mut blobs = Vec::new();
+    for entry in listing.split('\0').filter(|entry| !entry.is_empty()) {
+        let Some((info, name)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut fields = info.split(' ');
+        if let (Some(mode), Some("blob"), Some(object)) =
+            (fields.next(), fields.next(), fields.next())
+            && mode != SYMLINK_MODE
+        {
+            blobs.push((dir.join(name), object.to_string()));
+        }
+    }
+
+    let input = blobs
+        .iter()
+        .flat_map(|(_, object)| [object.as_bytes(), b"\n"])
+        .flatten()
+        .copied()
+        .collect();
+    let output = run_git_with_input(dir, &["cat-file", "--batch"], input)?;
+
+    let mut rest = output.as_slice();
+    let mut files = Vec::with_capacity(blobs.len());
+    for (path, object) in blobs {
+        let content = next_batch_object(&mut rest).ok_or_else(|| {
+            CodeStatsError::GitError(format!(
+                "git cat-file --batch: missing contents of {object} ({})",
+                path.display()
+            ))
+        })?;
+        files.push((path, content.to_vec()));
+    }
+    Ok(files)
+}
+
+/// Takes the next object from `git cat-file --batch` output.
+///
+/// Each object is a `<object> <type> <size>` header line followed by `size`
+/// bytes of content and a newline.
+fn next_batch_object<'a>(output: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let header_end = output.iter().position(|&byte| byte == b'\n')?;
+    let header = std::str::from_utf8(&output[..header_end]).ok()?;
+    let size: usize = header.rsplit(' ').next()?.parse().ok()?;
+
+    let content = output.get(header_end + 1..header_end + 1 + size)?;
+    *output = output.get(header_end + 2 + size..).unwrap_or_default();
+    Some(content)
+}
+
+/// Splits `-z` output into paths joined onto `dir`.
+fn nul_separated(dir: &Path, output: &str) -> HashSet<PathBuf> {
+    output
+        .split('\0')
+        .filter(|name| !name.is_empty())
+        .map(|name| dir.join(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_git_reports_failures() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let result = run_git(temp_dir.path(), &["rev-parse", "HEAD"]);
+        assert!(matches!(
+            result,
+            Err(CodeStatsError::GitError(msg)) if msg.starts_with("git rev-parse HEAD: ")
+        ));
+    }
+
+    #[test]
+    fn test_run_git_returns_trimmed_stdout() {
+        let temp_dir = TempDir::new().unwrap();
+        run_git(temp_dir.path(), &["init", "-q"]).unwrap();
+
+        let output = run_git(temp_dir.path(), &["rev-parse", "--is-inside-work-tree"]).unwrap();
+        assert_eq!(output, "true");
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let git = |args: &[&str]| run_git(dir, args).unwrap();
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "user.name", "Test"]);
+        for name in ["kept.rs", "edited.rs", "staged.rs", "deleted.rs"] {
+            std::fs::write(dir.join(name), "fn a() {}\n").unwrap();
+        }
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+
+        std::fs::write(dir.join("edited.rs"), "fn b() {}\n").unwrap();
+        std::fs::write(dir.join("staged.rs"), "fn c() {}\n").unwrap();
+        std::fs::write(dir.join("new.rs"), "fn d() {}\n").unwrap();
+        std::fs::remove_file(dir.join("deleted.rs")).unwrap();
+        git(&["add", "staged.rs"]);
+
+        let expected = |names: &[&str]| names.iter().map(|name| dir.join(name)).collect();
+        assert_eq!(
+            changed_files(dir, None, false).unwrap(),
+            expected(&["edited.rs", "staged.rs", "new.rs"])
+        );
+        assert_eq!(
+            changed_files(dir, None, true).unwrap(),
+            expected(&["staged.rs"])
+        );
+        assert!(changed_files(dir, Some("no-such-ref"), false).is_err());
+    }
+
+    #[test]
+    fn test_file_churn() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let git = |args: &[&str]| run_git(dir, args).unwrap();
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "user.name", "Test"]);
+        std::fs::create_dir(dir.join("src")).unwrap();
+        for (i, names) in [
+            &["src/lib.rs", "a.py"][..],
+            &["src/lib.rs"],
+            &["src/lib.rs", "b.rs"],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            for name in names {
+                std::fs::write(dir.join(name), format!("# version {i}\n")).unwrap();
+            }
+            git(&["add", "."]);
+            git(&["commit", "-q", "-m", &format!("commit {i}")]);
+        }
+
+        let expected = |counts: &[(&str, usize)]| {
+            counts
+                .iter()
+                .map(|&(name, count)| (dir.join(name), count))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(
+            file_churn(dir, None).unwrap(),
+            expected(&[("src/lib.rs", 3), ("a.py", 1), ("b.rs", 1)])
+        );
+        assert_eq!(
+            file_churn(&dir.join("src"), None).unwrap(),
+            expected(&[("src/lib.rs", 3)])
+        );
+        assert!(file_churn(dir, Some("2090-01-01")).unwrap().is_empty());
+        assert!(file_churn(TempDir::new().unwrap().path(), None).is_err());
+    }
+
+    #[test]
+    fn test_blame_authors() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let git = |args: &[&str]| run_git(dir, args).unwrap();
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "ada@example.com"]);
+        git(&["config", "user.name", "Ada"]);
+        std::fs::create_dir(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+
+        git(&["config", "user.name", "Grace"]);
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\nfn c() {}\n").unwrap();
+        git(&["commit", "-q", "-am", "second"]);
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\nfn c() {}\nfn d() {}\n").unwrap();
+
+        assert_eq!(
+            blame_authors(&dir.join("src/lib.rs")).unwrap(),
+            [Some("Ada".to_string()), Some("Grace".to_string()), None]
+        );
+        std::fs::write(dir.join("src/new.rs"), "fn e() {}\n").unwrap();
+        assert!(blame_authors(&dir.join("src/new.rs")).is_err());
+    }
+
+    #[test]
+    fn test_revision_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let git = |args: &[&str]| run_git(dir, args).unwrap();
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "user.name", "Test"]);
+        std::fs::create_dir(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(dir.join("empty.py"), "").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+
+        std::fs::write(dir.join("src/lib.rs"), "fn b() {}\n").unwrap();
+        std::fs::write(dir.join("new.rs"), "fn c() {}\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "second"]);
+
+        assert_eq!(
+            revision_files(dir, "HEAD~1").unwrap(),
+            vec![
+                (dir.join("empty.py"), Vec::new()),
+                (dir.join("src/lib.rs"), b"fn a() {}\n".to_vec()),
+            ]
+        );
+        assert_eq!(
+            revision_files(&dir.join("src"), "HEAD").unwrap(),
+            vec![(dir.join("src/lib.rs"), b"fn b() {}\n".to_vec())]
+        );
+        assert!(revision_files(dir, "no-such-ref").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Extraction of inheritance relationships between declared types.
+//!
+//! Each class or interface that names a parent is recorded with its direct
+//! parents, as written in the source. Names are not resolved across files, so
+//! downstream tools can join them by name to compute inheritance depth and
+//! breadth per repository.
+
+use crate::language::SupportedLanguage;
+use crate::visitor::NodeVisitor;
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+/// The direct parents of one declared type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct TypeRelations {
+    /// Name of the declared type
+    pub name: String,
+    /// 1-based line of the declaration
+    pub line: usize,
+    /// Parent classes, Python bases and interfaces extended by an interface
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+    /// Interfaces implemented by a class, enum or record
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub implements: Vec<String>,
+}
+
+/// Collects the relations of every type declaration it visits.
+///
+/// Supports Java (`extends`, `implements`), TypeScript (`extends`,
+/// `implements`, interface `extends`), JavaScript (`extends`) and Python
+/// (base classes; keyword arguments such as `metaclass=` are not bases) and
+/// Dart (`extends` with the `with` mixins, `implements`).
+/// Anonymous class expressions and types without parents are not recorded.
+#[derive(Debug, Default)]
+pub(crate) struct HierarchyCollector {
+    relations: Vec<TypeRelations>,
+}
+
+impl HierarchyCollector {
+    /// Returns the collected relations in source order.
+    pub(crate) fn into_relations(self) -> Vec<TypeRelations> {
+        self.relations
+    }
+}
+
+impl NodeVisitor for HierarchyCollector {
+    fn visit(&mut self, language: SupportedLanguage, node: &Node<'_>, source: &str) {
+        let (extends, implements) = match (language, node.kind()) {
+            (
+                SupportedLanguage::Java,
+                "class_declaration" | "enum_declaration" | "record_declaration",
+            ) => (
+                java_superclass(node, source),
+                java_type_list(node.child_by_field_name("interfaces"), source),
+            ),
+            (SupportedLanguage::Java, "interface_declaration") => (
+                java_type_list(child_of_kind(node, "extends_interfaces"), source),
+                Vec::new(),
+            ),
+            (
+                SupportedLanguage::JavaScript | SupportedLanguage::TypeScript,
+                "class_declaration" | "abstract_class_declaration" | "class",
+            ) => class_heritage(node, source),
+            (SupportedLanguage::TypeScript, "interface_declaration") => {
+                let extends = child_of_kind(node, "extends_type_clause")
+                    .map(|clause| named_children_text(&clause, source))
+                    .unwrap_or_default();
+                (extends, Vec::new())
+            }
+            (SupportedLanguage::Dart, "class_declaration") => dart_heritage(node, source),
+            (SupportedLanguage::Python, "class_definition") => {
+                let bases = node
+                    .child_by_field_name("superclasses")
+                    .map(|arguments| {
+                        let mut cursor = arguments.walk();
+                        arguments
+                            .named_children(&mut cursor)
+                            .filter(|argument| {
+                                !matches!(
+                                    argument.kind(),
+                                    "keyword_argument" | "comment" | "dictionary_splat"
+                                )
+                            })
+                            .map(|argument| type_name(&argument, source))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (bases, Vec::new())
+            }
+            _ => return,
+        };
+
+        if extends.is_empty() && implements.is_empty() {
+            return;
+        }
+        let Some(name) = node.child_by_field_name("name") else {
+            return;
+        };
+
+        self.relations.push(TypeRelations {
+            name: text(&name, source).to_string(),
+            line: node.start_position().row + 1,
+            extends,
+            implements,
+        });
+    }
+}
+
+/// Returns the text of a node, or an empty string if it is not valid UTF-8.
+fn text<'a>(node: &Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or_default()
+}
+
+/// Returns the name of a referenced type without its type arguments.
+fn type_name(node: &Node, source: &str) -> String {
+    match node.kind() {
+        "generic_type" => node
+            .child_by_field_name("name")
+            .or_else(|| node.named_child(0))
+            .map_or_else(|| text(node, source), |name| text(&name, source))
+            .to_string(),
+        _ => text(node, source).to_string(),
+    }
+}
+
+/// Returns the first direct child of the given kind.
+fn child_of_kind<'tree>(node: &Node<'tree>, kind: &str) -> Option<Node<'tree>> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .find(|child| child.kind() == kind)
+}
+
+/// Returns the type names of every named child, skipping type arguments.
+fn named_children_text(node: &Node, source: &str) -> Vec<String> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .filter(|child| child.kind() != "type_arguments")
+        .map(|child| type_name(&child, source))
+        .collect()
+}
+
+/// Returns the superclass of a Java class, if it names one.
+fn java_superclass(node: &Node, source: &str) -> Vec<String> {
+    node.child_by_field_name("superclass")
+        .and_then(|superclass| superclass.named_child(0))
+        .map(|parent| vec![type_name(&parent, source)])
+        .unwrap_or_default()
+}
+
+/// Returns the types of a Java `super_interfaces` or `extends_interfaces` clause.
+fn java_type_list(clause: Option<Node>, source: &str) -> Vec<String> {
+    clause
+        .and_then(|clause| child_of_kind(&clause, "type_list"))
+        .map(|list| named_children_text(&list, source))
+        .unwrap_or_default()
+}
+
+/// Returns the `extends` and `implements` parents of a JavaScript or TypeScript class.
+///
+/// JavaScript's heritage is a bare expression, TypeScript's is split into
+/// `extends_clause` and `implements_clause`.
+fn class_heritage(node: &Node, source: &str) -> (Vec<String>, Vec<String>) {
+    let mut extends = Vec::new();
+    let mut implements = Vec::new();
+
+    let Some(heritage) = child_of_kind(node, "class_heritage") else {
+        return (extends, implements);
+    };
+
+    let mut cursor = heritage.walk();
+    for clause in heritage.named_children(&mut cursor) {
+        match clause.kind() {
+            "extends_clause" => extends.extend(named_children_text(&clause, source)),
+            "implements_clause" => implements.extend(named_children_text(&clause, source)),
+            "comment" => {}
+            _ => extends.push(type_name(&clause, source)),
+        }
+    }
+
+    (extends, implements)
+}
+
+/// Returns the `extends` and `implements` parents of a Dart class.
+///
+/// Mixins applied with `with` are listed with the superclass, since they
+/// become part of the class's superclass chain.
+fn dart_heritage(node: &Node, source: &str) -> (Vec<String>, Vec<String>) {
+    let mut extends = Vec::new();
+    if let Some(superclass) = node.child_by_field_name("superclass") {
+        extends = dart_types(&superclass, source);
+        if let Some(mixins) = child_of_kind(&superclass, "mixins") {
+            extends.extend(dart_types(&mixins, source));
+        }
+    }
+    let implements = node
+        .child_by_field_name("interfaces")
+        .map(|interfaces| dart_types(&interfaces, source))
+        .unwrap_or_default();
+
+    (extends, implements)
+}
+
+/// Returns the types listed directly in a Dart clause, skipping type arguments.
+///
+/// The Dart grammar places type arguments next to the type they belong to,
+/// as a `type` node starting with `<`.
+fn dart_types(clause: &Node, source: &str) -> Vec<String> {
+    let mut cursor = clause.walk();
+    clause
+        .named_children(&mut cursor)
+        .filter(|child| {
+            child.kind() == "type" && child.child(0).is_none_or(|first| first.kind() != "<")
+        })
+        .map(|child| text(&child, source).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_source_with;
+
+    /// Collects the relations of a piece of source code.
+    fn relations(language: SupportedLanguage, source: &str) -> Vec<TypeRelations> {
+        let mut collector = HierarchyCollector::default();
+        count_source_with(language, source, &mut collector);
+        collector.into_relations()
+    }
+
+    /// Builds expected relations with the names given as string slices.
+    fn relation(name: &str, line: usize, extends: &[&str], implements: &[&str]) -> TypeRelations {
+        TypeRelations {
+            name: name.to_string(),
+            line,
+            extends: extends.iter().map(ToString::to_string).collect(),
+            implements: implements.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_java_relations() {
+        let source = "class A extends B<T> implements C, d.E {}\n\
+                      interface I extends J, K {}\n\
+                      enum X implements Y {}\n\
+                      class Root {}\n";
+
+        assert_eq!(
+            relations(SupportedLanguage::Java, source),
+            vec![
+                relation("A", 1, &["B"], &["C", "d.E"]),
+                relation("I", 2, &["J", "K"], &[]),
+                relation("X", 3, &[], &["Y"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typescript_relations() {
+        let source = "class A extends B<T> implements C, ns.D {}\n\
+                      interface I extends J, K<L> {}\n\
+                      abstract class Z extends Base {}\n";
+
+        assert_eq!(
+            relations(SupportedLanguage::TypeScript, source),
+            vec![
+                relation("A", 1, &["B"], &["C", "ns.D"]),
+                relation("I", 2, &["J", "K"], &[]),
+                relation("Z", 3, &["Base"], &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_javascript_relations() {
+        let source =
+            "class A extends B {}\nclass C extends lib.D {}\nconst E = class extends F {};\n";
+
+        assert_eq!(
+            relations(SupportedLanguage::JavaScript, source),
+            vec![
+                relation("A", 1, &["B"], &[]),
+                relation("C", 2, &["lib.D"], &[])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_relations() {
+        let source = "class A(B, mod.C, metaclass=M):\n    pass\n\nclass D:\n    pass\n";
+
+        assert_eq!(
+            relations(SupportedLanguage::Python, source),
+            vec![relation("A", 1, &["B", "mod.C"], &[])]
+        );
+    }
+
+    #[test]
+    fn test_dart_relations() {
+        let source = "class A extends p.B<T> with M, N implements I<X>, J {}
+                      class Z {}
+                      class W implements Widget {}
+";
+
+        assert_eq!(
+            relations(SupportedLanguage::Dart, source),
+            vec![
+                relation("A", 1, &["p.B", "M", "N"], &["I", "J"]),
+                relation("W", 3, &[], &["Widget"]),
+            ]
+        );
+    }
+}
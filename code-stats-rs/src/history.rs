@@ -0,0 +1,228 @@
+//! Time series of statistics across the commits of a git history.
+//!
+//! Commits are taken from the first-parent history ending at a revision, so
+//! merged branches contribute through their merge commits only. Each sampled
+//! commit is analyzed from the repository's objects (see
+//! [`CodeAnalyzer::analyze_revision`]), leaving the work tree untouched.
+
+use crate::analyzer::CodeAnalyzer;
+use crate::error::Result;
+use crate::git::run_git;
+use crate::language::SupportedLanguage;
+use crate::stats::{DirectoryStats, LanguageStats};
+use serde::Serialize;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+/// A commit of the sampled history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Commit {
+    /// Full object name
+    pub id: String,
+    /// Committer date in strict ISO 8601 format
+    pub date: String,
+}
+
+/// The counts tracked over time for a language or the whole tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct TrendCounts {
+    pub files: usize,
+    pub functions: usize,
+    pub classes: usize,
+}
+
+impl From<&LanguageStats> for TrendCounts {
+    fn from(stats: &LanguageStats) -> Self {
+        Self {
+            files: stats.file_count,
+            functions: stats.function_count,
+            classes: stats.class_struct_count,
+        }
+    }
+}
+
+/// The counts of one language at a commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct LanguageTrend {
+    pub language: SupportedLanguage,
+    #[serde(flatten)]
+    pub counts: TrendCounts,
+}
+
+/// The statistics of the tree at one commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct HistoryPoint {
+    pub commit: String,
+    pub date: String,
+    /// Counts across all languages
+    pub total: TrendCounts,
+    /// Counts per language, sorted by name
+    pub languages: Vec<LanguageTrend>,
+}
+
+impl HistoryPoint {
+    /// Summarizes the statistics of a commit.
+    pub(crate) fn new(commit: Commit, stats: &DirectoryStats) -> Self {
+        let mut languages: Vec<LanguageTrend> = stats
+            .total_by_language
+            .iter()
+            .map(|(language, stats)| LanguageTrend {
+                language: *language,
+                counts: stats.into(),
+            })
+            .collect();
+        languages.sort_by_key(|trend| trend.language.name());
+
+        Self {
+            commit: commit.id,
+            date: commit.date,
+            total: TrendCounts {
+                files: stats.total_files(),
+                functions: stats.total_stats.function_count,
+                classes: stats.total_stats.class_struct_count,
+            },
+            languages,
+        }
+    }
+}
+
+/// Lists the commits to analyze, oldest first.
+///
+/// # Arguments
+///
+/// * `dir` - Directory inside a git repository
+/// * `rev` - The newest commit of the range
+/// * `since` - Only commits made after this date (any format `git log
+///   --since` accepts, e.g. `2024-01-01` or `6 months ago`)
+/// * `every` - Keep every Nth commit, counting back from `rev`, which is
+///   always kept
+///
+/// # Returns
+///
+/// * `Ok(Vec<Commit>)` - The sampled commits in chronological order
+/// * `Err(CodeStatsError::GitError)` if `dir` is not in a repository or `rev`
+///   does not exist
+pub(crate) fn sample_commits(
+    dir: &Path,
+    rev: &str,
+    since: Option<&str>,
+    every: NonZeroUsize,
+) -> Result<Vec<Commit>> {
+    let since = since.map(|date| format!("--since={date}"));
+    let mut args = vec!["log", "--first-parent", "--format=%H %cI"];
+    args.extend(since.as_deref());
+    args.extend([rev, "--"]);
+
+    let log = run_git(dir, &args)?;
+    let mut commits: Vec<Commit> = log
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .step_by(every.get())
+        .map(|(id, date)| Commit {
+            id: id.to_string(),
+            date: date.to_string(),
+        })
+        .collect();
+    commits.reverse();
+    Ok(commits)
+}
+
+/// Analyzes a directory at each of the given commits.
+///
+/// Stops before the next commit once the analyzer's interrupt is raised; the
+/// commit being analyzed then is left out.
+///
+/// # Returns
+///
+/// * `Ok((Vec<HistoryPoint>, bool))` - The points in the order of `commits`,
+///   and whether the run was interrupted
+/// * `Err` if a commit cannot be read or analyzed
+pub(crate) fn collect_history(
+    analyzer: &mut CodeAnalyzer,
+    dir: &Path,
+    commits: Vec<Commit>,
+    max_depth: usize,
+    ignore_patterns: &[String],
+) -> Result<(Vec<HistoryPoint>, bool)> {
+    let mut points = Vec::with_capacity(commits.len());
+    for commit in commits {
+        let stats = analyzer.analyze_revision(dir, &commit.id, max_depth, ignore_patterns)?;
+        if stats.interrupted {
+            return Ok((points, true));
+        }
+        points.push(HistoryPoint::new(commit, &stats));
+    }
+    Ok((points, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Creates a repository with one commit per content of `lib.rs`.
+    fn repository(versions: &[&str]) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let git = |args: &[&str]| run_git(dir, args).unwrap();
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "user.name", "Test"]);
+        for (i, content) in versions.iter().enumerate() {
+            std::fs::write(dir.join("lib.rs"), content).unwrap();
+            git(&["add", "."]);
+            git(&["commit", "-q", "-m", &format!("version {i}")]);
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_sample_commits_keeps_every_nth_from_the_newest() {
+        let temp_dir = repository(&["fn a() {}", "fn b() {}", "fn c() {}", "fn d() {}"]);
+        let dir = temp_dir.path();
+        let id = |rev: &str| run_git(dir, &["rev-parse", rev]).unwrap();
+
+        let all = sample_commits(dir, "HEAD", None, NonZeroUsize::MIN).unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[3].id, id("HEAD"));
+
+        let sampled = sample_commits(dir, "HEAD", None, NonZeroUsize::new(2).unwrap()).unwrap();
+        let ids: Vec<&str> = sampled.iter().map(|commit| commit.id.as_str()).collect();
+        assert_eq!(ids, [id("HEAD~2"), id("HEAD")]);
+
+        let future = sample_commits(dir, "HEAD", Some("2090-01-01"), NonZeroUsize::MIN).unwrap();
+        assert!(future.is_empty());
+
+        assert!(sample_commits(dir, "no-such-ref", None, NonZeroUsize::MIN).is_err());
+    }
+
+    #[test]
+    fn test_collect_history() {
+        let temp_dir = repository(&["fn a() {}\n", "fn a() {}\nfn b() {}\nstruct S;\n"]);
+        let dir = temp_dir.path();
+
+        let commits = sample_commits(dir, "HEAD", None, NonZeroUsize::MIN).unwrap();
+        let (points, interrupted) =
+            collect_history(&mut CodeAnalyzer::new(), dir, commits, 100, &[]).unwrap();
+
+        assert!(!interrupted);
+        let totals: Vec<TrendCounts> = points.iter().map(|point| point.total).collect();
+        assert_eq!(
+            totals,
+            [
+                TrendCounts {
+                    files: 1,
+                    functions: 1,
+                    classes: 0
+                },
+                TrendCounts {
+                    files: 1,
+                    functions: 2,
+                    classes: 1
+                },
+            ]
+        );
+        assert_eq!(points[1].languages[0].language, SupportedLanguage::Rust);
+        assert_eq!(points[1].languages[0].counts, points[1].total);
+    }
+}
@@ -0,0 +1,137 @@
+//! Refactoring priorities from the git history and the code's complexity.
+//!
+//! A hotspot is a file that is both complex and changed often: complex code
+//! that nobody touches costs little, and simple code that changes every week
+//! is cheap to change. Files are ranked by the product of their churn (the
+//! commits touching them over a window, see [`file_churn`]) and their
+//! cyclomatic complexity, so the top of the list is where a refactoring pays
+//! off first.
+//!
+//! [`file_churn`]: crate::git::file_churn
+
+use crate::language::SupportedLanguage;
+use crate::stats::DirectoryStats;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A file ranked by churn and complexity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Hotspot {
+    pub path: PathBuf,
+    pub language: SupportedLanguage,
+    /// Commits touching the file over the window
+    pub commits: usize,
+    /// Cyclomatic complexity of the whole file
+    pub complexity: usize,
+    pub code_lines: usize,
+    /// `commits × complexity`, the value the ranking is sorted by
+    pub score: usize,
+}
+
+/// Ranks the analyzed files by churn times complexity.
+///
+/// Files without extended metrics, or that no commit of the window touched,
+/// are left out.
+///
+/// # Arguments
+///
+/// * `stats` - Statistics analyzed with extended metrics, with the same
+///   paths as the keys of `churn`
+/// * `churn` - The commits touching each file
+/// * `top` - The number of hotspots to keep
+///
+/// # Returns
+///
+/// The `top` files with the highest score, highest first; ties are ranked by
+/// churn, then by path
+pub(crate) fn rank_hotspots(
+    stats: &DirectoryStats,
+    churn: &HashMap<PathBuf, usize>,
+    top: usize,
+) -> Vec<Hotspot> {
+    let mut hotspots: Vec<Hotspot> = stats
+        .files
+        .iter()
+        .filter_map(|file| {
+            let commits = *churn.get(&file.path)?;
+            let complexity = file.metrics.as_ref()?.cyclomatic_complexity;
+            Some(Hotspot {
+                path: file.path.clone(),
+                language: file.language,
+                commits,
+                complexity,
+                code_lines: file.stats.code_lines,
+                score: commits * complexity,
+            })
+        })
+        .collect();
+    hotspots.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(b.commits.cmp(&a.commits))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    hotspots.truncate(top);
+    hotspots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::ExtendedMetrics;
+    use crate::parser::CodeStats;
+    use crate::stats::FileStats;
+
+    /// Builds directory statistics from `(path, cyclomatic complexity)`.
+    fn directory(files: &[(&str, usize)]) -> DirectoryStats {
+        let mut stats = DirectoryStats::new();
+        for &(path, complexity) in files {
+            let mut file = FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats::default(),
+            );
+            file.metrics = Some(ExtendedMetrics {
+                distinct_operators: 0,
+                distinct_operands: 0,
+                total_operators: 0,
+                total_operands: 0,
+                volume: 0.0,
+                cyclomatic_complexity: complexity,
+                maintainability_index: 100.0,
+            });
+            stats.add_file(file);
+        }
+        stats
+    }
+
+    #[test]
+    fn test_rank_hotspots() {
+        let stats = directory(&[
+            ("a.rs", 10),
+            ("b.rs", 2),
+            ("c.rs", 5),
+            ("d.rs", 20),
+            ("e.rs", 4),
+        ]);
+        let churn: HashMap<PathBuf, usize> = [("a.rs", 2), ("b.rs", 30), ("c.rs", 4), ("e.rs", 5)]
+            .into_iter()
+            .map(|(path, commits)| (PathBuf::from(path), commits))
+            .collect();
+
+        let hotspots = rank_hotspots(&stats, &churn, 10);
+        let ranked: Vec<(&str, usize)> = hotspots
+            .iter()
+            .map(|hotspot| (hotspot.path.to_str().unwrap(), hotspot.score))
+            .collect();
+        // d.rs was never committed; e.rs ties with c.rs on more commits
+        assert_eq!(
+            ranked,
+            [("b.rs", 60), ("e.rs", 20), ("c.rs", 20), ("a.rs", 20)]
+        );
+
+        assert_eq!(rank_hotspots(&stats, &churn, 1).len(), 1);
+        assert!(rank_hotspots(&stats, &HashMap::new(), 10).is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! Heuristic detection of user-facing strings that bypass translation.
+
+use crate::language::SupportedLanguage;
+use tree_sitter::{Node, Tree};
+
+/// Function and macro names whose string arguments are already translated.
+///
+/// Matched against the last segment of the callee, so `i18n.t(...)` and
+/// `t(...)` are both recognized.
+const TRANSLATION_CALLS: &[&str] = &[
+    "t",
+    "tr",
+    "_",
+    "gettext",
+    "ngettext",
+    "pgettext",
+    "dgettext",
+    "translate",
+    "fl",
+];
+
+/// Counts string literals that look like user-facing text outside translation calls.
+///
+/// A string is considered user-facing when it contains at least two words, so
+/// identifiers, keys, paths and format specifiers are not counted. Strings
+/// passed to translation functions (`t()`, `gettext()`, ...) are translated
+/// already, and strings passed to logging/debug output (`println!`,
+/// `console.log`, ...) are developer-facing; both are excluded, as are Python
+/// docstrings.
+pub(crate) fn count_untranslated_strings(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+) -> usize {
+    let mut count = 0;
+    count_node(
+        &tree.root_node(),
+        source_code.as_bytes(),
+        language,
+        &mut count,
+    );
+    count
+}
+
+/// Recursively counts untranslated strings below `node`.
+fn count_node(node: &Node, source: &[u8], language: &SupportedLanguage, count: &mut usize) {
+    if language.string_literal_kinds().contains(&node.kind()) {
+        let is_docstring = *language == SupportedLanguage::Python
+            && node
+                .parent()
+                .is_some_and(|parent| parent.kind() == "expression_statement");
+
+        if !is_docstring && node.utf8_text(source).is_ok_and(looks_like_text) {
+            *count += 1;
+        }
+        return;
+    }
+
+    if let Some(callee) = callee_name(node, source, language)
+        && (is_translation_call(&callee) || is_logging_call(&callee, language))
+    {
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        count_node(&child, source, language, count);
+    }
+}
+
+/// Returns the callee of a call or macro invocation, e.g. `console.log` or `println`.
+fn callee_name(node: &Node, source: &[u8], language: &SupportedLanguage) -> Option<String> {
+    let callee = match (language, node.kind()) {
+        (SupportedLanguage::Rust, "macro_invocation") => node.child_by_field_name("macro")?,
+        (SupportedLanguage::Python, "call") => node.child_by_field_name("function")?,
+        (SupportedLanguage::Java, "method_invocation") => {
+            let name = node.child_by_field_name("name")?.utf8_text(source).ok()?;
+            return Some(match node.child_by_field_name("object") {
+                Some(object) => format!("{}.{name}", object.utf8_text(source).ok()?),
+                None => name.to_string(),
+            });
+        }
+        (_, "call_expression") => node.child_by_field_name("function")?,
+        _ => return None,
+    };
+
+    callee.utf8_text(source).ok().map(str::to_string)
+}
+
+/// Returns `true` if the callee is a known translation function.
+fn is_translation_call(callee: &str) -> bool {
+    let last_segment = callee.rsplit(['.', ':']).next().unwrap_or(callee);
+    TRANSLATION_CALLS.contains(&last_segment)
+}
+
+/// Returns `true` if the callee writes developer-facing log or debug output.
+fn is_logging_call(callee: &str, language: &SupportedLanguage) -> bool {
+    let first_segment = callee.split(['.', ':']).next().unwrap_or(callee);
+    match language {
+        SupportedLanguage::Rust => {
+            matches!(callee, "println" | "eprintln" | "print" | "eprint" | "dbg")
+                || matches!(first_segment, "log" | "tracing")
+        }
+        SupportedLanguage::Go => matches!(first_segment, "fmt" | "log" | "slog"),
+        SupportedLanguage::Python => {
+            callee == "print" || matches!(first_segment, "logging" | "logger" | "log")
+        }
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript => {
+            matches!(first_segment, "console" | "logger" | "log")
+        }
+        SupportedLanguage::Java => {
+            callee.starts_with("System.out.")
+                || callee.starts_with("System.err.")
+                || matches!(first_segment, "logger" | "log" | "LOGGER" | "LOG")
+        }
+        SupportedLanguage::Dart => {
+            matches!(callee, "print" | "debugPrint")
+                || matches!(first_segment, "developer" | "logger" | "log")
+        }
+    }
+}
+
+/// Returns `true` if the literal text reads like a phrase rather than a token.
+fn looks_like_text(literal: &str) -> bool {
+    literal
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphabetic))
+        .take(2)
+        .count()
+        == 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{create_parser, parse_source};
+
+    fn count(source: &str, language: SupportedLanguage) -> usize {
+        let mut parser = create_parser(&language).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+        count_untranslated_strings(&tree, source, &language)
+    }
+
+    #[test]
+    fn test_looks_like_text() {
+        assert!(looks_like_text("\"Save changes\""));
+        assert!(!looks_like_text("\"utf-8\""));
+        assert!(!looks_like_text("\"{}: {}\""));
+        assert!(!looks_like_text("\"user_id\""));
+    }
+
+    #[test]
+    fn test_javascript_translation_and_logging_calls_excluded() {
+        let source = r#"
+const title = "Welcome back";
+const label = t("Save changes");
+const other = i18n.t("Cancel editing");
+console.log("Loaded the page");
+const key = "user_id";
+"#;
+        assert_eq!(count(source, SupportedLanguage::JavaScript), 1);
+    }
+
+    #[test]
+    fn test_python_docstrings_and_gettext_excluded() {
+        let source = r#"
+"""Module docstring with words."""
+
+def greet():
+    print("Debug output here")
+    return _("Hello there") + " and goodbye"
+"#;
+        assert_eq!(count(source, SupportedLanguage::Python), 1);
+    }
+
+    #[test]
+    fn test_rust_macros() {
+        let source = r#"
+fn main() {
+    println!("Starting the server");
+    let message = t!("Server is ready");
+    let error = format!("Could not bind {}", 8080);
+}
+"#;
+        assert_eq!(count(source, SupportedLanguage::Rust), 1);
+    }
+
+    #[test]
+    fn test_java_and_go_logging_excluded() {
+        let java = r#"
+class Main {
+    void run() {
+        System.out.println("Running the job");
+        logger.info("Job started now");
+        showDialog("Job finished successfully");
+    }
+}
+"#;
+        assert_eq!(count(java, SupportedLanguage::Java), 1);
+
+        let go = r#"
+package main
+
+func main() {
+    fmt.Println("Starting up now")
+    render("Hello from Go")
+}
+"#;
+        assert_eq!(count(go, SupportedLanguage::Go), 1);
+    }
+}
@@ -0,0 +1,79 @@
+//! Cooperative handling of Ctrl-C and termination requests.
+//!
+//! A signal only raises a flag. Long-running loops check it between files and
+//! stop early, and parsing checks it within a file, so the work done until
+//! then can still be reported.
+
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Exit status used when a second signal aborts the process outright (128 + SIGINT).
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// The flag raised by the installed signal handler, shared by every caller.
+static HANDLER_FLAG: OnceLock<Interrupt> = OnceLock::new();
+
+/// A request to stop, shared between a signal handler and the work it interrupts.
+///
+/// Clones observe the same flag. The default value is never raised unless
+/// [`raise`](Self::raise) is called, so code that is not interruptible can
+/// pass one without installing a handler.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    /// Requests that the work observing this flag stops.
+    pub(crate) fn raise(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether a stop has been requested.
+    pub(crate) fn is_raised(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Installs the handler for SIGINT and SIGTERM (Ctrl-C on Windows).
+///
+/// The first signal raises the returned flag. A second signal exits
+/// immediately, for work that does not check the flag. Calling this more
+/// than once returns the flag of the handler installed first.
+///
+/// # Returns
+///
+/// * `Ok(Interrupt)` - The flag raised by the handler
+/// * `Err(String)` if the handler cannot be installed
+pub(crate) fn install_handler() -> Result<Interrupt, String> {
+    if let Some(interrupt) = HANDLER_FLAG.get() {
+        return Ok(interrupt.clone());
+    }
+
+    let interrupt = Interrupt::default();
+    let raised = interrupt.clone();
+    ctrlc::set_handler(move || {
+        if raised.is_raised() {
+            std::process::exit(FORCED_EXIT_CODE);
+        }
+        eprintln!("Interrupted; finishing with a partial report (interrupt again to abort)");
+        raised.raise();
+    })
+    .map_err(|e| format!("Failed to install signal handler: {e}"))?;
+
+    Ok(HANDLER_FLAG.get_or_init(|| interrupt).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_the_flag() {
+        let interrupt = Interrupt::default();
+        let observer = interrupt.clone();
+        assert!(!observer.is_raised());
+
+        interrupt.raise();
+        assert!(observer.is_raised());
+    }
+}
@@ -1,7 +1,9 @@
 //! Language support definitions and file type detection using Magika.
 
+use crate::queries::declaration_kinds;
+use std::collections::BTreeMap;
 use std::path::Path;
-use tree_sitter::Language;
+use tree_sitter::{Language, Tree};
 
 /// Enumeration of supported programming languages.
 ///
@@ -14,20 +16,106 @@ use tree_sitter::Language;
 /// - `Rust` - `.rs` files
 /// - `Go` - `.go` files
 /// - `Python` - `.py` files
-/// - `JavaScript` - `.js` files
-/// - `TypeScript` - `.ts` files
+/// - `JavaScript` - `.js` and `.jsx` files
+/// - `TypeScript` - `.ts` and `.tsx` files
 /// - `Java` - `.java` files
+/// - `Dart` - `.dart` files
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
-pub(crate) enum SupportedLanguage {
+pub enum SupportedLanguage {
     Rust,
     Go,
     Python,
     JavaScript,
     TypeScript,
     Java,
+    Dart,
 }
 
+/// Languages are ordered by name, so maps keyed by language list them
+/// alphabetically, the same in every report.
+impl Ord for SupportedLanguage {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.name().cmp(other.name())
+    }
+}
+
+impl PartialOrd for SupportedLanguage {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// File extensions of each supported language, matched case-insensitively.
+const EXTENSIONS: &[(&str, SupportedLanguage)] = &[
+    ("rs", SupportedLanguage::Rust),
+    ("go", SupportedLanguage::Go),
+    ("py", SupportedLanguage::Python),
+    ("js", SupportedLanguage::JavaScript),
+    ("jsx", SupportedLanguage::JavaScript),
+    ("ts", SupportedLanguage::TypeScript),
+    ("tsx", SupportedLanguage::TypeScript),
+    ("java", SupportedLanguage::Java),
+    ("dart", SupportedLanguage::Dart),
+];
+
+/// Magika content type labels of each supported language.
+const MAGIKA_LABELS: &[(&str, SupportedLanguage)] = &[
+    ("rust", SupportedLanguage::Rust),
+    ("go", SupportedLanguage::Go),
+    ("python", SupportedLanguage::Python),
+    ("javascript", SupportedLanguage::JavaScript),
+    ("typescript", SupportedLanguage::TypeScript),
+    ("java", SupportedLanguage::Java),
+    ("dart", SupportedLanguage::Dart),
+];
+
 impl SupportedLanguage {
+    /// Every supported language.
+    pub(crate) const ALL: [SupportedLanguage; 7] = [
+        Self::Rust,
+        Self::Go,
+        Self::Python,
+        Self::JavaScript,
+        Self::TypeScript,
+        Self::Java,
+        Self::Dart,
+    ];
+
+    /// Lists the file extensions of the language, without the leading dot.
+    pub(crate) fn extensions(&self) -> impl Iterator<Item = &'static str> {
+        EXTENSIONS
+            .iter()
+            .filter(move |(_, language)| language == self)
+            .map(|(extension, _)| *extension)
+    }
+
+    /// Returns the display name of the language, e.g. `"JavaScript"`.
+    ///
+    /// The name is a static string, so reports can sort and print languages
+    /// without formatting the enum for every row.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Rust => "Rust",
+            Self::Go => "Go",
+            Self::Python => "Python",
+            Self::JavaScript => "JavaScript",
+            Self::TypeScript => "TypeScript",
+            Self::Java => "Java",
+            Self::Dart => "Dart",
+        }
+    }
+
+    /// Looks up a language by its name as written in configuration files.
+    ///
+    /// Names are matched case-insensitively against [`name`](Self::name), so
+    /// both `python` and `Python` are accepted.
+    pub(crate) fn from_config_name(name: &str) -> Option<Self> {
+        EXTENSIONS
+            .iter()
+            .map(|(_, language)| *language)
+            .find(|language| language.name().eq_ignore_ascii_case(name))
+    }
+
     /// Maps Magika's content type label to a supported language.
     ///
     /// # Arguments
@@ -38,16 +126,19 @@ impl SupportedLanguage {
     ///
     /// * `Some(SupportedLanguage)` if the label matches a supported language
     /// * `None` if the label is not a supported programming language
-    fn from_magika_label(label: &str) -> Option<Self> {
-        match label {
-            "rust" => Some(Self::Rust),
-            "go" => Some(Self::Go),
-            "python" => Some(Self::Python),
-            "javascript" => Some(Self::JavaScript),
-            "typescript" => Some(Self::TypeScript),
-            "java" => Some(Self::Java),
-            _ => None,
-        }
+    pub(crate) fn from_magika_label(label: &str) -> Option<Self> {
+        MAGIKA_LABELS
+            .iter()
+            .find(|(known, _)| *known == label)
+            .map(|(_, language)| *language)
+    }
+
+    /// Lists the Magika content type labels detected as the language.
+    pub(crate) fn magika_labels(&self) -> impl Iterator<Item = &'static str> {
+        MAGIKA_LABELS
+            .iter()
+            .filter(move |(_, language)| language == self)
+            .map(|(label, _)| *label)
     }
 
     /// Determines the programming language from a file path using AI-powered content detection.
@@ -74,36 +165,69 @@ impl SupportedLanguage {
     ///
     /// If Magika fails to analyze the file or returns an unsupported language label,
     /// this function automatically falls back to extension-based detection.
-    pub fn from_file_path(file_path: &str) -> Option<Self> {
-        // Try AI-powered detection first
-        let mut magika = match magika::Session::new() {
-            Ok(session) => session,
-            Err(_) => {
-                // Magika initialization failed, fall back to extension-based detection
-                return Self::from_file_extension(file_path);
-            }
-        };
-
-        // Identify the file type using Magika
-        let result = match magika.identify_file_sync(file_path) {
-            Ok(inferred) => inferred,
-            Err(_) => {
-                // Magika detection failed, fall back to extension-based detection
-                return Self::from_file_extension(file_path);
-            }
-        };
+    pub fn from_file_path(file_path: impl AsRef<Path>) -> Option<Self> {
+        Self::from_file_path_with(file_path, DetectionStrategy::Auto)
+    }
 
-        // Get the label and try to map it to our supported languages
-        let label = result.info().label;
+    /// Determines the programming language of a file with the given strategy.
+    ///
+    /// [`DetectionStrategy::Auto`] behaves like [`from_file_path`](Self::from_file_path).
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A path to a file (can be absolute or relative)
+    /// * `strategy` - Whether to use the file's content, its extension, or both
+    ///
+    /// # Returns
+    ///
+    /// * `Some(SupportedLanguage)` if the file is detected as a supported language
+    /// * `None` if the file cannot be detected or is not a supported language
+    pub fn from_file_path_with(
+        file_path: impl AsRef<Path>,
+        strategy: DetectionStrategy,
+    ) -> Option<Self> {
+        let file_path = file_path.as_ref();
+        match strategy {
+            DetectionStrategy::Extension => Self::from_file_extension(file_path),
+            DetectionStrategy::Content | DetectionStrategy::Auto => Self::from_identified_content(
+                file_path,
+                strategy,
+                Self::from_file_content(file_path),
+            ),
+        }
+    }
 
-        // If Magika successfully identified a supported language, use it
-        if let Some(lang) = Self::from_magika_label(label) {
-            return Some(lang);
+    /// Determines the language of a file whose content Magika already identified.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The file, for the extension fallback
+    /// * `strategy` - Whether to use the content, the extension, or both
+    /// * `content` - The language identified from the content, if any
+    pub(crate) fn from_identified_content(
+        file_path: &Path,
+        strategy: DetectionStrategy,
+        content: Option<Self>,
+    ) -> Option<Self> {
+        match strategy {
+            DetectionStrategy::Content => content,
+            DetectionStrategy::Extension => Self::from_file_extension(file_path),
+            // Magika detected something else (e.g., 'txt', 'unknown') or failed
+            DetectionStrategy::Auto => content.or_else(|| Self::from_file_extension(file_path)),
         }
+    }
 
-        // Magika detected something else (e.g., 'txt', 'unknown'),
-        // fall back to extension-based detection
-        Self::from_file_extension(file_path)
+    /// Determines the programming language from a file's content using Magika alone.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(SupportedLanguage)` if Magika identifies a supported language
+    /// * `None` if Magika cannot be initialized, fails to read the file, or
+    ///   identifies something else
+    fn from_file_content(file_path: &Path) -> Option<Self> {
+        let mut magika = magika::Session::new().ok()?;
+        let result = magika.identify_file_sync(file_path).ok()?;
+        Self::from_magika_label(result.info().label)
     }
 
     /// Determines the programming language from a file path based on its extension.
@@ -112,7 +236,8 @@ impl SupportedLanguage {
     /// It extracts the extension from the provided path and maps it to the
     /// corresponding `SupportedLanguage` variant.
     ///
-    /// Used internally as a fallback when Magika cannot detect the file type.
+    /// Used as a fallback when Magika cannot detect the file type, and by the
+    /// pipeline's `ExtensionDetector`. The file does not need to exist.
     ///
     /// # Arguments
     ///
@@ -122,19 +247,14 @@ impl SupportedLanguage {
     ///
     /// * `Some(SupportedLanguage)` if the extension matches a supported language
     /// * `None` if the file has no extension or the extension is not supported
-    pub(crate) fn from_file_extension(file_path: &str) -> Option<Self> {
-        // Extract extension, convert to string, then to lowercase for case-insensitive matching
-        let extension = Path::new(file_path).extension()?.to_str()?.to_lowercase();
-
-        match extension.as_str() {
-            "rs" => Some(Self::Rust),
-            "go" => Some(Self::Go),
-            "py" => Some(Self::Python),
-            "js" => Some(Self::JavaScript),
-            "ts" => Some(Self::TypeScript),
-            "java" => Some(Self::Java),
-            _ => None,
-        }
+    pub fn from_file_extension(file_path: impl AsRef<Path>) -> Option<Self> {
+        // Compare in place instead of lowercasing, so no string is allocated per file
+        let extension = file_path.as_ref().extension()?.to_str()?;
+
+        EXTENSIONS
+            .iter()
+            .find(|(known, _)| extension.eq_ignore_ascii_case(known))
+            .map(|(_, language)| *language)
     }
 
     /// Returns the tree-sitter `Language` instance for this language.
@@ -147,7 +267,9 @@ impl SupportedLanguage {
     ///
     /// TypeScript uses `LANGUAGE_TYPESCRIPT` instead of `LANGUAGE` because
     /// the tree-sitter-typescript crate provides separate language definitions
-    /// for TypeScript and TSX, with TypeScript being the primary one.
+    /// for TypeScript and TSX, with TypeScript being the primary one. The
+    /// analyzer parses `.tsx` files with the TSX definition instead, which
+    /// this method never returns.
     pub fn get_language(&self) -> Language {
         match self {
             Self::Rust => tree_sitter_rust::LANGUAGE.into(),
@@ -156,10 +278,318 @@ impl SupportedLanguage {
             Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
             Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
             Self::Java => tree_sitter_java::LANGUAGE.into(),
+            Self::Dart => tree_sitter_dart::LANGUAGE.into(),
+        }
+    }
+
+    /// Returns the AST node kinds that represent string literals in this language.
+    ///
+    /// Only the outermost literal node is listed; fragments nested inside a
+    /// literal (e.g. `string_content`) are not, so each literal is seen once.
+    pub(crate) fn string_literal_kinds(&self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["string_literal", "raw_string_literal"],
+            Self::Go => &["interpreted_string_literal", "raw_string_literal"],
+            Self::Python => &["string"],
+            Self::JavaScript | Self::TypeScript => &["string", "template_string"],
+            Self::Java | Self::Dart => &["string_literal"],
+        }
+    }
+
+    /// Returns the AST node kinds that represent comments in this language.
+    pub(crate) fn comment_kinds(&self) -> &'static [&'static str] {
+        match self {
+            Self::Rust | Self::Java => &["line_comment", "block_comment"],
+            Self::Go | Self::Python | Self::JavaScript | Self::TypeScript | Self::Dart => {
+                &["comment"]
+            }
+        }
+    }
+
+    /// Returns the AST node kinds that represent numeric literals in this language.
+    pub(crate) fn numeric_literal_kinds(&self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["integer_literal", "float_literal"],
+            Self::Go => &["int_literal", "float_literal", "imaginary_literal"],
+            Self::Python => &["integer", "float"],
+            Self::JavaScript | Self::TypeScript => &["number"],
+            Self::Java => &[
+                "decimal_integer_literal",
+                "hex_integer_literal",
+                "octal_integer_literal",
+                "binary_integer_literal",
+                "decimal_floating_point_literal",
+                "hex_floating_point_literal",
+            ],
+            Self::Dart => &[
+                "decimal_integer_literal",
+                "hex_integer_literal",
+                "decimal_floating_point_literal",
+            ],
+        }
+    }
+}
+
+/// How a language is recognized and counted, listed by the `languages`
+/// subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct LanguageCoverage {
+    /// Display name, as in reports
+    pub name: &'static str,
+    /// File extensions, without the leading dot
+    pub extensions: Vec<&'static str>,
+    /// Magika content type labels
+    pub magika_labels: Vec<&'static str>,
+    /// Node kinds of the built-in query by capture name, e.g. `function`
+    pub node_kinds: BTreeMap<&'static str, Vec<&'static str>>,
+}
+
+impl LanguageCoverage {
+    /// Describes every supported language, ordered by name.
+    pub(crate) fn all() -> Vec<Self> {
+        let mut languages = SupportedLanguage::ALL;
+        languages.sort();
+        languages
+            .into_iter()
+            .map(|language| Self {
+                name: language.name(),
+                extensions: language.extensions().collect(),
+                magika_labels: language.magika_labels().collect(),
+                node_kinds: declaration_kinds(language),
+            })
+            .collect()
+    }
+}
+
+/// The tree-sitter grammar a file is parsed with.
+///
+/// Most languages have a single grammar. TypeScript has two: `.tsx` files need
+/// the TSX grammar to parse JSX elements, while `.ts` files must keep the
+/// TypeScript grammar because TSX rejects `<Type>value` assertions. JavaScript
+/// needs no variant, as its grammar parses JSX everywhere. The grammar only
+/// affects parsing; files are still reported under their language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Grammar {
+    /// The language's primary grammar, see [`SupportedLanguage::get_language`]
+    Primary(SupportedLanguage),
+    /// TypeScript with JSX elements
+    Tsx,
+}
+
+impl Grammar {
+    /// Every grammar, each language's primary one first.
+    pub(crate) const ALL: [Grammar; 8] = [
+        Self::Primary(SupportedLanguage::Rust),
+        Self::Primary(SupportedLanguage::Go),
+        Self::Primary(SupportedLanguage::Python),
+        Self::Primary(SupportedLanguage::JavaScript),
+        Self::Primary(SupportedLanguage::TypeScript),
+        Self::Primary(SupportedLanguage::Java),
+        Self::Primary(SupportedLanguage::Dart),
+        Self::Tsx,
+    ];
+
+    /// Selects the grammar for a file of the given language.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The detected language of the file
+    /// * `file_path` - Path of the file; only its extension is inspected
+    pub(crate) fn for_file(language: SupportedLanguage, file_path: impl AsRef<Path>) -> Self {
+        let is_tsx = file_path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("tsx"));
+
+        if language == SupportedLanguage::TypeScript && is_tsx {
+            Self::Tsx
+        } else {
+            Self::Primary(language)
+        }
+    }
+
+    /// Returns the grammar a tree of the given language was parsed with.
+    pub(crate) fn of_tree(language: SupportedLanguage, tree: &Tree) -> Self {
+        if language == SupportedLanguage::TypeScript && *tree.language() == Self::Tsx.get_language()
+        {
+            Self::Tsx
+        } else {
+            Self::Primary(language)
+        }
+    }
+
+    /// Returns the language files parsed with this grammar are reported under.
+    pub(crate) fn language(&self) -> SupportedLanguage {
+        match self {
+            Self::Primary(language) => *language,
+            Self::Tsx => SupportedLanguage::TypeScript,
+        }
+    }
+
+    /// Returns the tree-sitter `Language` instance for this grammar.
+    pub(crate) fn get_language(&self) -> Language {
+        match self {
+            Self::Primary(language) => language.get_language(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        }
+    }
+}
+
+/// How the language of a file is detected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DetectionStrategy {
+    /// Identify the content with Magika only; files it cannot identify are skipped
+    Content,
+    /// Match the file extension only, without reading the file (fastest)
+    Extension,
+    /// Identify the content with Magika, falling back to the extension
+    #[default]
+    Auto,
+}
+
+/// Changes to the extension table, applied before content sniffing.
+///
+/// Added extensions are mapped to their language directly, without running
+/// Magika. A removed extension is never detected as its language, neither by
+/// Magika nor by the extension fallback; other languages are still detected.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct ExtensionOverrides {
+    /// Extensions (lowercase, without a dot) forced to a language
+    added: Vec<(String, SupportedLanguage)>,
+    /// Extensions (lowercase, without a dot) that must not map to a language
+    removed: Vec<(String, SupportedLanguage)>,
+}
+
+impl ExtensionOverrides {
+    /// Recognizes files with `extension` as `language`.
+    ///
+    /// Replaces any earlier addition of the same extension and cancels an
+    /// earlier removal of it for this language.
+    pub(crate) fn add(&mut self, extension: &str, language: SupportedLanguage) {
+        let extension = normalize_extension(extension);
+        self.added.retain(|(known, _)| *known != extension);
+        self.removed
+            .retain(|entry| *entry != (extension.clone(), language));
+        self.added.push((extension, language));
+    }
+
+    /// Creates overrides from mappings given as `EXT=LANGUAGE`, e.g. on the command line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ExtensionOverrides)` adding every mapping, later ones winning
+    /// * `Err(String)` describing the first invalid specification
+    pub(crate) fn from_mappings(specs: &[String]) -> Result<Self, String> {
+        let mut overrides = Self::default();
+        for spec in specs {
+            overrides.add_mapping(spec)?;
+        }
+        Ok(overrides)
+    }
+
+    /// Adds a mapping given as `EXT=LANGUAGE`, e.g. `pyx=python`.
+    ///
+    /// Language names are matched like in configuration files.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the mapping was added
+    /// * `Err(String)` describing why the specification is invalid
+    fn add_mapping(&mut self, spec: &str) -> Result<(), String> {
+        let (extension, name) = spec
+            .split_once('=')
+            .map(|(extension, name)| (extension.trim(), name.trim()))
+            .filter(|(extension, _)| !extension.trim_start_matches('.').is_empty())
+            .ok_or_else(|| format!("Invalid extension mapping '{spec}': expected EXT=LANGUAGE"))?;
+
+        let language = SupportedLanguage::from_config_name(name).ok_or_else(|| {
+            format!("Invalid extension mapping '{spec}': unknown language `{name}`")
+        })?;
+        self.add(extension, language);
+        Ok(())
+    }
+
+    /// Stops recognizing files with `extension` as `language`.
+    ///
+    /// Cancels an earlier addition of the extension for this language.
+    pub(crate) fn remove(&mut self, extension: &str, language: SupportedLanguage) {
+        let extension = normalize_extension(extension);
+        self.added
+            .retain(|entry| *entry != (extension.clone(), language));
+        self.removed.push((extension, language));
+    }
+
+    /// Detects the language of a file, honoring the overrides.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The file to detect
+    /// * `strategy` - How to detect files whose extension was not added
+    ///
+    /// # Returns
+    ///
+    /// * `Some(SupportedLanguage)` - The added language of the file's extension,
+    ///   or the detected language unless it was removed for this extension
+    /// * `None` if the file is not a supported language
+    pub(crate) fn detect(
+        &self,
+        file_path: &Path,
+        strategy: DetectionStrategy,
+    ) -> Option<SupportedLanguage> {
+        self.resolve(file_path, || {
+            SupportedLanguage::from_file_path_with(file_path, strategy)
+        })
+    }
+
+    /// Detects the language of a file whose content Magika already identified.
+    ///
+    /// Behaves like [`detect`](Self::detect) without identifying the content again.
+    pub(crate) fn detect_identified(
+        &self,
+        file_path: &Path,
+        strategy: DetectionStrategy,
+        content: Option<SupportedLanguage>,
+    ) -> Option<SupportedLanguage> {
+        self.resolve(file_path, || {
+            SupportedLanguage::from_identified_content(file_path, strategy, content)
+        })
+    }
+
+    /// Applies the overrides around a detection, which only runs for
+    /// extensions that were not added.
+    fn resolve(
+        &self,
+        file_path: &Path,
+        detect: impl FnOnce() -> Option<SupportedLanguage>,
+    ) -> Option<SupportedLanguage> {
+        let extension = file_path.extension().and_then(|ext| ext.to_str());
+
+        if let Some(extension) = extension
+            && let Some((_, language)) = self
+                .added
+                .iter()
+                .find(|(known, _)| extension.eq_ignore_ascii_case(known))
+        {
+            return Some(*language);
         }
+
+        let language = detect()?;
+        let is_removed = extension.is_some_and(|extension| {
+            self.removed.iter().any(|(known, removed)| {
+                *removed == language && extension.eq_ignore_ascii_case(known)
+            })
+        });
+
+        (!is_removed).then_some(language)
     }
 }
 
+/// Lowercases an extension and strips a leading dot, e.g. `.CGI` to `cgi`.
+fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_ascii_lowercase()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,6 +620,44 @@ mod tests {
             SupportedLanguage::from_file_extension("Main.java"),
             Some(SupportedLanguage::Java)
         ));
+        assert!(matches!(
+            SupportedLanguage::from_file_extension("main.dart"),
+            Some(SupportedLanguage::Dart)
+        ));
+    }
+
+    #[test]
+    fn test_from_file_extension_jsx_and_tsx() {
+        assert_eq!(
+            SupportedLanguage::from_file_extension("App.jsx"),
+            Some(SupportedLanguage::JavaScript)
+        );
+        assert_eq!(
+            SupportedLanguage::from_file_extension("App.TSX"),
+            Some(SupportedLanguage::TypeScript)
+        );
+    }
+
+    #[test]
+    fn test_grammar_for_file() {
+        assert_eq!(
+            Grammar::for_file(SupportedLanguage::TypeScript, "src/App.tsx"),
+            Grammar::Tsx
+        );
+        assert_eq!(
+            Grammar::for_file(SupportedLanguage::TypeScript, "src/app.ts"),
+            Grammar::Primary(SupportedLanguage::TypeScript)
+        );
+        // The JavaScript grammar already understands JSX
+        assert_eq!(
+            Grammar::for_file(SupportedLanguage::JavaScript, "App.jsx"),
+            Grammar::Primary(SupportedLanguage::JavaScript)
+        );
+        // A `.tsx` file detected as another language keeps that language's grammar
+        assert_eq!(
+            Grammar::for_file(SupportedLanguage::JavaScript, "App.tsx"),
+            Grammar::Primary(SupportedLanguage::JavaScript)
+        );
     }
 
     #[test]
@@ -254,6 +722,13 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_name_matches_debug_output() {
+        for (_, language) in EXTENSIONS {
+            assert_eq!(language.name(), format!("{language:?}"));
+        }
+    }
+
     #[test]
     fn test_get_language() {
         // Test that each language variant returns a valid Language instance
@@ -339,6 +814,49 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn test_from_file_path_with_extension_strategy_ignores_content() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let script = temp_dir.path().join("script");
+        let misnamed = temp_dir.path().join("notes.rs");
+        std::fs::write(&script, "#!/usr/bin/env python3\ndef main():\n    pass\n").unwrap();
+        std::fs::write(&misnamed, "These are plain notes.\n").unwrap();
+
+        let strategy = DetectionStrategy::Extension;
+        assert_eq!(
+            SupportedLanguage::from_file_path_with(&script, strategy),
+            None
+        );
+        assert_eq!(
+            SupportedLanguage::from_file_path_with(&misnamed, strategy),
+            Some(SupportedLanguage::Rust)
+        );
+
+        // Files that do not exist can still be matched by name
+        assert_eq!(
+            SupportedLanguage::from_file_path_with(temp_dir.path().join("gone.go"), strategy),
+            Some(SupportedLanguage::Go)
+        );
+    }
+
+    #[test]
+    fn test_language_coverage() {
+        let languages = LanguageCoverage::all();
+        assert_eq!(languages.len(), SupportedLanguage::ALL.len());
+        assert!(languages.is_sorted_by_key(|language| language.name));
+
+        let typescript = languages
+            .iter()
+            .find(|language| language.name == "TypeScript")
+            .unwrap();
+        assert_eq!(typescript.extensions, ["ts", "tsx"]);
+        assert_eq!(typescript.magika_labels, ["typescript"]);
+        assert_eq!(
+            typescript.node_kinds["interface"],
+            ["interface_declaration"]
+        );
+    }
+
     #[test]
     fn test_from_magika_label() {
         // Test the internal label mapping
@@ -366,7 +884,117 @@ mod tests {
             SupportedLanguage::from_magika_label("java"),
             Some(SupportedLanguage::Java)
         );
+        assert_eq!(
+            SupportedLanguage::from_magika_label("dart"),
+            Some(SupportedLanguage::Dart)
+        );
         assert_eq!(SupportedLanguage::from_magika_label("txt"), None);
         assert_eq!(SupportedLanguage::from_magika_label("unknown"), None);
     }
+
+    #[test]
+    fn test_from_config_name() {
+        assert_eq!(
+            SupportedLanguage::from_config_name("python"),
+            Some(SupportedLanguage::Python)
+        );
+        assert_eq!(
+            SupportedLanguage::from_config_name("JavaScript"),
+            Some(SupportedLanguage::JavaScript)
+        );
+        assert_eq!(SupportedLanguage::from_config_name("cobol"), None);
+    }
+
+    #[test]
+    fn test_extension_overrides_add_mapping() {
+        let mut overrides =
+            ExtensionOverrides::from_mappings(&["pyx=python".into(), ".MJS = JavaScript".into()])
+                .unwrap();
+
+        let strategy = DetectionStrategy::Extension;
+        assert_eq!(
+            overrides.detect(Path::new("fast.pyx"), strategy),
+            Some(SupportedLanguage::Python)
+        );
+        assert_eq!(
+            overrides.detect(Path::new("app.mjs"), strategy),
+            Some(SupportedLanguage::JavaScript)
+        );
+
+        for spec in ["pyx", "=python", "pyx=cython"] {
+            let error = overrides.add_mapping(spec).unwrap_err();
+            assert!(error.starts_with(&format!("Invalid extension mapping '{spec}'")));
+        }
+    }
+
+    #[test]
+    fn test_extension_overrides_add_and_remove() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cgi = temp_dir.path().join("form.CGI");
+        let suite = temp_dir.path().join("suite.ts");
+        let main = temp_dir.path().join("main.ts");
+        std::fs::write(&cgi, "print('hello')\n").unwrap();
+        std::fs::write(&suite, "<TestSuite/>\n").unwrap();
+        std::fs::write(&main, "let x: number = 1;\n").unwrap();
+
+        let mut overrides = ExtensionOverrides::default();
+        assert_eq!(overrides.detect(&cgi, DetectionStrategy::Auto), None);
+        assert_eq!(
+            overrides.detect(&suite, DetectionStrategy::Auto),
+            Some(SupportedLanguage::TypeScript)
+        );
+
+        overrides.add(".cgi", SupportedLanguage::Python);
+        overrides.remove("ts", SupportedLanguage::TypeScript);
+        assert_eq!(
+            overrides.detect(&cgi, DetectionStrategy::Auto),
+            Some(SupportedLanguage::Python)
+        );
+        assert_eq!(overrides.detect(&suite, DetectionStrategy::Auto), None);
+        assert_eq!(overrides.detect(&main, DetectionStrategy::Auto), None);
+
+        // A later addition re-enables the extension
+        overrides.add("ts", SupportedLanguage::TypeScript);
+        assert_eq!(
+            overrides.detect(&main, DetectionStrategy::Auto),
+            Some(SupportedLanguage::TypeScript)
+        );
+    }
+
+    #[test]
+    fn test_detect_identified_uses_content_result() {
+        let path = Path::new("script.cgi");
+        let python = Some(SupportedLanguage::Python);
+        assert_eq!(
+            SupportedLanguage::from_identified_content(path, DetectionStrategy::Content, python),
+            python
+        );
+        assert_eq!(
+            SupportedLanguage::from_identified_content(path, DetectionStrategy::Extension, python),
+            None
+        );
+
+        // Auto falls back to the extension when the content is not recognized
+        let path = Path::new("main.go");
+        assert_eq!(
+            SupportedLanguage::from_identified_content(path, DetectionStrategy::Auto, None),
+            Some(SupportedLanguage::Go)
+        );
+        assert_eq!(
+            SupportedLanguage::from_identified_content(path, DetectionStrategy::Content, None),
+            None
+        );
+
+        let mut overrides = ExtensionOverrides::default();
+        overrides.add("cgi", SupportedLanguage::Rust);
+        overrides.remove("go", SupportedLanguage::Go);
+        assert_eq!(
+            overrides.detect_identified(Path::new("a.cgi"), DetectionStrategy::Auto, python),
+            Some(SupportedLanguage::Rust)
+        );
+        assert_eq!(
+            overrides.detect_identified(path, DetectionStrategy::Auto, None),
+            None
+        );
+    }
 }
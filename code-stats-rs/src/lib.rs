@@ -9,32 +9,221 @@
 //! The crate is organized into several modules:
 //!
 //! - `analyzer` - Core analysis engine that orchestrates parsing and statistics collection
+//! - `archive` - Reading the files of zip and tar archives into memory
+//! - `audit` - Policy audits such as license header checks
+//! - `badge` - shields.io-style SVG badges of a metric or language share
+//! - `cache` - On-disk cache of per-file results keyed by content hash
 //! - `cli` - Command-line interface and argument parsing
+//! - `components` - Detection of React components in JavaScript and TypeScript
+//! - `config` - Cascading per-directory `.code-stats.toml` settings
+//! - `detection` - Parallel batch content detection ahead of directory analysis
+//! - `diff` - Per-language and per-file changes since a baseline report
+//! - `documentation` - Detection of documented functions
 //! - `error` - Error types and handling
+//! - `fixtures` - Synthetic repository generation (`gen-fixtures` feature)
+//! - `findings` - Severity-tiered issues reported with the statistics
 //! - `formatter` - Output formatting for different display modes
+//! - `functions` - Parameter counts and block nesting of functions
+//! - `git` - Invocation of the `git` command line
+//! - `hierarchy` - Inheritance relationships between declared types
+//! - `history` - Time series of statistics across git commits
+//! - `hotspots` - Ranking of files by commit churn times complexity
+//! - `i18n` - Detection of user-facing strings missing translation
+//! - `interrupt` - Ctrl-C handling that stops runs with a partial report
 //! - `language` - Language detection and configuration
+//! - `lines` - Code, comment and blank line counting
+//! - `metrics` - Halstead volume and maintainability index of files
+//! - `multi` - Consolidated reports across repositories listed in a manifest
+//! - `obfuscation` - Detection of minified or obfuscated files
+//! - `origin` - Classification of vendored and generated files
+//! - `ownership` - Per-directory authorship concentration from git blame
+//! - `pager` - Paging of long reports on interactive terminals
+//! - `paths` - Allocation-free path matching helpers
 //! - `parser` - Tree-sitter integration and AST traversal
+//! - `pipeline` - Public source → detect → count → aggregate → format stages, and saved reports
+//! - `profile` - Per-file detection, reading and parsing times for `--profile`
+//! - `provenance` - in-toto attestations and signatures proving reports are unmodified
+//! - `queries` - Tree-sitter queries selecting the counted declarations, built-in and custom
+//! - `rust_modules` - Attribution of Rust declarations to their module paths
+//! - `secrets` - Secret pattern scanning over string literals
+//! - `source` - Source file loading, optionally memory-mapped
 //! - `stats` - Data structures for storing analysis results
+//! - `symbols` - Named declarations with their spans, indexed through the cache
+//! - `test_code` - Separation of test code from production code
+//! - `thresholds` - `--fail-if` rules that gate CI on metric limits
+//! - `visibility` - Public and private declarations forming the API surface
+//! - `visitor` - Callback hook for custom metrics during traversal
+//! - `walk` - Directory traversal skipping hidden, ignored, duplicate and oversized files
 //!
 //! See the `language` module for supported programming languages.
+//!
+//! # Library API
+//!
+//! [`count_source`] counts the statistics of in-memory source text without
+//! touching the file system:
+//!
+//! ```
+//! use code_stats_rs::{SupportedLanguage, count_source};
+//!
+//! let stats = count_source(SupportedLanguage::Python, "def f():\n    pass\n");
+//! assert_eq!(stats.function_count, 1);
+//! ```
+//!
+//! [`count_source_with`] additionally calls a [`NodeVisitor`] for every syntax
+//! node, so custom metrics can be computed from the same parse.
+//!
+//! The [`pipeline`] module exposes the stages of a whole analysis, so
+//! embedders can, for instance, analyze editor buffers instead of files
+//! while reusing detection, counting, aggregation and report formatting.
+//! Its [`AnalysisStream`](pipeline::AnalysisStream) yields each file's
+//! statistics as soon as they are counted, for custom progress displays and
+//! aggregations. With the `async` feature,
+//! `pipeline::AsyncDirectoryAnalysis` analyzes a directory on a tokio
+//! runtime with bounded concurrency, for embedding in async services.
 
 /// Core analysis engine for processing files and directories.
 mod analyzer;
 
+/// In-memory reading of `.zip`, `.tar` and `.tar.gz` archives.
+mod archive;
+
+/// Repository policy audits over analyzed source files.
+mod audit;
+
+/// SVG badges for READMEs.
+mod badge;
+
+/// Persistent per-file result cache.
+mod cache;
+
 /// Command-line interface definitions and execution logic.
 pub mod cli;
 
+/// React function and class component detection.
+mod components;
+
+/// Per-directory configuration files.
+mod config;
+
+/// Batch identification of file contents with Magika.
+mod detection;
+
+/// Comparison of an analysis against a saved baseline report.
+mod diff;
+
+/// Doc comment and docstring detection.
+mod documentation;
+
 /// Error types and result definitions.
 mod error;
 
+/// Synthetic repository generation for benchmarks and stress tests.
+#[cfg(feature = "gen-fixtures")]
+mod fixtures;
+
+/// Errors, warnings and informational findings of an analysis run.
+mod findings;
+
 /// Output formatting utilities for different display modes.
 mod formatter;
 
+/// Per-function parameter and nesting measurements.
+mod functions;
+
+/// Wrapper around the `git` command line.
+mod git;
+
+/// Class and interface inheritance extraction.
+mod hierarchy;
+
+/// Per-commit statistics over a git history.
+mod history;
+
+/// Files ranked by churn times complexity.
+mod hotspots;
+
+/// Heuristic detection of untranslated user-facing strings.
+mod i18n;
+
+/// Signal handling for interruptible runs.
+mod interrupt;
+
 /// Language detection and tree-sitter language configuration.
 mod language;
 
+/// Line classification into code, comment and blank lines.
+mod lines;
+
+/// Halstead and maintainability index metrics behind `--metrics extended`.
+mod metrics;
+
+/// Multi-repository aggregation driven by a manifest.
+mod multi;
+
+/// Heuristics for obfuscated and machine-generated files.
+mod obfuscation;
+
+/// Vendored and generated file classification.
+mod origin;
+
+/// Authorship concentration per directory from git blame.
+mod ownership;
+
+/// Display of long reports through an external pager.
+mod pager;
+
+/// Path matching helpers used during traversal.
+mod paths;
+
 /// Tree-sitter parsing and AST analysis.
 mod parser;
 
+/// Public analysis stages for embedding the analyzer in other tools.
+pub mod pipeline;
+
+/// Per-file timings recorded with `--profile`.
+mod profile;
+
+/// Signed provenance attestations of reports.
+mod provenance;
+
+/// Built-in and user-supplied tree-sitter count queries.
+mod queries;
+
+/// Per-module counts of Rust functions and types.
+mod rust_modules;
+
+/// Secret pattern scanning over string literals.
+mod secrets;
+
+/// Source file loading through reads or memory maps.
+mod source;
+
+/// Symbol tables of named declarations, cached as a symbol index.
+mod symbols;
+
 /// Statistics data structures for storing analysis results.
 mod stats;
+
+/// Test file and inline test module detection.
+mod test_code;
+
+/// Metric limits checked after an analysis.
+mod thresholds;
+
+/// Visibility of declarations for API surface counts.
+mod visibility;
+
+/// Node visitor hook for custom metrics.
+mod visitor;
+
+/// Directory traversal shared by directory analysis and the pipeline.
+mod walk;
+
+pub use error::{CodeStatsError, ErrorKind};
+pub use language::{DetectionStrategy, SupportedLanguage};
+pub use parser::{CodeStats, count_source, count_source_with};
+pub use visitor::NodeVisitor;
+
+/// The tree-sitter version whose nodes are passed to a [`NodeVisitor`].
+pub use tree_sitter;
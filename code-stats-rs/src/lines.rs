@@ -0,0 +1,222 @@
+//! Classification of source lines into code, comment and blank lines.
+
+use crate::language::SupportedLanguage;
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+/// Line counts of a single source file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LineCounts {
+    /// Lines containing at least one character of code
+    pub code: usize,
+    /// Lines containing only comments (and whitespace)
+    pub comment: usize,
+    /// Lines containing only whitespace
+    pub blank: usize,
+}
+
+impl LineCounts {
+    /// Returns the total number of lines.
+    pub(crate) fn total(&self) -> usize {
+        self.code + self.comment + self.blank
+    }
+
+    /// Records one line given what it contained.
+    fn add_line(&mut self, has_code: bool, has_comment: bool) {
+        if has_code {
+            self.code += 1;
+        } else if has_comment {
+            self.comment += 1;
+        } else {
+            self.blank += 1;
+        }
+    }
+}
+
+/// Counts code, comment and blank lines using the comment nodes of a parsed tree.
+///
+/// A line counts as code if any non-whitespace character on it lies outside a
+/// comment, so a statement with a trailing comment is a code line. Python
+/// docstrings are counted as comments, as cloc does. A final line without a
+/// trailing newline is counted; an empty file has no lines.
+pub(crate) fn count_lines(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+) -> LineCounts {
+    count_lines_where(tree, source_code, language, |_| true)
+}
+
+/// Counts code, comment and blank lines among the given rows only.
+///
+/// Rows are zero-based, as in tree-sitter positions; lines are classified as
+/// in [`count_lines`].
+pub(crate) fn count_lines_in_rows(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    rows: &[Range<usize>],
+) -> LineCounts {
+    count_lines_where(tree, source_code, language, |row| {
+        rows.iter().any(|range| range.contains(&row))
+    })
+}
+
+/// Counts the lines whose row `include` accepts.
+fn count_lines_where(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    include: impl Fn(usize) -> bool,
+) -> LineCounts {
+    let mut comments = Vec::new();
+    collect_comment_ranges(&tree.root_node(), language, &mut comments);
+    comments.sort_by_key(|range| range.start);
+
+    let mut counts = LineCounts::default();
+    let mut comments = comments.into_iter().peekable();
+    let mut row = 0;
+    let mut has_code = false;
+    let mut has_comment = false;
+
+    for (offset, ch) in source_code.char_indices() {
+        if ch == '\n' {
+            if include(row) {
+                counts.add_line(has_code, has_comment);
+            }
+            row += 1;
+            has_code = false;
+            has_comment = false;
+            continue;
+        }
+        if ch.is_whitespace() {
+            continue;
+        }
+
+        while comments.next_if(|range| range.end <= offset).is_some() {}
+        if comments.peek().is_some_and(|range| range.contains(&offset)) {
+            has_comment = true;
+        } else {
+            has_code = true;
+        }
+    }
+
+    if !source_code.is_empty() && !source_code.ends_with('\n') && include(row) {
+        counts.add_line(has_code, has_comment);
+    }
+
+    counts
+}
+
+/// Collects the byte ranges of all comments (and Python docstrings) in the tree.
+///
+/// Nested comment nodes (e.g. Rust doc comment content) are not visited, since
+/// their range is already covered by the enclosing comment.
+fn collect_comment_ranges(
+    node: &Node,
+    language: &SupportedLanguage,
+    ranges: &mut Vec<Range<usize>>,
+) {
+    let is_docstring = *language == SupportedLanguage::Python
+        && node.kind() == "string"
+        && node
+            .parent()
+            .is_some_and(|parent| parent.kind() == "expression_statement");
+
+    if language.comment_kinds().contains(&node.kind()) || is_docstring {
+        ranges.push(node.byte_range());
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_comment_ranges(&child, language, ranges);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{create_parser, parse_source};
+
+    fn count(source: &str, language: SupportedLanguage) -> LineCounts {
+        let mut parser = create_parser(&language).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+        count_lines(&tree, source, &language)
+    }
+
+    #[test]
+    fn test_count_lines_rust() {
+        let source = "\
+//! Crate docs
+/// Function docs
+fn main() { // trailing comment
+    /* block
+       comment */
+
+    let x = 1;
+}
+";
+        assert_eq!(
+            count(source, SupportedLanguage::Rust),
+            LineCounts {
+                code: 3,
+                comment: 4,
+                blank: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_count_lines_python_docstring_and_hash_comments() {
+        let source =
+            "\"\"\"Module docstring.\"\"\"\n\n# comment\ndef f():\n    return \"# not a comment\"";
+        let counts = count(source, SupportedLanguage::Python);
+
+        assert_eq!(counts.code, 2);
+        assert_eq!(counts.comment, 2);
+        assert_eq!(counts.blank, 1);
+        assert_eq!(counts.total(), 5);
+    }
+
+    #[test]
+    fn test_count_lines_in_rows() {
+        let source = "fn a() {}\n\n// c\nfn b() {}\nfn c() {}";
+        let mut parser = create_parser(&SupportedLanguage::Rust).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+
+        let counts = count_lines_in_rows(&tree, source, &SupportedLanguage::Rust, &[1..3, 4..5]);
+        assert_eq!(
+            counts,
+            LineCounts {
+                code: 1,
+                comment: 1,
+                blank: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_count_lines_empty_source() {
+        assert_eq!(count("", SupportedLanguage::Go), LineCounts::default());
+    }
+
+    #[test]
+    fn test_count_lines_comment_kinds_per_language() {
+        let cases = [
+            (SupportedLanguage::Go, "// c\npackage main\n"),
+            (SupportedLanguage::JavaScript, "// c\nlet x = 1;\n"),
+            (
+                SupportedLanguage::TypeScript,
+                "/* c */\nlet x: number = 1;\n",
+            ),
+            (SupportedLanguage::Java, "// c\nclass A {}\n"),
+            (SupportedLanguage::Dart, "/// c\nclass A {}\n"),
+        ];
+
+        for (language, source) in cases {
+            let counts = count(source, language);
+            assert_eq!((counts.code, counts.comment), (1, 1), "{language:?}");
+        }
+    }
+}
@@ -1,17 +1,30 @@
 //! Entry point for the code-stats-rs command-line tool.
 
-use clap::Parser;
-use code_stats_rs::cli::Cli;
+use clap::FromArgMatches;
+use code_stats_rs::cli::{Cli, RunError, command};
 
 /// Main entry point for the code statistics analyzer.
 ///
 /// Parses command-line arguments and executes the analysis.
-/// Exits with status code 1 if an error occurs.
+/// Exits with status code 1 on usage errors or if an error occurs, 2 if a
+/// `--fail-if` rule is violated, 3 if `--check` is set and some files could
+/// not be analyzed, or 130 if interrupted after printing a partial report.
 fn main() {
-    let cli = Cli::parse();
+    let cli = command()
+        .try_get_matches()
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+        .unwrap_or_else(|e| {
+            // Usage errors share status 1 with other failures; 2 means a violated rule
+            let _ = e.print();
+            std::process::exit(if e.use_stderr() {
+                RunError::EXIT_CODE
+            } else {
+                0
+            });
+        });
 
     if let Err(e) = cli.run() {
         eprintln!("Error: {e}");
-        std::process::exit(1);
+        std::process::exit(e.exit_code);
     }
 }
@@ -0,0 +1,210 @@
+//! Halstead volume and maintainability index of files.
+//!
+//! Halstead's metrics treat a program as a sequence of operators and
+//! operands. Every token of the syntax tree is one or the other: keywords
+//! and punctuation are operators, identifiers and literals operands. The
+//! maintainability index combines the resulting volume with the cyclomatic
+//! complexity and the number of code lines.
+
+use crate::language::SupportedLanguage;
+use crate::visitor::NodeVisitor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tree_sitter::Node;
+
+/// Node kinds that add a path through the code, for the cyclomatic complexity.
+const DECISION_KINDS: [&str; 26] = [
+    "if_statement",
+    "if_expression",
+    "elif_clause",
+    "for_statement",
+    "for_in_statement",
+    "enhanced_for_statement",
+    "for_expression",
+    "while_statement",
+    "while_expression",
+    "do_statement",
+    "match_arm",
+    "switch_case",
+    "switch_label",
+    "switch_statement_case",
+    "expression_case",
+    "type_case",
+    "communication_case",
+    "case_clause",
+    "catch_clause",
+    "except_clause",
+    "conditional_expression",
+    "ternary_expression",
+    "&&",
+    "||",
+    "and",
+    "or",
+];
+
+/// Extended metrics of a file, computed with `--metrics extended`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ExtendedMetrics {
+    /// Number of different operators (η1)
+    pub distinct_operators: usize,
+    /// Number of different operands (η2)
+    pub distinct_operands: usize,
+    /// Number of operator occurrences (N1)
+    pub total_operators: usize,
+    /// Number of operand occurrences (N2)
+    pub total_operands: usize,
+    /// Halstead volume, `(N1 + N2) × log2(η1 + η2)`
+    pub volume: f64,
+    /// One plus the number of branches, loops, cases and short-circuit operators
+    pub cyclomatic_complexity: usize,
+    /// `171 − 5.2 ln(volume) − 0.23 complexity − 16.2 ln(code lines)`,
+    /// rescaled to 0 (unmaintainable) to 100
+    pub maintainability_index: f64,
+}
+
+/// Collects operators, operands and decision points while the statistics
+/// are counted.
+///
+/// Operators are told apart by their kind, operands by their text. A string
+/// literal is a single operand, including its quotes and escapes; comments
+/// and tokens inserted by error recovery are ignored.
+#[derive(Debug, Default)]
+pub(crate) struct HalsteadCounter {
+    operators: HashSet<&'static str>,
+    operands: HashSet<String>,
+    total_operators: usize,
+    total_operands: usize,
+    decisions: usize,
+}
+
+impl NodeVisitor for HalsteadCounter {
+    fn visit(&mut self, language: SupportedLanguage, node: &Node<'_>, source: &str) {
+        if DECISION_KINDS.contains(&node.kind()) {
+            self.decisions += 1;
+        }
+
+        let comments = language.comment_kinds();
+        let parent_kind = node.parent().map(|parent| parent.kind());
+        if node.is_missing()
+            || node.start_byte() == node.end_byte()
+            || comments.contains(&node.kind())
+            || parent_kind.is_some_and(|kind| comments.contains(&kind) || is_string_kind(kind))
+        {
+            return;
+        }
+
+        if is_string_kind(node.kind()) || (node.is_named() && node.child_count() == 0) {
+            self.total_operands += 1;
+            self.operands.insert(source[node.byte_range()].to_string());
+        } else if node.child_count() == 0 {
+            self.total_operators += 1;
+            self.operators.insert(node.kind());
+        }
+    }
+}
+
+impl HalsteadCounter {
+    /// Computes the metrics of the file the counter visited.
+    ///
+    /// # Arguments
+    ///
+    /// * `code_lines` - The file's lines holding code
+    pub(crate) fn finish(self, code_lines: usize) -> ExtendedMetrics {
+        let vocabulary = self.operators.len() + self.operands.len();
+        let length = self.total_operators + self.total_operands;
+        let volume = if vocabulary == 0 {
+            0.0
+        } else {
+            length as f64 * (vocabulary as f64).log2()
+        };
+        let cyclomatic_complexity = self.decisions + 1;
+
+        ExtendedMetrics {
+            distinct_operators: self.operators.len(),
+            distinct_operands: self.operands.len(),
+            total_operators: self.total_operators,
+            total_operands: self.total_operands,
+            volume,
+            cyclomatic_complexity,
+            maintainability_index: maintainability_index(volume, cyclomatic_complexity, code_lines),
+        }
+    }
+}
+
+/// Computes the maintainability index on the 0 to 100 scale; files without
+/// code are perfectly maintainable.
+fn maintainability_index(volume: f64, cyclomatic_complexity: usize, code_lines: usize) -> f64 {
+    if volume <= 0.0 || code_lines == 0 {
+        return 100.0;
+    }
+
+    let raw = 171.0
+        - 5.2 * volume.ln()
+        - 0.23 * cyclomatic_complexity as f64
+        - 16.2 * (code_lines as f64).ln();
+    (raw * 100.0 / 171.0).clamp(0.0, 100.0)
+}
+
+/// Checks whether a node kind is a string literal, whose tokens form one operand.
+fn is_string_kind(kind: &str) -> bool {
+    kind.contains("string") && !kind.contains("content") && !kind.contains("fragment")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::count_source_with;
+
+    fn metrics(language: SupportedLanguage, source: &str) -> ExtendedMetrics {
+        let mut counter = HalsteadCounter::default();
+        let stats = count_source_with(language, source, &mut counter);
+        counter.finish(stats.code_lines)
+    }
+
+    #[test]
+    fn test_halstead_counts() {
+        // Operators: fn ( : ) -> { + }; operands: add a i32 i32 a 1
+        let result = metrics(SupportedLanguage::Rust, "fn add(a: i32) -> i32 { a + 1 }\n");
+        assert_eq!(result.total_operators, 8);
+        assert_eq!(result.distinct_operators, 8);
+        assert_eq!(result.total_operands, 6);
+        assert_eq!(result.distinct_operands, 4);
+        assert!((result.volume - 14.0 * 12f64.log2()).abs() < 1e-9);
+        assert_eq!(result.cyclomatic_complexity, 1);
+        assert!((result.maintainability_index - 87.96).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_strings_and_comments() {
+        // A string is one operand whatever its content; comments are no tokens
+        let source = "# greet\ndef greet():\n    return \"hello, world\"\n";
+        let result = metrics(SupportedLanguage::Python, source);
+        assert_eq!(result.total_operands, 2);
+        assert_eq!(result.total_operators, 5);
+    }
+
+    #[test]
+    fn test_cyclomatic_complexity() {
+        let source = "\
+package m
+
+func sign(x int) int {
+\tif x > 0 && x < 10 {
+\t\treturn 1
+\t}
+\tfor i := 0; i < x; i++ {
+\t}
+\treturn 0
+}
+";
+        let result = metrics(SupportedLanguage::Go, source);
+        assert_eq!(result.cyclomatic_complexity, 4);
+    }
+
+    #[test]
+    fn test_maintainability_index_bounds() {
+        assert_eq!(maintainability_index(0.0, 1, 0), 100.0);
+        assert_eq!(maintainability_index(1e12, 500, 100_000), 0.0);
+        assert!(maintainability_index(1000.0, 10, 100) < maintainability_index(100.0, 1, 10));
+    }
+}
@@ -0,0 +1,604 @@
+//! Aggregation of statistics across many repositories listed in a manifest.
+//!
+//! ```toml
+//! [[repo]]
+//! name = "api"
+//! path = "../api"
+//!
+//! [[repo]]
+//! name = "web"
+//! git = "https://github.com/example/web.git"
+//! rev = "main"
+//! ignore = ["vendor/"]
+//! ```
+//!
+//! Relative paths are resolved against the manifest's directory. Git
+//! repositories are shallow-fetched into the cache directory. The results of
+//! every repository whose checkout is a clean git work tree are cached by
+//! commit, so unchanged repositories are not analyzed again; results cached
+//! by another version of the tool are not reused.
+
+use crate::analyzer::CodeAnalyzer;
+use crate::error::{CodeStatsError, Result};
+use crate::findings::{Finding, Severity};
+use crate::git::run_git;
+use crate::interrupt::Interrupt;
+use crate::language::SupportedLanguage;
+use crate::parser::CodeStats;
+use crate::stats::LanguageStats;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the cache directory created next to the manifest by default.
+pub(crate) const DEFAULT_CACHE_DIR: &str = ".code-stats-cache";
+
+/// Maximum directory depth used when analyzing each repository.
+const MAX_DEPTH: usize = 100;
+
+/// The contents of a manifest file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+    /// The repositories to analyze, in report order
+    #[serde(default)]
+    repo: Vec<RepoEntry>,
+}
+
+/// A single `[[repo]]` table as written in the manifest.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RepoEntry {
+    name: String,
+    path: Option<PathBuf>,
+    git: Option<String>,
+    rev: Option<String>,
+    #[serde(default)]
+    ignore: Vec<String>,
+}
+
+/// Where a repository's sources come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RepoSource {
+    /// A directory on the local file system
+    Local(PathBuf),
+    /// A git URL, fetched at `rev` (the remote's HEAD if unset)
+    Git { url: String, rev: Option<String> },
+}
+
+/// A repository to analyze, validated from the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Repo {
+    /// Unique name, used for report rows and cache entries
+    pub name: String,
+    /// Where the sources come from
+    pub source: RepoSource,
+    /// Patterns to exclude files (substring matching)
+    pub ignore: Vec<String>,
+}
+
+/// Loads and validates a manifest file.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Repo>)` - The repositories in manifest order
+/// * `Err(CodeStatsError::IoError)` if the manifest cannot be read
+/// * `Err(CodeStatsError::ConfigError)` if it is malformed, a repository has
+///   neither or both of `path` and `git`, names are missing or duplicated, or
+///   `git` or `rev` start with `-` (they would be read as options of git)
+pub(crate) fn load_manifest(path: &Path) -> Result<Vec<Repo>> {
+    let text = fs::read_to_string(path).map_err(|e| CodeStatsError::io(path, e))?;
+    let invalid =
+        |message: String| CodeStatsError::ConfigError(format!("{}: {message}", path.display()));
+
+    let manifest: ManifestFile = toml::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut names = HashSet::new();
+
+    manifest
+        .repo
+        .into_iter()
+        .map(|entry| {
+            if !is_valid_name(&entry.name) {
+                return Err(invalid(format!(
+                    "invalid repository name {:?} (use letters, digits, '.', '-' and '_')",
+                    entry.name
+                )));
+            }
+            if !names.insert(entry.name.clone()) {
+                return Err(invalid(format!(
+                    "duplicate repository name {:?}",
+                    entry.name
+                )));
+            }
+
+            let source = match (entry.path, entry.git) {
+                (Some(dir), None) if entry.rev.is_none() => RepoSource::Local(base.join(dir)),
+                (Some(_), None) => {
+                    return Err(invalid(format!("{}: `rev` requires `git`", entry.name)));
+                }
+                (None, Some(url))
+                    if url.starts_with('-')
+                        || entry.rev.as_ref().is_some_and(|rev| rev.starts_with('-')) =>
+                {
+                    return Err(invalid(format!(
+                        "{}: `git` and `rev` must not start with '-'",
+                        entry.name
+                    )));
+                }
+                (None, Some(url)) => RepoSource::Git {
+                    url,
+                    rev: entry.rev,
+                },
+                _ => {
+                    return Err(invalid(format!(
+                        "{}: set exactly one of `path` and `git`",
+                        entry.name
+                    )));
+                }
+            };
+
+            Ok(Repo {
+                name: entry.name,
+                source,
+                ignore: entry.ignore,
+            })
+        })
+        .collect()
+}
+
+/// Returns `true` if a repository name is safe to use as a file name.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '-' | '_'))
+}
+
+/// Totals of one repository in a multi-repository report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RepoReport {
+    /// The repository's name from the manifest
+    pub name: String,
+    /// The local path or git URL (with `@rev` if set) that was analyzed
+    pub source: String,
+    /// The analyzed commit, if the checkout is a clean git work tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Whether the results were reused from the cache
+    #[serde(default)]
+    pub cached: bool,
+    /// Whether the analysis was interrupted before visiting every file
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// Number of files analyzed
+    pub file_count: usize,
+    /// Statistics aggregated by programming language
+    pub total_by_language: BTreeMap<SupportedLanguage, LanguageStats>,
+    /// Totals across all files of the repository
+    pub total_stats: CodeStats,
+}
+
+/// Consolidated report across every repository of a manifest.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct MultiReport {
+    /// One entry per successfully analyzed repository, in manifest order
+    pub repos: Vec<RepoReport>,
+    /// Number of files analyzed across all repositories
+    pub total_files: usize,
+    /// Totals across all repositories
+    pub total_stats: CodeStats,
+    /// Repositories that could not be analyzed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    /// Set when the run was interrupted; later repositories are missing
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+}
+
+/// A cached repository result and the inputs it was computed from.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Version of the tool that computed the result
+    #[serde(default)]
+    version: String,
+    commit: String,
+    ignore: Vec<String>,
+    report: RepoReport,
+}
+
+/// Analyzes every repository and builds the consolidated report.
+///
+/// A repository that cannot be fetched or analyzed is recorded as an error
+/// finding and left out of the totals; the remaining repositories are still
+/// reported.
+///
+/// Once `interrupt` is raised, the repository being analyzed is reported
+/// with the files visited so far (and not cached), and later repositories
+/// are skipped.
+///
+/// # Arguments
+///
+/// * `repos` - Repositories to analyze, from [`load_manifest`]
+/// * `cache_dir` - Directory for git checkouts and cached results
+/// * `reuse_cached` - Whether cached results may be reused
+/// * `interrupt` - Flag that stops the run early
+pub(crate) fn analyze_repos(
+    repos: &[Repo],
+    cache_dir: &Path,
+    reuse_cached: bool,
+    interrupt: &Interrupt,
+) -> MultiReport {
+    let mut report = MultiReport::default();
+
+    for repo in repos {
+        if interrupt.is_raised() {
+            report.interrupted = true;
+            break;
+        }
+
+        match analyze_repo(repo, cache_dir, reuse_cached, interrupt) {
+            Ok(repo_report) => {
+                report.interrupted |= repo_report.partial;
+                report.total_files += repo_report.file_count;
+                report.total_stats.merge(&repo_report.total_stats);
+                report.repos.push(repo_report);
+            }
+            Err(e) => report.findings.push(Finding::new(
+                Severity::Error,
+                None,
+                format!("{}: {e}", repo.name),
+            )),
+        }
+    }
+
+    report
+}
+
+/// Fetches (if needed) and analyzes one repository, going through the cache.
+fn analyze_repo(
+    repo: &Repo,
+    cache_dir: &Path,
+    reuse_cached: bool,
+    interrupt: &Interrupt,
+) -> Result<RepoReport> {
+    let (checkout, source) = match &repo.source {
+        RepoSource::Local(path) => (path.clone(), path.display().to_string()),
+        RepoSource::Git { url, rev } => {
+            let checkout = cache_dir.join("repos").join(&repo.name);
+            fetch(url, rev.as_deref(), &checkout)?;
+            let source = match rev {
+                Some(rev) => format!("{url}@{rev}"),
+                None => url.clone(),
+            };
+            (checkout, source)
+        }
+    };
+
+    let commit = clean_commit(&checkout);
+    let cache_file = cache_dir
+        .join("results")
+        .join(format!("{}.json", repo.name));
+
+    if reuse_cached
+        && let Some(commit) = &commit
+        && let Some(mut cached) = read_cache(&cache_file, commit, &repo.ignore)
+    {
+        cached.source = source;
+        cached.cached = true;
+        return Ok(cached);
+    }
+
+    let stats = CodeAnalyzer::new()
+        .with_interrupt(interrupt.clone())
+        .analyze_directory(&checkout, MAX_DEPTH, false, &repo.ignore)?;
+    let report = RepoReport {
+        name: repo.name.clone(),
+        source,
+        commit: commit.clone(),
+        cached: false,
+        partial: stats.interrupted,
+        file_count: stats.total_files(),
+        total_by_language: stats.total_by_language,
+        total_stats: stats.total_stats,
+    };
+
+    // A partial result must never be reused as if it were complete
+    if let Some(commit) = commit
+        && !report.partial
+    {
+        write_cache(&cache_file, commit, &repo.ignore, &report)?;
+    }
+
+    Ok(report)
+}
+
+/// Shallow-fetches `rev` of a git URL into `checkout` and checks it out.
+///
+/// The checkout directory is reused between runs, so only new commits are
+/// transferred. The URL and revision are passed after `--end-of-options`, so
+/// git never reads them as options.
+fn fetch(url: &str, rev: Option<&str>, checkout: &Path) -> Result<()> {
+    if !checkout.join(".git").is_dir() {
+        fs::create_dir_all(checkout).map_err(|e| CodeStatsError::io(checkout, e))?;
+        run_git(checkout, &["init", "-q"])?;
+    }
+
+    run_git(
+        checkout,
+        &[
+            "fetch",
+            "-q",
+            "--depth",
+            "1",
+            "--end-of-options",
+            url,
+            rev.unwrap_or("HEAD"),
+        ],
+    )?;
+    run_git(
+        checkout,
+        &["checkout", "-q", "--force", "--detach", "FETCH_HEAD"],
+    )?;
+    Ok(())
+}
+
+/// Returns the HEAD commit of `dir` if it is inside a git work tree without
+/// uncommitted changes, i.e. if the commit fully determines its contents.
+fn clean_commit(dir: &Path) -> Option<String> {
+    let commit = run_git(dir, &["rev-parse", "HEAD"]).ok()?;
+    let status = run_git(dir, &["status", "--porcelain", "--", "."]).ok()?;
+    status.is_empty().then_some(commit)
+}
+
+/// Reads a cached result if it was computed by this version of the tool for
+/// the same commit and ignore patterns.
+///
+/// Missing or unreadable cache files are treated as cache misses.
+fn read_cache(cache_file: &Path, commit: &str, ignore: &[String]) -> Option<RepoReport> {
+    let text = fs::read_to_string(cache_file).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&text).ok()?;
+    (entry.version == env!("CARGO_PKG_VERSION") && entry.commit == commit && entry.ignore == ignore)
+        .then_some(entry.report)
+}
+
+/// Stores a repository result in the cache.
+fn write_cache(
+    cache_file: &Path,
+    commit: String,
+    ignore: &[String],
+    report: &RepoReport,
+) -> Result<()> {
+    let write_error = |e| CodeStatsError::io(cache_file, e);
+
+    if let Some(dir) = cache_file.parent() {
+        fs::create_dir_all(dir).map_err(write_error)?;
+    }
+    let entry = CacheEntry {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit,
+        ignore: ignore.to_vec(),
+        report: report.clone(),
+    };
+    let json = serde_json::to_string(&entry).map_err(|e| write_error(e.into()))?;
+    fs::write(cache_file, json).map_err(write_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Creates a git repository with one committed Rust file.
+    fn create_git_repo(dir: &Path, source: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("lib.rs"), source).unwrap();
+        run_git(dir, &["init", "-q"]).unwrap();
+        run_git(dir, &["add", "."]).unwrap();
+        run_git(
+            dir,
+            &[
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "-m",
+                "initial",
+            ],
+        )
+        .unwrap();
+    }
+
+    fn write_manifest(dir: &Path, text: &str) -> PathBuf {
+        let path = dir.join("repos.toml");
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_manifest_resolves_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let manifest = write_manifest(
+            temp_dir.path(),
+            r#"
+[[repo]]
+name = "api"
+path = "services/api"
+ignore = ["generated"]
+
+[[repo]]
+name = "web"
+git = "https://example.com/web.git"
+rev = "v1.2"
+"#,
+        );
+
+        let repos = load_manifest(&manifest).unwrap();
+
+        assert_eq!(repos.len(), 2);
+        assert_eq!(
+            repos[0].source,
+            RepoSource::Local(temp_dir.path().join("services/api"))
+        );
+        assert_eq!(repos[0].ignore, vec!["generated"]);
+        assert_eq!(
+            repos[1].source,
+            RepoSource::Git {
+                url: "https://example.com/web.git".to_string(),
+                rev: Some("v1.2".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_load_manifest_rejects_invalid_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let cases = [
+            "[[repo]]\nname = \"a\"\n",
+            "[[repo]]\nname = \"a\"\npath = \".\"\ngit = \"url\"\n",
+            "[[repo]]\nname = \"a\"\npath = \".\"\nrev = \"main\"\n",
+            "[[repo]]\nname = \"../a\"\npath = \".\"\n",
+            "[[repo]]\nname = \"a\"\npath = \".\"\n[[repo]]\nname = \"a\"\npath = \".\"\n",
+            "[[repo]]\nname = \"a\"\npath = \".\"\nbranch = \"main\"\n",
+            "[[repo]]\nname = \"a\"\ngit = \"--upload-pack=touch pwned\"\n",
+            "[[repo]]\nname = \"a\"\ngit = \"https://example.com/a.git\"\nrev = \"-x\"\n",
+        ];
+
+        for text in cases {
+            let manifest = write_manifest(temp_dir.path(), text);
+            assert!(
+                matches!(
+                    load_manifest(&manifest),
+                    Err(CodeStatsError::ConfigError(_))
+                ),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_analyze_repos_aggregates_and_reports_failures() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("a")).unwrap();
+        fs::write(temp_dir.path().join("a/main.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        fs::create_dir(temp_dir.path().join("b")).unwrap();
+        fs::write(temp_dir.path().join("b/main.py"), "class C:\n    pass\n").unwrap();
+
+        let repos = [
+            ("a", RepoSource::Local(temp_dir.path().join("a"))),
+            ("b", RepoSource::Local(temp_dir.path().join("b"))),
+            (
+                "missing",
+                RepoSource::Local(temp_dir.path().join("missing")),
+            ),
+        ]
+        .map(|(name, source)| Repo {
+            name: name.to_string(),
+            source,
+            ignore: Vec::new(),
+        });
+
+        let report = analyze_repos(
+            &repos,
+            &temp_dir.path().join("cache"),
+            true,
+            &Interrupt::default(),
+        );
+
+        assert_eq!(report.repos.len(), 2);
+        assert_eq!(report.total_files, 2);
+        assert_eq!(report.total_stats.function_count, 2);
+        assert_eq!(report.total_stats.class_struct_count, 1);
+        assert_eq!(report.findings.len(), 1);
+        assert!(report.findings[0].message.starts_with("missing: "));
+    }
+
+    #[test]
+    fn test_git_repos_are_fetched_and_cached_by_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = temp_dir.path().join("upstream");
+        create_git_repo(&upstream, "fn a() {}\n");
+
+        let repos = [Repo {
+            name: "upstream".to_string(),
+            source: RepoSource::Git {
+                url: upstream.display().to_string(),
+                rev: None,
+            },
+            ignore: Vec::new(),
+        }];
+        let cache_dir = temp_dir.path().join("cache");
+
+        let first = analyze_repos(&repos, &cache_dir, true, &Interrupt::default());
+        assert!(first.findings.is_empty(), "{:?}", first.findings);
+        assert!(!first.repos[0].cached);
+        assert!(first.repos[0].commit.is_some());
+        assert_eq!(first.total_stats.function_count, 1);
+        assert!(cache_dir.join("repos/upstream/lib.rs").is_file());
+
+        let second = analyze_repos(&repos, &cache_dir, true, &Interrupt::default());
+        assert!(second.repos[0].cached);
+        assert_eq!(second.total_stats, first.total_stats);
+
+        let uncached = analyze_repos(&repos, &cache_dir, false, &Interrupt::default());
+        assert!(!uncached.repos[0].cached);
+
+        // Results of another version of the tool are not reused
+        let cache_file = cache_dir.join("results/upstream.json");
+        let mut entry: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&cache_file).unwrap()).unwrap();
+        entry["version"] = "0.0.0".into();
+        fs::write(&cache_file, entry.to_string()).unwrap();
+        let outdated = analyze_repos(&repos, &cache_dir, true, &Interrupt::default());
+        assert!(!outdated.repos[0].cached);
+    }
+
+    #[test]
+    fn test_dirty_work_trees_are_not_cached() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        create_git_repo(&repo_dir, "fn a() {}\n");
+        fs::write(repo_dir.join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+
+        let repos = [Repo {
+            name: "repo".to_string(),
+            source: RepoSource::Local(repo_dir),
+            ignore: Vec::new(),
+        }];
+        let cache_dir = temp_dir.path().join("cache");
+
+        let report = analyze_repos(&repos, &cache_dir, true, &Interrupt::default());
+        assert_eq!(report.total_stats.function_count, 2);
+        assert!(report.repos[0].commit.is_none());
+        assert!(!cache_dir.join("results/repo.json").exists());
+    }
+
+    #[test]
+    fn test_interrupted_repos_are_partial_and_not_cached() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        create_git_repo(&repo_dir, "fn a() {}\n");
+
+        let repo = Repo {
+            name: "repo".to_string(),
+            source: RepoSource::Local(repo_dir),
+            ignore: Vec::new(),
+        };
+        let cache_dir = temp_dir.path().join("cache");
+        let interrupt = Interrupt::default();
+        interrupt.raise();
+
+        let repo_report = analyze_repo(&repo, &cache_dir, true, &interrupt).unwrap();
+        assert!(repo_report.partial);
+        assert!(repo_report.commit.is_some());
+        assert!(!cache_dir.join("results/repo.json").exists());
+
+        let report = analyze_repos(&[repo], &cache_dir, true, &interrupt);
+        assert!(report.interrupted);
+        assert!(report.repos.is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+//! Heuristics flagging files that look obfuscated or machine-generated.
+//!
+//! Minified and obfuscated bundles distort the statistics of a code base, so
+//! such files are reported separately and can be left out of the totals.
+
+use crate::language::SupportedLanguage;
+use crate::visitor::NodeVisitor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use tree_sitter::Node;
+
+/// Files with fewer non-whitespace characters are too small for a meaningful entropy.
+const MIN_ENTROPY_CHARS: usize = 1024;
+
+/// Character entropy (bits per character) at or above which content looks encoded.
+///
+/// Hand-written code stays between 4.5 and 5.5 bits; base64 and packed
+/// payloads approach 6 bits.
+const HIGH_ENTROPY_BITS: f64 = 5.8;
+
+/// Files with fewer identifiers are too small to judge their naming.
+const MIN_IDENTIFIERS: usize = 100;
+
+/// Mean identifier length (in characters) below which names look minified.
+const SHORT_IDENTIFIER_LENGTH: f64 = 3.0;
+
+/// A property of a file suggesting it was not written by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Signal {
+    /// The characters are as evenly distributed as in encoded data
+    HighEntropy,
+    /// Identifiers are mostly one or two characters long, as after minification
+    ShortIdentifiers,
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::HighEntropy => "high entropy",
+            Self::ShortIdentifiers => "short identifiers",
+        })
+    }
+}
+
+/// Why a file was flagged as possibly obfuscated, with the measured values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Suspicion {
+    /// The signals that fired; never empty
+    pub signals: Vec<Signal>,
+    /// Shannon entropy of the non-whitespace characters, in bits per character
+    pub entropy: f64,
+    /// Mean length of the identifiers, in characters
+    pub mean_identifier_length: f64,
+}
+
+/// Collects identifier lengths while the statistics are counted.
+///
+/// Only nodes of kind `identifier` are measured: they name variables,
+/// parameters and functions in every supported grammar, and are the names a
+/// minifier shortens. Property and type names usually survive minification.
+#[derive(Debug, Default)]
+pub(crate) struct IdentifierLengths {
+    count: usize,
+    total_chars: usize,
+}
+
+impl NodeVisitor for IdentifierLengths {
+    fn visit(&mut self, _language: SupportedLanguage, node: &Node<'_>, source: &str) {
+        if node.kind() == "identifier" {
+            self.count += 1;
+            self.total_chars += source[node.byte_range()].chars().count();
+        }
+    }
+}
+
+/// Decides whether a file looks obfuscated or machine-generated.
+///
+/// # Arguments
+///
+/// * `source_code` - The complete source text of the file
+/// * `identifiers` - Identifier lengths collected from the file's syntax tree
+///
+/// # Returns
+///
+/// * `Some(Suspicion)` if at least one signal fired
+/// * `None` if the file looks hand-written or is too small to judge
+pub(crate) fn assess(source_code: &str, identifiers: &IdentifierLengths) -> Option<Suspicion> {
+    let (entropy, chars) = character_entropy(source_code);
+    let mean_identifier_length = if identifiers.count == 0 {
+        0.0
+    } else {
+        identifiers.total_chars as f64 / identifiers.count as f64
+    };
+
+    let mut signals = Vec::new();
+    if chars >= MIN_ENTROPY_CHARS && entropy >= HIGH_ENTROPY_BITS {
+        signals.push(Signal::HighEntropy);
+    }
+    if identifiers.count >= MIN_IDENTIFIERS && mean_identifier_length < SHORT_IDENTIFIER_LENGTH {
+        signals.push(Signal::ShortIdentifiers);
+    }
+
+    (!signals.is_empty()).then_some(Suspicion {
+        signals,
+        entropy,
+        mean_identifier_length,
+    })
+}
+
+/// Computes the Shannon entropy of the non-whitespace characters of `text`.
+///
+/// # Returns
+///
+/// The entropy in bits per character and the number of characters measured.
+fn character_entropy(text: &str) -> (f64, usize) {
+    let mut frequencies: HashMap<char, usize> = HashMap::new();
+    let mut total = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        *frequencies.entry(c).or_default() += 1;
+        total += 1;
+    }
+
+    let entropy = frequencies
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum();
+
+    (entropy, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_source_with;
+
+    /// Assesses JavaScript source the way the analyzer does.
+    fn assess_js(source: &str) -> Option<Suspicion> {
+        let mut identifiers = IdentifierLengths::default();
+        count_source_with(SupportedLanguage::JavaScript, source, &mut identifiers);
+        assess(source, &identifiers)
+    }
+
+    #[test]
+    fn test_character_entropy() {
+        let (entropy, chars) = character_entropy("ab ab\nab");
+        assert_eq!(chars, 6);
+        assert!((entropy - 1.0).abs() < 1e-9);
+
+        assert_eq!(character_entropy("   ").1, 0);
+    }
+
+    #[test]
+    fn test_hand_written_code_is_not_flagged() {
+        let source = "function renderGreeting(userName, greetingTemplate) {\n    \
+                      const message = greetingTemplate.replace('{name}', userName);\n    \
+                      return message;\n}\n"
+            .repeat(30);
+
+        assert_eq!(assess_js(&source), None);
+    }
+
+    #[test]
+    fn test_minified_code_is_flagged() {
+        let source = "function a(b,c){var d=b+c;return e(d,b)}".repeat(30);
+
+        let suspicion = assess_js(&source).unwrap();
+        assert_eq!(suspicion.signals, vec![Signal::ShortIdentifiers]);
+        assert!(suspicion.mean_identifier_length < 1.5);
+    }
+
+    #[test]
+    fn test_encoded_payload_is_flagged() {
+        // Every printable ASCII character occurs equally often
+        let payload: String = (0..2000)
+            .map(|i| char::from(b'!' + (i % 94) as u8))
+            .collect();
+        let source = format!("const data = {:?};\n", payload.replace(['"', '\\'], ""));
+
+        let suspicion = assess_js(&source).unwrap();
+        assert_eq!(suspicion.signals, vec![Signal::HighEntropy]);
+        assert!(suspicion.entropy >= HIGH_ENTROPY_BITS);
+    }
+
+    #[test]
+    fn test_small_files_are_not_judged() {
+        assert_eq!(assess_js("var a=b(c);"), None);
+    }
+}
@@ -0,0 +1,119 @@
+//! Classification of vendored and generated source files.
+//!
+//! Third-party code checked into a repository and the output of code
+//! generators were not written by the project, so they are reported apart
+//! from the project's own code. Vendored files are recognized by the
+//! directory they live in, generated files by the marker generators put at
+//! the top of their output.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Directories holding copies of third-party code.
+const VENDOR_DIRECTORIES: [&str; 3] = ["vendor", "third_party", "node_modules"];
+
+/// Markers that code generators write into the header of their output,
+/// e.g. Go's `// Code generated by stringer; DO NOT EDIT.` or `@generated`.
+const GENERATED_MARKERS: [&str; 2] = ["@generated", "DO NOT EDIT"];
+
+/// Number of leading lines searched for a generated marker.
+const HEADER_LINES: usize = 10;
+
+/// Where a file's code comes from, when it is not the project's own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Origin {
+    /// Third-party code copied into the repository
+    Vendored,
+    /// Output of a code generator
+    Generated,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Vendored => "vendored",
+            Self::Generated => "generated",
+        })
+    }
+}
+
+/// Decides whether a file is vendored or generated.
+///
+/// # Arguments
+///
+/// * `path` - The file's path relative to the analyzed directory, so the
+///   directory itself may be named e.g. `vendor`
+/// * `source_code` - The file's content
+///
+/// # Returns
+///
+/// * `Some(Origin::Vendored)` if a directory on the path is a vendor
+///   directory, even for generated files
+/// * `Some(Origin::Generated)` if one of the first lines has a generated marker
+/// * `None` for the project's own code
+pub(crate) fn classify(path: &Path, source_code: &str) -> Option<Origin> {
+    let mut directories = path.parent().into_iter().flat_map(Path::iter);
+    if directories.any(|name| VENDOR_DIRECTORIES.iter().any(|vendor| name == *vendor)) {
+        return Some(Origin::Vendored);
+    }
+
+    source_code
+        .lines()
+        .take(HEADER_LINES)
+        .any(|line| GENERATED_MARKERS.iter().any(|marker| line.contains(marker)))
+        .then_some(Origin::Generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_vendored_paths() {
+        for path in [
+            "vendor/github.com/pkg/errors/errors.go",
+            "web/node_modules/react/index.js",
+            "third_party/zlib/zlib.rs",
+        ] {
+            assert_eq!(
+                classify(Path::new(path), "fn f() {}\n"),
+                Some(Origin::Vendored),
+                "{path}"
+            );
+        }
+
+        // Only directory names count, not file names or parts of names
+        assert_eq!(classify(Path::new("src/vendor.rs"), ""), None);
+        assert_eq!(classify(Path::new("vendors/lib.rs"), ""), None);
+    }
+
+    #[test]
+    fn test_classify_generated_markers() {
+        let go = "// Code generated by stringer; DO NOT EDIT.\n\npackage main\n";
+        assert_eq!(
+            classify(Path::new("color_string.go"), go),
+            Some(Origin::Generated)
+        );
+
+        let python = "# @generated by protoc\nclass Message:\n    pass\n";
+        assert_eq!(
+            classify(Path::new("message.py"), python),
+            Some(Origin::Generated)
+        );
+
+        // Vendoring takes precedence
+        assert_eq!(
+            classify(Path::new("vendor/x.go"), go),
+            Some(Origin::Vendored)
+        );
+
+        // Markers further down are mentions, not headers
+        let late = format!(
+            "{}// DO NOT EDIT below\n",
+            "fn f() {}\n".repeat(HEADER_LINES)
+        );
+        assert_eq!(classify(Path::new("lib.rs"), &late), None);
+    }
+}
@@ -0,0 +1,165 @@
+//! How concentrated the authorship of each directory is.
+//!
+//! Every function is attributed to the author of its declaration line, as
+//! `git blame` reports it (see [`blame_authors`]), and counted for its
+//! file's directory and every directory above it. A directory's bus factor
+//! is the smallest number of authors who together own more than half of its
+//! functions: with a bus factor of 1, a single person owns most of the code
+//! and the area is at risk if they leave.
+//!
+//! [`blame_authors`]: crate::git::blame_authors
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Authorship of the functions below a directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct DirectoryOwnership {
+    /// The directory, relative to the analyzed root (`.` for the root)
+    pub path: PathBuf,
+    /// Functions declared below the directory
+    pub functions: usize,
+    /// Number of different authors of those functions
+    pub authors: usize,
+    /// The author owning the most functions; ties go to the first name
+    pub top_author: String,
+    /// Percentage of the functions owned by the top author
+    pub top_share: f64,
+    /// Smallest number of authors owning more than half of the functions
+    pub bus_factor: usize,
+}
+
+impl DirectoryOwnership {
+    /// Returns whether a single author owns most of the directory.
+    pub(crate) fn at_risk(&self) -> bool {
+        self.bus_factor == 1
+    }
+}
+
+/// Rolls the authors of functions up into every directory above them.
+///
+/// # Arguments
+///
+/// * `functions` - The relative path of the file declaring each function,
+///   with the function's author
+///
+/// # Returns
+///
+/// The ownership of every directory holding at least one of the functions,
+/// sorted by path
+pub(crate) fn directory_ownership<'a>(
+    functions: impl IntoIterator<Item = (&'a Path, &'a str)>,
+) -> Vec<DirectoryOwnership> {
+    let mut directories: BTreeMap<PathBuf, HashMap<&str, usize>> = BTreeMap::new();
+    for (file, author) in functions {
+        for dir in file.ancestors().skip(1) {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            *directories
+                .entry(dir.to_path_buf())
+                .or_default()
+                .entry(author)
+                .or_default() += 1;
+        }
+    }
+
+    directories
+        .into_iter()
+        .map(|(path, authors)| {
+            let mut counts: Vec<(&str, usize)> = authors.into_iter().collect();
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            let functions: usize = counts.iter().map(|(_, count)| count).sum();
+
+            let mut owned = 0;
+            let bus_factor = counts
+                .iter()
+                .take_while(|(_, count)| {
+                    let more_than_half = owned * 2 > functions;
+                    owned += count;
+                    !more_than_half
+                })
+                .count();
+
+            DirectoryOwnership {
+                path,
+                functions,
+                authors: counts.len(),
+                top_author: counts[0].0.to_string(),
+                top_share: counts[0].1 as f64 * 100.0 / functions as f64,
+                bus_factor,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_ownership() {
+        let functions = [
+            ("main.rs", "Ada"),
+            ("src/lib.rs", "Grace"),
+            ("src/lib.rs", "Ada"),
+            ("src/legacy/old.rs", "Ada"),
+            ("src/legacy/old.rs", "Ada"),
+            ("src/legacy/util.rs", "Ada"),
+            ("src/legacy/util.rs", "Linus"),
+        ];
+        let ownership = directory_ownership(
+            functions
+                .iter()
+                .map(|&(file, author)| (Path::new(file), author)),
+        );
+
+        let summary: Vec<(&str, usize, usize, &str, usize)> = ownership
+            .iter()
+            .map(|dir| {
+                (
+                    dir.path.to_str().unwrap(),
+                    dir.functions,
+                    dir.authors,
+                    dir.top_author.as_str(),
+                    dir.bus_factor,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (".", 7, 3, "Ada", 1),
+                ("src", 6, 3, "Ada", 1),
+                ("src/legacy", 4, 2, "Ada", 1),
+            ]
+        );
+        assert_eq!(ownership[2].top_share, 75.0);
+        assert!(ownership[2].at_risk());
+    }
+
+    #[test]
+    fn test_bus_factor_counts_authors_owning_more_than_half() {
+        let functions = [
+            ("a.rs", "Ada"),
+            ("a.rs", "Ada"),
+            ("a.rs", "Grace"),
+            ("a.rs", "Grace"),
+            ("a.rs", "Linus"),
+        ];
+        let ownership = directory_ownership(
+            functions
+                .iter()
+                .map(|&(file, author)| (Path::new(file), author)),
+        );
+
+        assert_eq!(ownership.len(), 1);
+        assert_eq!(ownership[0].top_author, "Ada");
+        assert_eq!(ownership[0].top_share, 40.0);
+        assert_eq!(ownership[0].bus_factor, 2);
+        assert!(!ownership[0].at_risk());
+    }
+}
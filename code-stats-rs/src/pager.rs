@@ -0,0 +1,86 @@
+//! Display of long reports through an external pager on interactive terminals.
+
+use std::env;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Pager used when `$PAGER` is not set.
+///
+/// `-F` exits immediately when the output fits on one screen, so short
+/// reports behave as if no pager was involved.
+const DEFAULT_PAGER: &str = "less -FRX";
+
+/// Writes a report to stdout, through the user's pager when stdout is a terminal.
+///
+/// The report is written directly when stdout is redirected, when paging is
+/// disabled, or when the pager cannot be started, so scripts always receive
+/// the plain report. Output is buffered and streamed rather than collected
+/// first, and quitting the pager early is not an error.
+///
+/// # Arguments
+///
+/// * `use_pager` - Whether paging is allowed at all (`--no-pager` clears it)
+/// * `write` - Writes the report into the given writer
+///
+/// # Returns
+///
+/// * `Ok(())` if the report was written, or the reader stopped early
+/// * `Err(io::Error)` if writing to stdout failed
+pub(crate) fn write_paged<F>(use_pager: bool, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    if use_pager && io::stdout().is_terminal() {
+        let pager = env::var("PAGER").ok();
+        if let Some((program, args)) = pager_command(pager.as_deref())
+            && let Ok(mut child) = Command::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .spawn()
+        {
+            if let Some(stdin) = child.stdin.take() {
+                let mut writer = BufWriter::new(stdin);
+                // The user may quit the pager before reading everything
+                let _ = write(&mut writer).and_then(|()| writer.flush());
+            }
+            child.wait()?;
+            return Ok(());
+        }
+    }
+
+    let mut writer = BufWriter::new(io::stdout().lock());
+    ignore_broken_pipe(write(&mut writer).and_then(|()| writer.flush()))
+}
+
+/// Treats a closed stdout (e.g. piping into `head`) as a normal end of output.
+fn ignore_broken_pipe(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => other,
+    }
+}
+
+/// Splits a `$PAGER` value into a program and its arguments.
+///
+/// # Returns
+///
+/// * `Some((program, args))` for the pager to run
+/// * `None` if paging is disabled by an empty `$PAGER` or `PAGER=cat`
+fn pager_command(pager: Option<&str>) -> Option<(&str, Vec<&str>)> {
+    let mut parts = pager.unwrap_or(DEFAULT_PAGER).split_whitespace();
+    let program = parts.next().filter(|program| *program != "cat")?;
+    Some((program, parts.collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command() {
+        assert_eq!(pager_command(None), Some(("less", vec!["-FRX"])));
+        assert_eq!(pager_command(Some("more -d")), Some(("more", vec!["-d"])));
+        assert_eq!(pager_command(Some("")), None);
+        assert_eq!(pager_command(Some("cat")), None);
+    }
+}
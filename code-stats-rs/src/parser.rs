@@ -1,20 +1,128 @@
 //! Tree-sitter based code parser for extracting function and class statistics.
 
+use crate::components::is_component;
+use crate::config::CountPolicy;
+use crate::documentation::is_documented;
 use crate::error::{CodeStatsError, Result};
-use crate::language::SupportedLanguage;
-use tree_sitter::{Node, Parser};
+use crate::functions::{MANY_PARAMETERS, function_shape};
+use crate::interrupt::Interrupt;
+use crate::language::{Grammar, SupportedLanguage};
+use crate::lines::count_lines;
+use crate::queries::{Declaration, DeclarationQuery};
+use crate::visibility::{Visibility, visibility};
+use crate::visitor::NodeVisitor;
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+use std::path::Path;
+use tree_sitter::{Node, ParseOptions, ParseState, Parser, Tree};
 
 /// Statistics about code structure.
 ///
 /// Holds counts of functions and class/struct definitions found in source code.
-#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub(crate) struct CodeStats {
+/// Counts missing from serialized statistics (e.g. reports written by older
+/// versions) deserialize as zero.
+#[derive(Default, Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct CodeStats {
     /// Number of function declarations found in the source code.
     /// Includes regular functions, methods, constructors, and arrow functions.
     pub function_count: usize,
-    /// Number of class or struct declarations found in the source code.
-    /// Includes classes, structs, enums, and interfaces depending on the language.
+    /// Number of functions preceded by a doc comment (`///`, `/** */`, or
+    /// any comment in Go) or, in Python, starting with a docstring.
+    pub documented_function_count: usize,
+    /// Number of functions and methods that are part of the API: `pub` Rust
+    /// items, `public` Java members and interface methods, and exported
+    /// TypeScript/JavaScript declarations with the public methods of exported
+    /// classes. Languages without visibility count neither public nor private.
+    pub public_function_count: usize,
+    /// Number of functions and methods of languages with visibility that are
+    /// not part of the API. Anonymous callbacks are neither public nor private.
+    pub private_function_count: usize,
+    /// Number of public classes/structs, interfaces/traits, enums and type aliases.
+    pub public_type_count: usize,
+    /// Number of classes/structs, interfaces/traits, enums and type aliases of
+    /// languages with visibility that are not part of the API.
+    pub private_type_count: usize,
+    /// Number of class or struct declarations found in the source code,
+    /// including Java records. Enums, interfaces and type aliases are counted separately.
     pub class_struct_count: usize,
+    /// Number of interface declarations found in the source code.
+    /// Rust traits, Go interface types, TypeScript and Java interfaces, Java
+    /// annotation types, and Dart mixins.
+    pub interface_count: usize,
+    /// Number of enum declarations found in the source code.
+    /// Rust, TypeScript, Java and Dart enums.
+    pub enum_count: usize,
+    /// Number of type alias declarations found in the source code.
+    /// Rust `type` items, Python `type` statements, TypeScript type aliases,
+    /// Dart typedefs, and Go aliases and defined types other than structs and interfaces.
+    pub type_alias_count: usize,
+    /// Number of module or namespace declarations found in the source code.
+    /// Rust `mod` items, TypeScript namespaces and modules, Go and Java package
+    /// declarations, Dart `library` directives, and Python packages (`__init__.py` files, counted by the
+    /// analyzer since they depend on the file name).
+    pub module_count: usize,
+    /// Number of Rust impl blocks without a trait, e.g. `impl Parser { .. }`.
+    pub impl_count: usize,
+    /// Number of Rust impl blocks implementing a trait, e.g. `impl Display for Parser { .. }`.
+    pub trait_impl_count: usize,
+    /// Number of functions declared directly in Rust impl blocks, inherent
+    /// or trait. Also included in `function_count`.
+    pub impl_method_count: usize,
+    /// Number of Go functions and types declaring type parameters, e.g.
+    /// `func Map[T, U any](..)`. They are also counted as functions or types.
+    pub generic_count: usize,
+    /// Number of type parameters of those generic declarations, 2 for `[T, U any]`.
+    pub type_parameter_count: usize,
+    /// Number of Python `async def` functions, also counted in `function_count`.
+    pub async_function_count: usize,
+    /// Number of Python functions with decorators, e.g. `@property` or
+    /// `@staticmethod`, also counted in `function_count`.
+    pub decorated_function_count: usize,
+    /// Number of Python and Java lambdas, which are not counted as functions.
+    pub lambda_count: usize,
+    /// Number of React components in JavaScript and TypeScript: capitalized
+    /// functions returning JSX and classes extending `React.Component`. They
+    /// are also counted as functions or classes.
+    pub component_count: usize,
+    /// Number of string literals outside constant and enum declarations.
+    pub magic_string_count: usize,
+    /// Number of numeric literals outside constant and enum declarations.
+    /// The ubiquitous values `0` and `1` are not counted.
+    pub magic_number_count: usize,
+    /// Number of user-facing string literals not wrapped in a translation call.
+    pub untranslated_string_count: usize,
+    /// Combined length in lines of all functions, each limited to the
+    /// configured maximum. Nested functions also count towards the length of
+    /// the function enclosing them.
+    pub function_lines: usize,
+    /// Number of functions longer than the configured maximum.
+    pub oversized_function_count: usize,
+    /// Combined number of parameters of all functions.
+    pub parameter_count: usize,
+    /// Number of functions with more than five parameters.
+    pub many_parameter_function_count: usize,
+    /// Deepest nesting of blocks inside any one function, e.g. 2 for a loop
+    /// inside an `if` statement. Merged statistics keep the maximum.
+    pub max_nesting_depth: usize,
+    /// Total number of lines (code + comment + blank).
+    pub total_lines: usize,
+    /// Number of lines containing code, including lines with trailing comments.
+    pub code_lines: usize,
+    /// Number of lines containing only comments.
+    pub comment_lines: usize,
+    /// Number of lines containing only whitespace.
+    pub blank_lines: usize,
+    /// Size of the source in bytes.
+    pub byte_count: usize,
+    /// Number of `ERROR` and `MISSING` nodes tree-sitter inserted while
+    /// recovering from syntax it could not parse. Other counts of a file with
+    /// parse errors may be incomplete.
+    pub parse_errors: usize,
+    /// Number of captures of each user-supplied query (`--query`), by capture
+    /// name. Empty unless such queries apply to the file's language.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub query_counts: BTreeMap<String, usize>,
 }
 
 impl CodeStats {
@@ -22,6 +130,94 @@ impl CodeStats {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Adds another set of statistics to this one.
+    pub fn merge(&mut self, other: &CodeStats) {
+        self.function_count += other.function_count;
+        self.documented_function_count += other.documented_function_count;
+        self.public_function_count += other.public_function_count;
+        self.private_function_count += other.private_function_count;
+        self.public_type_count += other.public_type_count;
+        self.private_type_count += other.private_type_count;
+        self.class_struct_count += other.class_struct_count;
+        self.interface_count += other.interface_count;
+        self.enum_count += other.enum_count;
+        self.type_alias_count += other.type_alias_count;
+        self.module_count += other.module_count;
+        self.impl_count += other.impl_count;
+        self.trait_impl_count += other.trait_impl_count;
+        self.impl_method_count += other.impl_method_count;
+        self.generic_count += other.generic_count;
+        self.type_parameter_count += other.type_parameter_count;
+        self.async_function_count += other.async_function_count;
+        self.decorated_function_count += other.decorated_function_count;
+        self.lambda_count += other.lambda_count;
+        self.component_count += other.component_count;
+        self.magic_string_count += other.magic_string_count;
+        self.magic_number_count += other.magic_number_count;
+        self.untranslated_string_count += other.untranslated_string_count;
+        self.function_lines += other.function_lines;
+        self.oversized_function_count += other.oversized_function_count;
+        self.parameter_count += other.parameter_count;
+        self.many_parameter_function_count += other.many_parameter_function_count;
+        self.max_nesting_depth = self.max_nesting_depth.max(other.max_nesting_depth);
+        self.total_lines += other.total_lines;
+        self.code_lines += other.code_lines;
+        self.comment_lines += other.comment_lines;
+        self.blank_lines += other.blank_lines;
+        self.byte_count += other.byte_count;
+        self.parse_errors += other.parse_errors;
+        for (name, count) in &other.query_counts {
+            *self.query_counts.entry(name.clone()).or_insert(0) += count;
+        }
+    }
+
+    /// Returns the number of type definitions: interfaces/traits, enums and
+    /// type aliases, which declare types without classes or structs.
+    pub fn type_definition_count(&self) -> usize {
+        self.interface_count + self.enum_count + self.type_alias_count
+    }
+
+    /// Returns the combined number of magic strings and magic numbers.
+    pub fn magic_value_count(&self) -> usize {
+        self.magic_string_count + self.magic_number_count
+    }
+
+    /// Returns the mean function length in lines, or `None` without functions.
+    ///
+    /// Oversized functions contribute their capped length.
+    pub fn average_function_lines(&self) -> Option<f64> {
+        (self.function_count > 0).then(|| self.function_lines as f64 / self.function_count as f64)
+    }
+
+    /// Returns the mean number of parameters per function, or `None` without functions.
+    pub fn average_parameters(&self) -> Option<f64> {
+        (self.function_count > 0).then(|| self.parameter_count as f64 / self.function_count as f64)
+    }
+
+    /// Returns the number of parse errors per line of source.
+    ///
+    /// One error in a long file is harmless, but a ratio of 0.05 or more
+    /// (one error every 20 lines), at which reports mark a file's counts as
+    /// unreliable, means much of the file was not understood by the grammar.
+    pub fn parse_error_ratio(&self) -> f64 {
+        self.parse_errors as f64 / self.total_lines.max(1) as f64
+    }
+}
+
+/// Parse errors per line at or above which a file's counts are reported as
+/// unreliable: on average one syntax error every 20 lines.
+pub(crate) const UNRELIABLE_PARSE_ERROR_RATIO: f64 = 0.05;
+
+/// A function longer than the configured maximum, listed so the cap is visible.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct OversizedFunction {
+    /// 1-based line where the function starts
+    pub line: usize,
+    /// Actual length of the function in lines
+    pub lines: usize,
+    /// Length the function contributes to the statistics
+    pub counted_lines: usize,
 }
 
 /// Creates a new tree-sitter parser configured for the specified language.
@@ -34,13 +230,109 @@ impl CodeStats {
 ///
 /// A configured `Parser` instance or an error if language setup fails.
 pub(crate) fn create_parser(language: &SupportedLanguage) -> Result<Parser> {
+    create_grammar_parser(Grammar::Primary(*language))
+}
+
+/// Creates a new tree-sitter parser configured for a specific grammar.
+///
+/// Unlike [`create_parser`], this can select a language's secondary grammar,
+/// such as TSX for `.tsx` files.
+///
+/// # Arguments
+///
+/// * `grammar` - The grammar to configure the parser for
+///
+/// # Returns
+///
+/// A configured `Parser` instance or an error if language setup fails.
+pub(crate) fn create_grammar_parser(grammar: Grammar) -> Result<Parser> {
     let mut parser = Parser::new();
     parser
-        .set_language(&language.get_language())
+        .set_language(&grammar.get_language())
         .map_err(|_| CodeStatsError::LanguageSetupError)?;
     Ok(parser)
 }
 
+/// Returns whether a file declares a Python package by its name alone.
+///
+/// A package is declared by its `__init__.py` file rather than in code, so
+/// the module is counted by whoever knows the file's path.
+pub(crate) fn declares_python_package(language: SupportedLanguage, path: &Path) -> bool {
+    language == SupportedLanguage::Python && path.file_name() == Some("__init__.py".as_ref())
+}
+
+/// Counts the statistics of a piece of source code.
+///
+/// This is the library entry point for callers that already have source text
+/// in memory. It has no side effects and every optional counter is collected,
+/// so the result depends only on the arguments.
+///
+/// # Arguments
+///
+/// * `language` - The programming language of the source code
+/// * `source_code` - The source code to analyze
+///
+/// # Returns
+///
+/// A `CodeStats` instance containing the counts.
+///
+/// # Example
+///
+/// ```
+/// use code_stats_rs::{SupportedLanguage, count_source};
+///
+/// let stats = count_source(SupportedLanguage::Rust, "struct A;\nfn main() {}\n");
+/// assert_eq!(stats.function_count, 1);
+/// assert_eq!(stats.class_struct_count, 1);
+/// ```
+pub fn count_source(language: SupportedLanguage, source_code: &str) -> CodeStats {
+    count_parsed(language, source_code, None)
+}
+
+/// Counts the statistics of a piece of source code, calling a visitor for every node.
+///
+/// Behaves like [`count_source`], and additionally passes each node of the
+/// syntax tree to `visitor` during the same traversal that produces the
+/// built-in counts, so custom metrics do not need a second parse.
+///
+/// # Arguments
+///
+/// * `language` - The programming language of the source code
+/// * `source_code` - The source code to analyze
+/// * `visitor` - Callback invoked for every node (see [`NodeVisitor`])
+///
+/// # Returns
+///
+/// A `CodeStats` instance containing the built-in counts.
+pub fn count_source_with(
+    language: SupportedLanguage,
+    source_code: &str,
+    visitor: &mut dyn NodeVisitor,
+) -> CodeStats {
+    count_parsed(language, source_code, Some(visitor))
+}
+
+/// Parses source code with a fresh parser and collects its statistics with
+/// the default counting policy, for [`count_source`] and [`count_source_with`].
+fn count_parsed(
+    language: SupportedLanguage,
+    source_code: &str,
+    visitor: Option<&mut dyn NodeVisitor>,
+) -> CodeStats {
+    let mut parser =
+        create_parser(&language).expect("bundled grammars are compatible with tree-sitter");
+    // Parsing only fails when cancelled or without a language, neither of which applies here
+    let tree = parse_source(&mut parser, source_code, "<source>")
+        .expect("parsing with a configured language always produces a tree");
+    collect_stats(
+        &tree,
+        source_code,
+        &language,
+        &CountPolicy::default(),
+        visitor,
+    )
+}
+
 /// Analyzes source code to extract code statistics.
 ///
 /// Parses the provided source code using tree-sitter and counts
@@ -56,85 +348,463 @@ pub(crate) fn create_parser(language: &SupportedLanguage) -> Result<Parser> {
 /// # Returns
 ///
 /// A `CodeStats` instance containing the counts or an error if parsing fails.
+///
+/// The analyzer calls [`parse_source`] and [`collect_stats`] separately so the
+/// tree can be shared with other passes; this wrapper serves the parser tests.
+#[cfg(test)]
 pub(crate) fn analyze_code(
     parser: &mut Parser,
     source_code: &str,
     file_path: &str,
     language: &SupportedLanguage,
 ) -> Result<CodeStats> {
-    let tree = parser
+    let tree = parse_source(parser, source_code, file_path)?;
+    Ok(collect_stats(
+        &tree,
+        source_code,
+        language,
+        &CountPolicy::default(),
+        None,
+    ))
+}
+
+/// Parses source code into a tree-sitter syntax tree.
+///
+/// # Arguments
+///
+/// * `parser` - A mutable reference to the tree-sitter parser
+/// * `source_code` - The source code to parse
+/// * `file_path` - The path to the file being parsed (used for error reporting)
+///
+/// # Returns
+///
+/// The parsed `Tree` or an error if parsing fails.
+pub(crate) fn parse_source(
+    parser: &mut Parser,
+    source_code: &str,
+    file_path: impl AsRef<Path>,
+) -> Result<Tree> {
+    parser
         .parse(source_code, None)
-        .ok_or_else(|| CodeStatsError::ParseError(file_path.to_string()))?;
+        .ok_or_else(|| CodeStatsError::ParseError(file_path.as_ref().display().to_string()))
+}
+
+/// Parses source code like [`parse_source`], giving up as soon as `interrupt`
+/// is raised.
+///
+/// Tree-sitter checks the interrupt periodically while parsing, so even a
+/// single huge file stops promptly. The parser is reset afterwards and can be
+/// used for the next file.
+///
+/// # Returns
+///
+/// * `Ok(Tree)` - The parsed tree
+/// * `Err(CodeStatsError::Interrupted)` if the interrupt was raised first
+/// * `Err(CodeStatsError::ParseError)` if parsing fails otherwise
+pub(crate) fn parse_source_interruptible(
+    parser: &mut Parser,
+    source_code: &str,
+    file_path: impl AsRef<Path>,
+    interrupt: &Interrupt,
+) -> Result<Tree> {
+    let bytes = source_code.as_bytes();
+    let mut progress = |_: &ParseState| {
+        if interrupt.is_raised() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    };
+    let options = ParseOptions::new().progress_callback(&mut progress);
+    let tree = parser.parse_with_options(
+        &mut |offset, _| bytes.get(offset..).unwrap_or_default(),
+        None,
+        Some(options),
+    );
 
+    tree.ok_or_else(|| {
+        let path = file_path.as_ref().display().to_string();
+        if interrupt.is_raised() {
+            parser.reset();
+            CodeStatsError::Interrupted(path)
+        } else {
+            CodeStatsError::ParseError(path)
+        }
+    })
+}
+
+/// Counts declarations and literals in an already parsed tree.
+///
+/// # Arguments
+///
+/// * `tree` - The syntax tree produced by [`parse_source`]
+/// * `source_code` - The source code the tree was parsed from
+/// * `language` - The programming language of the parsed source
+/// * `policy` - Which optional counters to collect; disabled ones stay at zero
+/// * `visitor` - Optional callback invoked for every node during the traversal
+///
+/// # Returns
+///
+/// A `CodeStats` instance containing the counts.
+pub(crate) fn collect_stats(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    policy: &CountPolicy,
+    mut visitor: Option<&mut dyn NodeVisitor>,
+) -> CodeStats {
     let root_node = tree.root_node();
     let mut stats = CodeStats::new();
 
-    count_nodes(&root_node, &mut stats, language);
+    count_declarations(tree, source_code, language, policy, &mut stats);
+    visit_nodes(&root_node, &mut stats, language, source_code, &mut visitor);
 
-    Ok(stats)
+    let lines = count_lines(tree, source_code, language);
+    stats.total_lines = lines.total();
+    stats.code_lines = lines.code;
+    stats.comment_lines = lines.comment;
+    stats.blank_lines = lines.blank;
+    stats.byte_count = source_code.len();
+
+    if policy.magic_values {
+        count_magic_values(
+            &root_node,
+            source_code.as_bytes(),
+            &mut stats,
+            language,
+            false,
+        );
+    }
+    if policy.untranslated_strings {
+        stats.untranslated_string_count =
+            crate::i18n::count_untranslated_strings(tree, source_code, language);
+    }
+
+    stats
+}
+
+/// Counts the declarations matched by the built-in query of the tree's grammar.
+///
+/// Function lengths are limited to the policy's maximum, counting functions
+/// that exceed it as oversized, and their parameters and block nesting are
+/// measured. Functions and types are also counted as
+/// public or private where the language has visibility. Anonymous functions
+/// count as functions as far as the policy says.
+fn count_declarations(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    policy: &CountPolicy,
+    stats: &mut CodeStats,
+) {
+    let query = DeclarationQuery::for_grammar(Grammar::of_tree(*language, tree));
+    query.for_each_counted(tree, source_code, policy, |declaration, node| {
+        match declaration {
+            // Counted anonymous functions are visited as functions
+            Declaration::Function | Declaration::AnonymousFunction => {
+                let lines = line_span(&node);
+                let counted_lines = capped_lines(lines, policy);
+                stats.function_count += 1;
+                stats.function_lines += counted_lines;
+                if counted_lines < lines {
+                    stats.oversized_function_count += 1;
+                }
+                if is_documented(&node, source_code, language) {
+                    stats.documented_function_count += 1;
+                }
+                let shape = function_shape(&node, language);
+                stats.parameter_count += shape.parameters;
+                if shape.parameters > MANY_PARAMETERS {
+                    stats.many_parameter_function_count += 1;
+                }
+                stats.max_nesting_depth = stats.max_nesting_depth.max(shape.nesting_depth);
+                if is_impl_method(&node) {
+                    stats.impl_method_count += 1;
+                }
+            }
+            Declaration::Class => stats.class_struct_count += 1,
+            Declaration::Interface => stats.interface_count += 1,
+            Declaration::Enum => stats.enum_count += 1,
+            Declaration::TypeAlias => stats.type_alias_count += 1,
+            Declaration::Module => {
+                stats.module_count += 1;
+                return;
+            }
+            Declaration::Impl => {
+                stats.impl_count += 1;
+                return;
+            }
+            Declaration::TraitImpl => {
+                stats.trait_impl_count += 1;
+                return;
+            }
+            Declaration::Generic => {
+                stats.generic_count += 1;
+                stats.type_parameter_count += type_parameter_count(&node);
+                return;
+            }
+            Declaration::AsyncFunction => {
+                stats.async_function_count += 1;
+                return;
+            }
+            Declaration::DecoratedFunction => {
+                stats.decorated_function_count += 1;
+                return;
+            }
+            Declaration::Lambda => {
+                stats.lambda_count += 1;
+                return;
+            }
+        }
+
+        if is_component(&node, source_code, language) {
+            stats.component_count += 1;
+        }
+
+        let is_function = declaration == Declaration::Function;
+        match visibility(&node, source_code, language) {
+            Some(Visibility::Public) if is_function => stats.public_function_count += 1,
+            Some(Visibility::Private) if is_function => stats.private_function_count += 1,
+            Some(Visibility::Public) => stats.public_type_count += 1,
+            Some(Visibility::Private) => stats.private_type_count += 1,
+            None => {}
+        }
+    });
+}
+
+/// Returns whether a function is declared directly in a Rust impl block.
+fn is_impl_method(node: &Node) -> bool {
+    node.parent()
+        .filter(|body| body.kind() == "declaration_list")
+        .and_then(|body| body.parent())
+        .is_some_and(|parent| parent.kind() == "impl_item")
+}
+
+/// Returns the number of type parameters a generic Go declaration declares.
+///
+/// Parameters sharing a constraint, as in `[K, V comparable]`, are counted
+/// one by one.
+fn type_parameter_count(node: &Node) -> usize {
+    let Some(parameters) = node.child_by_field_name("type_parameters") else {
+        return 0;
+    };
+    let mut cursor = parameters.walk();
+    parameters
+        .named_children(&mut cursor)
+        .filter(|declaration| declaration.kind() == "type_parameter_declaration")
+        .map(|declaration| {
+            let mut cursor = declaration.walk();
+            declaration
+                .children_by_field_name("name", &mut cursor)
+                .count()
+        })
+        .sum()
+}
+
+/// Recursively passes every node to the visitor, if any, and counts parse errors.
+///
+/// Without a visitor, only subtrees containing errors are traversed.
+fn visit_nodes(
+    node: &Node,
+    stats: &mut CodeStats,
+    language: &SupportedLanguage,
+    source_code: &str,
+    visitor: &mut Option<&mut dyn NodeVisitor>,
+) {
+    if let Some(visitor) = visitor {
+        visitor.visit(*language, node, source_code);
+    } else if !node.has_error() {
+        return;
+    }
+
+    if node.is_error() || node.is_missing() {
+        stats.parse_errors += 1;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        visit_nodes(&child, stats, language, source_code, visitor);
+    }
+}
+
+/// Returns the number of lines a node spans, counting partial first and last lines.
+fn line_span(node: &Node) -> usize {
+    node.end_position().row - node.start_position().row + 1
 }
 
-/// Recursively traverses the AST and counts function and class/struct nodes.
+/// Limits a function length to the policy's maximum.
+fn capped_lines(lines: usize, policy: &CountPolicy) -> usize {
+    policy
+        .max_function_lines
+        .map_or(lines, |limit| lines.min(limit))
+}
+
+/// Lists the functions longer than the policy's maximum, in source order.
+///
+/// [`collect_stats`] only counts oversized functions; the analyzer calls this
+/// for files where that count is non-zero, so reports can name each one.
+pub(crate) fn oversized_functions(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    policy: &CountPolicy,
+) -> Vec<OversizedFunction> {
+    let mut oversized = Vec::new();
+    let query = DeclarationQuery::for_grammar(Grammar::of_tree(*language, tree));
+    query.for_each_counted(tree, source_code, policy, |declaration, node| {
+        let lines = line_span(&node);
+        let counted_lines = capped_lines(lines, policy);
+        if declaration == Declaration::Function && counted_lines < lines {
+            oversized.push(OversizedFunction {
+                line: node.start_position().row + 1,
+                lines,
+                counted_lines,
+            });
+        }
+    });
+    oversized.sort_by_key(|function| function.line);
+    oversized
+}
+
+/// Returns the package a Go or Java file declares, e.g. `main` or `com.example.app`.
 ///
-/// Uses depth-first traversal to examine each node and determine if it represents
-/// a function or class/struct declaration based on language-specific node types.
-fn count_nodes(node: &Node, stats: &mut CodeStats, language: &SupportedLanguage) {
+/// Every file of a package repeats the declaration, so directory totals use
+/// the name to count each package once. Other languages return `None`.
+pub(crate) fn package_name(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+) -> Option<String> {
+    let kind = match language {
+        SupportedLanguage::Go => "package_clause",
+        SupportedLanguage::Java => "package_declaration",
+        _ => return None,
+    };
+
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let declaration = root
+        .children(&mut cursor)
+        .find(|child| child.kind() == kind)?;
+
+    // Java package declarations may carry annotations before the name
+    let mut cursor = declaration.walk();
+    let name = declaration.named_children(&mut cursor).find(|child| {
+        matches!(
+            child.kind(),
+            "package_identifier" | "identifier" | "scoped_identifier"
+        )
+    })?;
+    name.utf8_text(source_code.as_bytes())
+        .ok()
+        .map(str::to_string)
+}
+
+/// Recursively counts string and numeric literals that are "magic values".
+///
+/// Literals are only counted outside constant and enum declarations, where a
+/// value is given a name. Import paths, attributes/annotations and Python
+/// docstrings are not values either and are skipped as well.
+fn count_magic_values(
+    node: &Node,
+    source: &[u8],
+    stats: &mut CodeStats,
+    language: &SupportedLanguage,
+    in_constant: bool,
+) {
     let node_kind = node.kind();
 
+    if language.string_literal_kinds().contains(&node_kind) {
+        if !in_constant && !is_docstring(node, language) {
+            stats.magic_string_count += 1;
+        }
+        // Nested nodes are fragments or interpolations of this literal
+        return;
+    }
+
+    if language.numeric_literal_kinds().contains(&node_kind) {
+        let is_trivial = matches!(node.utf8_text(source), Ok("0" | "1"));
+        if !in_constant && !is_trivial {
+            stats.magic_number_count += 1;
+        }
+        return;
+    }
+
+    let in_constant = in_constant || is_constant_context(node, source, language);
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        count_magic_values(&child, source, stats, language, in_constant);
+    }
+}
+
+/// Returns `true` if literals below this node name a value rather than use one.
+fn is_constant_context(node: &Node, source: &[u8], language: &SupportedLanguage) -> bool {
     match language {
-        SupportedLanguage::Rust => match node_kind {
-            "function_item" => stats.function_count += 1,
-            "struct_item" | "enum_item" => stats.class_struct_count += 1,
-            _ => {}
-        },
-        SupportedLanguage::Go => {
-            match node_kind {
-                "function_declaration" | "method_declaration" => stats.function_count += 1,
-                "type_spec" => {
-                    // Go uses type_spec for type declarations, but we only want to count structs.
-                    // A type_spec node has a "type" field that contains the actual type definition.
-                    // We need to check if this type is specifically a struct_type, not an interface,
-                    // type alias, or other type declaration.
-                    if let Some(type_node) = node.child_by_field_name("type")
-                        && type_node.kind() == "struct_type"
-                    {
-                        stats.class_struct_count += 1;
-                    }
-                }
-                _ => {}
-            }
+        SupportedLanguage::Rust => matches!(
+            node.kind(),
+            "const_item" | "static_item" | "enum_item" | "attribute_item" | "inner_attribute_item"
+        ),
+        SupportedLanguage::Go => matches!(node.kind(), "const_declaration" | "import_declaration"),
+        SupportedLanguage::Python => {
+            // Python has no const keyword; module constants are spelled in UPPER_CASE
+            node.kind() == "assignment"
+                && node
+                    .child_by_field_name("left")
+                    .filter(|left| left.kind() == "identifier")
+                    .and_then(|left| left.utf8_text(source).ok())
+                    .is_some_and(|name| {
+                        name.chars().any(|c| c.is_ascii_uppercase())
+                            && !name.chars().any(|c| c.is_ascii_lowercase())
+                    })
         }
-        SupportedLanguage::Python => match node_kind {
-            "function_definition" => stats.function_count += 1,
-            "class_definition" => stats.class_struct_count += 1,
-            _ => {}
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript => match node.kind() {
+            "lexical_declaration" => node
+                .child_by_field_name("kind")
+                .is_some_and(|kind| kind.kind() == "const"),
+            "enum_declaration" | "import_statement" => true,
+            _ => false,
         },
-        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript => match node_kind {
-            "function_declaration"
-            | "function_expression"
-            | "arrow_function"
-            | "method_definition" => {
-                stats.function_count += 1;
+        SupportedLanguage::Java => match node.kind() {
+            "enum_declaration" | "annotation" | "marker_annotation" => true,
+            "field_declaration" => {
+                let mut cursor = node.walk();
+                node.children(&mut cursor)
+                    .find(|child| child.kind() == "modifiers")
+                    .and_then(|modifiers| modifiers.utf8_text(source).ok())
+                    .is_some_and(|modifiers| {
+                        let words: Vec<_> = modifiers.split_whitespace().collect();
+                        words.contains(&"static") && words.contains(&"final")
+                    })
             }
-            "class_declaration" => stats.class_struct_count += 1,
-            _ => {}
+            _ => false,
         },
-        SupportedLanguage::Java => match node_kind {
-            "method_declaration" | "constructor_declaration" => stats.function_count += 1,
-            "class_declaration" | "interface_declaration" => stats.class_struct_count += 1,
-            _ => {}
+        SupportedLanguage::Dart => match node.kind() {
+            // Top-level and static `const`/`final` declarations
+            "static_final_declaration_list"
+            | "enum_declaration"
+            | "annotation"
+            | "import_or_export"
+            | "part_directive"
+            | "part_of_directive" => true,
+            // Local `const x = ...`
+            "initialized_variable_definition" => node
+                .child(0)
+                .is_some_and(|keyword| keyword.kind() == "const"),
+            _ => false,
         },
     }
+}
 
-    // Recursively traverse all child nodes to find nested declarations.
-    // This ensures we count all functions and classes, including:
-    // - Nested functions (e.g., closures, inner functions)
-    // - Nested classes
-    // - Methods within classes
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        count_nodes(&child, stats, language);
-    }
+/// Returns `true` if the string literal is a Python docstring.
+///
+/// A string that forms an expression statement on its own has no effect other
+/// than documenting the surrounding module, class or function.
+fn is_docstring(node: &Node, language: &SupportedLanguage) -> bool {
+    *language == SupportedLanguage::Python
+        && node
+            .parent()
+            .is_some_and(|parent| parent.kind() == "expression_statement")
 }
 
 #[cfg(test)]
@@ -164,6 +834,7 @@ mod tests {
             SupportedLanguage::JavaScript,
             SupportedLanguage::TypeScript,
             SupportedLanguage::Java,
+            SupportedLanguage::Dart,
         ];
 
         for lang in languages {
@@ -172,6 +843,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tsx_grammar_parses_jsx_elements() {
+        let jsx = "const App = () => <div className=\"app\">{title}</div>;\n";
+        let assertion = "const n = <number>value;\n";
+        let typescript = Grammar::Primary(SupportedLanguage::TypeScript);
+
+        let has_error = |grammar: Grammar, source: &str| {
+            let mut parser = create_grammar_parser(grammar).unwrap();
+            let tree = parse_source(&mut parser, source, "test").unwrap();
+            tree.root_node().has_error()
+        };
+
+        assert!(!has_error(Grammar::Tsx, jsx));
+        assert!(has_error(typescript, jsx));
+        assert!(!has_error(typescript, assertion));
+    }
+
+    #[test]
+    fn test_parse_source_interruptible() {
+        let source = "fn f() { let x = 1 + 2; }\n".repeat(2_000);
+        let mut parser = create_parser(&SupportedLanguage::Rust).unwrap();
+
+        let interrupt = Interrupt::default();
+        interrupt.raise();
+        let result = parse_source_interruptible(&mut parser, &source, "big.rs", &interrupt);
+        assert!(matches!(result, Err(CodeStatsError::Interrupted(path)) if path == "big.rs"));
+
+        // The reset parser starts over for the next file
+        let tree =
+            parse_source_interruptible(&mut parser, &source, "big.rs", &Interrupt::default())
+                .unwrap();
+        assert_eq!(tree.root_node().named_child_count(), 2_000);
+    }
+
     #[test]
     fn test_analyze_code_rust() {
         let rust_code = r#"
@@ -198,7 +903,47 @@ enum Status {
         let stats = analyze_code(&mut parser, rust_code, "test.rs", &language).unwrap();
 
         assert_eq!(stats.function_count, 2);
-        assert_eq!(stats.class_struct_count, 2);
+        assert_eq!(stats.class_struct_count, 1); // Person
+        assert_eq!(stats.enum_count, 1); // Status
+        assert_eq!(stats.byte_count, rust_code.len());
+    }
+
+    #[test]
+    fn test_analyze_code_rust_impl_blocks() {
+        let rust_code = r#"
+struct Parser;
+
+impl Parser {
+    fn new() -> Self {
+        fn helper() {}
+        Parser
+    }
+    fn parse(&self) {}
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser
+    }
+}
+
+impl<T> From<T> for Parser {
+    fn from(_: T) -> Self {
+        Parser
+    }
+}
+
+trait Parse {
+    fn parse(&self) {}
+}
+"#;
+
+        let stats = count_source(SupportedLanguage::Rust, rust_code);
+        assert_eq!(stats.impl_count, 1);
+        assert_eq!(stats.trait_impl_count, 2);
+        // The nested helper and the trait's default method are no impl methods
+        assert_eq!(stats.impl_method_count, 4);
+        assert_eq!(stats.function_count, 6);
     }
 
     #[test]
@@ -229,6 +974,37 @@ class Animal:
         assert_eq!(stats.class_struct_count, 2); // Person, Animal
     }
 
+    #[test]
+    fn test_analyze_code_python_function_kinds() {
+        let python_code = r#"
+import functools
+
+class Account:
+    @property
+    def balance(self):
+        return 0
+
+    @staticmethod
+    @functools.cache
+    def rate():
+        return 1
+
+async def fetch(urls):
+    key = lambda url: len(url)
+    return sorted(urls, key=key)
+
+@app.route("/")
+async def index():
+    return map(lambda x: x, [])
+"#;
+
+        let stats = count_source(SupportedLanguage::Python, python_code);
+        assert_eq!(stats.function_count, 4);
+        assert_eq!(stats.async_function_count, 2); // fetch, index
+        assert_eq!(stats.decorated_function_count, 3); // balance, rate, index
+        assert_eq!(stats.lambda_count, 2);
+    }
+
     #[test]
     fn test_analyze_code_javascript() {
         let js_code = r#"
@@ -320,7 +1096,86 @@ interface Runnable {
         let stats = analyze_code(&mut parser, java_code, "Main.java", &language).unwrap();
 
         assert_eq!(stats.function_count, 4); // main, helper, constructor, run (interface method)
-        assert_eq!(stats.class_struct_count, 2); // Main, Runnable
+        assert_eq!(stats.class_struct_count, 1); // Main
+        assert_eq!(stats.interface_count, 1); // Runnable
+    }
+
+    #[test]
+    fn test_analyze_code_java_records_annotations_and_lambdas() {
+        let java_code = r#"
+public record Point(int x, int y) {
+    public Point {
+        if (x < 0) throw new IllegalArgumentException();
+    }
+
+    double length() {
+        return Math.sqrt(x * x + y * y);
+    }
+}
+
+public @interface Audited {
+    String value() default "";
+}
+
+enum Axis { X, Y }
+
+class Sorter {
+    void sort(List<Point> points) {
+        points.sort((a, b) -> a.x() - b.x());
+        points.forEach(p -> System.out.println(p));
+    }
+}
+"#;
+
+        let stats = count_source(SupportedLanguage::Java, java_code);
+        assert_eq!(stats.class_struct_count, 2); // Point, Sorter
+        assert_eq!(stats.interface_count, 1); // Audited
+        assert_eq!(stats.enum_count, 1); // Axis
+        assert_eq!(stats.lambda_count, 2);
+        // length and sort; the compact constructor, annotation elements and
+        // lambdas are no functions
+        assert_eq!(stats.function_count, 2);
+        assert_eq!(stats.public_type_count, 2); // Point, Audited
+    }
+
+    #[test]
+    fn test_analyze_code_dart() {
+        let dart_code = r#"
+library app.shapes;
+
+int add(int a, int b) => a + b;
+
+abstract class Shape {
+  Shape(this.x);
+  final int x;
+  double area();
+  int get size => 3;
+  static const limit = 42;
+
+  String describe() {
+    final twice = (y) => y * 2;
+    return "size ${twice(7)}";
+  }
+}
+
+mixin Flying on Shape {
+  void fly() {}
+}
+
+enum Color { red, green }
+"#;
+
+        let stats = count_source(SupportedLanguage::Dart, dart_code);
+
+        // add, constructor, area, size, describe, twice, fly
+        assert_eq!(stats.function_count, 7);
+        assert_eq!(stats.class_struct_count, 1); // Shape
+        assert_eq!(stats.interface_count, 1); // Flying
+        assert_eq!(stats.enum_count, 1); // Color
+        assert_eq!(stats.module_count, 1); // app.shapes
+        // 3 and 2; limit is a constant and 7 is part of the string
+        assert_eq!(stats.magic_number_count, 2);
+        assert_eq!(stats.magic_string_count, 1);
     }
 
     #[test]
@@ -342,6 +1197,21 @@ interface Runnable {
         }
     }
 
+    #[test]
+    fn test_analyze_code_counts_parse_errors() {
+        let language = SupportedLanguage::Rust;
+        let mut parser = create_parser(&language).unwrap();
+
+        let stats = analyze_code(&mut parser, "fn ok() {}\n", "ok.rs", &language).unwrap();
+        assert_eq!(stats.parse_errors, 0);
+        assert_eq!(stats.parse_error_ratio(), 0.0);
+
+        // An unclosed parameter list is recovered as an ERROR or MISSING node
+        let stats = analyze_code(&mut parser, "fn test( {\n", "broken.rs", &language).unwrap();
+        assert!(stats.parse_errors > 0);
+        assert!(stats.parse_error_ratio() >= UNRELIABLE_PARSE_ERROR_RATIO);
+    }
+
     #[test]
     fn test_analyze_code_nested_functions() {
         let js_code = r#"
@@ -416,7 +1286,491 @@ type Person struct {
         let stats = analyze_code(&mut parser, source, "test.go", &SupportedLanguage::Go).unwrap();
         // Only the Person struct should be counted
         assert_eq!(stats.class_struct_count, 1);
+        assert_eq!(stats.interface_count, 1); // Writer
+        assert_eq!(stats.type_alias_count, 2); // StringAlias, Counter
         // Functions: Increment method
         assert_eq!(stats.function_count, 1);
     }
+
+    #[test]
+    fn test_analyze_code_go_generics() {
+        let source = r#"
+package main
+
+type Number interface {
+    ~int | ~float64
+}
+
+type Pair[K comparable, V any] struct {
+    Key   K
+    Value V
+}
+
+type List[T any] []T
+
+func Map[T, U any](items []T, f func(T) U) []U {
+    return nil
+}
+
+func (p Pair[K, V]) Swap() Pair[K, V] {
+    return p
+}
+
+func Plain() {}
+"#;
+
+        let stats = count_source(SupportedLanguage::Go, source);
+        // Pair, List and Map; methods cannot declare type parameters of their own
+        assert_eq!(stats.generic_count, 3);
+        assert_eq!(stats.type_parameter_count, 5);
+        // Generic declarations keep being counted as what they declare
+        assert_eq!(stats.interface_count, 1);
+        assert_eq!(stats.class_struct_count, 1);
+        assert_eq!(stats.type_alias_count, 1);
+        assert_eq!(stats.function_count, 3);
+    }
+
+    #[test]
+    fn test_type_categories_per_language() {
+        // (language, source, interfaces, enums, type aliases)
+        let cases = [
+            (
+                SupportedLanguage::Rust,
+                "trait Shape {}\nenum Kind { A }\ntype Id = u32;\nstruct S;\n",
+                1,
+                1,
+                1,
+            ),
+            (
+                SupportedLanguage::Python,
+                "type Vector = list[float]\nclass Color:\n    pass\n",
+                0,
+                0,
+                1,
+            ),
+            (
+                SupportedLanguage::TypeScript,
+                "interface Shape {}\nenum Kind { A }\ntype Id = number;\nclass C {}\n",
+                1,
+                1,
+                1,
+            ),
+            (SupportedLanguage::JavaScript, "class C {}\n", 0, 0, 0),
+            (
+                SupportedLanguage::Java,
+                "interface Shape {}\nenum Kind { A }\nclass C {}\n",
+                1,
+                1,
+                0,
+            ),
+            (
+                SupportedLanguage::Dart,
+                "mixin Shape {}\nenum Kind { a }\ntypedef Id = int;\nclass C = Object with Shape;\n",
+                1,
+                1,
+                1,
+            ),
+        ];
+
+        for (language, source, interfaces, enums, type_aliases) in cases {
+            let stats = count_source(language, source);
+            assert_eq!(stats.class_struct_count, 1, "{language:?}");
+            assert_eq!(stats.interface_count, interfaces, "{language:?}");
+            assert_eq!(stats.enum_count, enums, "{language:?}");
+            assert_eq!(stats.type_alias_count, type_aliases, "{language:?}");
+        }
+    }
+
+    #[test]
+    fn test_analyze_code_magic_values_rust() {
+        let source = r#"
+const LIMIT: usize = 100;
+static NAME: &str = "static";
+
+enum Level {
+    Low = 10,
+    High = 20,
+}
+
+#[cfg(feature = "extra")]
+fn main() {
+    let retries = 3;
+    let label = "retrying";
+    let start = 0;
+    let step = 1;
+}
+"#;
+
+        let language = SupportedLanguage::Rust;
+        let mut parser = create_parser(&language).unwrap();
+        let stats = analyze_code(&mut parser, source, "magic.rs", &language).unwrap();
+
+        assert_eq!(stats.magic_string_count, 1); // "retrying"
+        assert_eq!(stats.magic_number_count, 1); // 3
+        assert_eq!(stats.magic_value_count(), 2);
+    }
+
+    #[test]
+    fn test_analyze_code_magic_values_python() {
+        let source = r#"
+"""Module docstring."""
+import os
+
+TIMEOUT = 30
+GREETING = "hello"
+
+def connect():
+    """Connect with retries."""
+    retries = 5
+    return os.environ.get("HOST", "localhost")
+"#;
+
+        let language = SupportedLanguage::Python;
+        let mut parser = create_parser(&language).unwrap();
+        let stats = analyze_code(&mut parser, source, "magic.py", &language).unwrap();
+
+        assert_eq!(stats.magic_string_count, 2); // "HOST", "localhost"
+        assert_eq!(stats.magic_number_count, 1); // 5
+    }
+
+    #[test]
+    fn test_analyze_code_magic_values_javascript_and_java() {
+        let js = r#"
+import { x } from "./module";
+const MAX = 42;
+let count = 7;
+console.log("done", count * 60);
+"#;
+        let language = SupportedLanguage::JavaScript;
+        let mut parser = create_parser(&language).unwrap();
+        let stats = analyze_code(&mut parser, js, "magic.js", &language).unwrap();
+        assert_eq!(stats.magic_string_count, 1); // "done"
+        assert_eq!(stats.magic_number_count, 2); // 7, 60
+
+        let java = r#"
+public class Config {
+    private static final int PORT = 8080;
+    private int retries = 3;
+
+    @SuppressWarnings("unchecked")
+    void run() {
+        System.out.println("running");
+    }
+}
+"#;
+        let language = SupportedLanguage::Java;
+        let mut parser = create_parser(&language).unwrap();
+        let stats = analyze_code(&mut parser, java, "Config.java", &language).unwrap();
+        assert_eq!(stats.magic_string_count, 1); // "running"
+        assert_eq!(stats.magic_number_count, 1); // 3
+    }
+
+    #[test]
+    fn test_count_source_with_visits_every_node_once() {
+        let source = "def f():\n    return g(1)\n\nclass C:\n    pass\n";
+        let mut kinds = Vec::new();
+        let mut record = |language: SupportedLanguage, node: &Node, source: &str| {
+            assert_eq!(language, SupportedLanguage::Python);
+            kinds.push((
+                node.kind(),
+                node.utf8_text(source.as_bytes()).unwrap().len(),
+            ));
+        };
+
+        let stats = count_source_with(SupportedLanguage::Python, source, &mut record);
+
+        assert_eq!(stats, count_source(SupportedLanguage::Python, source));
+        // Pre-order: the module comes first and spans the whole source
+        assert_eq!(kinds[0], ("module", source.len()));
+        assert_eq!(
+            kinds
+                .iter()
+                .filter(|(kind, _)| *kind == "function_definition")
+                .count(),
+            stats.function_count
+        );
+
+        let mut parser = create_parser(&SupportedLanguage::Python).unwrap();
+        let tree = parse_source(&mut parser, source, "test.py").unwrap();
+        assert_eq!(kinds.len(), tree.root_node().descendant_count());
+    }
+
+    #[test]
+    fn test_function_lengths_are_capped() {
+        let long_body = "    let x = 1;\n".repeat(8);
+        let source = format!("fn long() {{\n{long_body}}}\n\nfn short() {{\n    work();\n}}\n");
+        let language = SupportedLanguage::Rust;
+        let mut parser = create_parser(&language).unwrap();
+        let tree = parse_source(&mut parser, &source, "test.rs").unwrap();
+        let policy = CountPolicy {
+            max_function_lines: Some(5),
+            ..CountPolicy::default()
+        };
+
+        let stats = collect_stats(&tree, &source, &language, &policy, None);
+        assert_eq!(stats.function_count, 2);
+        assert_eq!(stats.function_lines, 5 + 3);
+        assert_eq!(stats.oversized_function_count, 1);
+        assert_eq!(stats.average_function_lines(), Some(4.0));
+        assert_eq!(
+            oversized_functions(&tree, &source, &language, &policy),
+            vec![OversizedFunction {
+                line: 1,
+                lines: 10,
+                counted_lines: 5,
+            }]
+        );
+
+        let unlimited = CountPolicy {
+            max_function_lines: None,
+            ..CountPolicy::default()
+        };
+        let stats = collect_stats(&tree, &source, &language, &unlimited, None);
+        assert_eq!(stats.function_lines, 10 + 3);
+        assert_eq!(stats.oversized_function_count, 0);
+        assert!(oversized_functions(&tree, &source, &language, &unlimited).is_empty());
+        assert_eq!(CodeStats::new().average_function_lines(), None);
+    }
+
+    #[test]
+    fn test_module_counts_per_language() {
+        let cases = [
+            (
+                SupportedLanguage::Rust,
+                "mod a;\nmod b {\n    mod c {}\n}\nfn f() {}\n",
+                3,
+            ),
+            (
+                SupportedLanguage::TypeScript,
+                "namespace A {\n  export namespace B {}\n}\ndeclare module \"m\" {}\n",
+                3,
+            ),
+            (SupportedLanguage::Go, "package main\n\nfunc f() {}\n", 1),
+            (
+                SupportedLanguage::Java,
+                "package com.example;\n\nclass A {}\n",
+                1,
+            ),
+            (SupportedLanguage::Python, "import os\n", 0),
+            (SupportedLanguage::JavaScript, "export function f() {}\n", 0),
+        ];
+
+        for (language, source, expected) in cases {
+            assert_eq!(
+                count_source(language, source).module_count,
+                expected,
+                "{language:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_package_name() {
+        let cases = [
+            (SupportedLanguage::Go, "package server\n", Some("server")),
+            (
+                SupportedLanguage::Java,
+                "@Deprecated\npackage com.example.app;\n",
+                Some("com.example.app"),
+            ),
+            (SupportedLanguage::Java, "class A {}\n", None),
+            (SupportedLanguage::Rust, "mod a;\n", None),
+        ];
+
+        for (language, source, expected) in cases {
+            let mut parser = create_parser(&language).unwrap();
+            let tree = parse_source(&mut parser, source, "test").unwrap();
+            assert_eq!(
+                package_name(&tree, source, &language).as_deref(),
+                expected,
+                "{language:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_anonymous_function_policies() {
+        use crate::config::AnonymousFunctions;
+
+        let cases = [
+            (
+                SupportedLanguage::JavaScript,
+                "const handler = () => 1;\nfunction main() {\n    [1].map((x) => x);\n    \
+                 setTimeout(function () {}, 0);\n}\n",
+                [4, 4, 1, 2],
+            ),
+            (
+                SupportedLanguage::Rust,
+                "fn main() {\n    let add = |a: i32, b: i32| a + b;\n}\n",
+                [1, 2, 1, 1],
+            ),
+            (
+                SupportedLanguage::Python,
+                "square = lambda x: x * x\ndef f(items):\n    return sorted(items, key=lambda v: v)\n",
+                [1, 3, 1, 2],
+            ),
+            (
+                SupportedLanguage::Java,
+                "class A {\n    Runnable r = () -> {};\n    void f() {\n        list.forEach(p -> p.run());\n    }\n}\n",
+                [1, 3, 1, 2],
+            ),
+        ];
+
+        // Counted functions without a policy, and with all, none and top-level
+        let policies = [
+            None,
+            Some(AnonymousFunctions::All),
+            Some(AnonymousFunctions::None),
+            Some(AnonymousFunctions::TopLevel),
+        ];
+        for (language, source, expected) in cases {
+            let mut parser = create_parser(&language).unwrap();
+            let tree = parse_source(&mut parser, source, "test").unwrap();
+            for (anonymous_functions, expected) in policies.into_iter().zip(expected) {
+                let policy = CountPolicy {
+                    anonymous_functions,
+                    ..CountPolicy::default()
+                };
+                let stats = collect_stats(&tree, source, &language, &policy, None);
+                assert_eq!(
+                    stats.function_count, expected,
+                    "{language:?} {anonymous_functions:?}"
+                );
+            }
+        }
+
+        // Counted closures have parameters but no visibility
+        let policy = CountPolicy {
+            anonymous_functions: Some(AnonymousFunctions::All),
+            ..CountPolicy::default()
+        };
+        let source = "pub fn main() {\n    let add = |a: i32, b: i32| a + b;\n}\n";
+        let mut parser = create_parser(&SupportedLanguage::Rust).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+        let stats = collect_stats(&tree, source, &SupportedLanguage::Rust, &policy, None);
+        assert_eq!(stats.parameter_count, 2);
+        assert_eq!(stats.public_function_count, 1);
+        assert_eq!(stats.private_function_count, 0);
+    }
+
+    #[test]
+    fn test_kind_rules() {
+        let mut policy = CountPolicy::default();
+        let rules = [
+            ("trait_item", "class"),
+            ("macro_definition", "function"),
+            ("enum_item", "none"),
+            ("closure_expression", "function"),
+        ];
+        for (kind, declaration) in rules {
+            policy
+                .kind_rules
+                .insert(SupportedLanguage::Rust, kind, declaration)
+                .unwrap();
+        }
+
+        let source = "pub trait Shape {}
+enum Color { Red }
+struct Point;
+                      macro_rules! square { ($x:expr) => { $x * $x }; }
+                      fn main() {
+    let add = |a: i32| a + 1;
+}
+";
+        let mut parser = create_parser(&SupportedLanguage::Rust).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+        let stats = collect_stats(&tree, source, &SupportedLanguage::Rust, &policy, None);
+        assert_eq!(stats.class_struct_count, 2);
+        assert_eq!(stats.interface_count, 0);
+        assert_eq!(stats.enum_count, 0);
+        assert_eq!(stats.function_count, 3);
+        assert_eq!(stats.public_type_count, 1);
+
+        // Rules of other languages do not apply
+        let source = "class Shape {}
+";
+        let mut parser = create_parser(&SupportedLanguage::JavaScript).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+        let stats = collect_stats(&tree, source, &SupportedLanguage::JavaScript, &policy, None);
+        assert_eq!(stats.class_struct_count, 1);
+    }
+
+    #[test]
+    fn test_code_stats_merge() {
+        let mut total = CodeStats::new();
+        total.merge(&CodeStats {
+            function_count: 2,
+            documented_function_count: 1,
+            public_function_count: 1,
+            private_function_count: 1,
+            public_type_count: 1,
+            private_type_count: 1,
+            class_struct_count: 1,
+            interface_count: 2,
+            enum_count: 3,
+            type_alias_count: 4,
+            module_count: 2,
+            impl_count: 3,
+            trait_impl_count: 2,
+            impl_method_count: 5,
+            generic_count: 2,
+            type_parameter_count: 3,
+            async_function_count: 1,
+            decorated_function_count: 2,
+            lambda_count: 3,
+            component_count: 4,
+            magic_string_count: 3,
+            magic_number_count: 4,
+            untranslated_string_count: 5,
+            function_lines: 30,
+            oversized_function_count: 1,
+            parameter_count: 4,
+            many_parameter_function_count: 1,
+            max_nesting_depth: 3,
+            total_lines: 10,
+            code_lines: 6,
+            comment_lines: 3,
+            blank_lines: 1,
+            byte_count: 240,
+            parse_errors: 2,
+            query_counts: BTreeMap::from([("macro".to_string(), 3)]),
+        });
+        total.merge(&CodeStats {
+            function_count: 1,
+            max_nesting_depth: 2,
+            ..CodeStats::default()
+        });
+
+        assert_eq!(total.function_count, 3);
+        assert_eq!(total.documented_function_count, 1);
+        assert_eq!(total.public_function_count, 1);
+        assert_eq!(total.private_type_count, 1);
+        assert_eq!(total.class_struct_count, 1);
+        assert_eq!(total.interface_count, 2);
+        assert_eq!(total.enum_count, 3);
+        assert_eq!(total.type_alias_count, 4);
+        assert_eq!(total.type_definition_count(), 9);
+        assert_eq!(total.module_count, 2);
+        assert_eq!(total.impl_count, 3);
+        assert_eq!(total.trait_impl_count, 2);
+        assert_eq!(total.impl_method_count, 5);
+        assert_eq!(total.generic_count, 2);
+        assert_eq!(total.type_parameter_count, 3);
+        assert_eq!(total.async_function_count, 1);
+        assert_eq!(total.decorated_function_count, 2);
+        assert_eq!(total.lambda_count, 3);
+        assert_eq!(total.component_count, 4);
+        assert_eq!(total.magic_value_count(), 7);
+        assert_eq!(total.untranslated_string_count, 5);
+        assert_eq!(total.function_lines, 30);
+        assert_eq!(total.oversized_function_count, 1);
+        assert_eq!(total.parameter_count, 4);
+        assert_eq!(total.many_parameter_function_count, 1);
+        assert_eq!(total.max_nesting_depth, 3);
+        assert_eq!(total.total_lines, 10);
+        assert_eq!(total.code_lines, 6);
+        assert_eq!(total.byte_count, 240);
+        assert_eq!(total.parse_errors, 2);
+        assert_eq!(total.query_counts["macro"], 3);
+    }
 }
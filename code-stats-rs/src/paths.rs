@@ -0,0 +1,114 @@
+//! Allocation-free helpers for matching paths during traversal, and the
+//! rewriting of reported paths.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Returns `true` if the path contains `pattern` as a substring.
+///
+/// `/` and `\` are interchangeable on both sides, so a pattern such as
+/// `src/generated` also matches `src\generated\api.rs` in a Windows checkout,
+/// and patterns written with backslashes match Unix paths.
+///
+/// The comparison runs on the path's encoded bytes, so no string is built for
+/// each visited entry. Both separators are ASCII, so they can never be confused
+/// with part of a multi-byte character.
+pub(crate) fn path_contains(path: &Path, pattern: &str) -> bool {
+    let haystack = path.as_os_str().as_encoded_bytes();
+    let needle = pattern.as_bytes();
+
+    needle.is_empty()
+        || haystack.windows(needle.len()).any(|window| {
+            window
+                .iter()
+                .zip(needle)
+                .all(|(&a, &b)| a == b || (is_separator(a) && is_separator(b)))
+        })
+}
+
+/// Returns `true` if a file or directory name marks it as hidden by starting
+/// with a dot, like `.git` or `.env`.
+pub(crate) fn is_hidden_name(name: &OsStr) -> bool {
+    name.as_encoded_bytes().first() == Some(&b'.')
+}
+
+/// Expresses `path` relative to the directory `root`.
+///
+/// Paths are first compared as spelled, then made absolute against the
+/// current directory, so `src/a.rs` lies inside `/work/src` when run from
+/// `/work`. Symbolic links are not resolved and the file system is not
+/// accessed.
+///
+/// # Returns
+///
+/// * `Some(PathBuf)` with the part of `path` below `root`
+/// * `None` if `path` lies outside of `root`, or is `root` itself
+pub(crate) fn relative_to(path: &Path, root: &Path) -> Option<PathBuf> {
+    let relative = match path.strip_prefix(root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => {
+            let root = std::path::absolute(root).ok()?;
+            std::path::absolute(path)
+                .ok()?
+                .strip_prefix(&root)
+                .ok()?
+                .to_path_buf()
+        }
+    };
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Returns `true` for the path separators of Unix (`/`) and Windows (`\`).
+fn is_separator(byte: u8) -> bool {
+    byte == b'/' || byte == b'\\'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_contains() {
+        let path = Path::new("src/generated/api.rs");
+
+        assert!(path_contains(path, "generated"));
+        assert!(path_contains(path, "/api.rs"));
+        assert!(path_contains(path, ""));
+        assert!(!path_contains(path, "vendor"));
+        assert!(!path_contains(Path::new("a"), "abc"));
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let cwd = std::env::current_dir().unwrap();
+        for (path, root, expected) in [
+            ("src/lib.rs", "src", Some("lib.rs")),
+            ("./src/a/b.rs", ".", Some("src/a/b.rs")),
+            ("src/lib.rs", "./src", Some("lib.rs")),
+            ("/repo/src/lib.rs", "/repo", Some("src/lib.rs")),
+            ("src/lib.rs", cwd.to_str().unwrap(), Some("src/lib.rs")),
+            ("src/lib.rs", "tests", None),
+            ("src", "src", None),
+        ] {
+            assert_eq!(
+                relative_to(Path::new(path), Path::new(root)),
+                expected.map(PathBuf::from),
+                "{path} relative to {root}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_path_contains_ignores_separator_style() {
+        let unix = Path::new("repo/src/generated/api.rs");
+        let windows = Path::new(r"C:\repo\src\generated\api.rs");
+
+        for path in [unix, windows] {
+            assert!(path_contains(path, "src/generated"));
+            assert!(path_contains(path, r"src\generated"));
+            assert!(path_contains(path, r"generated/api.rs"));
+            assert!(!path_contains(path, "src/generated/lib.rs"));
+        }
+        assert!(!path_contains(unix, "src.generated"));
+    }
+}
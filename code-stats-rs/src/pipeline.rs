@@ -0,0 +1,1031 @@
+//! Composable analysis stages for embedding the analyzer.
+//!
+//! An analysis runs as a pipeline of swappable stages:
+//!
+//! 1. A [`SourceProvider`] yields the [`Source`]s to analyze, e.g. the files
+//!    of a directory ([`DirectorySource`]) or an editor's open buffers.
+//! 2. A [`Detector`] decides the language of each source ([`ExtensionDetector`]).
+//!    Files are read only once they have a language.
+//! 3. A [`Counter`] computes its statistics ([`TreeSitterCounter`]).
+//! 4. An [`Aggregator`] combines the statistics of all sources ([`Report`]).
+//! 5. A [`Formatter`] writes the aggregate ([`OutputFormat`] for a [`Report`]).
+//!
+//! The defaults are the stages of the command line: [`DirectorySource`]
+//! walks a directory as directory analysis does, files are read and screened
+//! the same way, and [`TreeSitterCounter`] counts with the analyzer itself.
+//!
+//! Closures work as detectors, counters, aggregators and formatters, and any iterator of
+//! `Result<Source, CodeStatsError>` is a source provider, so a single stage can be
+//! replaced without reimplementing the others:
+//!
+//! ```
+//! use code_stats_rs::SupportedLanguage;
+//! use code_stats_rs::cli::OutputFormat;
+//! use code_stats_rs::pipeline::{Formatter, Pipeline, Source};
+//!
+//! // Unsaved editor buffers instead of files on disk
+//! let buffers = vec![
+//!     Ok(Source::new("main.rs", "fn main() {}\nstruct App;\n")),
+//!     Ok(Source::new("scratch", "def helper():\n    pass\n")),
+//! ];
+//!
+//! let report = Pipeline::new(buffers.into_iter())
+//!     // The scratch buffer has no extension, but the editor knows its language
+//!     .with_detector(|source: &Source| match source.path.extension() {
+//!         Some(_) => SupportedLanguage::from_file_extension(&source.path),
+//!         None => Some(SupportedLanguage::Python),
+//!     })
+//!     .run();
+//!
+//! assert_eq!(report.file_count(), 2);
+//! assert_eq!(report.total_stats().function_count, 2);
+//!
+//! let mut output = Vec::new();
+//! OutputFormat::Summary.write(&report, &mut output).unwrap();
+//! assert!(String::from_utf8(output).unwrap().contains("Total: 2 functions"));
+//! ```
+//!
+//! Instead of aggregating, [`Pipeline::stream`] yields each source's
+//! [`FileStats`] as soon as it is counted, e.g. to drive a progress display:
+//!
+//! ```
+//! use code_stats_rs::pipeline::{Pipeline, Source};
+//!
+//! let buffers = vec![
+//!     Ok(Source::new("a.go", "package a\nfunc A() {}\n")),
+//!     Ok(Source::new("b.go", "package b\nfunc B() {}\nfunc C() {}\n")),
+//! ];
+//!
+//! let mut functions = 0;
+//! for (done, file) in Pipeline::new(buffers.into_iter()).stream().enumerate() {
+//!     let file = file.unwrap();
+//!     functions += file.stats.function_count;
+//!     println!("[{}] {}", done + 1, file.path.display());
+//! }
+//! assert_eq!(functions, 3);
+//! ```
+//!
+//! [`Aggregator`]: crate::pipeline::Aggregator
+//! [`Counter`]: crate::pipeline::Counter
+//! [`Detector`]: crate::pipeline::Detector
+//! [`DirectorySource`]: crate::pipeline::DirectorySource
+//! [`ExtensionDetector`]: crate::pipeline::ExtensionDetector
+//! [`FileStats`]: crate::pipeline::FileStats
+//! [`Formatter`]: crate::pipeline::Formatter
+//! [`Report`]: crate::pipeline::Report
+//! [`Source`]: crate::pipeline::Source
+//! [`SourceProvider`]: crate::pipeline::SourceProvider
+//! [`TreeSitterCounter`]: crate::pipeline::TreeSitterCounter
+//! [`Pipeline::stream`]: crate::pipeline::Pipeline::stream
+//! [`OutputFormat`]: crate::cli::OutputFormat
+
+use crate::analyzer::CodeAnalyzer;
+use crate::cli::OutputFormat;
+use crate::config::{ConfigResolver, CountPolicy};
+use crate::error::CodeStatsError;
+use crate::findings::Finding;
+use crate::formatter::{FormatOptions, write_output};
+use crate::language::SupportedLanguage;
+use crate::parser::CodeStats;
+use crate::source::{decode_source, screen};
+use crate::stats::{DirectoryStats, ReportMeta};
+use crate::walk::{DirectoryWalk, WalkOptions, Walked};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// A source to analyze, with the path it is reported under.
+///
+/// A source is either text in memory, whose path does not need to exist and
+/// only names it in reports and lets detectors look at the extension, or a
+/// file that is read once a [`Detector`] gave it a language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    /// Path the source is reported under
+    pub path: PathBuf,
+    /// The complete source text, `None` for a file not read yet
+    text: Option<String>,
+}
+
+impl Source {
+    /// Creates a source from a path and its text.
+    pub fn new(path: impl Into<PathBuf>, text: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            text: Some(text.into()),
+        }
+    }
+
+    /// Creates a source for a file, read only if its language is detected.
+    ///
+    /// Like on the command line, files in other encodings are transcoded,
+    /// and files that are not text or have minified-length lines are passed
+    /// over without being counted.
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            text: None,
+        }
+    }
+
+    /// Returns the source text, or `None` for a file not read yet.
+    ///
+    /// Detectors see files before they are read; aggregators only see
+    /// sources with their text.
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Reads the text of a file source, unless it is in memory already.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - The text is there to count
+    /// * `Ok(false)` - The file is malformed text, binary or minified
+    /// * `Err(CodeStatsError::IoError)` if the file cannot be read
+    fn read(&mut self) -> Result<bool, CodeStatsError> {
+        if self.text.is_some() {
+            return Ok(true);
+        }
+        let bytes = std::fs::read(&self.path).map_err(|e| CodeStatsError::io(&self.path, e))?;
+        Ok(self.accept(bytes))
+    }
+
+    /// Takes the contents of a file source as its text, decoding them like
+    /// the command line, unless they are not text worth counting.
+    fn accept(&mut self, bytes: Vec<u8>) -> bool {
+        match decode_source(&self.path, bytes, None) {
+            Ok(text) if screen(&text).is_none() => {
+                self.text = Some(text);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Supplies the sources of a pipeline.
+///
+/// Every iterator over `Result<Source, CodeStatsError>` is a provider.
+/// Errors are handed to the [`Aggregator`] and do not stop the pipeline;
+/// I/O errors keep their [`io::ErrorKind`] and the path they were about.
+pub trait SourceProvider {
+    /// Returns the next source, or `None` when every source was provided.
+    fn next_source(&mut self) -> Option<Result<Source, CodeStatsError>>;
+}
+
+impl<I> SourceProvider for I
+where
+    I: Iterator<Item = Result<Source, CodeStatsError>>,
+{
+    fn next_source(&mut self) -> Option<Result<Source, CodeStatsError>> {
+        self.next()
+    }
+}
+
+/// Provides the files below a directory, to be read once detected.
+///
+/// The directory is walked like by the command line's directory analysis:
+/// in file name order, leaving out hidden entries, `.code-stats.toml` files,
+/// files matching an ignore pattern of the walker or of a `.code-stats.toml`,
+/// and files larger than 10 MiB. Symbolic links are not followed. An
+/// invalid `.code-stats.toml` is provided as an error that ends the
+/// directory. The counting settings of `.code-stats.toml` files are not
+/// applied.
+pub struct DirectorySource {
+    root: PathBuf,
+    ignore_patterns: Vec<String>,
+    /// The walk, started by the first call to `next`
+    walk: Option<DirectoryWalk>,
+}
+
+impl DirectorySource {
+    /// Creates a provider for every file below `root`, following no symbolic links.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            ignore_patterns: Vec::new(),
+            walk: None,
+        }
+    }
+
+    /// Skips files whose path contains any of the patterns (substring matching).
+    pub fn with_ignore(mut self, ignore_patterns: Vec<String>) -> Self {
+        self.ignore_patterns = ignore_patterns;
+        self
+    }
+}
+
+impl Iterator for DirectorySource {
+    type Item = Result<Source, CodeStatsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let walk = self.walk.get_or_insert_with(|| {
+            let options = WalkOptions {
+                ignore_patterns: std::mem::take(&mut self.ignore_patterns),
+                ..WalkOptions::default()
+            };
+            DirectoryWalk::new(&self.root, options, ConfigResolver::new(&self.root))
+        });
+        loop {
+            return match walk.next()? {
+                Ok(Walked::File(path, _)) => Some(Ok(Source::file(path))),
+                // Duplicates and oversized files are left out silently
+                Ok(Walked::Skipped(_)) => continue,
+                Ok(Walked::Error(e)) | Err(e) => Some(Err(e)),
+            };
+        }
+    }
+}
+
+/// Decides the language of a source.
+///
+/// Any closure taking a `&Source` and returning an `Option<SupportedLanguage>`
+/// is a detector.
+pub trait Detector {
+    /// Returns the language of the source, or `None` to skip it.
+    fn detect(&mut self, source: &Source) -> Option<SupportedLanguage>;
+}
+
+impl<F> Detector for F
+where
+    F: FnMut(&Source) -> Option<SupportedLanguage>,
+{
+    fn detect(&mut self, source: &Source) -> Option<SupportedLanguage> {
+        self(source)
+    }
+}
+
+/// Detects languages by file extension, like the command line does before
+/// content-based detection.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExtensionDetector;
+
+impl Detector for ExtensionDetector {
+    fn detect(&mut self, source: &Source) -> Option<SupportedLanguage> {
+        SupportedLanguage::from_file_extension(&source.path)
+    }
+}
+
+/// Computes the statistics of a source whose language is known.
+///
+/// Any closure taking `(SupportedLanguage, &Path, &str)`, the language, path
+/// and text of the source, and returning [`CodeStats`] is a counter.
+pub trait Counter {
+    /// Counts the statistics of one source text.
+    fn count(&mut self, language: SupportedLanguage, path: &Path, text: &str) -> CodeStats;
+}
+
+impl<F> Counter for F
+where
+    F: FnMut(SupportedLanguage, &Path, &str) -> CodeStats,
+{
+    fn count(&mut self, language: SupportedLanguage, path: &Path, text: &str) -> CodeStats {
+        self(language, path, text)
+    }
+}
+
+/// Counts with the built-in tree-sitter rules, reusing one parser per grammar.
+///
+/// Counts are those of the command line with default settings: they match
+/// [`count_source`](crate::count_source), except that `.tsx` sources are
+/// parsed with the TSX grammar and a Python `__init__.py` counts as a module.
+#[derive(Default)]
+pub struct TreeSitterCounter {
+    analyzer: CodeAnalyzer,
+}
+
+impl TreeSitterCounter {
+    /// Creates a counter with an empty parser cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Counter for TreeSitterCounter {
+    fn count(&mut self, language: SupportedLanguage, path: &Path, text: &str) -> CodeStats {
+        self.analyzer
+            .analyze_source(path, text, language, &CountPolicy::default())
+            .expect("parsing with a bundled grammar always produces a tree")
+            .stats
+    }
+}
+
+/// Combines the statistics of every source of a pipeline.
+///
+/// Any closure taking `(&Source, SupportedLanguage, CodeStats)` is an
+/// aggregator that ignores skipped sources and errors.
+pub trait Aggregator {
+    /// Adds the statistics of a counted source.
+    fn add(&mut self, source: &Source, language: SupportedLanguage, stats: CodeStats);
+
+    /// Notes a source the detector skipped. Does nothing by default.
+    fn skip(&mut self, _source: &Source) {}
+
+    /// Notes a source that could not be provided. Does nothing by default.
+    fn error(&mut self, _error: CodeStatsError) {}
+}
+
+impl<F> Aggregator for F
+where
+    F: FnMut(&Source, SupportedLanguage, CodeStats),
+{
+    fn add(&mut self, source: &Source, language: SupportedLanguage, stats: CodeStats) {
+        self(source, language, stats)
+    }
+}
+
+/// The aggregate behind the command line's reports.
+///
+/// Skipped sources are counted and provider errors kept as findings, so
+/// [`OutputFormat`] reports them like the command line does.
+#[derive(Debug, Default)]
+pub struct Report {
+    stats: DirectoryStats,
+}
+
+impl Report {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of counted sources.
+    pub fn file_count(&self) -> usize {
+        self.stats.total_files()
+    }
+
+    /// Returns the totals across every counted source.
+    pub fn total_stats(&self) -> &CodeStats {
+        &self.stats.total_stats
+    }
+
+    /// Returns the sources' paths, languages and statistics, in the order counted.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, SupportedLanguage, &CodeStats)> {
+        self.stats
+            .files
+            .iter()
+            .map(|file| (file.path.as_path(), file.language, &file.stats))
+    }
+
+    /// Returns the number of sources that could not be provided.
+    pub fn error_count(&self) -> usize {
+        self.stats.error_count()
+    }
+
+    /// Returns the analyzed directory recorded when the report was saved.
+    pub fn root(&self) -> Option<&Path> {
+        self.stats.meta.as_ref().map(|meta| meta.root.as_path())
+    }
+
+    /// Saves the report as a JSON snapshot, for [`Report::load`] or to
+    /// compare later runs against.
+    ///
+    /// The snapshot is the report `--format json` writes, described by
+    /// `schema/report.schema.json`. Its metadata records the tool version,
+    /// the analyzed directory and, when `SOURCE_DATE_EPOCH` is set, the time.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The analyzed directory to record
+    /// * `writer` - Where the snapshot is written
+    pub fn save(&mut self, root: &Path, writer: impl Write) -> io::Result<()> {
+        self.stats.meta = Some(ReportMeta::new(root.to_path_buf(), Vec::new()));
+        self.stats.to_writer(writer).map_err(io::Error::other)
+    }
+
+    /// Loads a report saved with [`Report::save`] or `--format json`.
+    ///
+    /// Reports of older versions of the tool, including ones without
+    /// metadata, are accepted.
+    ///
+    /// # Returns
+    ///
+    /// The report, or an [`io::ErrorKind::InvalidData`] error for text that
+    /// is not a saved report or a report of a newer schema version
+    pub fn load(reader: impl Read) -> io::Result<Self> {
+        let stats = DirectoryStats::from_reader(reader)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        Ok(Self { stats })
+    }
+}
+
+impl Aggregator for Report {
+    fn add(&mut self, source: &Source, language: SupportedLanguage, stats: CodeStats) {
+        self.stats.add_file(crate::stats::FileStats::new(
+            source.path.clone(),
+            language,
+            stats,
+        ));
+    }
+
+    fn skip(&mut self, _source: &Source) {
+        self.stats.unsupported_files += 1;
+    }
+
+    fn error(&mut self, error: CodeStatsError) {
+        let path = error.path().map(Path::to_path_buf);
+        self.stats.findings.push(Finding::error(path, &error));
+    }
+}
+
+/// The statistics of one counted source, yielded by an [`AnalysisStream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStats {
+    /// Path the source is reported under
+    pub path: PathBuf,
+    /// The detected language
+    pub language: SupportedLanguage,
+    /// The counted statistics
+    pub stats: CodeStats,
+}
+
+/// Yields the statistics of each source as soon as it is counted.
+///
+/// Created by [`Pipeline::stream`]. Sources are detected, read and counted
+/// lazily, one per call to `next`, so dropping the stream stops the
+/// analysis. Provider and read errors are yielded as `Err` without ending
+/// the stream; sources the detector skips are not yielded but counted by
+/// [`skipped`](Self::skipped).
+pub struct AnalysisStream<S, D = ExtensionDetector, C = TreeSitterCounter> {
+    sources: S,
+    detector: D,
+    counter: C,
+    skipped: usize,
+}
+
+impl<S, D, C> AnalysisStream<S, D, C> {
+    /// Returns the number of sources skipped so far for lack of a language.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
+
+impl<S, D, C> Iterator for AnalysisStream<S, D, C>
+where
+    S: SourceProvider,
+    D: Detector,
+    C: Counter,
+{
+    type Item = Result<FileStats, CodeStatsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut source = match self.sources.next_source()? {
+                Ok(source) => source,
+                Err(e) => return Some(Err(e)),
+            };
+            let Some(language) = self.detector.detect(&source) else {
+                self.skipped += 1;
+                continue;
+            };
+            match source.read() {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+
+            let stats =
+                self.counter
+                    .count(language, &source.path, source.text().unwrap_or_default());
+            return Some(Ok(FileStats {
+                path: source.path,
+                language,
+                stats,
+            }));
+        }
+    }
+}
+
+/// Writes an aggregate, e.g. to stdout.
+///
+/// Any closure taking `(&A, &mut dyn Write)` and returning `io::Result<()>`
+/// is a formatter.
+pub trait Formatter<A> {
+    /// Writes `aggregate` to `out`.
+    fn write(&self, aggregate: &A, out: &mut dyn Write) -> io::Result<()>;
+}
+
+impl<A, F> Formatter<A> for F
+where
+    F: Fn(&A, &mut dyn Write) -> io::Result<()>,
+{
+    fn write(&self, aggregate: &A, out: &mut dyn Write) -> io::Result<()> {
+        self(aggregate, out)
+    }
+}
+
+/// Writes a report exactly as the command line prints it in this format.
+impl Formatter<Report> for OutputFormat {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        write_output(&report.stats, *self, &FormatOptions::default(), out)
+    }
+}
+
+/// Runs sources through detection, counting and aggregation.
+///
+/// Created with the default stages by [`Pipeline::new`]; each `with_*` method
+/// replaces one stage.
+pub struct Pipeline<S, D = ExtensionDetector, C = TreeSitterCounter, A = Report> {
+    sources: S,
+    detector: D,
+    counter: C,
+    aggregator: A,
+}
+
+impl<S: SourceProvider> Pipeline<S> {
+    /// Creates a pipeline over `sources` with the default stages.
+    pub fn new(sources: S) -> Self {
+        Self {
+            sources,
+            detector: ExtensionDetector,
+            counter: TreeSitterCounter::new(),
+            aggregator: Report::new(),
+        }
+    }
+}
+
+impl<S, D, C, A> Pipeline<S, D, C, A>
+where
+    S: SourceProvider,
+    D: Detector,
+    C: Counter,
+    A: Aggregator,
+{
+    /// Replaces the language detector.
+    pub fn with_detector<D2: Detector>(self, detector: D2) -> Pipeline<S, D2, C, A> {
+        Pipeline {
+            sources: self.sources,
+            detector,
+            counter: self.counter,
+            aggregator: self.aggregator,
+        }
+    }
+
+    /// Replaces the statistics counter.
+    pub fn with_counter<C2: Counter>(self, counter: C2) -> Pipeline<S, D, C2, A> {
+        Pipeline {
+            sources: self.sources,
+            detector: self.detector,
+            counter,
+            aggregator: self.aggregator,
+        }
+    }
+
+    /// Replaces the aggregator, e.g. with one that keeps state between runs.
+    pub fn with_aggregator<A2: Aggregator>(self, aggregator: A2) -> Pipeline<S, D, C, A2> {
+        Pipeline {
+            sources: self.sources,
+            detector: self.detector,
+            counter: self.counter,
+            aggregator,
+        }
+    }
+
+    /// Processes every source and returns the aggregate.
+    ///
+    /// Sources are processed one at a time in the provider's order. Provider
+    /// and read errors and skipped sources are reported to the aggregator and
+    /// never stop the run; files that are not text worth counting are passed
+    /// over.
+    pub fn run(mut self) -> A {
+        while let Some(next) = self.sources.next_source() {
+            let mut source = match next {
+                Ok(source) => source,
+                Err(e) => {
+                    self.aggregator.error(e);
+                    continue;
+                }
+            };
+            let Some(language) = self.detector.detect(&source) else {
+                self.aggregator.skip(&source);
+                continue;
+            };
+            match source.read() {
+                Ok(true) => {
+                    let text = source.text().unwrap_or_default();
+                    let stats = self.counter.count(language, &source.path, text);
+                    self.aggregator.add(&source, language, stats);
+                }
+                Ok(false) => {}
+                Err(e) => self.aggregator.error(e),
+            }
+        }
+        self.aggregator
+    }
+
+    /// Yields the statistics of each source as it is counted, leaving the
+    /// aggregation to the caller.
+    ///
+    /// The pipeline's aggregator is not used.
+    pub fn stream(self) -> AnalysisStream<S, D, C> {
+        AnalysisStream {
+            sources: self.sources,
+            detector: self.detector,
+            counter: self.counter,
+            skipped: 0,
+        }
+    }
+}
+
+/// Analyzes a directory on a tokio runtime without blocking its executor.
+///
+/// Files are provided by a [`DirectorySource`], detected by extension, read
+/// with tokio's file IO and counted on blocking threads by
+/// [`TreeSitterCounter`]s, with at most
+/// [`with_max_concurrency`](Self::with_max_concurrency) files in flight. The
+/// report is the one of a [`Pipeline`] over the same directory.
+///
+/// ```
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # let dir = tempfile::TempDir::new().unwrap();
+/// # std::fs::write(dir.path().join("lib.rs"), "fn a() {}\n").unwrap();
+/// # let root = dir.path();
+/// use code_stats_rs::pipeline::AsyncDirectoryAnalysis;
+///
+/// # runtime.block_on(async {
+/// let report = AsyncDirectoryAnalysis::new(root)
+///     .with_max_concurrency(16)
+///     .run()
+///     .await;
+/// assert_eq!(report.total_stats().function_count, 1);
+/// # });
+/// ```
+#[cfg(feature = "async")]
+pub struct AsyncDirectoryAnalysis {
+    root: PathBuf,
+    ignore_patterns: Vec<String>,
+    max_concurrency: usize,
+}
+
+#[cfg(feature = "async")]
+impl AsyncDirectoryAnalysis {
+    /// Files analyzed at the same time unless configured otherwise.
+    pub const DEFAULT_MAX_CONCURRENCY: usize = 64;
+
+    /// Creates an analysis of every file below `root`, following no symbolic links.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            ignore_patterns: Vec::new(),
+            max_concurrency: Self::DEFAULT_MAX_CONCURRENCY,
+        }
+    }
+
+    /// Skips files whose path contains any of the patterns (substring matching).
+    pub fn with_ignore(mut self, ignore_patterns: Vec<String>) -> Self {
+        self.ignore_patterns = ignore_patterns;
+        self
+    }
+
+    /// Limits how many files are read or counted at the same time (at least one).
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Analyzes the directory into a [`Report`].
+    ///
+    /// Files are added in the order their analysis finishes. Errors walking
+    /// the directory or reading a file are reported like those of a
+    /// [`Pipeline`] and never stop the run.
+    ///
+    /// # Panics
+    ///
+    /// If not called within a tokio runtime.
+    pub async fn run(self) -> Report {
+        let mut report = Report::new();
+        let ignore_patterns = self.ignore_patterns;
+        let root = self.root;
+
+        // walkdir has no async interface, so the listing runs on a blocking thread
+        let sources = tokio::task::spawn_blocking(move || {
+            DirectorySource::new(root)
+                .with_ignore(ignore_patterns)
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_else(|e| vec![Err(io::Error::other(e).into())]);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for source in sources {
+            let path = match source {
+                Ok(source) => source.path,
+                Err(e) => {
+                    report.error(e);
+                    continue;
+                }
+            };
+            if tasks.len() >= self.max_concurrency
+                && let Some(done) = tasks.join_next().await
+            {
+                aggregate_async(&mut report, done);
+            }
+            tasks.spawn(analyze_file_async(path));
+        }
+        while let Some(done) = tasks.join_next().await {
+            aggregate_async(&mut report, done);
+        }
+
+        report
+    }
+}
+
+/// What became of one file of an [`AsyncDirectoryAnalysis`].
+#[cfg(feature = "async")]
+enum FileOutcome {
+    /// The file was read and counted; the statistics are boxed, as they
+    /// are much larger than the other outcomes
+    Counted(Source, SupportedLanguage, Box<CodeStats>),
+    /// The file's language is not supported, so it was not read
+    Skipped(Source),
+    /// The file is malformed text, binary or minified
+    NotText,
+}
+
+/// Detects, reads and counts one file of an [`AsyncDirectoryAnalysis`].
+#[cfg(feature = "async")]
+async fn analyze_file_async(path: PathBuf) -> Result<FileOutcome, CodeStatsError> {
+    thread_local! {
+        static COUNTER: std::cell::RefCell<TreeSitterCounter> =
+            std::cell::RefCell::new(TreeSitterCounter::new());
+    }
+
+    let mut source = Source::file(path);
+    let Some(language) = ExtensionDetector.detect(&source) else {
+        return Ok(FileOutcome::Skipped(source));
+    };
+    let bytes = tokio::fs::read(&source.path)
+        .await
+        .map_err(|e| CodeStatsError::io(&source.path, e))?;
+    if !source.accept(bytes) {
+        return Ok(FileOutcome::NotText);
+    }
+
+    // Parsing is CPU-bound and must not hold up the executor
+    tokio::task::spawn_blocking(move || {
+        let text = source.text().unwrap_or_default();
+        let stats = COUNTER.with_borrow_mut(|counter| counter.count(language, &source.path, text));
+        FileOutcome::Counted(source, language, Box::new(stats))
+    })
+    .await
+    .map_err(|e| io::Error::other(e).into())
+}
+
+/// Adds the outcome of an [`analyze_file_async`] task to a report.
+#[cfg(feature = "async")]
+fn aggregate_async(
+    report: &mut Report,
+    done: Result<Result<FileOutcome, CodeStatsError>, tokio::task::JoinError>,
+) {
+    match done
+        .map_err(|e| io::Error::other(e).into())
+        .and_then(|outcome| outcome)
+    {
+        Ok(FileOutcome::Counted(source, language, stats)) => report.add(&source, language, *stats),
+        Ok(FileOutcome::Skipped(source)) => report.skip(&source),
+        Ok(FileOutcome::NotText) => {}
+        Err(e) => report.error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Wraps in-memory sources as a provider.
+    fn sources(sources: &[(&str, &str)]) -> impl SourceProvider {
+        sources
+            .iter()
+            .map(|(path, text)| Ok(Source::new(*path, *text)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_default_pipeline_counts_and_skips() {
+        let report = Pipeline::new(sources(&[
+            ("a.rs", "fn a() {}\nstruct A;\n"),
+            ("b.tsx", "function B() { return <div />; }\n"),
+            ("notes.txt", "fn not_code() {}\n"),
+        ]))
+        .run();
+
+        assert_eq!(report.file_count(), 2);
+        assert_eq!(report.total_stats().function_count, 2);
+        assert_eq!(report.total_stats().class_struct_count, 1);
+        assert_eq!(report.stats.unsupported_files, 1);
+        assert!(report.stats.findings.is_empty());
+    }
+
+    #[test]
+    fn test_stages_can_be_replaced() {
+        let mut counted = Vec::new();
+        let _ = Pipeline::new(sources(&[("a", "x"), ("b", "yy")]))
+            .with_detector(|_: &Source| Some(SupportedLanguage::Go))
+            .with_counter(|_: SupportedLanguage, _: &Path, text: &str| CodeStats {
+                total_lines: text.len(),
+                ..CodeStats::default()
+            })
+            .with_aggregator(|source: &Source, _: SupportedLanguage, stats: CodeStats| {
+                counted.push((source.path.clone(), stats.total_lines));
+            })
+            .run();
+
+        assert_eq!(
+            counted,
+            vec![(PathBuf::from("a"), 1), (PathBuf::from("b"), 2)]
+        );
+    }
+
+    #[test]
+    fn test_provider_errors_are_reported() {
+        let provider = vec![
+            Err(io::Error::other("buffer closed").into()),
+            Ok(Source::new("a.py", "def a():\n    pass\n")),
+        ];
+
+        let report = Pipeline::new(provider.into_iter()).run();
+
+        assert_eq!(report.file_count(), 1);
+        assert_eq!(report.error_count(), 1);
+    }
+
+    #[test]
+    fn test_io_errors_keep_their_kind_and_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("missing");
+
+        let error = DirectorySource::new(&missing).next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), crate::error::ErrorKind::Io);
+        assert_eq!(error.path(), Some(missing.as_path()));
+        match &error {
+            CodeStatsError::IoError { source, .. } => {
+                assert_eq!(source.kind(), io::ErrorKind::NotFound)
+            }
+            other => panic!("expected an IO error, got {other:?}"),
+        }
+
+        // The finding names the path, so the message does not repeat it
+        let report = Pipeline::new(DirectorySource::new(&missing)).run();
+        let finding = &report.stats.findings[0];
+        assert_eq!(finding.path.as_deref(), Some(missing.as_path()));
+        assert!(finding.message.starts_with("IO error: "));
+        assert!(!finding.message.contains("missing"));
+    }
+
+    #[test]
+    fn test_stream_yields_counted_sources() {
+        let provider = vec![
+            Ok(Source::new("a.rs", "fn a() {}\n")),
+            Ok(Source::new("notes.txt", "fn not_code() {}\n")),
+            Err(io::Error::other("buffer closed").into()),
+            Ok(Source::new("b.py", "def b():\n    pass\n")),
+        ];
+
+        let mut stream = Pipeline::new(provider.into_iter()).stream();
+        let first = stream.next().unwrap().unwrap();
+        assert_eq!(first.path, PathBuf::from("a.rs"));
+        assert_eq!(first.language, SupportedLanguage::Rust);
+        assert_eq!(first.stats.function_count, 1);
+        assert_eq!(stream.skipped(), 0);
+
+        // The skipped text file is passed over on the way to the error
+        assert!(stream.next().unwrap().is_err());
+        assert_eq!(stream.skipped(), 1);
+
+        let last = stream.next().unwrap().unwrap();
+        assert_eq!(last.language, SupportedLanguage::Python);
+        assert!(stream.next().is_none());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_directory_analysis() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("vendor")).unwrap();
+        for i in 0..5 {
+            std::fs::write(root.join(format!("f{i}.rs")), "fn a() {}\nfn b() {}\n").unwrap();
+        }
+        std::fs::write(root.join("vendor/lib.rs"), "fn c() {}\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "fn not_code() {}\n").unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G', 0xff, 0xfe]).unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let report = runtime.block_on(
+            AsyncDirectoryAnalysis::new(root)
+                .with_ignore(vec!["vendor".to_string()])
+                .with_max_concurrency(2)
+                .run(),
+        );
+
+        assert_eq!(report.file_count(), 5);
+        assert_eq!(report.total_stats().function_count, 10);
+        assert_eq!(report.error_count(), 0);
+        // The text and the image are skipped by extension, without being read
+        assert_eq!(report.stats.unsupported_files, 2);
+
+        let sequential =
+            Pipeline::new(DirectorySource::new(root).with_ignore(vec!["vendor".to_string()])).run();
+        assert_eq!(report.total_stats(), sequential.total_stats());
+        assert_eq!(
+            report.stats.unsupported_files,
+            sequential.stats.unsupported_files
+        );
+    }
+
+    #[test]
+    fn test_directory_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("vendor")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("gen")).unwrap();
+        std::fs::write(root.join("main.go"), "package main\nfunc main() {}\n").unwrap();
+        std::fs::write(root.join("vendor/lib.go"), "package lib\nfunc f() {}\n").unwrap();
+        std::fs::write(root.join(".git/hook.go"), "package git\nfunc h() {}\n").unwrap();
+        std::fs::write(root.join("gen/api.go"), "package gen\nfunc g() {}\n").unwrap();
+        std::fs::write(root.join("blob.go"), b"package blob\0\n").unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G', 0xff, 0xfe]).unwrap();
+        std::fs::write(root.join(".code-stats.toml"), "ignore = [\"gen/\"]\n").unwrap();
+        let directory = || DirectorySource::new(root).with_ignore(vec!["vendor".to_string()]);
+
+        // Files are provided unread, skipping hidden and ignored entries
+        let provided: Vec<Source> = directory().map(Result::unwrap).collect();
+        let paths: Vec<&Path> = provided
+            .iter()
+            .map(|source| source.path.as_path())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                root.join("blob.go"),
+                root.join("logo.png"),
+                root.join("main.go")
+            ]
+        );
+        assert!(provided.iter().all(|source| source.text().is_none()));
+
+        // Only detected files are read, and binary ones are passed over
+        let report = Pipeline::new(directory()).run();
+        assert_eq!(report.file_count(), 1);
+        assert_eq!(report.total_stats().function_count, 1);
+        assert_eq!(report.stats.unsupported_files, 1);
+        assert_eq!(report.error_count(), 0);
+    }
+
+    #[test]
+    fn test_directory_source_stops_at_invalid_config() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join(".code-stats.toml"), "ignore = 1\n").unwrap();
+        std::fs::write(temp_dir.path().join("main.go"), "package main\n").unwrap();
+
+        let provided: Vec<_> = DirectorySource::new(temp_dir.path()).collect();
+        assert_eq!(provided.len(), 1);
+        assert_eq!(
+            provided[0].as_ref().unwrap_err().kind(),
+            crate::error::ErrorKind::Config
+        );
+    }
+
+    #[test]
+    fn test_report_formats_like_command_line() {
+        let report = Pipeline::new(sources(&[("a.rs", "fn a() {}\n")])).run();
+
+        let mut json = Vec::new();
+        OutputFormat::Json.write(&report, &mut json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed["total_stats"]["function_count"], 1);
+        assert_eq!(parsed["files"][0]["path"], "a.rs");
+
+        let count_files =
+            |report: &Report, out: &mut dyn Write| write!(out, "{} files", report.file_count());
+        let mut text = Vec::new();
+        count_files.write(&report, &mut text).unwrap();
+        assert_eq!(text, b"1 files");
+    }
+
+    #[test]
+    fn test_report_save_and_load() {
+        let mut report = Pipeline::new(sources(&[("a.rs", "fn a() {}\n")])).run();
+        assert!(report.root().is_none());
+
+        let mut snapshot = Vec::new();
+        report.save(Path::new("/work/repo"), &mut snapshot).unwrap();
+        let loaded = Report::load(snapshot.as_slice()).unwrap();
+        assert_eq!(loaded.root(), Some(Path::new("/work/repo")));
+        assert_eq!(loaded.file_count(), 1);
+        assert_eq!(loaded.total_stats(), report.total_stats());
+
+        // The command line's JSON reports load as well, without metadata
+        let mut json = Vec::new();
+        OutputFormat::Json.write(&report, &mut json).unwrap();
+        assert_eq!(Report::load(json.as_slice()).unwrap().file_count(), 1);
+
+        let error = Report::load(b"{\"repos\": []}".as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,126 @@
+//! Per-file timings of a directory analysis, recorded with `--profile`.
+//!
+//! Every file is timed in three phases: detecting its language, reading it,
+//! and parsing it together with the counts taken from the syntax tree. A few
+//! pathological inputs, such as huge generated files, often account for most
+//! of a slow run, so the slowest files are reported next to the phase totals.
+
+use crate::paths::relative_to;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Time spent on the phases of analyzing one file, or on all files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PhaseTimes {
+    /// Detecting the language by extension or by content
+    pub detection: Duration,
+    /// Reading and decoding the file
+    pub reading: Duration,
+    /// Parsing and counting, or looking up a cached result
+    pub parsing: Duration,
+}
+
+impl PhaseTimes {
+    /// Returns the time spent on all phases.
+    pub(crate) fn total(&self) -> Duration {
+        self.detection + self.reading + self.parsing
+    }
+}
+
+/// The phase times of one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileTiming {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Time spent on each phase; phases after a file was skipped are zero
+    pub times: PhaseTimes,
+}
+
+/// Timings of every file a directory analysis detected the language of.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Profile {
+    /// Content detection of all files in batches, ahead of the per-file phases
+    pub batch_detection: Duration,
+    /// Files in the order they were analyzed
+    pub files: Vec<FileTiming>,
+}
+
+impl Profile {
+    /// Adds the timings of a file.
+    pub(crate) fn record(&mut self, path: &Path, times: PhaseTimes) {
+        self.files.push(FileTiming {
+            path: path.to_path_buf(),
+            times,
+        });
+    }
+
+    /// Sums the phase times of all files.
+    pub(crate) fn totals(&self) -> PhaseTimes {
+        self.files
+            .iter()
+            .fold(PhaseTimes::default(), |mut totals, file| {
+                totals.detection += file.times.detection;
+                totals.reading += file.times.reading;
+                totals.parsing += file.times.parsing;
+                totals
+            })
+    }
+
+    /// Returns the `count` files that took longest, slowest first.
+    pub(crate) fn slowest(&self, count: usize) -> Vec<&FileTiming> {
+        let mut files: Vec<&FileTiming> = self.files.iter().collect();
+        files.sort_by(|a, b| {
+            b.times
+                .total()
+                .cmp(&a.times.total())
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        files.truncate(count);
+        files
+    }
+
+    /// Rewrites the file paths relative to `root`, like
+    /// [`DirectoryStats::make_paths_relative`](crate::stats::DirectoryStats::make_paths_relative).
+    pub(crate) fn make_paths_relative(&mut self, root: &Path) {
+        for file in &mut self.files {
+            if let Some(relative) = relative_to(&file.path, root) {
+                file.path = relative;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(detection: u64, reading: u64, parsing: u64) -> PhaseTimes {
+        PhaseTimes {
+            detection: Duration::from_millis(detection),
+            reading: Duration::from_millis(reading),
+            parsing: Duration::from_millis(parsing),
+        }
+    }
+
+    #[test]
+    fn test_slowest_files_and_totals() {
+        let mut profile = Profile::default();
+        profile.record(Path::new("root/a.rs"), times(1, 2, 3));
+        profile.record(Path::new("root/big.rs"), times(1, 10, 90));
+        profile.record(Path::new("root/b.rs"), times(2, 2, 2));
+
+        let slowest: Vec<_> = profile
+            .slowest(2)
+            .into_iter()
+            .map(|file| file.path.clone())
+            .collect();
+        assert_eq!(
+            slowest,
+            [PathBuf::from("root/big.rs"), PathBuf::from("root/a.rs")]
+        );
+        assert_eq!(profile.totals(), times(4, 14, 95));
+
+        profile.make_paths_relative(Path::new("root"));
+        assert_eq!(profile.files[1].path, PathBuf::from("big.rs"));
+    }
+}
@@ -0,0 +1,398 @@
+//! Provenance attestations proving a published report was produced by the tool.
+//!
+//! An attestation is an [in-toto] statement whose subject is the SHA-256 digest
+//! of the report bytes. Its predicate records the tool version, the command-line
+//! options and the digest of every analyzed input file. With a signing key the
+//! statement is wrapped in a [DSSE] envelope carrying an HMAC-SHA256 signature,
+//! so anyone holding the key can detect a hand-edited report or attestation.
+//!
+//! [in-toto]: https://github.com/in-toto/attestation
+//! [DSSE]: https://github.com/secure-systems-lab/dsse
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac_sha256::{HMAC, Hash};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Statement type of in-toto attestations.
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// Predicate type of the provenance recorded for a report.
+const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+
+/// Build type identifying how the report was produced.
+const BUILD_TYPE: &str = "code-stats-rs/report@v1";
+
+/// Payload type of DSSE envelopes holding in-toto statements.
+const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// Number of hex digits of the key digest used as the key id.
+const KEY_ID_LENGTH: usize = 16;
+
+/// An in-toto statement attesting the provenance of one report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Statement {
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+    /// The report, identified by its digest
+    pub subject: Vec<ResourceDescriptor>,
+    #[serde(rename = "predicateType")]
+    pub predicate_type: String,
+    pub predicate: Provenance,
+}
+
+/// A named artifact and its digests, keyed by algorithm.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ResourceDescriptor {
+    pub name: String,
+    pub digest: BTreeMap<String, String>,
+}
+
+/// How the report was produced: options, inputs and tool version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Provenance {
+    pub build_definition: BuildDefinition,
+    pub run_details: RunDetails,
+}
+
+/// The options and analyzed files of a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BuildDefinition {
+    pub build_type: String,
+    /// Command-line arguments, without the program name
+    pub external_parameters: BTreeMap<String, Vec<String>>,
+    /// Every analyzed file with the digest of its content, in path order
+    pub resolved_dependencies: Vec<ResourceDescriptor>,
+}
+
+/// The tool that produced the report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RunDetails {
+    pub builder: Builder,
+}
+
+/// Name and version of the tool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Builder {
+    pub id: String,
+    pub version: BTreeMap<String, String>,
+}
+
+/// A DSSE envelope holding a signed statement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Envelope {
+    pub payload_type: String,
+    /// Base64 of the serialized statement
+    pub payload: String,
+    pub signatures: Vec<Signature>,
+}
+
+/// An HMAC-SHA256 signature over the envelope's pre-authentication encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Signature {
+    /// Leading hex digits of the key's SHA-256 digest
+    pub keyid: String,
+    /// Base64 of the signature
+    pub sig: String,
+}
+
+/// A writer that computes the SHA-256 digest of everything written through it.
+///
+/// Lets a report be streamed to stdout or a pager while its digest is taken.
+pub(crate) struct DigestWriter<'a> {
+    inner: &'a mut dyn Write,
+    hash: &'a mut Hash,
+}
+
+impl<'a> DigestWriter<'a> {
+    /// Wraps `inner`, adding every written byte to `hash`.
+    pub(crate) fn new(inner: &'a mut dyn Write, hash: &'a mut Hash) -> Self {
+        Self { inner, hash }
+    }
+}
+
+impl Write for DigestWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hash.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Statement {
+    /// Builds the statement for a report, hashing every input file.
+    ///
+    /// # Arguments
+    ///
+    /// * `report_digest` - SHA-256 digest of the report bytes
+    /// * `inputs` - The analyzed files
+    /// * `arguments` - The command-line arguments, without the program name
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Statement)` with the inputs sorted by path
+    /// * `Err(io::Error)` if an input file cannot be read
+    pub(crate) fn for_report(
+        report_digest: [u8; 32],
+        inputs: &[&Path],
+        arguments: Vec<String>,
+    ) -> io::Result<Self> {
+        let mut inputs = inputs.to_vec();
+        inputs.sort();
+        inputs.dedup();
+
+        let resolved_dependencies = inputs
+            .into_iter()
+            .map(|path| {
+                let content = std::fs::read(path)?;
+                Ok(descriptor(path.display().to_string(), Hash::hash(&content)))
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            statement_type: STATEMENT_TYPE.to_string(),
+            subject: vec![descriptor("report".to_string(), report_digest)],
+            predicate_type: PREDICATE_TYPE.to_string(),
+            predicate: Provenance {
+                build_definition: BuildDefinition {
+                    build_type: BUILD_TYPE.to_string(),
+                    external_parameters: BTreeMap::from([("arguments".to_string(), arguments)]),
+                    resolved_dependencies,
+                },
+                run_details: RunDetails {
+                    builder: Builder {
+                        id: env!("CARGO_PKG_NAME").to_string(),
+                        version: BTreeMap::from([(
+                            env!("CARGO_PKG_NAME").to_string(),
+                            env!("CARGO_PKG_VERSION").to_string(),
+                        )]),
+                    },
+                },
+            },
+        })
+    }
+
+    /// Returns whether the statement's subject is a report with the given content.
+    pub(crate) fn covers(&self, report: &[u8]) -> bool {
+        let expected = hex(&Hash::hash(report));
+        self.subject
+            .iter()
+            .any(|subject| subject.digest.get("sha256") == Some(&expected))
+    }
+}
+
+impl Envelope {
+    /// Signs a statement with an HMAC-SHA256 key.
+    pub(crate) fn sign(statement: &Statement, key: &[u8]) -> Self {
+        let payload = serde_json::to_vec(statement).expect("statements always serialize");
+        let sig = HMAC::mac(pae(PAYLOAD_TYPE, &payload), key);
+
+        Self {
+            payload_type: PAYLOAD_TYPE.to_string(),
+            payload: BASE64.encode(&payload),
+            signatures: vec![Signature {
+                keyid: key_id(key),
+                sig: BASE64.encode(sig),
+            }],
+        }
+    }
+
+    /// Checks the signature made with `key` and returns the signed statement.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Statement)` if a signature by `key` matches the payload
+    /// * `Err(String)` if no signature by `key` matches or the payload is invalid
+    pub(crate) fn verify(&self, key: &[u8]) -> Result<Statement, String> {
+        let payload = BASE64
+            .decode(&self.payload)
+            .map_err(|e| format!("invalid attestation payload: {e}"))?;
+
+        let keyid = key_id(key);
+        let signed = self
+            .signatures
+            .iter()
+            .filter(|signature| signature.keyid == keyid)
+            .filter_map(|signature| BASE64.decode(&signature.sig).ok())
+            .filter_map(|sig| <[u8; 32]>::try_from(sig).ok())
+            .any(|sig| HMAC::verify(pae(&self.payload_type, &payload), key, &sig));
+        if !signed {
+            return Err("the attestation is not signed with this key".to_string());
+        }
+
+        serde_json::from_slice(&payload).map_err(|e| format!("invalid attestation payload: {e}"))
+    }
+}
+
+/// Writes an attestation for a report to `path`.
+///
+/// The statement is written as-is without a key, or as a signed DSSE
+/// envelope with one.
+pub(crate) fn write_attestation(
+    path: &Path,
+    statement: &Statement,
+    key: Option<&[u8]>,
+) -> io::Result<()> {
+    let json = match key {
+        Some(key) => serde_json::to_string_pretty(&Envelope::sign(statement, key)),
+        None => serde_json::to_string_pretty(statement),
+    }
+    .map_err(io::Error::other)?;
+    std::fs::write(path, json + "\n")
+}
+
+/// Reads a signing key, ignoring one trailing newline so key files can be edited by hand.
+pub(crate) fn read_key(path: &Path) -> Result<Vec<u8>, String> {
+    let mut key =
+        std::fs::read(path).map_err(|e| format!("Failed to read key {}: {e}", path.display()))?;
+    if key.ends_with(b"\n") {
+        key.pop();
+        if key.ends_with(b"\r") {
+            key.pop();
+        }
+    }
+    if key.is_empty() {
+        return Err(format!("Key {} is empty", path.display()));
+    }
+    Ok(key)
+}
+
+/// Checks that a report matches its signed attestation.
+///
+/// # Arguments
+///
+/// * `report` - Path of the published report
+/// * `attestation` - Path of the DSSE envelope written with `--sign`
+/// * `key` - The key the attestation was signed with
+///
+/// # Returns
+///
+/// * `Ok(Statement)` with the attested provenance if the report is unmodified
+/// * `Err(String)` if a file cannot be read, the signature does not match or
+///   the report differs from the attested one
+pub(crate) fn verify_report(
+    report: &Path,
+    attestation: &Path,
+    key: &[u8],
+) -> Result<Statement, String> {
+    let report_bytes =
+        std::fs::read(report).map_err(|e| format!("Failed to read {}: {e}", report.display()))?;
+    let envelope_json = std::fs::read_to_string(attestation)
+        .map_err(|e| format!("Failed to read {}: {e}", attestation.display()))?;
+    let envelope: Envelope = serde_json::from_str(&envelope_json)
+        .map_err(|e| format!("{} is not a signed attestation: {e}", attestation.display()))?;
+
+    let statement = envelope.verify(key)?;
+    if !statement.covers(&report_bytes) {
+        return Err(format!(
+            "{} does not match the attested report",
+            report.display()
+        ));
+    }
+    Ok(statement)
+}
+
+/// Builds a descriptor with a SHA-256 digest.
+fn descriptor(name: String, digest: [u8; 32]) -> ResourceDescriptor {
+    ResourceDescriptor {
+        name,
+        digest: BTreeMap::from([("sha256".to_string(), hex(&digest))]),
+    }
+}
+
+/// DSSE pre-authentication encoding of a payload.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut encoded = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    )
+    .into_bytes();
+    encoded.extend_from_slice(payload);
+    encoded
+}
+
+/// Identifies a key without revealing it.
+fn key_id(key: &[u8]) -> String {
+    let mut id = hex(&Hash::hash(key));
+    id.truncate(KEY_ID_LENGTH);
+    id
+}
+
+/// Lowercase hex encoding of a digest.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a statement for a report without input files.
+    fn statement(report: &[u8]) -> Statement {
+        Statement::for_report(Hash::hash(report), &[], vec!["src".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_statement_records_inputs_and_tool() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let b = temp_dir.path().join("b.rs");
+        let a = temp_dir.path().join("a.rs");
+        std::fs::write(&a, "fn a() {}").unwrap();
+        std::fs::write(&b, "fn b() {}").unwrap();
+
+        let statement =
+            Statement::for_report(Hash::hash(b"report"), &[&b, &a, &b], Vec::new()).unwrap();
+
+        let inputs = &statement.predicate.build_definition.resolved_dependencies;
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].name, a.display().to_string());
+        assert_eq!(inputs[0].digest["sha256"], hex(&Hash::hash(b"fn a() {}")));
+        assert_eq!(
+            statement.predicate.run_details.builder.version["code-stats-rs"],
+            env!("CARGO_PKG_VERSION")
+        );
+        assert!(statement.covers(b"report"));
+        assert!(!statement.covers(b"report edited"));
+    }
+
+    #[test]
+    fn test_signed_envelope_round_trip() {
+        let statement = statement(b"report");
+        let envelope = Envelope::sign(&statement, b"secret");
+
+        assert_eq!(envelope.verify(b"secret"), Ok(statement));
+        assert!(envelope.verify(b"other key").is_err());
+    }
+
+    #[test]
+    fn test_tampered_payload_is_rejected() {
+        let mut envelope = Envelope::sign(&statement(b"report"), b"secret");
+        envelope.payload = BASE64.encode(serde_json::to_vec(&statement(b"edited")).unwrap());
+
+        assert!(envelope.verify(b"secret").is_err());
+    }
+
+    #[test]
+    fn test_pae() {
+        // Example from the DSSE specification
+        assert_eq!(
+            pae("http://example.com/HelloWorld", b"hello world"),
+            b"DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+        );
+    }
+}
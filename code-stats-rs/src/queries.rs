@@ -0,0 +1,611 @@
+//! Tree-sitter queries that select the nodes to count.
+//!
+//! Each language's declarations are described by a built-in query in
+//! `queries/<language>.scm`, whose capture names (`@function`, `@class`, ...)
+//! say which [`CodeStats`](crate::parser::CodeStats) field a node is counted
+//! in. Users can add queries of their own with `--query LANG=PATH` to count
+//! arbitrary constructs; every capture of such a query is counted under its
+//! own name, except for helper captures starting with `_`.
+
+use crate::config::{AnonymousFunctions, CountPolicy};
+use crate::language::{Grammar, SupportedLanguage};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use tree_sitter::{Node, Query, QueryCursor, StreamingIterator, Tree};
+
+/// The built-in queries of all grammars, compiled once on first use.
+static DECLARATION_QUERIES: LazyLock<HashMap<Grammar, DeclarationQuery>> = LazyLock::new(|| {
+    Grammar::ALL
+        .into_iter()
+        .map(|grammar| (grammar, DeclarationQuery::compile(grammar)))
+        .collect()
+});
+
+/// Node kinds of anonymous functions in the grammars: closures, lambdas,
+/// function literals and expressions, and arrow functions.
+const ANONYMOUS_FUNCTION_KINDS: [&str; 6] = [
+    "arrow_function",
+    "function_expression",
+    "closure_expression",
+    "func_literal",
+    "lambda",
+    "lambda_expression",
+];
+
+/// Node kinds of named functions and methods in the grammars; Dart's
+/// top-level functions and methods have their body in a `function_body`.
+const NAMED_FUNCTION_KINDS: [&str; 9] = [
+    "function_item",
+    "function_declaration",
+    "generator_function_declaration",
+    "function_definition",
+    "method_declaration",
+    "method_definition",
+    "constructor_declaration",
+    "local_function_declaration",
+    "function_body",
+];
+
+/// A kind of declaration counted by the built-in queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Declaration {
+    /// `@function`: functions, methods, constructors and closures
+    Function,
+    /// `@class`: classes and structs
+    Class,
+    /// `@interface`: interfaces and traits
+    Interface,
+    /// `@enum`
+    Enum,
+    /// `@type_alias`
+    TypeAlias,
+    /// `@module`: modules, namespaces and packages
+    Module,
+    /// `@impl`: Rust inherent impl blocks
+    Impl,
+    /// `@trait_impl`: Rust impl blocks implementing a trait
+    TraitImpl,
+    /// `@generic`: Go functions and types with type parameters, which are
+    /// also captured as their declaration
+    Generic,
+    /// `@async_function`: Python `async def` functions, also captured as functions
+    AsyncFunction,
+    /// `@decorated_function`: Python functions with decorators, such as
+    /// `@property`; the capture is the decorated definition
+    DecoratedFunction,
+    /// `@lambda`: Python and Java lambdas, which are not counted as functions
+    Lambda,
+    /// `@anonymous_function`: closures and lambdas of languages that do not
+    /// count them as functions, unless `--count-anonymous` says otherwise
+    AnonymousFunction,
+}
+
+impl Declaration {
+    /// Looks up the declaration a capture name stands for.
+    fn from_capture_name(name: &str) -> Option<Self> {
+        match name {
+            "function" => Some(Self::Function),
+            "class" => Some(Self::Class),
+            "interface" => Some(Self::Interface),
+            "enum" => Some(Self::Enum),
+            "type_alias" => Some(Self::TypeAlias),
+            "module" => Some(Self::Module),
+            "impl" => Some(Self::Impl),
+            "trait_impl" => Some(Self::TraitImpl),
+            "generic" => Some(Self::Generic),
+            "async_function" => Some(Self::AsyncFunction),
+            "decorated_function" => Some(Self::DecoratedFunction),
+            "lambda" => Some(Self::Lambda),
+            "anonymous_function" => Some(Self::AnonymousFunction),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the declaration says what a node is counted as, rather
+    /// than a trait of a node counted otherwise, such as `@generic`.
+    fn is_counted_as(self) -> bool {
+        matches!(
+            self,
+            Self::Function
+                | Self::Class
+                | Self::Interface
+                | Self::Enum
+                | Self::TypeAlias
+                | Self::Module
+                | Self::AnonymousFunction
+        )
+    }
+}
+
+/// Names a node kind rule can count a node as.
+const RULE_NAMES: [&str; 7] = [
+    "function",
+    "class",
+    "interface",
+    "enum",
+    "type_alias",
+    "module",
+    "none",
+];
+
+/// Node kinds counted differently than the built-in queries say, per language.
+///
+/// A rule applies to every node of its kind: the node is counted as the
+/// rule's declaration instead of whatever the query captures it as, or not at
+/// all for `none`. Kinds the query does not capture are counted as well,
+/// e.g. Rust's `trait_item` as a class or `macro_definition` as a function.
+/// Traits such as `@generic` or `@async_function` are still counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct KindRules(BTreeMap<SupportedLanguage, BTreeMap<String, Option<Declaration>>>);
+
+impl KindRules {
+    /// Counts nodes of a kind as the declaration `name`, one of [`RULE_NAMES`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once the rule replaced any earlier rule for the kind
+    /// * `Err(message)` if the language has no such node kind or the name is unknown
+    pub(crate) fn insert(
+        &mut self,
+        language: SupportedLanguage,
+        kind: &str,
+        name: &str,
+    ) -> Result<(), String> {
+        let grammars = [Grammar::Primary(language), Grammar::Tsx];
+        let is_kind = grammars
+            .iter()
+            .filter(|grammar| grammar.language() == language)
+            .any(|grammar| grammar.get_language().id_for_node_kind(kind, true) != 0);
+        if !is_kind {
+            return Err(format!(
+                "unknown node kind `{kind}` for {}",
+                language.name()
+            ));
+        }
+        if !RULE_NAMES.contains(&name) {
+            return Err(format!(
+                "cannot count `{kind}` as `{name}`; expected one of {}",
+                RULE_NAMES.join(", ")
+            ));
+        }
+
+        self.0
+            .entry(language)
+            .or_default()
+            .insert(kind.to_string(), Declaration::from_capture_name(name));
+        Ok(())
+    }
+
+    /// Returns the rules of a language, if it has any.
+    fn for_language(
+        &self,
+        language: SupportedLanguage,
+    ) -> Option<&BTreeMap<String, Option<Declaration>>> {
+        self.0.get(&language).filter(|rules| !rules.is_empty())
+    }
+}
+
+/// The built-in query of one grammar, with its captures resolved.
+pub(crate) struct DeclarationQuery {
+    query: Query,
+    /// The language whose [`KindRules`] apply to the query's matches
+    language: SupportedLanguage,
+    /// The declaration of each capture, indexed by capture index
+    declarations: Vec<Declaration>,
+}
+
+impl DeclarationQuery {
+    /// Returns the built-in query for a grammar.
+    pub(crate) fn for_grammar(grammar: Grammar) -> &'static Self {
+        &DECLARATION_QUERIES[&grammar]
+    }
+
+    /// Compiles the built-in query of a grammar.
+    ///
+    /// # Panics
+    ///
+    /// If the bundled query does not match the bundled grammar, which the
+    /// tests rule out.
+    fn compile(grammar: Grammar) -> Self {
+        let query = Query::new(&grammar.get_language(), builtin_source(grammar.language()))
+            .unwrap_or_else(|e| panic!("built-in query for {grammar:?} is invalid: {e}"));
+        let declarations = query
+            .capture_names()
+            .iter()
+            .map(|name| {
+                Declaration::from_capture_name(name)
+                    .unwrap_or_else(|| panic!("unknown capture @{name} in {grammar:?} query"))
+            })
+            .collect();
+
+        Self {
+            query,
+            language: grammar.language(),
+            declarations,
+        }
+    }
+
+    /// Calls `visit` for every declaration in the tree.
+    ///
+    /// A node matched by several patterns is visited once per pattern, so
+    /// the patterns of each capture are kept disjoint.
+    pub(crate) fn for_each(
+        &self,
+        tree: &Tree,
+        source_code: &str,
+        mut visit: impl FnMut(Declaration, Node<'_>),
+    ) {
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&self.query, tree.root_node(), source_code.as_bytes());
+        while let Some(found) = matches.next() {
+            for capture in found.captures {
+                visit(self.declarations[capture.index as usize], capture.node);
+            }
+        }
+    }
+
+    /// Calls `visit` for every declaration counted under a policy.
+    ///
+    /// Anonymous functions the policy counts are visited as
+    /// [`Declaration::Function`], whether the query captures them as
+    /// `@function` (JavaScript arrow functions) or as `@anonymous_function`
+    /// (Rust closures); the others are skipped. Without an anonymous function
+    /// policy, the captures of the query decide. Nodes of a kind the policy
+    /// has a [`KindRules`] rule for are visited as the rule says, after the
+    /// matches of the query.
+    pub(crate) fn for_each_counted(
+        &self,
+        tree: &Tree,
+        source_code: &str,
+        policy: &CountPolicy,
+        mut visit: impl FnMut(Declaration, Node<'_>),
+    ) {
+        let rules = policy.kind_rules.for_language(self.language);
+        let anonymous = policy.anonymous_functions;
+        self.for_each(tree, source_code, |declaration, node| {
+            if declaration.is_counted_as()
+                && rules.is_some_and(|rules| rules.contains_key(node.kind()))
+            {
+                return;
+            }
+            let is_anonymous = match declaration {
+                Declaration::Function => ANONYMOUS_FUNCTION_KINDS.contains(&node.kind()),
+                Declaration::AnonymousFunction => true,
+                _ => return visit(declaration, node),
+            };
+            let counted = !is_anonymous
+                || match anonymous {
+                    None => declaration == Declaration::Function,
+                    Some(AnonymousFunctions::All) => true,
+                    Some(AnonymousFunctions::None) => false,
+                    Some(AnonymousFunctions::TopLevel) => !is_nested_function(&node),
+                };
+            if counted {
+                visit(Declaration::Function, node);
+            }
+        });
+
+        let Some(rules) = rules else {
+            return;
+        };
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            if let Some(Some(declaration)) = rules.get(node.kind()) {
+                visit(*declaration, node);
+            }
+            if cursor.goto_first_child() || cursor.goto_next_sibling() {
+                continue;
+            }
+            loop {
+                if !cursor.goto_parent() {
+                    return;
+                }
+                if cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Checks whether a function is declared inside another function.
+fn is_nested_function(function: &Node) -> bool {
+    let mut ancestor = function.parent();
+    while let Some(node) = ancestor {
+        if ANONYMOUS_FUNCTION_KINDS.contains(&node.kind())
+            || NAMED_FUNCTION_KINDS.contains(&node.kind())
+        {
+            return true;
+        }
+        ancestor = node.parent();
+    }
+    false
+}
+
+/// Returns the source of a language's built-in query.
+fn builtin_source(language: SupportedLanguage) -> &'static str {
+    match language {
+        SupportedLanguage::Rust => include_str!("../queries/rust.scm"),
+        SupportedLanguage::Go => include_str!("../queries/go.scm"),
+        SupportedLanguage::Python => include_str!("../queries/python.scm"),
+        SupportedLanguage::JavaScript => include_str!("../queries/javascript.scm"),
+        SupportedLanguage::TypeScript => include_str!("../queries/typescript.scm"),
+        SupportedLanguage::Java => include_str!("../queries/java.scm"),
+        SupportedLanguage::Dart => include_str!("../queries/dart.scm"),
+    }
+}
+
+/// Lists the node kinds the built-in query of a language counts, by capture
+/// name, e.g. `function_item` for `function` in Rust.
+///
+/// The kinds are read from the query source: the node of each top-level
+/// pattern, or every node of a top-level `[...]` alternation. A pattern that
+/// also constrains the node's children, such as Go's `type_spec` with a
+/// `struct_type`, lists the outer node only.
+pub(crate) fn declaration_kinds(
+    language: SupportedLanguage,
+) -> BTreeMap<&'static str, Vec<&'static str>> {
+    static TOKEN: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"[()\[\]]|[^\s()\[\]]+").expect("valid token regex"));
+
+    let mut kinds: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut pattern = Vec::new();
+    let mut depth = 0;
+    let mut alternation = false;
+    // Whether the next word names a node counted by the current pattern
+    let mut node_follows = false;
+
+    let code = builtin_source(language)
+        .lines()
+        .map(|line| line.split(';').next().unwrap_or_default());
+    for line in code {
+        for token in TOKEN.find_iter(line).map(|token| token.as_str()) {
+            match token {
+                "(" | "[" => {
+                    node_follows = token == "(" && (depth == 0 || (depth == 1 && alternation));
+                    alternation |= depth == 0 && token == "[";
+                    depth += 1;
+                }
+                ")" | "]" => depth -= 1,
+                _ if depth == 0 => {
+                    if let Some(capture) = token.strip_prefix('@') {
+                        let captured = kinds.entry(capture).or_default();
+                        for kind in pattern.drain(..) {
+                            if !captured.contains(&kind) {
+                                captured.push(kind);
+                            }
+                        }
+                    }
+                    alternation = false;
+                }
+                _ => {
+                    if node_follows {
+                        pattern.push(token);
+                    }
+                    node_follows = false;
+                }
+            }
+        }
+    }
+
+    kinds
+}
+
+/// Counts the captures of user-supplied queries.
+///
+/// Each query applies to one language and is compiled for every grammar of
+/// that language, e.g. for both TypeScript and TSX.
+#[derive(Debug)]
+pub(crate) struct QueryCounter {
+    queries: Vec<(Grammar, Query)>,
+    /// The `LANG=PATH` specifications and query sources, for cache keys
+    fingerprint: String,
+}
+
+impl QueryCounter {
+    /// Reads and compiles queries given as `LANG=PATH`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(QueryCounter)` if every file could be read and compiled
+    /// * `Err(String)` naming the first specification that could not
+    pub(crate) fn new(specs: &[String]) -> Result<Self, String> {
+        let mut queries = Vec::new();
+        let mut fingerprint = String::new();
+
+        for spec in specs {
+            let (language, path) = spec
+                .split_once('=')
+                .and_then(|(name, path)| {
+                    Some((SupportedLanguage::from_config_name(name.trim())?, path))
+                })
+                .filter(|(_, path)| !path.is_empty())
+                .ok_or_else(|| {
+                    format!("Invalid query '{spec}': expected LANG=PATH, e.g. rust=macros.scm")
+                })?;
+            let path = PathBuf::from(path);
+            let source = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Cannot read query file '{}': {e}", path.display()))?;
+
+            for grammar in Grammar::ALL {
+                if grammar.language() != language {
+                    continue;
+                }
+                let query = Query::new(&grammar.get_language(), &source)
+                    .map_err(|e| format!("Invalid query file '{}': {e}", path.display()))?;
+                queries.push((grammar, query));
+            }
+            fingerprint.push_str(&format!("{spec}\n{source}\n"));
+        }
+
+        Ok(Self {
+            queries,
+            fingerprint,
+        })
+    }
+
+    /// Identifies the queries, so cached counts are only reused with the
+    /// same queries.
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Counts the captures of the queries for the tree's grammar, by name.
+    ///
+    /// Every capture of an applicable query is listed, with zero if it did not
+    /// match, so files of the same language report the same names.
+    pub(crate) fn count(
+        &self,
+        tree: &Tree,
+        source_code: &str,
+        grammar: Grammar,
+    ) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+
+        for (_, query) in self.queries.iter().filter(|(g, _)| *g == grammar) {
+            let names = query.capture_names();
+            for name in names.iter().filter(|name| !name.starts_with('_')) {
+                counts.entry((*name).to_string()).or_insert(0);
+            }
+
+            let mut cursor = QueryCursor::new();
+            let mut matches = cursor.matches(query, tree.root_node(), source_code.as_bytes());
+            while let Some(found) = matches.next() {
+                for capture in found.captures {
+                    if let Some(count) = counts.get_mut(names[capture.index as usize]) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{create_grammar_parser, parse_source};
+
+    fn parse(grammar: Grammar, source: &str) -> Tree {
+        let mut parser = create_grammar_parser(grammar).unwrap();
+        parse_source(&mut parser, source, "test").unwrap()
+    }
+
+    #[test]
+    fn test_builtin_queries_compile_for_every_grammar() {
+        for grammar in Grammar::ALL {
+            let query = DeclarationQuery::for_grammar(grammar);
+            assert!(!query.declarations.is_empty(), "{grammar:?}");
+        }
+    }
+
+    #[test]
+    fn test_declaration_query_finds_declarations() {
+        let source = "mod m { struct S; }\nfn a() {}\nfn b() { let c = || 1; }\n";
+        let tree = parse(Grammar::Primary(SupportedLanguage::Rust), source);
+
+        let query = DeclarationQuery::for_grammar(Grammar::Primary(SupportedLanguage::Rust));
+        let mut found = Vec::new();
+        query.for_each(&tree, source, |declaration, node| {
+            found.push((declaration, node.start_position().row))
+        });
+        found.sort_by_key(|(_, row)| *row);
+
+        assert_eq!(
+            found,
+            vec![
+                (Declaration::Module, 0),
+                (Declaration::Class, 0),
+                (Declaration::Function, 1),
+                (Declaration::Function, 2),
+                (Declaration::AnonymousFunction, 2),
+            ]
+        );
+
+        // Rust does not count closures as functions unless asked to
+        let mut counted = Vec::new();
+        query.for_each_counted(&tree, source, &CountPolicy::default(), |declaration, _| {
+            counted.push(declaration)
+        });
+        assert!(!counted.contains(&Declaration::AnonymousFunction));
+        assert_eq!(counted.len(), 4);
+    }
+
+    #[test]
+    fn test_declaration_kinds() {
+        let rust = declaration_kinds(SupportedLanguage::Rust);
+        assert_eq!(rust["function"], ["function_item"]);
+        assert_eq!(rust["class"], ["struct_item"]);
+        assert_eq!(rust["impl"], ["impl_item"]);
+        assert_eq!(rust["trait_impl"], ["impl_item"]);
+
+        // Only the outer node of patterns constraining their children
+        let go = declaration_kinds(SupportedLanguage::Go);
+        assert_eq!(
+            go["function"],
+            ["function_declaration", "method_declaration"]
+        );
+        assert_eq!(go["class"], ["type_spec"]);
+        assert_eq!(go["type_alias"], ["type_alias", "type_spec"]);
+        assert_eq!(go["generic"], ["function_declaration", "type_spec"]);
+
+        // Every capture name of every query is listed
+        for language in SupportedLanguage::ALL {
+            let kinds = declaration_kinds(language);
+            let query = DeclarationQuery::for_grammar(Grammar::Primary(language));
+            for name in query.query.capture_names() {
+                assert!(!kinds[name].is_empty(), "{language:?} @{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_counter_counts_named_captures() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("macros.scm");
+        std::fs::write(
+            &path,
+            "(macro_invocation macro: (identifier) @_name (#eq? @_name \"println\")) @println\n\
+             (macro_definition) @macro_rules\n",
+        )
+        .unwrap();
+        let counter = QueryCounter::new(&[format!("rust={}", path.display())]).unwrap();
+
+        let source = "fn main() { println!(\"a\"); println!(\"b\"); vec![1]; }\n";
+        let grammar = Grammar::Primary(SupportedLanguage::Rust);
+        let counts = counter.count(&parse(grammar, source), source, grammar);
+        assert_eq!(
+            counts,
+            BTreeMap::from([("macro_rules".to_string(), 0), ("println".to_string(), 2)])
+        );
+
+        // Queries for other languages do not apply
+        let grammar = Grammar::Primary(SupportedLanguage::Python);
+        assert!(
+            counter
+                .count(&parse(grammar, "print(1)\n"), "print(1)\n", grammar)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_query_counter_rejects_invalid_queries() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("bad.scm");
+        std::fs::write(&path, "(no_such_node) @x").unwrap();
+
+        let error = QueryCounter::new(&[format!("rust={}", path.display())]).unwrap_err();
+        assert!(error.starts_with("Invalid query file"), "{error}");
+
+        let error = QueryCounter::new(&["cobol=x.scm".to_string()]).unwrap_err();
+        assert!(error.contains("expected LANG=PATH"), "{error}");
+
+        let error = QueryCounter::new(&["rust=missing.scm".to_string()]).unwrap_err();
+        assert!(error.starts_with("Cannot read query file"), "{error}");
+    }
+}
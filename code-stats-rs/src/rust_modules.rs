@@ -0,0 +1,245 @@
+//! Attribution of Rust functions and types to their module paths.
+//!
+//! A Rust file's module follows from its place below the crate's `src`
+//! directory: `src/lib.rs` is the crate root, `src/foo.rs` and
+//! `src/foo/mod.rs` are `crate::foo`. Inline `mod` items nest further
+//! modules inside a file, so every function, struct, enum and trait is
+//! counted under the path of the innermost module around it. Files outside
+//! of `src`, such as integration tests and build scripts, are crate roots of
+//! their own.
+
+use crate::language::SupportedLanguage;
+use crate::visitor::NodeVisitor;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use tree_sitter::Node;
+
+/// Functions and types declared in one Rust module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ModuleStats {
+    /// The directory holding the crate's `src`, relative to the analyzed
+    /// directory; `None` for the crate at its root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crate_root: Option<PathBuf>,
+    /// The module path, e.g. `crate::parser::tests`
+    pub path: String,
+    /// Number of functions and methods
+    pub functions: usize,
+    /// Number of structs
+    pub structs: usize,
+    /// Number of enums
+    pub enums: usize,
+    /// Number of traits
+    pub traits: usize,
+}
+
+impl ModuleStats {
+    /// Creates a module without declarations.
+    fn new(crate_root: Option<PathBuf>, path: String) -> Self {
+        Self {
+            crate_root,
+            path,
+            functions: 0,
+            structs: 0,
+            enums: 0,
+            traits: 0,
+        }
+    }
+
+    /// Orders modules by crate, then by path.
+    pub(crate) fn key(&self) -> (Option<&Path>, &str) {
+        (self.crate_root.as_deref(), &self.path)
+    }
+
+    /// Adds the declarations of the same module in another file.
+    pub(crate) fn merge(&mut self, other: &ModuleStats) {
+        self.functions += other.functions;
+        self.structs += other.structs;
+        self.enums += other.enums;
+        self.traits += other.traits;
+    }
+}
+
+/// Counts the declarations of a Rust file per module while the statistics
+/// are counted. Other languages are ignored.
+#[derive(Debug)]
+pub(crate) struct ModuleCollector {
+    crate_root: Option<PathBuf>,
+    /// Module path of the file itself, starting with `crate`
+    file_module: Vec<String>,
+    modules: Vec<ModuleStats>,
+}
+
+impl ModuleCollector {
+    /// Creates a collector for the file at `relative`, a path relative to the
+    /// analyzed directory.
+    pub(crate) fn new(relative: &Path) -> Self {
+        let (crate_root, file_module) = file_module(relative);
+        Self {
+            crate_root,
+            file_module,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Returns the modules with declarations, sorted by path.
+    pub(crate) fn into_modules(mut self) -> Vec<ModuleStats> {
+        self.modules.sort_by(|a, b| a.path.cmp(&b.path));
+        self.modules
+    }
+}
+
+impl NodeVisitor for ModuleCollector {
+    fn visit(&mut self, language: SupportedLanguage, node: &Node<'_>, source: &str) {
+        if language != SupportedLanguage::Rust {
+            return;
+        }
+        let count: fn(&mut ModuleStats) = match node.kind() {
+            "function_item" => |module| module.functions += 1,
+            "struct_item" => |module| module.structs += 1,
+            "enum_item" => |module| module.enums += 1,
+            "trait_item" => |module| module.traits += 1,
+            _ => return,
+        };
+
+        let mut inline = Vec::new();
+        let mut ancestor = node.parent();
+        while let Some(parent) = ancestor {
+            if parent.kind() == "mod_item"
+                && let Some(name) = parent.child_by_field_name("name")
+            {
+                inline.push(&source[name.byte_range()]);
+            }
+            ancestor = parent.parent();
+        }
+        let path = self
+            .file_module
+            .iter()
+            .map(String::as_str)
+            .chain(inline.into_iter().rev())
+            .collect::<Vec<_>>()
+            .join("::");
+
+        let index = match self.modules.iter().position(|module| module.path == path) {
+            Some(index) => index,
+            None => {
+                self.modules
+                    .push(ModuleStats::new(self.crate_root.clone(), path));
+                self.modules.len() - 1
+            }
+        };
+        count(&mut self.modules[index]);
+    }
+}
+
+/// Derives the crate and module of a Rust file from its path.
+///
+/// # Returns
+///
+/// The directory holding the crate's `src` (`None` if that is the analyzed
+/// directory itself) and the module path of the file, starting with `crate`
+fn file_module(relative: &Path) -> (Option<PathBuf>, Vec<String>) {
+    let components: Vec<&str> = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    let Some((file, directories)) = components.split_last() else {
+        return (None, vec!["crate".to_string()]);
+    };
+
+    let (root, mut below_src) = match directories.iter().rposition(|name| *name == "src") {
+        Some(src) => (&directories[..src], &directories[src + 1..]),
+        // Integration tests, examples and build scripts are crates of their own
+        None => (directories, &[][..]),
+    };
+    let mut module = vec!["crate".to_string()];
+    if below_src.first() == Some(&"bin") {
+        // `src/bin/tool.rs` and `src/bin/tool/main.rs` are binary crate roots
+        if below_src.len() == 1 {
+            return (crate_root(root), module);
+        }
+        below_src = &below_src[2..];
+    }
+    module.extend(below_src.iter().map(|name| name.to_string()));
+
+    let stem = Path::new(file)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let is_root = below_src.is_empty() && matches!(stem, "lib" | "main");
+    if stem != "mod" && !is_root && directories.contains(&"src") {
+        module.push(stem.to_string());
+    }
+    (crate_root(root), module)
+}
+
+/// Returns the crate directory, or `None` for the analyzed directory itself.
+fn crate_root(components: &[&str]) -> Option<PathBuf> {
+    (!components.is_empty()).then(|| components.iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::count_source_with;
+
+    #[test]
+    fn test_file_module() {
+        for (path, root, module) in [
+            ("src/lib.rs", None, "crate"),
+            ("src/main.rs", None, "crate"),
+            ("src/parser.rs", None, "crate::parser"),
+            ("src/parser/mod.rs", None, "crate::parser"),
+            ("src/parser/lexer.rs", None, "crate::parser::lexer"),
+            ("src/bin/tool.rs", None, "crate"),
+            ("src/bin/tool/args.rs", None, "crate::args"),
+            ("crates/core/src/io.rs", Some("crates/core"), "crate::io"),
+            ("tests/cli.rs", Some("tests"), "crate"),
+            ("build.rs", None, "crate"),
+        ] {
+            let (crate_root, path_module) = file_module(Path::new(path));
+            assert_eq!(crate_root, root.map(PathBuf::from), "{path}");
+            assert_eq!(path_module.join("::"), module, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_declarations_are_attributed_to_inline_modules() {
+        let source = "\
+pub struct Parser;
+impl Parser {
+    fn parse(&self) {}
+}
+mod lexer {
+    enum Token {}
+    fn lex() {
+        fn helper() {}
+    }
+}
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parses() {}
+}
+";
+        let mut collector = ModuleCollector::new(Path::new("src/parser.rs"));
+        count_source_with(SupportedLanguage::Rust, source, &mut collector);
+
+        let modules: Vec<_> = collector
+            .into_modules()
+            .into_iter()
+            .map(|module| (module.path, module.functions, module.structs, module.enums))
+            .collect();
+        assert_eq!(
+            modules,
+            [
+                ("crate::parser".to_string(), 1, 1, 0),
+                ("crate::parser::lexer".to_string(), 2, 0, 1),
+                ("crate::parser::tests".to_string(), 1, 0, 0),
+            ]
+        );
+    }
+}
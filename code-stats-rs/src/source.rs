@@ -0,0 +1,323 @@
+//! Loading of source files, either into memory or through a memory map.
+//!
+//! Files that are not valid UTF-8 are transcoded: with the encoding given
+//! on the command line, or else with the one detected from their bytes
+//! (byte order marks take precedence in both cases). Valid UTF-8 is always
+//! read as is.
+
+use crate::error::{CodeStatsError, ErrorKind, Result};
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Default limit of `--max-file-size`: larger files are skipped unread.
+pub(crate) const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Lines at least this long (in bytes) are only found in minified or
+/// generated code, which is not worth parsing.
+pub(crate) const MINIFIED_LINE_LENGTH: usize = 5_000;
+
+/// Read buffers that one large file grew beyond this many bytes are released
+/// after use instead of being kept for the next file.
+const RETAINED_BUFFER_CAPACITY: usize = 1024 * 1024;
+
+/// The text of a source file, owned or borrowed from a memory map.
+///
+/// Both variants are validated UTF-8, so [`SourceText::as_str`] never copies.
+pub(crate) enum SourceText {
+    /// Contents read into a heap-allocated string, transcoded if necessary
+    Owned(String),
+    /// Contents mapped directly from the file
+    Mapped(Mmap),
+}
+
+impl SourceText {
+    /// Returns the source code as a string slice.
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            SourceText::Owned(text) => text,
+            // SAFETY: `read_source` validated the contents as UTF-8, and the
+            // file is assumed not to change while mapped (see `map_file`)
+            SourceText::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+
+    /// Hands the allocation of owned text back to `buffer`, so the next
+    /// [`read_source`] does not allocate again.
+    pub(crate) fn recycle(self, buffer: &mut Vec<u8>) {
+        if let SourceText::Owned(text) = self
+            && text.capacity() <= RETAINED_BUFFER_CAPACITY
+        {
+            *buffer = text.into_bytes();
+        }
+    }
+}
+
+/// Reads a source file, memory-mapping it when it is large enough.
+///
+/// Mapping falls back to a regular read whenever it is not possible, e.g. for
+/// empty files or file systems that do not support mmap, so `mmap_threshold`
+/// never turns a readable file into an error. Files that are not valid UTF-8
+/// are transcoded into memory (see [`decode_source`]).
+///
+/// Files that are read go into `buffer`, whose allocation becomes the
+/// returned text; [`SourceText::recycle`] returns it for the next file.
+///
+/// # Arguments
+///
+/// * `path` - Path to the source file
+/// * `mmap_threshold` - Size in bytes from which files are memory-mapped;
+///   `None` reads every file
+/// * `encoding` - Encoding of files that are not valid UTF-8; `None` detects it
+/// * `buffer` - Reusable read buffer; left empty afterwards
+///
+/// # Returns
+///
+/// * `Ok(SourceText)` - The file's contents
+/// * `Err(CodeStatsError::IoError)` if the file cannot be read
+/// * `Err(CodeStatsError::EncodingError)` if the file is not valid text in
+///   its encoding
+pub(crate) fn read_source(
+    path: &Path,
+    mmap_threshold: Option<u64>,
+    encoding: Option<&'static Encoding>,
+    buffer: &mut Vec<u8>,
+) -> Result<SourceText> {
+    let read_error = |e| CodeStatsError::io(path, e);
+    let mut file = File::open(path).map_err(read_error)?;
+
+    if let Some(threshold) = mmap_threshold
+        && let Some(map) = map_file(&file, threshold)
+    {
+        return match std::str::from_utf8(&map) {
+            Ok(_) => Ok(SourceText::Mapped(map)),
+            Err(_) => decode_source(path, map.to_vec(), encoding).map(SourceText::Owned),
+        };
+    }
+
+    buffer.clear();
+    file.read_to_end(buffer).map_err(read_error)?;
+    decode_source(path, std::mem::take(buffer), encoding).map(SourceText::Owned)
+}
+
+/// Converts the bytes of a source file to text.
+///
+/// Valid UTF-8 is taken as is. Other bytes are decoded with `encoding`, or
+/// with the encoding guessed from their content, unless they start with a
+/// UTF-16 byte order mark.
+///
+/// # Arguments
+///
+/// * `path` - Path of the file, for error messages
+/// * `bytes` - The file's contents
+/// * `encoding` - Encoding of files that are not valid UTF-8; `None` detects it
+///
+/// # Returns
+///
+/// * `Ok(String)` - The contents as UTF-8
+/// * `Err(CodeStatsError::EncodingError)` if the bytes are malformed in the
+///   chosen encoding
+pub(crate) fn decode_source(
+    path: &Path,
+    bytes: Vec<u8>,
+    encoding: Option<&'static Encoding>,
+) -> Result<String> {
+    let bytes = match String::from_utf8(bytes) {
+        Ok(text) => return Ok(text),
+        Err(e) => e.into_bytes(),
+    };
+
+    let encoding = encoding.unwrap_or_else(|| {
+        let mut detector = EncodingDetector::new();
+        detector.feed(&bytes, true);
+        detector.guess(None, true)
+    });
+    let (text, actual, had_errors) = encoding.decode(&bytes);
+    if had_errors {
+        return Err(CodeStatsError::EncodingError(format!(
+            "{}: not valid {}",
+            path.display(),
+            actual.name()
+        )));
+    }
+    Ok(text.into_owned())
+}
+
+/// Looks up an encoding by one of its WHATWG labels, e.g. `shift_jis` or
+/// `latin1`, for `--encoding`.
+pub(crate) fn parse_encoding(label: &str) -> std::result::Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| {
+        format!("unknown encoding `{label}` (expected e.g. utf-8, shift_jis, latin1)")
+    })
+}
+
+/// Checks whether source text looks like something worth parsing.
+///
+/// # Returns
+///
+/// * `Some((ErrorKind, String))` - Why the text should be skipped: it contains
+///   NUL bytes, or a line of at least [`MINIFIED_LINE_LENGTH`] bytes
+/// * `None` - The text looks like hand-written source code
+pub(crate) fn screen(text: &str) -> Option<(ErrorKind, String)> {
+    if text.contains('\0') {
+        return Some((ErrorKind::Binary, "binary content".to_string()));
+    }
+
+    let longest = text.lines().map(str::len).max().unwrap_or(0);
+    (longest >= MINIFIED_LINE_LENGTH).then(|| {
+        (
+            ErrorKind::LongLines,
+            format!("line of {longest} bytes, likely minified"),
+        )
+    })
+}
+
+/// Memory-maps a file of at least `threshold` bytes.
+///
+/// # Returns
+///
+/// * `Some(Mmap)` - The mapping
+/// * `None` - The file is empty or smaller than `threshold`, or mapping is not
+///   possible; the caller should read the file instead
+fn map_file(file: &File, threshold: u64) -> Option<Mmap> {
+    if file
+        .metadata()
+        .map_or(true, |meta| meta.len() == 0 || meta.len() < threshold)
+    {
+        return None;
+    }
+
+    // SAFETY: the map is only read, and source files are assumed not to be
+    // modified while they are being analyzed. A concurrent writer is the one
+    // case where mapping is unsound, which is why mmap is opt-in.
+    unsafe { Mmap::map(file) }.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_read_source_with_and_without_mmap() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("main.rs");
+        fs::write(&path, "fn main() {}\n").unwrap();
+
+        let owned = read_source(&path, None, None, &mut Vec::new()).unwrap();
+        assert!(matches!(owned, SourceText::Owned(_)));
+
+        let mapped = read_source(&path, Some(0), None, &mut Vec::new()).unwrap();
+        assert!(matches!(mapped, SourceText::Mapped(_)));
+        assert_eq!(mapped.as_str(), owned.as_str());
+    }
+
+    #[test]
+    fn test_read_source_maps_files_above_threshold() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("main.rs");
+        fs::write(&path, "fn main() {}\n").unwrap();
+
+        let small = read_source(&path, Some(100), None, &mut Vec::new()).unwrap();
+        assert!(matches!(small, SourceText::Owned(_)));
+
+        let large = read_source(&path, Some(13), None, &mut Vec::new()).unwrap();
+        assert!(matches!(large, SourceText::Mapped(_)));
+    }
+
+    #[test]
+    fn test_read_source_mmap_falls_back_for_empty_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("empty.rs");
+        fs::write(&path, "").unwrap();
+
+        let source = read_source(&path, Some(0), None, &mut Vec::new()).unwrap();
+        assert!(matches!(source, SourceText::Owned(_)));
+        assert_eq!(source.as_str(), "");
+    }
+
+    #[test]
+    fn test_read_source_reuses_recycled_buffer() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first.rs");
+        let second = temp_dir.path().join("second.rs");
+        fs::write(&first, "fn first() {}\n").unwrap();
+        fs::write(&second, "fn b() {}\n").unwrap();
+
+        let mut buffer = Vec::new();
+        let source = read_source(&first, None, None, &mut buffer).unwrap();
+        assert!(buffer.is_empty());
+        let allocation = source.as_str().as_ptr();
+        source.recycle(&mut buffer);
+
+        let source = read_source(&second, None, None, &mut buffer).unwrap();
+        assert_eq!(source.as_str(), "fn b() {}\n");
+        assert_eq!(source.as_str().as_ptr(), allocation);
+
+        // Text beyond the retained capacity is released rather than kept
+        let large = SourceText::Owned("x".repeat(RETAINED_BUFFER_CAPACITY + 1));
+        let mut buffer = Vec::new();
+        large.recycle(&mut buffer);
+        assert_eq!(buffer.capacity(), 0);
+    }
+
+    #[test]
+    fn test_read_source_transcodes_legacy_encodings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("legacy.py");
+        let text = "# データベース接続の設定を読み込む関数\ndef load():\n    return '設定'\n";
+        fs::write(&path, encoding_rs::SHIFT_JIS.encode(text).0).unwrap();
+
+        for mmap_threshold in [None, Some(0)] {
+            let source = read_source(&path, mmap_threshold, None, &mut Vec::new()).unwrap();
+            assert!(matches!(source, SourceText::Owned(_)));
+            assert_eq!(source.as_str(), text);
+        }
+
+        let text = "# caf\u{e9}\ndef f():\n    pass\n";
+        fs::write(&path, encoding_rs::WINDOWS_1252.encode(text).0).unwrap();
+        let latin1 = parse_encoding("latin1").unwrap();
+        assert_eq!(
+            read_source(&path, None, Some(latin1), &mut Vec::new())
+                .unwrap()
+                .as_str(),
+            text
+        );
+    }
+
+    #[test]
+    fn test_read_source_rejects_malformed_text() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("invalid.rs");
+        fs::write(&path, [b'f', 0xC3, 0x28]).unwrap();
+
+        for mmap_threshold in [None, Some(0)] {
+            assert!(matches!(
+                read_source(&path, mmap_threshold, Some(encoding_rs::UTF_8), &mut Vec::new()),
+                Err(CodeStatsError::EncodingError(msg)) if msg.contains("invalid.rs: not valid UTF-8")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_encoding() {
+        assert_eq!(parse_encoding("Shift_JIS"), Ok(encoding_rs::SHIFT_JIS));
+        assert_eq!(parse_encoding("utf8"), Ok(encoding_rs::UTF_8));
+        assert!(parse_encoding("klingon").is_err());
+    }
+
+    #[test]
+    fn test_screen() {
+        assert_eq!(screen("fn main() {}\n"), None);
+        assert_eq!(screen("\x7fELF\0\0").unwrap().0, ErrorKind::Binary);
+
+        let minified = format!("var a={};\n", "1+".repeat(MINIFIED_LINE_LENGTH));
+        let (kind, reason) = screen(&minified).unwrap();
+        assert_eq!(kind, ErrorKind::LongLines);
+        assert!(reason.contains("likely minified"));
+    }
+}
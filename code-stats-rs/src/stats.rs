@@ -1,10 +1,25 @@
 //! Data structures for collecting and aggregating code statistics.
 
+use crate::error::{CodeStatsError, Result};
+use crate::findings::{Finding, Severity, count_severity};
+use crate::functions::FunctionName;
+use crate::hierarchy::TypeRelations;
 use crate::language::SupportedLanguage;
-use crate::parser::CodeStats;
+use crate::metrics::ExtendedMetrics;
+use crate::obfuscation::Suspicion;
+use crate::origin::Origin;
+use crate::parser::{CodeStats, OversizedFunction};
+use crate::paths::relative_to;
+use crate::rust_modules::ModuleStats;
+use crate::secrets::SecretMatch;
+use crate::symbols::Symbol;
+use crate::test_code::{CodeGroup, InlineTests, TestSplit};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::Metadata;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Statistics for a single source code file.
 ///
@@ -19,6 +34,85 @@ pub(crate) struct FileStats {
     pub language: SupportedLanguage,
     /// The computed code statistics for this file
     pub stats: CodeStats,
+    /// String literals matching secret patterns (only populated when scanning is enabled)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<SecretMatch>,
+    /// The Go or Java package the file declares
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// Why the file looks obfuscated or machine-generated, if it does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspicion: Option<Suspicion>,
+    /// Named functions of the file (only populated when duplicates are searched)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub functions: Vec<FunctionName>,
+    /// Named declarations of the file (only populated for symbol lookups)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub symbols: Vec<Symbol>,
+    /// Halstead volume and maintainability index (only populated with extended metrics)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<ExtendedMetrics>,
+    /// Functions longer than the configured maximum, counted at that maximum
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub oversized_functions: Vec<OversizedFunction>,
+    /// Types declared in the file that extend or implement other types
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hierarchy: Vec<TypeRelations>,
+    /// Whether the file is vendored or generated rather than the project's own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+    /// Whether the whole file is test code, judged by its path
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_test: bool,
+    /// The `#[cfg(test)]` modules of a Rust file that is not a test file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inline_tests: Option<InlineTests>,
+    /// The functions and types of a Rust file per module
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rust_modules: Vec<ModuleStats>,
+    /// When the file was last modified, in seconds since the Unix epoch
+    /// (only populated with `--mtime`); unknown for files read from archives
+    /// and git revisions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    /// SHA-256 of the analyzed source in hex (only populated with `--hash`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl FileStats {
+    /// Creates file statistics with no additional findings attached.
+    pub(crate) fn new(path: PathBuf, language: SupportedLanguage, stats: CodeStats) -> Self {
+        Self {
+            path,
+            language,
+            stats,
+            secrets: Vec::new(),
+            functions: Vec::new(),
+            symbols: Vec::new(),
+            metrics: None,
+            package: None,
+            suspicion: None,
+            oversized_functions: Vec::new(),
+            hierarchy: Vec::new(),
+            origin: None,
+            is_test: false,
+            inline_tests: None,
+            rust_modules: Vec::new(),
+            modified: None,
+            sha256: None,
+        }
+    }
+
+    /// Records the modification time of the file on disk.
+    pub(crate) fn with_modification_time(mut self, metadata: &Metadata) -> Self {
+        self.modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_secs());
+        self
+    }
 }
 
 /// Aggregated statistics for a directory containing multiple source files.
@@ -32,17 +126,151 @@ pub(crate) struct FileStats {
 /// - `files`: Individual statistics for each analyzed file
 /// - `total_by_language`: Aggregated statistics grouped by programming language
 /// - `total_stats`: Overall totals across all files and languages
+/// - `findings`: Errors, warnings and skipped files noticed during the analysis
+/// - `unsupported_files`: Number of files skipped because their language is not supported
+/// - `rust_modules`: Functions and types of every Rust module
+/// - `excluded_files`: Suspicious files analyzed but left out of the totals
+/// - `set_aside_files`: Vendored and generated files analyzed but left out of the totals
+/// - `interrupted`: Whether the analysis was stopped before visiting every file
+/// - `meta`: How and when a saved report was produced
 ///
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct DirectoryStats {
+    /// Describes how the report was produced; the command line sets it for
+    /// every directory analysis, see also [`DirectoryStats::to_writer`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ReportMeta>,
     /// Individual statistics for each analyzed file
     pub files: Vec<FileStats>,
     /// Statistics aggregated by programming language
-    pub total_by_language: HashMap<SupportedLanguage, LanguageStats>,
+    pub total_by_language: BTreeMap<SupportedLanguage, LanguageStats>,
     /// Overall totals across all files and languages
     pub total_stats: CodeStats,
+    /// Issues noticed during the analysis, in traversal order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    /// Number of files skipped because their language is not supported;
+    /// counted rather than listed, since most trees hold many of them
+    #[serde(default)]
+    pub unsupported_files: usize,
+    /// Functions and types of the Rust modules of all files, sorted by
+    /// crate and module path
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rust_modules: Vec<ModuleStats>,
+    /// Files flagged as possibly obfuscated and kept out of every total
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_files: Vec<FileStats>,
+    /// Vendored and generated files kept out of every total
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub set_aside_files: Vec<FileStats>,
+    /// Set when the analysis was interrupted, so the statistics are partial
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+    /// Packages already counted in `module_count`, see [`DirectoryStats::add_file`]
+    #[serde(skip)]
+    packages: HashSet<PackageKey>,
+    /// Whether files are dropped after merging, see [`DirectoryStats::aggregate_only`]
+    #[serde(skip)]
+    aggregate_only: bool,
+    /// What the summary needs of the dropped files
+    #[serde(skip)]
+    dropped: DroppedFiles,
+}
+
+/// Sizes of the files an aggregate-only analysis did not keep, for the
+/// summary sections computed from single files.
+#[derive(Debug, Default)]
+struct DroppedFiles {
+    /// Production and test code of the dropped files
+    test_split: TestSplit,
+    /// Dropped vendored files
+    vendored: CodeGroup,
+    /// Dropped generated files
+    generated: CodeGroup,
+}
+
+impl DroppedFiles {
+    /// Records the sizes of a file before it is dropped.
+    fn add(&mut self, file: &FileStats) {
+        self.test_split.add(file);
+        match file.origin {
+            Some(Origin::Vendored) => self.vendored.add(file),
+            Some(Origin::Generated) => self.generated.add(file),
+            None => {}
+        }
+    }
+}
+
+/// Version of the report layout, written as `meta.schema_version`.
+///
+/// Raised whenever a change would make older versions of the tool, or other
+/// consumers, misread a report: a field is removed or renamed, or changes its
+/// type or meaning. Adding fields, and new values of open-ended strings such
+/// as languages or finding kinds, does not count, so consumers must ignore
+/// what they do not know. [`REPORT_SCHEMA`] describes the current version.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of the JSON report, printed by `--schema`.
+pub(crate) const REPORT_SCHEMA: &str = include_str!("../schema/report.schema.json");
+
+/// How and when a report was produced, so that it describes itself when
+/// archived, e.g. for audits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ReportMeta {
+    /// Layout version of the report, see [`SCHEMA_VERSION`]
+    pub schema_version: u32,
+    /// Version of the tool that wrote the report
+    pub tool_version: String,
+    /// When the report was written, in seconds since the Unix epoch; only
+    /// recorded with `SOURCE_DATE_EPOCH` or `--timestamp`, so reports of
+    /// identical sources are identical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    /// The analyzed directory or archive: `.` like the file paths, or
+    /// relative to `--relative-to` when that is an ancestor
+    pub root: PathBuf,
+    /// The options the analysis ran with, as given on the command line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+impl ReportMeta {
+    /// Describes a report of `root` written by this version of the tool.
+    ///
+    /// The time is only recorded when `SOURCE_DATE_EPOCH` is set, as
+    /// reproducible builds do; see [`with_current_time`](Self::with_current_time).
+    pub(crate) fn new(root: PathBuf, options: Vec<String>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: source_date(std::env::var("SOURCE_DATE_EPOCH").ok()),
+            root,
+            options,
+        }
+    }
+
+    /// Records the current time, unless `SOURCE_DATE_EPOCH` set the time.
+    pub(crate) fn with_current_time(mut self) -> Self {
+        self.timestamp = self.timestamp.or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs())
+        });
+        self
+    }
 }
 
+/// Returns the value of `SOURCE_DATE_EPOCH` in seconds since the Unix epoch,
+/// if it is a number.
+fn source_date(source_date_epoch: Option<String>) -> Option<u64> {
+    source_date_epoch.and_then(|epoch| epoch.trim().parse().ok())
+}
+
+/// Identifies a package across files: Go packages are scoped to a directory,
+/// Java packages are global.
+type PackageKey = (SupportedLanguage, Option<PathBuf>, String);
+
 /// Statistics aggregated for a specific programming language.
 ///
 /// This structure holds the accumulated statistics for all files of a particular
@@ -52,16 +280,78 @@ pub(crate) struct DirectoryStats {
 ///
 /// - `file_count`: Number of files analyzed for this language
 /// - `function_count`: Total number of functions found across all files
+/// - `documented_function_count`: How many of those functions are documented
+/// - `public_function_count`, `private_function_count`, `public_type_count`,
+///   `private_type_count`: The API surface, for languages with visibility
 /// - `class_struct_count`: Total number of classes/structs found across all files
+/// - `interface_count`, `enum_count`, `type_alias_count`: Totals of the other
+///   type declarations, counted separately from classes/structs
+/// - `module_count`: Number of distinct modules/namespaces/packages
+/// - `impl_count`, `trait_impl_count`, `impl_method_count`: Rust impl blocks
+///   without and with a trait, and the functions declared in them
+/// - `generic_count`, `type_parameter_count`: Go generic functions and types,
+///   and their type parameters
+/// - `async_function_count`, `decorated_function_count`, `lambda_count`:
+///   Python async and decorated functions, and Python and Java lambdas
+/// - `component_count`: React components in JavaScript and TypeScript
+/// - `total_lines`, `code_lines`, `comment_lines`, `blank_lines`: Line counts across all files
+/// - `byte_count`: Combined size of all files
 ///
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct LanguageStats {
     /// Number of files analyzed for this programming language
     pub file_count: usize,
     /// Total number of functions found across all files of this language
     pub function_count: usize,
+    /// Number of documented functions across all files of this language
+    pub documented_function_count: usize,
+    /// Number of public functions across all files of this language
+    pub public_function_count: usize,
+    /// Number of private functions across all files of this language
+    pub private_function_count: usize,
+    /// Number of public type declarations across all files of this language
+    pub public_type_count: usize,
+    /// Number of private type declarations across all files of this language
+    pub private_type_count: usize,
     /// Total number of classes/structs found across all files of this language
     pub class_struct_count: usize,
+    /// Total number of interfaces/traits found across all files of this language
+    pub interface_count: usize,
+    /// Total number of enums found across all files of this language
+    pub enum_count: usize,
+    /// Total number of type aliases found across all files of this language
+    pub type_alias_count: usize,
+    /// Number of distinct modules, namespaces and packages of this language
+    pub module_count: usize,
+    /// Number of inherent impl blocks across all files of this language
+    pub impl_count: usize,
+    /// Number of trait impl blocks across all files of this language
+    pub trait_impl_count: usize,
+    /// Number of functions in impl blocks across all files of this language
+    pub impl_method_count: usize,
+    /// Number of generic functions and types across all files of this language
+    pub generic_count: usize,
+    /// Number of type parameters across all files of this language
+    pub type_parameter_count: usize,
+    /// Number of async functions across all files of this language
+    pub async_function_count: usize,
+    /// Number of decorated functions across all files of this language
+    pub decorated_function_count: usize,
+    /// Number of lambdas across all files of this language
+    pub lambda_count: usize,
+    /// Number of React components across all files of this language
+    pub component_count: usize,
+    /// Total number of lines across all files of this language
+    pub total_lines: usize,
+    /// Number of code lines across all files of this language
+    pub code_lines: usize,
+    /// Number of comment-only lines across all files of this language
+    pub comment_lines: usize,
+    /// Number of blank lines across all files of this language
+    pub blank_lines: usize,
+    /// Combined size in bytes of all files of this language
+    pub byte_count: usize,
 }
 
 impl DirectoryStats {
@@ -70,19 +360,46 @@ impl DirectoryStats {
         Self::default()
     }
 
+    /// Creates empty statistics that only keep the totals of added files.
+    ///
+    /// Files are merged into the totals and then dropped, so memory stays
+    /// flat however many files are analyzed. Files the summary lists by name
+    /// (with secrets, oversized functions or suspicious content) are kept,
+    /// and the test/production split and vendored/generated totals stay exact.
+    pub(crate) fn aggregate_only() -> Self {
+        Self {
+            aggregate_only: true,
+            ..Self::default()
+        }
+    }
+
     /// Adds a file's statistics to the directory aggregation.
     ///
     /// This method updates both the overall totals and the language-specific
     /// statistics. It increments file counts, function counts, and class/struct
     /// counts appropriately.
     ///
+    /// Every Go or Java file repeats its package declaration, so a package
+    /// only adds to `module_count` for the first file that declares it.
+    ///
     /// # Parameters
     ///
     /// * `file_stats` - The statistics for the file to be added to the aggregation
     pub(crate) fn add_file(&mut self, file_stats: FileStats) {
+        let mut counted = file_stats.stats.clone();
+        if let Some(package) = &file_stats.package {
+            let scope = match file_stats.language {
+                SupportedLanguage::Go => file_stats.path.parent().map(Path::to_path_buf),
+                _ => None,
+            };
+            let key = (file_stats.language, scope, package.clone());
+            if !self.packages.insert(key) {
+                counted.module_count = counted.module_count.saturating_sub(1);
+            }
+        }
+
         // Update total stats
-        self.total_stats.function_count += file_stats.stats.function_count;
-        self.total_stats.class_struct_count += file_stats.stats.class_struct_count;
+        self.total_stats.merge(&counted);
 
         // Update language-specific stats
         let lang_stats = self
@@ -92,15 +409,234 @@ impl DirectoryStats {
 
         lang_stats.file_count += 1;
         lang_stats.function_count += file_stats.stats.function_count;
+        lang_stats.documented_function_count += file_stats.stats.documented_function_count;
+        lang_stats.public_function_count += file_stats.stats.public_function_count;
+        lang_stats.private_function_count += file_stats.stats.private_function_count;
+        lang_stats.public_type_count += file_stats.stats.public_type_count;
+        lang_stats.private_type_count += file_stats.stats.private_type_count;
         lang_stats.class_struct_count += file_stats.stats.class_struct_count;
+        lang_stats.interface_count += file_stats.stats.interface_count;
+        lang_stats.enum_count += file_stats.stats.enum_count;
+        lang_stats.type_alias_count += file_stats.stats.type_alias_count;
+        lang_stats.module_count += counted.module_count;
+        lang_stats.impl_count += file_stats.stats.impl_count;
+        lang_stats.trait_impl_count += file_stats.stats.trait_impl_count;
+        lang_stats.impl_method_count += file_stats.stats.impl_method_count;
+        lang_stats.generic_count += file_stats.stats.generic_count;
+        lang_stats.type_parameter_count += file_stats.stats.type_parameter_count;
+        lang_stats.async_function_count += file_stats.stats.async_function_count;
+        lang_stats.decorated_function_count += file_stats.stats.decorated_function_count;
+        lang_stats.lambda_count += file_stats.stats.lambda_count;
+        lang_stats.component_count += file_stats.stats.component_count;
+        lang_stats.total_lines += file_stats.stats.total_lines;
+        lang_stats.code_lines += file_stats.stats.code_lines;
+        lang_stats.comment_lines += file_stats.stats.comment_lines;
+        lang_stats.blank_lines += file_stats.stats.blank_lines;
+        lang_stats.byte_count += file_stats.stats.byte_count;
+
+        // A module may be spread over files, e.g. `foo.rs` and its inline modules
+        for module in &file_stats.rust_modules {
+            match self
+                .rust_modules
+                .binary_search_by(|known| known.key().cmp(&module.key()))
+            {
+                Ok(index) => self.rust_modules[index].merge(module),
+                Err(index) => self.rust_modules.insert(index, module.clone()),
+            }
+        }
 
-        // Add file to list
-        self.files.push(file_stats);
+        let listed = !file_stats.secrets.is_empty()
+            || !file_stats.oversized_functions.is_empty()
+            || file_stats.suspicion.is_some();
+        if self.aggregate_only && !listed {
+            self.dropped.add(&file_stats);
+        } else {
+            self.files.push(file_stats);
+        }
     }
 
     /// Returns the total number of files that have been analyzed.
     pub(crate) fn total_files(&self) -> usize {
-        self.files.len()
+        self.total_by_language
+            .values()
+            .map(|lang_stats| lang_stats.file_count)
+            .sum()
+    }
+
+    /// Returns the number and size of the counted files of an origin.
+    pub(crate) fn origin_totals(&self, origin: Origin) -> CodeGroup {
+        let mut totals = match origin {
+            Origin::Vendored => self.dropped.vendored,
+            Origin::Generated => self.dropped.generated,
+        };
+        for file in self.files.iter().filter(|file| file.origin == Some(origin)) {
+            totals.add(file);
+        }
+        totals
+    }
+
+    /// Returns the number of files or directory entries that could not be analyzed.
+    pub(crate) fn error_count(&self) -> usize {
+        count_severity(&self.findings, Severity::Error)
+    }
+
+    /// Splits the totals into production and test code.
+    ///
+    /// Test files count entirely as test code; the inline test modules of
+    /// other files move their functions and code lines to the test group.
+    pub(crate) fn test_split(&self) -> TestSplit {
+        let mut split = self.dropped.test_split;
+        for file in &self.files {
+            split.add(file);
+        }
+        split
+    }
+
+    /// Returns the statistics of the files and findings below `path`, a
+    /// directory or file, e.g. to hold part of a tree to a rule.
+    ///
+    /// Paths are compared as recorded, by whole components.
+    pub(crate) fn below(&self, path: &Path) -> DirectoryStats {
+        let mut below = DirectoryStats::new();
+        for file in self.files.iter().filter(|file| file.path.starts_with(path)) {
+            below.add_file(file.clone());
+        }
+        below.findings = self
+            .findings
+            .iter()
+            .filter(|finding| finding.path.as_deref().is_some_and(|p| p.starts_with(path)))
+            .cloned()
+            .collect();
+        below
+    }
+
+    /// Rewrites the paths of files and findings relative to `root`, so
+    /// reports do not depend on where the analyzed tree was checked out.
+    ///
+    /// Paths outside of `root` are left as they are.
+    pub(crate) fn make_paths_relative(&mut self, root: &Path) {
+        let relativize = |path: &mut PathBuf| {
+            if let Some(relative) = relative_to(path, root) {
+                *path = relative;
+            }
+        };
+
+        self.files
+            .iter_mut()
+            .chain(&mut self.excluded_files)
+            .chain(&mut self.set_aside_files)
+            .for_each(|file| relativize(&mut file.path));
+        self.findings
+            .iter_mut()
+            .filter_map(|finding| finding.path.as_mut())
+            .for_each(relativize);
+    }
+
+    /// Saves the statistics as a JSON snapshot.
+    ///
+    /// Set [`meta`](Self::meta) beforehand so the snapshot records the tool
+    /// version, time, root and options it was produced with.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once the snapshot has been written
+    /// * `Err(CodeStatsError::IoError)` if writing fails
+    pub(crate) fn to_writer(&self, writer: impl Write) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+            .map_err(|e| CodeStatsError::from(std::io::Error::from(e)))
+    }
+
+    /// Loads a snapshot saved with [`to_writer`](Self::to_writer) or
+    /// `--format json`.
+    ///
+    /// Reports without metadata, written by versions of the tool before it
+    /// was recorded, are accepted.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DirectoryStats)` with the files, totals and metadata of the report
+    /// * `Err(CodeStatsError::InvalidReport)` if the text is not a directory JSON
+    ///   report, or one of a newer schema version than this tool understands
+    pub(crate) fn from_reader(reader: impl Read) -> Result<Self> {
+        let stats: Self = serde_json::from_reader(reader)
+            .map_err(|e| CodeStatsError::InvalidReport(e.to_string()))?;
+        if let Some(meta) = &stats.meta
+            && meta.schema_version > SCHEMA_VERSION
+        {
+            return Err(CodeStatsError::InvalidReport(format!(
+                "schema version {} was written by code-stats-rs {}, which is newer than \
+                 this version (schema version {SCHEMA_VERSION})",
+                meta.schema_version, meta.tool_version
+            )));
+        }
+        Ok(stats)
+    }
+}
+
+impl LanguageStats {
+    /// Returns the language's share of all analyzed files, in percent.
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The totals of the analysis the language belongs to
+    pub(crate) fn file_share(&self, total: &DirectoryStats) -> f64 {
+        percentage(self.file_count, total.total_files())
+    }
+
+    /// Returns the language's share of all functions, in percent.
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The totals of the analysis the language belongs to
+    pub(crate) fn function_share(&self, total: &DirectoryStats) -> f64 {
+        percentage(self.function_count, total.total_stats.function_count)
+    }
+
+    /// Returns the language's share of all lines, in percent.
+    ///
+    /// Unlike [`file_share`](Self::file_share), this weighs languages by how
+    /// much code they hold rather than by how many files it is split into.
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The totals of the analysis the language belongs to
+    pub(crate) fn line_share(&self, total: &DirectoryStats) -> f64 {
+        percentage(self.total_lines, total.total_stats.total_lines)
+    }
+
+    /// Returns the language's share of all bytes of source, in percent.
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The totals of the analysis the language belongs to
+    pub(crate) fn byte_share(&self, total: &DirectoryStats) -> f64 {
+        percentage(self.byte_count, total.total_stats.byte_count)
+    }
+
+    /// Returns the share of the language's functions that are documented, in percent.
+    pub(crate) fn documentation_coverage(&self) -> f64 {
+        percentage(self.documented_function_count, self.function_count)
+    }
+
+    /// Returns the number of interfaces/traits, enums and type aliases.
+    pub(crate) fn type_definition_count(&self) -> usize {
+        self.interface_count + self.enum_count + self.type_alias_count
+    }
+
+    /// Returns the mean number of impl block methods per struct, or `None`
+    /// without structs.
+    pub(crate) fn methods_per_struct(&self) -> Option<f64> {
+        (self.class_struct_count > 0)
+            .then(|| self.impl_method_count as f64 / self.class_struct_count as f64)
+    }
+}
+
+/// Returns `part` as a percentage of `whole`, or 0 when the whole is empty.
+pub(crate) fn percentage(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / whole as f64
     }
 }
 
@@ -111,14 +647,15 @@ mod tests {
 
     #[test]
     fn test_file_stats_creation() {
-        let file_stats = FileStats {
-            path: PathBuf::from("test.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
+        let file_stats = FileStats::new(
+            PathBuf::from("test.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
                 function_count: 5,
                 class_struct_count: 2,
+                ..CodeStats::default()
             },
-        };
+        );
 
         assert_eq!(file_stats.path, PathBuf::from("test.rs"));
         assert_eq!(file_stats.language, SupportedLanguage::Rust);
@@ -126,6 +663,136 @@ mod tests {
         assert_eq!(file_stats.stats.class_struct_count, 2);
     }
 
+    #[test]
+    fn test_language_shares() {
+        let mut dir_stats = DirectoryStats::new();
+        for (path, language, functions) in [
+            ("a.rs", SupportedLanguage::Rust, 3),
+            ("b.rs", SupportedLanguage::Rust, 0),
+            ("c.py", SupportedLanguage::Python, 1),
+        ] {
+            dir_stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                language,
+                CodeStats {
+                    function_count: functions,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+
+        let rust = &dir_stats.total_by_language[&SupportedLanguage::Rust];
+        assert!((rust.file_share(&dir_stats) - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(rust.function_share(&dir_stats), 75.0);
+
+        // Without any functions, no language has a share of them
+        let empty = LanguageStats::default();
+        assert_eq!(empty.function_share(&DirectoryStats::new()), 0.0);
+    }
+
+    #[test]
+    fn test_test_split() {
+        use crate::test_code::InlineTests;
+
+        let mut dir_stats = DirectoryStats::new();
+        let code = |functions, code_lines| CodeStats {
+            function_count: functions,
+            code_lines,
+            ..CodeStats::default()
+        };
+        let mut lib = FileStats::new(
+            PathBuf::from("src/lib.rs"),
+            SupportedLanguage::Rust,
+            code(5, 100),
+        );
+        lib.inline_tests = Some(InlineTests {
+            function_count: 2,
+            code_lines: 30,
+        });
+        let mut test = FileStats::new(
+            PathBuf::from("tests/cli.rs"),
+            SupportedLanguage::Rust,
+            code(4, 50),
+        );
+        test.is_test = true;
+        dir_stats.add_file(lib);
+        dir_stats.add_file(test);
+
+        let split = dir_stats.test_split();
+        assert_eq!(
+            (
+                split.production.files,
+                split.production.function_count,
+                split.production.code_lines
+            ),
+            (1, 3, 70)
+        );
+        assert_eq!(
+            (
+                split.test.files,
+                split.test.function_count,
+                split.test.code_lines
+            ),
+            (1, 6, 80)
+        );
+        assert!((split.test_line_share() - 80.0 / 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_aggregate_only_keeps_totals() {
+        use crate::parser::OversizedFunction;
+
+        let code = |functions, code_lines| CodeStats {
+            function_count: functions,
+            code_lines,
+            ..CodeStats::default()
+        };
+        let mut test = FileStats::new(
+            PathBuf::from("tests/cli.rs"),
+            SupportedLanguage::Rust,
+            code(4, 50),
+        );
+        test.is_test = true;
+        let mut generated = FileStats::new(
+            PathBuf::from("src/schema.rs"),
+            SupportedLanguage::Rust,
+            code(1, 20),
+        );
+        generated.origin = Some(Origin::Generated);
+        let mut long = FileStats::new(
+            PathBuf::from("src/long.py"),
+            SupportedLanguage::Python,
+            code(1, 300),
+        );
+        long.oversized_functions.push(OversizedFunction {
+            line: 1,
+            lines: 300,
+            counted_lines: 200,
+        });
+
+        let mut full = DirectoryStats::new();
+        let mut aggregated = DirectoryStats::aggregate_only();
+        for file in [test, generated, long] {
+            full.add_file(file.clone());
+            aggregated.add_file(file);
+        }
+
+        // Only the file listed for its oversized function is kept
+        assert_eq!(aggregated.files.len(), 1);
+        assert_eq!(aggregated.files[0].path, PathBuf::from("src/long.py"));
+        assert_eq!(aggregated.total_files(), 3);
+        assert_eq!(
+            aggregated.total_stats.code_lines,
+            full.total_stats.code_lines
+        );
+        assert_eq!(aggregated.test_split(), full.test_split());
+        assert_eq!(
+            aggregated.origin_totals(Origin::Generated),
+            full.origin_totals(Origin::Generated)
+        );
+        assert_eq!(aggregated.origin_totals(Origin::Generated).code_lines, 20);
+    }
+
     #[test]
     fn test_directory_stats_new() {
         let dir_stats = DirectoryStats::new();
@@ -141,14 +808,15 @@ mod tests {
     fn test_directory_stats_add_single_file() {
         let mut dir_stats = DirectoryStats::new();
 
-        let file_stats = FileStats {
-            path: PathBuf::from("test.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
+        let file_stats = FileStats::new(
+            PathBuf::from("test.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
                 function_count: 3,
                 class_struct_count: 1,
+                ..CodeStats::default()
             },
-        };
+        );
 
         dir_stats.add_file(file_stats);
 
@@ -167,24 +835,26 @@ mod tests {
         let mut dir_stats = DirectoryStats::new();
 
         // Add first Rust file
-        dir_stats.add_file(FileStats {
-            path: PathBuf::from("file1.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
+        dir_stats.add_file(FileStats::new(
+            PathBuf::from("file1.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
                 function_count: 2,
                 class_struct_count: 1,
+                ..CodeStats::default()
             },
-        });
+        ));
 
         // Add second Rust file
-        dir_stats.add_file(FileStats {
-            path: PathBuf::from("file2.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
+        dir_stats.add_file(FileStats::new(
+            PathBuf::from("file2.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
                 function_count: 3,
                 class_struct_count: 2,
+                ..CodeStats::default()
             },
-        });
+        ));
 
         assert_eq!(dir_stats.total_files(), 2);
         assert_eq!(dir_stats.total_stats.function_count, 5);
@@ -201,34 +871,37 @@ mod tests {
         let mut dir_stats = DirectoryStats::new();
 
         // Add Rust file
-        dir_stats.add_file(FileStats {
-            path: PathBuf::from("main.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
+        dir_stats.add_file(FileStats::new(
+            PathBuf::from("main.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
                 function_count: 4,
                 class_struct_count: 2,
+                ..CodeStats::default()
             },
-        });
+        ));
 
         // Add Python file
-        dir_stats.add_file(FileStats {
-            path: PathBuf::from("script.py"),
-            language: SupportedLanguage::Python,
-            stats: CodeStats {
+        dir_stats.add_file(FileStats::new(
+            PathBuf::from("script.py"),
+            SupportedLanguage::Python,
+            CodeStats {
                 function_count: 3,
                 class_struct_count: 1,
+                ..CodeStats::default()
             },
-        });
+        ));
 
         // Add Go file
-        dir_stats.add_file(FileStats {
-            path: PathBuf::from("main.go"),
-            language: SupportedLanguage::Go,
-            stats: CodeStats {
+        dir_stats.add_file(FileStats::new(
+            PathBuf::from("main.go"),
+            SupportedLanguage::Go,
+            CodeStats {
                 function_count: 2,
                 class_struct_count: 1,
+                ..CodeStats::default()
             },
-        });
+        ));
 
         assert_eq!(dir_stats.total_files(), 3);
         assert_eq!(dir_stats.total_stats.function_count, 9);
@@ -249,6 +922,42 @@ mod tests {
         assert_eq!(go_stats.function_count, 2);
     }
 
+    #[test]
+    fn test_directory_stats_counts_each_package_once() {
+        let mut dir_stats = DirectoryStats::new();
+        for (path, language, package) in [
+            ("a/x.go", SupportedLanguage::Go, "util"),
+            ("a/y.go", SupportedLanguage::Go, "util"),
+            ("b/z.go", SupportedLanguage::Go, "util"),
+            ("p/A.java", SupportedLanguage::Java, "com.example"),
+            ("q/B.java", SupportedLanguage::Java, "com.example"),
+        ] {
+            let mut file_stats = FileStats::new(
+                PathBuf::from(path),
+                language,
+                CodeStats {
+                    module_count: 1,
+                    ..CodeStats::default()
+                },
+            );
+            file_stats.package = Some(package.to_string());
+            dir_stats.add_file(file_stats);
+        }
+
+        // Go packages are per directory, Java packages span directories
+        assert_eq!(
+            dir_stats.total_by_language[&SupportedLanguage::Go].module_count,
+            2
+        );
+        assert_eq!(
+            dir_stats.total_by_language[&SupportedLanguage::Java].module_count,
+            1
+        );
+        assert_eq!(dir_stats.total_stats.module_count, 3);
+        // Per-file stats still report the declaration
+        assert!(dir_stats.files.iter().all(|f| f.stats.module_count == 1));
+    }
+
     #[test]
     fn test_language_stats_default() {
         let lang_stats = LanguageStats::default();
@@ -260,14 +969,15 @@ mod tests {
 
     #[test]
     fn test_serialization_roundtrip() {
-        let file_stats = FileStats {
-            path: PathBuf::from("test.rs"),
-            language: SupportedLanguage::Rust,
-            stats: CodeStats {
+        let file_stats = FileStats::new(
+            PathBuf::from("test.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
                 function_count: 10,
                 class_struct_count: 5,
+                ..CodeStats::default()
             },
-        };
+        );
 
         // Serialize to JSON
         let json = serde_json::to_string(&file_stats).unwrap();
@@ -286,4 +996,109 @@ mod tests {
             file_stats.stats.class_struct_count
         );
     }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut stats = DirectoryStats::new();
+        stats.add_file(FileStats::new(
+            PathBuf::from("src/main.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 3,
+                ..CodeStats::default()
+            },
+        ));
+        stats.meta = Some(ReportMeta::new(
+            PathBuf::from("."),
+            vec!["--detail".to_string()],
+        ));
+
+        let mut snapshot = Vec::new();
+        stats.to_writer(&mut snapshot).unwrap();
+        let loaded = DirectoryStats::from_reader(snapshot.as_slice()).unwrap();
+        assert_eq!(loaded.meta, stats.meta);
+        assert_eq!(loaded.files[0].stats.function_count, 3);
+        assert_eq!(
+            loaded.total_by_language[&SupportedLanguage::Rust].file_count,
+            1
+        );
+
+        let meta = loaded.meta.unwrap();
+        assert_eq!(meta.schema_version, SCHEMA_VERSION);
+        assert_eq!(meta.tool_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            meta.timestamp.is_some(),
+            std::env::var_os("SOURCE_DATE_EPOCH").is_some()
+        );
+        assert!(meta.with_current_time().timestamp.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_report_time_honours_source_date_epoch() {
+        assert_eq!(
+            source_date(Some("1700000000".to_string())),
+            Some(1_700_000_000)
+        );
+        assert_eq!(source_date(Some("yesterday".to_string())), None);
+        assert_eq!(source_date(None), None);
+
+        let meta = ReportMeta {
+            timestamp: Some(1_700_000_000),
+            ..ReportMeta::new(PathBuf::from("."), Vec::new())
+        };
+        assert_eq!(meta.with_current_time().timestamp, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn test_snapshot_from_newer_schema_is_rejected() {
+        let text = format!(
+            r#"{{"meta": {{"schema_version": {}, "tool_version": "99.0.0", "timestamp": 0,
+                         "root": "."}},
+                "files": [], "total_by_language": {{}}, "total_stats": {{}}}}"#,
+            SCHEMA_VERSION + 1
+        );
+        let Err(CodeStatsError::InvalidReport(message)) =
+            DirectoryStats::from_reader(text.as_bytes())
+        else {
+            panic!("Expected the snapshot to be rejected");
+        };
+        assert!(message.contains("code-stats-rs 99.0.0"));
+    }
+
+    #[test]
+    fn test_report_schema_describes_the_current_version() {
+        let schema: serde_json::Value = serde_json::from_str(REPORT_SCHEMA).unwrap();
+        let version = &schema["$defs"]["ReportMeta"]["properties"]["schema_version"]["const"];
+        assert_eq!(*version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_serialization_is_deterministic() {
+        let files = [
+            ("b.py", SupportedLanguage::Python),
+            ("a.rs", SupportedLanguage::Rust),
+            ("c.go", SupportedLanguage::Go),
+            ("d.ts", SupportedLanguage::TypeScript),
+        ];
+        let json = |order: &[usize]| {
+            let mut stats = DirectoryStats::new();
+            for &index in order {
+                let (path, language) = files[index];
+                stats.add_file(FileStats::new(
+                    PathBuf::from(path),
+                    language,
+                    CodeStats::default(),
+                ));
+            }
+            serde_json::to_string(&stats.total_by_language).unwrap()
+        };
+
+        let forward = json(&[0, 1, 2, 3]);
+        assert_eq!(forward, json(&[3, 2, 1, 0]));
+        let positions: Vec<_> = ["Go", "Python", "Rust", "TypeScript"]
+            .iter()
+            .map(|name| forward.find(&format!("\"{name}\"")).unwrap())
+            .collect();
+        assert!(positions.is_sorted());
+    }
 }
@@ -0,0 +1,311 @@
+//! Symbol tables of files: the declared functions and types with their spans.
+//!
+//! Symbols are the declarations the built-in queries count (see
+//! [`DeclarationQuery`]), named and located in the source. They are stored
+//! with the statistics of a file, so the on-disk cache doubles as a symbol
+//! index: the `find` subcommand looks symbols up without parsing the files
+//! that did not change since the last run.
+
+use crate::config::CountPolicy;
+use crate::functions::function_name;
+use crate::language::{Grammar, SupportedLanguage};
+use crate::paths::relative_to;
+use crate::queries::{Declaration, DeclarationQuery};
+use crate::stats::DirectoryStats;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Tree};
+
+/// What a symbol declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    /// Functions, methods and named anonymous functions
+    Function,
+    /// Classes and structs
+    Class,
+    /// Interfaces and traits
+    Interface,
+    Enum,
+    TypeAlias,
+    /// Modules, namespaces and packages
+    Module,
+}
+
+impl SymbolKind {
+    /// Returns the kind of symbol a declaration is, if it is one.
+    fn of(declaration: Declaration) -> Option<Self> {
+        match declaration {
+            Declaration::Function => Some(Self::Function),
+            Declaration::Class => Some(Self::Class),
+            Declaration::Interface => Some(Self::Interface),
+            Declaration::Enum => Some(Self::Enum),
+            Declaration::TypeAlias => Some(Self::TypeAlias),
+            Declaration::Module => Some(Self::Module),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind as written in reports, e.g. `type_alias`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Function => "function",
+            Self::Class => "class",
+            Self::Interface => "interface",
+            Self::Enum => "enum",
+            Self::TypeAlias => "type_alias",
+            Self::Module => "module",
+        }
+    }
+}
+
+/// A named declaration of a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 1-based line where the declaration starts
+    pub line: usize,
+    /// 1-based column where the declaration starts, in bytes
+    pub column: usize,
+    /// 1-based line where the declaration ends
+    pub end_line: usize,
+}
+
+/// Lists the named declarations of a file in source order.
+///
+/// Functions are named like [`function_names`](crate::functions::function_names)
+/// names them; `policy` decides which declarations count at all.
+pub(crate) fn symbols(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    policy: &CountPolicy,
+) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let query = DeclarationQuery::for_grammar(Grammar::of_tree(*language, tree));
+    query.for_each_counted(tree, source_code, policy, |declaration, node| {
+        let Some(kind) = SymbolKind::of(declaration) else {
+            return;
+        };
+        let name = match kind {
+            SymbolKind::Function => function_name(&node),
+            _ => declared_name(&node),
+        };
+        if let Some(name) = name {
+            let start = node.start_position();
+            symbols.push(Symbol {
+                name: source_code[name.byte_range()].to_string(),
+                kind,
+                line: start.row + 1,
+                column: start.column + 1,
+                end_line: node.end_position().row + 1,
+            });
+        }
+    });
+    symbols.sort_by_key(|symbol| (symbol.line, symbol.column));
+    symbols
+}
+
+/// A symbol found by [`find_symbols`], with the file declaring it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct SymbolMatch {
+    /// The file, below the searched directory as it was given
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub symbol: Symbol,
+}
+
+/// Looks up symbols by name in the files of a directory analysis.
+///
+/// # Arguments
+///
+/// * `stats` - Statistics analyzed with symbols, see
+///   [`CodeAnalyzer::with_symbols`](crate::analyzer::CodeAnalyzer::with_symbols)
+/// * `root` - The analyzed directory as given, which the paths start with,
+///   so they lead to the files from the current directory
+/// * `name` - The name to find, matched case-sensitively
+/// * `prefix` - Whether names starting with `name` match too
+/// * `kind` - The only kind of symbol to find, if any
+///
+/// # Returns
+///
+/// The matching symbols, by path and then in source order
+pub(crate) fn find_symbols(
+    stats: &DirectoryStats,
+    root: &Path,
+    name: &str,
+    prefix: bool,
+    kind: Option<SymbolKind>,
+) -> Vec<SymbolMatch> {
+    let mut matches: Vec<SymbolMatch> = stats
+        .files
+        .iter()
+        .flat_map(|file| {
+            let path = match relative_to(&file.path, root) {
+                Some(relative) if root == Path::new(".") => relative,
+                Some(relative) => root.join(relative),
+                None => file.path.clone(),
+            };
+            file.symbols
+                .iter()
+                .filter(|symbol| kind.is_none_or(|kind| symbol.kind == kind))
+                .filter(|symbol| match prefix {
+                    true => symbol.name.starts_with(name),
+                    false => symbol.name == name,
+                })
+                .map(move |symbol| SymbolMatch {
+                    path: path.clone(),
+                    symbol: symbol.clone(),
+                })
+        })
+        .collect();
+    matches.sort_by(|a, b| a.path.cmp(&b.path));
+    matches
+}
+
+/// Finds the name node of a type or module declaration.
+///
+/// Package declarations have no `name` field, but name themselves with
+/// their only named child, e.g. `com.example` in `package com.example;`.
+fn declared_name<'tree>(declaration: &Node<'tree>) -> Option<Node<'tree>> {
+    declaration.child_by_field_name("name").or_else(|| {
+        let mut cursor = declaration.walk();
+        declaration
+            .named_children(&mut cursor)
+            .find(|child| child.kind().contains("identifier"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CodeStats, create_parser, parse_source};
+    use crate::stats::FileStats;
+
+    /// Lists the `(kind, name, line, end line)` of the symbols of a source.
+    fn symbol_table(
+        language: SupportedLanguage,
+        source: &str,
+    ) -> Vec<(SymbolKind, String, usize, usize)> {
+        let mut parser = create_parser(&language).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+        symbols(&tree, source, &language, &CountPolicy::default())
+            .into_iter()
+            .map(|symbol| (symbol.kind, symbol.name, symbol.line, symbol.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_symbols_per_language() {
+        use SymbolKind::*;
+
+        let rust = symbol_table(
+            SupportedLanguage::Rust,
+            "mod net;\nstruct Client {\n    url: String,\n}\n\
+             trait Send {}\nenum State { Idle }\ntype Id = u64;\n\
+             impl Client {\n    fn new() -> Self {\n        todo!()\n    }\n}\n",
+        );
+        let named = |kind, name: &str, line, end_line| (kind, name.to_string(), line, end_line);
+        assert_eq!(
+            rust,
+            [
+                named(Module, "net", 1, 1),
+                named(Class, "Client", 2, 4),
+                named(Interface, "Send", 5, 5),
+                named(Enum, "State", 6, 6),
+                named(TypeAlias, "Id", 7, 7),
+                named(Function, "new", 9, 11),
+            ]
+        );
+
+        let go = symbol_table(
+            SupportedLanguage::Go,
+            "package store\n\ntype Item struct{}\n\nfunc (i Item) Save() {}\n",
+        );
+        assert_eq!(
+            go,
+            [
+                named(Module, "store", 1, 1),
+                named(Class, "Item", 3, 3),
+                named(Function, "Save", 5, 5),
+            ]
+        );
+
+        let java = symbol_table(
+            SupportedLanguage::Java,
+            "package com.example;\n\ninterface Shape {\n    double area();\n}\n",
+        );
+        assert_eq!(java[0], named(Module, "com.example", 1, 1));
+        assert_eq!(java[1], named(Interface, "Shape", 3, 5));
+
+        let typescript = symbol_table(
+            SupportedLanguage::TypeScript,
+            "const handler = () => {};\nclass View {}\n",
+        );
+        assert_eq!(
+            typescript,
+            [named(Function, "handler", 1, 1), named(Class, "View", 2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_find_symbols() {
+        let mut stats = DirectoryStats::new();
+        for (path, names) in [
+            ("/repo/src/b.rs", ["parse", "Parser"]),
+            ("/repo/src/a.rs", ["parse_args", "parse"]),
+        ] {
+            let mut file = FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats::default(),
+            );
+            file.symbols = names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| Symbol {
+                    name: name.to_string(),
+                    kind: match name.starts_with(char::is_uppercase) {
+                        true => SymbolKind::Class,
+                        false => SymbolKind::Function,
+                    },
+                    line: index + 1,
+                    column: 1,
+                    end_line: index + 1,
+                })
+                .collect();
+            stats.add_file(file);
+        }
+        let find = |name, prefix, kind| {
+            find_symbols(&stats, Path::new("/repo/src"), name, prefix, kind)
+                .into_iter()
+                .map(|found| {
+                    format!(
+                        "{}:{} {}",
+                        found.path.display(),
+                        found.symbol.line,
+                        found.symbol.name
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            find("parse", false, None),
+            ["/repo/src/a.rs:2 parse", "/repo/src/b.rs:1 parse"]
+        );
+        assert_eq!(
+            find("parse", true, None),
+            [
+                "/repo/src/a.rs:1 parse_args",
+                "/repo/src/a.rs:2 parse",
+                "/repo/src/b.rs:1 parse"
+            ]
+        );
+        assert_eq!(find("Parse", true, None), ["/repo/src/b.rs:2 Parser"]);
+        assert!(find("Parser", false, Some(SymbolKind::Function)).is_empty());
+        assert!(find("missing", true, None).is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+//! Separation of test code from production code.
+//!
+//! Test files are recognized by the conventions of each ecosystem: a `tests/`
+//! directory, `*_test.go`, `*.spec.ts` and the like. Rust keeps unit tests
+//! next to the code they test, so the `#[cfg(test)]` modules of other Rust
+//! files are counted as test code as well.
+
+use crate::config::CountPolicy;
+use crate::language::{Grammar, SupportedLanguage};
+use crate::lines::count_lines_in_rows;
+use crate::queries::{Declaration, DeclarationQuery};
+use crate::stats::{FileStats, percentage};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+use tree_sitter::{Node, Tree};
+
+/// Directories whose files are all test code.
+const TEST_DIRECTORIES: [&str; 3] = ["tests", "test", "__tests__"];
+
+/// Extensions of the JavaScript and TypeScript files named `*.test.*` or `*.spec.*`.
+const SCRIPT_EXTENSIONS: [&str; 6] = ["js", "jsx", "mjs", "cjs", "ts", "tsx"];
+
+/// The test code inside a file that is otherwise production code.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct InlineTests {
+    /// Functions declared in test modules
+    pub function_count: usize,
+    /// Code lines of test modules
+    pub code_lines: usize,
+}
+
+/// Size of the production or the test code of an analysis.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct CodeGroup {
+    /// Files of the group; files with inline tests count as production files
+    pub files: usize,
+    /// Functions of the group
+    pub function_count: usize,
+    /// Code lines of the group
+    pub code_lines: usize,
+}
+
+/// The analyzed code split into production and test code.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct TestSplit {
+    /// Code that ships
+    pub production: CodeGroup,
+    /// Test files and inline test modules
+    pub test: CodeGroup,
+}
+
+impl CodeGroup {
+    /// Adds a file's functions and code lines to the group.
+    pub(crate) fn add(&mut self, file: &FileStats) {
+        self.files += 1;
+        self.function_count += file.stats.function_count;
+        self.code_lines += file.stats.code_lines;
+    }
+}
+
+impl TestSplit {
+    /// Adds a file to the test or production group; the inline test modules
+    /// of a production file move to the test group.
+    pub(crate) fn add(&mut self, file: &FileStats) {
+        if file.is_test {
+            self.test.add(file);
+            return;
+        }
+
+        self.production.add(file);
+        if let Some(inline) = file.inline_tests {
+            self.production.function_count -= inline.function_count;
+            self.production.code_lines -= inline.code_lines;
+            self.test.function_count += inline.function_count;
+            self.test.code_lines += inline.code_lines;
+        }
+    }
+
+    /// Returns the percentage of code lines that are test code, 0 without code.
+    pub(crate) fn test_line_share(&self) -> f64 {
+        percentage(
+            self.test.code_lines,
+            self.production.code_lines + self.test.code_lines,
+        )
+    }
+}
+
+/// Decides whether a file is a test file by its path.
+///
+/// # Arguments
+///
+/// * `path` - The file's path relative to the analyzed directory, so the
+///   directory itself may be named e.g. `tests`
+pub(crate) fn is_test_path(path: &Path) -> bool {
+    let mut directories = path.parent().into_iter().flat_map(Path::iter);
+    if directories.any(|name| TEST_DIRECTORIES.iter().any(|test| name == *test)) {
+        return true;
+    }
+
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    match extension {
+        "go" | "dart" => stem.ends_with("_test"),
+        "py" => stem.starts_with("test_") || stem.ends_with("_test"),
+        "java" => stem.ends_with("Test") || stem.ends_with("Tests"),
+        _ if SCRIPT_EXTENSIONS.contains(&extension) => {
+            stem.ends_with(".test") || stem.ends_with(".spec")
+        }
+        _ => false,
+    }
+}
+
+/// Measures the `#[cfg(test)]` modules of a Rust file, counting closures as
+/// functions as far as `policy` says.
+///
+/// # Returns
+///
+/// * `Some(InlineTests)` if the file declares at least one test module with a body
+/// * `None` for other files and other languages
+pub(crate) fn inline_tests(
+    tree: &Tree,
+    source_code: &str,
+    language: &SupportedLanguage,
+    policy: &CountPolicy,
+) -> Option<InlineTests> {
+    if *language != SupportedLanguage::Rust {
+        return None;
+    }
+
+    let mut modules = Vec::new();
+    collect_test_modules(&tree.root_node(), source_code, &mut modules);
+    if modules.is_empty() {
+        return None;
+    }
+
+    let mut function_count = 0;
+    let query = DeclarationQuery::for_grammar(Grammar::of_tree(*language, tree));
+    query.for_each_counted(tree, source_code, policy, |declaration, node| {
+        if declaration == Declaration::Function
+            && modules
+                .iter()
+                .any(|(module, _)| module.byte_range().contains(&node.start_byte()))
+        {
+            function_count += 1;
+        }
+    });
+
+    let rows: Vec<Range<usize>> = modules
+        .iter()
+        .map(|(module, attribute)| attribute.start_position().row..module.end_position().row + 1)
+        .collect();
+    let lines = count_lines_in_rows(tree, source_code, language, &rows);
+
+    Some(InlineTests {
+        function_count,
+        code_lines: lines.code,
+    })
+}
+
+/// Collects the `#[cfg(test)]` modules below a node, with their attribute.
+fn collect_test_modules<'tree>(
+    node: &Node<'tree>,
+    source_code: &str,
+    modules: &mut Vec<(Node<'tree>, Node<'tree>)>,
+) {
+    if node.kind() == "mod_item"
+        && node.child_by_field_name("body").is_some()
+        && let Some(attribute) = cfg_test_attribute(node, source_code)
+    {
+        modules.push((*node, attribute));
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_test_modules(&child, source_code, modules);
+    }
+}
+
+/// Finds the `#[cfg(test)]` attribute among those preceding an item.
+fn cfg_test_attribute<'tree>(item: &Node<'tree>, source_code: &str) -> Option<Node<'tree>> {
+    let mut sibling = item.prev_sibling();
+    while let Some(node) = sibling {
+        match node.kind() {
+            "attribute_item" => {
+                let attribute: String = source_code[node.byte_range()]
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                if attribute == "#[cfg(test)]" {
+                    return Some(node);
+                }
+            }
+            "line_comment" | "block_comment" => {}
+            _ => return None,
+        }
+        sibling = node.prev_sibling();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{create_parser, parse_source};
+
+    #[test]
+    fn test_is_test_path() {
+        for path in [
+            "tests/cli.rs",
+            "src/__tests__/app.js",
+            "pkg/server_test.go",
+            "test_models.py",
+            "app/models_test.py",
+            "src/app.spec.ts",
+            "src/Button.test.tsx",
+            "src/main/FooTest.java",
+            "lib/widget_test.dart",
+        ] {
+            assert!(is_test_path(Path::new(path)), "{path}");
+        }
+
+        for path in [
+            "src/lib.rs",
+            "src/tests.rs",
+            "contest/main.go",
+            "testing.py",
+            "src/spec.ts",
+            "src/Testable.java",
+        ] {
+            assert!(!is_test_path(Path::new(path)), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_inline_tests() {
+        let source = "\
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        assert_eq!(add(1, 2), 3);
+    }
+}
+";
+        let mut parser = create_parser(&SupportedLanguage::Rust).unwrap();
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+
+        assert_eq!(
+            inline_tests(
+                &tree,
+                source,
+                &SupportedLanguage::Rust,
+                &CountPolicy::default()
+            ),
+            Some(InlineTests {
+                function_count: 1,
+                code_lines: 8,
+            })
+        );
+
+        let source = "#[cfg(feature = \"x\")]\nmod extra {}\nmod tests;\n";
+        let tree = parse_source(&mut parser, source, "test").unwrap();
+        assert_eq!(
+            inline_tests(
+                &tree,
+                source,
+                &SupportedLanguage::Rust,
+                &CountPolicy::default()
+            ),
+            None
+        );
+    }
+}
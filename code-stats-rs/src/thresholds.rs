@@ -0,0 +1,555 @@
+//! `--fail-if` rules checked against the statistics after an analysis, and
+//! `diff --ratchet` rules checked against a baseline report.
+//!
+//! A rule compares one metric with a limit, e.g. `functions_per_file>100`.
+//! Totals are measured over the whole analysis, `*_per_file` metrics over the
+//! file with the highest value, so a violation can name the offending file.
+//! A ratchet, e.g. `functions@src/legacy`, keeps a metric from growing beyond
+//! its baseline value, so legacy code that cannot meet an absolute limit can
+//! still be held to gradual improvement.
+
+use crate::findings::{Severity, count_severity};
+use crate::stats::{DirectoryStats, FileStats};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A value measured on the statistics of an analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Number of analyzed files
+    Files,
+    /// Total functions
+    Functions,
+    /// Total classes/structs
+    Classes,
+    /// Total interfaces/traits
+    Interfaces,
+    /// Total enums
+    Enums,
+    /// Total type aliases
+    TypeAliases,
+    /// Total modules/namespaces/packages
+    Modules,
+    /// Total code lines
+    CodeLines,
+    /// Total comment-only lines
+    CommentLines,
+    /// Total lines
+    TotalLines,
+    /// Most functions in a single file
+    FunctionsPerFile,
+    /// Most classes/structs in a single file
+    ClassesPerFile,
+    /// Most lines in a single file
+    LinesPerFile,
+    /// Mean function length in lines (0 without functions)
+    AvgFunctionLines,
+    /// Functions longer than the configured maximum
+    OversizedFunctions,
+    /// Magic strings and numbers
+    MagicValues,
+    /// ERROR/MISSING nodes produced by the parser
+    ParseErrors,
+    /// Findings of severity error
+    Errors,
+    /// Findings of severity warning
+    Warnings,
+}
+
+impl Metric {
+    /// Every metric, in the order listed in error messages.
+    const ALL: [Metric; 19] = [
+        Metric::Files,
+        Metric::Functions,
+        Metric::Classes,
+        Metric::Interfaces,
+        Metric::Enums,
+        Metric::TypeAliases,
+        Metric::Modules,
+        Metric::CodeLines,
+        Metric::CommentLines,
+        Metric::TotalLines,
+        Metric::FunctionsPerFile,
+        Metric::ClassesPerFile,
+        Metric::LinesPerFile,
+        Metric::AvgFunctionLines,
+        Metric::OversizedFunctions,
+        Metric::MagicValues,
+        Metric::ParseErrors,
+        Metric::Errors,
+        Metric::Warnings,
+    ];
+
+    /// Looks up a metric by the name used in rules.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Metric)` - The metric named `name`
+    /// * `Err(String)` - If the metric is unknown, listing the supported ones
+    ///   for the rule `spec`
+    fn from_name(spec: &str, name: &str) -> Result<Self, String> {
+        Metric::ALL
+            .into_iter()
+            .find(|metric| metric.name() == name)
+            .ok_or_else(|| {
+                let supported: Vec<&str> = Metric::ALL.iter().map(|metric| metric.name()).collect();
+                format!(
+                    "Invalid rule '{spec}': unknown metric `{name}` (supported: {})",
+                    supported.join(", ")
+                )
+            })
+    }
+
+    /// Returns the name used in rules.
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Files => "files",
+            Metric::Functions => "functions",
+            Metric::Classes => "classes",
+            Metric::Interfaces => "interfaces",
+            Metric::Enums => "enums",
+            Metric::TypeAliases => "type_aliases",
+            Metric::Modules => "modules",
+            Metric::CodeLines => "code_lines",
+            Metric::CommentLines => "comment_lines",
+            Metric::TotalLines => "total_lines",
+            Metric::FunctionsPerFile => "functions_per_file",
+            Metric::ClassesPerFile => "classes_per_file",
+            Metric::LinesPerFile => "lines_per_file",
+            Metric::AvgFunctionLines => "avg_function_lines",
+            Metric::OversizedFunctions => "oversized_functions",
+            Metric::MagicValues => "magic_values",
+            Metric::ParseErrors => "parse_errors",
+            Metric::Errors => "errors",
+            Metric::Warnings => "warnings",
+        }
+    }
+
+    /// Measures the metric.
+    ///
+    /// # Returns
+    ///
+    /// The value, and for `*_per_file` metrics the file it was measured on
+    /// (`None` when no file was analyzed)
+    fn measure(self, stats: &DirectoryStats) -> (f64, Option<&Path>) {
+        let total = &stats.total_stats;
+        let value = match self {
+            Metric::Files => stats.total_files(),
+            Metric::Functions => total.function_count,
+            Metric::Classes => total.class_struct_count,
+            Metric::Interfaces => total.interface_count,
+            Metric::Enums => total.enum_count,
+            Metric::TypeAliases => total.type_alias_count,
+            Metric::Modules => total.module_count,
+            Metric::CodeLines => total.code_lines,
+            Metric::CommentLines => total.comment_lines,
+            Metric::TotalLines => total.total_lines,
+            Metric::FunctionsPerFile => return max_per_file(stats, |f| f.stats.function_count),
+            Metric::ClassesPerFile => return max_per_file(stats, |f| f.stats.class_struct_count),
+            Metric::LinesPerFile => return max_per_file(stats, |f| f.stats.total_lines),
+            Metric::AvgFunctionLines => {
+                return (total.average_function_lines().unwrap_or(0.0), None);
+            }
+            Metric::OversizedFunctions => total.oversized_function_count,
+            Metric::MagicValues => total.magic_string_count + total.magic_number_count,
+            Metric::ParseErrors => total.parse_errors,
+            Metric::Errors => count_severity(&stats.findings, Severity::Error),
+            Metric::Warnings => count_severity(&stats.findings, Severity::Warning),
+        };
+        (value as f64, None)
+    }
+}
+
+/// Returns the highest per-file value and the file it belongs to.
+fn max_per_file(
+    stats: &DirectoryStats,
+    value: impl Fn(&FileStats) -> usize,
+) -> (f64, Option<&Path>) {
+    stats
+        .files
+        .iter()
+        .max_by_key(|file| value(file))
+        .map_or((0.0, None), |file| {
+            (value(file) as f64, Some(file.path.as_path()))
+        })
+}
+
+/// How a measured value is compared with the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `==`
+    Equal,
+}
+
+impl Comparison {
+    /// The operator symbols accepted in rules.
+    const OPERATORS: [(&'static str, Comparison); 5] = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        ("==", Comparison::Equal),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+    ];
+
+    fn holds(self, value: f64, limit: f64) -> bool {
+        match self {
+            Comparison::Greater => value > limit,
+            Comparison::GreaterOrEqual => value >= limit,
+            Comparison::Less => value < limit,
+            Comparison::LessOrEqual => value <= limit,
+            Comparison::Equal => value == limit,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        Self::OPERATORS
+            .iter()
+            .find(|(_, comparison)| *comparison == self)
+            .map_or("", |(symbol, _)| symbol)
+    }
+}
+
+/// A `--fail-if` rule such as `functions_per_file>100`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    /// The measured metric
+    pub metric: Metric,
+    /// When the rule is violated: the metric compared with the limit
+    pub comparison: Comparison,
+    /// The limit to compare with
+    pub limit: f64,
+}
+
+impl Threshold {
+    /// Parses a rule of the form `METRIC OP NUMBER`.
+    ///
+    /// `OP` is one of `>`, `>=`, `<`, `<=` and `==`; whitespace around the
+    /// parts is ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Threshold)` - The parsed rule
+    /// * `Err(String)` - If the rule is malformed or the metric is unknown;
+    ///   the message lists the supported metrics
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        // The leftmost operator wins, preferring `>=` over `>` at the same position
+        let (position, symbol, comparison) = Comparison::OPERATORS
+            .iter()
+            .filter_map(|(symbol, comparison)| {
+                spec.find(symbol)
+                    .map(|position| (position, *symbol, *comparison))
+            })
+            .min_by_key(|(position, symbol, _)| (*position, std::cmp::Reverse(symbol.len())))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid rule '{spec}': expected METRIC>NUMBER, e.g. functions_per_file>100"
+                )
+            })?;
+
+        let metric = Metric::from_name(spec, spec[..position].trim())?;
+
+        let limit = spec[position + symbol.len()..].trim();
+        let limit = limit
+            .parse::<f64>()
+            .ok()
+            .filter(|limit| limit.is_finite())
+            .ok_or_else(|| format!("Invalid rule '{spec}': `{limit}` is not a number"))?;
+
+        Ok(Self {
+            metric,
+            comparison,
+            limit,
+        })
+    }
+
+    /// Returns whether the rule is measured on single files, which needs
+    /// the per-file records of the analysis.
+    pub(crate) fn is_per_file(&self) -> bool {
+        matches!(
+            self.metric,
+            Metric::FunctionsPerFile | Metric::ClassesPerFile | Metric::LinesPerFile
+        )
+    }
+
+    /// Checks the rule against the statistics of an analysis.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` - A description of the violation, naming the file for
+    ///   `*_per_file` metrics
+    /// * `None` - If the rule holds
+    pub(crate) fn violation(&self, stats: &DirectoryStats) -> Option<String> {
+        let (value, file) = self.metric.measure(stats);
+        if !self.comparison.holds(value, self.limit) {
+            return None;
+        }
+        let value = (value * 10.0).round() / 10.0;
+        Some(match file {
+            Some(file) => format!("{self} ({} is {value})", file.display()),
+            None => format!("{self} ({} is {value})", self.metric.name()),
+        })
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.metric.name(),
+            self.comparison.symbol(),
+            self.limit
+        )
+    }
+}
+
+/// A `--ratchet` rule such as `functions@src/legacy`: a metric that may not
+/// grow beyond its value in a baseline report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ratchet {
+    /// The measured metric
+    pub metric: Metric,
+    /// The directory or file the metric is measured on, relative to the
+    /// analyzed directory; `None` measures the whole analysis
+    pub path: Option<PathBuf>,
+}
+
+impl Ratchet {
+    /// Parses a rule of the form `METRIC` or `METRIC@PATH`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Ratchet)` - The parsed rule
+    /// * `Err(String)` - If the metric is unknown; the message lists the
+    ///   supported metrics
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, path) = match spec.split_once('@') {
+            Some((name, path)) => (name, Some(PathBuf::from(path.trim()))),
+            None => (spec, None),
+        };
+        Ok(Self {
+            metric: Metric::from_name(spec, name.trim())?,
+            path: path.filter(|path| !path.as_os_str().is_empty()),
+        })
+    }
+
+    /// Checks the rule against the statistics of the baseline and of the
+    /// current tree, whose paths are relative to the analyzed directory.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` - A description of the violation with both values,
+    ///   naming the file for `*_per_file` metrics
+    /// * `None` - If the metric did not grow
+    pub(crate) fn violation(
+        &self,
+        baseline: &DirectoryStats,
+        current: &DirectoryStats,
+    ) -> Option<String> {
+        let (before, _) = self.measure(baseline);
+        let (after, file) = self.measure(current);
+        if after <= before {
+            return None;
+        }
+
+        let round = |value: f64| (value * 10.0).round() / 10.0;
+        let (before, after) = (round(before), round(after));
+        Some(match file {
+            Some(file) => format!(
+                "{self} increased from {before} to {after} ({})",
+                file.display()
+            ),
+            None => format!("{self} increased from {before} to {after}"),
+        })
+    }
+
+    /// Measures the metric on the files below the rule's path, or on all.
+    fn measure(&self, stats: &DirectoryStats) -> (f64, Option<PathBuf>) {
+        let below;
+        let stats = match &self.path {
+            Some(path) => {
+                below = stats.below(path);
+                &below
+            }
+            None => stats,
+        };
+        let (value, file) = self.metric.measure(stats);
+        (value, file.map(Path::to_path_buf))
+    }
+}
+
+impl fmt::Display for Ratchet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.metric.name())?;
+        match &self.path {
+            Some(path) => write!(f, "@{}", path.display()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::findings::Finding;
+    use crate::language::SupportedLanguage;
+    use crate::parser::CodeStats;
+    use std::path::PathBuf;
+
+    fn stats() -> DirectoryStats {
+        let mut stats = DirectoryStats::new();
+        for (path, functions, function_lines) in [("a.rs", 3, 30), ("big.rs", 140, 700)] {
+            stats.add_file(FileStats::new(
+                PathBuf::from(path),
+                SupportedLanguage::Rust,
+                CodeStats {
+                    function_count: functions,
+                    function_lines,
+                    ..CodeStats::default()
+                },
+            ));
+        }
+        stats
+    }
+
+    #[test]
+    fn test_parse() {
+        let rule = Threshold::parse("functions_per_file>100").unwrap();
+        assert_eq!(rule.metric, Metric::FunctionsPerFile);
+        assert_eq!(rule.comparison, Comparison::Greater);
+        assert_eq!(rule.limit, 100.0);
+
+        let rule = Threshold::parse(" avg_function_lines >= 12.5 ").unwrap();
+        assert_eq!(rule.metric, Metric::AvgFunctionLines);
+        assert_eq!(rule.comparison, Comparison::GreaterOrEqual);
+        assert_eq!(rule.limit, 12.5);
+        assert_eq!(rule.to_string(), "avg_function_lines>=12.5");
+
+        assert_eq!(
+            Threshold::parse("errors==0").unwrap().comparison,
+            Comparison::Equal
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_rules() {
+        let error = Threshold::parse("max_complexity>15").unwrap_err();
+        assert!(error.contains("unknown metric `max_complexity`"));
+        assert!(error.contains("functions_per_file"));
+
+        assert!(Threshold::parse("functions").is_err());
+        assert!(Threshold::parse("functions>many").is_err());
+        assert!(Threshold::parse("functions>inf").is_err());
+    }
+
+    #[test]
+    fn test_violation() {
+        let stats = stats();
+
+        let rule = Threshold::parse("functions_per_file>100").unwrap();
+        assert_eq!(
+            rule.violation(&stats).as_deref(),
+            Some("functions_per_file>100 (big.rs is 140)")
+        );
+        assert_eq!(
+            Threshold::parse("functions_per_file>140")
+                .unwrap()
+                .violation(&stats),
+            None
+        );
+
+        // 730 lines over 143 functions
+        let rule = Threshold::parse("avg_function_lines>5").unwrap();
+        assert_eq!(
+            rule.violation(&stats).as_deref(),
+            Some("avg_function_lines>5 (avg_function_lines is 5.1)")
+        );
+    }
+
+    #[test]
+    fn test_ratchet_parse() {
+        let rule = Ratchet::parse("functions").unwrap();
+        assert_eq!(rule.metric, Metric::Functions);
+        assert_eq!(rule.path, None);
+
+        let rule = Ratchet::parse("avg_function_lines @ src/legacy").unwrap();
+        assert_eq!(rule.metric, Metric::AvgFunctionLines);
+        assert_eq!(rule.path, Some(PathBuf::from("src/legacy")));
+        assert_eq!(rule.to_string(), "avg_function_lines@src/legacy");
+
+        assert!(
+            Ratchet::parse("complexity@src")
+                .unwrap_err()
+                .contains("unknown metric `complexity`")
+        );
+    }
+
+    #[test]
+    fn test_ratchet_violation() {
+        let baseline = stats();
+        let mut current = stats();
+        current.add_file(FileStats::new(
+            PathBuf::from("legacy/new.rs"),
+            SupportedLanguage::Rust,
+            CodeStats {
+                function_count: 2,
+                ..CodeStats::default()
+            },
+        ));
+
+        assert_eq!(
+            Ratchet::parse("functions")
+                .unwrap()
+                .violation(&baseline, &current)
+                .as_deref(),
+            Some("functions increased from 143 to 145")
+        );
+        assert_eq!(
+            Ratchet::parse("functions@legacy")
+                .unwrap()
+                .violation(&baseline, &current)
+                .as_deref(),
+            Some("functions@legacy increased from 0 to 2")
+        );
+        // Other paths, fewer or as many are fine
+        for rule in ["functions@a.rs", "functions_per_file", "classes"] {
+            let rule = Ratchet::parse(rule).unwrap();
+            assert_eq!(rule.violation(&baseline, &current), None, "{rule}");
+        }
+        assert_eq!(
+            Ratchet::parse("files")
+                .unwrap()
+                .violation(&current, &baseline),
+            None
+        );
+    }
+
+    #[test]
+    fn test_violation_counts_findings() {
+        let mut stats = stats();
+        assert_eq!(
+            Threshold::parse("errors>0").unwrap().violation(&stats),
+            None
+        );
+
+        stats.findings.push(Finding::new(
+            Severity::Error,
+            Some(PathBuf::from("broken.rs")),
+            "Failed to read",
+        ));
+        assert!(
+            Threshold::parse("errors>0")
+                .unwrap()
+                .violation(&stats)
+                .is_some()
+        );
+    }
+}
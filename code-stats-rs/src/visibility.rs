@@ -0,0 +1,218 @@
+//! Visibility of declarations, to measure a code base's API surface.
+//!
+//! Only languages that mark visibility in the syntax are covered: Rust
+//! `pub`, Java `public`, and TypeScript/JavaScript `export`. Declarations
+//! in other languages, and anonymous functions such as closures and
+//! callbacks, have no visibility.
+
+use crate::language::SupportedLanguage;
+use tree_sitter::Node;
+
+/// Whether a declaration is part of the API of its crate, package or module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Visibility {
+    /// Usable from other crates, packages or modules
+    Public,
+    /// Only usable where it is declared, including `pub(crate)` Rust items
+    /// and package-private or protected Java members
+    Private,
+}
+
+impl Visibility {
+    fn of(public: bool) -> Self {
+        if public { Self::Public } else { Self::Private }
+    }
+}
+
+/// Determines the visibility of a declaration counted by the built-in query.
+///
+/// # Arguments
+///
+/// * `node` - A function or type declaration
+/// * `source_code` - The source code the node was parsed from
+/// * `language` - The programming language of the source
+///
+/// # Returns
+///
+/// * `Some(Visibility)` for declarations of Rust, Java, TypeScript and JavaScript
+/// * `None` for anonymous functions that are not assigned to a variable, and
+///   for other languages
+pub(crate) fn visibility(
+    node: &Node,
+    source_code: &str,
+    language: &SupportedLanguage,
+) -> Option<Visibility> {
+    // Closures and lambdas counted with `--count-anonymous`
+    if matches!(node.kind(), "closure_expression" | "lambda_expression") {
+        return None;
+    }
+    match language {
+        SupportedLanguage::Rust => Some(rust_visibility(node, source_code)),
+        SupportedLanguage::Java => java_visibility(node),
+        SupportedLanguage::JavaScript | SupportedLanguage::TypeScript => {
+            script_visibility(node, source_code)
+        }
+        _ => None,
+    }
+}
+
+/// Rust items are public with a plain `pub`; default methods of a trait
+/// share the trait's visibility.
+fn rust_visibility(node: &Node, source_code: &str) -> Visibility {
+    if let Some(declarations) = node.parent()
+        && let Some(item) = declarations.parent()
+        && item.kind() == "trait_item"
+    {
+        return rust_visibility(&item, source_code);
+    }
+
+    let mut cursor = node.walk();
+    let public = node.children(&mut cursor).any(|child| {
+        child.kind() == "visibility_modifier" && &source_code[child.byte_range()] == "pub"
+    });
+    Visibility::of(public)
+}
+
+/// Java members are public with a `public` modifier, or implicitly as
+/// members of an interface.
+fn java_visibility(node: &Node) -> Option<Visibility> {
+    let modifiers = modifier_kinds(node);
+    if modifiers.contains(&"public") {
+        return Some(Visibility::Public);
+    }
+    let in_interface = node
+        .parent()
+        .is_some_and(|parent| parent.kind() == "interface_body");
+    Some(Visibility::of(
+        in_interface && !modifiers.contains(&"private"),
+    ))
+}
+
+/// Lists the keyword kinds in the `modifiers` child of a Java declaration.
+fn modifier_kinds(node: &Node) -> Vec<&'static str> {
+    let mut cursor = node.walk();
+    let Some(modifiers) = node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "modifiers")
+    else {
+        return Vec::new();
+    };
+    let mut cursor = modifiers.walk();
+    modifiers
+        .children(&mut cursor)
+        .map(|child| child.kind())
+        .collect()
+}
+
+/// TypeScript and JavaScript declarations are public when exported;
+/// methods when their class is, unless marked `private`, `protected` or `#`.
+fn script_visibility(node: &Node, source_code: &str) -> Option<Visibility> {
+    let is_exported = |declaration: &Node| {
+        declaration
+            .parent()
+            .is_some_and(|parent| parent.kind() == "export_statement")
+    };
+
+    match node.kind() {
+        // Functions are declarations when assigned, e.g. `export const f = () => 1`
+        "arrow_function" | "function_expression" => {
+            let declarator = node
+                .parent()
+                .filter(|p| p.kind() == "variable_declarator")?;
+            let declaration = declarator.parent()?;
+            Some(Visibility::of(is_exported(&declaration)))
+        }
+        "method_definition" => {
+            let mut cursor = node.walk();
+            let hidden = node.children(&mut cursor).any(|child| {
+                child.kind() == "private_property_identifier"
+                    || (child.kind() == "accessibility_modifier"
+                        && &source_code[child.byte_range()] != "public")
+            });
+            // Methods of class expressions and object literals are not declarations
+            let class = node
+                .parent()
+                .and_then(|body| body.parent())
+                .filter(|class| class.kind().ends_with("class_declaration"))?;
+            let class_visibility = script_visibility(&class, source_code)?;
+            Some(if hidden {
+                Visibility::Private
+            } else {
+                class_visibility
+            })
+        }
+        _ => Some(Visibility::of(is_exported(node))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::language::SupportedLanguage;
+    use crate::parser::count_source;
+
+    /// Returns the public/private function and type counts of a source.
+    fn surface(language: SupportedLanguage, source: &str) -> [usize; 4] {
+        let stats = count_source(language, source);
+        [
+            stats.public_function_count,
+            stats.private_function_count,
+            stats.public_type_count,
+            stats.private_type_count,
+        ]
+    }
+
+    #[test]
+    fn test_rust_visibility() {
+        let source = "\
+pub fn api() {}
+pub(crate) fn internal() {}
+fn helper() {}
+pub struct Point;
+enum State {}
+pub trait Shape {
+    fn area(&self) -> f64 { 0.0 }
+}
+";
+        assert_eq!(surface(SupportedLanguage::Rust, source), [2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_java_visibility() {
+        let source = "\
+public class Service {
+    public void run() {}
+    void helper() {}
+    private Service() {}
+}
+interface Listener {
+    void onEvent();
+}
+";
+        assert_eq!(surface(SupportedLanguage::Java, source), [2, 2, 1, 1]);
+    }
+
+    #[test]
+    fn test_typescript_visibility() {
+        let source = "\
+export function api(): void {}
+function helper(): void {}
+export const handler = () => 1;
+[1, 2].map((x) => x * 2);
+export class Store {
+    get(): void {}
+    private load(): void {}
+}
+interface Options {}
+export type Id = string;
+";
+        assert_eq!(surface(SupportedLanguage::TypeScript, source), [3, 2, 2, 1]);
+    }
+
+    #[test]
+    fn test_languages_without_visibility() {
+        assert_eq!(
+            surface(SupportedLanguage::Python, "def f():\n    pass\n"),
+            [0; 4]
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Hook for computing custom metrics during the built-in traversal.
+
+use crate::language::SupportedLanguage;
+use tree_sitter::Node;
+
+/// Receives every syntax node while the built-in counts are collected.
+///
+/// Pass a visitor to [`count_source_with`](crate::count_source_with) to
+/// compute additional metrics from the same parse. Nodes are visited in
+/// depth-first pre-order, including anonymous nodes such as punctuation.
+///
+/// Any closure taking `(SupportedLanguage, &Node, &str)` is a visitor; the
+/// `&str` is the complete source text, so `node.utf8_text(source.as_bytes())`
+/// yields the node's text.
+///
+/// # Example
+///
+/// ```
+/// use code_stats_rs::tree_sitter::Node;
+/// use code_stats_rs::{SupportedLanguage, count_source_with};
+///
+/// let mut if_count = 0;
+/// let mut count_ifs = |_: SupportedLanguage, node: &Node, _: &str| {
+///     if node.kind() == "if_expression" {
+///         if_count += 1;
+///     }
+/// };
+///
+/// let source = "fn f(x: i32) -> i32 { if x > 0 { 1 } else { 2 } }";
+/// let stats = count_source_with(SupportedLanguage::Rust, source, &mut count_ifs);
+/// assert_eq!(stats.function_count, 1);
+/// assert_eq!(if_count, 1);
+/// ```
+pub trait NodeVisitor {
+    /// Called once for every node of the syntax tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The language the source was parsed as
+    /// * `node` - The node being visited
+    /// * `source` - The complete source text the tree was parsed from
+    fn visit(&mut self, language: SupportedLanguage, node: &Node<'_>, source: &str);
+}
+
+impl<F> NodeVisitor for F
+where
+    F: FnMut(SupportedLanguage, &Node<'_>, &str),
+{
+    fn visit(&mut self, language: SupportedLanguage, node: &Node<'_>, source: &str) {
+        self(language, node, source)
+    }
+}
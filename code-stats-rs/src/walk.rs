@@ -0,0 +1,294 @@
+//! Directory traversal deciding which files an analysis reads.
+//!
+//! [`DirectoryWalk`] is the source stage of both the command line's
+//! directory analysis and the embedding pipeline's `DirectorySource`, so
+//! both skip the same hidden, ignored, duplicate and oversized files.
+
+use crate::analyzer::TraversalLimits;
+use crate::config::{CONFIG_FILE_NAME, ConfigResolver, DirectoryConfig};
+use crate::error::{CodeStatsError, ErrorKind, Result};
+use crate::findings::Finding;
+use crate::paths::{is_hidden_name, path_contains};
+use crate::source::DEFAULT_MAX_FILE_SIZE;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use walkdir::{DirEntry, WalkDir};
+
+/// Which files below a directory a [`DirectoryWalk`] yields.
+#[derive(Debug, Clone)]
+pub(crate) struct WalkOptions {
+    /// Maximum depth of the yielded files below the root
+    pub max_depth: usize,
+    /// Whether to follow symbolic links
+    pub follow_links: bool,
+    /// Whether to include hidden files and directories
+    pub hidden: bool,
+    /// Whether a file reached again through another link is skipped
+    pub dedup_links: bool,
+    /// Files larger than this many bytes are skipped unread
+    pub max_file_size: Option<u64>,
+    /// Limits that stop the traversal
+    pub limits: TraversalLimits,
+    /// Patterns to exclude files (substring matching)
+    pub ignore_patterns: Vec<String>,
+    /// The only files to yield, e.g. those changed since a revision
+    pub only_files: Option<HashSet<PathBuf>>,
+    /// Whether files too large are reported as warnings instead of info
+    pub collect_warnings: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            follow_links: false,
+            hidden: false,
+            dedup_links: true,
+            max_file_size: Some(DEFAULT_MAX_FILE_SIZE),
+            limits: TraversalLimits::default(),
+            ignore_patterns: Vec::new(),
+            only_files: None,
+            collect_warnings: false,
+        }
+    }
+}
+
+/// An entry yielded by a [`DirectoryWalk`].
+#[derive(Debug)]
+pub(crate) enum Walked {
+    /// A file to analyze, with the effective configuration of its directory
+    File(PathBuf, Arc<DirectoryConfig>),
+    /// An entry left out for a reason worth reporting, e.g. a duplicate
+    Skipped(Finding),
+    /// An entry that could not be read; the traversal goes on
+    Error(CodeStatsError),
+}
+
+/// Walks a directory in file name order, yielding the files to analyze.
+///
+/// Entries are left out, in this order, when they are:
+/// 1. Hidden, unless [`WalkOptions::hidden`] is set
+/// 2. Behind more symbolic links than allowed, with an info finding
+/// 3. Not regular files, `.code-stats.toml` files, matching an ignore
+///    pattern of the options or of the effective directory config, or not
+///    among [`WalkOptions::only_files`]
+/// 4. Already reached through another link while following links, with an
+///    info finding
+/// 5. Larger than [`WalkOptions::max_file_size`], with an info finding (a
+///    warning with [`WalkOptions::collect_warnings`])
+///
+/// The remaining files count against [`WalkOptions::limits`]. An invalid
+/// configuration file or an exceeded limit is yielded as an `Err` that ends
+/// the traversal.
+pub(crate) struct DirectoryWalk {
+    entries: walkdir::IntoIter,
+    options: WalkOptions,
+    configs: ConfigResolver,
+    link_depths: LinkDepths,
+    /// Canonical paths of the files yielded so far and the path each was
+    /// first reached by, kept while following links
+    seen_files: Option<HashMap<PathBuf, PathBuf>>,
+    /// Files and bytes yielded so far, checked against the limits
+    visited: (usize, u64),
+    /// Set once an `Err` was yielded
+    failed: bool,
+}
+
+impl DirectoryWalk {
+    /// Creates a walk of `root`, resolving configuration files with `configs`.
+    pub(crate) fn new(root: &Path, options: WalkOptions, configs: ConfigResolver) -> Self {
+        // Sorted, so identical trees produce identical reports
+        let entries = WalkDir::new(root)
+            .max_depth(options.max_depth)
+            .follow_links(options.follow_links)
+            .sort_by_file_name()
+            .into_iter();
+        let seen_files = (options.follow_links && options.dedup_links).then(HashMap::new);
+        Self {
+            entries,
+            options,
+            configs,
+            link_depths: LinkDepths::default(),
+            seen_files,
+            visited: (0, 0),
+            failed: false,
+        }
+    }
+
+    /// Decides what becomes of one entry of the traversal.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Walked))` - The entry is yielded
+    /// * `Ok(None)` - The entry is passed over silently
+    /// * `Err` - A configuration file is invalid or a limit was exceeded
+    fn walk_entry(&mut self, entry: DirEntry) -> Result<Option<Walked>> {
+        if !is_visible(&entry, self.options.hidden) {
+            if entry.file_type().is_dir() {
+                self.entries.skip_current_dir();
+            }
+            return Ok(None);
+        }
+
+        if let Some(max) = self.options.limits.max_link_depth
+            && self.link_depths.of(&entry) > max
+        {
+            if entry.file_type().is_dir() {
+                self.entries.skip_current_dir();
+            }
+            return Ok(Some(Walked::Skipped(Finding::skipped(
+                entry.into_path(),
+                ErrorKind::LinkDepth,
+                format_args!("more than {max} symbolic links deep"),
+            ))));
+        }
+
+        let path = entry.path();
+        let config = self.configs.for_file(path)?;
+        if !is_candidate(path, &self.options.ignore_patterns, &config)
+            || self
+                .options
+                .only_files
+                .as_ref()
+                .is_some_and(|files| !files.contains(path))
+        {
+            return Ok(None);
+        }
+
+        if let Some(seen_files) = &mut self.seen_files
+            && let Ok(canonical) = path.canonicalize()
+        {
+            match seen_files.entry(canonical) {
+                Entry::Occupied(first) => {
+                    return Ok(Some(Walked::Skipped(Finding::skipped(
+                        path.to_path_buf(),
+                        ErrorKind::Duplicate,
+                        format_args!("same file as {}", first.get().display()),
+                    ))));
+                }
+                Entry::Vacant(first) => {
+                    first.insert(path.to_path_buf());
+                }
+            }
+        }
+
+        let limits = self.options.limits;
+        let size = (self.options.max_file_size.is_some() || limits.max_total_bytes.is_some())
+            .then(|| entry.metadata().ok())
+            .flatten()
+            .map(|metadata| metadata.len());
+        if let Some(limit) = self.options.max_file_size
+            && let Some(size) = size
+            && size > limit
+        {
+            return Ok(Some(Walked::Skipped(
+                Finding::skipped(
+                    path.to_path_buf(),
+                    ErrorKind::TooLarge,
+                    format_args!("{size} bytes, larger than {limit}"),
+                )
+                .warning_if(self.options.collect_warnings),
+            )));
+        }
+
+        self.visited.0 += 1;
+        self.visited.1 += size.unwrap_or(0);
+        limits.check(self.visited)?;
+
+        Ok(Some(Walked::File(entry.into_path(), config)))
+    }
+}
+
+impl Iterator for DirectoryWalk {
+    type Item = Result<Walked>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            let walked = match self.entries.next()? {
+                Ok(entry) => self.walk_entry(entry),
+                Err(e) => Ok(Some(walk_error(e))),
+            };
+            match walked {
+                Ok(Some(walked)) => return Some(Ok(walked)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Converts an error of the traversal: a link back to a parent directory is
+/// skipped with an info finding, anything else is an error about its path.
+fn walk_error(e: walkdir::Error) -> Walked {
+    if let (Some(path), Some(ancestor)) = (e.path(), e.loop_ancestor()) {
+        return Walked::Skipped(Finding::skipped(
+            path.to_path_buf(),
+            ErrorKind::SymlinkLoop,
+            format!("symbolic link loop back to {}", ancestor.display()),
+        ));
+    }
+
+    let path = e.path().map(Path::to_path_buf);
+    let source = match e.io_error() {
+        // walkdir's own message repeats the path, which is kept separately
+        Some(inner) => io::Error::new(inner.kind(), inner.to_string()),
+        None => io::Error::from(e),
+    };
+    Walked::Error(CodeStatsError::IoError { path, source })
+}
+
+/// Counts the symbolic links on the path to each entry of a traversal.
+///
+/// Entries arrive depth-first, so the counts of the entry's ancestors are
+/// the first `depth` elements of the stack.
+#[derive(Debug, Default)]
+struct LinkDepths(Vec<usize>);
+
+impl LinkDepths {
+    /// Returns the number of symbolic links on the way to `entry`, the
+    /// entry itself included.
+    fn of(&mut self, entry: &DirEntry) -> usize {
+        self.0.truncate(entry.depth());
+        let depth = self.0.last().copied().unwrap_or(0) + usize::from(entry.path_is_symlink());
+        self.0.push(depth);
+        depth
+    }
+}
+
+/// Decides whether the walker yields an entry and descends into it: hidden
+/// files and directories below the root are left out unless `hidden` is set.
+/// The root itself is always visible, e.g. `.` or a temporary `.tmp` directory.
+pub(crate) fn is_visible(entry: &DirEntry, hidden: bool) -> bool {
+    hidden || entry.depth() == 0 || !is_hidden_name(entry.file_name())
+}
+
+/// Decides whether a traversal entry is a candidate for analysis.
+///
+/// Entries are skipped when they are not regular files or are config files,
+/// or when their path
+/// contains any of the ignore patterns or the directory config's patterns
+/// (substring matching). Whether the candidate's language is supported is
+/// left to the caller.
+pub(crate) fn is_candidate(
+    path: &Path,
+    ignore_patterns: &[String],
+    config: &DirectoryConfig,
+) -> bool {
+    // Skip if not a file, or if it is one of our own config files
+    if !path.is_file() || path.file_name() == Some(CONFIG_FILE_NAME.as_ref()) {
+        return false;
+    }
+
+    // Check if path matches any ignore pattern using substring matching
+    !(ignore_patterns
+        .iter()
+        .any(|pattern| path_contains(path, pattern))
+        || config.is_ignored(path))
+}
@@ -1,12 +1,12 @@
 mod common;
 
 use assert_cmd::Command;
-use common::{create_test_file, create_test_project};
+use common::{code_stats_command, create_test_file, create_test_project};
 use predicates::prelude::*;
 
 #[test]
 fn test_help_message() {
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg("--help")
         .assert()
         .success()
@@ -20,9 +20,68 @@ fn test_help_message() {
         .stdout(predicate::str::contains("--max-depth"));
 }
 
+#[test]
+fn test_long_help_and_man_page() {
+    // The examples and language list are only part of the long help
+    let run = |arg: &str| {
+        Command::from_std(code_stats_command())
+            .arg(arg)
+            .assert()
+            .success()
+    };
+    run("--help")
+        .stdout(predicate::str::contains("--format cloc-json"))
+        .stdout(predicate::str::contains("  Python      .py\n"));
+    run("-h").stdout(predicate::str::contains("Supported languages:").not());
+
+    run("man")
+        .stdout(predicate::str::starts_with(".ie"))
+        .stdout(predicate::str::contains(".TH code-stats-rs 1"))
+        .stdout(predicate::str::contains("Supported languages:"));
+}
+
+#[test]
+fn test_languages_subcommand() {
+    let run = |args: &[&str]| {
+        Command::from_std(code_stats_command())
+            .arg("languages")
+            .args(args)
+            .assert()
+            .success()
+    };
+
+    run(&[])
+        .stdout(predicate::str::contains(
+            "JavaScript\n  Extensions:    .js .jsx\n",
+        ))
+        .stdout(predicate::str::contains("  function:      function_item\n"));
+
+    let output = run(&["--format", "json"]).get_output().stdout.clone();
+    let languages: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let names: Vec<_> = languages
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|language| language["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "Dart",
+            "Go",
+            "Java",
+            "JavaScript",
+            "Python",
+            "Rust",
+            "TypeScript"
+        ]
+    );
+    assert_eq!(languages[6]["magika_labels"][0], "typescript");
+}
+
 #[test]
 fn test_version() {
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     // Version flag is not supported by default in clap v4
     cmd.arg("--version")
         .assert()
@@ -32,7 +91,7 @@ fn test_version() {
 
 #[test]
 fn test_missing_path_argument() {
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.assert()
         .failure()
         .stderr(predicate::str::contains("required arguments"));
@@ -42,7 +101,7 @@ fn test_missing_path_argument() {
 fn test_invalid_format_option() {
     let temp_dir = tempfile::TempDir::new().unwrap();
 
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(temp_dir.path())
         .arg("--format")
         .arg("invalid")
@@ -55,7 +114,7 @@ fn test_invalid_format_option() {
 fn test_multiple_ignore_patterns() {
     let (_temp_dir, project_root) = create_test_project();
 
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(project_root)
         .arg("--ignore")
         .arg(".git")
@@ -72,7 +131,7 @@ fn test_max_depth_validation() {
     let temp_dir = tempfile::TempDir::new().unwrap();
 
     // Valid max-depth
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(temp_dir.path())
         .arg("--max-depth")
         .arg("5")
@@ -80,7 +139,7 @@ fn test_max_depth_validation() {
         .success();
 
     // Invalid max-depth (not a number)
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(temp_dir.path())
         .arg("--max-depth")
         .arg("abc")
@@ -94,7 +153,7 @@ fn test_conflicting_format_options() {
     let temp_dir = tempfile::TempDir::new().unwrap();
 
     // --detail should override --format summary
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(temp_dir.path())
         .arg("--format")
         .arg("summary")
@@ -111,7 +170,7 @@ fn test_path_with_spaces() {
 
     create_test_file(&dir_with_spaces.join("test.rs"), "fn test() {}");
 
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(&dir_with_spaces)
         .assert()
         .success()
@@ -125,7 +184,7 @@ fn test_relative_path() {
     create_test_file(&test_file, "fn test() {}");
 
     // Change to temp directory and use relative path
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.current_dir(temp_dir.path())
         .arg("test.rs")
         .assert()
@@ -139,7 +198,7 @@ fn test_absolute_path() {
     let test_file = temp_dir.path().join("test.rs");
     create_test_file(&test_file, "fn test() {}");
 
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(test_file.to_str().unwrap())
         .assert()
         .success()
@@ -148,7 +207,7 @@ fn test_absolute_path() {
 
 #[test]
 fn test_nonexistent_path() {
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg("/this/path/does/not/exist")
         .assert()
         .failure()
@@ -162,7 +221,7 @@ fn test_file_vs_directory_detection() {
     create_test_file(&test_file, "fn test() {}");
 
     // Test file - should show single file format
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(&test_file)
         .assert()
         .success()
@@ -170,7 +229,7 @@ fn test_file_vs_directory_detection() {
         .stdout(predicate::str::contains("Code Statistics:"));
 
     // Test directory - should show summary format
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(temp_dir.path())
         .assert()
         .success()
@@ -182,7 +241,7 @@ fn test_file_vs_directory_detection() {
 fn test_stdin_not_supported() {
     // Test that we properly handle when no path is provided
     // (stdin input is not supported)
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.assert()
         .failure()
         .stderr(predicate::str::contains("required"));
@@ -192,7 +251,7 @@ fn test_stdin_not_supported() {
 fn test_all_options_combined() {
     let (_temp_dir, project_root) = create_test_project();
 
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    let mut cmd = Command::from_std(code_stats_command());
     cmd.arg(project_root)
         .arg("--format")
         .arg("json")
@@ -207,3 +266,278 @@ fn test_all_options_combined() {
         .success()
         .stdout(predicate::str::is_match(r#"\{[\s\S]*"files"[\s\S]*\}"#).unwrap());
 }
+
+#[test]
+fn test_signed_report_verifies_until_edited() {
+    let (temp_dir, project_root) = create_test_project();
+    let key = temp_dir.path().join("signing.key");
+    let attestation = temp_dir.path().join("report.att.json");
+    let report = temp_dir.path().join("report.json");
+    create_test_file(&key, "not-so-secret\n");
+
+    let output = Command::from_std(code_stats_command())
+        .arg(&project_root)
+        .args(["--format", "json", "--sign"])
+        .arg(&attestation)
+        .arg("--signing-key")
+        .arg(&key)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    std::fs::write(&report, &output.stdout).unwrap();
+
+    let envelope: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&attestation).unwrap()).unwrap();
+    assert_eq!(envelope["payloadType"], "application/vnd.in-toto+json");
+    assert_eq!(envelope["signatures"].as_array().unwrap().len(), 1);
+
+    let verify = |report: &std::path::Path| {
+        let mut cmd = Command::from_std(code_stats_command());
+        cmd.arg("verify")
+            .arg(report)
+            .arg("--attestation")
+            .arg(&attestation)
+            .arg("--key")
+            .arg(&key)
+            .assert()
+    };
+    verify(&report)
+        .success()
+        .stdout(predicate::str::contains("Verified"))
+        .stdout(predicate::str::contains("--format json"));
+
+    let edited = String::from_utf8(output.stdout).unwrap().replacen(
+        "\"function_count\": ",
+        "\"function_count\": 1",
+        1,
+    );
+    std::fs::write(&report, edited).unwrap();
+    verify(&report).failure().stderr(predicate::str::contains(
+        "does not match the attested report",
+    ));
+}
+
+#[test]
+fn test_unsigned_attestation_is_plain_statement() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let source = temp_dir.path().join("main.py");
+    let attestation = temp_dir.path().join("report.att.json");
+    create_test_file(&source, "def main():\n    pass\n");
+
+    Command::from_std(code_stats_command())
+        .arg(&source)
+        .arg("--sign")
+        .arg(&attestation)
+        .assert()
+        .success();
+
+    let statement: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&attestation).unwrap()).unwrap();
+    assert_eq!(statement["_type"], "https://in-toto.io/Statement/v1");
+    let inputs = statement["predicate"]["buildDefinition"]["resolvedDependencies"]
+        .as_array()
+        .unwrap();
+    assert_eq!(inputs.len(), 1);
+    assert_eq!(inputs[0]["name"], source.display().to_string());
+}
+
+#[test]
+fn test_extension_detection_skips_files_without_known_extension() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    create_test_file(&temp_dir.path().join("main.rs"), "fn main() {}\n");
+    create_test_file(
+        &temp_dir.path().join("script"),
+        "#!/usr/bin/env python3\ndef main():\n    pass\n",
+    );
+
+    Command::from_std(code_stats_command())
+        .arg(temp_dir.path())
+        .arg("--detection")
+        .arg("extension")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Total: 1 functions, 0 structs/classes in 1 files",
+        ));
+}
+
+#[test]
+fn test_map_ext_registers_extra_extensions() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    create_test_file(
+        &temp_dir.path().join("fast.pyx"),
+        "def a():\n    pass\n\ndef b():\n    pass\n",
+    );
+
+    let run = |args: &[&str]| {
+        Command::from_std(code_stats_command())
+            .arg(temp_dir.path())
+            .args(["--detection", "extension"])
+            .args(args)
+            .assert()
+    };
+
+    run(&[]).success().stdout(predicate::str::contains(
+        "Total: 0 functions, 0 structs/classes in 0 files",
+    ));
+    run(&["--map-ext", "pyx=python"])
+        .success()
+        .stdout(predicate::str::contains(
+            "Total: 2 functions, 0 structs/classes in 1 files",
+        ));
+    run(&["--map-ext", "pyx=cython"])
+        .failure()
+        .stderr(predicate::str::contains("unknown language `cython`"));
+}
+
+#[test]
+fn test_vendored_and_generated_flags() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    create_test_file(
+        &temp_dir.path().join("node_modules/left-pad/index.js"),
+        "function leftPad() {}\n",
+    );
+    create_test_file(
+        &temp_dir.path().join("schema.py"),
+        "# @generated by codegen\ndef parse():\n    pass\n",
+    );
+    create_test_file(&temp_dir.path().join("app.js"), "function main() {}\n");
+
+    let run = |args: &[&str]| {
+        Command::from_std(code_stats_command())
+            .arg(temp_dir.path())
+            .args(["--detection", "extension"])
+            .args(args)
+            .assert()
+            .success()
+    };
+
+    run(&[])
+        .stdout(predicate::str::contains(
+            "Total: 2 functions, 0 structs/classes in 2 files",
+        ))
+        .stdout(predicate::str::contains(
+            "Vendored: 1 files, 1 code lines, excluded from totals",
+        ));
+    run(&["--include-vendored", "--exclude-generated"])
+        .stdout(predicate::str::contains(
+            "Total: 2 functions, 0 structs/classes in 2 files",
+        ))
+        .stdout(predicate::str::contains(
+            "Generated: 1 files, 2 code lines, excluded from totals",
+        ));
+}
+
+#[test]
+fn test_find_duplicates() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    create_test_file(
+        &temp_dir.path().join("a.py"),
+        "def load():\n    pass\n\ndef save():\n    pass\n",
+    );
+    create_test_file(&temp_dir.path().join("b.py"), "def load():\n    pass\n");
+    create_test_file(&temp_dir.path().join("c.js"), "function load() {}\n");
+
+    Command::from_std(code_stats_command())
+        .arg(temp_dir.path())
+        .args(["--detection", "extension", "--find-duplicates"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Function names defined in 3 or more places:\n  load: 3 definitions",
+        ))
+        .stdout(predicate::str::contains("save").not());
+
+    Command::from_std(code_stats_command())
+        .arg(temp_dir.path())
+        .args(["--detection", "extension", "--find-duplicates"])
+        .args(["--min-duplicates", "4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(none found)"));
+}
+
+#[test]
+fn test_extended_metrics() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    create_test_file(
+        &temp_dir.path().join("main.py"),
+        "def main(x):\n    if x and x > 1:\n        return 1\n    return 0\n",
+    );
+
+    let run = |args: &[&str]| {
+        Command::from_std(code_stats_command())
+            .arg(temp_dir.path())
+            .args(["--detection", "extension"])
+            .args(args)
+            .assert()
+            .success()
+    };
+
+    run(&["--format", "json"]).stdout(predicate::str::contains("maintainability_index").not());
+    run(&["--format", "json", "--metrics", "extended"])
+        .stdout(predicate::str::contains("\"cyclomatic_complexity\": 3"))
+        .stdout(predicate::str::contains("\"volume\": "));
+    run(&["--detail", "--metrics", "extended"])
+        .stdout(predicate::str::contains("  Halstead volume: "))
+        .stdout(predicate::str::contains("(cyclomatic complexity 3)"));
+}
+
+#[test]
+fn test_content_hashes() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("main.rs");
+    create_test_file(&path, "fn main() {}\n");
+    let hash = "536e506bb90914c243a12b397b9a998f85ae2cbd9ba02dfd03a9e155ca5ca0f4";
+
+    let run = |target: &std::path::Path, args: &[&str]| {
+        Command::from_std(code_stats_command())
+            .arg(target)
+            .args(["--detection", "extension"])
+            .args(args)
+            .assert()
+            .success()
+    };
+
+    run(temp_dir.path(), &["--format", "json"]).stdout(predicate::str::contains("sha256").not());
+    run(temp_dir.path(), &["--format", "json", "--hash"])
+        .stdout(predicate::str::contains(format!("\"sha256\": \"{hash}\"")));
+    run(temp_dir.path(), &["--detail", "--hash"])
+        .stdout(predicate::str::contains(format!("  SHA-256: {hash}\n")));
+    run(&path, &["--hash"]).stdout(predicate::str::contains(format!("SHA-256: {hash}")));
+}
+
+#[test]
+fn test_count_anonymous() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    create_test_file(
+        &temp_dir.path().join("app.js"),
+        "const handler = () => 1;\nfunction main() {\n    [1, 2].forEach((x) => x);\n}\n",
+    );
+    create_test_file(
+        &temp_dir.path().join("main.rs"),
+        "fn main() {\n    let add = |a: i32| a + 1;\n}\n",
+    );
+
+    let run = |args: &[&str]| {
+        Command::from_std(code_stats_command())
+            .arg(temp_dir.path())
+            .args(["--detection", "extension", "--no-align"])
+            .args(args)
+            .assert()
+            .success()
+    };
+
+    // JavaScript counts its anonymous functions, Rust not its closures
+    run(&[]).stdout(predicate::str::contains("Total: 4 functions"));
+    run(&["--count-anonymous", "all"]).stdout(predicate::str::contains("Total: 5 functions"));
+    run(&["--count-anonymous", "none"]).stdout(predicate::str::contains("Total: 2 functions"));
+    run(&["--count-anonymous", "top-level"]).stdout(predicate::str::contains("Total: 3 functions"));
+
+    // A config file overrides the command line for its subtree
+    create_test_file(
+        &temp_dir.path().join(".code-stats.toml"),
+        "[count]\nanonymous_functions = \"none\"\n",
+    );
+    run(&["--count-anonymous", "all"]).stdout(predicate::str::contains("Total: 2 functions"));
+}
@@ -299,10 +299,18 @@ class ClassOne:
     (temp_dir, root)
 }
 
+/// Create a command running the code-stats-rs binary, with its cache below
+/// the target directory instead of the user's cache directory
+pub fn code_stats_command() -> std::process::Command {
+    let mut cmd = std::process::Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
+    cmd.env("XDG_CACHE_HOME", env!("CARGO_TARGET_TMPDIR"));
+    cmd
+}
+
 /// Helper to run the code-stats-rs binary with args
 pub fn run_code_stats(args: &[&str]) -> std::process::Output {
-    let mut cmd = std::process::Command::new(env!("CARGO_BIN_EXE_code-stats-rs"));
-    cmd.args(args)
+    code_stats_command()
+        .args(args)
         .output()
         .expect("Failed to run code-stats-rs")
 }
@@ -325,6 +333,13 @@ pub fn create_symlink(src: &Path, dst: &Path) {
     std::os::unix::fs::symlink(src, dst).unwrap();
 }
 
+/// Create an entry that cannot be analyzed: a symbolic link to a missing
+/// file, which directory traversal reports as an error with --follow-links
+#[cfg(unix)]
+pub fn create_broken_link(path: &Path) {
+    std::os::unix::fs::symlink(path.with_extension("missing"), path).unwrap();
+}
+
 #[cfg(windows)]
 pub fn create_symlink(src: &Path, dst: &Path) {
     // On Windows, we'll just copy the directory for testing purposes
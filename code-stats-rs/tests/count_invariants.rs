@@ -0,0 +1,159 @@
+//! Property-based checks of the counting invariants behind `count_source`.
+
+use code_stats_rs::{CodeStats, SupportedLanguage, count_source};
+use proptest::prelude::*;
+
+/// Top-level snippets per language with their expected (functions, classes).
+///
+/// Counts follow the current node rules: interfaces, traits and enums have
+/// their own counters and are not counted as classes.
+fn snippets(language: SupportedLanguage) -> &'static [(&'static str, usize, usize)] {
+    match language {
+        SupportedLanguage::Rust => &[
+            ("fn a() {}\n", 1, 0),
+            ("struct S { x: i32 }\n", 0, 1),
+            ("enum E { A, B }\n", 0, 0),
+            ("impl S {\n    fn m(&self) {}\n}\n", 1, 0),
+            ("const N: u32 = 3;\n", 0, 0),
+            ("fn outer() {\n    let f = |x: i32| x + 2;\n}\n", 1, 0),
+        ],
+        SupportedLanguage::Go => &[
+            ("func A() {}\n", 1, 0),
+            ("type S struct {\n\tX int\n}\n", 0, 1),
+            ("func (s S) M() int {\n\treturn 2\n}\n", 1, 0),
+            ("var v = 3\n", 0, 0),
+        ],
+        SupportedLanguage::Python => &[
+            ("def a():\n    pass\n", 1, 0),
+            ("class C:\n    def m(self):\n        return 2\n", 1, 1),
+            ("x = [1, 2]\n", 0, 0),
+            ("async def b():\n    pass\n", 1, 0),
+        ],
+        SupportedLanguage::JavaScript => &[
+            ("function a() {}\n", 1, 0),
+            ("class C {\n  m() {}\n}\n", 1, 1),
+            ("const f = (x) => x * 2;\n", 1, 0),
+            ("let v = 3;\n", 0, 0),
+        ],
+        SupportedLanguage::TypeScript => &[
+            ("function a(): void {}\n", 1, 0),
+            ("class C {\n  m(): number { return 2; }\n}\n", 1, 1),
+            ("interface I {\n  x: number;\n}\n", 0, 0),
+            ("let v: number = 3;\n", 0, 0),
+        ],
+        SupportedLanguage::Java => &[
+            ("class A {\n    void m() {}\n}\n", 1, 1),
+            ("interface I {}\n", 0, 0),
+            ("enum E { X, Y }\n", 0, 0),
+            ("class B {\n    B() {}\n}\n", 1, 1),
+        ],
+        SupportedLanguage::Dart => &[
+            ("int a() => 2;\n", 1, 0),
+            ("class C {\n  C();\n  void m() {}\n}\n", 2, 1),
+            ("mixin M {}\n", 0, 0),
+            ("enum E { x, y }\n", 0, 0),
+            ("var v = 3;\n", 0, 0),
+        ],
+    }
+}
+
+/// Line comment syntax per language.
+fn line_comment(language: SupportedLanguage) -> &'static str {
+    match language {
+        SupportedLanguage::Python => "# fn fake() {} class Fake: def fake(): pass\n",
+        _ => "// fn fake() {} class Fake {} def fake(): function fake() {}\n",
+    }
+}
+
+fn any_language() -> impl Strategy<Value = SupportedLanguage> {
+    prop_oneof![
+        Just(SupportedLanguage::Rust),
+        Just(SupportedLanguage::Go),
+        Just(SupportedLanguage::Python),
+        Just(SupportedLanguage::JavaScript),
+        Just(SupportedLanguage::TypeScript),
+        Just(SupportedLanguage::Java),
+        Just(SupportedLanguage::Dart),
+    ]
+}
+
+/// Builds a source file from snippet indices, with the Go package clause when needed.
+fn build_source(language: SupportedLanguage, picks: &[usize]) -> String {
+    let pool = snippets(language);
+    let mut source = String::new();
+    if language == SupportedLanguage::Go {
+        source.push_str("package main\n");
+    }
+    for &pick in picks {
+        source.push_str(pool[pick % pool.len()].0);
+    }
+    source
+}
+
+fn expected_counts(language: SupportedLanguage, picks: &[usize]) -> (usize, usize) {
+    let pool = snippets(language);
+    picks.iter().fold((0, 0), |(functions, classes), &pick| {
+        let (_, f, c) = pool[pick % pool.len()];
+        (functions + f, classes + c)
+    })
+}
+
+fn structural(stats: &CodeStats) -> (usize, usize) {
+    (stats.function_count, stats.class_struct_count)
+}
+
+proptest! {
+    /// Each snippet contributes exactly its documented counts.
+    #[test]
+    fn counts_match_snippet_totals(
+        language in any_language(),
+        picks in prop::collection::vec(0usize..16, 0..10),
+    ) {
+        let stats = count_source(language, &build_source(language, &picks));
+        prop_assert_eq!(structural(&stats), expected_counts(language, &picks));
+    }
+
+    /// Counting the concatenation of two sources equals the sum of their counts.
+    #[test]
+    fn concatenation_sums_counts(
+        language in any_language(),
+        left in prop::collection::vec(0usize..16, 0..8),
+        right in prop::collection::vec(0usize..16, 0..8),
+    ) {
+        let mut combined = left.clone();
+        combined.extend(&right);
+
+        let mut summed = count_source(language, &build_source(language, &left));
+        let right_stats = count_source(language, &build_source(language, &right));
+        summed.merge(&right_stats);
+
+        let concatenated = count_source(language, &build_source(language, &combined));
+        prop_assert_eq!(structural(&concatenated), structural(&summed));
+    }
+
+    /// Comment lines never change structural counts, and are counted as comment lines.
+    #[test]
+    fn comments_never_change_counts(
+        language in any_language(),
+        picks in prop::collection::vec(0usize..16, 0..8),
+        comment_after in prop::collection::vec(any::<bool>(), 8),
+    ) {
+        let pool = snippets(language);
+        let mut commented = build_source(language, &[]);
+        let mut comment_count = 0;
+        for (i, &pick) in picks.iter().enumerate() {
+            commented.push_str(pool[pick % pool.len()].0);
+            if comment_after[i] {
+                commented.push_str(line_comment(language));
+                comment_count += 1;
+            }
+        }
+
+        let plain = count_source(language, &build_source(language, &picks));
+        let with_comments = count_source(language, &commented);
+
+        prop_assert_eq!(structural(&with_comments), structural(&plain));
+        prop_assert_eq!(with_comments.comment_lines, plain.comment_lines + comment_count);
+        prop_assert_eq!(with_comments.code_lines, plain.code_lines);
+    }
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use assert_cmd::Command;
+use common::{code_stats_command, create_test_file, parse_json_output};
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+/// Create a project and save its JSON report as `baseline.json` next to it
+fn create_project_with_baseline() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("project");
+
+    create_test_file(&root.join("src/lib.rs"), "fn a() {}\nfn b() {}\n");
+    create_test_file(&root.join("src/old.rs"), "struct Old;\n");
+    create_test_file(&root.join("app.py"), "def main():\n    pass\n");
+
+    let output = Command::from_std(code_stats_command())
+        .current_dir(&root)
+        .args([".", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    fs::write(temp_dir.path().join("baseline.json"), &output.stdout).unwrap();
+
+    temp_dir
+}
+
+#[test]
+fn test_diff_reports_language_and_file_changes() {
+    let temp_dir = create_project_with_baseline();
+    let root = temp_dir.path().join("project");
+
+    create_test_file(
+        &root.join("src/lib.rs"),
+        "fn a() {}\nfn b() {}\nfn c() {}\n",
+    );
+    create_test_file(&root.join("src/new.rs"), "fn d() {}\n");
+    fs::remove_file(root.join("src/old.rs")).unwrap();
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.current_dir(&root)
+        .args(["diff", "--baseline", "../baseline.json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Changes since baseline:"))
+        .stdout(predicate::str::contains(
+            "Rust:        +2 functions (2 -> 4), -1 structs/classes (1 -> 0)",
+        ))
+        .stdout(predicate::str::contains("Python").not())
+        .stdout(predicate::str::contains("added    src/new.rs: 1 functions"))
+        .stdout(predicate::str::contains("removed  src/old.rs"))
+        .stdout(predicate::str::contains(
+            "modified src/lib.rs: +1 functions (2 -> 3)",
+        ));
+}
+
+#[test]
+fn test_diff_json_output() {
+    let temp_dir = create_project_with_baseline();
+    let root = temp_dir.path().join("project");
+
+    create_test_file(&root.join("src/new.rs"), "fn d() {}\n");
+
+    let output = Command::from_std(code_stats_command())
+        .current_dir(&root)
+        .args(["diff", "--baseline", "../baseline.json", "--format", "json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(json["total"]["files"]["before"], 3);
+    assert_eq!(json["total"]["files"]["after"], 4);
+    assert_eq!(json["files"][0]["status"], "added");
+    assert_eq!(json["files"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_diff_without_changes() {
+    let temp_dir = create_project_with_baseline();
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.current_dir(temp_dir.path().join("project"))
+        .args(["diff", "--baseline", "../baseline.json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes since baseline"));
+}
+
+#[test]
+fn test_diff_reports_moved_files_as_renames() {
+    let temp_dir = create_project_with_baseline();
+    let root = temp_dir.path().join("project");
+
+    let diff = || {
+        let mut cmd = Command::from_std(code_stats_command());
+        cmd.current_dir(&root)
+            .args(["diff", "--baseline", "../baseline.json"])
+            .assert()
+            .success()
+    };
+
+    // Renames are only detected against a baseline saved with --hash
+    fs::create_dir(root.join("src/legacy")).unwrap();
+    fs::rename(root.join("src/old.rs"), root.join("src/legacy/old.rs")).unwrap();
+    diff()
+        .stdout(predicate::str::contains("added    src/legacy/old.rs"))
+        .stdout(predicate::str::contains("removed  src/old.rs"));
+
+    fs::rename(root.join("src/legacy/old.rs"), root.join("src/old.rs")).unwrap();
+    let output = Command::from_std(code_stats_command())
+        .current_dir(&root)
+        .args([".", "--format", "json", "--hash"])
+        .output()
+        .unwrap();
+    fs::write(temp_dir.path().join("baseline.json"), &output.stdout).unwrap();
+    fs::rename(root.join("src/old.rs"), root.join("src/legacy/old.rs")).unwrap();
+    diff()
+        .stdout(predicate::str::contains("No changes since baseline").not())
+        .stdout(predicate::str::contains(
+            "renamed  src/old.rs -> src/legacy/old.rs",
+        ))
+        .stdout(predicate::str::contains("added").not())
+        .stdout(predicate::str::contains("removed").not());
+}
+
+#[test]
+fn test_diff_ratchet_fails_when_a_metric_grows() {
+    let temp_dir = create_project_with_baseline();
+    let root = temp_dir.path().join("project");
+
+    // Python grew, src shrank
+    create_test_file(
+        &root.join("app.py"),
+        "def main():\n    pass\ndef extra():\n    pass\n",
+    );
+    fs::remove_file(root.join("src/old.rs")).unwrap();
+
+    let ratchet = |rules: &[&str]| {
+        let mut cmd = Command::from_std(code_stats_command());
+        cmd.current_dir(&root)
+            .args(["diff", "--baseline", "../baseline.json"]);
+        for rule in rules {
+            cmd.args(["--ratchet", rule]);
+        }
+        cmd.assert()
+    };
+
+    ratchet(&["classes", "functions@src", "files@src"]).success();
+    ratchet(&["functions", "classes@src"])
+        .code(2)
+        .stdout(predicate::str::contains("Changes since baseline:"))
+        .stderr(predicate::str::contains(
+            "ratchet violated: functions increased from 3 to 4",
+        ));
+}
+
+#[test]
+fn test_diff_rejects_text_baseline() {
+    let temp_dir = create_project_with_baseline();
+    create_test_file(&temp_dir.path().join("summary.txt"), "Summary:\n");
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.current_dir(temp_dir.path().join("project"))
+        .args(["diff", "--baseline", "../summary.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid report"));
+}
+
+#[test]
+fn test_compare_two_trees_side_by_side() {
+    let temp_dir = TempDir::new().unwrap();
+    let upstream = temp_dir.path().join("upstream");
+    let fork = temp_dir.path().join("fork");
+    for root in [&upstream, &fork] {
+        create_test_file(&root.join("src/lib.rs"), "fn a() {}\nfn b() {}\n");
+        create_test_file(&root.join("app.py"), "def main():\n    pass\n");
+    }
+    create_test_file(&fork.join("src/extra.rs"), "fn c() {}\nstruct Extra;\n");
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.current_dir(temp_dir.path())
+        .args(["compare", "upstream", "fork"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("A: upstream\nB: fork\n"))
+        .stdout(predicate::str::is_match(r"Python\s*┆\s*1\s*┆\s*1\s*┆\s*0\s*┆").unwrap())
+        .stdout(predicate::str::is_match(r"Rust\s*┆\s*1\s*┆\s*2\s*┆\s*\+1\s*┆").unwrap())
+        .stdout(predicate::str::contains(
+            "Files: 0 only in A, 1 only in B, 0 moved, 0 with different counts",
+        ));
+
+    let output = Command::from_std(code_stats_command())
+        .current_dir(temp_dir.path())
+        .args(["compare", "upstream", "fork", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(json["a"], "upstream");
+    assert_eq!(json["total"]["functions"]["before"], 3);
+    assert_eq!(json["total"]["functions"]["after"], 4);
+    assert_eq!(json["languages"].as_array().unwrap().len(), 2);
+    assert_eq!(json["files"][0]["path"], "src/extra.rs");
+    assert_eq!(json["files"][0]["status"], "added");
+}
@@ -1,8 +1,8 @@
 mod common;
 
 use common::{
-    assert_contains_all, create_controlled_test_project, create_symlink, create_test_file,
-    create_test_project, run_code_stats,
+    assert_contains_all, code_stats_command, create_broken_link, create_controlled_test_project,
+    create_symlink, create_test_file, create_test_project, parse_json_output, run_code_stats,
 };
 use std::fs;
 
@@ -42,10 +42,10 @@ fn test_directory_analysis_with_controlled_counts() {
 
     assert!(output.status.success());
 
-    // Rust: 3 functions (2 + 1), 4 structs/classes (1 + 1 struct + 1 enum)
+    // Rust: 3 functions (2 + 1), 2 structs/classes (the enum is counted separately)
     assert!(stdout.contains("Rust:"));
     assert!(
-        stdout.contains("3 functions") && stdout.contains("4 structs/classes"),
+        stdout.contains("3 functions") && stdout.contains("2 structs/classes"),
         "Unexpected Rust counts in output:\n{}",
         stdout
     );
@@ -236,12 +236,41 @@ fn test_follow_links_option() {
     assert!(stdout_no_follow.contains("1 functions"));
 
     // With --follow-links, symlink should be followed
-    let output_follow = run_code_stats(&[root.to_str().unwrap(), "--follow-links"]);
+    let output_follow = run_code_stats(&[root.to_str().unwrap(), "--follow-links", "--detail"]);
     let stdout_follow = String::from_utf8_lossy(&output_follow.stdout);
 
     assert!(output_follow.status.success());
-    // Should count the file twice (once in source, once through link)
-    assert!(stdout_follow.contains("2 functions"));
+    // The file reached through the link is the same file, so it counts once
+    assert!(
+        stdout_follow.contains("1 functions"),
+        "stdout: {stdout_follow}"
+    );
+    assert!(stdout_follow.contains("(duplicate): skipped: same file as"));
+
+    // Unless each path should count
+    let output_no_dedup = run_code_stats(&[root.to_str().unwrap(), "--follow-links", "--no-dedup"]);
+    let stdout_no_dedup = String::from_utf8_lossy(&output_no_dedup.stdout);
+
+    assert!(output_no_dedup.status.success());
+    assert!(stdout_no_dedup.contains("2 functions"));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_follow_links_skips_symlink_loops() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("src/lib.rs"), "fn lib() {}");
+    create_symlink(root, &root.join("src/back_to_root"));
+
+    let output = run_code_stats(&[root.to_str().unwrap(), "--follow-links", "--detail"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(stdout.contains("1 functions"), "stdout: {stdout}");
+    assert!(stdout.contains("(symlink_loop): skipped: symbolic link loop back to"));
+    assert!(!stdout.contains("Findings: 1 errors"));
 }
 
 #[test]
@@ -264,6 +293,79 @@ fn test_directory_with_errors() {
     assert!(stdout.contains("1 functions"));
 }
 
+#[test]
+fn test_malformed_text_in_a_forced_encoding_is_skipped() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("valid.rs"), "fn valid() {}");
+    fs::write(root.join("invalid.rs"), [b'f', 0xC3, 0x28]).unwrap();
+
+    let output = run_code_stats(&[root.to_str().unwrap(), "--detail", "--encoding", "utf-8"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // The valid file is still counted and the malformed one reported
+    assert!(output.status.success());
+    assert!(stdout.contains("1 functions"));
+    assert!(stdout.contains("invalid.rs (encoding): skipped: malformed text"));
+}
+
+#[test]
+fn test_legacy_encodings_are_transcoded() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    // Shift-JIS: "Loads the database connection settings"
+    let (shift_jis, _, _) = encoding_rs::SHIFT_JIS
+        .encode("// データベース接続の設定を読み込む関数\nfn load_settings() {}\n");
+    fs::write(root.join("settings.rs"), shift_jis).unwrap();
+    let (latin1, _, _) =
+        encoding_rs::WINDOWS_1252.encode("# r\u{e9}sum\u{e9}\ndef resume():\n    pass\n");
+    fs::write(root.join("resume.py"), latin1).unwrap();
+    let root = root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--detection", "extension"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("2 functions"), "stdout: {stdout}");
+    assert!(!stdout.contains("Findings:"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_binary_minified_and_large_files_are_skipped() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("valid.js"), "function valid() {}\n");
+    fs::write(root.join("blob.js"), b"function f() {}\0\x01\x02").unwrap();
+    let minified = format!("function m(){{{}}}\n", "a=1;".repeat(2_000));
+    create_test_file(&root.join("bundle.min.js"), &minified);
+    let root = root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--detail", "--detection", "extension"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("in 1 files"), "stdout: {stdout}");
+    assert!(stdout.contains("blob.js (binary): skipped: binary content"));
+    assert!(
+        stdout.contains("bundle.min.js (long_lines): skipped: line of 8014 bytes, likely minified")
+    );
+
+    let output = run_code_stats(&[
+        root,
+        "--detail",
+        "--detection",
+        "extension",
+        "--max-file-size",
+        "10",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("valid.js (too_large): skipped: 20 bytes, larger than 10"),
+        "stdout: {stdout}"
+    );
+}
+
 #[test]
 fn test_directory_not_found() {
     let output = run_code_stats(&["/nonexistent/directory/path"]);
@@ -276,3 +378,906 @@ fn test_directory_not_found() {
         stderr
     );
 }
+
+#[test]
+fn test_nested_config_files_override_subtree() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join(".code-stats.toml"), "ignore = [\"vendor\"]\n");
+    create_test_file(&root.join("main.rs"), "fn main() {}");
+    create_test_file(&root.join("vendor/dep.rs"), "fn dep() {}");
+    create_test_file(
+        &root.join("team/.code-stats.toml"),
+        "ignore = [\"generated\"]\n",
+    );
+    create_test_file(&root.join("team/lib.rs"), "fn lib() {}");
+    create_test_file(&root.join("team/generated/api.rs"), "fn api() {}");
+    // The team's patterns only apply to its own subtree
+    create_test_file(&root.join("generated/top.rs"), "fn top() {}");
+
+    let output = run_code_stats(&[root.to_str().unwrap(), "--detail"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(stdout.contains("Total: 3 functions"), "stdout: {stdout}");
+    assert!(!stdout.contains("dep.rs"));
+    assert!(!stdout.contains("api.rs"));
+    assert!(stdout.contains("top.rs"));
+}
+
+#[test]
+fn test_config_file_kind_rules() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("main.rs"), "fn main() {}\ntrait Shape {}\n");
+    create_test_file(
+        &root.join("macros/.code-stats.toml"),
+        "[rules.rust]\nmacro_definition = \"function\"\ntrait_item = \"class\"\n",
+    );
+    create_test_file(
+        &root.join("macros/lib.rs"),
+        "macro_rules! square { ($x:expr) => { $x * $x }; }\ntrait Area {}\n",
+    );
+
+    let output = run_code_stats(&[root.to_str().unwrap(), "--detail"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Total: 2 functions, 1 structs/classes"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_invalid_config_file_fails_analysis() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("main.rs"), "fn main() {}");
+    create_test_file(
+        &root.join("sub/.code-stats.toml"),
+        "ignore = \"not-a-list\"\n",
+    );
+    create_test_file(&root.join("sub/lib.rs"), "fn lib() {}");
+
+    let output = run_code_stats(&[root.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("Invalid configuration"), "stderr: {stderr}");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_status_line_on_stderr() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("valid.rs"), "fn valid() {}\nstruct S;");
+    create_broken_link(&root.join("broken.rs"));
+
+    for format in ["summary", "json"] {
+        let output =
+            run_code_stats(&[root.to_str().unwrap(), "--format", format, "--follow-links"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(output.status.success());
+        assert!(
+            stderr.starts_with("code-stats: files=1 functions=1 classes=1 errors=1 duration_ms="),
+            "stderr: {stderr}"
+        );
+    }
+
+    let output = run_code_stats(&["/nonexistent/directory/path"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("code-stats: files=0 functions=0 classes=0 errors=1"));
+}
+
+#[test]
+fn test_mmap_matches_regular_reading() {
+    let (_temp_dir, project_root) = create_controlled_test_project();
+    let root = project_root.to_str().unwrap();
+
+    let regular = run_code_stats(&[root, "--format", "json"]);
+    let regular = common::parse_json_output(&String::from_utf8_lossy(&regular.stdout));
+
+    for mmap in [&["--mmap"][..], &["--mmap-threshold", "100"]] {
+        let mapped = run_code_stats(&[&[root, "--format", "json"][..], mmap].concat());
+        assert!(mapped.status.success());
+        let mapped = common::parse_json_output(&String::from_utf8_lossy(&mapped.stdout));
+        assert_eq!(regular["total_stats"], mapped["total_stats"]);
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_fail_on_severity() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("valid.rs"), "fn valid() {}");
+    create_broken_link(&root.join("broken.rs"));
+    let root = root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--follow-links"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Findings: 1 errors"), "stdout: {stdout}");
+
+    let output = run_code_stats(&[root, "--follow-links", "--fail-on", "error"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("1 findings at or above severity error"),
+        "stderr: {stderr}"
+    );
+    // The report and status line are still written before failing
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 functions"));
+    assert!(stderr.contains("errors=1"));
+
+    let output = run_code_stats(&[root, "--follow-links", "--strict"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // The more specific failures of --fail-if and --check are kept
+    let output = run_code_stats(&[
+        root,
+        "--follow-links",
+        "--check",
+        "--fail-if",
+        "functions>0",
+        "--fail-on",
+        "info",
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("threshold exceeded"), "stderr: {stderr}");
+    let output = run_code_stats(&[root, "--follow-links", "--check", "--strict"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    // Skipped files are not errors
+    let output = run_code_stats(&[root, "--strict"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_profile_lists_slowest_files() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_file(&root.join("small.rs"), "fn small() {}\n");
+    create_test_file(&root.join("large.rs"), &"fn large() {}\n".repeat(5000));
+    let root = root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--format", "json", "--profile", "1"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains("Profile of 2 files:"), "stderr: {stderr}");
+    assert!(stderr.contains("\n  Parsing: "), "stderr: {stderr}");
+    let slowest: Vec<_> = stderr
+        .lines()
+        .skip_while(|line| *line != "Slowest files:")
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .collect();
+    assert_eq!(slowest.len(), 1, "stderr: {stderr}");
+    assert!(
+        slowest[0].contains(" large.rs (detection "),
+        "stderr: {stderr}"
+    );
+    // The report itself is untouched
+    parse_json_output(&String::from_utf8_lossy(&output.stdout));
+
+    let file = format!("{root}/small.rs");
+    let output = run_code_stats(&[&file, "--profile", "1"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_warnings_list_recoverable_issues() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_file(&root.join("main.rs"), "fn main() {}\n");
+    create_test_file(&root.join("big.rs"), &"fn big() {}\n".repeat(100));
+    create_test_file(&root.join("native.c"), "int main(void) { return 0; }\n");
+    create_test_file(&root.join("notes.txt"), "not code\n");
+    let root = root.to_str().unwrap();
+    let args = [root, "--detection", "extension", "--max-file-size", "100"];
+
+    let output = run_code_stats(&[&args[..], &["--warnings"]].concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains(
+            "\n\nFindings: 0 errors, 2 warnings, 0 info\n  \
+             warning: big.rs: skipped: 1200 bytes, larger than 100\n  \
+             warning: native.c: skipped: .c source code, which is not supported"
+        ),
+        "stdout: {stdout}"
+    );
+    assert!(!stdout.contains("notes.txt"), "stdout: {stdout}");
+
+    let output = run_code_stats(&[&args[..], &["--warnings", "--format", "json"]].concat());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let findings = json["findings"].as_array().unwrap();
+    // The oversized file is reported once, as a warning instead of as info
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[1]["severity"], "warning");
+    assert_eq!(findings[1]["path"], "native.c");
+    assert_eq!(findings[1]["kind"], "unsupported");
+    assert_eq!(json["unsupported_files"], 2);
+
+    // Without --warnings, the oversized file is only an info finding
+    let output = run_code_stats(&args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("Findings:"), "stdout: {stdout}");
+    let output = run_code_stats(&[&args[..], &["--format", "json"]].concat());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(json["findings"][0]["severity"], "info");
+    assert_eq!(json["findings"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_archive_is_analyzed_like_a_directory() {
+    use std::io::Write;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let archive = temp_dir.path().join("release-1.0.zip");
+    let mut writer = zip::ZipWriter::new(fs::File::create(&archive).unwrap());
+    let options = zip::write::SimpleFileOptions::default();
+    for (name, content) in [
+        ("release-1.0/src/main.rs", "fn main() {}\nstruct App;\n"),
+        ("release-1.0/tools/gen.py", "def gen():\n    pass\n"),
+        ("release-1.0/README.md", "# Release\n"),
+        ("../escape.rs", "fn escape() {}\n"),
+    ] {
+        writer.start_file(name, options).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let output = run_code_stats(&[archive.to_str().unwrap(), "--format", "json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    let json = parse_json_output(&stdout);
+    assert_eq!(json["total_stats"]["function_count"], 2);
+    let paths: Vec<_> = json["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        paths,
+        ["release-1.0/src/main.rs", "release-1.0/tools/gen.py"]
+    );
+    // Archive members have no modification time
+    let output = run_code_stats(&[archive.to_str().unwrap(), "--format", "json", "--mtime"]);
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(json["files"][0]["stats"]["byte_count"], 25);
+    assert!(json["files"][0].get("modified").is_none());
+    // Nothing was extracted next to the archive
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+    // The attestation covers the archive, not its members
+    let attestation = temp_dir.path().join("report.att.json");
+    let output = run_code_stats(&[
+        archive.to_str().unwrap(),
+        "--sign",
+        attestation.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let statement = parse_json_output(&fs::read_to_string(&attestation).unwrap());
+    let inputs = statement["predicate"]["buildDefinition"]["resolvedDependencies"]
+        .as_array()
+        .unwrap();
+    assert_eq!(inputs.len(), 1);
+    assert_eq!(inputs[0]["name"], archive.display().to_string());
+}
+
+#[test]
+fn test_traversal_limits_fail_the_run() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_file(&root.join("a.rs"), "fn a() {}\n");
+    create_test_file(&root.join("b.rs"), "fn b() {}\n");
+    let root = root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--max-files", "1"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "stderr: {stderr}");
+    assert!(
+        stderr.contains("Limit exceeded: more than 1 files (--max-files)"),
+        "stderr: {stderr}"
+    );
+
+    let output = run_code_stats(&[root, "--max-files", "2", "--max-total-bytes", "100"]);
+    assert!(output.status.success());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_check_exit_codes() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("valid.rs"), "fn valid() {}");
+    let root = root.to_str().unwrap();
+
+    // Only the status line is written
+    let output = run_code_stats(&[root, "--check"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("files=1"));
+
+    let output = run_code_stats(&[root, "--check", "--fail-if", "files>=1"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    create_broken_link(&temp_dir.path().join("broken.rs"));
+    let output = run_code_stats(&[root, "--check", "--follow-links"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(3), "stderr: {stderr}");
+    assert!(
+        stderr.contains("1 files could not be analyzed"),
+        "stderr: {stderr}"
+    );
+    // A violated rule takes precedence over errored files
+    let output = run_code_stats(&[root, "--check", "--follow-links", "--fail-if", "files>=1"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    // Usage errors exit with 1, leaving 2 for violated rules
+    let output = run_code_stats(&[root, "--check", "--format", "invalid"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+#[cfg(unix)]
+fn test_errored_files_are_reported() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("valid.rs"), "fn valid() {}");
+    create_broken_link(&root.join("broken.rs"));
+    let root = root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--follow-links", "--detail"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("Skipped/Errored files:"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("broken.rs (io): "), "stdout: {stdout}");
+
+    let output = run_code_stats(&[root, "--follow-links", "--format", "json"]);
+    let json = common::parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let errors = json["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]["path"].as_str().unwrap().ends_with("broken.rs"));
+    assert_eq!(errors[0]["kind"], "io");
+}
+
+#[test]
+fn test_fail_if_threshold() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_file(&root.join("small.rs"), "fn a() {}\n");
+    create_test_file(&root.join("big.rs"), "fn a() {}\nfn b() {}\nfn c() {}\n");
+    let root = root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--fail-if", "functions_per_file>3"]);
+    assert!(output.status.success());
+
+    let output = run_code_stats(&[
+        root,
+        "--fail-if",
+        "functions_per_file>2",
+        "--fail-if",
+        "files>=2",
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "stderr: {stderr}");
+    assert!(stderr.contains("functions_per_file>2"), "stderr: {stderr}");
+    assert!(stderr.contains("big.rs is 3"), "stderr: {stderr}");
+    assert!(stderr.contains("files>=2 (files is 2)"), "stderr: {stderr}");
+    // The report is still written before failing
+    assert!(String::from_utf8_lossy(&output.stdout).contains("4 functions"));
+
+    // Unknown metrics are rejected before analyzing
+    let output = run_code_stats(&[root, "--fail-if", "max_complexity>15"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("unknown metric `max_complexity`"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn test_ignore_patterns_match_either_separator() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_file(&root.join("src/generated/api.rs"), "fn generated() {}\n");
+    create_test_file(&root.join("src/main.rs"), "fn main() {}\n");
+
+    // The same pattern works whichever separator the checkout uses
+    for pattern in ["src/generated", r"src\generated"] {
+        let output = run_code_stats(&[root.to_str().unwrap(), "--ignore", pattern]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(output.status.success());
+        assert!(
+            stdout.contains("Total: 1 functions"),
+            "pattern {pattern}: {stdout}"
+        );
+    }
+}
+
+#[test]
+fn test_cache_dir_is_written_and_no_cache_skips_it() {
+    let (temp_dir, project_root) = create_test_project();
+    let root = project_root.to_str().unwrap();
+    let cache_dir = temp_dir.path().join("cache");
+
+    for _ in 0..2 {
+        let output = run_code_stats(&[root, "--cache-dir", cache_dir.to_str().unwrap()]);
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Total:"));
+    }
+    // One cache file and the record of its root
+    assert_eq!(fs::read_dir(cache_dir.join("files")).unwrap().count(), 2);
+
+    let unused = temp_dir.path().join("unused");
+    let output = code_stats_command()
+        .args([root, "--no-cache"])
+        .env("XDG_CACHE_HOME", &unused)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!unused.exists());
+}
+
+#[test]
+fn test_git_diff_and_staged_restrict_analysis() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.email", "test@example.com"]);
+    git(&["config", "user.name", "Test"]);
+    create_test_file(&root.join("old.rs"), "fn old() {}\n");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+
+    create_test_file(&root.join("staged.py"), "def staged():\n    pass\n");
+    create_test_file(
+        &root.join("unstaged.js"),
+        "function a() {}\nfunction b() {}\n",
+    );
+    git(&["add", "staged.py"]);
+
+    let root_arg = root.to_str().unwrap();
+    let output = run_code_stats(&[root_arg, "--no-cache", "--staged"]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &["Total: 1 functions, 0 structs/classes in 1 files"],
+    );
+
+    let output = run_code_stats(&[root_arg, "--no-cache", "--git-diff", "HEAD"]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &["Total: 3 functions, 0 structs/classes in 2 files"],
+    );
+
+    let output = run_code_stats(&[root_arg, "--no-cache", "--git-diff", "no-such-ref"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Git command failed"));
+}
+
+#[test]
+fn test_rev_analyzes_committed_contents_without_checkout() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.email", "test@example.com"]);
+    git(&["config", "user.name", "Test"]);
+    create_test_file(&root.join("lib.rs"), "fn a() {}\n");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+
+    create_test_file(&root.join("lib.rs"), "fn a() {}\nfn b() {}\nstruct S;\n");
+    create_test_file(&root.join("app.py"), "def c():\n    pass\n");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "second"]);
+    create_test_file(&root.join("untracked.rs"), "fn d() {}\n");
+
+    let root_arg = root.to_str().unwrap();
+    let output = run_code_stats(&[root_arg, "--rev", "HEAD~1"]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &["Total: 1 functions, 0 structs/classes in 1 files"],
+    );
+
+    let output = run_code_stats(&[root_arg, "--rev", "HEAD"]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &["Total: 3 functions, 1 structs/classes in 2 files"],
+    );
+
+    // The work tree is left as it was
+    assert!(root.join("untracked.rs").exists());
+    assert!(
+        std::fs::read_to_string(root.join("lib.rs"))
+            .unwrap()
+            .contains("struct S")
+    );
+
+    let output = run_code_stats(&[root_arg, "--rev", "no-such-ref"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Git command failed"));
+}
+
+#[test]
+fn test_history_prints_time_series_of_commits() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.email", "test@example.com"]);
+    git(&["config", "user.name", "Test"]);
+    create_test_file(&root.join("lib.rs"), "fn a() {}\n");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+    create_test_file(&root.join("app.py"), "def b():\n    pass\n");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "second"]);
+
+    let root_arg = root.to_str().unwrap();
+    let output = run_code_stats(&["history", root_arg, "--format", "csv"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .map(|line| line.split(',').collect())
+        .collect();
+    assert_eq!(
+        rows[0],
+        [
+            "commit",
+            "date",
+            "language",
+            "files",
+            "functions",
+            "classes"
+        ]
+    );
+    let counts: Vec<&[&str]> = rows[1..].iter().map(|row| &row[2..]).collect();
+    assert_eq!(
+        counts,
+        [
+            &["Rust", "1", "1", "0"][..],
+            &["Python", "1", "1", "0"],
+            &["Rust", "1", "1", "0"],
+        ]
+    );
+
+    let output = run_code_stats(&["history", root_arg, "--every", "2"]);
+    assert!(output.status.success());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let points = json.as_array().unwrap();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0]["total"]["functions"], 2);
+}
+
+#[test]
+fn test_hotspots_rank_files_by_churn_times_complexity() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.email", "test@example.com"]);
+    git(&["config", "user.name", "Test"]);
+    for i in 0..3 {
+        if i < 2 {
+            create_test_file(
+                &root.join("src/complex.rs"),
+                &format!(
+                    "fn a{i}(x: i32) -> i32 {{\n    if x > 0 {{ 1 }} else if x < 0 {{ 2 }} else {{ 3 }}\n}}\n"
+                ),
+            );
+        }
+        create_test_file(&root.join("src/busy.rs"), &format!("fn b{i}() {{}}\n"));
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", &format!("commit {i}")]);
+    }
+    create_test_file(&root.join("src/untracked.rs"), "fn c() {}\n");
+
+    let root_arg = root.to_str().unwrap();
+    let output = run_code_stats(&["hotspots", root_arg, "--format", "json"]);
+    assert!(output.status.success());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let ranked: Vec<(&str, u64, u64)> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hotspot| {
+            (
+                hotspot["path"].as_str().unwrap(),
+                hotspot["commits"].as_u64().unwrap(),
+                hotspot["score"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(ranked, [("src/complex.rs", 2, 6), ("src/busy.rs", 3, 3)]);
+
+    let output = run_code_stats(&["hotspots", root_arg, "--top", "1"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_contains_all(&stdout, &["Commits", "Complexity", "src/complex.rs"]);
+    assert!(!stdout.contains("src/busy.rs"));
+
+    let output = run_code_stats(&["hotspots", root_arg, "--since", "2090-01-01"]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &["No files changed in the window"],
+    );
+}
+
+#[test]
+fn test_ownership_flags_directories_owned_by_one_author() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["config", "user.email", "test@example.com"]);
+    git(&["config", "user.name", "Ada"]);
+    create_test_file(
+        &root.join("src/legacy/old.rs"),
+        "fn a() {}\nfn b() {}\nfn c() {}\n",
+    );
+    create_test_file(&root.join("src/lib.rs"), "fn d() {}\n");
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+    git(&["config", "user.name", "Grace"]);
+    create_test_file(
+        &root.join("src/lib.rs"),
+        "fn d() {}\nfn e() {}\nfn f() {}\n",
+    );
+    create_test_file(
+        &root.join("app.py"),
+        "def g():\n    pass\ndef i():\n    pass\n",
+    );
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "second"]);
+    create_test_file(&root.join("untracked.rs"), "fn h() {}\n");
+
+    let root_arg = root.to_str().unwrap();
+    let output = run_code_stats(&["ownership", root_arg, "--format", "json"]);
+    assert!(output.status.success());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let directories: Vec<(&str, u64, &str, u64)> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|dir| {
+            (
+                dir["path"].as_str().unwrap(),
+                dir["functions"].as_u64().unwrap(),
+                dir["top_author"].as_str().unwrap(),
+                dir["bus_factor"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        directories,
+        [
+            (".", 8, "Ada", 2),
+            ("src", 6, "Ada", 1),
+            ("src/legacy", 3, "Ada", 1)
+        ]
+    );
+
+    let output = run_code_stats(&["ownership", root_arg]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &[
+            "Top author",
+            "Bus factor",
+            "2 directories with a bus factor of 1: src, src/legacy",
+        ],
+    );
+
+    let output = run_code_stats(&["ownership", temp_dir.path().join("src").to_str().unwrap()]);
+    assert!(output.status.success());
+
+    let not_a_repo = tempfile::TempDir::new().unwrap();
+    let output = run_code_stats(&["ownership", not_a_repo.path().to_str().unwrap()]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_custom_queries() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_file(
+        &root.join("main.rs"),
+        "fn main() {\n    println!(\"a\");\n    assert!(true);\n}\n",
+    );
+    create_test_file(
+        &root.join("App.tsx"),
+        "function App() {\n  const [a] = useState(0);\n  useEffect(() => {});\n  return <div />;\n}\n",
+    );
+    let queries = tempfile::TempDir::new().unwrap();
+    let macros = queries.path().join("macros.scm");
+    create_test_file(&macros, "(macro_invocation) @macros\n");
+    let hooks = queries.path().join("hooks.scm");
+    create_test_file(
+        &hooks,
+        "(call_expression function: (identifier) @_f (#match? @_f \"^use[A-Z]\")) @hooks\n",
+    );
+
+    let output = run_code_stats(&[
+        root.to_str().unwrap(),
+        "--format",
+        "json",
+        "--detection",
+        "extension",
+        "--query",
+        &format!("rust={}", macros.display()),
+        "--query",
+        &format!("typescript={}", hooks.display()),
+    ]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(json["total_stats"]["query_counts"]["macros"], 2);
+    assert_eq!(json["total_stats"]["query_counts"]["hooks"], 2);
+
+    // Queries that do not compile against the grammar are rejected
+    let invalid = queries.path().join("invalid.scm");
+    create_test_file(&invalid, "(no_such_node) @x\n");
+    let output = run_code_stats(&[
+        root.to_str().unwrap(),
+        "--query",
+        &format!("rust={}", invalid.display()),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid query file"));
+}
+
+#[test]
+fn test_find_serves_symbols_of_unchanged_files_from_the_cache() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path().join("repo");
+    let cache_dir = temp_dir.path().join("cache");
+    create_test_file(
+        &root.join("src/parse.rs"),
+        "struct Parser;\n\nfn parse() {}\n",
+    );
+    create_test_file(&root.join("src/util.rs"), "fn helper() {}\n");
+    create_test_file(&root.join("app.py"), "def parse_args():\n    pass\n");
+    let find = |args: &[&str]| {
+        let output = code_stats_command()
+            .current_dir(&root)
+            .arg("find")
+            .args(args)
+            .args(["--cache-dir", cache_dir.to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string()
+    };
+
+    assert_eq!(find(&["parse"]), "src/parse.rs:3:1: function parse");
+    assert_eq!(
+        find(&["parse", "src", "--prefix"]),
+        "src/parse.rs:3:1: function parse"
+    );
+    assert_eq!(
+        find(&["Parse", "--prefix", "--kind", "class"]),
+        "src/parse.rs:1:1: class Parser"
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&find(&["parse_args", "-f", "json"])).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([{
+            "path": "app.py",
+            "name": "parse_args",
+            "kind": "function",
+            "line": 1,
+            "column": 1,
+            "end_line": 2
+        }])
+    );
+    assert_eq!(find(&["missing"]), "No symbols named missing found");
+
+    // Unchanged files are not parsed again: a symbol planted in the cache is found
+    // The cache of the repository rather than of its src directory
+    let (cache_file, cached) = fs::read_dir(cache_dir.join("files"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .map(|path| {
+            let text = fs::read_to_string(&path).unwrap();
+            (path, text)
+        })
+        .find(|(_, text)| text.contains("parse_args"))
+        .unwrap();
+    assert!(cached.contains("\"name\":\"helper\""));
+    fs::write(
+        &cache_file,
+        cached.replace("\"name\":\"helper\"", "\"name\":\"cached\""),
+    )
+    .unwrap();
+    assert_eq!(find(&["cached"]), "src/util.rs:1:1: function cached");
+
+    // Edited files are parsed again
+    create_test_file(&root.join("src/util.rs"), "fn renamed() {}\n");
+    assert_eq!(find(&["renamed"]), "src/util.rs:1:1: function renamed");
+    assert_eq!(find(&["cached"]), "No symbols named cached found");
+}
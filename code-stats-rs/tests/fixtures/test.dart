@@ -0,0 +1,33 @@
+import 'package:flutter/material.dart';
+
+// Top-level function
+void main() {
+  runApp(const CounterApp());
+}
+
+// Widget class
+class CounterApp extends StatelessWidget {
+  const CounterApp({super.key});
+
+  @override
+  Widget build(BuildContext context) {
+    return const MaterialApp(home: Text('Counter'));
+  }
+}
+
+// Mixin
+mixin Logging {
+  void log(String message) {
+    print(message);
+  }
+}
+
+// Enum
+enum Status { idle, running }
+
+// Abstract class
+abstract class Repository {
+  Future<int> load();
+
+  int get size => 0;
+}
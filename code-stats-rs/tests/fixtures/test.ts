@@ -28,7 +28,7 @@ class TypeScriptClass {
     }
 }
 
-// Interface (should be counted as class/struct)
+// Interface (counted as an interface, not a class/struct)
 interface Person {
     name: string;
     age: number;
@@ -0,0 +1,88 @@
+mod common;
+
+use assert_cmd::Command;
+use common::{code_stats_command, create_test_file};
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+const HEADER: &str = "Copyright 2024 Example Corp\nSPDX-License-Identifier: MIT\n";
+
+/// Create a project where one Rust file carries the header and the other does not
+fn create_licensed_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("header.txt"), HEADER);
+    create_test_file(
+        &root.join("src/licensed.rs"),
+        "// Copyright 2024 Example Corp\n// SPDX-License-Identifier: MIT\n\nfn main() {}\n",
+    );
+    create_test_file(
+        &root.join("scripts/run.py"),
+        "#!/usr/bin/env python3\n# Copyright 2024 Example Corp\n# SPDX-License-Identifier: MIT\n\ndef run():\n    pass\n",
+    );
+    create_test_file(&root.join("src/unlicensed.rs"), "fn helper() {}\n");
+
+    temp_dir
+}
+
+#[test]
+fn test_license_audit_reports_missing_headers_and_fails() {
+    let temp_dir = create_licensed_project();
+    let root = temp_dir.path();
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.args(["audit", "license-headers", "--template"])
+        .arg(root.join("header.txt"))
+        .arg(root)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("License Header Audit:"))
+        .stdout(predicate::str::contains("unlicensed.rs (Rust)"))
+        .stdout(predicate::str::contains("licensed.rs (Rust)").count(1))
+        .stdout(predicate::str::contains(
+            "Total: 1 of 3 files missing header",
+        ))
+        .stderr(predicate::str::contains(
+            "1 of 3 files are missing the required license header",
+        ));
+}
+
+#[test]
+fn test_license_audit_passes_when_all_files_have_header() {
+    let temp_dir = create_licensed_project();
+    let root = temp_dir.path();
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.args([
+        "audit",
+        "license-headers",
+        "--ignore",
+        "unlicensed",
+        "--template",
+    ])
+    .arg(root.join("header.txt"))
+    .arg(root)
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+        "Total: 0 of 2 files missing header",
+    ));
+}
+
+#[test]
+fn test_license_audit_missing_template() {
+    let temp_dir = create_licensed_project();
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.args([
+        "audit",
+        "license-headers",
+        "--template",
+        "no-such-header.txt",
+    ])
+    .arg(temp_dir.path())
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Failed to read license template"));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use assert_cmd::Command;
+use common::{code_stats_command, create_test_file, parse_json_output};
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+/// Create two local repositories and a manifest listing them
+fn create_manifest_project(extra_repo: &str) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    create_test_file(&root.join("api/src/main.rs"), "fn main() {}\nstruct App;\n");
+    create_test_file(
+        &root.join("web/app.js"),
+        "function render() {}\nfunction mount() {}\n",
+    );
+    create_test_file(
+        &root.join("repos.toml"),
+        &format!(
+            "[[repo]]\nname = \"api\"\npath = \"api\"\n\n[[repo]]\nname = \"web\"\npath = \"web\"\n{extra_repo}"
+        ),
+    );
+
+    temp_dir
+}
+
+#[test]
+fn test_multi_reports_each_repo_and_totals() {
+    let temp_dir = create_manifest_project("");
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.arg("multi")
+        .arg(temp_dir.path().join("repos.toml"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Repository Summary:"))
+        .stdout(predicate::str::contains("api:"))
+        .stdout(predicate::str::contains("web:"))
+        .stdout(predicate::str::contains(
+            "Total: 3 functions, 1 structs/classes in 2 files across 2 repositories",
+        ));
+}
+
+#[test]
+fn test_multi_json_output() {
+    let temp_dir = create_manifest_project("");
+
+    let output = Command::from_std(code_stats_command())
+        .arg("multi")
+        .arg(temp_dir.path().join("repos.toml"))
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(json["repos"][0]["name"], "api");
+    assert_eq!(json["repos"][1]["total_stats"]["function_count"], 2);
+    assert_eq!(json["total_files"], 2);
+}
+
+#[test]
+fn test_multi_fails_when_a_repo_cannot_be_analyzed() {
+    let temp_dir =
+        create_manifest_project("\n[[repo]]\nname = \"gone\"\npath = \"does-not-exist\"\n");
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.arg("multi")
+        .arg(temp_dir.path().join("repos.toml"))
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("across 2 repositories"))
+        .stdout(predicate::str::contains("error: gone: "))
+        .stderr(predicate::str::contains(
+            "1 of 3 repositories could not be analyzed",
+        ));
+}
+
+#[test]
+fn test_multi_rejects_invalid_manifest() {
+    let temp_dir = create_manifest_project("\n[[repo]]\nname = \"api\"\npath = \"api\"\n");
+
+    let mut cmd = Command::from_std(code_stats_command());
+    cmd.arg("multi")
+        .arg(temp_dir.path().join("repos.toml"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("duplicate repository name"));
+}
@@ -1,8 +1,10 @@
 mod common;
 
 use common::{
-    assert_contains_all, create_controlled_test_project, parse_json_output, run_code_stats,
+    assert_contains_all, code_stats_command, create_controlled_test_project, create_test_file,
+    create_test_project, parse_json_output, run_code_stats,
 };
+use serde_json::Value;
 
 #[test]
 fn test_summary_format() {
@@ -29,7 +31,7 @@ fn test_summary_format() {
 
     // Verify the format includes proper counts
     assert!(stdout.contains("3 functions"));
-    assert!(stdout.contains("4 structs/classes"));
+    assert!(stdout.contains("3 structs/classes"));
     assert!(stdout.contains("in 2 files")); // Rust files
     assert!(stdout.contains("in 1 files")); // Python file
 }
@@ -88,6 +90,10 @@ fn test_json_format() {
     let json = parse_json_output(&stdout);
 
     // Check top-level structure
+    assert_eq!(json["meta"]["tool_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(json["meta"]["root"], ".");
+    assert_eq!(json["meta"]["options"][1], "--format");
+    assert!(json["meta"].get("timestamp").is_none());
     assert!(json.get("files").is_some());
     assert!(json.get("total_by_language").is_some());
     assert!(json.get("total_stats").is_some());
@@ -105,12 +111,144 @@ fn test_json_format() {
         let stats = &file["stats"];
         assert!(stats.get("function_count").is_some());
         assert!(stats.get("class_struct_count").is_some());
+        assert!(stats.get("interface_count").is_some());
+        assert!(stats.get("enum_count").is_some());
+        assert!(stats.get("type_alias_count").is_some());
     }
 
     // Check total stats
     let total_stats = &json["total_stats"];
     assert_eq!(total_stats["function_count"], 5); // 3 Rust + 2 Python
-    assert_eq!(total_stats["class_struct_count"], 4); // 3 Rust + 1 Python
+    assert_eq!(total_stats["class_struct_count"], 3); // 2 Rust + 1 Python
+    assert_eq!(total_stats["enum_count"], 1); // 1 Rust
+}
+
+#[test]
+fn test_json_format_is_reproducible() {
+    let report = |args: &[&str], epoch: Option<&str>| {
+        let (temp_dir, _) = create_controlled_test_project();
+        let mut cmd = code_stats_command();
+        cmd.current_dir(temp_dir.path())
+            .arg(".")
+            .args(args)
+            .args(["--format", "json"]);
+        if let Some(epoch) = epoch {
+            cmd.env("SOURCE_DATE_EPOCH", epoch);
+        } else {
+            cmd.env_remove("SOURCE_DATE_EPOCH");
+        }
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Identical trees in different places give identical reports
+    let first = report(&[], None);
+    assert_eq!(first, report(&[], None));
+    let json = parse_json_output(&first);
+    assert!(json["meta"].get("timestamp").is_none());
+    assert_eq!(json["meta"]["root"], ".");
+
+    let stamped = report(&["--timestamp"], Some("1700000000"));
+    assert_eq!(stamped, report(&["--timestamp"], Some("1700000000")));
+    assert_eq!(
+        parse_json_output(&stamped)["meta"]["timestamp"],
+        1_700_000_000
+    );
+    let json = parse_json_output(&report(&["--timestamp"], None));
+    assert!(json["meta"]["timestamp"].as_u64().unwrap() > 1_700_000_000);
+}
+
+#[test]
+fn test_json_format_compact() {
+    let (_temp_dir, project_root) = create_controlled_test_project();
+
+    let output = run_code_stats(&[
+        project_root.to_str().unwrap(),
+        "--format",
+        "json",
+        "--compact",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    // A single line, terminated by a newline
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.ends_with("}\n"));
+    let json = parse_json_output(&stdout);
+    assert_eq!(json["files"].as_array().unwrap().len(), 3);
+    assert_eq!(json["total_stats"]["function_count"], 5);
+
+    // --compact only applies to JSON reports
+    let output = run_code_stats(&[project_root.to_str().unwrap(), "--compact"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("--compact requires --format json or cloc-json")
+    );
+}
+
+#[test]
+fn test_json_paths_are_relative() {
+    let (_temp_dir, project_root) = create_controlled_test_project();
+    let paths = |args: &[&str]| {
+        let mut all_args = vec![project_root.to_str().unwrap(), "--format", "json"];
+        all_args.extend_from_slice(args);
+        let output = run_code_stats(&all_args);
+        assert!(output.status.success());
+        let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+        json["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| file["path"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // Relative to the analyzed directory by default, in traversal order
+    assert_eq!(paths(&[]), ["file1.rs", "file2.rs", "script.py"]);
+
+    let parent = project_root.parent().unwrap();
+    let name = project_root.file_name().unwrap().to_str().unwrap();
+    assert_eq!(
+        paths(&["--relative-to", parent.to_str().unwrap()])[0],
+        format!("{name}/file1.rs")
+    );
+
+    // Paths outside the given directory are kept
+    let elsewhere = paths(&["--relative-to", "/nonexistent"]);
+    assert_eq!(
+        elsewhere[0],
+        project_root.join("file1.rs").to_str().unwrap()
+    );
+}
+
+#[test]
+fn test_no_file_list_keeps_totals() {
+    let (_temp_dir, project_root) = create_controlled_test_project();
+    let json = |args: &[&str]| {
+        let mut all_args = vec![project_root.to_str().unwrap(), "--format", "json"];
+        all_args.extend_from_slice(args);
+        let output = run_code_stats(&all_args);
+        assert!(output.status.success());
+        parse_json_output(&String::from_utf8_lossy(&output.stdout))
+    };
+
+    let full = json(&[]);
+    let totals = json(&["--no-file-list"]);
+    assert_eq!(totals["files"].as_array().unwrap().len(), 0);
+    assert_eq!(totals["total_stats"], full["total_stats"]);
+    assert_eq!(totals["total_by_language"], full["total_by_language"]);
+
+    // Rules on single files cannot be checked without them
+    let output = run_code_stats(&[
+        project_root.to_str().unwrap(),
+        "--no-file-list",
+        "--fail-if",
+        "functions_per_file>10",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--no-file-list"));
 }
 
 #[test]
@@ -145,7 +283,8 @@ fn test_json_format_language_grouping() {
     // Check Rust stats
     let rust_stats = &by_language["Rust"];
     assert_eq!(rust_stats["function_count"], 3);
-    assert_eq!(rust_stats["class_struct_count"], 3); // 2 structs + 1 enum
+    assert_eq!(rust_stats["class_struct_count"], 2);
+    assert_eq!(rust_stats["enum_count"], 1);
     assert_eq!(rust_stats["file_count"], 2);
 
     // Check Python stats
@@ -155,6 +294,239 @@ fn test_json_format_language_grouping() {
     assert_eq!(python_stats["file_count"], 1);
 }
 
+#[test]
+fn test_json_format_includes_type_hierarchy() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("Shapes.java"),
+        "class Square extends Rectangle implements Comparable<Square> {}\nclass Rectangle {}\n",
+    );
+
+    let output = run_code_stats(&[temp_dir.path().to_str().unwrap(), "--format", "json"]);
+    assert!(output.status.success());
+
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let hierarchy = &json["files"][0]["hierarchy"];
+    assert_eq!(hierarchy.as_array().unwrap().len(), 1);
+    assert_eq!(hierarchy[0]["name"], "Square");
+    assert_eq!(hierarchy[0]["extends"][0], "Rectangle");
+    assert_eq!(hierarchy[0]["implements"][0], "Comparable");
+}
+
+#[test]
+fn test_json_format_splits_test_code() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("src/lib.rs"),
+        "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\n\
+         #[cfg(test)]\nmod tests {\n    #[test]\n    fn test_add() {}\n}\n",
+    );
+    common::create_test_file(
+        &temp_dir.path().join("server_test.go"),
+        "package main\n\nfunc TestServe() {}\n",
+    );
+
+    let output = run_code_stats(&[temp_dir.path().to_str().unwrap(), "--format", "json"]);
+    assert!(output.status.success());
+
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let split = &json["test_split"];
+    assert_eq!(split["production"]["files"], 1);
+    assert_eq!(split["production"]["function_count"], 1);
+    assert_eq!(split["production"]["code_lines"], 3);
+    assert_eq!(split["test"]["files"], 1);
+    assert_eq!(split["test"]["function_count"], 2);
+    assert_eq!(split["test"]["code_lines"], 7);
+}
+
+#[test]
+fn test_summary_counts_rust_impl_blocks() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("lib.rs"),
+        "struct A;\nstruct B;\nimpl A {\n    fn a(&self) {}\n    fn b(&self) {}\n}\n\
+         impl Clone for A {\n    fn clone(&self) -> Self {\n        A\n    }\n}\n",
+    );
+    let root = temp_dir.path().to_str().unwrap();
+
+    let output = run_code_stats(&[root]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Impl Blocks:\n  Rust:"));
+    assert!(stdout.contains("1 inherent,    1 trait impls;    3 methods, 1.5 per struct"));
+
+    let output = run_code_stats(&[root, "--format", "json"]);
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let rust = &json["total_by_language"]["Rust"];
+    assert_eq!(rust["impl_count"], 1);
+    assert_eq!(rust["trait_impl_count"], 1);
+    assert_eq!(rust["impl_method_count"], 3);
+}
+
+#[test]
+fn test_summary_counts_go_generics() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("slices.go"),
+        "package slices\n\ntype Ordered interface {\n\t~int | ~string\n}\n\n\
+         func Max[T Ordered](a, b T) T {\n\treturn a\n}\n\n\
+         type Set[K comparable, V any] map[K]V\n",
+    );
+    let root = temp_dir.path().to_str().unwrap();
+
+    let output = run_code_stats(&[root]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Generics:\n  Go:"));
+    assert!(stdout.contains("2 generic functions and types,    3 type parameters"));
+
+    let output = run_code_stats(&[root, "--format", "json"]);
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let go = &json["total_by_language"]["Go"];
+    assert_eq!(go["generic_count"], 2);
+    assert_eq!(go["type_parameter_count"], 3);
+    assert_eq!(go["interface_count"], 1);
+}
+
+#[test]
+fn test_summary_counts_python_function_kinds() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("handlers.py"),
+        "class Handler:\n    @staticmethod\n    def name():\n        return 'h'\n\n\
+         async def handle(items):\n    return sorted(items, key=lambda item: item.id)\n",
+    );
+    let root = temp_dir.path().to_str().unwrap();
+
+    let output = run_code_stats(&[root]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Function Kinds:\n  Python:"));
+    assert!(stdout.contains("1 async,    1 decorated functions;    1 lambdas"));
+
+    let output = run_code_stats(&[root, "--format", "json"]);
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let python = &json["total_by_language"]["Python"];
+    assert_eq!(python["async_function_count"], 1);
+    assert_eq!(python["decorated_function_count"], 1);
+    assert_eq!(python["lambda_count"], 1);
+}
+
+#[test]
+fn test_react_components_are_counted_per_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("App.tsx"),
+        "export const App = () => <Layout><Nav /></Layout>;\n\
+         export function Nav(): JSX.Element {\n  return <nav />;\n}\n\
+         export function formatDate(date: Date): string {\n  return date.toISOString();\n}\n",
+    );
+    common::create_test_file(
+        &temp_dir.path().join("Legacy.jsx"),
+        "class Legacy extends React.Component {\n  render() {\n    return <div />;\n  }\n}\n",
+    );
+    let root = temp_dir.path().to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--detail"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_contains_all(
+        &stdout,
+        &[
+            "React components: 2",
+            "React components: 1",
+            "React Components:\n  JavaScript:",
+            "1 components in 1 files",
+        ],
+    );
+
+    let output = run_code_stats(&[root, "--format", "json"]);
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    assert_eq!(json["total_stats"]["component_count"], 3);
+    assert_eq!(
+        json["total_by_language"]["TypeScript"]["component_count"],
+        2
+    );
+}
+
+#[test]
+fn test_summary_lists_type_definitions() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("types.ts"),
+        "interface User {}\ninterface Admin extends User {}\n\
+         type Id = string;\nenum Role { Admin, Guest }\nclass Service {}\n",
+    );
+    let root = temp_dir.path().to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--detail"]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &[
+            "  Type definitions: 4\n",
+            "Type Definitions:\n  TypeScript:",
+            "2 interfaces,    1 enums,    1 type aliases (4 type definitions)",
+        ],
+    );
+}
+
+#[test]
+fn test_rust_modules_are_rolled_up() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    common::create_test_file(
+        &temp_dir.path().join("src/lib.rs"),
+        "mod parser;\npub struct Config;\nfn init() {}\n",
+    );
+    common::create_test_file(
+        &temp_dir.path().join("src/parser/mod.rs"),
+        "pub trait Parse {}\nfn parse() {}\nmod lexer {\n    enum Token {}\n}\n",
+    );
+    common::create_test_file(
+        &temp_dir.path().join("crates/core/src/lib.rs"),
+        "fn run() {}\n",
+    );
+    let root = temp_dir.path().to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--format", "json"]);
+    assert!(output.status.success());
+    let json = parse_json_output(&String::from_utf8_lossy(&output.stdout));
+    let modules: Vec<_> = json["rust_modules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|module| {
+            (
+                module["crate_root"].as_str(),
+                module["path"].as_str().unwrap(),
+                module["functions"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        modules,
+        [
+            (None, "crate", 1),
+            (None, "crate::parser", 1),
+            (None, "crate::parser::lexer", 0),
+            (Some("crates/core"), "crate", 1),
+        ]
+    );
+
+    let output = run_code_stats(&[root, "--detail"]);
+    assert!(output.status.success());
+    assert_contains_all(
+        &String::from_utf8_lossy(&output.stdout),
+        &[
+            "Rust modules:",
+            "  crate: 1 functions, 1 structs, 0 enums, 0 traits",
+            "  crate::parser: 1 functions, 0 structs, 0 enums, 1 traits",
+            "  crate::parser::lexer: 0 functions, 0 structs, 1 enums, 0 traits",
+            "  crate (crates/core): 1 functions, 0 structs, 0 enums, 0 traits",
+        ],
+    );
+}
+
 #[test]
 fn test_format_case_insensitive() {
     let (_temp_dir, project_root) = create_controlled_test_project();
@@ -229,3 +601,210 @@ fn test_format_with_special_characters_in_path() {
     let file_path = files[0]["path"].as_str().unwrap();
     assert!(file_path.contains("file with spaces.rs"));
 }
+
+#[test]
+fn test_magic_values_section() {
+    let (_temp_dir, project_root) = create_test_project();
+
+    let output = run_code_stats(&[project_root.to_str().unwrap(), "--magic-values"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert_contains_all(
+        &stdout,
+        &[
+            "Magic Values:",
+            "outside constant declarations",
+            "Worst offenders:",
+            "main.rs",
+        ],
+    );
+
+    // The section is opt-in for text output
+    let output = run_code_stats(&[project_root.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("Magic Values:"));
+}
+
+#[test]
+fn test_badge_from_analysis_and_report() {
+    let (temp_dir, project_root) = create_controlled_test_project();
+    let root = project_root.to_str().unwrap();
+
+    let output = run_code_stats(&["badge", root, "--detection", "extension"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(stdout.contains("<title>functions: "));
+
+    // A saved report gives the same badge without analyzing again
+    let report = temp_dir.path().join("report.json");
+    let output = run_code_stats(&[root, "--format", "json", "--detection", "extension"]);
+    std::fs::write(&report, &output.stdout).unwrap();
+    let output = run_code_stats(&[
+        "badge",
+        "--report",
+        report.to_str().unwrap(),
+        "--metric",
+        "language",
+        "--language",
+        "rust",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("<title>Rust: "));
+    assert!(stdout.contains("%</title>"));
+
+    let output = run_code_stats(&["badge", root, "--metric", "language", "--language", "cobol"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_table_format() {
+    let (_temp_dir, project_root) = create_controlled_test_project();
+    let root = project_root.to_str().unwrap();
+
+    let output = run_code_stats(&[root, "--format", "table", "--detection", "extension"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_contains_all(&stdout, &["│ Language ┆ Files ┆", "│ Total ", "└"]);
+    assert!(!stdout.contains("│ Path "));
+
+    // --detail adds a table of the files
+    let output = run_code_stats(&[
+        root,
+        "--format",
+        "table",
+        "--detail",
+        "--detection",
+        "extension",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_contains_all(&stdout, &["│ Path ", "│ Language ┆ Files ┆"]);
+}
+
+/// Checks a JSON value against the subset of JSON Schema the report schema
+/// uses. Unlike a validator, properties missing from the schema are errors,
+/// so the schema cannot fall behind the report.
+fn check_schema(value: &Value, schema: &Value, root: &Value, at: &str) {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/$defs/");
+        return check_schema(value, &root["$defs"][name], root, at);
+    }
+    if let Some(expected) = schema.get("const") {
+        assert_eq!(value, expected, "{at}");
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        assert!(
+            values.contains(value),
+            "{at}: {value} is not one of {values:?}"
+        );
+    }
+    match schema["type"].as_str() {
+        Some("object") => {
+            let object = value
+                .as_object()
+                .unwrap_or_else(|| panic!("{at} is not an object"));
+            for required in schema["required"].as_array().into_iter().flatten() {
+                assert!(
+                    object.contains_key(required.as_str().unwrap()),
+                    "{at}.{required} missing"
+                );
+            }
+            for (key, item) in object {
+                let item_schema = schema["properties"]
+                    .get(key)
+                    .or_else(|| schema.get("additionalProperties"))
+                    .unwrap_or_else(|| panic!("{at}.{key} is not in the schema"));
+                check_schema(item, item_schema, root, &format!("{at}.{key}"));
+            }
+        }
+        Some("array") => {
+            let items = value
+                .as_array()
+                .unwrap_or_else(|| panic!("{at} is not an array"));
+            for (i, item) in items.iter().enumerate() {
+                check_schema(item, &schema["items"], root, &format!("{at}[{i}]"));
+            }
+        }
+        Some("integer") => assert!(value.is_u64(), "{at} is not a count"),
+        Some("number") => assert!(value.is_number(), "{at} is not a number"),
+        Some("string") => assert!(value.is_string(), "{at} is not a string"),
+        Some("boolean") => assert!(value.is_boolean(), "{at} is not a boolean"),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_json_report_matches_schema() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_file(
+        &root.join(".code-stats.toml"),
+        "[count]\nmax_function_lines = 3\n",
+    );
+    create_test_file(
+        &root.join("src/lib.rs"),
+        "mod inner {\n    pub struct S;\n}\n\nfn long() {\n    let token = \"tok_1234567890\";\n\n\n}\n\n\
+         #[cfg(test)]\nmod tests {\n    fn check() {}\n}\n",
+    );
+    create_test_file(
+        &root.join("src/app.ts"),
+        "class Shape extends Base implements Area {}\nfunction long() {}\n",
+    );
+    create_test_file(
+        &root.join("tests/test_app.py"),
+        "def test_app():\n    pass\n",
+    );
+    create_test_file(
+        &root.join("vendor/dep.go"),
+        "package dep\n\nfunc Dep() {}\n",
+    );
+    create_test_file(&root.join("notes.txt"), "not code\n");
+    create_test_file(&root.join("src/blob.rs"), "fn blob() {}\0\n");
+
+    let output = run_code_stats(&["--schema"]);
+    assert!(output.status.success());
+    let schema: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let output = run_code_stats(&[
+        root.to_str().unwrap(),
+        "--format",
+        "json",
+        "--detection",
+        "extension",
+        "--metrics",
+        "extended",
+        "--find-duplicates",
+        "--hash",
+        "--scan-secrets",
+        "--secret-pattern",
+        "token=tok_[0-9]+",
+        "--warnings",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    let report = parse_json_output(&stdout);
+
+    // Every optional part of a file record is present at least once
+    let files = report["files"].as_array().unwrap();
+    for key in [
+        "secrets",
+        "functions",
+        "metrics",
+        "oversized_functions",
+        "hierarchy",
+        "is_test",
+        "inline_tests",
+        "rust_modules",
+        "sha256",
+    ] {
+        assert!(files.iter().any(|file| file.get(key).is_some()), "{key}");
+    }
+    assert!(report.get("set_aside_files").is_some());
+    assert!(report.get("findings").is_some());
+    assert_eq!(report["unsupported_files"], 1);
+
+    check_schema(&report, &schema, &schema, "report");
+}